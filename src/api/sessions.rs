@@ -18,7 +18,7 @@ static STATS_PARSER: Lazy<Regex> = lazy_regex!(
 );
 static LOOT_PARSER: Lazy<Regex> = lazy_regex!(r"(?m)^.+\[(.+)\]\s\(([^)]+)\)(\s<(.+)>)?\s(.+)");
 
-use crate::{session::Error, utils::parse_multiple_targets, Options};
+use crate::{session::Error, utils::Targets, Options};
 
 pub(crate) type SharedState = Arc<RwLock<Sessions>>;
 
@@ -294,11 +294,7 @@ impl Sessions {
 
         // validate argv
         let opts = Options::try_parse_from(&argv).map_err(|e| e.to_string())?;
-        let targets = if let Some(target) = opts.target.as_ref() {
-            parse_multiple_targets(target)?
-        } else {
-            return Err("no --target/-T argument provided".to_owned());
-        };
+        let targets = Targets::from_options(&opts)?.to_vec()?;
 
         let avail_workers = self
             .available_workers
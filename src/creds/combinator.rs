@@ -1,13 +1,19 @@
+use std::sync::Arc;
 use std::time;
 
 use clap::ValueEnum;
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    creds::{self, expression, iterator, Credentials},
+    creds::{
+        self, expression, iterator, mutations::parse_mutations, policy::Policy, rules::load_rules,
+        Credentials,
+    },
     options::Options,
     session::Error,
+    utils::{Target, Targets},
 };
 
 use super::Expression;
@@ -25,13 +31,69 @@ enum Mode {
     Single,
 }
 
+// iterates every credential over the whole list of targets before moving to the next one,
+// optionally reshuffling the targets for each credential
+struct SprayTargets<I: Iterator<Item = (String, String)>> {
+    targets: Vec<Target>,
+    creds: I,
+    current: Option<(String, String)>,
+    index: usize,
+    rng: Option<StdRng>,
+}
+
+impl<I: Iterator<Item = (String, String)>> SprayTargets<I> {
+    fn new(targets: Vec<Target>, creds: I, options: &Options) -> Self {
+        Self {
+            targets,
+            creds,
+            current: None,
+            index: 0,
+            rng: if options.shuffle_targets {
+                Some(StdRng::seed_from_u64(options.seed.unwrap_or_default()))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl<I: Iterator<Item = (String, String)>> Iterator for SprayTargets<I> {
+    type Item = (Target, String, String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.targets.is_empty() {
+            return None;
+        }
+
+        if self.current.is_none() || self.index == self.targets.len() {
+            // move to the next credentials and reshuffle the targets if needed
+            self.current = Some(self.creds.next()?);
+            if let Some(rng) = self.rng.as_mut() {
+                self.targets.shuffle(rng);
+            }
+            self.index = 0;
+        }
+
+        let (outer, inner) = self.current.as_ref().unwrap();
+        let target = self.targets[self.index].to_owned();
+        self.index += 1;
+
+        Some((target, outer.to_owned(), inner.to_owned()))
+    }
+}
+
+type TargetsIter = Box<dyn Iterator<Item = Target>>;
+
+// target, outer and inner payloads
+type Product = Box<dyn Iterator<Item = (Target, String, String)>>;
+
 pub(crate) struct Combinator {
     options: Options,
 
     mode: Mode,
     user_expr: creds::Expression,
     pass_expr: creds::Expression,
-    product: Box<dyn Iterator<Item = (String, String, String)>>,
+    product: Product,
 
     wait: Option<time::Duration>,
     dispatched: usize,
@@ -40,7 +102,7 @@ pub(crate) struct Combinator {
 
 impl Combinator {
     fn reset_from(&mut self, from: usize) {
-        if from > 0 {
+        if from > self.dispatched {
             let start = time::Instant::now();
             while self.dispatched < from {
                 let _ = self.product.next();
@@ -52,40 +114,305 @@ impl Combinator {
 
     fn combine_iterators(
         options: &Options,
-        targets: Vec<String>,
+        targets: TargetsIter,
         user_it: Box<dyn creds::Iterator>,
         pass_it: Option<Box<dyn creds::Iterator>>,
-    ) -> Box<dyn Iterator<Item = (String, String, String)>> {
+    ) -> Product {
         if let Some(pass_it) = pass_it {
             let (outer, inner) = match options.iterate_by {
                 IterationStrategy::User => (user_it, pass_it),
                 IterationStrategy::Password => (pass_it, user_it),
             };
 
-            Box::new(
-                targets
-                    .into_iter()
-                    .cartesian_product(outer)
-                    .cartesian_product(inner)
-                    .map(|((t, out), inn)| (t.to_owned(), out, inn)),
-            )
+            if options.spray || options.shuffle_targets {
+                Box::new(SprayTargets::new(
+                    targets.collect(),
+                    outer.cartesian_product(inner),
+                    options,
+                ))
+            } else {
+                Box::new(
+                    targets
+                        .cartesian_product(outer)
+                        .cartesian_product(inner)
+                        .map(|((t, out), inn)| (t.to_owned(), out, inn)),
+                )
+            }
+        } else if options.spray || options.shuffle_targets {
+            Box::new(SprayTargets::new(
+                targets.collect(),
+                user_it.map(|payload| (payload, "".to_owned())),
+                options,
+            ))
         } else {
             Box::new(
                 targets
-                    .into_iter()
                     .cartesian_product(user_it)
                     .map(|(t, payload)| (t.to_owned(), payload, "".to_owned())),
             )
         }
     }
 
+    // jump directly to the from-th combination by seeking the iterators instead of iterating
+    // them, only possible when the combinations are iterated in order and their count is exact
+    fn seek_product(
+        options: &Options,
+        targets: &Targets,
+        user_it: &dyn creds::Iterator,
+        pass_it: Option<&dyn creds::Iterator>,
+        from: usize,
+    ) -> Result<Option<Product>, Error> {
+        let (outer, inner) = match (pass_it, &options.iterate_by) {
+            (Some(pass_it), IterationStrategy::Password) => (pass_it, Some(user_it)),
+            (pass_it, _) => (user_it, pass_it),
+        };
+
+        if from == 0
+            || options.spray
+            || options.shuffle_targets
+            || !outer.is_exact()
+            || inner.is_some_and(|it| !it.is_exact())
+        {
+            return Ok(None);
+        }
+
+        let inner_size = inner.map_or(1, |it| it.search_space_size());
+        let per_target = outer.search_space_size() * inner_size;
+        if per_target == 0 || from >= per_target * targets.len() {
+            return Ok(None);
+        }
+
+        let start = time::Instant::now();
+        let mut targets_it = targets.iter()?.skip(from / per_target);
+        let Some(first) = targets_it.next() else {
+            return Ok(None);
+        };
+        let offset = from % per_target;
+
+        let mut first_outer = outer.create_boxed_copy();
+        first_outer.seek(offset / inner_size);
+
+        let product: Product = if let Some(inner) = inner {
+            let Some(current) = first_outer.next() else {
+                return Ok(None);
+            };
+            let mut first_inner = inner.create_boxed_copy();
+            first_inner.seek(offset % inner_size);
+
+            // rest of the current outer element, rest of the current target, other targets
+            let target = first.clone();
+            let current_row = first_inner.map(move |inn| (target.clone(), current.clone(), inn));
+            let target = first.clone();
+            let current_target = first_outer
+                .cartesian_product(inner.create_boxed_copy())
+                .map(move |(out, inn)| (target.clone(), out, inn));
+            let others = targets_it
+                .cartesian_product(outer.create_boxed_copy())
+                .cartesian_product(inner.create_boxed_copy())
+                .map(|((t, out), inn)| (t, out, inn));
+
+            Box::new(current_row.chain(current_target).chain(others))
+        } else {
+            let current_target =
+                first_outer.map(move |payload| (first.clone(), payload, "".to_owned()));
+            let others = targets_it
+                .cartesian_product(outer.create_boxed_copy())
+                .map(|(t, payload)| (t, payload, "".to_owned()));
+
+            Box::new(current_target.chain(others))
+        };
+
+        log::info!("restored from credential {} in {:?}", from, start.elapsed());
+
+        Ok(Some(product))
+    }
+
+    // create the iterator for the expression, normalizing and deduplicating its payloads if
+    // --dedup-payloads is specified
+    fn new_iterator(
+        options: &Options,
+        expr: Expression,
+    ) -> Result<Box<dyn creds::Iterator>, Error> {
+        let it = iterator::new(expr)?;
+        Ok(if options.dedup_payloads {
+            iterator::dedup(it)
+        } else {
+            it
+        })
+    }
+
+    // mangle the payloads with the rules from --rules and the --mutate modifiers if specified, for
+    // combinations only the part after the separator is mangled by the rules
+    fn apply_rules(
+        options: &Options,
+        it: Box<dyn creds::Iterator>,
+        combinations: bool,
+    ) -> Result<Box<dyn creds::Iterator>, Error> {
+        let it = if let Some(path) = options.rules.as_ref() {
+            let separator = if combinations {
+                Some(options.separator.to_owned())
+            } else {
+                None
+            };
+            iterator::mangle(it, Arc::new(load_rules(path)?), separator)
+        } else {
+            it
+        };
+
+        if combinations {
+            Ok(it)
+        } else if let Some(mutations) = options.mutate.as_ref() {
+            Ok(iterator::mutate(it, Arc::new(parse_mutations(mutations)?)))
+        } else {
+            Ok(it)
+        }
+    }
+
+    // skip the passwords not satisfying --password-policy if specified, for combinations only
+    // the part after the separator is checked
+    fn apply_policy(
+        options: &Options,
+        it: Box<dyn creds::Iterator>,
+        combinations: bool,
+    ) -> Result<Box<dyn creds::Iterator>, Error> {
+        if let Some(policy) = options.password_policy.as_ref() {
+            let separator = if combinations {
+                Some(options.separator.to_owned())
+            } else {
+                None
+            };
+            Ok(iterator::filter(
+                it,
+                Arc::new(Policy::parse(policy)?),
+                separator,
+            ))
+        } else {
+            Ok(it)
+        }
+    }
+
+    // uniformly sample the payloads with the --sample probability, or with the one needed to
+    // perform about --max-attempts attempts out of `others` times the payloads
+    fn apply_sampling(
+        options: &Options,
+        it: Box<dyn creds::Iterator>,
+        others: usize,
+    ) -> Result<Box<dyn creds::Iterator>, Error> {
+        let probability = if let Some(probability) = options.sample {
+            if probability <= 0.0 || probability > 1.0 {
+                return Err("--sample must be greater than 0 and not greater than 1".to_owned());
+            }
+            probability
+        } else if let Some(max_attempts) = options.max_attempts {
+            let total = others.saturating_mul(it.search_space_size());
+            // the size is unknown or already within the limit
+            if total == 0 || max_attempts >= total {
+                return Ok(it);
+            }
+            max_attempts as f64 / total as f64
+        } else {
+            return Ok(it);
+        };
+
+        log::info!("sampling {:.6}% of the payloads", probability * 100.0);
+
+        Ok(iterator::sample(
+            it,
+            probability,
+            options.seed.unwrap_or_else(rand::random),
+        ))
+    }
+
+    // split the targets with their own credentials file from the others
+    fn split_targets(targets: &Targets) -> Result<(TargetsIter, Vec<(Target, String)>), Error> {
+        if targets.overridden() == 0 {
+            return Ok((targets.iter()?, vec![]));
+        }
+
+        let overridden = targets
+            .iter()?
+            .filter_map(|t| t.credentials.clone().map(|credentials| (t, credentials)))
+            .collect();
+        let others = targets.iter()?.filter(|t| t.credentials.is_none());
+
+        Ok((Box::new(others), overridden))
+    }
+
+    // iterate the credentials file of each overridden target, yielding the elements in the
+    // same order the main product would for this mode
+    fn combine_overrides(
+        options: &Options,
+        mode: &Mode,
+        overridden: Vec<(Target, String)>,
+    ) -> Result<(Product, usize), Error> {
+        let mut size = 0;
+        let mut product: Product = Box::new(std::iter::empty());
+
+        for (target, filename) in overridden {
+            let combo_it = Self::apply_policy(
+                options,
+                Self::apply_rules(
+                    options,
+                    Self::new_iterator(
+                        options,
+                        expression::Expression::Wordlist {
+                            filename: filename.to_owned(),
+                        },
+                    )?,
+                    true,
+                )?,
+                true,
+            )?;
+            size += combo_it.search_space_size();
+
+            let separator = options.separator.to_owned();
+            let iterate_by = options.iterate_by.clone();
+            let combo_mode = matches!(mode, Mode::Combo);
+
+            product = Box::new(product.chain(combo_it.filter_map(move |line| {
+                if combo_mode {
+                    return Some((target.to_owned(), line, "".to_owned()));
+                }
+
+                let Some((user, pass)) = line.split_once(&separator) else {
+                    log::error!(
+                        "line '{}' of {} can't be splitted with '{}'",
+                        line,
+                        filename,
+                        separator
+                    );
+                    return None;
+                };
+
+                Some(match iterate_by {
+                    IterationStrategy::User => {
+                        (target.to_owned(), user.to_owned(), pass.to_owned())
+                    }
+                    IterationStrategy::Password => {
+                        (target.to_owned(), pass.to_owned(), user.to_owned())
+                    }
+                })
+            })));
+        }
+
+        Ok((product, size))
+    }
+
     fn for_single_payload(
-        targets: &Vec<String>,
-        options: Options,
+        targets: &Targets,
+        mut options: Options,
         override_expr: Option<Expression>,
+        from: usize,
     ) -> Result<Self, Error> {
+        if targets.overridden() > 0 {
+            return Err(
+                "per-target credentials files can't be used with plugins expecting a single payload"
+                    .to_owned(),
+            );
+        }
+
         let mode = Mode::Single;
-        let dispatched = 0;
+        let mut dispatched = 0;
         let wait = if options.wait > 0 {
             Some(time::Duration::from_millis(options.wait as u64))
         } else {
@@ -100,9 +427,29 @@ impl Combinator {
         } else {
             expression::parse_expression(options.password.as_ref())
         };
-        let payload_it = iterator::new(payload_expr.clone())?;
+        Self::adapt_to_single_pass(&mut options, targets, &payload_expr, None, 0)?;
+        let payload_it = Self::apply_sampling(
+            &options,
+            Self::apply_policy(
+                &options,
+                Self::apply_rules(
+                    &options,
+                    Self::new_iterator(&options, payload_expr.clone())?,
+                    false,
+                )?,
+                false,
+            )?,
+            targets.len(),
+        )?;
         let search_space_size: usize = targets.len() * payload_it.search_space_size();
-        let product = Self::combine_iterators(&options, targets.to_owned(), payload_it, None);
+        let product = if let Some(product) =
+            Self::seek_product(&options, targets, payload_it.as_ref(), None, from)?
+        {
+            dispatched = from;
+            product
+        } else {
+            Self::combine_iterators(&options, targets.iter()?, payload_it, None)
+        };
 
         Ok(Self {
             options,
@@ -116,8 +463,54 @@ impl Combinator {
         })
     }
 
-    fn for_double_payload(targets: &Vec<String>, options: Options) -> Result<Self, Error> {
-        let dispatched = 0;
+    // the standard input and the output of commands can only be consumed once, so they must be
+    // iterated as the outermost loop
+    fn adapt_to_single_pass(
+        options: &mut Options,
+        targets: &Targets,
+        outer_expr: &Expression,
+        inner_expr: Option<&Expression>,
+        outer_size: usize,
+    ) -> Result<(), Error> {
+        let inner_single = inner_expr.is_some_and(|expr| expr.is_single_pass());
+        let outer_single = outer_expr.is_single_pass();
+
+        if inner_single && outer_single {
+            return Err(
+                "only one of --username and --password can be read from stdin or a command"
+                    .to_owned(),
+            );
+        } else if !inner_single && !outer_single {
+            return Ok(());
+        }
+
+        let source = match inner_expr {
+            Some(expr) if inner_single => expr,
+            _ => outer_expr,
+        };
+
+        if inner_single && outer_size != 1 {
+            log::info!("reading from {}, switching iteration strategy", source);
+            options.iterate_by = match options.iterate_by {
+                IterationStrategy::User => IterationStrategy::Password,
+                IterationStrategy::Password => IterationStrategy::User,
+            };
+        }
+
+        if targets.len() > 1 && !options.spray {
+            log::info!("reading from {}, enabling --spray", source);
+            options.spray = true;
+        }
+
+        Ok(())
+    }
+
+    fn for_double_payload(
+        targets: &Targets,
+        mut options: Options,
+        from: usize,
+    ) -> Result<Self, Error> {
+        let mut dispatched = 0;
         let wait = if options.wait > 0 {
             Some(time::Duration::from_millis(options.wait as u64))
         } else {
@@ -127,14 +520,48 @@ impl Combinator {
         if let Some(combo_filename) = options.combinations.as_ref() {
             // get username:password combinations from the specified file
             let mode = Mode::Combo;
-            let combo_expr = expression::Expression::Wordlist {
-                filename: combo_filename.to_owned(),
+            let combo_expr = if combo_filename == "-" {
+                expression::Expression::Stdin
+            } else {
+                expression::Expression::Wordlist {
+                    filename: combo_filename.to_owned(),
+                }
             };
-            let combo_it = iterator::new(combo_expr.clone())?;
+            Self::adapt_to_single_pass(&mut options, targets, &combo_expr, None, 0)?;
+            let combo_it = Self::apply_sampling(
+                &options,
+                Self::apply_policy(
+                    &options,
+                    Self::apply_rules(
+                        &options,
+                        Self::new_iterator(&options, combo_expr.clone())?,
+                        true,
+                    )?,
+                    true,
+                )?,
+                targets.len(),
+            )?;
             let pass_expr = combo_expr.clone();
 
-            let search_space_size = targets.len() * combo_it.search_space_size();
-            let product = Self::combine_iterators(&options, targets.to_owned(), combo_it, None);
+            let (main_targets, overridden) = Self::split_targets(targets)?;
+            let (overrides, overrides_size) =
+                Self::combine_overrides(&options, &mode, overridden.clone())?;
+            let search_space_size =
+                (targets.len() - overridden.len()) * combo_it.search_space_size() + overrides_size;
+            let seeked = if overridden.is_empty() {
+                Self::seek_product(&options, targets, combo_it.as_ref(), None, from)?
+            } else {
+                None
+            };
+            let product: Product = if let Some(product) = seeked {
+                dispatched = from;
+                product
+            } else {
+                Box::new(
+                    Self::combine_iterators(&options, main_targets, combo_it, None)
+                        .chain(overrides),
+                )
+            };
 
             Ok(Self {
                 options,
@@ -150,13 +577,64 @@ impl Combinator {
             // perform the cartesian product of all usernames and passwords from distinct sources
             let mode = Mode::Multi;
             let user_expr = expression::parse_expression(options.username.as_ref());
-            let user_it = iterator::new(user_expr.clone())?;
+            let user_it = Self::new_iterator(&options, user_expr.clone())?;
             let pass_expr = expression::parse_expression(options.password.as_ref());
-            let pass_it = iterator::new(pass_expr.clone())?;
-            let search_space_size =
-                targets.len() * user_it.search_space_size() * pass_it.search_space_size();
-            let product =
-                Self::combine_iterators(&options, targets.to_owned(), user_it, Some(pass_it));
+            let pass_it = Self::apply_sampling(
+                &options,
+                Self::apply_policy(
+                    &options,
+                    Self::apply_rules(
+                        &options,
+                        Self::new_iterator(&options, pass_expr.clone())?,
+                        false,
+                    )?,
+                    false,
+                )?,
+                targets.len() * user_it.search_space_size(),
+            )?;
+            match options.iterate_by {
+                IterationStrategy::User => Self::adapt_to_single_pass(
+                    &mut options,
+                    targets,
+                    &user_expr,
+                    Some(&pass_expr),
+                    user_it.search_space_size(),
+                )?,
+                IterationStrategy::Password => Self::adapt_to_single_pass(
+                    &mut options,
+                    targets,
+                    &pass_expr,
+                    Some(&user_expr),
+                    pass_it.search_space_size(),
+                )?,
+            }
+            let (main_targets, overridden) = Self::split_targets(targets)?;
+            let (overrides, overrides_size) =
+                Self::combine_overrides(&options, &mode, overridden.clone())?;
+            let search_space_size = (targets.len() - overridden.len())
+                * user_it.search_space_size()
+                * pass_it.search_space_size()
+                + overrides_size;
+            let seeked = if overridden.is_empty() {
+                Self::seek_product(
+                    &options,
+                    targets,
+                    user_it.as_ref(),
+                    Some(pass_it.as_ref()),
+                    from,
+                )?
+            } else {
+                None
+            };
+            let product: Product = if let Some(product) = seeked {
+                dispatched = from;
+                product
+            } else {
+                Box::new(
+                    Self::combine_iterators(&options, main_targets, user_it, Some(pass_it))
+                        .chain(overrides),
+                )
+            };
 
             Ok(Self {
                 options,
@@ -172,21 +650,25 @@ impl Combinator {
     }
 
     pub fn create(
-        targets: &Vec<String>,
+        targets: &Targets,
         options: Options,
         from: usize,
         single: bool,
         override_expression: Option<Expression>,
     ) -> Result<Self, Error> {
         let mut combinator = if single {
-            Self::for_single_payload(targets, options, override_expression)?
+            Self::for_single_payload(targets, options, override_expression, from)?
         } else {
-            Self::for_double_payload(targets, options)?
+            Self::for_double_payload(targets, options, from)?
         };
 
-        // restore from last state if needed
+        // restore from last state by iterating if the generators couldn't be seeked
         combinator.reset_from(from);
 
+        if let Some(max_attempts) = combinator.options.max_attempts {
+            combinator.search_space_size = combinator.search_space_size.min(max_attempts);
+        }
+
         Ok(combinator)
     }
 
@@ -194,6 +676,10 @@ impl Combinator {
         self.search_space_size
     }
 
+    pub fn dispatched(&self) -> usize {
+        self.dispatched
+    }
+
     pub fn username_expression(&self) -> &creds::Expression {
         &self.user_expr
     }
@@ -207,17 +693,16 @@ impl Iterator for Combinator {
     type Item = Credentials;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // we're done
-        if let Some((target, outer, inner)) = self.product.next() {
-            // check if we have to rate limit
-            if self.options.rate_limit > 0 && self.dispatched % self.options.rate_limit == 0 {
-                std::thread::sleep(time::Duration::from_secs(1));
-            }
-
-            // check if we have a wait time
-            if let Some(wait) = self.wait {
-                std::thread::sleep(wait);
+        loop {
+            // we're done
+            if self
+                .options
+                .max_attempts
+                .is_some_and(|max_attempts| self.dispatched >= max_attempts)
+            {
+                return None;
             }
+            let (target, outer, inner) = self.product.next()?;
 
             let (username, password) = match self.mode {
                 Mode::Multi | Mode::Single => match self.options.iterate_by {
@@ -225,28 +710,44 @@ impl Iterator for Combinator {
                     IterationStrategy::Password => (inner, outer),
                 },
                 Mode::Combo => {
+                    // split on the first separator only, so that passwords can contain it
                     if let Some((user, pass)) = outer.split_once(&self.options.separator) {
                         (user.to_owned(), pass.to_owned())
                     } else {
-                        panic!(
-                            "line '{}' of {} can't be splitted with '{}'",
+                        // breach dumps often contain malformed lines, skip them
+                        log::warn!(
+                            "skipping line '{}' of {}, can't be splitted with '{}'",
                             outer,
-                            self.options.combinations.as_ref().unwrap(),
+                            self.options.combinations.as_deref().unwrap_or("?"),
                             &self.options.separator,
                         );
+                        continue;
                     }
                 }
             };
 
+            // check if we have to rate limit
+            if self.options.rate_limit > 0 && self.dispatched % self.options.rate_limit == 0 {
+                std::thread::sleep(time::Duration::from_secs(1));
+            }
+
+            // check if we have a wait time
+            if let Some(wait) = self.wait {
+                std::thread::sleep(wait);
+            }
+
             self.dispatched += 1;
 
-            Some(Credentials {
-                target,
+            // payload templates such as {USERNAME}2024! are interpolated per attempt
+            let username = creds::interpolate(&username, "", &target.address);
+            let password = creds::interpolate(&password, &username, &target.address);
+
+            return Some(Credentials {
+                target: target.address,
                 username,
                 password,
-            })
-        } else {
-            None
+                tags: target.tags,
+            });
         }
     }
 }
@@ -257,12 +758,13 @@ mod tests {
     use std::io::Write;
 
     use crate::creds::{Credentials, Expression, IterationStrategy};
+    use crate::utils::Targets;
 
     use super::Combinator;
 
     #[test]
     fn can_restore_from_step() {
-        let targets = vec!["foo".to_owned()];
+        let targets = Targets::parse("foo", None, true).unwrap();
         let mut opts = crate::Options::default();
 
         opts.iterate_by = IterationStrategy::User; // default
@@ -275,11 +777,13 @@ mod tests {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "p".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "pp".to_owned(),
+                ..Default::default()
             },
         ];
         let mut got = vec![];
@@ -292,7 +796,7 @@ mod tests {
 
     #[test]
     fn can_handle_user_iteration_strategy() {
-        let targets = vec!["foo".to_owned()];
+        let targets = Targets::parse("foo", None, true).unwrap();
         let mut opts = crate::Options::default();
 
         opts.iterate_by = IterationStrategy::User; // default
@@ -305,21 +809,25 @@ mod tests {
                 target: "foo".to_owned(),
                 username: "u".to_owned(),
                 password: "p".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "u".to_owned(),
                 password: "pp".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "p".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "pp".to_owned(),
+                ..Default::default()
             },
         ];
         let mut got = vec![];
@@ -330,9 +838,29 @@ mod tests {
         assert_eq!(expected, got);
     }
 
+    #[test]
+    fn can_limit_attempts() {
+        let targets = Targets::parse("foo", None, true).unwrap();
+        let opts = crate::Options {
+            username: Some("admin".to_owned()),
+            password: Some("#1-8:0123456789".to_owned()),
+            max_attempts: Some(100),
+            seed: Some(1),
+            ..Default::default()
+        };
+
+        let comb = Combinator::create(&targets, opts, 0, false, None).unwrap();
+        assert_eq!(comb.search_space_size(), 100);
+
+        let got: Vec<Credentials> = comb.collect();
+        assert!(got.len() > 10 && got.len() <= 100);
+        // sampled from the whole keyspace, not just the first passwords
+        assert!(got.iter().any(|c| c.password.len() == 8));
+    }
+
     #[test]
     fn can_handle_password_iteration_strategy() {
-        let targets = vec!["foo".to_owned()];
+        let targets = Targets::parse("foo", None, true).unwrap();
         let mut opts = crate::Options::default();
 
         opts.iterate_by = IterationStrategy::Password;
@@ -345,21 +873,25 @@ mod tests {
                 target: "foo".to_owned(),
                 username: "u".to_owned(),
                 password: "p".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "p".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "u".to_owned(),
                 password: "pp".to_owned(),
+                ..Default::default()
             },
             Credentials {
                 target: "foo".to_owned(),
                 username: "uu".to_owned(),
                 password: "pp".to_owned(),
+                ..Default::default()
             },
         ];
         let mut got = vec![];
@@ -372,7 +904,7 @@ mod tests {
 
     #[test]
     fn iteration_strategies_return_same_results() {
-        let targets = vec!["foo".to_owned()];
+        let targets = Targets::parse("foo", None, true).unwrap();
 
         let mut by_user_opts = crate::Options::default();
         by_user_opts.iterate_by = IterationStrategy::User;
@@ -403,7 +935,7 @@ mod tests {
 
     #[test]
     fn can_handle_multiple_targets_and_double_credentials() {
-        let targets = vec!["foo".to_owned(), "bar".to_owned()];
+        let targets = Targets::parse("foo, bar", None, true).unwrap();
         let mut opts = crate::Options::default();
 
         opts.username = Some("[1, 2, 3]".to_owned());
@@ -413,13 +945,14 @@ mod tests {
         let mut expected = vec![];
         let mut got = vec![];
 
-        for t in targets {
+        for t in targets.to_vec().unwrap() {
             for u in 1..=3 {
                 for p in 1..=3 {
                     expected.push(Credentials {
                         target: t.to_owned(),
                         username: u.to_string(),
                         password: p.to_string(),
+                        ..Default::default()
                     });
                 }
             }
@@ -434,7 +967,7 @@ mod tests {
 
     #[test]
     fn can_handle_multiple_targets_and_single_credentials() {
-        let targets = vec!["foo".to_owned(), "bar".to_owned()];
+        let targets = Targets::parse("foo, bar", None, true).unwrap();
         let mut opts = crate::Options::default();
 
         opts.username = Some("[1, 2, 3]".to_owned());
@@ -443,12 +976,13 @@ mod tests {
         let mut expected = vec![];
         let mut got = vec![];
 
-        for t in targets {
+        for t in targets.to_vec().unwrap() {
             for u in 1..=3 {
                 expected.push(Credentials {
                     target: t.to_owned(),
                     username: u.to_string(),
                     password: "".to_string(),
+                    ..Default::default()
                 });
             }
         }
@@ -468,7 +1002,14 @@ mod tests {
             set: vec![],
         };
         let opts = crate::Options::default();
-        let comb = Combinator::create(&vec!["foo".to_owned()], opts, 0, true, Some(expr)).unwrap();
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            true,
+            Some(expr),
+        )
+        .unwrap();
         let mut expected = vec![];
         let mut got = vec![];
 
@@ -477,6 +1018,7 @@ mod tests {
                 target: "foo".to_owned(),
                 username: i.to_string(),
                 password: "".to_owned(),
+                ..Default::default()
             });
         }
 
@@ -496,7 +1038,14 @@ mod tests {
             set: set.clone(),
         };
         let opts = crate::Options::default();
-        let comb = Combinator::create(&vec!["foo".to_owned()], opts, 0, true, Some(expr)).unwrap();
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            true,
+            Some(expr),
+        )
+        .unwrap();
         let mut expected = vec![];
         let mut got = vec![];
 
@@ -505,6 +1054,7 @@ mod tests {
                 target: "foo".to_owned(),
                 username: i.to_string(),
                 password: "".to_owned(),
+                ..Default::default()
             });
         }
 
@@ -541,6 +1091,7 @@ mod tests {
                     target: "foo".to_owned(),
                     username: format!("user{}", i),
                     password: format!("pass{}", j),
+                    ..Default::default()
                 })
             }
         }
@@ -548,7 +1099,14 @@ mod tests {
         opts.username = Some(tmpuserspath.to_str().unwrap().to_owned());
         opts.password = Some(tmppasspath.to_str().unwrap().to_owned());
 
-        let comb = Combinator::create(&vec!["foo".to_owned()], opts, 0, false, None).unwrap();
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            false,
+            None,
+        )
+        .unwrap();
         let tot = comb.search_space_size();
         let mut got = vec![];
 
@@ -579,6 +1137,7 @@ mod tests {
                 target: "foo".to_owned(),
                 username: format!("test{}", i),
                 password: "".to_owned(),
+                ..Default::default()
             });
         }
 
@@ -588,7 +1147,14 @@ mod tests {
         let mut opts = crate::Options::default();
         opts.username = Some(tmppath.to_str().unwrap().to_owned());
 
-        let comb = Combinator::create(&vec!["foo".to_owned()], opts, 0, true, None).unwrap();
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            true,
+            None,
+        )
+        .unwrap();
         let tot = comb.search_space_size();
         assert_eq!(expected.len(), tot);
 
@@ -618,6 +1184,7 @@ mod tests {
                 target: "foo".to_owned(),
                 username: format!("user{}", i),
                 password: format!("pass{}", i),
+                ..Default::default()
             });
         }
 
@@ -628,7 +1195,14 @@ mod tests {
         opts.combinations = Some(tmppath.to_str().unwrap().to_owned());
         opts.separator = String::from(":");
 
-        let comb = Combinator::create(&vec!["foo".to_owned()], opts, 0, false, None).unwrap();
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            false,
+            None,
+        )
+        .unwrap();
         let tot = comb.search_space_size();
         assert_eq!(expected.len(), tot);
 
@@ -640,4 +1214,247 @@ mod tests {
         assert_eq!(got.len(), tot);
         assert_eq!(expected, got);
     }
+
+    #[test]
+    fn can_skip_malformed_combo_lines() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("combinations.txt");
+        let mut tmpdata = File::create(&tmppath).unwrap();
+
+        writeln!(tmpdata, "admin@example.com:p:ss").unwrap();
+        writeln!(tmpdata, "garbage").unwrap();
+        writeln!(tmpdata, "root:toor").unwrap();
+        drop(tmpdata);
+
+        let opts = crate::Options {
+            combinations: Some(tmppath.to_str().unwrap().to_owned()),
+            separator: String::from(":"),
+            ..Default::default()
+        };
+
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            false,
+            None,
+        )
+        .unwrap();
+        let got: Vec<Credentials> = comb.collect();
+
+        assert_eq!(
+            got,
+            vec![
+                Credentials {
+                    target: "foo".to_owned(),
+                    username: "admin@example.com".to_owned(),
+                    password: "p:ss".to_owned(),
+                    ..Default::default()
+                },
+                Credentials {
+                    target: "foo".to_owned(),
+                    username: "root".to_owned(),
+                    password: "toor".to_owned(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn can_apply_rules_to_combo_passwords() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let combos = tmpdir.path().join("combinations.txt");
+        let rules = tmpdir.path().join("rules.txt");
+        std::fs::write(&combos, "root:toor\n").unwrap();
+        std::fs::write(&rules, ":\n$1\n").unwrap();
+
+        let opts = crate::Options {
+            combinations: Some(combos.to_str().unwrap().to_owned()),
+            rules: Some(rules.to_str().unwrap().to_owned()),
+            separator: String::from(":"),
+            ..Default::default()
+        };
+
+        let comb = Combinator::create(
+            &Targets::parse("foo", None, true).unwrap(),
+            opts,
+            0,
+            false,
+            None,
+        )
+        .unwrap();
+        let got: Vec<String> = comb
+            .map(|c| format!("{}:{}", c.username, c.password))
+            .collect();
+
+        assert_eq!(got, vec!["root:toor", "root:toor1"]);
+    }
+
+    #[test]
+    fn can_shuffle_targets() {
+        let targets = Targets::parse("10.0.0.1-8", None, true).unwrap();
+        let opts = crate::Options {
+            username: Some("[1, 2]".to_owned()),
+            password: Some("[1, 2, 3]".to_owned()),
+            shuffle_targets: true,
+            seed: Some(1234),
+            ..Default::default()
+        };
+
+        let comb = Combinator::create(&targets, opts.clone(), 0, false, None).unwrap();
+        let tot = comb.search_space_size();
+        let got: Vec<Credentials> = comb.collect();
+
+        assert_eq!(got.len(), tot);
+
+        // every credential is attempted against all targets before moving to the next one
+        for chunk in got.chunks(8) {
+            let mut chunk_targets: Vec<String> = chunk.iter().map(|c| c.target.clone()).collect();
+            chunk_targets.sort();
+            assert_eq!(chunk_targets, targets.to_vec().unwrap());
+            assert!(chunk
+                .iter()
+                .all(|c| c.username == chunk[0].username && c.password == chunk[0].password));
+        }
+
+        // same seed, same order
+        let again: Vec<Credentials> = Combinator::create(&targets, opts, 0, false, None)
+            .unwrap()
+            .collect();
+        assert_eq!(got, again);
+
+        // not just sorted
+        let ordered: Vec<String> = got.iter().take(8).map(|c| c.target.clone()).collect();
+        assert_ne!(ordered, targets.to_vec().unwrap());
+    }
+
+    #[test]
+    fn can_seek_to_step() {
+        let targets = Targets::parse("foo, bar", None, true).unwrap();
+        for iterate_by in [IterationStrategy::User, IterationStrategy::Password] {
+            let opts = crate::Options {
+                username: Some("#1-2:ab".to_owned()),
+                password: Some("[1-3]".to_owned()),
+                iterate_by,
+                ..Default::default()
+            };
+
+            let all: Vec<Credentials> = Combinator::create(&targets, opts.clone(), 0, false, None)
+                .unwrap()
+                .collect();
+            assert_eq!(all.len(), 2 * 6 * 3);
+
+            for from in 0..=all.len() {
+                let comb = Combinator::create(&targets, opts.clone(), from, false, None).unwrap();
+                assert_eq!(comb.dispatched(), from);
+                assert_eq!(comb.collect::<Vec<Credentials>>(), all[from..]);
+            }
+        }
+    }
+
+    #[test]
+    fn can_spray_targets() {
+        let targets = Targets::parse("foo, bar", None, true).unwrap();
+        let opts = crate::Options {
+            username: Some("#1-2:u".to_owned()),
+            password: Some("p".to_owned()),
+            spray: true,
+            ..Default::default()
+        };
+
+        let got: Vec<(String, String)> = Combinator::create(&targets, opts, 0, false, None)
+            .unwrap()
+            .map(|c| (c.target, c.username))
+            .collect();
+
+        assert_eq!(
+            got,
+            vec![
+                ("foo".to_owned(), "u".to_owned()),
+                ("bar".to_owned(), "u".to_owned()),
+                ("foo".to_owned(), "uu".to_owned()),
+                ("bar".to_owned(), "uu".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_use_per_target_credentials() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let combos = tmpdir.path().join("combos.txt");
+        let mut file = File::create(&combos).unwrap();
+        writeln!(file, "admin:secret").unwrap();
+        drop(file);
+
+        let targets_file = tmpdir.path().join("targets.yml");
+        let mut file = File::create(&targets_file).unwrap();
+        writeln!(file, "- address: bar").unwrap();
+        writeln!(file, "  credentials: {}", combos.to_str().unwrap()).unwrap();
+        writeln!(file, "- address: foo").unwrap();
+        writeln!(file, "  tags: [prod]").unwrap();
+        drop(file);
+
+        let targets =
+            Targets::parse(&format!("@{}", targets_file.to_str().unwrap()), None, true).unwrap();
+        let opts = crate::Options {
+            separator: ":".to_owned(),
+            username: Some("u".to_owned()),
+            password: Some("p".to_owned()),
+            ..Default::default()
+        };
+
+        let comb = Combinator::create(&targets, opts, 0, false, None).unwrap();
+        assert_eq!(comb.search_space_size(), 2);
+
+        let expected = vec![
+            Credentials {
+                target: "foo".to_owned(),
+                username: "u".to_owned(),
+                password: "p".to_owned(),
+                tags: vec!["prod".to_owned()],
+            },
+            Credentials {
+                target: "bar".to_owned(),
+                username: "admin".to_owned(),
+                password: "secret".to_owned(),
+                ..Default::default()
+            },
+        ];
+        let got: Vec<Credentials> = comb.collect();
+
+        assert_eq!(expected, got);
+
+        assert!(Combinator::create(&targets, crate::Options::default(), 0, true, None).is_err());
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn iterates_commands_output_once() {
+        let targets = Targets::parse("foo, bar", None, true).unwrap();
+        let opts = crate::Options {
+            username: Some("#1-2:u".to_owned()),
+            password: Some("cmd:printf 'a\\nb\\n'".to_owned()),
+            ..Default::default()
+        };
+
+        // the command output is the inner loop by default, it's moved to the outer one
+        let mut got: Vec<(String, String, String)> =
+            Combinator::create(&targets, opts, 0, false, None)
+                .unwrap()
+                .map(|c| (c.target, c.username, c.password))
+                .collect();
+        got.sort();
+
+        let mut expected = vec![];
+        for target in ["bar", "foo"] {
+            for username in ["u", "uu"] {
+                for password in ["a", "b"] {
+                    expected.push((target.to_owned(), username.to_owned(), password.to_owned()));
+                }
+            }
+        }
+
+        assert_eq!(expected, got);
+    }
 }
@@ -7,6 +7,19 @@ use serde::Serialize;
 
 const DEFAULT_PERMUTATIONS_MIN_LEN: usize = 4;
 const DEFAULT_PERMUTATIONS_MAX_LEN: usize = 8;
+const DEFAULT_MARKOV_ORDER: usize = 3;
+const DEFAULT_MARKOV_MIN_LEN: usize = 1;
+const DEFAULT_MARKOV_MAX_LEN: usize = 16;
+const DEFAULT_MARKOV_LIMIT: usize = 100000;
+const DEFAULT_PRINCE_MIN_LEN: usize = 1;
+const DEFAULT_PRINCE_MAX_LEN: usize = 16;
+const DEFAULT_PRINCE_ELEMS_MIN: usize = 2;
+const DEFAULT_PRINCE_ELEMS_MAX: usize = 4;
+const DEFAULT_DATES_FORMATS: &str = "DDMMYYYY,MMDDYYYY,YYYYMMDD,DDMMYY,MMYY,YYYY";
+const DEFAULT_WALKS_LAYOUT: &str = "qwerty";
+const DEFAULT_WALKS_TURNS: usize = 1;
+const DEFAULT_NAMES_FORMATS: &str =
+    "flast,first.last,first,lastf,f.last,firstlast,first_last,firstl";
 const DEFAULT_PERMUTATIONS_CHARSET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_ !\"#$%&\'()*+,-./:;<=>?@[\\]^`{|}~";
 
 static PERMUTATIONS_PARSER: Lazy<Regex> = lazy_regex!(r"^#(\d+)-(\d+)(:.+)?$");
@@ -21,6 +34,10 @@ pub(crate) enum Expression {
     Wordlist {
         filename: String,
     },
+    Stdin,
+    Command {
+        command: String,
+    },
     Permutations {
         min: usize,
         max: usize,
@@ -34,16 +51,67 @@ pub(crate) enum Expression {
     Glob {
         pattern: String,
     },
+    Markov {
+        filename: String,
+        order: usize,
+        min: usize,
+        max: usize,
+        limit: usize,
+        save: Option<String>,
+    },
+    Prince {
+        filename: String,
+        min: usize,
+        max: usize,
+        elems_min: usize,
+        elems_max: usize,
+    },
+    Dates {
+        from: i32,
+        to: i32,
+        formats: Vec<String>,
+    },
+    Walks {
+        min: usize,
+        max: usize,
+        layout: String,
+        turns: usize,
+    },
+    Names {
+        filename: String,
+        formats: Vec<String>,
+    },
+    Frequency {
+        filenames: Vec<String>,
+    },
+    Mask {
+        mask: String,
+        charsets: Vec<String>,
+    },
+    Keys {
+        path: String,
+    },
     Multiple {
         expressions: Vec<Expression>,
     },
 }
 
 impl Expression {
+    // sources that can only be consumed once, like the standard input or the output of a command
+    pub fn is_single_pass(&self) -> bool {
+        match self {
+            Expression::Stdin | Expression::Command { .. } => true,
+            Expression::Multiple { expressions } => expressions.iter().any(|e| e.is_single_pass()),
+            _ => false,
+        }
+    }
+
     pub fn as_string(&self) -> String {
         match self {
             Expression::Constant { value } => value.to_owned(),
             Expression::Wordlist { filename } => filename.to_owned(),
+            Expression::Stdin => "-".to_owned(),
+            Expression::Command { command } => format!("cmd:{command}"),
             Expression::Permutations { min, max, charset } => {
                 format!("#{min}-{max}:{charset}")
             }
@@ -61,6 +129,52 @@ impl Expression {
                 }
             }
             Expression::Glob { pattern } => format!("@{pattern}"),
+            Expression::Markov {
+                filename,
+                order,
+                min,
+                max,
+                limit,
+                save,
+            } => {
+                let expr =
+                    format!("markov:{filename}:order={order}:min={min}:max={max}:limit={limit}");
+                if let Some(save) = save {
+                    format!("{expr}:save={save}")
+                } else {
+                    expr
+                }
+            }
+            Expression::Prince {
+                filename,
+                min,
+                max,
+                elems_min,
+                elems_max,
+            } => format!("prince:{filename}:min={min}:max={max}:elems={elems_min}-{elems_max}"),
+            Expression::Dates { from, to, formats } => {
+                format!("dates:{from}-{to}:formats={}", formats.join(","))
+            }
+            Expression::Walks {
+                min,
+                max,
+                layout,
+                turns,
+            } => format!("walks:{min}-{max}:layout={layout}:turns={turns}"),
+            Expression::Names { filename, formats } => {
+                format!("names:{filename}:formats={}", formats.join(","))
+            }
+            Expression::Frequency { filenames } => format!("freq:{}", filenames.join(",")),
+            Expression::Mask { mask, charsets } => {
+                let mut expr = format!("mask:{mask}");
+                for (i, charset) in charsets.iter().enumerate() {
+                    if !charset.is_empty() {
+                        expr.push_str(&format!(":{}={charset}", i + 1));
+                    }
+                }
+                expr
+            }
+            Expression::Keys { path } => format!("keys:{path}"),
             Expression::Multiple { expressions } => expressions
                 .iter()
                 .map(|e| e.as_string())
@@ -89,6 +203,8 @@ impl fmt::Display for Expression {
         match self {
             Expression::Constant { value } => write!(f, "string '{}'", value),
             Expression::Wordlist { filename } => write!(f, "wordlist {}", filename),
+            Expression::Stdin => write!(f, "stdin"),
+            Expression::Command { command } => write!(f, "output of '{}'", command),
             Expression::Permutations { min, max, charset } => {
                 write!(
                     f,
@@ -97,6 +213,56 @@ impl fmt::Display for Expression {
                 )
             }
             Expression::Glob { pattern } => write!(f, "glob {}", pattern),
+            Expression::Markov {
+                filename,
+                order,
+                min,
+                max,
+                limit,
+                save: _,
+            } => write!(
+                f,
+                "markov {} (order:{} min:{} max:{} limit:{})",
+                filename, order, min, max, limit
+            ),
+            Expression::Prince {
+                filename,
+                min,
+                max,
+                elems_min,
+                elems_max,
+            } => write!(
+                f,
+                "prince {} (min:{} max:{} elements:{}-{})",
+                filename, min, max, elems_min, elems_max
+            ),
+            Expression::Dates { from, to, formats } => {
+                write!(
+                    f,
+                    "dates {} -> {} (formats:{})",
+                    from,
+                    to,
+                    formats.join(",")
+                )
+            }
+            Expression::Walks {
+                min,
+                max,
+                layout,
+                turns,
+            } => write!(
+                f,
+                "keyboard walks (min:{} max:{} layout:{} turns:{})",
+                min, max, layout, turns
+            ),
+            Expression::Names { filename, formats } => {
+                write!(f, "names {} (formats:{})", filename, formats.join(","))
+            }
+            Expression::Frequency { filenames } => {
+                write!(f, "by frequency {}", filenames.join(", "))
+            }
+            Expression::Mask { mask, charsets: _ } => write!(f, "mask {}", mask),
+            Expression::Keys { path } => write!(f, "private keys {}", path),
             Expression::Range { min, max, set } => {
                 if set.is_empty() {
                     write!(f, "range {} -> {}", min, max)
@@ -109,8 +275,218 @@ impl fmt::Display for Expression {
     }
 }
 
+// split the trailing key=value options of a generator expression from its argument, options
+// are at the end so that the argument (usually a file name) can contain colons
+fn split_options(expr: &str) -> (String, Vec<(&str, &str)>) {
+    let mut parts: Vec<&str> = expr.split(':').collect();
+    let mut options = vec![];
+
+    while parts.len() > 1 {
+        let Some(option) = parts[parts.len() - 1].split_once('=') else {
+            break;
+        };
+        options.insert(0, option);
+        parts.pop();
+    }
+
+    (parts.join(":"), options)
+}
+
+// parse markov:<file>[:order=N][:min=N][:max=N][:limit=N][:save=model.json]
+fn parse_markov(expr: &str) -> Option<Expression> {
+    let (filename, options) = split_options(expr);
+    let mut order = DEFAULT_MARKOV_ORDER;
+    let mut min = DEFAULT_MARKOV_MIN_LEN;
+    let mut max = DEFAULT_MARKOV_MAX_LEN;
+    let mut limit = DEFAULT_MARKOV_LIMIT;
+    let mut save = None;
+
+    for (key, value) in options {
+        match key {
+            "order" => order = value.parse().ok()?,
+            "min" => min = value.parse().ok()?,
+            "max" => max = value.parse().ok()?,
+            "limit" => limit = value.parse().ok()?,
+            "save" => save = Some(value.to_owned()),
+            _ => return None,
+        }
+    }
+
+    Some(Expression::Markov {
+        filename,
+        order,
+        min,
+        max,
+        limit,
+        save,
+    })
+}
+
+// parse prince:<file>[:min=N][:max=N][:elems=N-M]
+fn parse_prince(expr: &str) -> Option<Expression> {
+    let (filename, options) = split_options(expr);
+    let mut min = DEFAULT_PRINCE_MIN_LEN;
+    let mut max = DEFAULT_PRINCE_MAX_LEN;
+    let mut elems_min = DEFAULT_PRINCE_ELEMS_MIN;
+    let mut elems_max = DEFAULT_PRINCE_ELEMS_MAX;
+
+    for (key, value) in options {
+        match key {
+            "min" => min = value.parse().ok()?,
+            "max" => max = value.parse().ok()?,
+            "elems" => (elems_min, elems_max) = parse_span(value)?,
+            _ => return None,
+        }
+    }
+
+    Some(Expression::Prince {
+        filename,
+        min,
+        max,
+        elems_min,
+        elems_max,
+    })
+}
+
+// parse N-M or N as a range
+fn parse_span<T: std::str::FromStr + Copy>(value: &str) -> Option<(T, T)> {
+    if let Some((from, to)) = value.split_once('-') {
+        Some((from.trim().parse().ok()?, to.trim().parse().ok()?))
+    } else {
+        let value = value.trim().parse().ok()?;
+        Some((value, value))
+    }
+}
+
+// parse dates:<from>-<to>[:formats=F,F,...]
+fn parse_dates(expr: &str) -> Option<Expression> {
+    let (years, options) = split_options(expr);
+    let (from, to) = parse_span(&years)?;
+    let mut formats = DEFAULT_DATES_FORMATS;
+
+    for (key, value) in options {
+        match key {
+            "formats" => formats = value,
+            _ => return None,
+        }
+    }
+
+    Some(Expression::Dates {
+        from,
+        to,
+        formats: formats
+            .split(',')
+            .map(|f| f.trim().to_owned())
+            .filter(|f| !f.is_empty())
+            .collect(),
+    })
+}
+
+// parse walks:<min>-<max>[:layout=qwerty|qwertz|azerty][:turns=N]
+fn parse_walks(expr: &str) -> Option<Expression> {
+    let (lengths, options) = split_options(expr);
+    let (min, max) = parse_span(&lengths)?;
+    let mut layout = DEFAULT_WALKS_LAYOUT.to_owned();
+    let mut turns = DEFAULT_WALKS_TURNS;
+
+    for (key, value) in options {
+        match key {
+            "layout" => layout = value.to_lowercase(),
+            "turns" => turns = value.parse().ok()?,
+            _ => return None,
+        }
+    }
+
+    Some(Expression::Walks {
+        min,
+        max,
+        layout,
+        turns,
+    })
+}
+
+// parse names:<file>[:formats=F,F,...]
+fn parse_names(expr: &str) -> Option<Expression> {
+    let (filename, options) = split_options(expr);
+    let mut formats = DEFAULT_NAMES_FORMATS;
+
+    for (key, value) in options {
+        match key {
+            "formats" => formats = value,
+            _ => return None,
+        }
+    }
+
+    Some(Expression::Names {
+        filename,
+        formats: formats
+            .split(',')
+            .map(|f| f.trim().to_lowercase())
+            .filter(|f| !f.is_empty())
+            .collect(),
+    })
+}
+
+// parse mask:<mask>[:1=charset][:2=charset][:3=charset][:4=charset]
+fn parse_mask(expr: &str) -> Option<Expression> {
+    let (mask, options) = split_options(expr);
+    let mut charsets = vec![String::new(); 4];
+
+    for (key, value) in options {
+        let index: usize = key.parse().ok()?;
+        if !(1..=4).contains(&index) {
+            return None;
+        }
+        charsets[index - 1] = value.to_owned();
+    }
+
+    // drop the trailing undefined charsets
+    while charsets.last().is_some_and(|c| c.is_empty()) {
+        charsets.pop();
+    }
+
+    Some(Expression::Mask { mask, charsets })
+}
+
+// parse <generator>:<argument>[:key=value ...]
+fn parse_generator(expr: &str) -> Option<Expression> {
+    let (name, rest) = expr.split_once(':')?;
+    match name {
+        // the command line is taken as it is
+        "cmd" => Some(Expression::Command {
+            command: rest.to_owned(),
+        }),
+        "markov" => parse_markov(rest),
+        "prince" => parse_prince(rest),
+        "dates" => parse_dates(rest),
+        "walks" => parse_walks(rest),
+        "names" => parse_names(rest),
+        "mask" => parse_mask(rest),
+        // freq:<file>[,<file>...]
+        "freq" => Some(Expression::Frequency {
+            filenames: rest
+                .split(',')
+                .map(|f| f.trim().to_owned())
+                .filter(|f| !f.is_empty())
+                .collect(),
+        }),
+        // keys:@<folder>, keys:@<glob> or keys:<file>
+        "keys" => Some(Expression::Keys {
+            path: rest.strip_prefix('@').unwrap_or(rest).to_owned(),
+        }),
+        _ => None,
+    }
+}
+
 pub(crate) fn parse_expression(expr: Option<&String>) -> Expression {
     if let Some(expr) = expr {
+        if expr == "-" {
+            // read lazily from stdin
+            return Expression::Stdin;
+        } else if let Some(generator) = parse_generator(expr) {
+            return generator;
+        }
+
         match expr.chars().next().unwrap_or(' ') {
             // permutations or constant
             '#' => {
@@ -253,6 +629,175 @@ mod tests {
         )
     }
 
+    #[test]
+    fn can_parse_stdin() {
+        let res = parse_expression(Some("-".to_owned()).as_ref());
+        assert_eq!(res, Expression::Stdin)
+    }
+
+    #[test]
+    fn can_parse_markov() {
+        let res = parse_expression(Some("markov:/tmp/model.bin:max=10".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Markov {
+                filename: "/tmp/model.bin".to_owned(),
+                order: 3,
+                min: 1,
+                max: 10,
+                limit: 100000,
+                save: None,
+            }
+        );
+
+        let res =
+            parse_expression(Some("markov:c:\\rockyou.txt:order=2:limit=50".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Markov {
+                filename: "c:\\rockyou.txt".to_owned(),
+                order: 2,
+                min: 1,
+                max: 16,
+                limit: 50,
+                save: None,
+            }
+        );
+
+        let res = parse_expression(
+            Some("markov:rockyou.txt:order=4:save=/tmp/rockyou.json".to_owned()).as_ref(),
+        );
+        assert_eq!(
+            res,
+            Expression::Markov {
+                filename: "rockyou.txt".to_owned(),
+                order: 4,
+                min: 1,
+                max: 16,
+                limit: 100000,
+                save: Some("/tmp/rockyou.json".to_owned()),
+            }
+        );
+        assert_eq!(
+            res.as_string(),
+            "markov:rockyou.txt:order=4:min=1:max=16:limit=100000:save=/tmp/rockyou.json"
+        );
+    }
+
+    #[test]
+    fn can_parse_prince() {
+        let res = parse_expression(Some("prince:words.txt:min=8:max=16".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Prince {
+                filename: "words.txt".to_owned(),
+                min: 8,
+                max: 16,
+                elems_min: 2,
+                elems_max: 4,
+            }
+        );
+
+        let res = parse_expression(Some("prince:words.txt:elems=3".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Prince {
+                filename: "words.txt".to_owned(),
+                min: 1,
+                max: 16,
+                elems_min: 3,
+                elems_max: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_dates() {
+        let res =
+            parse_expression(Some("dates:2015-2025:formats=DDMMYYYY,MMYY".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Dates {
+                from: 2015,
+                to: 2025,
+                formats: vec!["DDMMYYYY".to_owned(), "MMYY".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_walks() {
+        let res = parse_expression(Some("walks:4-8:layout=AZERTY".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Walks {
+                min: 4,
+                max: 8,
+                layout: "azerty".to_owned(),
+                turns: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_names() {
+        let res =
+            parse_expression(Some("names:employees.txt:formats=f.last,flast".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Names {
+                filename: "employees.txt".to_owned(),
+                formats: vec!["f.last".to_owned(), "flast".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_command() {
+        let res =
+            parse_expression(Some("cmd:./gen-users.sh --domain=corp:local".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Command {
+                command: "./gen-users.sh --domain=corp:local".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_frequency() {
+        let res = parse_expression(Some("freq:hibp.txt, rockyou.txt".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Frequency {
+                filenames: vec!["hibp.txt".to_owned(), "rockyou.txt".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_mask() {
+        let res = parse_expression(Some("mask:?u?l?l?1?1:1=?d!".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Mask {
+                mask: "?u?l?l?1?1".to_owned(),
+                charsets: vec!["?d!".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn can_parse_keys() {
+        let res = parse_expression(Some("keys:@/path/to/keys/".to_owned()).as_ref());
+        assert_eq!(
+            res,
+            Expression::Keys {
+                path: "/path/to/keys/".to_owned(),
+            }
+        );
+    }
+
     #[test]
     fn can_parse_filename() {
         let res = parse_expression(Some("/etc/hosts".to_owned()).as_ref());
@@ -0,0 +1,190 @@
+use std::io::{BufRead, BufReader, Lines};
+use std::process::{Child, ChildStdout, Command as Process, Stdio};
+use std::sync::{Arc, Mutex};
+
+use crate::{creds, session::Error};
+
+// set if a command died before completing its output, reported once the session is over
+static FAILURE: Mutex<Option<Error>> = Mutex::new(None);
+
+pub(crate) fn failure() -> Option<Error> {
+    FAILURE.lock().unwrap().clone()
+}
+
+// the running command, shared by all the copies of the iterator
+struct Output {
+    command: String,
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+    done: bool,
+}
+
+impl Output {
+    // reap the process once its output is over and fail the session if it didn't exit cleanly
+    fn finish(&mut self) {
+        self.done = true;
+        let error = match self.child.wait() {
+            Ok(status) if status.success() => {
+                log::debug!("'{}' completed", &self.command);
+                return;
+            }
+            Ok(status) => format!("'{}' exited with {}", &self.command, status),
+            Err(e) => format!("can't wait for '{}': {}", &self.command, e),
+        };
+
+        log::error!("{}, its payloads might be incomplete", &error);
+        *FAILURE.lock().unwrap() = Some(error);
+    }
+
+    fn read_next(&mut self) -> Option<String> {
+        if self.done {
+            return None;
+        }
+
+        match self.lines.next() {
+            Some(Ok(line)) => Some(line),
+            Some(Err(e)) => {
+                log::error!("could not read the output of '{}': {:?}", &self.command, e);
+                let _ = self.child.kill();
+                self.finish();
+                None
+            }
+            None => {
+                self.finish();
+                None
+            }
+        }
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+// streams the lines printed by a command as payloads, like the standard input the output can only
+// be consumed once and copies of this iterator share the same reader
+pub(crate) struct Command {
+    output: Arc<Mutex<Output>>,
+}
+
+impl Command {
+    pub fn new(command: String) -> Result<Self, Error> {
+        log::debug!("running '{}' ...", &command);
+
+        #[cfg(windows)]
+        let mut process = {
+            let mut p = Process::new("cmd");
+            p.arg("/C").arg(&command);
+            p
+        };
+        #[cfg(not(windows))]
+        let mut process = {
+            let mut p = Process::new("sh");
+            p.arg("-c").arg(&command);
+            p
+        };
+
+        let mut child = process
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| format!("can't run '{}': {}", command, e))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(format!("can't read the output of '{}'", command))?;
+
+        Ok(Self {
+            output: Arc::new(Mutex::new(Output {
+                command,
+                child,
+                lines: BufReader::new(stdout).lines(),
+                done: false,
+            })),
+        })
+    }
+}
+
+impl creds::Iterator for Command {
+    fn search_space_size(&self) -> usize {
+        // unknown
+        0
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Command {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            output: self.output.clone(),
+        })
+    }
+}
+
+impl std::iter::Iterator for Command {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.output.lock().unwrap().read_next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    #[cfg(not(windows))]
+    fn can_stream_command_output() {
+        let mut gen = iterator::new(Expression::Command {
+            command: "printf 'admin\\nroot\\n'".to_owned(),
+        })
+        .unwrap();
+        let copy = gen.clone();
+
+        // copies share the same output
+        assert_eq!(gen.next(), Some("admin".to_owned()));
+        assert_eq!(copy.collect::<Vec<String>>(), vec!["root"]);
+        assert_eq!(gen.next(), None);
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn runs_the_command_once() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let counter = tmpdir.path().join("runs");
+        let gen = iterator::new(Expression::Command {
+            command: format!("echo x >> {}; printf 'a\\nb\\n'", counter.display()),
+        })
+        .unwrap();
+
+        let mut got = vec![];
+        for _ in 0..3 {
+            got.extend(gen.clone());
+        }
+        assert_eq!(got, vec!["a", "b"]);
+        assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\n");
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn reports_failures() {
+        assert!(iterator::new(Expression::Command {
+            command: "printf 'admin\\n'; exit 1".to_owned(),
+        })
+        .unwrap()
+        .eq(["admin".to_owned()]));
+        assert!(iterator::command_failure()
+            .is_some_and(|e| e.contains("exit 1") && e.contains("exited with")));
+    }
+}
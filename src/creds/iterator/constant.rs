@@ -16,6 +16,12 @@ impl creds::Iterator for Constant {
     fn search_space_size(&self) -> usize {
         1
     }
+
+    fn seek(&mut self, n: usize) {
+        if n > 0 {
+            self.done = true;
+        }
+    }
 }
 
 impl creds::IteratorClone for Constant {
@@ -53,4 +59,11 @@ mod tests {
         assert_eq!(tot, 1);
         assert_eq!(vec, vec!["hi".to_owned()]);
     }
+
+    #[test]
+    fn can_seek_like_skip() {
+        iterator::assert_seek_is_skip(Expression::Constant {
+            value: "hi".to_owned(),
+        });
+    }
 }
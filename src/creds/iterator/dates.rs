@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::{Datelike, NaiveDate};
+
+use crate::{creds, session::Error};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// longest first so that MONTH is not parsed as MON + TH
+const TOKENS: [&str; 9] = ["SEASON", "MONTH", "YYYY", "MON", "YY", "MM", "DD", "M", "D"];
+
+fn season(month: u32) -> &'static str {
+    match month {
+        3..=5 => "Spring",
+        6..=8 => "Summer",
+        9..=11 => "Autumn",
+        _ => "Winter",
+    }
+}
+
+// render the date with a format such as DDMMYYYY or SEASONYYYY!, any other character is literal
+fn render(format: &str, date: &NaiveDate) -> String {
+    let mut out = String::new();
+    let mut rest = format;
+
+    while !rest.is_empty() {
+        if let Some(token) = TOKENS.iter().find(|t| rest.starts_with(*t)) {
+            match *token {
+                "SEASON" => out.push_str(season(date.month())),
+                "MONTH" => out.push_str(MONTHS[date.month0() as usize]),
+                "MON" => out.push_str(&MONTHS[date.month0() as usize][..3]),
+                "YYYY" => out.push_str(&format!("{:04}", date.year())),
+                "YY" => out.push_str(&format!("{:02}", date.year() % 100)),
+                "MM" => out.push_str(&format!("{:02}", date.month())),
+                "DD" => out.push_str(&format!("{:02}", date.day())),
+                "M" => out.push_str(&date.month().to_string()),
+                "D" => out.push_str(&date.day().to_string()),
+                _ => unreachable!(),
+            }
+            rest = &rest[token.len()..];
+        } else {
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    out
+}
+
+// every date between the first and last year rendered with each format
+pub(crate) struct Dates {
+    dates: Arc<Vec<String>>,
+    current: usize,
+}
+
+impl Dates {
+    pub fn new(from: i32, to: i32, formats: Vec<String>) -> Result<Self, Error> {
+        if from > to {
+            return Err("first year can't be greater than the last one".to_owned());
+        } else if formats.is_empty() {
+            return Err("at least one date format is required".to_owned());
+        }
+
+        let first = NaiveDate::from_ymd_opt(from, 1, 1).ok_or(format!("invalid year {}", from))?;
+        let last = NaiveDate::from_ymd_opt(to, 12, 31).ok_or(format!("invalid year {}", to))?;
+
+        let mut dates = vec![];
+        let mut seen = HashSet::new();
+        for format in formats.iter() {
+            let mut date = first;
+            while date <= last {
+                // formats without the day (MMYY, YYYY, ...) render the same value for several dates
+                let value = render(format, &date);
+                if seen.insert(value.clone()) {
+                    dates.push(value);
+                }
+                date = match date.succ_opt() {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+
+        Ok(Self {
+            dates: Arc::new(dates),
+            current: 0,
+        })
+    }
+}
+
+impl creds::Iterator for Dates {
+    fn search_space_size(&self) -> usize {
+        self.dates.len()
+    }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
+}
+
+impl creds::IteratorClone for Dates {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            dates: self.dates.clone(),
+            current: 0,
+        })
+    }
+}
+
+impl std::iter::Iterator for Dates {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.dates.get(self.current)?.to_owned();
+        self.current += 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_generate_dates() {
+        let gen = iterator::new(Expression::Dates {
+            from: 2023,
+            to: 2024,
+            formats: vec!["MMYY".to_owned(), "SEASONYYYY!".to_owned()],
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, vec.len());
+        assert_eq!(vec.len(), 24 + 8);
+        assert_eq!(vec[0], "0123");
+        assert_eq!(vec[23], "1224");
+        assert_eq!(vec[24], "Winter2023!");
+        assert!(vec.contains(&"Summer2024!".to_owned()));
+    }
+
+    #[test]
+    fn can_render_formats() {
+        let gen = iterator::new(Expression::Dates {
+            from: 2024,
+            to: 2024,
+            formats: vec!["DDMMYYYY".to_owned(), "MON-D".to_owned()],
+        })
+        .unwrap();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(vec.len(), 366 * 2);
+        assert_eq!(vec[59], "29022024");
+        assert_eq!(vec[366 + 31], "Feb-1");
+    }
+
+    #[test]
+    fn can_seek_like_skip() {
+        iterator::assert_seek_is_skip(Expression::Dates {
+            from: 2023,
+            to: 2024,
+            formats: vec!["MMYY".to_owned(), "SEASONYYYY!".to_owned()],
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::creds;
+
+// number of bits per expected element and of hash functions, for a ~1% false positive rate
+const BITS_PER_ELEMENT: usize = 10;
+const HASHES: u64 = 7;
+// bounds the memory used by the filter to 16MB
+const MAX_BITS: usize = 1 << 27;
+const MIN_BITS: usize = 1 << 10;
+
+// payloads skipped by all the filters, copies replaying a stream don't count them again
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn skipped() -> usize {
+    SKIPPED.load(Ordering::Relaxed)
+}
+
+struct Bloom {
+    bits: Vec<u64>,
+    size: u64,
+}
+
+impl Bloom {
+    fn new(elements: usize) -> Self {
+        let size = if elements == 0 {
+            // unknown search space
+            MAX_BITS
+        } else {
+            elements
+                .saturating_mul(BITS_PER_ELEMENT)
+                .clamp(MIN_BITS, MAX_BITS)
+        };
+
+        Self {
+            bits: vec![0; size.div_ceil(64)],
+            size: size as u64,
+        }
+    }
+
+    // set the bits for the value returning true if they were all already set
+    fn check_and_set(&mut self, value: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // derive the other hashes with double hashing
+        let h2 = h1.rotate_left(32) | 1;
+
+        let mut found = true;
+        for i in 0..HASHES {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.size;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                found = false;
+                self.bits[word] |= mask;
+            }
+        }
+        found
+    }
+}
+
+// state shared by the copies of the iterator, which replay the same elements
+struct Filter {
+    seen: Bloom,
+    // elements of the stream checked against the filter so far
+    checked: usize,
+    // positions of the duplicates among them, in order
+    duplicates: Vec<usize>,
+}
+
+// normalizes the elements of the inner iterator (BOMs and carriage returns) and skips the ones
+// already seen, a bloom filter is used to bound memory so a few unique elements might be skipped
+pub(crate) struct Dedup {
+    inner: Box<dyn creds::Iterator>,
+    filter: Arc<Mutex<Filter>>,
+    // position of the next element in the stream and index of the next duplicate to skip
+    position: usize,
+    next_duplicate: usize,
+}
+
+impl Dedup {
+    pub fn new(inner: Box<dyn creds::Iterator>) -> Self {
+        let filter = Filter {
+            seen: Bloom::new(inner.search_space_size()),
+            checked: 0,
+            duplicates: vec![],
+        };
+        Self {
+            inner,
+            filter: Arc::new(Mutex::new(filter)),
+            position: 0,
+            next_duplicate: 0,
+        }
+    }
+
+    fn is_duplicate(&mut self, element: &str) -> bool {
+        let mut filter = self.filter.lock().unwrap();
+        let duplicate = if self.position < filter.checked {
+            // already checked by another copy
+            filter.duplicates.get(self.next_duplicate) == Some(&self.position)
+        } else {
+            filter.checked += 1;
+            let duplicate = filter.seen.check_and_set(element);
+            if duplicate {
+                filter.duplicates.push(self.position);
+                SKIPPED.fetch_add(1, Ordering::Relaxed);
+            }
+            duplicate
+        };
+
+        self.position += 1;
+        if duplicate {
+            self.next_duplicate += 1;
+        }
+        duplicate
+    }
+}
+
+impl creds::Iterator for Dedup {
+    fn search_space_size(&self) -> usize {
+        // upper bound, duplicates are skipped
+        self.inner.search_space_size()
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Dedup {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            inner: self.inner.clone(),
+            filter: self.filter.clone(),
+            position: self.position,
+            next_duplicate: self.next_duplicate,
+        })
+    }
+}
+
+impl std::iter::Iterator for Dedup {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let element = self.inner.next()?;
+            let element = element
+                .trim_start_matches('\u{feff}')
+                .trim_end_matches(['\r', '\n']);
+
+            if !self.is_duplicate(element) {
+                return Some(element.to_owned());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dedup;
+    use crate::creds::{iterator, Expression, IteratorClone};
+
+    #[test]
+    fn can_dedup_and_normalize() {
+        let gen = iterator::new(Expression::Multiple {
+            expressions: ["\u{feff}admin", "root\r", "admin", "root", "toor"]
+                .iter()
+                .map(|v| Expression::Constant {
+                    value: v.to_string(),
+                })
+                .collect(),
+        })
+        .unwrap();
+        let gen = iterator::dedup(gen);
+
+        assert_eq!(gen.collect::<Vec<String>>(), vec!["admin", "root", "toor"]);
+        assert!(iterator::skipped_duplicates() >= 2);
+    }
+
+    #[test]
+    fn copies_share_the_filter() {
+        let gen = iterator::new(Expression::Multiple {
+            expressions: ["admin", "root", "admin", "toor", "root"]
+                .iter()
+                .map(|v| Expression::Constant {
+                    value: v.to_string(),
+                })
+                .collect(),
+        })
+        .unwrap();
+        let mut gen = Dedup::new(gen);
+        let copy = gen.create_boxed_copy();
+
+        assert_eq!(gen.next(), Some("admin".to_owned()));
+        assert_eq!(gen.next(), Some("root".to_owned()));
+
+        // copies replay the same elements, whichever is ahead
+        assert_eq!(copy.collect::<Vec<String>>(), vec!["admin", "root", "toor"]);
+        assert_eq!(gen.by_ref().collect::<Vec<String>>(), vec!["toor"]);
+        // and the duplicates are counted once
+        assert_eq!(gen.filter.lock().unwrap().duplicates, vec![2, 4]);
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use crate::creds::{self, policy::Policy};
+
+// skips the elements of the inner iterator that don't satisfy the password policy
+pub(crate) struct Filter {
+    inner: Box<dyn creds::Iterator>,
+    policy: Arc<Policy>,
+    // for combinations, only the part after the separator is checked
+    separator: Option<String>,
+}
+
+impl Filter {
+    pub fn new(
+        inner: Box<dyn creds::Iterator>,
+        policy: Arc<Policy>,
+        separator: Option<String>,
+    ) -> Self {
+        Self {
+            inner,
+            policy,
+            separator,
+        }
+    }
+
+    fn accepts(&self, element: &str) -> bool {
+        if let Some(separator) = self.separator.as_ref() {
+            match element.split_once(separator.as_str()) {
+                Some((_, password)) => self.policy.accepts(password),
+                // malformed lines are reported by the combinator
+                None => true,
+            }
+        } else {
+            self.policy.accepts(element)
+        }
+    }
+}
+
+impl creds::Iterator for Filter {
+    fn search_space_size(&self) -> usize {
+        // upper bound, some elements might be rejected by the policy
+        self.inner.search_space_size()
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Filter {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            inner: self.inner.clone(),
+            policy: self.policy.clone(),
+            separator: self.separator.clone(),
+        })
+    }
+}
+
+impl std::iter::Iterator for Filter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let element = self.inner.next()?;
+            if self.accepts(&element) {
+                return Some(element);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::creds::policy::Policy;
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_filter_combinations() {
+        let gen = iterator::new(Expression::Multiple {
+            expressions: vec![
+                Expression::Constant {
+                    value: "admin:admin".to_owned(),
+                },
+                Expression::Constant {
+                    value: "admin:Admin123!".to_owned(),
+                },
+                Expression::Constant {
+                    value: "garbage".to_owned(),
+                },
+            ],
+        })
+        .unwrap();
+        let policy = Arc::new(Policy::parse("min=8,upper").unwrap());
+        let gen = iterator::filter(gen, policy, Some(":".to_owned()));
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(vec, vec!["admin:Admin123!", "garbage"]);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use crate::{creds, session::Error};
+
+// elements of one or more password<TAB>count wordlists sorted by descending frequency, the counts
+// of elements found in more than one list are summed
+pub(crate) struct Frequency {
+    words: Arc<Vec<String>>,
+    current: usize,
+}
+
+impl Frequency {
+    pub fn new(filenames: Vec<String>) -> Result<Self, Error> {
+        let mut counts: HashMap<String, u64> = HashMap::new();
+
+        for filename in filenames.iter() {
+            log::debug!("loading frequency wordlist from {} ...", filename);
+
+            let file =
+                File::open(filename).map_err(|e| format!("can't open {}: {}", filename, e))?;
+            for line in BufReader::new(file).lines() {
+                let line = line.map_err(|e| e.to_string())?;
+                let line = line.trim_end_matches('\r');
+                if line.is_empty() {
+                    continue;
+                }
+
+                // the count is after the last tab so that the element can contain tabs
+                let (word, count) = match line.rsplit_once('\t') {
+                    Some((word, count)) => match count.trim().parse::<u64>() {
+                        Ok(count) => (word, count),
+                        Err(_) => {
+                            log::warn!("invalid count in '{}' of {}", line, filename);
+                            (word, 1)
+                        }
+                    },
+                    // not annotated
+                    None => (line, 1),
+                };
+
+                *counts.entry(word.to_owned()).or_default() += count;
+            }
+        }
+
+        let mut words: Vec<(String, u64)> = counts.into_iter().collect();
+        words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Ok(Self {
+            words: Arc::new(words.into_iter().map(|(word, _)| word).collect()),
+            current: 0,
+        })
+    }
+}
+
+impl creds::Iterator for Frequency {
+    fn search_space_size(&self) -> usize {
+        self.words.len()
+    }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
+}
+
+impl creds::IteratorClone for Frequency {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            words: self.words.clone(),
+            current: 0,
+        })
+    }
+}
+
+impl std::iter::Iterator for Frequency {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.words.get(self.current)?.to_owned();
+        self.current += 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_merge_by_frequency() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let first = tmpdir.path().join("first.txt");
+        let second = tmpdir.path().join("second.txt");
+        std::fs::write(&first, "123456\t100\npassword\t50\nqwerty\t20\n").unwrap();
+        std::fs::write(&second, "qwerty\t40\nletmein\t60\r\nadmin\n").unwrap();
+
+        let gen = iterator::new(Expression::Frequency {
+            filenames: vec![
+                first.to_str().unwrap().to_owned(),
+                second.to_str().unwrap().to_owned(),
+            ],
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, 5);
+        assert_eq!(
+            vec,
+            vec!["123456", "letmein", "qwerty", "password", "admin"]
+        );
+    }
+
+    #[test]
+    fn can_seek_like_skip() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let first = tmpdir.path().join("first.txt");
+        let second = tmpdir.path().join("second.txt");
+        std::fs::write(&first, "123456\t100\npassword\t50\nqwerty\t20\n").unwrap();
+        std::fs::write(&second, "qwerty\t40\nletmein\t60\r\nadmin\n").unwrap();
+
+        iterator::assert_seek_is_skip(Expression::Frequency {
+            filenames: vec![
+                first.to_str().unwrap().to_owned(),
+                second.to_str().unwrap().to_owned(),
+            ],
+        });
+    }
+}
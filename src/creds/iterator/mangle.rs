@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::creds::{self, rules::Rule};
+
+// applies every rule to each element of the inner iterator
+pub(crate) struct Mangle {
+    inner: Box<dyn creds::Iterator>,
+    rules: Arc<Vec<Rule>>,
+    // for combinations, only the part after the separator is mangled
+    separator: Option<String>,
+    pending: VecDeque<String>,
+}
+
+impl Mangle {
+    pub fn new(
+        inner: Box<dyn creds::Iterator>,
+        rules: Arc<Vec<Rule>>,
+        separator: Option<String>,
+    ) -> Self {
+        log::debug!("loading Mangle with {} rules ...", rules.len());
+
+        Self {
+            inner,
+            rules,
+            separator,
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn expand(&mut self, element: String) {
+        if let Some(separator) = self.separator.as_ref() {
+            match element.split_once(separator.as_str()) {
+                Some((username, password)) => self.pending.extend(
+                    self.rules
+                        .iter()
+                        .filter_map(|rule| rule.apply(password))
+                        .map(|password| format!("{}{}{}", username, separator, password)),
+                ),
+                // malformed lines are reported by the combinator
+                None => self.pending.push_back(element),
+            }
+        } else {
+            self.pending
+                .extend(self.rules.iter().filter_map(|rule| rule.apply(&element)));
+        }
+    }
+}
+
+impl creds::Iterator for Mangle {
+    fn search_space_size(&self) -> usize {
+        // upper bound, some words might be rejected by the rules
+        self.inner.search_space_size() * self.rules.len()
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Mangle {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            inner: self.inner.clone(),
+            rules: self.rules.clone(),
+            separator: self.separator.clone(),
+            pending: self.pending.clone(),
+        })
+    }
+}
+
+impl std::iter::Iterator for Mangle {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.pending.pop_front() {
+                return Some(word);
+            }
+
+            let element = self.inner.next()?;
+            self.expand(element);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::creds::rules::Rule;
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_mangle_words() {
+        let gen = iterator::new(Expression::Range {
+            min: 1,
+            max: 2,
+            set: vec![],
+        })
+        .unwrap();
+        let rules = Arc::new(vec![
+            Rule::parse(":").unwrap(),
+            Rule::parse("$!").unwrap(),
+            Rule::parse("/2 d").unwrap(),
+        ]);
+        let gen = iterator::mangle(gen, rules, None);
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, 6);
+        assert_eq!(vec, vec!["1", "1!", "2", "2!", "22"]);
+    }
+
+    #[test]
+    fn can_mangle_combinations() {
+        let gen = iterator::new(Expression::Multiple {
+            expressions: vec![
+                Expression::Constant {
+                    value: "admin:pass".to_owned(),
+                },
+                Expression::Constant {
+                    value: "malformed".to_owned(),
+                },
+            ],
+        })
+        .unwrap();
+        let rules = Arc::new(vec![Rule::parse(":").unwrap(), Rule::parse("$1").unwrap()]);
+        let gen = iterator::mangle(gen, rules, Some(":".to_owned()));
+
+        assert_eq!(
+            gen.collect::<Vec<String>>(),
+            vec!["admin:pass", "admin:pass1", "malformed"]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{creds, session::Error};
+
+// padding used before the first character of a word
+const START: char = '\x02';
+// marks the end of a word
+const END: char = '\0';
+// when the queue grows past this size the least likely half of it is dropped
+const MAX_QUEUE_SIZE: usize = 4_000_000;
+
+// order-N markov model as stored in a prebuilt model file, produced with the save=model.json
+// option when training from a wordlist:
+//  {"order": 3, "counts": {"\u0002\u0002\u0002": {"p": 10, "1": 3}, ...}}
+#[derive(Serialize, Deserialize, Default)]
+struct Counts {
+    order: usize,
+    counts: HashMap<String, HashMap<char, usize>>,
+}
+
+struct Model {
+    order: usize,
+    // context -> (next character, -log(probability)) sorted by most likely first
+    transitions: HashMap<String, Vec<(char, f64)>>,
+}
+
+impl Model {
+    fn train<I: Iterator<Item = String>>(order: usize, words: I) -> Counts {
+        let mut model = Counts {
+            order,
+            ..Default::default()
+        };
+
+        for word in words {
+            let word = word.trim_end_matches(['\r', '\n']);
+            if word.is_empty() || word.contains([START, END]) {
+                continue;
+            }
+
+            let chars: Vec<char> = std::iter::repeat_n(START, order)
+                .chain(word.chars())
+                .chain(std::iter::once(END))
+                .collect();
+
+            for window in chars.windows(order + 1) {
+                let context: String = window[..order].iter().collect();
+                *model
+                    .counts
+                    .entry(context)
+                    .or_default()
+                    .entry(window[order])
+                    .or_default() += 1;
+            }
+        }
+
+        model
+    }
+
+    fn from_counts(counts: Counts) -> Self {
+        let transitions = counts
+            .counts
+            .into_iter()
+            .map(|(context, next)| {
+                let total: usize = next.values().sum();
+                let mut next: Vec<(char, f64)> = next
+                    .into_iter()
+                    .map(|(c, n)| (c, -(n as f64 / total as f64).ln()))
+                    .collect();
+                next.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+                (context, next)
+            })
+            .collect();
+
+        Self {
+            order: counts.order,
+            transitions,
+        }
+    }
+
+    // load a prebuilt model or train a new one from a wordlist, saving it if requested
+    fn load(path: &str, order: usize, save: Option<&str>) -> Result<Self, Error> {
+        if let Ok(data) = fs::read_to_string(path) {
+            if data.trim_start().starts_with('{') {
+                if let Ok(counts) = serde_json::from_str::<Counts>(&data) {
+                    log::debug!("loaded order {} markov model from {}", counts.order, path);
+                    return Ok(Self::from_counts(counts));
+                }
+            }
+        }
+
+        if order == 0 {
+            return Err("markov order can't be zero".to_owned());
+        }
+
+        log::debug!("training order {} markov model from {} ...", order, path);
+
+        let file = File::open(path).map_err(|e| format!("can't open {}: {}", path, e))?;
+        let words = BufReader::new(file).lines().map_while(Result::ok);
+        let counts = Self::train(order, words);
+
+        if let Some(save) = save {
+            let data = serde_json::to_string(&counts).map_err(|e| e.to_string())?;
+            fs::write(save, data).map_err(|e| format!("can't save {}: {}", save, e))?;
+            log::info!("saved order {} markov model to {}", order, save);
+        }
+
+        Ok(Self::from_counts(counts))
+    }
+
+    fn context_of(&self, word: &[char]) -> String {
+        let pad = self.order.saturating_sub(word.len());
+        std::iter::repeat_n(START, pad)
+            .chain(word[word.len() + pad - self.order..].iter().copied())
+            .collect()
+    }
+}
+
+// a partial or complete candidate in the search queue
+struct Node {
+    cost: f64,
+    word: Vec<char>,
+    complete: bool,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so that the heap pops the least costly (most likely) node first
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.word.cmp(&self.word))
+    }
+}
+
+// emits the words generated by the model in descending order of likelihood, since extending
+// a word can only lower its probability a best-first search yields the exact ordering
+pub(crate) struct Markov {
+    model: Arc<Model>,
+    min_length: usize,
+    max_length: usize,
+    limit: usize,
+    queue: BinaryHeap<Node>,
+    emitted: usize,
+}
+
+impl Markov {
+    pub fn new(
+        path: String,
+        order: usize,
+        min_length: usize,
+        max_length: usize,
+        limit: usize,
+        save: Option<String>,
+    ) -> Result<Self, Error> {
+        if min_length == 0 {
+            return Err("min length can't be zero".to_owned());
+        } else if min_length > max_length {
+            return Err("min length can't be greater than max length".to_owned());
+        }
+
+        let model = Arc::new(Model::load(&path, order, save.as_deref())?);
+
+        Ok(Self::with_model(model, min_length, max_length, limit))
+    }
+
+    fn with_model(model: Arc<Model>, min_length: usize, max_length: usize, limit: usize) -> Self {
+        let mut queue = BinaryHeap::new();
+        queue.push(Node {
+            cost: 0.0,
+            word: vec![],
+            complete: false,
+        });
+
+        Self {
+            model,
+            min_length,
+            max_length,
+            limit,
+            queue,
+            emitted: 0,
+        }
+    }
+
+    fn expand(&mut self, node: Node) {
+        let Some(next) = self
+            .model
+            .transitions
+            .get(&self.model.context_of(&node.word))
+        else {
+            return;
+        };
+
+        for (c, cost) in next {
+            if *c == END {
+                if node.word.len() >= self.min_length {
+                    self.queue.push(Node {
+                        cost: node.cost + cost,
+                        word: node.word.clone(),
+                        complete: true,
+                    });
+                }
+            } else if node.word.len() < self.max_length {
+                let mut word = node.word.clone();
+                word.push(*c);
+                self.queue.push(Node {
+                    cost: node.cost + cost,
+                    word,
+                    complete: false,
+                });
+            }
+        }
+
+        if self.queue.len() > MAX_QUEUE_SIZE {
+            let mut nodes = std::mem::take(&mut self.queue).into_sorted_vec();
+            // sorted from the least to the most likely
+            nodes.drain(..nodes.len() / 2);
+            self.queue = nodes.into();
+        }
+    }
+}
+
+impl creds::Iterator for Markov {
+    fn search_space_size(&self) -> usize {
+        // upper bound, the model might generate less candidates
+        self.limit
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Markov {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self::with_model(
+            self.model.clone(),
+            self.min_length,
+            self.max_length,
+            self.limit,
+        ))
+    }
+}
+
+impl std::iter::Iterator for Markov {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.emitted >= self.limit {
+            return None;
+        }
+
+        while let Some(node) = self.queue.pop() {
+            if node.complete {
+                self.emitted += 1;
+                return Some(node.word.into_iter().collect());
+            }
+            self.expand(node);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Markov, Model};
+
+    fn model(order: usize, words: &[&str]) -> Arc<Model> {
+        Arc::new(Model::from_counts(Model::train(
+            order,
+            words.iter().map(|w| w.to_string()),
+        )))
+    }
+
+    #[test]
+    fn can_generate_in_descending_likelihood() {
+        let model = model(1, &["abc", "abc", "abc", "abd", "ab", "abc", "xyz", "abd"]);
+        let gen = Markov::with_model(model, 1, 10, 100);
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(vec[0], "abc");
+        assert_eq!(vec[1], "abd");
+        assert!(vec.contains(&"xyz".to_owned()));
+        assert!(vec.contains(&"ab".to_owned()));
+    }
+
+    #[test]
+    fn can_honor_length_and_limit() {
+        let model = model(2, &["pass", "password", "passw0rd", "p4ss", "letmein"]);
+
+        let gen = Markov::with_model(model.clone(), 5, 7, 100);
+        let vec: Vec<String> = gen.collect();
+        assert_eq!(vec, vec!["letmein"]);
+
+        let gen = Markov::with_model(model, 1, 10, 2);
+        assert_eq!(gen.count(), 2);
+    }
+
+    #[test]
+    fn can_save_and_load_models() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let wordlist = tmpdir.path().join("words.txt");
+        let saved = tmpdir.path().join("model.json");
+        std::fs::write(&wordlist, "pass\npassword\npassw0rd\np4ss\nletmein\n").unwrap();
+
+        let trained = Markov::new(
+            wordlist.to_str().unwrap().to_owned(),
+            2,
+            1,
+            10,
+            100,
+            Some(saved.to_str().unwrap().to_owned()),
+        )
+        .unwrap();
+
+        // the order of the prebuilt model takes precedence
+        let loaded = Markov::new(saved.to_str().unwrap().to_owned(), 5, 1, 10, 100, None).unwrap();
+
+        assert_eq!(loaded.model.order, 2);
+        assert_eq!(
+            trained.collect::<Vec<String>>(),
+            loaded.collect::<Vec<String>>()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use crate::{creds, session::Error};
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SPECIAL: &str = " !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+// hashcat built-in charsets
+fn builtin(name: char) -> Option<String> {
+    match name {
+        'l' => Some(LOWER.to_owned()),
+        'u' => Some(UPPER.to_owned()),
+        'd' => Some(DIGITS.to_owned()),
+        'h' => Some(format!("{}abcdef", DIGITS)),
+        'H' => Some(format!("{}ABCDEF", DIGITS)),
+        's' => Some(SPECIAL.to_owned()),
+        'a' => Some(format!("{}{}{}{}", LOWER, UPPER, DIGITS, SPECIAL)),
+        _ => None,
+    }
+}
+
+// expand the ?l, ?u, ?d, ?h, ?H, ?s and ?a presets of a charset, any other character (or
+// unknown ?x sequence) is taken as it is, duplicated characters are removed
+pub(crate) fn expand_charset(charset: &str) -> Vec<char> {
+    let mut chars: Vec<char> = vec![];
+    let mut iter = charset.chars().peekable();
+    while let Some(c) = iter.next() {
+        let expanded = match (c, iter.peek()) {
+            ('?', Some(name)) => builtin(*name),
+            _ => None,
+        };
+        if let Some(expanded) = expanded {
+            iter.next();
+            chars.extend(expanded.chars());
+        } else {
+            chars.push(c);
+        }
+    }
+
+    let mut unique = vec![];
+    for c in chars {
+        if !unique.contains(&c) {
+            unique.push(c);
+        }
+    }
+    unique
+}
+
+// parse a mask such as ?u?l?l?l?d?d?d into the list of characters for each position, ?1 to ?4
+// refer to the custom charsets and ?? is a literal question mark
+fn parse_mask(mask: &str, custom: &[String]) -> Result<Vec<Vec<char>>, Error> {
+    let mut positions = vec![];
+    let mut iter = mask.chars();
+    while let Some(c) = iter.next() {
+        if c != '?' {
+            positions.push(vec![c]);
+            continue;
+        }
+
+        let name = iter.next().ok_or("mask can't end with ?")?;
+        let charset = match name {
+            '?' => vec!['?'],
+            '1'..='4' => {
+                let index = name.to_digit(10).unwrap() as usize - 1;
+                match custom.get(index) {
+                    Some(charset) if !charset.is_empty() => expand_charset(charset),
+                    _ => return Err(format!("custom charset ?{} is not defined", name)),
+                }
+            }
+            _ => builtin(name)
+                .ok_or(format!("unknown charset ?{}", name))?
+                .chars()
+                .collect(),
+        };
+        positions.push(charset);
+    }
+
+    if positions.is_empty() {
+        return Err("mask can't be empty".to_owned());
+    }
+
+    Ok(positions)
+}
+
+// hashcat style masks, every position has its own charset
+pub(crate) struct Mask {
+    positions: Arc<Vec<Vec<char>>>,
+    elements: usize,
+    current: usize,
+}
+
+impl Mask {
+    pub fn new(mask: String, custom: Vec<String>) -> Result<Self, Error> {
+        let positions = parse_mask(&mask, &custom)?;
+        let elements = positions
+            .iter()
+            .fold(1usize, |acc, chars| acc.saturating_mul(chars.len()));
+
+        Ok(Self {
+            positions: Arc::new(positions),
+            elements,
+            current: 0,
+        })
+    }
+}
+
+impl creds::Iterator for Mask {
+    fn search_space_size(&self) -> usize {
+        self.elements
+    }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
+}
+
+impl creds::IteratorClone for Mask {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            positions: self.positions.clone(),
+            elements: self.elements,
+            current: 0,
+        })
+    }
+}
+
+impl std::iter::Iterator for Mask {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current >= self.elements {
+            return None;
+        }
+
+        // the last position changes first
+        let mut offset = self.current;
+        let mut value = vec![' '; self.positions.len()];
+        for (pos, chars) in self.positions.iter().enumerate().rev() {
+            value[pos] = chars[offset % chars.len()];
+            offset /= chars.len();
+        }
+        self.current += 1;
+
+        Some(value.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_generate_masks() {
+        let gen = iterator::new(Expression::Mask {
+            mask: "?u?1x??".to_owned(),
+            charsets: vec!["?dä".to_owned()],
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, 26 * 11);
+        assert_eq!(vec.len(), tot);
+        assert_eq!(vec[0], "A0x?");
+        assert_eq!(vec[10], "Aäx?");
+        assert_eq!(vec.last().unwrap(), "Zäx?");
+    }
+
+    #[test]
+    fn can_seek_masks() {
+        let mut gen = iterator::new(Expression::Mask {
+            mask: "?d?d?d".to_owned(),
+            charsets: vec![],
+        })
+        .unwrap();
+        gen.seek(123);
+        assert_eq!(gen.next().unwrap(), "123");
+    }
+
+    #[test]
+    fn cant_use_undefined_charsets() {
+        assert!(iterator::new(Expression::Mask {
+            mask: "?l?2".to_owned(),
+            charsets: vec!["abc".to_owned()],
+        })
+        .is_err());
+    }
+}
@@ -1,17 +1,51 @@
+use std::sync::Arc;
+
 use crate::creds::expression::Expression;
+use crate::creds::mutations::Mutation;
+use crate::creds::policy::Policy;
+use crate::creds::rules::Rule;
 use crate::session::Error;
 
+mod command;
 mod constant;
+mod dates;
+mod dedup;
+mod filter;
+mod frequency;
 mod glob;
+mod keys;
+mod mangle;
+mod markov;
+mod mask;
 mod multi;
+mod mutate;
+mod names;
 mod permutations;
 mod permutator;
+mod prince;
 mod range;
+mod sample;
+mod stdin;
+mod walks;
 mod wordlist;
 
 // https://stackoverflow.com/questions/30353462/how-to-clone-a-struct-storing-a-boxed-trait-object
 pub(crate) trait Iterator: IteratorClone + std::iter::Iterator<Item = String> {
     fn search_space_size(&self) -> usize;
+
+    // false if the search space size is just an upper bound or unknown
+    fn is_exact(&self) -> bool {
+        true
+    }
+
+    // skip the next n elements, generators override this to jump ahead without iterating
+    fn seek(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.next().is_none() {
+                break;
+            }
+        }
+    }
 }
 
 pub(crate) trait IteratorClone {
@@ -30,6 +64,14 @@ pub(crate) fn new(expr: Expression) -> Result<Box<dyn Iterator>, Error> {
             let it = constant::Constant::new(value)?;
             Ok(Box::new(it))
         }
+        Expression::Stdin => {
+            let it = stdin::Stdin::new()?;
+            Ok(Box::new(it))
+        }
+        Expression::Command { command } => {
+            let it = command::Command::new(command)?;
+            Ok(Box::new(it))
+        }
         Expression::Wordlist { filename } => {
             let it = wordlist::Wordlist::new(filename)?;
             Ok(Box::new(it))
@@ -46,6 +88,56 @@ pub(crate) fn new(expr: Expression) -> Result<Box<dyn Iterator>, Error> {
             let it = range::Range::new(min, max, set)?;
             Ok(Box::new(it))
         }
+        Expression::Markov {
+            filename,
+            order,
+            min,
+            max,
+            limit,
+            save,
+        } => {
+            let it = markov::Markov::new(filename, order, min, max, limit, save)?;
+            Ok(Box::new(it))
+        }
+        Expression::Prince {
+            filename,
+            min,
+            max,
+            elems_min,
+            elems_max,
+        } => {
+            let it = prince::Prince::new(filename, min, max, elems_min, elems_max)?;
+            Ok(Box::new(it))
+        }
+        Expression::Dates { from, to, formats } => {
+            let it = dates::Dates::new(from, to, formats)?;
+            Ok(Box::new(it))
+        }
+        Expression::Walks {
+            min,
+            max,
+            layout,
+            turns,
+        } => {
+            let it = walks::Walks::new(min, max, layout, turns)?;
+            Ok(Box::new(it))
+        }
+        Expression::Names { filename, formats } => {
+            let it = names::Names::new(filename, formats)?;
+            Ok(Box::new(it))
+        }
+        Expression::Frequency { filenames } => {
+            let it = frequency::Frequency::new(filenames)?;
+            Ok(Box::new(it))
+        }
+        Expression::Mask { mask, charsets } => {
+            let it = mask::Mask::new(mask, charsets)?;
+            Ok(Box::new(it))
+        }
+        Expression::Keys { path } => {
+            let it = keys::Keys::new(path)?;
+            Ok(Box::new(it))
+        }
         Expression::Multiple { expressions } => {
             let mut iters = vec![];
             for expr in expressions.iter() {
@@ -58,3 +150,58 @@ pub(crate) fn new(expr: Expression) -> Result<Box<dyn Iterator>, Error> {
         }
     }
 }
+
+// seeking by n must yield the same elements as skipping n of them
+#[cfg(test)]
+pub(crate) fn assert_seek_is_skip(expr: Expression) {
+    let all: Vec<String> = new(expr.clone()).unwrap().collect();
+    for n in 0..=all.len() + 1 {
+        let mut gen = new(expr.clone()).unwrap();
+        gen.seek(n);
+        let rest: Vec<String> = gen.collect();
+        assert_eq!(rest, all[n.min(all.len())..], "seek({})", n);
+    }
+}
+
+// apply the rules to every element of the iterator
+pub(crate) fn mangle(
+    it: Box<dyn Iterator>,
+    rules: Arc<Vec<Rule>>,
+    separator: Option<String>,
+) -> Box<dyn Iterator> {
+    Box::new(mangle::Mangle::new(it, rules, separator))
+}
+
+// normalize the elements of the iterator and skip duplicates
+pub(crate) fn dedup(it: Box<dyn Iterator>) -> Box<dyn Iterator> {
+    Box::new(dedup::Dedup::new(it))
+}
+
+// number of duplicated payloads skipped so far
+pub(crate) fn skipped_duplicates() -> usize {
+    dedup::skipped()
+}
+
+// error of a payloads command that died before completing its output
+pub(crate) fn command_failure() -> Option<Error> {
+    command::failure()
+}
+
+// expand every element of the iterator with the chain of mutations
+pub(crate) fn mutate(it: Box<dyn Iterator>, mutations: Arc<Vec<Mutation>>) -> Box<dyn Iterator> {
+    Box::new(mutate::Mutate::new(it, mutations))
+}
+
+// skip the elements of the iterator that don't satisfy the policy
+pub(crate) fn filter(
+    it: Box<dyn Iterator>,
+    policy: Arc<Policy>,
+    separator: Option<String>,
+) -> Box<dyn Iterator> {
+    Box::new(filter::Filter::new(it, policy, separator))
+}
+
+// uniformly sample the elements of the iterator with the given probability
+pub(crate) fn sample(it: Box<dyn Iterator>, probability: f64, seed: u64) -> Box<dyn Iterator> {
+    Box::new(sample::Sample::new(it, probability, seed))
+}
@@ -32,6 +32,25 @@ impl creds::Iterator for Multi {
     fn search_space_size(&self) -> usize {
         self.elements
     }
+
+    fn is_exact(&self) -> bool {
+        self.iters.iter().all(|it| it.is_exact())
+    }
+
+    fn seek(&mut self, mut n: usize) {
+        // skip whole iterators when possible
+        while n > 0 && self.curr_it < self.num_its {
+            let curr = &mut self.iters[self.curr_it];
+            let size = curr.search_space_size();
+            if curr.is_exact() && n >= size {
+                n -= size;
+                self.curr_it += 1;
+            } else {
+                curr.seek(n);
+                return;
+            }
+        }
+    }
 }
 
 impl creds::IteratorClone for Multi {
@@ -57,3 +76,28 @@ impl std::iter::Iterator for Multi {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_seek_like_skip() {
+        iterator::assert_seek_is_skip(Expression::Multiple {
+            expressions: vec![
+                Expression::Constant {
+                    value: "hi".to_owned(),
+                },
+                Expression::Range {
+                    min: 1,
+                    max: 3,
+                    set: vec![],
+                },
+                Expression::Mask {
+                    mask: "?d?d".to_owned(),
+                    charsets: vec![],
+                },
+            ],
+        });
+    }
+}
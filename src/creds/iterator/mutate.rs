@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::creds::{
+    self,
+    mutations::{mutate, Mutation},
+};
+
+// expands each element of the inner iterator with the chain of mutations
+pub(crate) struct Mutate {
+    inner: Box<dyn creds::Iterator>,
+    mutations: Arc<Vec<Mutation>>,
+    pending: VecDeque<String>,
+}
+
+impl Mutate {
+    pub fn new(inner: Box<dyn creds::Iterator>, mutations: Arc<Vec<Mutation>>) -> Self {
+        Self {
+            inner,
+            mutations,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl creds::Iterator for Mutate {
+    fn search_space_size(&self) -> usize {
+        // upper bound, duplicated variants are skipped
+        self.mutations
+            .iter()
+            .fold(self.inner.search_space_size(), |acc, m| {
+                acc.saturating_mul(m.max_variants())
+            })
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Mutate {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            inner: self.inner.clone(),
+            mutations: self.mutations.clone(),
+            pending: self.pending.clone(),
+        })
+    }
+}
+
+impl std::iter::Iterator for Mutate {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(word) = self.pending.pop_front() {
+                return Some(word);
+            }
+
+            let word = self.inner.next()?;
+            self.pending.extend(mutate(&self.mutations, &word));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::creds::mutations::parse_mutations;
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_mutate_words() {
+        let gen = iterator::new(Expression::Multiple {
+            expressions: vec![
+                Expression::Constant {
+                    value: "pass".to_owned(),
+                },
+                Expression::Constant {
+                    value: "123".to_owned(),
+                },
+            ],
+        })
+        .unwrap();
+        let mutations = Arc::new(parse_mutations("capitalize,reverse").unwrap());
+        let gen = iterator::mutate(gen, mutations);
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, 8);
+        assert_eq!(vec, vec!["pass", "ssap", "Pass", "ssaP", "123", "321"]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use crate::{creds, session::Error};
+
+// longest first so that first is not parsed as f + irst
+const TOKENS: [&str; 4] = ["first", "last", "f", "l"];
+
+// render the name with a format such as f.last, any other character is literal
+fn render(format: &str, first: &str, last: &str) -> String {
+    let mut out = String::new();
+    let mut rest = format;
+
+    while !rest.is_empty() {
+        if let Some(token) = TOKENS.iter().find(|t| rest.starts_with(*t)) {
+            match *token {
+                "first" => out.push_str(first),
+                "last" => out.push_str(last),
+                "f" => out.extend(first.chars().next()),
+                "l" => out.extend(last.chars().next()),
+                _ => unreachable!(),
+            }
+            rest = &rest[token.len()..];
+        } else {
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    out
+}
+
+// usernames derived from a list of "First Last" names, one format at a time so that the most
+// likely formats are tried first for every name
+pub(crate) struct Names {
+    usernames: Arc<Vec<String>>,
+    current: usize,
+}
+
+impl Names {
+    pub fn new(path: String, formats: Vec<String>) -> Result<Self, Error> {
+        if formats.is_empty() {
+            return Err("at least one username format is required".to_owned());
+        }
+
+        log::debug!("loading names from {} ...", &path);
+
+        let file = File::open(&path).map_err(|e| format!("can't open {}: {}", path, e))?;
+        let mut names = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| e.to_string())?.to_lowercase();
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 2 {
+                if !parts.is_empty() {
+                    log::debug!("skipping '{}': not in 'first last' format", line);
+                }
+                continue;
+            }
+            // middle names are ignored
+            names.push((parts[0].to_owned(), parts[parts.len() - 1].to_owned()));
+        }
+
+        let mut seen = HashSet::new();
+        let mut usernames = vec![];
+        for format in formats.iter() {
+            for (first, last) in names.iter() {
+                let username = render(format, first, last);
+                if seen.insert(username.clone()) {
+                    usernames.push(username);
+                }
+            }
+        }
+
+        Ok(Self {
+            usernames: Arc::new(usernames),
+            current: 0,
+        })
+    }
+}
+
+impl creds::Iterator for Names {
+    fn search_space_size(&self) -> usize {
+        self.usernames.len()
+    }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
+}
+
+impl creds::IteratorClone for Names {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            usernames: self.usernames.clone(),
+            current: 0,
+        })
+    }
+}
+
+impl std::iter::Iterator for Names {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.usernames.get(self.current)?.to_owned();
+        self.current += 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_derive_usernames() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("employees.txt");
+        std::fs::write(&path, "John Doe\nJane Q. Roe\n\nadmin\n").unwrap();
+
+        let gen = iterator::new(Expression::Names {
+            filename: path.to_str().unwrap().to_owned(),
+            formats: vec![
+                "flast".to_owned(),
+                "first.last".to_owned(),
+                "lastf".to_owned(),
+                "f.last".to_owned(),
+            ],
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, vec.len());
+        assert_eq!(
+            vec,
+            vec!["jdoe", "jroe", "john.doe", "jane.roe", "doej", "roej", "j.doe", "j.roe"]
+        );
+    }
+
+    #[test]
+    fn can_seek_like_skip() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("employees.txt");
+        std::fs::write(&path, "John Doe\nJane Q. Roe\n\nadmin\n").unwrap();
+
+        iterator::assert_seek_is_skip(Expression::Names {
+            filename: path.to_str().unwrap().to_owned(),
+            formats: vec!["flast".to_owned(), "first.last".to_owned()],
+        });
+    }
+}
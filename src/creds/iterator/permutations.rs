@@ -1,5 +1,6 @@
 use crate::{creds, session::Error};
 
+use super::mask::expand_charset;
 use super::permutator::Permutator;
 
 pub(crate) struct Permutations {
@@ -18,7 +19,13 @@ impl Permutations {
             return Err("min length can't be greater than max length".to_owned());
         }
 
-        let permutator = Permutator::new(charset.chars().collect(), min_length, max_length);
+        // the charset can contain presets such as ?l?d
+        let chars = expand_charset(&charset);
+        if chars.is_empty() {
+            return Err("charset can't be empty".to_owned());
+        }
+
+        let permutator = Permutator::new(chars, min_length, max_length);
         let elements = permutator.search_space_size();
 
         Ok(Self {
@@ -35,6 +42,10 @@ impl creds::Iterator for Permutations {
     fn search_space_size(&self) -> usize {
         self.elements
     }
+
+    fn seek(&mut self, n: usize) {
+        self.permutator.seek(n)
+    }
 }
 
 impl creds::IteratorClone for Permutations {
@@ -72,4 +83,20 @@ mod tests {
         assert_eq!(tot, expected.len());
         assert_eq!(vec, expected);
     }
+
+    #[test]
+    fn can_expand_charset_presets() {
+        let gen = iterator::new(Expression::Permutations {
+            min: 1,
+            max: 1,
+            charset: "?dxé".to_owned(),
+        })
+        .unwrap();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(
+            vec,
+            vec!["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "x", "é"]
+        );
+    }
 }
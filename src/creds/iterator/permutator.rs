@@ -1,6 +1,4 @@
-use ahash::AHashMap;
-
-fn search_space_size(charset: &Vec<char>, min_size: usize, max_size: usize) -> usize {
+fn search_space_size(charset: &[char], min_size: usize, max_size: usize) -> usize {
     // compute the number of permutations to generate
     let charset_len = charset.len();
     let mut count = 0;
@@ -10,131 +8,116 @@ fn search_space_size(charset: &Vec<char>, min_size: usize, max_size: usize) -> u
     count
 }
 
-// shamefully stolen from https://github.com/agourlay/zip-password-finder and then modified
-
+// originally from https://github.com/agourlay/zip-password-finder, now an odometer over the
+// charset indexes: the last position changes first and, once every position
+// wrapped around, the permutation grows by one character
 #[derive(Debug)]
 pub(crate) struct Permutator {
     charset: Vec<char>,
-    charset_indices: AHashMap<char, usize>,
-    charset_len: usize,
-    charset_first: char,
-    charset_last: char,
-    max_size: usize,
-    current_len: usize,
-    current_index: usize,
+    min_size: usize,
+    // indexes of the next permutation to generate
+    indexes: Vec<usize>,
     generated_count: usize,
     total_to_generate: usize,
-    permutation: Vec<char>,
 }
 
 impl Permutator {
     pub(crate) fn new(charset: Vec<char>, min_size: usize, max_size: usize) -> Permutator {
-        let charset_len = charset.len();
-        let charset_first = *charset.first().expect("charset non empty");
-        let charset_last = *charset.last().expect("charset non empty");
-
-        // pre-compute charset indices
-        let charset_indices = charset
-            .iter()
-            .enumerate()
-            .map(|(i, c)| (*c, i))
-            .collect::<AHashMap<char, usize>>();
-
-        let permutation = vec![charset_first; min_size];
-        let current_len = permutation.len();
-        let current_index = current_len - 1;
-
-        let generated_count = 0;
+        assert!(!charset.is_empty(), "charset non empty");
+
         let total_to_generate = search_space_size(&charset, min_size, max_size);
 
         Permutator {
             charset,
-            charset_indices,
-            charset_len,
-            charset_first,
-            charset_last,
-            max_size,
-            current_len,
-            current_index,
-            generated_count,
+            min_size,
+            indexes: vec![0; min_size],
+            generated_count: 0,
             total_to_generate,
-            permutation,
         }
     }
 
     pub fn search_space_size(&self) -> usize {
         self.total_to_generate
     }
+
+    // jump n permutations ahead by computing the odometer state directly
+    pub fn seek(&mut self, n: usize) {
+        let target = self.generated_count.saturating_add(n);
+        if target >= self.total_to_generate {
+            self.generated_count = self.total_to_generate;
+            return;
+        }
+
+        let base = self.charset.len();
+        let mut offset = target;
+        let mut len = self.min_size;
+        while offset >= base.pow(len as u32) {
+            offset -= base.pow(len as u32);
+            len += 1;
+        }
+
+        self.indexes = vec![0; len];
+        for pos in (0..len).rev() {
+            self.indexes[pos] = offset % base;
+            offset /= base;
+        }
+        self.generated_count = target;
+    }
+
+    fn advance(&mut self) {
+        for pos in (0..self.indexes.len()).rev() {
+            self.indexes[pos] += 1;
+            if self.indexes[pos] < self.charset.len() {
+                return;
+            }
+            self.indexes[pos] = 0;
+        }
+        // every position wrapped around
+        self.indexes.push(0);
+    }
 }
 
 impl Iterator for Permutator {
     type Item = String;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.permutation.len() > self.max_size {
+        if self.generated_count >= self.total_to_generate {
             return None;
         }
 
-        // first permutation
-        if self.generated_count == 0 {
-            self.generated_count += 1;
-            return Some(self.permutation.iter().collect());
-        }
+        let permutation = self.indexes.iter().map(|i| self.charset[*i]).collect();
+        self.generated_count += 1;
+        self.advance();
 
-        // end of search space
-        if self.generated_count == self.total_to_generate {
-            return None;
-        }
+        Some(permutation)
+    }
+}
 
-        // check if we need to increase the length of the permutation
-        if self.current_len == self.current_index + 1
-            && !self.permutation.iter().any(|&c| c != self.charset_last)
-        {
-            // increase length and reset letters
-            self.current_index += 1;
-            self.current_len += 1;
-            self.permutation = vec![self.charset_first; self.current_len];
-        } else {
-            let current_char = *self.permutation.get(self.current_index).unwrap();
-            if current_char == self.charset_last {
-                // current char reached the end of the charset, reset current and bump previous
-                let at_prev = self
-                    .permutation
-                    .iter()
-                    .rposition(|&c| c != self.charset_last)
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "must find something else than {} in {:?}",
-                            self.charset_last, self.permutation
-                        )
-                    });
-                let next_prev = if at_prev == self.charset_len - 1 {
-                    self.charset.get(self.charset_len - 1).unwrap()
-                } else {
-                    let prev_char = *self.permutation.get(at_prev).unwrap();
-                    let prev_index_charset =
-                        self.charset.iter().position(|&c| c == prev_char).unwrap();
-                    self.charset.get(prev_index_charset + 1).unwrap()
-                };
-
-                self.permutation[self.current_index] = self.charset_first;
-                self.permutation[at_prev] = *next_prev;
-
-                // reset all chars after previous
-                for (i, x) in self.permutation.iter_mut().enumerate() {
-                    if *x == self.charset_last && i > at_prev {
-                        *x = self.charset_first
-                    }
-                }
-            } else {
-                // hot-path: increment current char (not at the end of charset)
-                let at = *self.charset_indices.get(&current_char).unwrap();
-                let next = *self.charset.get(at + 1).unwrap();
-                self.permutation[self.current_index] = next;
-            }
+#[cfg(test)]
+mod tests {
+    use super::Permutator;
+
+    #[test]
+    fn can_generate_every_permutation() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let all: Vec<String> = Permutator::new(charset.clone(), 3, 4).collect();
+
+        assert_eq!(all.len(), 27 + 81);
+        assert_eq!(all[0], "aaa");
+        assert_eq!(all[26], "ccc");
+        assert_eq!(all[27], "aaaa");
+        assert_eq!(all[27 + 3], "aaba");
+        assert_eq!(all.last().unwrap(), "cccc");
+
+        let mut sorted = all.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), all.len());
+
+        for n in 0..=all.len() {
+            let mut seeked = Permutator::new(charset.clone(), 3, 4);
+            seeked.seek(n);
+            assert_eq!(seeked.next(), all.get(n).cloned());
         }
-        self.generated_count += 1;
-        // TODO explore using a lending iterator to avoid allocation
-        Some(self.permutation.iter().collect::<String>())
     }
 }
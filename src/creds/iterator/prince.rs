@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use crate::{creds, session::Error};
+
+// chains elements of a wordlist PRINCE style: words are grouped by length and every chain of
+// element lengths whose sum is within the limits is enumerated, smallest keyspace first
+pub(crate) struct Prince {
+    // words grouped by their length in characters
+    buckets: Arc<Vec<Vec<String>>>,
+    // sequences of element lengths, sorted by keyspace
+    chains: Arc<Vec<Vec<usize>>>,
+    elements: usize,
+    chain: usize,
+    indexes: Vec<usize>,
+}
+
+impl Prince {
+    pub fn new(
+        path: String,
+        min_length: usize,
+        max_length: usize,
+        elems_min: usize,
+        elems_max: usize,
+    ) -> Result<Self, Error> {
+        if min_length > max_length {
+            return Err("min length can't be greater than max length".to_owned());
+        } else if elems_min == 0 {
+            return Err("min elements can't be zero".to_owned());
+        } else if elems_min > elems_max {
+            return Err("min elements can't be greater than max elements".to_owned());
+        }
+
+        log::debug!("loading prince wordlist from {} ...", &path);
+
+        let file = File::open(&path).map_err(|e| format!("can't open {}: {}", path, e))?;
+        let mut seen = HashSet::new();
+        let mut buckets: Vec<Vec<String>> = vec![vec![]; max_length + 1];
+        for line in BufReader::new(file).lines() {
+            let word = line.map_err(|e| e.to_string())?;
+            let len = word.chars().count();
+            if len > 0 && len <= max_length && seen.insert(word.clone()) {
+                buckets[len].push(word);
+            }
+        }
+
+        Ok(Self::with_buckets(
+            buckets, min_length, max_length, elems_min, elems_max,
+        ))
+    }
+
+    fn with_buckets(
+        buckets: Vec<Vec<String>>,
+        min_length: usize,
+        max_length: usize,
+        elems_min: usize,
+        elems_max: usize,
+    ) -> Self {
+        let lengths: Vec<usize> = (1..buckets.len())
+            .filter(|len| !buckets[*len].is_empty())
+            .collect();
+
+        let mut chains = vec![];
+        for elems in elems_min..=elems_max {
+            collect_chains(
+                &lengths,
+                elems,
+                min_length,
+                max_length,
+                &mut vec![],
+                &mut chains,
+            );
+        }
+
+        let keyspace = |chain: &Vec<usize>| {
+            chain
+                .iter()
+                .fold(1usize, |acc, len| acc.saturating_mul(buckets[*len].len()))
+        };
+
+        chains.sort_by_key(keyspace);
+
+        let elements = chains
+            .iter()
+            .fold(0usize, |acc, chain| acc.saturating_add(keyspace(chain)));
+
+        Self {
+            buckets: Arc::new(buckets),
+            chains: Arc::new(chains),
+            elements,
+            chain: 0,
+            indexes: vec![],
+        }
+    }
+}
+
+// recursively build every sequence of `elems` lengths whose sum is within min and max
+fn collect_chains(
+    lengths: &[usize],
+    elems: usize,
+    min_length: usize,
+    max_length: usize,
+    current: &mut Vec<usize>,
+    chains: &mut Vec<Vec<usize>>,
+) {
+    let sum: usize = current.iter().sum();
+    if current.len() == elems {
+        if sum >= min_length {
+            chains.push(current.clone());
+        }
+        return;
+    }
+
+    for len in lengths {
+        if sum + len > max_length {
+            break;
+        }
+        current.push(*len);
+        collect_chains(lengths, elems, min_length, max_length, current, chains);
+        current.pop();
+    }
+}
+
+impl creds::Iterator for Prince {
+    fn search_space_size(&self) -> usize {
+        self.elements
+    }
+
+    fn seek(&mut self, mut n: usize) {
+        while n > 0 {
+            let Some(chain) = self.chains.get(self.chain) else {
+                return;
+            };
+            let sizes: Vec<usize> = chain.iter().map(|len| self.buckets[*len].len()).collect();
+            let keyspace: usize = sizes.iter().product();
+            // position in the current chain from the mixed radix indexes
+            let position = self
+                .indexes
+                .iter()
+                .zip(sizes.iter())
+                .fold(0, |acc, (idx, size)| acc * size + idx);
+
+            if position + n < keyspace {
+                let mut offset = position + n;
+                self.indexes = vec![0; sizes.len()];
+                for pos in (0..sizes.len()).rev() {
+                    self.indexes[pos] = offset % sizes[pos];
+                    offset /= sizes[pos];
+                }
+                return;
+            }
+
+            n -= keyspace - position;
+            self.chain += 1;
+            self.indexes.clear();
+        }
+    }
+}
+
+impl creds::IteratorClone for Prince {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            buckets: self.buckets.clone(),
+            chains: self.chains.clone(),
+            elements: self.elements,
+            chain: 0,
+            indexes: vec![],
+        })
+    }
+}
+
+impl std::iter::Iterator for Prince {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chain = self.chains.get(self.chain)?;
+        if self.indexes.is_empty() {
+            self.indexes = vec![0; chain.len()];
+        }
+
+        let candidate: String = chain
+            .iter()
+            .zip(self.indexes.iter())
+            .map(|(len, idx)| self.buckets[*len][*idx].as_str())
+            .collect();
+
+        // advance to the next combination of this chain, or to the next chain
+        let mut pos = chain.len();
+        loop {
+            if pos == 0 {
+                self.chain += 1;
+                self.indexes.clear();
+                break;
+            }
+            pos -= 1;
+            self.indexes[pos] += 1;
+            if self.indexes[pos] < self.buckets[chain[pos]].len() {
+                break;
+            }
+            self.indexes[pos] = 0;
+        }
+
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_chain_elements() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("words.txt");
+        std::fs::write(&path, "a\nbb\ncc\na\n\nddd\n").unwrap();
+
+        let gen = iterator::new(Expression::Prince {
+            filename: path.to_str().unwrap().to_owned(),
+            min: 3,
+            max: 4,
+            elems_min: 2,
+            elems_max: 3,
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let mut vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, vec.len());
+        // smallest keyspace first
+        assert_eq!(vec[0], "addd");
+
+        vec.sort();
+        assert_eq!(
+            vec,
+            vec![
+                "aaa", "aabb", "aacc", "abb", "abba", "acc", "acca", "addd", "bba", "bbaa", "bbbb",
+                "bbcc", "cca", "ccaa", "ccbb", "cccc", "ddda",
+            ]
+        );
+    }
+
+    #[test]
+    fn can_seek_like_skip() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("words.txt");
+        std::fs::write(&path, "a\nbb\ncc\na\n\nddd\n").unwrap();
+
+        iterator::assert_seek_is_skip(Expression::Prince {
+            filename: path.to_str().unwrap().to_owned(),
+            min: 3,
+            max: 4,
+            elems_min: 2,
+            elems_max: 3,
+        });
+    }
+}
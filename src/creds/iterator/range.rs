@@ -42,6 +42,10 @@ impl creds::Iterator for Range {
     fn search_space_size(&self) -> usize {
         self.elements
     }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
 }
 
 impl creds::IteratorClone for Range {
@@ -107,4 +111,18 @@ mod tests {
         assert_eq!(tot, expected.len());
         assert_eq!(vec, expected);
     }
+
+    #[test]
+    fn can_seek_like_skip() {
+        iterator::assert_seek_is_skip(Expression::Range {
+            min: 1,
+            max: 5,
+            set: vec![],
+        });
+        iterator::assert_seek_is_skip(Expression::Range {
+            min: 0,
+            max: 0,
+            set: vec![1, 666, 2, 234, 5, 19],
+        });
+    }
 }
@@ -0,0 +1,105 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::creds;
+
+// uniformly samples the elements of the inner iterator with the given probability, the gaps
+// between the sampled elements are drawn from a geometric distribution and skipped with seek
+// so that huge generators don't need to be iterated exhaustively
+pub(crate) struct Sample {
+    inner: Box<dyn creds::Iterator>,
+    probability: f64,
+    seed: u64,
+    // every copy gets its own reproducible sequence
+    copies: Arc<AtomicU64>,
+    rng: StdRng,
+}
+
+impl Sample {
+    pub fn new(inner: Box<dyn creds::Iterator>, probability: f64, seed: u64) -> Self {
+        Self {
+            inner,
+            probability,
+            seed,
+            copies: Arc::new(AtomicU64::new(0)),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl creds::Iterator for Sample {
+    fn search_space_size(&self) -> usize {
+        // expected number of samples
+        (self.inner.search_space_size() as f64 * self.probability).ceil() as usize
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Sample {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        let copy = self.copies.fetch_add(1, Ordering::Relaxed) + 1;
+        Box::new(Self {
+            inner: self.inner.clone(),
+            probability: self.probability,
+            seed: self.seed,
+            copies: self.copies.clone(),
+            rng: StdRng::seed_from_u64(self.seed.wrapping_add(copy)),
+        })
+    }
+}
+
+impl std::iter::Iterator for Sample {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.probability < 1.0 {
+            // number of elements to skip before the next sampled one
+            let uniform: f64 = 1.0 - self.rng.gen::<f64>();
+            let gap = (uniform.ln() / (1.0 - self.probability).ln()).floor() as usize;
+            self.inner.seek(gap);
+        }
+        self.inner.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_sample_huge_keyspaces() {
+        let gen = iterator::new(Expression::Permutations {
+            min: 1,
+            max: 12,
+            charset: "?l?d".to_owned(),
+        })
+        .unwrap();
+        let total = gen.search_space_size();
+        let sampled = iterator::sample(gen, 1e-15, 42);
+        let expected = sampled.search_space_size();
+        let vec: Vec<String> = sampled.collect();
+
+        assert_eq!(expected, (total as f64 * 1e-15).ceil() as usize);
+        assert!(!vec.is_empty() && vec.len() < expected * 4);
+    }
+
+    #[test]
+    fn can_reproduce_samples() {
+        let gen = iterator::new(Expression::Range {
+            min: 0,
+            max: 9999,
+            set: vec![],
+        })
+        .unwrap();
+        let a: Vec<String> = iterator::sample(gen.clone(), 0.01, 1).collect();
+        let b: Vec<String> = iterator::sample(gen, 0.01, 1).collect();
+
+        assert_eq!(a, b);
+        assert!(a.len() > 50 && a.len() < 200);
+    }
+}
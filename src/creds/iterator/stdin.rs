@@ -0,0 +1,48 @@
+use std::io;
+
+use crate::{creds, session::Error};
+
+// lazily reads one element per line from the standard input, the stream can only be consumed
+// once and it's buffered process-wide, so copies of this iterator share the same reader
+pub(crate) struct Stdin {}
+
+impl Stdin {
+    pub fn new() -> Result<Self, Error> {
+        log::debug!("reading from stdin ...");
+
+        Ok(Self {})
+    }
+}
+
+impl creds::Iterator for Stdin {
+    fn search_space_size(&self) -> usize {
+        // unknown
+        0
+    }
+
+    fn is_exact(&self) -> bool {
+        false
+    }
+}
+
+impl creds::IteratorClone for Stdin {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {})
+    }
+}
+
+impl std::iter::Iterator for Stdin {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_owned()),
+            Err(e) => {
+                log::error!("could not read from stdin: {:?}", e);
+                None
+            }
+        }
+    }
+}
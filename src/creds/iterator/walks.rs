@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{creds, session::Error};
+
+// rows of the supported layouts, each row is shifted half a key to the right of the one above
+const QWERTY: [&str; 4] = ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"];
+const QWERTZ: [&str; 4] = ["1234567890ß", "qwertzuiopü", "asdfghjklöä", "yxcvbnm,.-"];
+const AZERTY: [&str; 4] = ["1234567890", "azertyuiop", "qsdfghjklm", "wxcvbn,;:!"];
+
+// left, right, up left, up right, down left, down right
+const DIRECTIONS: [(isize, isize); 6] = [(0, -1), (0, 1), (-1, 0), (-1, 1), (1, -1), (1, 0)];
+
+fn layout(name: &str) -> Result<Vec<Vec<char>>, Error> {
+    let rows = match name {
+        "qwerty" => QWERTY,
+        "qwertz" => QWERTZ,
+        "azerty" => AZERTY,
+        _ => return Err(format!("unsupported keyboard layout {}", name)),
+    };
+    Ok(rows.iter().map(|row| row.chars().collect()).collect())
+}
+
+// keyboard walks such as qwerty, 1qaz or zaq123: sequences of adjacent keys going in a
+// straight line and changing direction at most `turns` times
+pub(crate) struct Walks {
+    walks: Arc<Vec<String>>,
+    current: usize,
+}
+
+struct Walker<'a> {
+    keys: &'a [Vec<char>],
+    min_length: usize,
+    max_length: usize,
+    turns: usize,
+    seen: HashSet<String>,
+    walks: Vec<String>,
+}
+
+impl Walker<'_> {
+    fn key(&self, row: isize, col: isize) -> Option<char> {
+        if row < 0 || col < 0 {
+            return None;
+        }
+        self.keys.get(row as usize)?.get(col as usize).copied()
+    }
+
+    fn walk(&mut self, path: &mut String, pos: (isize, isize), dir: Option<usize>, turns: usize) {
+        let len = path.chars().count();
+        if len >= self.min_length && self.seen.insert(path.clone()) {
+            self.walks.push(path.clone());
+        }
+        if len == self.max_length {
+            return;
+        }
+
+        for (d, (dr, dc)) in DIRECTIONS.iter().enumerate() {
+            let turns = match dir {
+                Some(current) if current != d => turns + 1,
+                _ => turns,
+            };
+            if turns > self.turns {
+                continue;
+            }
+
+            let next = (pos.0 + dr, pos.1 + dc);
+            if let Some(key) = self.key(next.0, next.1) {
+                path.push(key);
+                self.walk(path, next, Some(d), turns);
+                path.pop();
+            }
+        }
+    }
+}
+
+impl Walks {
+    pub fn new(
+        min_length: usize,
+        max_length: usize,
+        layout_name: String,
+        turns: usize,
+    ) -> Result<Self, Error> {
+        if min_length == 0 {
+            return Err("min length can't be zero".to_owned());
+        } else if min_length > max_length {
+            return Err("min length can't be greater than max length".to_owned());
+        }
+
+        let keys = layout(&layout_name)?;
+        let mut walker = Walker {
+            keys: &keys,
+            min_length,
+            max_length,
+            turns,
+            seen: HashSet::new(),
+            walks: vec![],
+        };
+
+        for (row, chars) in keys.iter().enumerate() {
+            for (col, key) in chars.iter().enumerate() {
+                walker.walk(&mut key.to_string(), (row as isize, col as isize), None, 0);
+            }
+        }
+
+        // shortest first
+        let mut walks = walker.walks;
+        walks.sort_by_key(|w| w.chars().count());
+
+        Ok(Self {
+            walks: Arc::new(walks),
+            current: 0,
+        })
+    }
+}
+
+impl creds::Iterator for Walks {
+    fn search_space_size(&self) -> usize {
+        self.walks.len()
+    }
+
+    fn seek(&mut self, n: usize) {
+        self.current = self.current.saturating_add(n);
+    }
+}
+
+impl creds::IteratorClone for Walks {
+    fn create_boxed_copy(&self) -> Box<dyn creds::Iterator> {
+        Box::new(Self {
+            walks: self.walks.clone(),
+            current: 0,
+        })
+    }
+}
+
+impl std::iter::Iterator for Walks {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.walks.get(self.current)?.to_owned();
+        self.current += 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::creds::{iterator, Expression};
+
+    #[test]
+    fn can_generate_keyboard_walks() {
+        let gen = iterator::new(Expression::Walks {
+            min: 4,
+            max: 8,
+            layout: "qwerty".to_owned(),
+            turns: 1,
+        })
+        .unwrap();
+        let tot = gen.search_space_size();
+        let vec: Vec<String> = gen.collect();
+
+        assert_eq!(tot, vec.len());
+        assert!(vec.iter().all(|w| w.len() >= 4 && w.len() <= 8));
+        for walk in [
+            "qwer", "qwerty", "asdfgh", "1qaz", "zaq123", "qwedc", "poiuy", "12345678",
+        ] {
+            assert!(vec.contains(&walk.to_owned()), "{} not found", walk);
+        }
+        // two turns
+        assert!(!vec.contains(&"qwedcx".to_owned()));
+    }
+
+    #[test]
+    fn can_seek_like_skip() {
+        iterator::assert_seek_is_skip(Expression::Walks {
+            min: 3,
+            max: 4,
+            layout: "qwerty".to_owned(),
+            turns: 1,
+        });
+    }
+}
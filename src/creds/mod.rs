@@ -1,18 +1,28 @@
 mod combinator;
 mod expression;
 mod iterator;
+mod mutations;
+mod policy;
+mod rules;
+mod template;
 
 pub(crate) use combinator::{Combinator, IterationStrategy};
 pub(crate) use expression::{parse_expression, Expression};
-pub(crate) use iterator::{Iterator, IteratorClone};
+pub(crate) use iterator::{command_failure, skipped_duplicates, Iterator, IteratorClone};
+pub(crate) use template::{interpolate, PASSWORD, TEMPLATES, USERNAME};
 
 use serde::{Deserialize, Serialize};
 
+use crate::session::Error;
+
 #[derive(Serialize, Deserialize, PartialEq, PartialOrd, Default, Clone, Debug)]
 pub(crate) struct Credentials {
     pub target: String,
     pub username: String,
     pub password: String,
+    // from the extended targets file, reported with the loot
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl Credentials {
@@ -24,4 +34,64 @@ impl Credentials {
             &self.username
         }
     }
+
+    // returns the NT hash if the password is in the ntlm:LM:NT or ntlm:NT form, for pass-the-hash
+    pub fn ntlm_hash(&self) -> Result<Option<String>, Error> {
+        let Some(hashes) = self.password.strip_prefix("ntlm:") else {
+            return Ok(None);
+        };
+        let nt = match hashes.split_once(':') {
+            Some((lm, nt)) => {
+                if !is_ntlm_hash(lm) {
+                    return Err(format!("invalid LM hash '{}'", lm));
+                }
+                nt
+            }
+            None => hashes,
+        };
+        if !is_ntlm_hash(nt) {
+            return Err(format!("invalid NT hash '{}'", nt));
+        }
+        Ok(Some(nt.to_ascii_lowercase()))
+    }
+}
+
+fn is_ntlm_hash(hash: &str) -> bool {
+    hash.len() == 32 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Credentials;
+
+    fn with_password(password: &str) -> Credentials {
+        Credentials {
+            target: "127.0.0.1".to_owned(),
+            username: "admin".to_owned(),
+            password: password.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn can_parse_ntlm_hashes() {
+        let nt = "31d6cfe0d16ae931b73c59d7e0c089c0";
+        assert_eq!(with_password("hunter2").ntlm_hash(), Ok(None));
+        assert_eq!(
+            with_password(&format!("ntlm:{}", nt)).ntlm_hash(),
+            Ok(Some(nt.to_owned()))
+        );
+        assert_eq!(
+            with_password(&format!(
+                "ntlm:AAD3B435B51404EEAAD3B435B51404EE:{}",
+                nt.to_uppercase()
+            ))
+            .ntlm_hash(),
+            Ok(Some(nt.to_owned()))
+        );
+        assert!(with_password("ntlm:nope").ntlm_hash().is_err());
+        assert!(with_password(&format!("ntlm:xyz:{}", nt))
+            .ntlm_hash()
+            .is_err());
+    }
 }
@@ -0,0 +1,203 @@
+use crate::session::Error;
+
+const LEET: [(char, char); 8] = [
+    ('a', '4'),
+    ('a', '@'),
+    ('e', '3'),
+    ('i', '1'),
+    ('o', '0'),
+    ('s', '5'),
+    ('s', '$'),
+    ('t', '7'),
+];
+
+const SPECIAL: &str = "!@#$%&*?.";
+
+// a payload modifier expanding a word into its common variants, the original word is always
+// the first variant so that chained modifiers combine with each other
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Mutation {
+    Leet,
+    Capitalize,
+    Upper,
+    Reverse,
+    AppendDigits(usize),
+    PrependDigits(usize),
+    AppendSpecial,
+    AppendYears(u32, u32),
+}
+
+// all the digit strings from 1 to max characters
+fn digits(max: usize) -> Vec<String> {
+    let mut all = vec![];
+    for len in 1..=max {
+        for n in 0..10usize.pow(len as u32) {
+            all.push(format!("{:0width$}", n, width = len));
+        }
+    }
+    all
+}
+
+impl Mutation {
+    pub fn parse(expr: &str) -> Result<Self, Error> {
+        let (name, arg) = if let Some((name, arg)) = expr.split_once(':') {
+            (name.trim(), Some(arg.trim()))
+        } else {
+            (expr.trim(), None)
+        };
+
+        let digits = |arg: Option<&str>| -> Result<usize, Error> {
+            let n = arg.unwrap_or("1").parse().unwrap_or(0);
+            if n == 0 || n > 6 {
+                Err(format!("{} requires a number of digits from 1 to 6", name))
+            } else {
+                Ok(n)
+            }
+        };
+
+        match (name, arg) {
+            ("leet", None) => Ok(Self::Leet),
+            ("capitalize", None) => Ok(Self::Capitalize),
+            ("upper", None) => Ok(Self::Upper),
+            ("reverse", None) => Ok(Self::Reverse),
+            ("append-digits", arg) => Ok(Self::AppendDigits(digits(arg)?)),
+            ("prepend-digits", arg) => Ok(Self::PrependDigits(digits(arg)?)),
+            ("append-special", None) => Ok(Self::AppendSpecial),
+            ("append-years", Some(arg)) => {
+                let (from, to) = arg.split_once('-').unwrap_or((arg, arg));
+                match (from.parse(), to.parse()) {
+                    (Ok(from), Ok(to)) if from <= to => Ok(Self::AppendYears(from, to)),
+                    _ => Err(format!("invalid years range '{}'", arg)),
+                }
+            }
+            _ => Err(format!("invalid mutation '{}'", expr)),
+        }
+    }
+
+    // maximum number of variants generated for a word, used to estimate the search space
+    pub fn max_variants(&self) -> usize {
+        match self {
+            Self::Leet => LEET.len() + 2,
+            Self::Capitalize | Self::Upper => 2,
+            Self::Reverse => 2,
+            Self::AppendDigits(n) | Self::PrependDigits(n) => {
+                1 + (1..=*n).map(|len| 10usize.pow(len as u32)).sum::<usize>()
+            }
+            Self::AppendSpecial => 1 + SPECIAL.len(),
+            Self::AppendYears(from, to) => 1 + (to - from + 1) as usize,
+        }
+    }
+
+    pub fn apply(&self, word: &str) -> Vec<String> {
+        let mut variants = vec![word.to_owned()];
+
+        match self {
+            Self::Leet => {
+                // every single substitution, then all of them at once
+                let lower = word.to_lowercase();
+                for (from, to) in LEET {
+                    if lower.contains(from) {
+                        variants
+                            .push(word.replace([from, from.to_ascii_uppercase()], &to.to_string()));
+                    }
+                }
+                let mut all = word.to_owned();
+                for (from, to) in LEET {
+                    all = all.replace([from, from.to_ascii_uppercase()], &to.to_string());
+                }
+                variants.push(all);
+            }
+            Self::Capitalize => {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    variants.push(first.to_uppercase().chain(chars).collect());
+                }
+            }
+            Self::Upper => variants.push(word.to_uppercase()),
+            Self::Reverse => variants.push(word.chars().rev().collect()),
+            Self::AppendDigits(n) => {
+                variants.extend(digits(*n).into_iter().map(|d| format!("{}{}", word, d)))
+            }
+            Self::PrependDigits(n) => {
+                variants.extend(digits(*n).into_iter().map(|d| format!("{}{}", d, word)))
+            }
+            Self::AppendSpecial => {
+                variants.extend(SPECIAL.chars().map(|c| format!("{}{}", word, c)))
+            }
+            Self::AppendYears(from, to) => {
+                variants.extend((*from..=*to).map(|year| format!("{}{}", word, year)))
+            }
+        }
+
+        // keep the first occurrence only, preserving the order
+        let mut unique = Vec::with_capacity(variants.len());
+        for variant in variants {
+            if !unique.contains(&variant) {
+                unique.push(variant);
+            }
+        }
+        unique
+    }
+}
+
+// parse a comma separated list of mutations such as leet,capitalize,append-digits:2
+pub(crate) fn parse_mutations(expr: &str) -> Result<Vec<Mutation>, Error> {
+    expr.split(',')
+        .map(|m| m.trim())
+        .filter(|m| !m.is_empty())
+        .map(Mutation::parse)
+        .collect()
+}
+
+// apply the chain of mutations to the word
+pub(crate) fn mutate(mutations: &[Mutation], word: &str) -> Vec<String> {
+    let mut variants = vec![word.to_owned()];
+    for mutation in mutations {
+        let mut next = vec![];
+        for variant in variants.iter() {
+            for mutated in mutation.apply(variant) {
+                if !next.contains(&mutated) {
+                    next.push(mutated);
+                }
+            }
+        }
+        variants = next;
+    }
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mutate, parse_mutations, Mutation};
+
+    #[test]
+    fn can_parse_mutations() {
+        assert_eq!(
+            parse_mutations("leet, capitalize,append-digits:2,append-years:2020-2024").unwrap(),
+            vec![
+                Mutation::Leet,
+                Mutation::Capitalize,
+                Mutation::AppendDigits(2),
+                Mutation::AppendYears(2020, 2024)
+            ]
+        );
+        assert!(parse_mutations("leet,foo").is_err());
+        assert!(parse_mutations("append-digits:9").is_err());
+        assert!(parse_mutations("append-years:2024-2020").is_err());
+    }
+
+    #[test]
+    fn can_apply_mutations() {
+        assert_eq!(
+            Mutation::Leet.apply("test"),
+            vec!["test", "t3st", "te5t", "te$t", "7es7", "7357"]
+        );
+        assert_eq!(Mutation::AppendDigits(2).apply("a").len(), 111);
+
+        let mutations = parse_mutations("capitalize,append-special").unwrap();
+        let variants = mutate(&mutations, "summer");
+        assert_eq!(variants.len(), 20);
+        assert_eq!(&variants[..3], &["summer", "summer!", "summer@"]);
+        assert!(variants.contains(&"Summer!".to_owned()));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::session::Error;
+
+// password complexity policy of the target, used to skip candidates that can't be valid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Policy {
+    min: usize,
+    max: Option<usize>,
+    upper: bool,
+    lower: bool,
+    digit: bool,
+    special: bool,
+    // minimum number of character classes, for "3 out of 4" style policies
+    classes: usize,
+}
+
+impl Policy {
+    // parse a policy such as min=8,upper,digit,special or min=8,classes=3
+    pub fn parse(expr: &str) -> Result<Self, Error> {
+        let mut policy = Self::default();
+
+        for item in expr.split(',').map(|i| i.trim()).filter(|i| !i.is_empty()) {
+            let (key, value) = if let Some((key, value)) = item.split_once('=') {
+                let value = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("invalid value for password policy {}", key))?;
+                (key.trim(), Some(value))
+            } else {
+                (item, None)
+            };
+
+            match (key, value) {
+                ("min", Some(value)) => policy.min = value,
+                ("max", Some(value)) => policy.max = Some(value),
+                ("classes", Some(value)) => policy.classes = value,
+                ("upper", None) => policy.upper = true,
+                ("lower", None) => policy.lower = true,
+                ("digit", None) => policy.digit = true,
+                ("special", None) => policy.special = true,
+                _ => return Err(format!("invalid password policy requirement '{}'", item)),
+            }
+        }
+
+        Ok(policy)
+    }
+
+    pub fn accepts(&self, password: &str) -> bool {
+        let len = password.chars().count();
+        if len < self.min || self.max.is_some_and(|max| len > max) {
+            return false;
+        }
+
+        let upper = password.chars().any(|c| c.is_uppercase());
+        let lower = password.chars().any(|c| c.is_lowercase());
+        let digit = password.chars().any(|c| c.is_ascii_digit());
+        let special = password.chars().any(|c| !c.is_alphanumeric());
+
+        if (self.upper && !upper)
+            || (self.lower && !lower)
+            || (self.digit && !digit)
+            || (self.special && !special)
+        {
+            return false;
+        }
+
+        [upper, lower, digit, special]
+            .iter()
+            .filter(|c| **c)
+            .count()
+            >= self.classes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Policy;
+
+    #[test]
+    fn can_parse_policy() {
+        assert_eq!(
+            Policy::parse("min=8, upper,digit,special").unwrap(),
+            Policy {
+                min: 8,
+                upper: true,
+                digit: true,
+                special: true,
+                ..Default::default()
+            }
+        );
+        assert!(Policy::parse("min").is_err());
+        assert!(Policy::parse("min=eight").is_err());
+        assert!(Policy::parse("uppercase").is_err());
+    }
+
+    #[test]
+    fn can_check_passwords() {
+        let policy = Policy::parse("min=8,upper,digit,special").unwrap();
+        assert!(policy.accepts("Summer2024!"));
+        assert!(!policy.accepts("Summer2024"));
+        assert!(!policy.accepts("summer2024!"));
+        assert!(!policy.accepts("Sum24!"));
+
+        let policy = Policy::parse("min=6,max=10,classes=3").unwrap();
+        assert!(policy.accepts("Password1"));
+        assert!(policy.accepts("password1!"));
+        assert!(!policy.accepts("password1"));
+        assert!(!policy.accepts("Password123!"));
+    }
+}
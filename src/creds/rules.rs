@@ -0,0 +1,416 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use crate::session::Error;
+
+// a single hashcat rule function, see https://hashcat.net/wiki/doku.php?id=rule_based_attack, the
+// rejection rules follow hashcat rather than john where the two differ
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Noop,
+    Lower,
+    Upper,
+    Capitalize,
+    InvertCapitalize,
+    ToggleAll,
+    ToggleAt(usize),
+    Reverse,
+    Duplicate,
+    DuplicateN(usize),
+    Reflect,
+    RotateLeft,
+    RotateRight,
+    Append(char),
+    Prepend(char),
+    DeleteFirst,
+    DeleteLast,
+    DeleteAt(usize),
+    Extract(usize, usize),
+    Omit(usize, usize),
+    Insert(usize, char),
+    Overwrite(usize, char),
+    Truncate(usize),
+    Replace(char, char),
+    Purge(char),
+    DuplicateFirst(usize),
+    DuplicateLast(usize),
+    DuplicateAll,
+    SwapFront,
+    SwapBack,
+    Swap(usize, usize),
+    Title,
+    DuplicateBlockFront(usize),
+    DuplicateBlockBack(usize),
+    Increment(usize),
+    Decrement(usize),
+    // rejections
+    RejectLessThan(usize),
+    RejectGreaterThan(usize),
+    RejectEqualLength(usize),
+    RejectContains(char),
+    RejectNotContains(char),
+    RejectNotStartsWith(char),
+    RejectNotEndsWith(char),
+    RejectNotAt(usize, char),
+    RejectNotCount(usize, char),
+}
+
+// rule positions are 0-9 and A-Z for 10-35
+fn parse_position(c: char) -> Option<usize> {
+    match c {
+        '0'..='9' => Some(c as usize - '0' as usize),
+        'A'..='Z' => Some(c as usize - 'A' as usize + 10),
+        _ => None,
+    }
+}
+
+/// A list of rule functions applied in sequence to a word.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Rule {
+    ops: Vec<Op>,
+}
+
+impl Rule {
+    pub fn parse(rule: &str) -> Result<Self, Error> {
+        let mut ops = vec![];
+        let mut chars = rule.chars();
+        let err = || format!("invalid rule '{}'", rule);
+
+        while let Some(c) = chars.next() {
+            let mut pos = || chars.next().and_then(parse_position).ok_or_else(err);
+            let op = match c {
+                ' ' | '\t' => continue,
+                ':' => Op::Noop,
+                'l' => Op::Lower,
+                'u' => Op::Upper,
+                'c' => Op::Capitalize,
+                'C' => Op::InvertCapitalize,
+                't' => Op::ToggleAll,
+                'T' => Op::ToggleAt(pos()?),
+                'r' => Op::Reverse,
+                'd' => Op::Duplicate,
+                'p' => Op::DuplicateN(pos()?),
+                'f' => Op::Reflect,
+                '{' => Op::RotateLeft,
+                '}' => Op::RotateRight,
+                '[' => Op::DeleteFirst,
+                ']' => Op::DeleteLast,
+                'D' => Op::DeleteAt(pos()?),
+                '\'' => Op::Truncate(pos()?),
+                'z' => Op::DuplicateFirst(pos()?),
+                'Z' => Op::DuplicateLast(pos()?),
+                'q' => Op::DuplicateAll,
+                'k' => Op::SwapFront,
+                'K' => Op::SwapBack,
+                'E' => Op::Title,
+                'y' => Op::DuplicateBlockFront(pos()?),
+                'Y' => Op::DuplicateBlockBack(pos()?),
+                '+' => Op::Increment(pos()?),
+                '-' => Op::Decrement(pos()?),
+                '<' => Op::RejectGreaterThan(pos()?),
+                '>' => Op::RejectLessThan(pos()?),
+                '_' => Op::RejectEqualLength(pos()?),
+                'x' => {
+                    let (n, m) = (pos()?, pos()?);
+                    Op::Extract(n, m)
+                }
+                'O' => {
+                    let (n, m) = (pos()?, pos()?);
+                    Op::Omit(n, m)
+                }
+                '*' => {
+                    let (n, m) = (pos()?, pos()?);
+                    Op::Swap(n, m)
+                }
+                'i' | 'o' | '=' | '%' => {
+                    let n = pos()?;
+                    let x = chars.next().ok_or_else(err)?;
+                    match c {
+                        'i' => Op::Insert(n, x),
+                        'o' => Op::Overwrite(n, x),
+                        '=' => Op::RejectNotAt(n, x),
+                        _ => Op::RejectNotCount(n, x),
+                    }
+                }
+                's' => {
+                    let x = chars.next().ok_or_else(err)?;
+                    let y = chars.next().ok_or_else(err)?;
+                    Op::Replace(x, y)
+                }
+                '$' | '^' | '@' | '!' | '/' | '(' | ')' => {
+                    let x = chars.next().ok_or_else(err)?;
+                    match c {
+                        '$' => Op::Append(x),
+                        '^' => Op::Prepend(x),
+                        '@' => Op::Purge(x),
+                        '!' => Op::RejectContains(x),
+                        '/' => Op::RejectNotContains(x),
+                        '(' => Op::RejectNotStartsWith(x),
+                        _ => Op::RejectNotEndsWith(x),
+                    }
+                }
+                _ => return Err(err()),
+            };
+            ops.push(op);
+        }
+
+        Ok(Self { ops })
+    }
+
+    // apply the rule to the word, returns None if the word has been rejected
+    pub fn apply(&self, word: &str) -> Option<String> {
+        let mut w: Vec<char> = word.chars().collect();
+
+        for op in &self.ops {
+            match *op {
+                Op::Noop => {}
+                Op::Lower => w = w.iter().flat_map(|c| c.to_lowercase()).collect(),
+                Op::Upper => w = w.iter().flat_map(|c| c.to_uppercase()).collect(),
+                Op::Capitalize => {
+                    w = w.iter().flat_map(|c| c.to_lowercase()).collect();
+                    if let Some(first) = w.first_mut() {
+                        *first = first.to_ascii_uppercase();
+                    }
+                }
+                Op::InvertCapitalize => {
+                    w = w.iter().flat_map(|c| c.to_uppercase()).collect();
+                    if let Some(first) = w.first_mut() {
+                        *first = first.to_ascii_lowercase();
+                    }
+                }
+                Op::ToggleAll => w.iter_mut().for_each(|c| *c = toggle(*c)),
+                Op::ToggleAt(n) => {
+                    if let Some(c) = w.get_mut(n) {
+                        *c = toggle(*c);
+                    }
+                }
+                Op::Reverse => w.reverse(),
+                Op::Duplicate => w.extend(w.clone()),
+                Op::DuplicateN(n) => {
+                    let orig = w.clone();
+                    for _ in 0..n {
+                        w.extend(orig.iter());
+                    }
+                }
+                Op::Reflect => {
+                    let rev: Vec<char> = w.iter().rev().cloned().collect();
+                    w.extend(rev);
+                }
+                Op::RotateLeft if !w.is_empty() => {
+                    w.rotate_left(1);
+                }
+                Op::RotateRight if !w.is_empty() => {
+                    w.rotate_right(1);
+                }
+                Op::Append(x) => w.push(x),
+                Op::Prepend(x) => w.insert(0, x),
+                Op::DeleteFirst if !w.is_empty() => {
+                    w.remove(0);
+                }
+                Op::DeleteLast => {
+                    w.pop();
+                }
+                Op::DeleteAt(n) if n < w.len() => {
+                    w.remove(n);
+                }
+                Op::Extract(n, m) if n < w.len() => {
+                    w = w[n..(n + m).min(w.len())].to_vec();
+                }
+                Op::Omit(n, m) if n < w.len() => {
+                    w.drain(n..(n + m).min(w.len()));
+                }
+                Op::Insert(n, x) if n <= w.len() => {
+                    w.insert(n, x);
+                }
+                Op::Overwrite(n, x) => {
+                    if let Some(c) = w.get_mut(n) {
+                        *c = x;
+                    }
+                }
+                Op::Truncate(n) => w.truncate(n),
+                Op::Replace(x, y) => w.iter_mut().filter(|c| **c == x).for_each(|c| *c = y),
+                Op::Purge(x) => w.retain(|c| *c != x),
+                Op::DuplicateFirst(n) => {
+                    if let Some(first) = w.first().cloned() {
+                        for _ in 0..n {
+                            w.insert(0, first);
+                        }
+                    }
+                }
+                Op::DuplicateLast(n) => {
+                    if let Some(last) = w.last().cloned() {
+                        for _ in 0..n {
+                            w.push(last);
+                        }
+                    }
+                }
+                Op::DuplicateAll => w = w.iter().flat_map(|c| [*c, *c]).collect(),
+                Op::SwapFront if w.len() >= 2 => {
+                    w.swap(0, 1);
+                }
+                Op::SwapBack => {
+                    let len = w.len();
+                    if len >= 2 {
+                        w.swap(len - 1, len - 2);
+                    }
+                }
+                Op::Swap(n, m) if n < w.len() && m < w.len() => {
+                    w.swap(n, m);
+                }
+                Op::Title => {
+                    let mut upper = true;
+                    for c in w.iter_mut() {
+                        *c = if upper {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c.to_ascii_lowercase()
+                        };
+                        upper = *c == ' ';
+                    }
+                }
+                Op::DuplicateBlockFront(n) if n <= w.len() => {
+                    let block: Vec<char> = w[..n].to_vec();
+                    w.splice(0..0, block);
+                }
+                Op::DuplicateBlockBack(n) if n <= w.len() => {
+                    let block: Vec<char> = w[w.len() - n..].to_vec();
+                    w.extend(block);
+                }
+                Op::Increment(n) => {
+                    if let Some(c) = w.get_mut(n) {
+                        *c = char::from_u32(*c as u32 + 1).unwrap_or(*c);
+                    }
+                }
+                Op::Decrement(n) => {
+                    if let Some(c) = w.get_mut(n) {
+                        *c = char::from_u32((*c as u32).saturating_sub(1)).unwrap_or(*c);
+                    }
+                }
+                // unlike john, where <N and >N are strict
+                Op::RejectLessThan(n) if w.len() < n => return None,
+                Op::RejectGreaterThan(n) if w.len() > n => return None,
+                Op::RejectEqualLength(n) if w.len() != n => return None,
+                Op::RejectContains(x) if w.contains(&x) => return None,
+                Op::RejectNotContains(x) if !w.contains(&x) => return None,
+                Op::RejectNotStartsWith(x) if w.first() != Some(&x) => return None,
+                Op::RejectNotEndsWith(x) if w.last() != Some(&x) => return None,
+                Op::RejectNotAt(n, x) if w.get(n) != Some(&x) => return None,
+                Op::RejectNotCount(n, x) if w.iter().filter(|c| **c == x).count() < n => {
+                    return None
+                }
+                _ => {}
+            }
+        }
+
+        Some(w.into_iter().collect())
+    }
+}
+
+fn toggle(c: char) -> char {
+    if c.is_ascii_lowercase() {
+        c.to_ascii_uppercase()
+    } else {
+        c.to_ascii_lowercase()
+    }
+}
+
+// load the rules from a hashcat rules file, invalid rules are reported and skipped
+pub(crate) fn load_rules(path: &str) -> Result<Vec<Rule>, Error> {
+    let file = File::open(path).map_err(|e| format!("can't open {}: {}", path, e))?;
+    let mut rules = vec![];
+
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match Rule::parse(line) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => log::warn!("{}: {}", path, e),
+        }
+    }
+
+    if rules.is_empty() {
+        Err(format!("no valid rules found in {}", path))
+    } else {
+        log::info!("loaded {} rules from {}", rules.len(), path);
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rule;
+
+    fn apply(rule: &str, word: &str) -> Option<String> {
+        Rule::parse(rule).unwrap().apply(word)
+    }
+
+    #[test]
+    fn can_apply_case_rules() {
+        assert_eq!(apply(":", "pAssword"), Some("pAssword".to_owned()));
+        assert_eq!(apply("l", "PassWord"), Some("password".to_owned()));
+        assert_eq!(apply("u", "password"), Some("PASSWORD".to_owned()));
+        assert_eq!(apply("c", "pASSWORD"), Some("Password".to_owned()));
+        assert_eq!(apply("C", "password"), Some("pASSWORD".to_owned()));
+        assert_eq!(apply("t", "PassWord"), Some("pASSwORD".to_owned()));
+        assert_eq!(apply("T0", "password"), Some("Password".to_owned()));
+        assert_eq!(apply("E", "hello world"), Some("Hello World".to_owned()));
+    }
+
+    #[test]
+    fn can_apply_editing_rules() {
+        assert_eq!(apply("$1 $2 $3", "pass"), Some("pass123".to_owned()));
+        assert_eq!(apply("^!", "pass"), Some("!pass".to_owned()));
+        assert_eq!(apply("r", "pass"), Some("ssap".to_owned()));
+        assert_eq!(apply("d", "pass"), Some("passpass".to_owned()));
+        assert_eq!(apply("p2", "ab"), Some("ababab".to_owned()));
+        assert_eq!(apply("f", "abc"), Some("abccba".to_owned()));
+        assert_eq!(apply("{", "abc"), Some("bca".to_owned()));
+        assert_eq!(apply("}", "abc"), Some("cab".to_owned()));
+        assert_eq!(apply("[ ]", "abcd"), Some("bc".to_owned()));
+        assert_eq!(apply("D1", "abcd"), Some("acd".to_owned()));
+        assert_eq!(apply("x12", "abcd"), Some("bc".to_owned()));
+        assert_eq!(apply("O12", "abcd"), Some("ad".to_owned()));
+        assert_eq!(apply("i1!", "abcd"), Some("a!bcd".to_owned()));
+        assert_eq!(apply("o1!", "abcd"), Some("a!cd".to_owned()));
+        assert_eq!(apply("'2", "abcd"), Some("ab".to_owned()));
+        assert_eq!(apply("sa@ so0", "password"), Some("p@ssw0rd".to_owned()));
+        assert_eq!(apply("@s", "password"), Some("paword".to_owned()));
+        assert_eq!(apply("z2", "ab"), Some("aaab".to_owned()));
+        assert_eq!(apply("Z2", "ab"), Some("abbb".to_owned()));
+        assert_eq!(apply("q", "ab"), Some("aabb".to_owned()));
+        assert_eq!(apply("k", "abc"), Some("bac".to_owned()));
+        assert_eq!(apply("K", "abc"), Some("acb".to_owned()));
+        assert_eq!(apply("*02", "abc"), Some("cba".to_owned()));
+        assert_eq!(apply("y2", "abc"), Some("ababc".to_owned()));
+        assert_eq!(apply("Y2", "abc"), Some("abcbc".to_owned()));
+        assert_eq!(apply("+0", "abc"), Some("bbc".to_owned()));
+    }
+
+    #[test]
+    fn can_reject_words() {
+        // hashcat semantics, the length limits are inclusive
+        assert_eq!(apply("<4", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply("<3", "pass"), None);
+        assert_eq!(apply(">4", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply(">5", "pass"), None);
+        assert_eq!(apply("_4", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply("!a", "pass"), None);
+        assert_eq!(apply("/x", "pass"), None);
+        assert_eq!(apply("(p", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply(")x", "pass"), None);
+        assert_eq!(apply("=1a", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply("%2s", "pass"), Some("pass".to_owned()));
+        assert_eq!(apply("%3s", "pass"), None);
+    }
+
+    #[test]
+    fn returns_error_for_invalid_rules() {
+        assert!(Rule::parse("$").is_err());
+        assert!(Rule::parse("T!").is_err());
+        assert!(Rule::parse("M").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::net::IpAddr;
+
+use crate::utils;
+
+// same placeholders used by the plugins in their requests and commands
+pub(crate) const USERNAME: &str = "{USERNAME}";
+pub(crate) const PASSWORD: &str = "{PASSWORD}";
+pub(crate) const TARGET: &str = "{TARGET}";
+pub(crate) const DOMAIN: &str = "{DOMAIN}";
+pub(crate) const PORT: &str = "{PORT}";
+
+// the ones interpolated in the payloads, the password is never known in advance
+pub(crate) const TEMPLATES: [&str; 4] = [USERNAME, TARGET, DOMAIN, PORT];
+
+// the host without its first label for names like www.example.com, the host itself otherwise
+fn domain_of(host: &str) -> &str {
+    if host.parse::<IpAddr>().is_ok() || host.matches('.').count() < 2 {
+        host
+    } else {
+        host.split_once('.').unwrap().1
+    }
+}
+
+// replace the {USERNAME}, {TARGET}, {DOMAIN} and {PORT} placeholders of a payload template
+pub(crate) fn interpolate(value: &str, username: &str, target: &str) -> String {
+    if !TEMPLATES.iter().any(|t| value.contains(t)) {
+        return value.to_owned();
+    }
+
+    let (host, port) = match utils::parse_target(target, 0) {
+        Ok((host, 0)) => (host, String::new()),
+        Ok((host, port)) => (host, port.to_string()),
+        Err(_) => (target.to_owned(), String::new()),
+    };
+
+    value
+        .replace(USERNAME, username)
+        .replace(TARGET, &host)
+        .replace(DOMAIN, domain_of(&host))
+        .replace(PORT, &port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpolate;
+
+    #[test]
+    fn can_interpolate_templates() {
+        assert_eq!(interpolate("static", "admin", "foo"), "static");
+        assert_eq!(interpolate("{USERNAME}2024!", "admin", "foo"), "admin2024!");
+        assert_eq!(
+            interpolate(
+                "login={USERNAME}&host={TARGET}",
+                "admin",
+                "https://www.example.com:8443/login"
+            ),
+            "login=admin&host=www.example.com"
+        );
+        assert_eq!(
+            interpolate("{DOMAIN}:{PORT}", "admin", "mail.corp.local:25"),
+            "corp.local:25"
+        );
+        assert_eq!(interpolate("{DOMAIN}{PORT}", "", "10.0.0.1"), "10.0.0.1");
+        // only the known placeholders are replaced
+        assert_eq!(
+            interpolate("{username}{x}{PASSWORD}", "admin", "foo"),
+            "{username}{x}{PASSWORD}"
+        );
+    }
+}
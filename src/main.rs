@@ -1,5 +1,6 @@
 use std::env;
 use std::io;
+use std::sync::Arc;
 use std::time;
 
 use clap::{CommandFactory, Parser};
@@ -22,14 +23,12 @@ pub(crate) use crate::options::Options;
 pub(crate) use crate::plugins::Plugin;
 use crate::recipe::Recipe;
 pub(crate) use crate::session::Session;
+use crate::utils::Targets;
 
 fn setup() -> Result<Options, session::Error> {
     if env::var_os("RUST_LOG").is_none() {
         // set `RUST_LOG=debug` to see debug logs
-        env::set_var(
-            "RUST_LOG",
-            "info,blocking=off,pavao=off,fast_socks5=off,actix_server=warn",
-        );
+        env::set_var("RUST_LOG", "info,blocking=off,pavao=off,actix_server=warn");
     }
 
     env_logger::builder()
@@ -47,6 +46,10 @@ fn setup() -> Result<Options, session::Error> {
         std::process::exit(0);
     }
 
+    // load the external plugins before they can be listed or selected
+    #[cfg(feature = "wasm")]
+    plugins::wasm::load(&options)?;
+
     // list plugins and exit
     if options.list_plugins {
         plugins::manager::list();
@@ -86,13 +89,14 @@ fn setup() -> Result<Options, session::Error> {
     Ok(options)
 }
 
-async fn start_session(opts: Options) -> Result<(), session::Error> {
+// run a session until all the credentials have been tried or it's stopped
+async fn run_session(opts: Options) -> Result<Arc<Session>, session::Error> {
     // create the session object with runtime information
     // NOTE: from this moment on we use session.options
-    let session = Session::new(opts.clone())?;
+    let session = Session::new(opts.clone()).await?;
 
     // get selected plugin and configure it
-    let plugin = plugins::manager::setup(&session.options).map_err(|e| {
+    let plugin = plugins::manager::setup(&session.options, &session.targets).map_err(|e| {
         // set stop signal if the plugin failed to load
         session.set_stop();
         e
@@ -110,6 +114,37 @@ async fn start_session(opts: Options) -> Result<(), session::Error> {
 
     log::info!("runtime {:?}", start.elapsed());
 
+    // a payloads command died before completing its output
+    if let Some(e) = creds::command_failure() {
+        return Err(e);
+    }
+
+    Ok(session)
+}
+
+async fn start_session(opts: Options) -> Result<(), session::Error> {
+    if opts.dry_run {
+        let targets = Targets::from_options(&opts)?;
+        let plugin = plugins::manager::setup(&opts, &targets)?;
+        return report::estimate(&opts, &targets, plugin);
+    }
+
+    let session = run_session(opts).await?;
+
+    // run the follow-up stages unless the session has been interrupted
+    if !session.is_stop() {
+        // stop the workers and the statistics of this session
+        session.set_stop();
+
+        for stage in session::pipeline::follow_up_stages(&session)? {
+            let stage_session = run_session(stage.options.clone()).await?;
+            if stage_session.is_stop() {
+                break;
+            }
+            stage_session.set_stop();
+        }
+    }
+
     // sometimes the program hangs waiting for some remaining tokio tasks
     // to complete - we just exit(0) to avoid this.
     std::process::exit(0);
@@ -14,27 +14,79 @@ pub(crate) struct Options {
     #[clap(short = 'L', long, default_value_t = false)]
     /// List all available protocol plugins.
     pub list_plugins: bool,
-    /// Protocol plugin to use, run with --list-plugins for a list of all available plugins.
+    /// Protocol plugin to use, run with --list-plugins for a list of all available plugins. If not specified it is selected from each target URI scheme (ssh://host:2222) or port.
     pub plugin: Option<String>,
     #[clap(short = 'R', long)]
     /// Load a recipe from this YAML file.
     pub recipe: Option<String>,
 
-    /// Single target host, url or IP address, IP range, CIDR, @filename or comma separated combination of them.
+    /// Single target host, url or IP address, IP range, CIDR, @filename (.yml and .csv files can specify per-target overrides), nmap:scan.xml, masscan:out.json, asn:AS64500 or comma separated combination of them (hostnames support patterns like srv{01-20}.local), with an optional :port, :[port, list] or :port-range suffix.
     #[clap(short = 'T', long)]
     pub target: Option<String>,
 
+    /// Exclude these hosts from the targets, comma separated list of IP addresses, hostnames, IP ranges, CIDRs or @filename.
+    #[clap(long)]
+    pub exclude_targets: Option<String>,
+
+    /// ASN to prefix database used to expand asn:AS64500 targets (pyasn, iptoasn TSV or GeoLite2 ASN CSV).
+    #[clap(long)]
+    pub asn_database: Option<String>,
+
+    /// Do not remove duplicated targets after normalization. Deduplication keeps every target in memory when the expression combines several parts, files or imports, use this to save memory with very large lists of targets.
+    #[clap(long, default_value_t = false)]
+    pub no_dedup: bool,
+    /// Try each credential against all the targets before moving to the next one (the targets will be loaded in memory), use with --iterate-by password for password spraying.
+    #[clap(long, default_value_t = false)]
+    pub spray: bool,
+    /// Randomize the order of the targets for each credential (the targets will be loaded in memory).
+    #[clap(long, default_value_t = false)]
+    pub shuffle_targets: bool,
+    /// Uniformly sample this fraction of the password (or payload) keyspace instead of iterating it exhaustively, e.g. 0.01.
+    #[clap(long)]
+    pub sample: Option<f64>,
+    /// Stop after this number of attempts, sampling the password (or payload) keyspace uniformly if it's bigger.
+    #[clap(long)]
+    pub max_attempts: Option<usize>,
+    /// Seed for the random number generator, a random one will be used if not specified.
+    #[clap(long)]
+    pub seed: Option<u64>,
+
+    /// Comma separated list of DNS servers (ip or ip:port) used to pre-resolve the targets instead of the system resolver.
+    #[clap(long)]
+    pub dns_server: Option<String>,
+    /// Timeout in milliseconds for the pre-resolution of each target hostname.
+    #[clap(long, default_value_t = 5000)]
+    pub dns_timeout: u64,
+    /// Expand each target hostname into all of its resolved addresses as separate targets.
+    #[clap(long, default_value_t = false)]
+    pub resolve_all: bool,
+    /// Add the hostname from the PTR record of IP targets to the results.
+    #[clap(long, default_value_t = false)]
+    pub ptr_lookup: bool,
+
     /// Enable the REST API and bind it to the specified address:port.
     #[clap(long)]
     pub api: Option<String>,
 
-    /// Constant, filename, glob expression as @/some/path/*.txt, permutations as #min-max:charset / #min-max or range as [min-max] / [n, n, n]
+    /// Constant, filename, glob expression as @/some/path/*.txt, permutations as #min-max:charset / #min-max or range as [min-max] / [n, n, n], names:first_last_list.txt[:formats=f.last,flast], freq:list.txt[,list.txt] for password<TAB>count lists by descending frequency, cmd:command to read the output of a command or - to read from stdin
     #[clap(short = 'U', long, visible_alias = "payloads")]
     pub username: Option<String>,
-    /// Constant, filename, glob expression as @/some/path/*.txt or permutations as #min-max:charset / #min-max or range as [min-max] / [n, n, n]
+    /// Template such as {USERNAME}2024! (with {USERNAME}, {TARGET}, {DOMAIN} and {PORT} placeholders), constant, filename, glob expression as @/some/path/*.txt or permutations as #min-max:charset (with ?l?u?d?h?H?s?a presets) / #min-max or range as [min-max] / [n, n, n], markov:wordlist_or_model[:order=N][:min=N][:max=N][:limit=N][:save=model.json], prince:wordlist[:min=N][:max=N][:elems=N-M], dates:2015-2025[:formats=DDMMYYYY,SEASONYYYY!], walks:4-8[:layout=qwerty][:turns=N], freq:list.txt[,list.txt] for password<TAB>count lists by descending frequency, mask:?u?l?l?l?d?d[:1=charset]..[:4=charset] for hashcat style masks, keys:@/path/to/keys/ for the ssh private keys in a folder, cmd:command to read the output of a command or - to read from stdin. Payloads as ntlm:LM:NT or ntlm:NT are used as hashes by the smb and rdp plugins
     #[clap(short = 'P', long, visible_alias = "key")]
     pub password: Option<String>,
-    /// Load username:password combinations from this file.
+    /// Apply the hashcat rules from this file to every password (the part after the separator for --combinations, or payload for single payload plugins). The <N and >N rejections keep words up to and from N characters as in hashcat, unlike john where they are strict.
+    #[clap(long)]
+    pub rules: Option<String>,
+    /// Strip BOMs and carriage returns from the payloads and skip the duplicated ones (bloom filter based, a few unique payloads might be skipped).
+    #[clap(long, default_value_t = false)]
+    pub dedup_payloads: bool,
+    /// Expand every password (or payload for single payload plugins) with a chain of mutations: leet, capitalize, upper, reverse, append-digits:N, prepend-digits:N, append-special, append-years:2015-2025.
+    #[clap(long)]
+    pub mutate: Option<String>,
+    /// Skip passwords that don't satisfy the target password policy, e.g. 'min=8,max=16,upper,lower,digit,special,classes=3'.
+    #[clap(long)]
+    pub password_policy: Option<String>,
+    /// Load username:password combinations from this file (or - for stdin), each line is split on the first separator and malformed lines are skipped.
     #[clap(short = 'C', long)]
     pub combinations: Option<String>,
     /// Separator if using the --combinations/-C argument.
@@ -45,6 +97,9 @@ pub(crate) struct Options {
     #[clap(short = 'I', long, value_enum, default_value_t = creds::IterationStrategy::User)]
     pub iterate_by: creds::IterationStrategy,
 
+    /// Once done, run a follow-up stage against each target with the usernames found on it (or against the hosts found by the discovery plugin and the relevant open ports found by the port scanner, or the certificate names found by the tls plugin), e.g. --then 'ssh --password passwords.txt'.
+    #[clap(long)]
+    pub then: Option<String>,
     /// Save and restore session information to this file.
     #[clap(short = 'S', long)]
     pub session: Option<String>,
@@ -87,6 +142,12 @@ pub(crate) struct Options {
     /// Maximum number of milliseconds for random request jittering.
     #[clap(long, default_value_t = 0)]
     pub jitter_max: u64,
+    /// Print the number of attempts, the first credentials and an estimated time without connecting to the targets.
+    #[clap(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// Number of credentials to print with --dry-run.
+    #[clap(long, default_value_t = 10)]
+    pub dry_run_samples: usize,
     /// Do not report statistics.
     #[clap(short = 'Q', long, default_value_t = false)]
     pub quiet: bool,
@@ -98,52 +159,211 @@ pub(crate) struct Options {
 
     #[clap(flatten, next_help_heading = "COMMAND (CMD)")]
     pub cmd: crate::plugins::cmd::options::Options,
+    #[cfg(feature = "adfs")]
+    #[clap(flatten, next_help_heading = "ADFS")]
+    pub adfs: crate::plugins::adfs::options::Options,
     #[cfg(feature = "amqp")]
     #[clap(flatten, next_help_heading = "AMQP")]
     pub amqp: crate::plugins::amqp::options::Options,
+    #[cfg(feature = "archive")]
+    #[clap(flatten, next_help_heading = "ARCHIVE")]
+    pub archive: crate::plugins::archive::options::Options,
     #[cfg(feature = "http")]
     #[clap(flatten, next_help_heading = "HTTP")]
     pub http: crate::plugins::http::options::Options,
+    #[cfg(feature = "http_proxy")]
+    #[clap(flatten, next_help_heading = "HTTP PROXY")]
+    pub http_proxy: crate::plugins::http_proxy::options::Options,
     #[cfg(feature = "dns")]
     #[clap(flatten, next_help_heading = "DNS")]
     pub dns: crate::plugins::dns::options::Options,
+    #[cfg(feature = "cisco")]
+    #[clap(flatten, next_help_heading = "CISCO")]
+    pub cisco: crate::plugins::cisco::options::Options,
+    #[cfg(feature = "consul")]
+    #[clap(flatten, next_help_heading = "CONSUL")]
+    pub consul: crate::plugins::consul::options::Options,
+    #[cfg(feature = "couchdb")]
+    #[clap(flatten, next_help_heading = "COUCHDB")]
+    pub couchdb: crate::plugins::couchdb::options::Options,
+    #[cfg(feature = "cql")]
+    #[clap(flatten, next_help_heading = "CQL")]
+    pub cql: crate::plugins::cql::options::Options,
+    #[cfg(feature = "elastic")]
+    #[clap(flatten, next_help_heading = "ELASTICSEARCH")]
+    pub elastic: crate::plugins::elastic::options::Options,
+    #[cfg(feature = "etcd")]
+    #[clap(flatten, next_help_heading = "ETCD")]
+    pub etcd: crate::plugins::etcd::options::Options,
+    #[cfg(feature = "exchange")]
+    #[clap(flatten, next_help_heading = "EXCHANGE")]
+    pub exchange: crate::plugins::exchange::options::Options,
+    #[cfg(feature = "ftp")]
+    #[clap(flatten, next_help_heading = "FTP")]
+    pub ftp: crate::plugins::ftp::options::Options,
+    #[cfg(feature = "git")]
+    #[clap(flatten, next_help_heading = "GIT")]
+    pub git: crate::plugins::git::options::Options,
+    #[cfg(feature = "gitea")]
+    #[clap(flatten, next_help_heading = "GITEA")]
+    pub gitea: crate::plugins::gitea::options::Options,
+    #[cfg(feature = "gitlab")]
+    #[clap(flatten, next_help_heading = "GITLAB")]
+    pub gitlab: crate::plugins::gitlab::options::Options,
+    #[cfg(feature = "grafana")]
+    #[clap(flatten, next_help_heading = "GRAFANA")]
+    pub grafana: crate::plugins::grafana::options::Options,
+    #[cfg(feature = "ics")]
+    #[clap(flatten, next_help_heading = "ICS")]
+    pub ics: crate::plugins::ics::options::Options,
+    #[cfg(feature = "imap")]
+    #[clap(flatten, next_help_heading = "IMAP")]
+    pub imap: crate::plugins::imap::options::Options,
+    #[cfg(feature = "influxdb")]
+    #[clap(flatten, next_help_heading = "INFLUXDB")]
+    pub influxdb: crate::plugins::influxdb::options::Options,
+    #[cfg(feature = "ipmi")]
+    #[clap(flatten, next_help_heading = "IPMI")]
+    pub ipmi: crate::plugins::ipmi::options::Options,
+    #[cfg(feature = "kafka")]
+    #[clap(flatten, next_help_heading = "KAFKA")]
+    pub kafka: crate::plugins::kafka::options::Options,
+    #[cfg(feature = "mikrotik")]
+    #[clap(flatten, next_help_heading = "MIKROTIK")]
+    pub mikrotik: crate::plugins::mikrotik::options::Options,
+    #[cfg(feature = "mongodb")]
+    #[clap(flatten, next_help_heading = "MONGODB")]
+    pub mongodb: crate::plugins::mongodb::options::Options,
+    #[cfg(feature = "nats")]
+    #[clap(flatten, next_help_heading = "NATS")]
+    pub nats: crate::plugins::nats::options::Options,
+    #[cfg(feature = "nntp")]
+    #[clap(flatten, next_help_heading = "NNTP")]
+    pub nntp: crate::plugins::nntp::options::Options,
+    #[cfg(feature = "tacacs")]
+    #[clap(flatten, next_help_heading = "TACACS+")]
+    pub tacacs: crate::plugins::tacacs::options::Options,
     #[cfg(feature = "telnet")]
     #[clap(flatten, next_help_heading = "TELNET")]
     pub telnet: crate::plugins::telnet::options::Options,
     #[cfg(feature = "samba")]
     #[clap(flatten, next_help_heading = "SAMBA (SMB)")]
     pub smb: crate::plugins::samba::options::Options,
+    #[cfg(feature = "script")]
+    #[clap(flatten, next_help_heading = "SCRIPT")]
+    pub script: crate::plugins::script::options::Options,
     #[cfg(feature = "ssh")]
     #[clap(flatten, next_help_heading = "SSH")]
     pub ssh: crate::plugins::ssh::options::Options,
+    #[cfg(feature = "sslvpn")]
+    #[clap(flatten, next_help_heading = "SSL-VPN")]
+    pub sslvpn: crate::plugins::sslvpn::options::Options,
+    #[cfg(feature = "svn")]
+    #[clap(flatten, next_help_heading = "SVN")]
+    pub svn: crate::plugins::svn::options::Options,
     #[cfg(feature = "smtp")]
     #[clap(flatten, next_help_heading = "SMTP")]
     pub smtp: crate::plugins::smtp::options::Options,
+    #[cfg(feature = "snmp")]
+    #[clap(flatten, next_help_heading = "SNMP")]
+    pub snmp: crate::plugins::snmp::options::Options,
     #[cfg(feature = "socks5")]
     #[clap(flatten, next_help_heading = "SOCKS5")]
     pub socks5: crate::plugins::socks5::options::Options,
     #[cfg(feature = "pop3")]
     #[clap(flatten, next_help_heading = "POP3")]
     pub pop3: crate::plugins::pop3::options::Options,
+    #[cfg(feature = "msol")]
+    #[clap(flatten, next_help_heading = "MICROSOFT 365")]
+    pub msol: crate::plugins::msol::options::Options,
+    #[cfg(feature = "neo4j")]
+    #[clap(flatten, next_help_heading = "NEO4J")]
+    pub neo4j: crate::plugins::neo4j::options::Options,
+    #[cfg(feature = "onvif")]
+    #[clap(flatten, next_help_heading = "ONVIF")]
+    pub onvif: crate::plugins::onvif::options::Options,
+    #[cfg(feature = "db2")]
+    #[clap(flatten, next_help_heading = "DB2")]
+    pub db2: crate::plugins::db2::options::Options,
+    #[cfg(feature = "discovery")]
+    #[clap(flatten, next_help_heading = "DISCOVERY")]
+    pub discovery: crate::plugins::discovery::options::Options,
+    #[cfg(feature = "docker")]
+    #[clap(flatten, next_help_heading = "DOCKER")]
+    pub docker: crate::plugins::docker::options::Options,
+    #[cfg(feature = "firebird")]
+    #[clap(flatten, next_help_heading = "FIREBIRD")]
+    pub firebird: crate::plugins::firebird::options::Options,
+    #[cfg(feature = "oracle_sid")]
+    #[clap(flatten, next_help_heading = "ORACLE SID")]
+    pub oracle_sid: crate::plugins::oracle_sid::options::Options,
     #[cfg(feature = "oracle")]
     #[clap(flatten, next_help_heading = "ORACLE")]
     pub oracle: crate::plugins::oracle::options::Options,
+    #[cfg(feature = "jenkins")]
+    #[clap(flatten, next_help_heading = "JENKINS")]
+    pub jenkins: crate::plugins::jenkins::options::Options,
+    #[cfg(feature = "jwt")]
+    #[clap(flatten, next_help_heading = "JWT")]
+    pub jwt: crate::plugins::jwt::options::Options,
+    #[cfg(feature = "k8s")]
+    #[clap(flatten, next_help_heading = "KUBERNETES")]
+    pub k8s: crate::plugins::k8s::options::Options,
     #[cfg(feature = "ldap")]
     #[clap(flatten, next_help_heading = "LDAP")]
     pub ldap: crate::plugins::ldap::options::Options,
     #[cfg(feature = "kerberos")]
     #[clap(flatten, next_help_heading = "KERBEROS")]
     pub kerberos: crate::plugins::kerberos::options::Options,
+    #[cfg(feature = "radius")]
+    #[clap(flatten, next_help_heading = "RADIUS")]
+    pub radius: crate::plugins::radius::options::Options,
+    #[cfg(feature = "rsync")]
+    #[clap(flatten, next_help_heading = "RSYNC")]
+    pub rsync: crate::plugins::rsync::options::Options,
+    #[cfg(feature = "rtsp")]
+    #[clap(flatten, next_help_heading = "RTSP")]
+    pub rtsp: crate::plugins::rtsp::options::Options,
     #[cfg(feature = "rdp")]
     #[clap(flatten, next_help_heading = "RDP")]
     pub rdp: crate::plugins::rdp::options::Options,
     #[cfg(feature = "mqtt")]
     #[clap(flatten, next_help_heading = "MQTT")]
     pub mqtt: crate::plugins::mqtt::options::Options,
+    #[cfg(feature = "raw")]
+    #[clap(flatten, next_help_heading = "RAW")]
+    pub raw: crate::plugins::raw::options::Options,
     #[cfg(feature = "redis")]
     #[clap(flatten, next_help_heading = "REDIS")]
     pub redis: crate::plugins::redis::options::Options,
+    #[cfg(feature = "rexec")]
+    #[clap(flatten, next_help_heading = "REXEC")]
+    pub rexec: crate::plugins::rexec::options::Options,
+    #[cfg(feature = "rlogin")]
+    #[clap(flatten, next_help_heading = "RLOGIN")]
+    pub rlogin: crate::plugins::rlogin::options::Options,
     #[cfg(feature = "port_scanner")]
     #[clap(flatten, next_help_heading = "PORT SCANNER")]
     pub port_scanner: crate::plugins::port_scanner::options::Options,
+    #[cfg(feature = "tls")]
+    #[clap(flatten, next_help_heading = "TLS")]
+    pub tls: crate::plugins::tls::options::Options,
+    #[cfg(feature = "tomcat")]
+    #[clap(flatten, next_help_heading = "TOMCAT")]
+    pub tomcat: crate::plugins::tomcat::options::Options,
+    #[cfg(feature = "vault")]
+    #[clap(flatten, next_help_heading = "VAULT")]
+    pub vault: crate::plugins::vault::options::Options,
+    #[cfg(feature = "wasm")]
+    #[clap(flatten, next_help_heading = "WASM")]
+    pub wasm: crate::plugins::wasm::options::Options,
+    #[cfg(feature = "wildfly")]
+    #[clap(flatten, next_help_heading = "WILDFLY")]
+    pub wildfly: crate::plugins::wildfly::options::Options,
+    #[cfg(feature = "winrm")]
+    #[clap(flatten, next_help_heading = "WINRM")]
+    pub winrm: crate::plugins::winrm::options::Options,
+    #[cfg(feature = "wordpress")]
+    #[clap(flatten, next_help_heading = "WORDPRESS")]
+    pub wordpress: crate::plugins::wordpress::options::Options,
 }
@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{redirect, Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "adfs" => Adfs::new()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Valid,
+    // valid password, a second authentication method is required
+    MfaRequired,
+    PasswordExpired,
+    Invalid,
+    // MSIS error page, usually an unknown relying party
+    Error(String),
+}
+
+impl Outcome {
+    fn status(&self) -> &'static str {
+        match self {
+            Outcome::Valid => "valid",
+            Outcome::MfaRequired => "mfa required",
+            Outcome::PasswordExpired => "password expired",
+            Outcome::Invalid => "invalid",
+            Outcome::Error(_) => "error",
+        }
+    }
+}
+
+// localized text of the error label of the sign in form, empty when the form is first rendered
+fn error_text(body: &str) -> Option<String> {
+    let re = Regex::new(r#"(?is)<(?:span|label)\b[^>]*\bid\s*=\s*["']errorText["'][^>]*>(.*?)</"#)
+        .unwrap();
+    let found = re
+        .captures(body)
+        .map(|caps| caps[1].trim().to_owned())
+        .filter(|text| !text.is_empty());
+    found
+}
+
+// first MSIS error code of the page
+fn error_code(body: &str) -> Option<String> {
+    let re = Regex::new(r"\bMSIS\d{4}\b").unwrap();
+    let found = re.find(body).map(|m| m.as_str().to_owned());
+    found
+}
+
+fn classify(status: StatusCode, location: Option<&str>, auth_cookie: bool, body: &str) -> Outcome {
+    if status.is_redirection() {
+        return match location {
+            Some(location) if location.to_lowercase().contains("/adfs/portal/updatepassword") => {
+                Outcome::PasswordExpired
+            }
+            _ if auth_cookie => Outcome::Valid,
+            _ => Outcome::Invalid,
+        };
+    }
+
+    if let Some(code) = error_code(body) {
+        return Outcome::Error(code);
+    }
+    if error_text(body).is_some() {
+        return Outcome::Invalid;
+    }
+    if status == StatusCode::OK && !body.contains("name=\"Password\"") && body.contains("AuthMethod")
+    {
+        // the primary authentication succeeded and the additional authentication form is rendered
+        return Outcome::MfaRequired;
+    }
+    if auth_cookie {
+        return Outcome::Valid;
+    }
+    if status.is_success() {
+        Outcome::Invalid
+    } else {
+        Outcome::Error(status.to_string())
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Adfs {
+    opts: options::Options,
+}
+
+impl Adfs {
+    pub fn new() -> Self {
+        Adfs {
+            opts: options::Options::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Adfs {
+    fn description(&self) -> &'static str {
+        "On-premises ADFS forms authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.adfs.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 443)?;
+        let url = format!("https://{}{}", &address, &self.opts.adfs_path);
+
+        // each attempt needs its own cookies
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .redirect(redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let response = client
+            .post(&url)
+            .query(&[
+                ("wa", "wsignin1.0"),
+                ("wtrealm", self.opts.adfs_realm.as_str()),
+                ("wctx", ""),
+            ])
+            .form(&[
+                ("UserName", creds.username.as_str()),
+                ("Password", creds.password.as_str()),
+                ("AuthMethod", "FormsAuthentication"),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|l| l.to_str().ok())
+            .map(|l| l.to_owned());
+        // MSISAuth, MSISAuth1, ... hold the authentication token
+        let auth_cookie = response.cookies().any(|c| c.name().starts_with("MSISAuth"));
+        let body = response.text().await.unwrap_or_default();
+
+        let outcome = classify(status, location.as_deref(), auth_cookie, &body);
+        match outcome {
+            Outcome::Invalid => Ok(None),
+            Outcome::Error(error) => Err(format!("unexpected response from {}: {}", &url, error)),
+            _ => Ok(Some(vec![Loot::new(
+                "adfs",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                    ("status".to_owned(), outcome.status().to_owned()),
+                ],
+            )])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{classify, error_text, Outcome};
+
+    #[test]
+    fn can_classify_sign_in_responses() {
+        let form = |error: &str| {
+            format!(
+                r#"<form method="post" id="loginForm" autocomplete="off">
+                <input id="userNameInput" name="UserName" type="email" />
+                <input id="passwordInput" name="Password" type="password" />
+                <div id="error" class="fieldMargin error smallText">
+                    <span id="errorText" for="" aria-live="assertive" role="alert">{}</span>
+                </div>
+                <input id="optionForms" type="hidden" name="AuthMethod" value="FormsAuthentication"/>
+                </form>"#,
+                error
+            )
+        };
+
+        assert_eq!(error_text(&form("")), None);
+        assert_eq!(
+            error_text(&form("Incorrect user ID or password.")),
+            Some("Incorrect user ID or password.".to_owned())
+        );
+
+        assert_eq!(
+            classify(StatusCode::FOUND, Some("https://sts.corp.local/adfs/ls/"), true, ""),
+            Outcome::Valid
+        );
+        assert_eq!(
+            classify(
+                StatusCode::FOUND,
+                Some("/adfs/portal/updatepassword/?username=user"),
+                false,
+                ""
+            ),
+            Outcome::PasswordExpired
+        );
+        assert_eq!(
+            classify(StatusCode::OK, None, false, &form("Identifiant incorrect.")),
+            Outcome::Invalid
+        );
+        assert_eq!(
+            classify(
+                StatusCode::OK,
+                None,
+                false,
+                r#"<form id="options"><input type="hidden" name="AuthMethod" value="AzureMfaAuthentication"/></form>"#
+            ),
+            Outcome::MfaRequired
+        );
+        assert_eq!(
+            classify(
+                StatusCode::OK,
+                None,
+                false,
+                "<div id=\"errorDetails\">MSIS7007: The requested relying party trust is unspecified or unsupported.</div>"
+            ),
+            Outcome::Error("MSIS7007".to_owned())
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "/adfs/ls/")]
+    /// Path of the ADFS sign in endpoint.
+    pub adfs_path: String,
+    #[clap(long, default_value = "urn:federation:MicrosoftOnline")]
+    /// Identifier of the relying party to sign in to.
+    pub adfs_realm: String,
+}
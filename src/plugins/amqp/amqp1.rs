@@ -0,0 +1,305 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+// SASL layer protocol header, protocol id 3
+const PROTOCOL_HEADER_SASL: &[u8] = &[b'A', b'M', b'Q', b'P', 3, 1, 0, 0];
+
+const FRAME_TYPE_SASL: u8 = 0x01;
+
+const SASL_MECHANISMS: u64 = 0x40;
+const SASL_INIT: u64 = 0x41;
+const SASL_OUTCOME: u64 = 0x44;
+
+const SASL_CODE_OK: u64 = 0;
+const SASL_CODE_AUTH: u64 = 1;
+
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // the server only offers the ANONYMOUS mechanism
+    NoAuth,
+    Valid,
+    Invalid,
+}
+
+// subset of the AMQP 1.0 type system used by the SASL frames
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Uint(u64),
+    // binary, string and symbol
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Described(u64, Box<Value>),
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < n {
+            return Err("truncated AMQP frame".to_owned());
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn size(&mut self, bytes: usize) -> Result<usize, Error> {
+        Ok(self
+            .take(bytes)?
+            .iter()
+            .fold(0usize, |acc, b| acc << 8 | *b as usize))
+    }
+
+    fn list(&mut self, width: usize) -> Result<Value, Error> {
+        let _size = self.size(width)?;
+        let count = self.size(width)?;
+        Ok(Value::List(
+            (0..count)
+                .map(|_| self.decode())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn array(&mut self, width: usize) -> Result<Value, Error> {
+        let _size = self.size(width)?;
+        let count = self.size(width)?;
+        let constructor = self.take(1)?[0];
+        Ok(Value::List(
+            (0..count)
+                .map(|_| self.decode_with(constructor))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    fn decode(&mut self) -> Result<Value, Error> {
+        let constructor = self.take(1)?[0];
+        self.decode_with(constructor)
+    }
+
+    fn decode_with(&mut self, constructor: u8) -> Result<Value, Error> {
+        match constructor {
+            0x00 => {
+                let Value::Uint(descriptor) = self.decode()? else {
+                    return Err("unsupported AMQP descriptor".to_owned());
+                };
+                Ok(Value::Described(descriptor, Box::new(self.decode()?)))
+            }
+            0x40 => Ok(Value::Null),
+            0x41 => Ok(Value::Bool(true)),
+            0x42 => Ok(Value::Bool(false)),
+            0x56 => Ok(Value::Bool(self.take(1)?[0] != 0)),
+            0x43 | 0x44 => Ok(Value::Uint(0)),
+            0x50 | 0x52 | 0x53 => Ok(Value::Uint(self.size(1)? as u64)),
+            0x60 => Ok(Value::Uint(self.size(2)? as u64)),
+            0x70 => Ok(Value::Uint(self.size(4)? as u64)),
+            0x80 => Ok(Value::Uint(self.size(8)? as u64)),
+            0xa0 | 0xa1 | 0xa3 => {
+                let len = self.size(1)?;
+                Ok(Value::Bytes(self.take(len)?.to_vec()))
+            }
+            0xb0 | 0xb1 | 0xb3 => {
+                let len = self.size(4)?;
+                Ok(Value::Bytes(self.take(len)?.to_vec()))
+            }
+            0x45 => Ok(Value::List(vec![])),
+            0xc0 => self.list(1),
+            0xd0 => self.list(4),
+            0xe0 => self.array(1),
+            0xf0 => self.array(4),
+            other => Err(format!("unsupported AMQP type 0x{:02x}", other)),
+        }
+    }
+}
+
+fn variable(out: &mut Vec<u8>, small: u8, large: u8, value: &[u8]) {
+    if value.len() <= 0xff {
+        out.extend_from_slice(&[small, value.len() as u8]);
+    } else {
+        out.push(large);
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    }
+    out.extend_from_slice(value);
+}
+
+fn frame(body: &[u8]) -> Vec<u8> {
+    let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(&[2, FRAME_TYPE_SASL, 0, 0]);
+    out.extend_from_slice(body);
+    out
+}
+
+// sasl-init with the PLAIN mechanism and the target hostname
+fn sasl_init(hostname: &str, username: &str, password: &str) -> Vec<u8> {
+    let mut fields = vec![];
+    variable(&mut fields, 0xa3, 0xb3, b"PLAIN");
+    variable(
+        &mut fields,
+        0xa0,
+        0xb0,
+        &[&[0], username.as_bytes(), &[0], password.as_bytes()].concat(),
+    );
+    variable(&mut fields, 0xa1, 0xb1, hostname.as_bytes());
+
+    let mut body = vec![0x00, 0x53, SASL_INIT as u8, 0xd0];
+    body.extend_from_slice(&((fields.len() + 4) as u32).to_be_bytes());
+    body.extend_from_slice(&3u32.to_be_bytes());
+    body.extend(fields);
+    frame(&body)
+}
+
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(u64, Vec<Value>), Error> {
+    let size = stream.read_u32().await.map_err(|e| e.to_string())? as usize;
+    if !(8..=MAX_FRAME_SIZE).contains(&size) {
+        return Err(format!("invalid AMQP frame size {}", size));
+    }
+    let mut data = vec![0u8; size - 4];
+    stream
+        .read_exact(&mut data)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let offset = (data[0] as usize * 4).saturating_sub(4).max(4);
+    if data[1] != FRAME_TYPE_SASL || data.len() < offset {
+        return Err("unexpected AMQP frame".to_owned());
+    }
+
+    match (Decoder {
+        data: &data[offset..],
+    })
+    .decode()?
+    {
+        Value::Described(descriptor, fields) => match *fields {
+            Value::List(fields) => Ok((descriptor, fields)),
+            _ => Ok((descriptor, vec![])),
+        },
+        _ => Err("unexpected AMQP frame body".to_owned()),
+    }
+}
+
+fn mechanisms(fields: &[Value]) -> Vec<String> {
+    let symbols = match fields.first() {
+        Some(Value::List(symbols)) => symbols.iter().collect(),
+        Some(symbol) => vec![symbol],
+        None => vec![],
+    };
+    symbols
+        .into_iter()
+        .filter_map(|s| match s {
+            Value::Bytes(name) => Some(String::from_utf8_lossy(name).to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+// perform the SASL layer negotiation without opening the AMQP connection
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    hostname: &str,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    stream
+        .write_all(PROTOCOL_HEADER_SASL)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut header = [0u8; 8];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    if header != PROTOCOL_HEADER_SASL {
+        return Err(format!("unsupported AMQP protocol header {:?}", header));
+    }
+
+    let (descriptor, fields) = read_frame(stream).await?;
+    if descriptor != SASL_MECHANISMS {
+        return Err(format!("unexpected SASL frame 0x{:02x}", descriptor));
+    }
+    let mechanisms = mechanisms(&fields);
+    if !mechanisms.iter().any(|m| m == "PLAIN") {
+        return if mechanisms.iter().any(|m| m == "ANONYMOUS") {
+            Ok(Outcome::NoAuth)
+        } else {
+            Err(format!("PLAIN not supported, mechanisms: {}", mechanisms.join(", ")))
+        };
+    }
+
+    stream
+        .write_all(&sasl_init(hostname, username, password))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (descriptor, fields) = read_frame(stream).await?;
+    if descriptor != SASL_OUTCOME {
+        return Err(format!("unexpected SASL frame 0x{:02x}", descriptor));
+    }
+    match fields.first() {
+        Some(Value::Uint(SASL_CODE_OK)) => Ok(Outcome::Valid),
+        Some(Value::Uint(SASL_CODE_AUTH)) => Ok(Outcome::Invalid),
+        // system errors, usually temporary
+        other => Err(format!("SASL outcome {:?}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, frame, sasl_init, Decoder, Outcome, Value, PROTOCOL_HEADER_SASL};
+
+    #[test]
+    fn can_encode_and_decode_sasl_init() {
+        let init = sasl_init("broker", "guest", "guest");
+        let Value::Described(0x41, fields) = (Decoder { data: &init[8..] }).decode().unwrap()
+        else {
+            panic!("not a sasl-init");
+        };
+        assert_eq!(
+            *fields,
+            Value::List(vec![
+                Value::Bytes(b"PLAIN".to_vec()),
+                Value::Bytes(b"\x00guest\x00guest".to_vec()),
+                Value::Bytes(b"broker".to_vec()),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        // sasl-mechanisms with an array of ANONYMOUS and PLAIN
+        let mechanisms = frame(&[
+            0x00, 0x53, 0x40, 0xc0, 0x15, 0x01, 0xe0, 0x12, 0x02, 0xa3, 0x09, b'A', b'N', b'O',
+            b'N', b'Y', b'M', b'O', b'U', b'S', 0x05, b'P', b'L', b'A', b'I', b'N',
+        ]);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(PROTOCOL_HEADER_SASL)
+            .read(PROTOCOL_HEADER_SASL)
+            .read(&mechanisms)
+            .write(&sasl_init("broker", "admin", "admin"))
+            .read(&frame(&[0x00, 0x53, 0x44, 0xc0, 0x02, 0x01, 0x50, 0x00]))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "broker", "admin", "admin").await,
+            Ok(Outcome::Valid)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(PROTOCOL_HEADER_SASL)
+            .read(PROTOCOL_HEADER_SASL)
+            .read(&mechanisms)
+            .write(&sasl_init("broker", "admin", "wrong"))
+            .read(&frame(&[0x00, 0x53, 0x44, 0xc0, 0x02, 0x01, 0x50, 0x01]))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "broker", "admin", "wrong").await,
+            Ok(Outcome::Invalid)
+        );
+    }
+}
@@ -5,11 +5,13 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::session::{Error, Loot};
 use crate::utils;
+use crate::utils::once::OncePerTarget;
 use crate::Options;
 use crate::Plugin;
 
 use crate::creds::Credentials;
 
+mod amqp1;
 pub(crate) mod options;
 
 const PROTOCOL_HEADER_091: &[u8] = &[b'A', b'M', b'Q', b'P', 0, 0, 9, 1];
@@ -21,22 +23,70 @@ super::manager::register_plugin! {
 #[derive(Clone)]
 pub(crate) struct AMQP {
     ssl: bool,
+    version: options::Version,
+    // AMQP 1.0 targets only offering anonymous authentication, reported only once
+    no_auth: OncePerTarget,
 }
 
 impl AMQP {
     pub fn new() -> Self {
-        AMQP { ssl: false }
+        AMQP {
+            ssl: false,
+            version: options::Version::default(),
+            no_auth: OncePerTarget::new(),
+        }
+    }
+
+    async fn attempt_1_0(
+        &self,
+        address: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        if self.no_auth.contains(address) {
+            return Ok(None);
+        }
+
+        let (host, _) = utils::parse_target(address, 5672)?;
+        let mut stream = crate::utils::net::async_tcp_stream(address, timeout, self.ssl).await?;
+        let outcome = tokio::time::timeout(
+            timeout,
+            amqp1::authenticate(&mut stream, &host, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        match outcome {
+            amqp1::Outcome::Valid => Ok(Some(vec![Loot::new(
+                "amqp",
+                address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ],
+            )])),
+            amqp1::Outcome::NoAuth => Ok(self.no_auth.report(
+                address,
+                Loot::new(
+                    "amqp",
+                    address,
+                    [("authentication".to_owned(), "anonymous".to_owned())],
+                ),
+            )),
+            amqp1::Outcome::Invalid => Ok(None),
+        }
     }
 }
 
 #[async_trait]
 impl Plugin for AMQP {
     fn description(&self) -> &'static str {
-        "AMQP password authentication (ActiveMQ, RabbitMQ, Qpid, JORAM and Solace)."
+        "AMQP 0-9-1 and 1.0 password authentication (ActiveMQ, Artemis, RabbitMQ, Qpid, JORAM, Solace and Azure Service Bus)."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.ssl = opts.amqp.amqp_ssl;
+        self.version = opts.amqp.amqp_version;
         Ok(())
     }
 
@@ -46,6 +96,10 @@ impl Plugin for AMQP {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 5672)?;
+        if self.version == options::Version::V1_0 {
+            return self.attempt_1_0(&address, creds, timeout).await;
+        }
+
         let mut stream = crate::utils::net::async_tcp_stream(&address, timeout, self.ssl).await?;
 
         // send proto header
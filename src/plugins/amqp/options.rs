@@ -1,10 +1,22 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Version {
+    #[default]
+    #[value(name = "0-9-1")]
+    V0_9_1,
+    #[value(name = "1.0")]
+    V1_0,
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
 pub(crate) struct Options {
     #[clap(long, default_value_t = false)]
     /// Enable SSL for AMQP.
     pub amqp_ssl: bool,
+    #[clap(long, value_enum, default_value_t = Version::V0_9_1)]
+    /// AMQP protocol version, 0-9-1 for RabbitMQ and 1.0 for Azure Service Bus, ActiveMQ Artemis and Qpid.
+    pub amqp_version: Version,
 }
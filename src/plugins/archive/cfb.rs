@@ -0,0 +1,185 @@
+// read only compound file binary format (OLE2) access, enough to extract streams by name
+use crate::session::Error;
+
+const SIGNATURE: &[u8; 8] = b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1";
+const HEADER_SIZE: usize = 512;
+const HEADER_DIFAT_ENTRIES: usize = 109;
+const DIRECTORY_ENTRY_SIZE: usize = 128;
+const END_OF_CHAIN: u32 = 0xfffffffe;
+const MAX_REGULAR_SECTOR: u32 = 0xfffffffa;
+const STREAM_OBJECT: u8 = 2;
+const ROOT_OBJECT: u8 = 5;
+
+fn le16(data: &[u8], at: usize) -> Result<u16, Error> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or("truncated compound file".to_owned())
+}
+
+fn le32(data: &[u8], at: usize) -> Result<u32, Error> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or("truncated compound file".to_owned())
+}
+
+struct DirectoryEntry {
+    name: String,
+    kind: u8,
+    start: u32,
+    size: usize,
+}
+
+pub(crate) struct CompoundFile<'a> {
+    data: &'a [u8],
+    sector_size: usize,
+    mini_sector_size: usize,
+    mini_stream_cutoff: usize,
+    fat: Vec<u32>,
+    mini_fat: Vec<u32>,
+    mini_stream: Vec<u8>,
+    entries: Vec<DirectoryEntry>,
+}
+
+impl<'a> CompoundFile<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_SIZE || &data[..8] != SIGNATURE {
+            return Err("not a compound file".to_owned());
+        }
+        let sector_shift = le16(data, 30)?;
+        let mini_sector_shift = le16(data, 32)?;
+        if !(9..=12).contains(&sector_shift) || mini_sector_shift >= sector_shift {
+            return Err("invalid compound file sector size".to_owned());
+        }
+
+        let mut file = Self {
+            data,
+            sector_size: 1 << sector_shift,
+            mini_sector_size: 1 << mini_sector_shift,
+            mini_stream_cutoff: le32(data, 56)? as usize,
+            fat: vec![],
+            mini_fat: vec![],
+            mini_stream: vec![],
+            entries: vec![],
+        };
+
+        // sectors of the allocation table, from the header and then the DIFAT chain
+        let mut fat_sectors = vec![];
+        for i in 0..HEADER_DIFAT_ENTRIES {
+            let sector = le32(data, 76 + i * 4)?;
+            if sector <= MAX_REGULAR_SECTOR {
+                fat_sectors.push(sector);
+            }
+        }
+        let mut difat = le32(data, 68)?;
+        let mut difat_count = le32(data, 72)?;
+        while difat <= MAX_REGULAR_SECTOR && difat_count > 0 {
+            let sector = file.sector(difat)?;
+            let entries = file.sector_size / 4 - 1;
+            for i in 0..entries {
+                let fat_sector = le32(sector, i * 4)?;
+                if fat_sector <= MAX_REGULAR_SECTOR {
+                    fat_sectors.push(fat_sector);
+                }
+            }
+            difat = le32(sector, entries * 4)?;
+            difat_count -= 1;
+        }
+        for fat_sector in fat_sectors {
+            let sector = file.sector(fat_sector)?;
+            for i in 0..file.sector_size / 4 {
+                file.fat.push(le32(sector, i * 4)?);
+            }
+        }
+
+        let mini_fat = file.chain(le32(data, 60)?)?;
+        file.mini_fat = (0..mini_fat.len() / 4)
+            .map(|i| le32(&mini_fat, i * 4))
+            .collect::<Result<_, _>>()?;
+
+        let directory = file.chain(le32(data, 48)?)?;
+        for raw in directory.chunks_exact(DIRECTORY_ENTRY_SIZE) {
+            let name_len = (le16(raw, 64)? as usize).min(64).saturating_sub(2);
+            let name: Vec<u16> = raw[..name_len]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            file.entries.push(DirectoryEntry {
+                name: String::from_utf16_lossy(&name),
+                kind: raw[66],
+                start: le32(raw, 116)?,
+                size: le32(raw, 120)? as usize,
+            });
+        }
+
+        let root = file
+            .entries
+            .iter()
+            .find(|entry| entry.kind == ROOT_OBJECT)
+            .ok_or("compound file without root entry")?;
+        file.mini_stream = file.chain(root.start)?;
+        Ok(file)
+    }
+
+    fn sector(&self, sector: u32) -> Result<&'a [u8], Error> {
+        let offset = (sector as usize + 1) * self.sector_size;
+        self.data
+            .get(offset..offset + self.sector_size)
+            .ok_or("truncated compound file".to_owned())
+    }
+
+    fn next(table: &[u32], sector: u32) -> Result<u32, Error> {
+        table
+            .get(sector as usize)
+            .copied()
+            .ok_or("invalid compound file sector chain".to_owned())
+    }
+
+    fn chain(&self, start: u32) -> Result<Vec<u8>, Error> {
+        let mut data = vec![];
+        let mut sector = start;
+        while sector != END_OF_CHAIN {
+            if data.len() > self.data.len() {
+                return Err("compound file sector chain loop".to_owned());
+            }
+            data.extend_from_slice(self.sector(sector)?);
+            sector = Self::next(&self.fat, sector)?;
+        }
+        Ok(data)
+    }
+
+    fn mini_chain(&self, start: u32) -> Result<Vec<u8>, Error> {
+        let mut data = vec![];
+        let mut sector = start;
+        while sector != END_OF_CHAIN {
+            if data.len() > self.mini_stream.len() {
+                return Err("compound file sector chain loop".to_owned());
+            }
+            let offset = sector as usize * self.mini_sector_size;
+            data.extend_from_slice(
+                self.mini_stream
+                    .get(offset..offset + self.mini_sector_size)
+                    .ok_or("truncated compound file mini stream")?,
+            );
+            sector = Self::next(&self.mini_fat, sector)?;
+        }
+        Ok(data)
+    }
+
+    pub fn stream(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.kind == STREAM_OBJECT && entry.name == name)
+            .ok_or(format!("no {} stream in the compound file", name))?;
+        let mut data = if entry.size < self.mini_stream_cutoff {
+            self.mini_chain(entry.start)?
+        } else {
+            self.chain(entry.start)?
+        };
+        if data.len() < entry.size {
+            return Err(format!("truncated {} stream", name));
+        }
+        data.truncate(entry.size);
+        Ok(data)
+    }
+}
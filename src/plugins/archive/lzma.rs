@@ -0,0 +1,405 @@
+// LZMA and LZMA2 decoders, only what's needed to read 7z headers and verify folders
+use crate::session::Error;
+
+const BIT_MODEL_TOTAL: u16 = 1 << 11;
+const MOVE_BITS: u32 = 5;
+const TOP_VALUE: u32 = 1 << 24;
+const NUM_STATES: usize = 12;
+const POS_STATES_MAX: usize = 1 << 4;
+const END_POS_MODEL_INDEX: u32 = 14;
+const FULL_DISTANCES: usize = 1 << (END_POS_MODEL_INDEX >> 1);
+const ALIGN_BITS: u32 = 4;
+const MATCH_MIN_LEN: usize = 2;
+
+fn corrupted() -> Error {
+    "corrupted lzma stream".to_owned()
+}
+
+struct RangeDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    range: u32,
+    code: u32,
+}
+
+impl<'a> RangeDecoder<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < 5 || data[0] != 0 {
+            return Err(corrupted());
+        }
+        let code = u32::from_be_bytes([data[1], data[2], data[3], data[4]]);
+        if code == u32::MAX {
+            return Err(corrupted());
+        }
+        Ok(Self {
+            data,
+            pos: 5,
+            range: u32::MAX,
+            code,
+        })
+    }
+
+    fn normalize(&mut self) -> Result<(), Error> {
+        if self.range < TOP_VALUE {
+            let byte = *self.data.get(self.pos).ok_or_else(corrupted)?;
+            self.pos += 1;
+            self.range <<= 8;
+            self.code = (self.code << 8) | byte as u32;
+        }
+        Ok(())
+    }
+
+    fn decode_direct_bits(&mut self, count: u32) -> Result<u32, Error> {
+        let mut result = 0u32;
+        for _ in 0..count {
+            self.range >>= 1;
+            self.code = self.code.wrapping_sub(self.range);
+            let t = 0u32.wrapping_sub(self.code >> 31);
+            self.code = self.code.wrapping_add(self.range & t);
+            if self.code == self.range {
+                return Err(corrupted());
+            }
+            self.normalize()?;
+            result = (result << 1).wrapping_add(t.wrapping_add(1));
+        }
+        Ok(result)
+    }
+
+    fn decode_bit(&mut self, prob: &mut u16) -> Result<usize, Error> {
+        let bound = (self.range >> 11) * (*prob as u32);
+        let bit = if self.code < bound {
+            *prob += (BIT_MODEL_TOTAL - *prob) >> MOVE_BITS;
+            self.range = bound;
+            0
+        } else {
+            *prob -= *prob >> MOVE_BITS;
+            self.code -= bound;
+            self.range -= bound;
+            1
+        };
+        self.normalize()?;
+        Ok(bit)
+    }
+
+    fn decode_tree(&mut self, probs: &mut [u16], bits: u32) -> Result<usize, Error> {
+        let mut m = 1;
+        for _ in 0..bits {
+            m = (m << 1) + self.decode_bit(&mut probs[m])?;
+        }
+        Ok(m - (1 << bits))
+    }
+
+    fn decode_reverse_tree(&mut self, probs: &mut [u16], bits: u32) -> Result<usize, Error> {
+        let mut m = 1;
+        let mut symbol = 0;
+        for i in 0..bits {
+            let bit = self.decode_bit(&mut probs[m])?;
+            m = (m << 1) + bit;
+            symbol |= bit << i;
+        }
+        Ok(symbol)
+    }
+}
+
+#[derive(Clone)]
+struct LenDecoder {
+    choice: u16,
+    choice2: u16,
+    low: [[u16; 8]; POS_STATES_MAX],
+    mid: [[u16; 8]; POS_STATES_MAX],
+    high: [u16; 256],
+}
+
+impl LenDecoder {
+    fn new() -> Self {
+        let init = BIT_MODEL_TOTAL / 2;
+        Self {
+            choice: init,
+            choice2: init,
+            low: [[init; 8]; POS_STATES_MAX],
+            mid: [[init; 8]; POS_STATES_MAX],
+            high: [init; 256],
+        }
+    }
+
+    fn decode(&mut self, rc: &mut RangeDecoder, pos_state: usize) -> Result<usize, Error> {
+        if rc.decode_bit(&mut self.choice)? == 0 {
+            rc.decode_tree(&mut self.low[pos_state], 3)
+        } else if rc.decode_bit(&mut self.choice2)? == 0 {
+            Ok(8 + rc.decode_tree(&mut self.mid[pos_state], 3)?)
+        } else {
+            Ok(16 + rc.decode_tree(&mut self.high, 8)?)
+        }
+    }
+}
+
+struct Lzma {
+    properties: u8,
+    lc: usize,
+    lp: usize,
+    pb: usize,
+    literals: Vec<u16>,
+    pos_slot: [[u16; 64]; 4],
+    pos_decoders: [u16; 1 + FULL_DISTANCES - END_POS_MODEL_INDEX as usize],
+    align: [u16; 1 << ALIGN_BITS],
+    is_match: [u16; NUM_STATES * POS_STATES_MAX],
+    is_rep: [u16; NUM_STATES],
+    is_rep_g0: [u16; NUM_STATES],
+    is_rep_g1: [u16; NUM_STATES],
+    is_rep_g2: [u16; NUM_STATES],
+    is_rep0_long: [u16; NUM_STATES * POS_STATES_MAX],
+    len: LenDecoder,
+    rep_len: LenDecoder,
+    state: usize,
+    reps: [usize; 4],
+}
+
+impl Lzma {
+    fn new(properties: u8) -> Result<Self, Error> {
+        if properties >= 9 * 5 * 5 {
+            return Err("invalid lzma properties".to_owned());
+        }
+        let lc = (properties % 9) as usize;
+        let lp = ((properties / 9) % 5) as usize;
+        let pb = (properties / 45) as usize;
+        let init = BIT_MODEL_TOTAL / 2;
+        Ok(Self {
+            properties,
+            lc,
+            lp,
+            pb,
+            literals: vec![init; 0x300 << (lc + lp)],
+            pos_slot: [[init; 64]; 4],
+            pos_decoders: [init; 1 + FULL_DISTANCES - END_POS_MODEL_INDEX as usize],
+            align: [init; 1 << ALIGN_BITS],
+            is_match: [init; NUM_STATES * POS_STATES_MAX],
+            is_rep: [init; NUM_STATES],
+            is_rep_g0: [init; NUM_STATES],
+            is_rep_g1: [init; NUM_STATES],
+            is_rep_g2: [init; NUM_STATES],
+            is_rep0_long: [init; NUM_STATES * POS_STATES_MAX],
+            len: LenDecoder::new(),
+            rep_len: LenDecoder::new(),
+            state: 0,
+            reps: [0; 4],
+        })
+    }
+
+    fn decode_literal(&mut self, rc: &mut RangeDecoder, out: &mut Vec<u8>) -> Result<(), Error> {
+        let prev = out.last().copied().unwrap_or(0) as usize;
+        let lit_state = ((out.len() & ((1 << self.lp) - 1)) << self.lc) + (prev >> (8 - self.lc));
+        let probs = &mut self.literals[0x300 * lit_state..0x300 * (lit_state + 1)];
+
+        let mut symbol = 1;
+        if self.state >= 7 {
+            let mut match_byte = out[out.len() - self.reps[0] - 1] as usize;
+            while symbol < 0x100 {
+                let match_bit = (match_byte >> 7) & 1;
+                match_byte <<= 1;
+                let bit = rc.decode_bit(&mut probs[((1 + match_bit) << 8) + symbol])?;
+                symbol = (symbol << 1) | bit;
+                if match_bit != bit {
+                    break;
+                }
+            }
+        }
+        while symbol < 0x100 {
+            symbol = (symbol << 1) | rc.decode_bit(&mut probs[symbol])?;
+        }
+        out.push((symbol - 0x100) as u8);
+
+        self.state = match self.state {
+            0..=3 => 0,
+            4..=9 => self.state - 3,
+            _ => self.state - 6,
+        };
+        Ok(())
+    }
+
+    fn decode_distance(&mut self, rc: &mut RangeDecoder, len: usize) -> Result<u32, Error> {
+        let pos_slot = rc.decode_tree(&mut self.pos_slot[len.min(3)], 6)? as u32;
+        if pos_slot < 4 {
+            return Ok(pos_slot);
+        }
+        let direct_bits = (pos_slot >> 1) - 1;
+        let mut distance = (2 | (pos_slot & 1)) << direct_bits;
+        if pos_slot < END_POS_MODEL_INDEX {
+            let offset = (distance - pos_slot) as usize;
+            distance +=
+                rc.decode_reverse_tree(&mut self.pos_decoders[offset..], direct_bits)? as u32;
+        } else {
+            distance += rc.decode_direct_bits(direct_bits - ALIGN_BITS)? << ALIGN_BITS;
+            distance += rc.decode_reverse_tree(&mut self.align, ALIGN_BITS)? as u32;
+        }
+        Ok(distance)
+    }
+
+    // decodes until out holds limit bytes or the end marker is found
+    fn decode(
+        &mut self,
+        rc: &mut RangeDecoder,
+        out: &mut Vec<u8>,
+        limit: usize,
+    ) -> Result<(), Error> {
+        while out.len() < limit {
+            let pos_state = out.len() & ((1 << self.pb) - 1);
+            let state = self.state;
+
+            if rc.decode_bit(&mut self.is_match[(state << 4) + pos_state])? == 0 {
+                self.decode_literal(rc, out)?;
+                continue;
+            }
+
+            let len = if rc.decode_bit(&mut self.is_rep[state])? != 0 {
+                if out.is_empty() {
+                    return Err(corrupted());
+                }
+                if rc.decode_bit(&mut self.is_rep_g0[state])? == 0 {
+                    if rc.decode_bit(&mut self.is_rep0_long[(state << 4) + pos_state])? == 0 {
+                        // short rep
+                        self.state = if state < 7 { 9 } else { 11 };
+                        out.push(out[out.len() - self.reps[0] - 1]);
+                        continue;
+                    }
+                } else {
+                    let distance = if rc.decode_bit(&mut self.is_rep_g1[state])? == 0 {
+                        self.reps[1]
+                    } else {
+                        let distance = if rc.decode_bit(&mut self.is_rep_g2[state])? == 0 {
+                            self.reps[2]
+                        } else {
+                            let distance = self.reps[3];
+                            self.reps[3] = self.reps[2];
+                            distance
+                        };
+                        self.reps[2] = self.reps[1];
+                        distance
+                    };
+                    self.reps[1] = self.reps[0];
+                    self.reps[0] = distance;
+                }
+                let len = self.rep_len.decode(rc, pos_state)?;
+                self.state = if state < 7 { 8 } else { 11 };
+                len
+            } else {
+                self.reps[3] = self.reps[2];
+                self.reps[2] = self.reps[1];
+                self.reps[1] = self.reps[0];
+                let len = self.len.decode(rc, pos_state)?;
+                self.state = if state < 7 { 7 } else { 10 };
+                let distance = self.decode_distance(rc, len)?;
+                if distance == u32::MAX {
+                    // end marker
+                    return Ok(());
+                }
+                self.reps[0] = distance as usize;
+                len
+            };
+
+            let distance = self.reps[0];
+            if distance >= out.len() {
+                return Err(corrupted());
+            }
+            for _ in 0..(len + MATCH_MIN_LEN).min(limit - out.len()) {
+                out.push(out[out.len() - distance - 1]);
+            }
+        }
+        Ok(())
+    }
+}
+
+// properties byte followed by the dictionary size, as stored in 7z coder properties
+pub(crate) fn decode_lzma(properties: &[u8], data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    let mut lzma = Lzma::new(*properties.first().ok_or("missing lzma properties")?)?;
+    let mut rc = RangeDecoder::new(data)?;
+    let mut out = Vec::with_capacity(size);
+    lzma.decode(&mut rc, &mut out, size)?;
+    if out.len() != size {
+        return Err(corrupted());
+    }
+    Ok(out)
+}
+
+pub(crate) fn decode_lzma2(data: &[u8], size: usize) -> Result<Vec<u8>, Error> {
+    let mut lzma: Option<Lzma> = None;
+    let mut out = Vec::with_capacity(size);
+    let mut pos = 0;
+    loop {
+        let control = *data.get(pos).ok_or_else(corrupted)?;
+        pos += 1;
+        match control {
+            0x00 => break,
+            // uncompressed chunk, with or without dictionary reset
+            0x01 | 0x02 => {
+                let len = u16::from_be_bytes([
+                    *data.get(pos).ok_or_else(corrupted)?,
+                    *data.get(pos + 1).ok_or_else(corrupted)?,
+                ]) as usize
+                    + 1;
+                out.extend_from_slice(data.get(pos + 2..pos + 2 + len).ok_or_else(corrupted)?);
+                pos += 2 + len;
+            }
+            0x80..=0xff => {
+                let header = data.get(pos..pos + 4).ok_or_else(corrupted)?;
+                let unpacked =
+                    (((control & 0x1f) as usize) << 16) + u16::from_be_bytes([header[0], header[1]]) as usize + 1;
+                let packed = u16::from_be_bytes([header[2], header[3]]) as usize + 1;
+                pos += 4;
+
+                match (control >> 5) & 3 {
+                    0 => {}
+                    1 => {
+                        let properties = lzma.as_ref().ok_or_else(corrupted)?.properties;
+                        lzma = Some(Lzma::new(properties)?);
+                    }
+                    _ => {
+                        lzma = Some(Lzma::new(*data.get(pos).ok_or_else(corrupted)?)?);
+                        pos += 1;
+                    }
+                }
+                let lzma = lzma.as_mut().ok_or_else(corrupted)?;
+                let mut rc = RangeDecoder::new(data.get(pos..pos + packed).ok_or_else(corrupted)?)?;
+                let limit = out.len() + unpacked;
+                lzma.decode(&mut rc, &mut out, limit)?;
+                if out.len() != limit {
+                    return Err(corrupted());
+                }
+                pos += packed;
+            }
+            _ => return Err(corrupted()),
+        }
+        if out.len() > size {
+            return Err(corrupted());
+        }
+    }
+    if out.len() != size {
+        return Err(corrupted());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_lzma, decode_lzma2};
+
+    #[test]
+    fn can_decode_lzma_and_lzma2() {
+        let expected = b"legba legba legba legba!";
+        let lzma = [
+            0x00, 0x36, 0x19, 0x49, 0x29, 0x92, 0x06, 0x59, 0x3e, 0xfa, 0x4e, 0xd9, 0x61, 0xff,
+            0xff, 0xfb, 0xc9, 0xe0, 0x00,
+        ];
+        // lc=3 lp=0 pb=2
+        assert_eq!(
+            decode_lzma(&[0x5d, 0x00, 0x10, 0x00, 0x00], &lzma, expected.len()).unwrap(),
+            expected
+        );
+
+        let lzma2 = [
+            0xe0, 0x00, 0x17, 0x00, 0x0d, 0x5d, 0x00, 0x36, 0x19, 0x49, 0x29, 0x92, 0x06, 0x59,
+            0x3e, 0xfa, 0x45, 0xe4, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decode_lzma2(&lzma2, expected.len()).unwrap(), expected);
+        assert!(decode_lzma2(&lzma2[..10], expected.len()).is_err());
+    }
+}
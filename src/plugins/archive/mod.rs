@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Options;
+use crate::Plugin;
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+mod cfb;
+mod lzma;
+mod office;
+mod pdf;
+mod sevenz;
+mod zip;
+
+super::manager::register_plugin! {
+    "zip" => Archive::<zip::ZipFile>::new("zip", "ZIP archive password recovery (ZipCrypto and AES)."),
+    "7z" => Archive::<sevenz::SevenZipFile>::new("7z", "7z archive password recovery."),
+    "pdf" => Archive::<pdf::PdfFile>::new("pdf", "PDF document user and owner password recovery."),
+    "office" => Archive::<office::OfficeFile>::new("office", "Office 2007+ encrypted document password recovery.")
+}
+
+// a password protected file loaded in memory
+pub(crate) trait Protected: Sized + Send + Sync + 'static {
+    fn parse(data: &[u8]) -> Result<Self, Error>;
+
+    // returns the loot fields for a valid password
+    fn check(&self, password: &str) -> Option<Vec<(String, String)>>;
+}
+
+#[derive(Clone)]
+pub(crate) struct Archive<P: Protected> {
+    name: &'static str,
+    description: &'static str,
+    path: String,
+    file: Option<Arc<P>>,
+}
+
+impl<P: Protected> Archive<P> {
+    pub fn new(name: &'static str, description: &'static str) -> Self {
+        Archive {
+            name,
+            description,
+            path: String::new(),
+            file: None,
+        }
+    }
+}
+
+#[async_trait]
+impl<P: Protected> Plugin for Archive<P> {
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn requires_network(&self) -> bool {
+        false
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        let path = opts
+            .archive
+            .archive_file
+            .as_ref()
+            .ok_or("no --archive-file provided")?;
+        let data = std::fs::read(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+        self.file = Some(Arc::new(P::parse(&data)?));
+        self.path = path.to_owned();
+        Ok(())
+    }
+
+    // the target is not used, results are reported for the file
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        _timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let file = self.file.clone().unwrap();
+        // being this plugin single credentials, this is going to be the password
+        let password = creds.single().to_owned();
+        // key derivation is cpu bound and deliberately slow for most formats
+        let found = tokio::task::spawn_blocking(move || {
+            file.check(&password).map(|extra| {
+                let mut data = vec![("password".to_owned(), password)];
+                data.extend(extra);
+                data
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(found.map(|data| vec![Loot::new(self.name, &self.path, data)]))
+    }
+}
@@ -0,0 +1,398 @@
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyIvInit};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::session::Error;
+
+use super::cfb::CompoundFile;
+use super::Protected;
+
+const STANDARD_SPIN_COUNT: u32 = 50000;
+const AES_BLOCK_SIZE: usize = 16;
+const ALG_AES_128: u32 = 0x660e;
+const ALG_AES_192: u32 = 0x660f;
+const ALG_AES_256: u32 = 0x6610;
+const VERIFIER_HASH_INPUT_BLOCK: [u8; 8] = [0xfe, 0xa7, 0xd2, 0x76, 0x3b, 0x4b, 0x9e, 0x79];
+const VERIFIER_HASH_VALUE_BLOCK: [u8; 8] = [0xd7, 0xaa, 0x0f, 0x6d, 0x30, 0x61, 0x34, 0x4e];
+const MAX_PASSWORD_CHARS: usize = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Result<Self, Error> {
+        match name {
+            "SHA1" => Ok(HashAlgorithm::Sha1),
+            "SHA256" => Ok(HashAlgorithm::Sha256),
+            "SHA384" => Ok(HashAlgorithm::Sha384),
+            "SHA512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(format!("unsupported office hash algorithm {}", name)),
+        }
+    }
+
+    fn hash(&self, parts: &[&[u8]]) -> Vec<u8> {
+        fn hash<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        match self {
+            HashAlgorithm::Sha1 => hash::<Sha1>(parts),
+            HashAlgorithm::Sha256 => hash::<Sha256>(parts),
+            HashAlgorithm::Sha384 => hash::<Sha384>(parts),
+            HashAlgorithm::Sha512 => hash::<Sha512>(parts),
+        }
+    }
+
+    // H0 = H(salt + password), Hn = H(n + Hn-1)
+    fn spin(&self, salt: &[u8], password: &[u8], count: u32) -> Vec<u8> {
+        let mut hash = self.hash(&[salt, password]);
+        for i in 0..count {
+            hash = self.hash(&[&i.to_le_bytes(), &hash]);
+        }
+        hash
+    }
+}
+
+fn aes_cbc_decrypt(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buffer = data[..data.len() / AES_BLOCK_SIZE * AES_BLOCK_SIZE].to_vec();
+    match key.len() {
+        16 => cbc::Decryptor::<aes::Aes128>::new_from_slices(key, iv)
+            .map_err(|e| e.to_string())?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer)
+            .map(|_| ()),
+        24 => cbc::Decryptor::<aes::Aes192>::new_from_slices(key, iv)
+            .map_err(|e| e.to_string())?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer)
+            .map(|_| ()),
+        32 => cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+            .map_err(|e| e.to_string())?
+            .decrypt_padded_mut::<NoPadding>(&mut buffer)
+            .map(|_| ()),
+        len => return Err(format!("unsupported aes key size {}", len * 8)),
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+// each block decrypted on its own
+fn aes_ecb_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut plain = vec![];
+    for block in data.chunks_exact(AES_BLOCK_SIZE) {
+        plain.extend(aes_cbc_decrypt(key, &[0; AES_BLOCK_SIZE], block)?);
+    }
+    Ok(plain)
+}
+
+#[derive(Debug)]
+enum Encryption {
+    // ECMA-376 standard encryption, Office 2007
+    Standard {
+        key_size: usize,
+        salt: Vec<u8>,
+        verifier: Vec<u8>,
+        verifier_hash: Vec<u8>,
+    },
+    // ECMA-376 agile encryption, Office 2010 and later
+    Agile {
+        hash: HashAlgorithm,
+        spin_count: u32,
+        key_size: usize,
+        block_size: usize,
+        hash_size: usize,
+        salt: Vec<u8>,
+        verifier_hash_input: Vec<u8>,
+        verifier_hash_value: Vec<u8>,
+    },
+}
+
+fn parse_standard(info: &[u8]) -> Result<Encryption, Error> {
+    let le32 = |at: usize| {
+        info.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or("truncated office encryption info")
+    };
+    let header_size = le32(8)? as usize;
+    let algorithm = le32(12 + 8)?;
+    if ![ALG_AES_128, ALG_AES_192, ALG_AES_256].contains(&algorithm) {
+        return Err(format!("unsupported office encryption algorithm 0x{:x}", algorithm));
+    }
+    let key_size = le32(12 + 16)? as usize / 8;
+
+    let verifier = 12 + header_size;
+    let salt_size = le32(verifier)? as usize;
+    let salt = info
+        .get(verifier + 4..verifier + 4 + salt_size)
+        .ok_or("truncated office encryption info")?;
+    let encrypted = verifier + 4 + salt_size;
+    Ok(Encryption::Standard {
+        key_size,
+        salt: salt.to_vec(),
+        verifier: info
+            .get(encrypted..encrypted + 16)
+            .ok_or("truncated office encryption info")?
+            .to_vec(),
+        verifier_hash: info
+            .get(encrypted + 20..encrypted + 20 + 32)
+            .ok_or("truncated office encryption info")?
+            .to_vec(),
+    })
+}
+
+fn parse_agile(info: &[u8]) -> Result<Encryption, Error> {
+    let xml = String::from_utf8_lossy(info.get(8..).ok_or("truncated office encryption info")?);
+    let document = roxmltree::Document::parse(xml.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("invalid office encryption info: {}", e))?;
+    // the password key encryptor, not the data integrity or certificate ones
+    let encrypted_key = document
+        .descendants()
+        .find(|node| {
+            node.tag_name().name() == "encryptedKey"
+                && node.has_attribute("encryptedVerifierHashInput")
+        })
+        .ok_or("no password key encryptor in the office encryption info")?;
+    let attribute = |name: &str| {
+        encrypted_key
+            .attribute(name)
+            .ok_or(format!("missing office encryption attribute {}", name))
+    };
+    let number = |name: &str| {
+        attribute(name)?
+            .parse::<u32>()
+            .map_err(|e| format!("invalid office encryption attribute {}: {}", name, e))
+    };
+    let binary = |name: &str| {
+        BASE64_STANDARD
+            .decode(attribute(name)?)
+            .map_err(|e| format!("invalid office encryption attribute {}: {}", name, e))
+    };
+
+    if attribute("cipherAlgorithm")? != "AES" || attribute("cipherChaining")? != "ChainingModeCBC" {
+        return Err("only AES-CBC office encryption is supported".to_owned());
+    }
+    Ok(Encryption::Agile {
+        hash: HashAlgorithm::from_name(attribute("hashAlgorithm")?)?,
+        spin_count: number("spinCount")?,
+        key_size: number("keyBits")? as usize / 8,
+        block_size: number("blockSize")? as usize,
+        hash_size: number("hashSize")? as usize,
+        salt: binary("saltValue")?,
+        verifier_hash_input: binary("encryptedVerifierHashInput")?,
+        verifier_hash_value: binary("encryptedVerifierHashValue")?,
+    })
+}
+
+// key derivation of the agile encryption, truncated or padded with 0x36
+fn agile_key(hash: HashAlgorithm, spun: &[u8], block: &[u8], key_size: usize) -> Vec<u8> {
+    let mut key = hash.hash(&[spun, block]);
+    key.resize(key_size, 0x36);
+    key
+}
+
+pub(crate) struct OfficeFile {
+    encryption: Encryption,
+}
+
+impl OfficeFile {
+    fn encode(password: &str) -> Vec<u8> {
+        password
+            .encode_utf16()
+            .take(MAX_PASSWORD_CHARS)
+            .flat_map(|c| c.to_le_bytes())
+            .collect()
+    }
+
+    fn check_standard(
+        password: &[u8],
+        key_size: usize,
+        salt: &[u8],
+        verifier: &[u8],
+        verifier_hash: &[u8],
+    ) -> Result<bool, Error> {
+        let hash = HashAlgorithm::Sha1;
+        let spun = hash.spin(salt, password, STANDARD_SPIN_COUNT);
+        let derived = hash.hash(&[&spun, &0u32.to_le_bytes()]);
+
+        let mut x1 = [0x36u8; 64];
+        let mut x2 = [0x5cu8; 64];
+        for (i, b) in derived.iter().enumerate() {
+            x1[i] ^= b;
+            x2[i] ^= b;
+        }
+        let mut key = hash.hash(&[&x1]);
+        key.extend(hash.hash(&[&x2]));
+        key.truncate(key_size);
+
+        let verifier = aes_ecb_decrypt(&key, verifier)?;
+        let verifier_hash = aes_ecb_decrypt(&key, verifier_hash)?;
+        Ok(hash.hash(&[&verifier])[..] == verifier_hash[..20])
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_agile(
+        password: &[u8],
+        hash: HashAlgorithm,
+        spin_count: u32,
+        key_size: usize,
+        block_size: usize,
+        hash_size: usize,
+        salt: &[u8],
+        verifier_hash_input: &[u8],
+        verifier_hash_value: &[u8],
+    ) -> Result<bool, Error> {
+        let spun = hash.spin(salt, password, spin_count);
+        let mut iv = salt.to_vec();
+        iv.resize(block_size, 0x36);
+
+        let input_key = agile_key(hash, &spun, &VERIFIER_HASH_INPUT_BLOCK, key_size);
+        let mut verifier = aes_cbc_decrypt(&input_key, &iv, verifier_hash_input)?;
+        verifier.truncate(salt.len());
+
+        let value_key = agile_key(hash, &spun, &VERIFIER_HASH_VALUE_BLOCK, key_size);
+        let expected = aes_cbc_decrypt(&value_key, &iv, verifier_hash_value)?;
+        let computed = hash.hash(&[&verifier]);
+        Ok(expected.len() >= hash_size && computed.len() >= hash_size && computed[..hash_size] == expected[..hash_size])
+    }
+}
+
+impl Protected for OfficeFile {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let file = CompoundFile::parse(data).map_err(|e| {
+            if data.starts_with(b"PK") {
+                "the office document is not encrypted".to_owned()
+            } else {
+                e
+            }
+        })?;
+        let info = file.stream("EncryptionInfo")?;
+        if info.len() < 8 {
+            return Err("truncated office encryption info".to_owned());
+        }
+        let version = (
+            u16::from_le_bytes([info[0], info[1]]),
+            u16::from_le_bytes([info[2], info[3]]),
+        );
+        let encryption = match version {
+            (3 | 4, 2) => parse_standard(&info)?,
+            (4, 4) => parse_agile(&info)?,
+            (major, minor) => {
+                return Err(format!(
+                    "unsupported office encryption version {}.{}",
+                    major, minor
+                ))
+            }
+        };
+        Ok(Self { encryption })
+    }
+
+    fn check(&self, password: &str) -> Option<Vec<(String, String)>> {
+        let password = Self::encode(password);
+        let (valid, kind) = match &self.encryption {
+            Encryption::Standard {
+                key_size,
+                salt,
+                verifier,
+                verifier_hash,
+            } => (
+                Self::check_standard(&password, *key_size, salt, verifier, verifier_hash),
+                "standard",
+            ),
+            Encryption::Agile {
+                hash,
+                spin_count,
+                key_size,
+                block_size,
+                hash_size,
+                salt,
+                verifier_hash_input,
+                verifier_hash_value,
+            } => (
+                Self::check_agile(
+                    &password,
+                    *hash,
+                    *spin_count,
+                    *key_size,
+                    *block_size,
+                    *hash_size,
+                    salt,
+                    verifier_hash_input,
+                    verifier_hash_value,
+                ),
+                "agile",
+            ),
+        };
+        if valid.unwrap_or(false) {
+            Some(vec![("encryption".to_owned(), kind.to_owned())])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encryption, HashAlgorithm, OfficeFile};
+    use crate::plugins::archive::Protected;
+
+    #[test]
+    fn can_check_standard_password() {
+        let file = OfficeFile {
+            encryption: Encryption::Standard {
+                key_size: 16,
+                salt: (16..32).collect(),
+                verifier: vec![
+                    0x69, 0xbf, 0xda, 0x7e, 0x7e, 0xb4, 0x15, 0xa5, 0xbb, 0xb7, 0x8f, 0x9d, 0x02,
+                    0x29, 0xb6, 0x33,
+                ],
+                verifier_hash: vec![
+                    0x06, 0xae, 0x78, 0x83, 0x5b, 0x65, 0x0e, 0x79, 0x88, 0x36, 0x05, 0x57, 0x42,
+                    0x5e, 0x3d, 0x6e, 0xb5, 0x26, 0x8a, 0x0c, 0x31, 0x19, 0x43, 0xa1, 0x40, 0x1b,
+                    0x43, 0x83, 0x32, 0x35, 0x96, 0xd2,
+                ],
+            },
+        };
+        assert!(file.check("wrong").is_none());
+        assert_eq!(
+            file.check("s3cret"),
+            Some(vec![("encryption".to_owned(), "standard".to_owned())])
+        );
+    }
+
+    #[test]
+    fn can_check_agile_password() {
+        let file = OfficeFile {
+            encryption: Encryption::Agile {
+                hash: HashAlgorithm::Sha512,
+                spin_count: 1000,
+                key_size: 32,
+                block_size: 16,
+                hash_size: 64,
+                salt: (0..16).collect(),
+                verifier_hash_input: vec![
+                    0x2b, 0xbb, 0xf7, 0xf5, 0xf4, 0x2a, 0x79, 0x5e, 0x9b, 0x73, 0x6a, 0xa3, 0xa4,
+                    0xb8, 0x06, 0x4f,
+                ],
+                verifier_hash_value: vec![
+                    0x7e, 0x13, 0xb8, 0xa7, 0xe3, 0x35, 0x50, 0x90, 0x34, 0x5b, 0xb4, 0xcb, 0xe0,
+                    0xe4, 0x0b, 0x08, 0xd2, 0xf4, 0x85, 0x00, 0xdd, 0xea, 0x3e, 0x7f, 0x6a, 0xa2,
+                    0x38, 0x7d, 0x67, 0x73, 0xdd, 0xf1, 0x0e, 0xcc, 0x10, 0x3b, 0x35, 0x55, 0x9e,
+                    0xe9, 0x13, 0xd9, 0xbd, 0x89, 0x55, 0x7a, 0xc1, 0x40, 0x8b, 0xc5, 0xa4, 0xdd,
+                    0x46, 0xf4, 0x45, 0x53, 0x67, 0x9f, 0x21, 0x85, 0x6d, 0xc2, 0xfe, 0xc3,
+                ],
+            },
+        };
+        assert!(file.check("wrong").is_none());
+        assert_eq!(
+            file.check("s3cret"),
+            Some(vec![("encryption".to_owned(), "agile".to_owned())])
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long)]
+    /// Path of the ZIP, 7z, PDF or Office file to recover the password of.
+    pub archive_file: Option<String>,
+}
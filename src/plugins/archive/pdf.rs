@@ -0,0 +1,511 @@
+use aes::cipher::{block_padding::NoPadding, BlockEncryptMut, KeyIvInit};
+use md5::{Digest, Md5};
+use regex::bytes::Regex;
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::session::Error;
+
+use super::Protected;
+
+const PASSWORD_PADDING: [u8; 32] = [
+    0x28, 0xbf, 0x4e, 0x5e, 0x4e, 0x75, 0x8a, 0x41, 0x64, 0x00, 0x4e, 0x56, 0xff, 0xfa, 0x01, 0x08,
+    0x2e, 0x2e, 0x00, 0xb6, 0xd0, 0x68, 0x3e, 0x80, 0x2f, 0x0c, 0xa9, 0xfe, 0x64, 0x53, 0x69, 0x7a,
+];
+const MAX_UTF8_PASSWORD: usize = 127;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Number(String),
+    String(Vec<u8>),
+    DictStart,
+    DictEnd,
+    ArrayStart,
+    ArrayEnd,
+    Keyword(String),
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || b"()<>[]{}/%".contains(&b)
+}
+
+fn literal_string(data: &[u8], pos: &mut usize) -> Vec<u8> {
+    let mut value = vec![];
+    let mut depth = 1;
+    while let Some(&b) = data.get(*pos) {
+        *pos += 1;
+        match b {
+            b'\\' => {
+                let Some(&next) = data.get(*pos) else {
+                    break;
+                };
+                *pos += 1;
+                match next {
+                    b'n' => value.push(b'\n'),
+                    b'r' => value.push(b'\r'),
+                    b't' => value.push(b'\t'),
+                    b'b' => value.push(0x08),
+                    b'f' => value.push(0x0c),
+                    b'0'..=b'7' => {
+                        let mut octal = (next - b'0') as u32;
+                        for _ in 0..2 {
+                            match data.get(*pos) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    octal = octal * 8 + (d - b'0') as u32;
+                                    *pos += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        value.push(octal as u8);
+                    }
+                    // line continuation
+                    b'\r' => {
+                        if data.get(*pos) == Some(&b'\n') {
+                            *pos += 1;
+                        }
+                    }
+                    b'\n' => {}
+                    other => value.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                value.push(b);
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+                value.push(b);
+            }
+            _ => value.push(b),
+        }
+    }
+    value
+}
+
+fn hex_string(data: &[u8], pos: &mut usize) -> Vec<u8> {
+    let mut digits = vec![];
+    while let Some(&b) = data.get(*pos) {
+        *pos += 1;
+        if b == b'>' {
+            break;
+        }
+        if let Some(digit) = (b as char).to_digit(16) {
+            digits.push(digit as u8);
+        }
+    }
+    if digits.len() % 2 == 1 {
+        digits.push(0);
+    }
+    digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+}
+
+// minimal tokenizer, enough for the encryption and trailer dictionaries
+fn next_token(data: &[u8], pos: &mut usize) -> Option<Token> {
+    loop {
+        while data.get(*pos)?.is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if data[*pos] == b'%' {
+            while !matches!(data.get(*pos)?, b'\r' | b'\n') {
+                *pos += 1;
+            }
+        } else {
+            break;
+        }
+    }
+
+    let b = data[*pos];
+    *pos += 1;
+    Some(match b {
+        b'(' => Token::String(literal_string(data, pos)),
+        b'<' if data.get(*pos) == Some(&b'<') => {
+            *pos += 1;
+            Token::DictStart
+        }
+        b'<' => Token::String(hex_string(data, pos)),
+        b'>' if data.get(*pos) == Some(&b'>') => {
+            *pos += 1;
+            Token::DictEnd
+        }
+        b'[' => Token::ArrayStart,
+        b']' => Token::ArrayEnd,
+        _ => {
+            let start = if b == b'/' { *pos } else { *pos - 1 };
+            while data.get(*pos).map(|b| !is_delimiter(*b)).unwrap_or(false) {
+                *pos += 1;
+            }
+            let word = String::from_utf8_lossy(&data[start..*pos]).to_string();
+            if b == b'/' {
+                Token::Name(word)
+            } else if b.is_ascii_digit() || b == b'-' || b == b'+' || b == b'.' {
+                Token::Number(word)
+            } else {
+                Token::Keyword(word)
+            }
+        }
+    })
+}
+
+// top level entries with simple values of the dictionary starting at pos
+fn dictionary(data: &[u8], mut pos: usize) -> Result<Vec<(String, Token)>, Error> {
+    if next_token(data, &mut pos) != Some(Token::DictStart) {
+        return Err("invalid pdf dictionary".to_owned());
+    }
+    let mut entries = vec![];
+    let mut depth = 1;
+    let mut key: Option<String> = None;
+    while depth > 0 {
+        let token = next_token(data, &mut pos).ok_or("truncated pdf dictionary")?;
+        match token {
+            Token::DictStart | Token::ArrayStart => {
+                depth += 1;
+                key = None;
+            }
+            Token::DictEnd | Token::ArrayEnd => depth -= 1,
+            Token::Name(name) if depth == 1 && key.is_none() => key = Some(name),
+            token if depth == 1 => {
+                if let Some(key) = key.take() {
+                    entries.push((key, token));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut s: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+        s.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|b| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(s[i as usize]);
+            s.swap(i as usize, j as usize);
+            b ^ s[s[i as usize].wrapping_add(s[j as usize]) as usize]
+        })
+        .collect()
+}
+
+fn padded(password: &[u8]) -> Vec<u8> {
+    password
+        .iter()
+        .take(32)
+        .chain(PASSWORD_PADDING.iter())
+        .take(32)
+        .copied()
+        .collect()
+}
+
+// PDF 2.0 hash, algorithm 2.B
+fn hash_r6(password: &[u8], salt: &[u8], user_key: &[u8]) -> Vec<u8> {
+    let mut k = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(user_key)
+        .finalize()
+        .to_vec();
+    let mut round = 0;
+    loop {
+        let mut k1 = Vec::with_capacity(64 * (password.len() + k.len() + user_key.len()));
+        for _ in 0..64 {
+            k1.extend_from_slice(password);
+            k1.extend_from_slice(&k);
+            k1.extend_from_slice(user_key);
+        }
+        let len = k1.len();
+        let e = cbc::Encryptor::<aes::Aes128>::new_from_slices(&k[..16], &k[16..32])
+            .unwrap()
+            .encrypt_padded_mut::<NoPadding>(&mut k1, len)
+            .unwrap()
+            .to_vec();
+        k = match e[..16].iter().map(|b| *b as u32).sum::<u32>() % 3 {
+            0 => Sha256::digest(&e).to_vec(),
+            1 => Sha384::digest(&e).to_vec(),
+            _ => Sha512::digest(&e).to_vec(),
+        };
+        round += 1;
+        if round >= 64 && *e.last().unwrap() as u32 <= round - 32 {
+            break;
+        }
+    }
+    k.truncate(32);
+    k
+}
+
+#[derive(Debug)]
+pub(crate) struct PdfFile {
+    revision: u32,
+    key_length: usize,
+    permissions: i32,
+    owner: Vec<u8>,
+    user: Vec<u8>,
+    id: Vec<u8>,
+    encrypt_metadata: bool,
+    // the document opens without a password, only the owner one is worth finding
+    empty_user_password: bool,
+}
+
+impl PdfFile {
+    fn encode(&self, password: &str) -> Vec<u8> {
+        if self.revision >= 5 {
+            let mut utf8 = password.as_bytes().to_vec();
+            utf8.truncate(MAX_UTF8_PASSWORD);
+            utf8
+        } else {
+            // PDFDocEncoding is close enough to latin1
+            password
+                .chars()
+                .map(|c| if (c as u32) < 256 { c as u8 } else { b'?' })
+                .collect()
+        }
+    }
+
+    // algorithm 2, the rc4 file key from the user password
+    fn file_key(&self, password: &[u8]) -> Vec<u8> {
+        let mut hasher = Md5::new();
+        hasher.update(padded(password));
+        hasher.update(&self.owner[..32]);
+        hasher.update(self.permissions.to_le_bytes());
+        hasher.update(&self.id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            hasher.update([0xff; 4]);
+        }
+        let mut key = hasher.finalize().to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                key = Md5::digest(&key[..self.key_length]).to_vec();
+            }
+        }
+        key.truncate(self.key_length);
+        key
+    }
+
+    fn is_user_password(&self, password: &[u8]) -> bool {
+        match self.revision {
+            2 => rc4(&self.file_key(password), &PASSWORD_PADDING) == self.user[..32],
+            3 | 4 => {
+                let key = self.file_key(password);
+                let hash = Md5::new()
+                    .chain_update(PASSWORD_PADDING)
+                    .chain_update(&self.id)
+                    .finalize();
+                let mut x = rc4(&key, &hash);
+                for i in 1..=19u8 {
+                    let round_key: Vec<u8> = key.iter().map(|b| b ^ i).collect();
+                    x = rc4(&round_key, &x);
+                }
+                x == self.user[..16]
+            }
+            5 => Sha256::new()
+                .chain_update(password)
+                .chain_update(&self.user[32..40])
+                .finalize()[..]
+                == self.user[..32],
+            _ => hash_r6(password, &self.user[32..40], &[]) == self.user[..32],
+        }
+    }
+
+    fn is_owner_password(&self, password: &[u8]) -> bool {
+        match self.revision {
+            2..=4 => {
+                // algorithm 7, the owner entry holds the encrypted user password
+                let mut key = Md5::digest(padded(password)).to_vec();
+                if self.revision >= 3 {
+                    for _ in 0..50 {
+                        key = Md5::digest(&key).to_vec();
+                    }
+                }
+                key.truncate(self.key_length);
+                let user_password = if self.revision == 2 {
+                    rc4(&key, &self.owner[..32])
+                } else {
+                    let mut x = self.owner[..32].to_vec();
+                    for i in (0..=19u8).rev() {
+                        let round_key: Vec<u8> = key.iter().map(|b| b ^ i).collect();
+                        x = rc4(&round_key, &x);
+                    }
+                    x
+                };
+                self.is_user_password(&user_password)
+            }
+            5 => Sha256::new()
+                .chain_update(password)
+                .chain_update(&self.owner[32..40])
+                .chain_update(&self.user[..48])
+                .finalize()[..]
+                == self.owner[..32],
+            _ => hash_r6(password, &self.owner[32..40], &self.user[..48]) == self.owner[..32],
+        }
+    }
+}
+
+impl Protected for PdfFile {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        if !data.starts_with(b"%PDF-") {
+            return Err("not a pdf file".to_owned());
+        }
+
+        // the last trailer wins on incremental updates
+        let encrypt = Regex::new(r"/Encrypt\s*(?:(\d+)\s+(\d+)\s+R|<<)").unwrap();
+        let caps = encrypt
+            .captures_iter(data)
+            .last()
+            .ok_or("the pdf file is not encrypted")?;
+        let start = match (caps.get(1), caps.get(2)) {
+            (Some(number), Some(generation)) => {
+                let object = Regex::new(&format!(
+                    r"(?:^|[^0-9]){}\s+{}\s+obj",
+                    String::from_utf8_lossy(number.as_bytes()),
+                    String::from_utf8_lossy(generation.as_bytes())
+                ))
+                .unwrap();
+                object
+                    .find(data)
+                    .map(|found| found.end())
+                    .ok_or("can't find the pdf encryption dictionary")?
+            }
+            _ => caps.get(0).unwrap().end() - 2,
+        };
+        let entries = dictionary(data, start)?;
+        let get = |key: &str| entries.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+        let number = |key: &str| match get(key) {
+            Some(Token::Number(n)) => n.parse::<i64>().ok(),
+            _ => None,
+        };
+        let string = |key: &str| match get(key) {
+            Some(Token::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+
+        if !matches!(get("Filter"), Some(Token::Name(name)) if name == "Standard") {
+            return Err("only the standard pdf security handler is supported".to_owned());
+        }
+        let revision = number("R").ok_or("missing pdf encryption revision")? as u32;
+        if !(2..=6).contains(&revision) {
+            return Err(format!("unsupported pdf encryption revision {}", revision));
+        }
+        let owner = string("O").ok_or("missing pdf owner key")?;
+        let user = string("U").ok_or("missing pdf user key")?;
+        let key_size = if revision >= 5 { 48 } else { 32 };
+        if owner.len() < key_size || user.len() < key_size {
+            return Err("invalid pdf owner or user key".to_owned());
+        }
+
+        let id = if revision >= 5 {
+            vec![]
+        } else {
+            let trailer_id = Regex::new(r"/ID\s*\[").unwrap();
+            let mut pos = trailer_id
+                .find_iter(data)
+                .last()
+                .map(|found| found.end())
+                .ok_or("missing pdf document id")?;
+            match next_token(data, &mut pos) {
+                Some(Token::String(id)) => id,
+                _ => return Err("invalid pdf document id".to_owned()),
+            }
+        };
+
+        let mut pdf = Self {
+            revision,
+            key_length: if revision == 2 {
+                5
+            } else {
+                (number("Length").unwrap_or(40) / 8).clamp(5, 16) as usize
+            },
+            permissions: number("P").ok_or("missing pdf permissions")? as i32,
+            owner,
+            user,
+            id,
+            encrypt_metadata: !matches!(get("EncryptMetadata"), Some(Token::Keyword(k)) if k == "false"),
+            empty_user_password: false,
+        };
+        pdf.empty_user_password = pdf.is_user_password(b"");
+        Ok(pdf)
+    }
+
+    fn check(&self, password: &str) -> Option<Vec<(String, String)>> {
+        let encoded = self.encode(password);
+        let kind = if !self.empty_user_password && self.is_user_password(&encoded) {
+            "user"
+        } else if self.is_owner_password(&encoded) {
+            "owner"
+        } else {
+            return None;
+        };
+        Some(vec![
+            ("password_type".to_owned(), kind.to_owned()),
+            ("revision".to_owned(), self.revision.to_string()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PdfFile;
+    use crate::plugins::archive::Protected;
+
+    const R3: &[u8] = b"%PDF-1.4
+1 0 obj
+<< /Type /Catalog >>
+endobj
+5 0 obj
+<< /Filter /Standard /V 2 /R 3 /Length 128 /P -3904 \
+/O <0ba3835f88f90388e74e54584125ce142be0de24c6b0d37746e075b891756671> \
+/U <ad944bba9714b0f0d9da179955e1945000000000000000000000000000000000> >>
+endobj
+trailer
+<< /Root 1 0 R /Encrypt 5 0 R /ID [<0123456789abcdef0123456789abcdef> <0123456789abcdef0123456789abcdef>] >>
+%%EOF
+";
+
+    #[test]
+    fn can_check_rc4_passwords() {
+        let pdf = PdfFile::parse(R3).unwrap();
+        assert_eq!(pdf.revision, 3);
+        assert_eq!(pdf.key_length, 16);
+        assert!(pdf.check("wrong").is_none());
+        assert_eq!(
+            pdf.check("user"),
+            Some(vec![
+                ("password_type".to_owned(), "user".to_owned()),
+                ("revision".to_owned(), "3".to_owned()),
+            ])
+        );
+        assert_eq!(
+            pdf.check("owner"),
+            Some(vec![
+                ("password_type".to_owned(), "owner".to_owned()),
+                ("revision".to_owned(), "3".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn can_check_aes256_passwords() {
+        let pdf = PdfFile {
+            revision: 6,
+            key_length: 32,
+            permissions: -4,
+            owner: vec![0x43, 0x0f, 0xca, 0xed, 0x60, 0x2c, 0xed, 0x2e, 0xa5, 0xa8, 0xde, 0xaa, 0xb9, 0xe3, 0x23, 0x78, 0x8c, 0xe3, 0x24, 0xb8, 0xae, 0x39, 0xb7, 0xd6, 0x27, 0xf4, 0x7f, 0xdc, 0x2c, 0x3f, 0x80, 0x0d, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f],
+            user: vec![0x73, 0x17, 0x58, 0xc0, 0x9c, 0x8b, 0x01, 0x60, 0xa3, 0x47, 0x21, 0xd1, 0x8b, 0xdd, 0x24, 0x22, 0x0a, 0xba, 0xda, 0x00, 0x70, 0xaa, 0x3f, 0x05, 0xb8, 0x10, 0x3f, 0xd5, 0xb8, 0xd0, 0x5f, 0x17, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f],
+            id: vec![],
+            encrypt_metadata: true,
+            empty_user_password: false,
+        };
+        assert!(pdf.is_user_password(b"user"));
+        assert!(!pdf.is_user_password(b"owner"));
+        assert!(pdf.is_owner_password(b"owner"));
+        assert!(!pdf.is_owner_password(b"user"));
+    }
+}
@@ -0,0 +1,593 @@
+use aes::cipher::{block_padding::NoPadding, BlockDecryptMut, KeyIvInit};
+use sha2::{Digest, Sha256};
+
+use crate::session::Error;
+
+use super::lzma;
+use super::Protected;
+
+const SIGNATURE: &[u8; 6] = b"7z\xbc\xaf\x27\x1c";
+const SIGNATURE_HEADER_SIZE: usize = 32;
+const AES_BLOCK_SIZE: usize = 16;
+// on 7z the whole key derivation is skipped with this
+const NO_KEY_DERIVATION: u8 = 0x3f;
+
+const ID_END: u8 = 0x00;
+const ID_HEADER: u8 = 0x01;
+const ID_ARCHIVE_PROPERTIES: u8 = 0x02;
+const ID_ADDITIONAL_STREAMS_INFO: u8 = 0x03;
+const ID_MAIN_STREAMS_INFO: u8 = 0x04;
+const ID_PACK_INFO: u8 = 0x06;
+const ID_UNPACK_INFO: u8 = 0x07;
+const ID_SUBSTREAMS_INFO: u8 = 0x08;
+const ID_SIZE: u8 = 0x09;
+const ID_CRC: u8 = 0x0a;
+const ID_FOLDER: u8 = 0x0b;
+const ID_CODERS_UNPACK_SIZE: u8 = 0x0c;
+const ID_NUM_UNPACK_STREAM: u8 = 0x0d;
+const ID_ENCODED_HEADER: u8 = 0x17;
+
+const CODER_COPY: &[u8] = &[0x00];
+const CODER_LZMA: &[u8] = &[0x03, 0x01, 0x01];
+const CODER_LZMA2: &[u8] = &[0x21];
+const CODER_AES: &[u8] = &[0x06, 0xf1, 0x07, 0x01];
+
+fn truncated() -> Error {
+    "truncated 7z header".to_owned()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let byte = *self.data.get(self.pos).ok_or_else(truncated)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // variable length number, the leading one bits of the first byte are the number of extra bytes
+    fn number(&mut self) -> Result<u64, Error> {
+        let first = self.byte()?;
+        let mut mask = 0x80u8;
+        let mut value = 0u64;
+        for i in 0..8 {
+            if first & mask == 0 {
+                let high = (first & mask.wrapping_sub(1)) as u64;
+                return Ok(value | (high << (8 * i)));
+            }
+            value |= (self.byte()? as u64) << (8 * i);
+            mask >>= 1;
+        }
+        Ok(value)
+    }
+
+    fn size(&mut self) -> Result<usize, Error> {
+        usize::try_from(self.number()?).map_err(|e| e.to_string())
+    }
+
+    fn expect(&mut self, id: u8) -> Result<(), Error> {
+        let found = self.byte()?;
+        if found != id {
+            return Err(format!("unexpected 7z property 0x{:02x}", found));
+        }
+        Ok(())
+    }
+
+    fn bits(&mut self, count: usize) -> Result<Vec<bool>, Error> {
+        let mut bits = Vec::with_capacity(count);
+        let mut byte = 0;
+        for i in 0..count {
+            if i % 8 == 0 {
+                byte = self.byte()?;
+            }
+            bits.push(byte & (0x80 >> (i % 8)) != 0);
+        }
+        Ok(bits)
+    }
+
+    fn digests(&mut self, count: usize) -> Result<Vec<Option<u32>>, Error> {
+        let defined = if self.byte()? == 0 {
+            self.bits(count)?
+        } else {
+            vec![true; count]
+        };
+        defined
+            .into_iter()
+            .map(|defined| if defined { self.u32().map(Some) } else { Ok(None) })
+            .collect()
+    }
+
+    fn skip_property(&mut self) -> Result<(), Error> {
+        let size = self.size()?;
+        self.bytes(size)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Coder {
+    id: Vec<u8>,
+    properties: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Folder {
+    // simple coders only, each one feeding the previous one
+    coders: Vec<Coder>,
+    unpack_sizes: Vec<usize>,
+    crc: Option<u32>,
+    // offset and size of its packed stream in the file
+    pack_offset: usize,
+    pack_size: usize,
+    // crc of the first file when the folder has none
+    first_stream: Option<(usize, u32)>,
+    // coder indexes in chain order
+    order: Vec<usize>,
+}
+
+impl Folder {
+    fn is_encrypted(&self) -> bool {
+        self.coders.iter().any(|coder| coder.id == CODER_AES)
+    }
+}
+
+fn parse_folder(reader: &mut Reader) -> Result<Folder, Error> {
+    let num_coders = reader.size()?;
+    let mut coders = vec![];
+    for _ in 0..num_coders {
+        let flags = reader.byte()?;
+        let id = reader.bytes((flags & 0x0f) as usize)?.to_vec();
+        if flags & 0x10 != 0 {
+            // BCJ2 and other multi stream coders
+            return Err("unsupported 7z coder with multiple streams".to_owned());
+        }
+        let properties = if flags & 0x20 != 0 {
+            let size = reader.size()?;
+            reader.bytes(size)?.to_vec()
+        } else {
+            vec![]
+        };
+        coders.push(Coder { id, properties });
+    }
+
+    // bind pairs, in index then out index
+    let mut binds = vec![];
+    for _ in 1..num_coders {
+        binds.push((reader.size()?, reader.size()?));
+    }
+    // the only unbound input is the packed stream
+    let packed = (0..num_coders)
+        .find(|coder| !binds.iter().any(|(input, _)| input == coder))
+        .ok_or("invalid 7z folder")?;
+
+    // order coders from the packed stream to the output
+    let mut chain = vec![];
+    let mut current = packed;
+    while chain.len() < num_coders {
+        chain.push(current);
+        match binds.iter().find(|(_, output)| *output == current) {
+            Some((input, _)) => current = *input,
+            None => break,
+        }
+    }
+    if chain.len() != num_coders {
+        return Err("invalid 7z folder".to_owned());
+    }
+
+    Ok(Folder {
+        coders: chain.iter().map(|i| coders[*i].clone()).collect(),
+        order: chain,
+        ..Default::default()
+    })
+}
+
+// streams info of the main or encoded header
+fn parse_streams_info(reader: &mut Reader) -> Result<Vec<Folder>, Error> {
+    let mut pack_pos = 0;
+    let mut pack_sizes = vec![];
+    let mut folders = vec![];
+
+    loop {
+        match reader.byte()? {
+            ID_END => break,
+            ID_PACK_INFO => {
+                pack_pos = reader.size()?;
+                let count = reader.size()?;
+                loop {
+                    match reader.byte()? {
+                        ID_END => break,
+                        ID_SIZE => {
+                            for _ in 0..count {
+                                pack_sizes.push(reader.size()?);
+                            }
+                        }
+                        ID_CRC => {
+                            reader.digests(count)?;
+                        }
+                        _ => reader.skip_property()?,
+                    }
+                }
+            }
+            ID_UNPACK_INFO => {
+                reader.expect(ID_FOLDER)?;
+                let count = reader.size()?;
+                if reader.byte()? != 0 {
+                    return Err("unsupported external 7z folders".to_owned());
+                }
+                for _ in 0..count {
+                    folders.push(parse_folder(reader)?);
+                }
+                reader.expect(ID_CODERS_UNPACK_SIZE)?;
+                for folder in folders.iter_mut() {
+                    // stored by coder index
+                    let sizes = (0..folder.coders.len())
+                        .map(|_| reader.size())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    folder.unpack_sizes = folder.order.iter().map(|i| sizes[*i]).collect();
+                }
+                loop {
+                    match reader.byte()? {
+                        ID_END => break,
+                        ID_CRC => {
+                            for (folder, crc) in folders.iter_mut().zip(reader.digests(count)?) {
+                                folder.crc = crc;
+                            }
+                        }
+                        _ => reader.skip_property()?,
+                    }
+                }
+            }
+            ID_SUBSTREAMS_INFO => parse_substreams_info(reader, &mut folders)?,
+            id => return Err(format!("unexpected 7z property 0x{:02x}", id)),
+        }
+    }
+
+    // one packed stream per folder with simple coders
+    let mut offset = SIGNATURE_HEADER_SIZE + pack_pos;
+    for (folder, size) in folders.iter_mut().zip(pack_sizes) {
+        folder.pack_offset = offset;
+        folder.pack_size = size;
+        offset += size;
+    }
+    Ok(folders)
+}
+
+fn parse_substreams_info(reader: &mut Reader, folders: &mut [Folder]) -> Result<(), Error> {
+    let mut streams = vec![1; folders.len()];
+    let mut first_sizes: Vec<Option<usize>> = folders
+        .iter()
+        .map(|folder| folder.unpack_sizes.last().copied())
+        .collect();
+
+    let mut id = reader.byte()?;
+    if id == ID_NUM_UNPACK_STREAM {
+        for count in streams.iter_mut() {
+            *count = reader.size()?;
+        }
+        id = reader.byte()?;
+    }
+    if id == ID_SIZE {
+        for (i, count) in streams.iter().enumerate() {
+            // the last size of each folder is implicit
+            for j in 1..*count {
+                let size = reader.size()?;
+                if j == 1 {
+                    first_sizes[i] = Some(size);
+                }
+            }
+            if *count == 0 {
+                first_sizes[i] = None;
+            }
+        }
+        id = reader.byte()?;
+    }
+
+    // digests of the streams not already covered by a folder crc
+    let missing: Vec<usize> = folders
+        .iter()
+        .zip(&streams)
+        .enumerate()
+        .filter(|(_, (folder, count))| !(**count == 1 && folder.crc.is_some()))
+        .map(|(i, _)| i)
+        .collect();
+    while id != ID_END {
+        if id == ID_CRC {
+            let total = missing.iter().map(|i| streams[*i]).sum();
+            let mut digests = reader.digests(total)?.into_iter();
+            for i in &missing {
+                let first = digests.next().flatten();
+                if let (Some(size), Some(crc)) = (first_sizes[*i], first) {
+                    folders[*i].first_stream = Some((size, crc));
+                }
+                for _ in 1..streams[*i] {
+                    digests.next();
+                }
+            }
+        } else {
+            reader.skip_property()?;
+        }
+        id = reader.byte()?;
+    }
+    Ok(())
+}
+
+fn password_key(password: &str, properties: &[u8]) -> Result<([u8; 32], [u8; AES_BLOCK_SIZE]), Error> {
+    let (cycles, salt, iv) = match properties {
+        [first, ..] if first & 0xc0 == 0 => (first & 0x3f, &[][..], &[][..]),
+        [first, second, rest @ ..] => {
+            let salt_size = ((first >> 7) & 1) as usize + (second >> 4) as usize;
+            let iv_size = ((first >> 6) & 1) as usize + (second & 0x0f) as usize;
+            if rest.len() < salt_size + iv_size {
+                return Err("invalid 7z aes properties".to_owned());
+            }
+            (first & 0x3f, &rest[..salt_size], &rest[salt_size..salt_size + iv_size])
+        }
+        _ => return Err("invalid 7z aes properties".to_owned()),
+    };
+
+    let password: Vec<u8> = password
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    let mut key = [0u8; 32];
+    if cycles == NO_KEY_DERIVATION {
+        for (i, b) in salt.iter().chain(password.iter()).take(32).enumerate() {
+            key[i] = *b;
+        }
+    } else {
+        let mut hasher = Sha256::new();
+        for round in 0..(1u64 << cycles) {
+            hasher.update(salt);
+            hasher.update(&password);
+            hasher.update(round.to_le_bytes());
+        }
+        key.copy_from_slice(&hasher.finalize());
+    }
+
+    let mut padded_iv = [0u8; AES_BLOCK_SIZE];
+    padded_iv[..iv.len()].copy_from_slice(iv);
+    Ok((key, padded_iv))
+}
+
+fn decrypt(key: &[u8; 32], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buffer = data.to_vec();
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+        .map_err(|e| e.to_string())?
+        .decrypt_padded_mut::<NoPadding>(&mut buffer)
+        .map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+// runs the folder coders, only aes, lzma, lzma2 and copy are supported
+fn decode_folder(folder: &Folder, packed: &[u8], password: &str) -> Result<Vec<u8>, Error> {
+    let mut data = packed.to_vec();
+    for (coder, size) in folder.coders.iter().zip(&folder.unpack_sizes) {
+        data = if coder.id == CODER_AES {
+            let (key, iv) = password_key(password, &coder.properties)?;
+            let mut plain = decrypt(&key, &iv, &data[..data.len() / AES_BLOCK_SIZE * AES_BLOCK_SIZE])?;
+            // the padding up to the aes block size is made of zeroes
+            if plain.len() < *size || plain[*size..].iter().any(|b| *b != 0) {
+                return Err("invalid padding".to_owned());
+            }
+            plain.truncate(*size);
+            plain
+        } else if coder.id == CODER_LZMA {
+            lzma::decode_lzma(&coder.properties, &data, *size)?
+        } else if coder.id == CODER_LZMA2 {
+            lzma::decode_lzma2(&data, *size)?
+        } else if coder.id == CODER_COPY {
+            data
+        } else {
+            return Err(format!("unsupported 7z coder {:02x?}", coder.id));
+        };
+    }
+    Ok(data)
+}
+
+enum Header {
+    Plain(Vec<u8>),
+    // the folder of the encrypted header
+    Encrypted(Folder),
+}
+
+// the encoded header is only compressed when it's not encrypted
+fn decode_header(data: &[u8], header: &[u8]) -> Result<Header, Error> {
+    let mut header = header.to_vec();
+    loop {
+        let mut reader = Reader::new(&header);
+        match reader.byte()? {
+            ID_HEADER => return Ok(Header::Plain(header)),
+            ID_ENCODED_HEADER => {
+                let folder = parse_streams_info(&mut reader)?
+                    .into_iter()
+                    .next()
+                    .ok_or("invalid 7z encoded header")?;
+                if folder.is_encrypted() {
+                    return Ok(Header::Encrypted(folder));
+                }
+                let packed = data
+                    .get(folder.pack_offset..folder.pack_offset + folder.pack_size)
+                    .ok_or("truncated 7z file")?;
+                header = decode_folder(&folder, packed, "")?;
+            }
+            id => return Err(format!("unexpected 7z header 0x{:02x}", id)),
+        }
+    }
+}
+
+// main streams info of a plain header
+fn parse_header(header: &[u8]) -> Result<Vec<Folder>, Error> {
+    let mut reader = Reader::new(header);
+    reader.expect(ID_HEADER)?;
+    let mut id = reader.byte()?;
+    if id == ID_ARCHIVE_PROPERTIES {
+        while reader.byte()? != ID_END {
+            reader.skip_property()?;
+        }
+        id = reader.byte()?;
+    }
+    if id == ID_ADDITIONAL_STREAMS_INFO {
+        parse_streams_info(&mut reader)?;
+        id = reader.byte()?;
+    }
+    if id == ID_MAIN_STREAMS_INFO {
+        parse_streams_info(&mut reader)
+    } else {
+        Ok(vec![])
+    }
+}
+
+pub(crate) struct SevenZipFile {
+    folder: Folder,
+    packed: Vec<u8>,
+    encrypted_header: bool,
+}
+
+impl SevenZipFile {
+    // cheap checks on the first and last blocks before decoding the whole folder
+    fn quick_check(&self, password: &str) -> Result<bool, Error> {
+        let coder = &self.folder.coders[0];
+        let (key, iv) = password_key(password, &coder.properties)?;
+        let size = self.folder.unpack_sizes[0];
+        let aligned = self.packed.len() / AES_BLOCK_SIZE * AES_BLOCK_SIZE;
+        if aligned < AES_BLOCK_SIZE || size > aligned {
+            return Err("invalid 7z encrypted stream".to_owned());
+        }
+
+        if size < aligned {
+            let last = aligned - AES_BLOCK_SIZE;
+            let iv = if last == 0 {
+                &iv[..]
+            } else {
+                &self.packed[last - AES_BLOCK_SIZE..last]
+            };
+            let block = decrypt(&key, iv, &self.packed[last..aligned])?;
+            if block[size.saturating_sub(last)..].iter().any(|b| *b != 0) {
+                return Ok(false);
+            }
+        }
+
+        let first = decrypt(&key, &iv, &self.packed[..AES_BLOCK_SIZE])?;
+        Ok(match self.folder.coders.get(1).map(|coder| &coder.id[..]) {
+            Some(CODER_LZMA) => first[0] == 0x00,
+            // uncompressed or compressed chunk with a dictionary reset
+            Some(CODER_LZMA2) => first[0] == 0x01 || first[0] >= 0xe0,
+            _ => true,
+        })
+    }
+}
+
+impl Protected for SevenZipFile {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < SIGNATURE_HEADER_SIZE || &data[..6] != SIGNATURE {
+            return Err("not a 7z file".to_owned());
+        }
+        let mut reader = Reader::new(&data[12..SIGNATURE_HEADER_SIZE]);
+        let offset = usize::try_from(u64::from_le_bytes(reader.bytes(8)?.try_into().unwrap()))
+            .map_err(|e| e.to_string())?;
+        let size = usize::try_from(u64::from_le_bytes(reader.bytes(8)?.try_into().unwrap()))
+            .map_err(|e| e.to_string())?;
+        let header = data
+            .get(SIGNATURE_HEADER_SIZE + offset..SIGNATURE_HEADER_SIZE + offset + size)
+            .ok_or("truncated 7z file")?;
+
+        let (folders, encrypted_header) = match decode_header(data, header)? {
+            Header::Plain(header) => (parse_header(&header)?, false),
+            Header::Encrypted(folder) => (vec![folder], true),
+        };
+
+        // the smallest encrypted folder is the fastest to verify
+        let folder = folders
+            .into_iter()
+            .filter(|folder| {
+                folder.is_encrypted() && folder.coders[0].id == CODER_AES && folder.pack_size > 0
+            })
+            .min_by_key(|folder| folder.pack_size)
+            .ok_or("no encrypted stream found in the 7z file")?;
+        let packed = data
+            .get(folder.pack_offset..folder.pack_offset + folder.pack_size)
+            .ok_or("truncated 7z file")?
+            .to_vec();
+
+        Ok(Self {
+            folder,
+            packed,
+            encrypted_header,
+        })
+    }
+
+    fn check(&self, password: &str) -> Option<Vec<(String, String)>> {
+        if !self.quick_check(password).unwrap_or(false) {
+            return None;
+        }
+
+        let decoded = decode_folder(&self.folder, &self.packed, password).ok()?;
+        let valid = if let Some(crc) = self.folder.crc {
+            crc32fast::hash(&decoded) == crc
+        } else if let Some((size, crc)) = self.folder.first_stream {
+            decoded.len() >= size && crc32fast::hash(&decoded[..size]) == crc
+        } else {
+            // decoding without errors is the best we can do
+            !self.encrypted_header || decoded.first() == Some(&ID_HEADER)
+        };
+
+        if valid {
+            Some(vec![(
+                "encrypted_header".to_owned(),
+                self.encrypted_header.to_string(),
+            )])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SevenZipFile;
+    use crate::plugins::archive::Protected;
+
+    #[test]
+    fn can_check_password() {
+        // aes with 2^10 key derivation rounds over lzma2, plain header
+        let data = [
+            0x37, 0x7a, 0xbc, 0xaf, 0x27, 0x1c, 0x00, 0x04, 0x2f, 0xb4, 0xd6, 0xf1, 0x20, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x37, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xde, 0xd1, 0x43, 0x5d, 0x36, 0x36, 0x32, 0x95, 0x51, 0x06, 0x86, 0x4a, 0x07, 0xde,
+            0x0b, 0xd3, 0x38, 0x4c, 0x17, 0x7d, 0xae, 0x89, 0x91, 0xe6, 0x87, 0xbf, 0x74, 0xce,
+            0x24, 0x19, 0x2a, 0x41, 0x07, 0x81, 0xb9, 0x3f, 0x01, 0x04, 0x06, 0x00, 0x01, 0x09,
+            0x20, 0x00, 0x07, 0x0b, 0x01, 0x00, 0x02, 0x21, 0x21, 0x01, 0x0c, 0x24, 0x06, 0xf1,
+            0x07, 0x01, 0x12, 0x4a, 0x0f, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+            0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x00, 0x01, 0x0c, 0x0e, 0x12, 0x0a, 0x01,
+            0xf1, 0x5b, 0xce, 0xb1, 0x00, 0x00, 0x00,
+        ];
+        let archive = SevenZipFile::parse(&data).unwrap();
+        assert_eq!(archive.folder.crc, Some(0xb1ce5bf1));
+        assert!(archive.check("wrong").is_none());
+        assert_eq!(
+            archive.check("s3cret"),
+            Some(vec![("encrypted_header".to_owned(), "false".to_owned())])
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+use crate::session::Error;
+
+use super::Protected;
+
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const MAX_COMMENT_SIZE: usize = 0xffff;
+const FLAG_ENCRYPTED: u16 = 0x0001;
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const AES_ENCRYPTED: u16 = 99;
+const AES_EXTRA_FIELD: u16 = 0x9901;
+const ZIPCRYPTO_HEADER_SIZE: usize = 12;
+const AES_VERIFIER_SIZE: usize = 2;
+const AES_AUTH_CODE_SIZE: usize = 10;
+const AES_ITERATIONS: u32 = 1000;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn le16(data: &[u8], at: usize) -> Result<u16, Error> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or("truncated zip file".to_owned())
+}
+
+fn le32(data: &[u8], at: usize) -> Result<u32, Error> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or("truncated zip file".to_owned())
+}
+
+// traditional PKWARE encryption
+struct ZipCryptoKeys([u32; 3]);
+
+impl ZipCryptoKeys {
+    fn new(password: &str) -> Self {
+        let mut keys = ZipCryptoKeys([0x12345678, 0x23456789, 0x34567890]);
+        for b in password.bytes() {
+            keys.update(b);
+        }
+        keys
+    }
+
+    fn crc32(crc: u32, b: u8) -> u32 {
+        CRC_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8)
+    }
+
+    fn update(&mut self, b: u8) {
+        self.0[0] = Self::crc32(self.0[0], b);
+        self.0[1] = self.0[1]
+            .wrapping_add(self.0[0] & 0xff)
+            .wrapping_mul(134775813)
+            .wrapping_add(1);
+        self.0[2] = Self::crc32(self.0[2], (self.0[1] >> 24) as u8);
+    }
+
+    fn decrypt(&mut self, b: u8) -> u8 {
+        let t = (self.0[2] | 2) as u16;
+        let plain = b ^ (t.wrapping_mul(t ^ 1) >> 8) as u8;
+        self.update(plain);
+        plain
+    }
+}
+
+#[derive(Debug)]
+enum Encryption {
+    ZipCrypto {
+        // high byte of the crc, or of the modification time with data descriptors
+        check_byte: u8,
+        crc: u32,
+        method: u16,
+        size: usize,
+    },
+    // WinZip AE-1 and AE-2
+    Aes {
+        key_size: usize,
+    },
+}
+
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    encryption: Encryption,
+    data: Vec<u8>,
+}
+
+impl Entry {
+    fn check_zipcrypto(&self, password: &str, check_byte: u8, crc: u32, method: u16, size: usize) -> bool {
+        let mut keys = ZipCryptoKeys::new(password);
+        let mut header = [0u8; ZIPCRYPTO_HEADER_SIZE];
+        for (i, b) in self.data[..ZIPCRYPTO_HEADER_SIZE].iter().enumerate() {
+            header[i] = keys.decrypt(*b);
+        }
+        // quick check, one in 256 wrong passwords will pass it
+        if header[ZIPCRYPTO_HEADER_SIZE - 1] != check_byte {
+            return false;
+        }
+
+        let compressed: Vec<u8> = self.data[ZIPCRYPTO_HEADER_SIZE..]
+            .iter()
+            .map(|b| keys.decrypt(*b))
+            .collect();
+        let mut plain = Vec::with_capacity(size);
+        let decompressed = match method {
+            STORED => {
+                plain = compressed;
+                true
+            }
+            _ => DeflateDecoder::new(&compressed[..])
+                .take(size as u64 + 1)
+                .read_to_end(&mut plain)
+                .is_ok(),
+        };
+        decompressed && plain.len() == size && crc32fast::hash(&plain) == crc
+    }
+
+    fn check_aes(&self, password: &str, key_size: usize) -> bool {
+        let salt_size = key_size / 2;
+        let salt = &self.data[..salt_size];
+        let verifier = &self.data[salt_size..salt_size + AES_VERIFIER_SIZE];
+        let encrypted = &self.data[salt_size + AES_VERIFIER_SIZE..self.data.len() - AES_AUTH_CODE_SIZE];
+        let auth_code = &self.data[self.data.len() - AES_AUTH_CODE_SIZE..];
+
+        let mut derived = vec![0u8; 2 * key_size + AES_VERIFIER_SIZE];
+        pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), salt, AES_ITERATIONS, &mut derived);
+        // quick check, one in 65536 wrong passwords will pass it
+        if &derived[2 * key_size..] != verifier {
+            return false;
+        }
+
+        // authentication code of the encrypted data
+        let mut mac = Hmac::<Sha1>::new_from_slice(&derived[key_size..2 * key_size]).unwrap();
+        mac.update(encrypted);
+        &mac.finalize().into_bytes()[..AES_AUTH_CODE_SIZE] == auth_code
+    }
+}
+
+// WinZip AES extra field strength and compression method
+fn parse_aes_extra(extra: &[u8]) -> Option<(usize, u16)> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let id = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let size = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        let field = extra.get(pos + 4..pos + 4 + size)?;
+        if id == AES_EXTRA_FIELD && size >= 7 && &field[2..4] == b"AE" {
+            let key_size = match field[4] {
+                1 => 16,
+                2 => 24,
+                3 => 32,
+                _ => return None,
+            };
+            return Some((key_size, u16::from_le_bytes([field[5], field[6]])));
+        }
+        pos += 4 + size;
+    }
+    None
+}
+
+pub(crate) struct ZipFile {
+    entry: Entry,
+}
+
+impl Protected for ZipFile {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let search_from = data.len().saturating_sub(22 + MAX_COMMENT_SIZE);
+        let eocd = (search_from..data.len().saturating_sub(21))
+            .rev()
+            .find(|at| le32(data, *at).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or("not a zip file")?;
+        let entries = le16(data, eocd + 10)?;
+        let mut pos = le32(data, eocd + 16)? as usize;
+
+        // the smallest encrypted entry is the fastest to verify
+        let mut best: Option<Entry> = None;
+        for _ in 0..entries {
+            if le32(data, pos)? != CENTRAL_HEADER {
+                return Err("invalid zip central directory".to_owned());
+            }
+            let flags = le16(data, pos + 8)?;
+            let method = le16(data, pos + 10)?;
+            let mod_time = le16(data, pos + 12)?;
+            let crc = le32(data, pos + 16)?;
+            let compressed_size = le32(data, pos + 20)? as usize;
+            let size = le32(data, pos + 24)? as usize;
+            let name_len = le16(data, pos + 28)? as usize;
+            let extra_len = le16(data, pos + 30)? as usize;
+            let comment_len = le16(data, pos + 32)? as usize;
+            let offset = le32(data, pos + 42)? as usize;
+            let name = data
+                .get(pos + 46..pos + 46 + name_len)
+                .map(|name| String::from_utf8_lossy(name).to_string())
+                .ok_or("truncated zip file")?;
+            let extra = data
+                .get(pos + 46 + name_len..pos + 46 + name_len + extra_len)
+                .ok_or("truncated zip file")?;
+            pos += 46 + name_len + extra_len + comment_len;
+
+            if flags & FLAG_ENCRYPTED == 0 || flags & FLAG_STRONG_ENCRYPTION != 0 {
+                continue;
+            }
+            let encryption = if method == AES_ENCRYPTED {
+                let Some((key_size, _)) = parse_aes_extra(extra) else {
+                    continue;
+                };
+                if compressed_size < key_size / 2 + AES_VERIFIER_SIZE + AES_AUTH_CODE_SIZE {
+                    continue;
+                }
+                Encryption::Aes { key_size }
+            } else if (method == STORED || method == DEFLATED)
+                && compressed_size >= ZIPCRYPTO_HEADER_SIZE
+            {
+                Encryption::ZipCrypto {
+                    check_byte: if flags & FLAG_DATA_DESCRIPTOR != 0 {
+                        (mod_time >> 8) as u8
+                    } else {
+                        (crc >> 24) as u8
+                    },
+                    crc,
+                    method,
+                    size,
+                }
+            } else {
+                continue;
+            };
+
+            if best
+                .as_ref()
+                .map(|best| compressed_size >= best.data.len())
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            let start = offset + 30 + le16(data, offset + 26)? as usize + le16(data, offset + 28)? as usize;
+            let entry_data = data
+                .get(start..start + compressed_size)
+                .ok_or("truncated zip file")?
+                .to_vec();
+            best = Some(Entry {
+                name,
+                encryption,
+                data: entry_data,
+            });
+        }
+
+        match best {
+            Some(entry) => Ok(Self { entry }),
+            None => Err("no supported encrypted entry found in the zip file".to_owned()),
+        }
+    }
+
+    fn check(&self, password: &str) -> Option<Vec<(String, String)>> {
+        let (valid, encryption) = match self.entry.encryption {
+            Encryption::ZipCrypto {
+                check_byte,
+                crc,
+                method,
+                size,
+            } => (
+                self.entry
+                    .check_zipcrypto(password, check_byte, crc, method, size),
+                "ZipCrypto".to_owned(),
+            ),
+            Encryption::Aes { key_size } => (
+                self.entry.check_aes(password, key_size),
+                format!("AES-{}", key_size * 8),
+            ),
+        };
+        if valid {
+            Some(vec![
+                ("entry".to_owned(), self.entry.name.to_owned()),
+                ("encryption".to_owned(), encryption),
+            ])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encryption, Entry, ZipFile};
+    use crate::plugins::archive::Protected;
+
+    #[test]
+    fn can_check_zipcrypto_password() {
+        // zip -P s3cret, stored with a data descriptor
+        let data = [
+  0x50, 0x4b, 0x03, 0x04, 0x0a, 0x00, 0x09, 0x00, 0x00, 0x00, 0xf6, 0x56,
+  0x4f, 0x5d, 0x2e, 0xef, 0xfe, 0xb6, 0x11, 0x00, 0x00, 0x00, 0x05, 0x00,
+  0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x71,
+  0xf9, 0xa3, 0xd1, 0x36, 0x05, 0x10, 0xfe, 0x66, 0x8e, 0xec, 0xdd, 0x4d,
+  0xed, 0xcd, 0xef, 0x73, 0x50, 0x4b, 0x07, 0x08, 0x2e, 0xef, 0xfe, 0xb6,
+  0x11, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x50, 0x4b, 0x01, 0x02,
+  0x1e, 0x03, 0x0a, 0x00, 0x09, 0x00, 0x00, 0x00, 0xf6, 0x56, 0x4f, 0x5d,
+  0x2e, 0xef, 0xfe, 0xb6, 0x11, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+  0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+  0xa4, 0x81, 0x00, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x74, 0x78, 0x74, 0x50,
+  0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x33,
+  0x00, 0x00, 0x00, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00
+        ];
+        let zip = ZipFile::parse(&data).unwrap();
+        assert_eq!(zip.entry.name, "a.txt");
+        assert!(zip.check("wrong").is_none());
+        assert_eq!(
+            zip.check("s3cret"),
+            Some(vec![
+                ("entry".to_owned(), "a.txt".to_owned()),
+                ("encryption".to_owned(), "ZipCrypto".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn can_check_aes_password() {
+        let entry = Entry {
+            name: "a.txt".to_owned(),
+            encryption: Encryption::Aes { key_size: 32 },
+            // salt, verifier, encrypted data and authentication code
+            data: vec![
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f, 0xda, 0x01, 0x11, 0x22, 0x33, 0x44, 0x55, 0xf4, 0x9c, 0xbb, 0x9c, 0x28,
+                0x83, 0x00, 0x23, 0x5a, 0xbf,
+            ],
+        };
+        assert!(!entry.check_aes("wrong", 32));
+        assert!(entry.check_aes("s3cret", 32));
+    }
+}
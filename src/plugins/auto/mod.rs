@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::session::{Error, Loot};
+use crate::utils::{parse_target, plugin_for_port, plugin_for_scheme, Targets};
+use crate::Options;
+use crate::Plugin;
+
+use crate::creds::Credentials;
+
+use super::manager::INVENTORY;
+use super::plugin::PayloadStrategy;
+
+super::manager::register_plugin! {
+    "auto" => Auto::new()
+}
+
+// select the plugin for the target from its uri scheme or port, returns the plugin name and the
+// target to pass to it
+fn select(target: &str) -> Option<(&'static str, String)> {
+    if let Some((scheme, _)) = target.split_once("://") {
+        let (plugin, default_port) = plugin_for_scheme(scheme)?;
+        if plugin == "http" {
+            // the http plugin needs the full url
+            Some((plugin, target.to_owned()))
+        } else {
+            let (host, port) = parse_target(target, default_port).ok()?;
+            Some((
+                plugin,
+                if host.contains(':') {
+                    format!("[{}]:{}", host, port)
+                } else {
+                    format!("{}:{}", host, port)
+                },
+            ))
+        }
+    } else {
+        let (_, port) = parse_target(target, 0).ok()?;
+        Some((plugin_for_port(port)?, target.to_owned()))
+    }
+}
+
+pub(crate) struct Auto {
+    // plugins needed by the targets, in order of appearance
+    names: Vec<&'static str>,
+    plugins: BTreeMap<&'static str, Box<dyn Plugin>>,
+}
+
+impl Auto {
+    pub fn new() -> Self {
+        Auto {
+            names: vec![],
+            plugins: BTreeMap::new(),
+        }
+    }
+
+    fn plugin_for_target(&self, target: &str) -> Option<(&dyn Plugin, String)> {
+        let (name, target) = select(target)?;
+        self.plugins
+            .get(name)
+            .map(|plugin| (plugin.as_ref(), target))
+    }
+}
+
+#[async_trait]
+impl Plugin for Auto {
+    fn description(&self) -> &'static str {
+        "Select the plugin for each target according to its URI scheme or port."
+    }
+
+    fn set_targets(&mut self, targets: &Targets) -> Result<(), Error> {
+        for target in targets.iter()? {
+            if let Some((name, _)) = select(&target.address) {
+                if !self.names.contains(&name) {
+                    self.names.push(name);
+                }
+            } else {
+                log::warn!("no plugin available for {}, skipping", target.address);
+            }
+        }
+        Ok(())
+    }
+
+    fn setup(&mut self, options: &Options) -> Result<(), Error> {
+        for name in self.names.clone() {
+            let Some(mut plugin) = INVENTORY.lock().unwrap().remove(name) else {
+                log::warn!("plugin {} is not available in this build", name);
+                continue;
+            };
+
+            if matches!(plugin.payload_strategy(), PayloadStrategy::Single) {
+                log::warn!("plugin {} does not use credentials, skipping", name);
+                continue;
+            }
+
+            if let Err(e) = plugin.setup(options) {
+                log::warn!("can't setup plugin {}: {}", name, e);
+                continue;
+            }
+
+            log::info!("using plugin {}", name);
+            self.plugins.insert(name, plugin);
+        }
+
+        if self.plugins.is_empty() {
+            Err("no plugin could be selected for the provided targets".to_owned())
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        if let Some((plugin, target)) = self.plugin_for_target(&creds.target) {
+            if target == creds.target {
+                plugin.attempt(creds, timeout).await
+            } else {
+                let mut creds = creds.clone();
+                creds.target = target;
+                plugin.attempt(&creds, timeout).await
+            }
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select;
+
+    #[test]
+    fn can_select_plugins() {
+        assert_eq!(select("10.0.0.1:22"), Some(("ssh", "10.0.0.1:22".to_owned())));
+        assert_eq!(select("ssh://10.0.0.1:2222"), Some(("ssh", "10.0.0.1:2222".to_owned())));
+        assert_eq!(select("mysql://db.local"), Some(("mysql", "db.local:3306".to_owned())));
+        assert_eq!(
+            select("https://login.corp/admin"),
+            Some(("http", "https://login.corp/admin".to_owned()))
+        );
+        assert_eq!(select("10.0.0.1:12345"), None);
+        assert_eq!(select("gopher://10.0.0.1"), None);
+    }
+}
@@ -0,0 +1,302 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const MAX_OUTPUT: usize = 64 * 1024;
+const DENIALS: &[&str] = &[
+    "% access denied",
+    "% bad secrets",
+    "% bad passwords",
+    "% login invalid",
+    "% authentication failed",
+    "% no password set",
+];
+
+const IAC: u8 = 255;
+const WILL: u8 = 251;
+const WONT: u8 = 252;
+const DO: u8 = 253;
+const DONT: u8 = 254;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Prompt {
+    Username,
+    Password,
+    // user exec mode, with the device hostname
+    User(String),
+    // privileged exec mode
+    Privileged(String),
+    Denied,
+}
+
+// state of the command line given its output so far
+pub(crate) fn prompt(output: &str) -> Option<Prompt> {
+    let lower = output.to_lowercase();
+    if DENIALS.iter().any(|denial| lower.contains(denial)) {
+        return Some(Prompt::Denied);
+    }
+
+    let last = output.lines().last().unwrap_or_default().trim();
+    let lower = last.to_lowercase();
+    if lower.ends_with("username:") || lower.ends_with("login:") {
+        Some(Prompt::Username)
+    } else if lower.ends_with("password:") {
+        Some(Prompt::Password)
+    } else if last.len() > 1 && !last.contains(' ') {
+        if let Some(hostname) = last.strip_suffix('>') {
+            Some(Prompt::User(hostname.to_owned()))
+        } else {
+            last.strip_suffix('#')
+                .map(|hostname| Prompt::Privileged(hostname.to_owned()))
+        }
+    } else {
+        None
+    }
+}
+
+// software description and version from the show version output
+pub(crate) fn parse_version(output: &str) -> Vec<(String, String)> {
+    let mut info = vec![];
+    if let Some(line) = output.lines().map(|l| l.trim()).find(|l| {
+        l.starts_with("Cisco") && (l.contains("Software") || l.contains("Operating System"))
+    }) {
+        let software = line.split(',').next().unwrap_or(line).trim();
+        info.push(("software".to_owned(), software.to_owned()));
+        if let Some((_, version)) = line.split_once("Version ") {
+            let version = version
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .next()
+                .unwrap_or_default();
+            info.push(("version".to_owned(), version.to_owned()));
+        }
+    }
+    info
+}
+
+pub(crate) struct Cli<S> {
+    stream: S,
+    telnet: bool,
+    // received bytes, including incomplete telnet commands
+    raw: Vec<u8>,
+    output: String,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Cli<S> {
+    pub fn new(stream: S, telnet: bool) -> Self {
+        Self {
+            stream,
+            telnet,
+            raw: vec![],
+            output: String::new(),
+        }
+    }
+
+    // strips the telnet commands from the received data, refusing every option
+    // but echo and suppress go ahead, returns the replies to send
+    fn negotiate(&mut self) -> Vec<u8> {
+        let mut data = vec![];
+        let mut replies = vec![];
+        let mut i = 0;
+        while i < self.raw.len() {
+            let byte = self.raw[i];
+            if !self.telnet || byte != IAC {
+                data.push(byte);
+                i += 1;
+                continue;
+            }
+
+            let Some(&command) = self.raw.get(i + 1) else {
+                break;
+            };
+            match command {
+                IAC => {
+                    data.push(IAC);
+                    i += 2;
+                }
+                WILL | WONT | DO | DONT => {
+                    let Some(&option) = self.raw.get(i + 2) else {
+                        break;
+                    };
+                    match command {
+                        WILL if option == ECHO || option == SUPPRESS_GO_AHEAD => {
+                            replies.extend([IAC, DO, option])
+                        }
+                        WILL => replies.extend([IAC, DONT, option]),
+                        DO => replies.extend([IAC, WONT, option]),
+                        _ => {}
+                    }
+                    i += 3;
+                }
+                SB => match self.raw[i..].windows(2).position(|w| w == [IAC, SE]) {
+                    Some(end) => i += end + 2,
+                    None => break,
+                },
+                _ => i += 2,
+            }
+        }
+
+        self.raw.drain(..i);
+        self.output.push_str(&String::from_utf8_lossy(&data));
+        replies
+    }
+
+    async fn read_more(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; 1024];
+        let read = self
+            .stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_owned());
+        }
+        self.raw.extend_from_slice(&buf[..read]);
+        let replies = self.negotiate();
+        if !replies.is_empty() {
+            self.stream
+                .write_all(&replies)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        if self.output.len() > MAX_OUTPUT {
+            return Err("too much output without a prompt".to_owned());
+        }
+        Ok(())
+    }
+
+    async fn read_prompt(&mut self) -> Result<Prompt, Error> {
+        loop {
+            if let Some(prompt) = prompt(&self.output) {
+                self.output.clear();
+                return Ok(prompt);
+            }
+            self.read_more().await?;
+        }
+    }
+
+    // reads up to the next exec prompt and returns the output before it
+    async fn read_output(&mut self) -> Result<String, Error> {
+        loop {
+            if let Some(Prompt::User(_) | Prompt::Privileged(_)) = prompt(&self.output) {
+                return Ok(std::mem::take(&mut self.output));
+            }
+            self.read_more().await?;
+        }
+    }
+
+    async fn send(&mut self, line: &str) -> Result<(), Error> {
+        let eol = if self.telnet { "\r\n" } else { "\n" };
+        self.stream
+            .write_all(format!("{}{}", line, eol).as_bytes())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // answers the login prompts, if any, and returns the hostname and whether the
+    // session is already privileged on success
+    pub async fn login(
+        &mut self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<(String, bool)>, Error> {
+        let (mut sent_username, mut sent_password) = (false, false);
+        loop {
+            match self.read_prompt().await? {
+                Prompt::Username if !sent_username => {
+                    self.send(username).await?;
+                    sent_username = true;
+                }
+                Prompt::Password if !sent_password => {
+                    self.send(password).await?;
+                    sent_password = true;
+                }
+                Prompt::User(hostname) => return Ok(Some((hostname, false))),
+                Prompt::Privileged(hostname) => return Ok(Some((hostname, true))),
+                // asked again
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    // returns true if the password grants the privileged exec mode
+    pub async fn enable(&mut self, password: &str) -> Result<bool, Error> {
+        self.send("enable").await?;
+        match self.read_prompt().await? {
+            Prompt::Password => {
+                self.send(password).await?;
+                Ok(matches!(self.read_prompt().await?, Prompt::Privileged(_)))
+            }
+            Prompt::Privileged(_) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    pub async fn show_version(&mut self) -> Result<Vec<(String, String)>, Error> {
+        self.send("terminal length 0").await?;
+        self.read_output().await?;
+        self.send("show version").await?;
+        Ok(parse_version(&self.read_output().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_version, prompt, Cli, Prompt};
+
+    #[test]
+    fn can_detect_prompts() {
+        assert_eq!(prompt("\r\nUser Access Verification\r\n\r\nUsername: "), Some(Prompt::Username));
+        assert_eq!(prompt("Password: "), Some(Prompt::Password));
+        assert_eq!(prompt("\r\nR1>"), Some(Prompt::User("R1".to_owned())));
+        assert_eq!(prompt("\r\nR1#"), Some(Prompt::Privileged("R1".to_owned())));
+        assert_eq!(prompt("% Access denied\r\n\r\nR1>"), Some(Prompt::Denied));
+        assert_eq!(prompt("Cisco IOS Software, "), None);
+        assert_eq!(
+            parse_version(
+                "\r\nCisco IOS Software, C2900 Software (C2900-UNIVERSALK9-M), Version 15.2(4)M7, RELEASE SOFTWARE (fc2)\r\nROM: System Bootstrap\r\n"
+            ),
+            vec![
+                ("software".to_owned(), "Cisco IOS Software".to_owned()),
+                ("version".to_owned(), "15.2(4)M7".to_owned())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn can_login_and_enable_over_telnet() {
+        let stream = tokio_test::io::Builder::new()
+            // will echo, will suppress go ahead, do terminal type
+            .read(&[255, 251, 1, 255, 251, 3, 255, 253, 24])
+            .write(&[255, 253, 1, 255, 253, 3, 255, 252, 24])
+            .read(b"\r\nUser Access Verification\r\n\r\nUsername: ")
+            .write(b"admin\r\n")
+            .read(b"Password: ")
+            .write(b"cisco\r\n")
+            .read(b"\r\nR1>")
+            .write(b"enable\r\n")
+            .read(b"Password: ")
+            .write(b"class\r\n")
+            .read(b"\r\n% Access denied\r\n\r\nR1>")
+            .build();
+        let mut cli = Cli::new(stream, true);
+        assert_eq!(
+            cli.login("admin", "cisco").await,
+            Ok(Some(("R1".to_owned(), false)))
+        );
+        assert_eq!(cli.enable("class").await, Ok(false));
+
+        let stream = tokio_test::io::Builder::new()
+            .read(b"\r\nUsername: ")
+            .write(b"admin\r\n")
+            .read(b"Password: ")
+            .write(b"wrong\r\n")
+            .read(b"\r\n% Login invalid\r\n\r\nUsername: ")
+            .build();
+        let mut cli = Cli::new(stream, true);
+        assert_eq!(cli.login("admin", "wrong").await, Ok(None));
+    }
+}
@@ -0,0 +1,144 @@
+use async_ssh2_tokio::client::{AuthMethod, Client, ServerCheckMethod};
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use cli::Cli;
+use options::Transport;
+
+pub(crate) mod cli;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "cisco" => Cisco::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct Cisco {
+    opts: options::Options,
+}
+
+impl Cisco {
+    pub fn new() -> Self {
+        Cisco {
+            opts: options::Options::default(),
+        }
+    }
+
+    // logs in, checks the enable password and collects the device details
+    async fn session<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        cli: &mut Cli<S>,
+        creds: &Credentials,
+    ) -> Result<Option<Vec<(String, String)>>, Error> {
+        let Some((hostname, mut privileged)) =
+            cli.login(&creds.username, &creds.password).await?
+        else {
+            return Ok(None);
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+            ("hostname".to_owned(), hostname),
+        ];
+
+        if !privileged {
+            let enable = self.opts.cisco_enable.as_ref().unwrap_or(&creds.password);
+            if cli.enable(enable).await? {
+                data.push(("enable".to_owned(), enable.to_owned()));
+                privileged = true;
+            }
+        }
+        data.push((
+            "privileged".to_owned(),
+            if privileged { "yes" } else { "no" }.to_owned(),
+        ));
+
+        match cli.show_version().await {
+            Ok(version) => data.extend(version),
+            Err(e) => log::debug!("show version failed: {}", e),
+        }
+
+        Ok(Some(data))
+    }
+}
+
+#[async_trait]
+impl Plugin for Cisco {
+    fn description(&self) -> &'static str {
+        "Cisco devices SSH and telnet authentication with enable password check."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.cisco.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let data = match self.opts.cisco_transport {
+            Transport::Telnet => {
+                let address = utils::parse_target_address(&creds.target, 23)?;
+                let stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+                let mut cli = Cli::new(stream, true);
+                tokio::time::timeout(timeout, self.session(&mut cli, creds))
+                    .await
+                    .map_err(|e| e.to_string())??
+                    .map(|data| (address, data))
+            }
+            Transport::Ssh => {
+                let address = utils::parse_target_address(&creds.target, 22)?;
+                let client = match tokio::time::timeout(
+                    timeout,
+                    Client::connect(
+                        address.clone(),
+                        &creds.username,
+                        AuthMethod::with_password(&creds.password),
+                        ServerCheckMethod::NoCheck,
+                    ),
+                )
+                .await
+                .map_err(|e| e.to_string())?
+                {
+                    Ok(client) => client,
+                    Err(
+                        async_ssh2_tokio::Error::PasswordWrong
+                        | async_ssh2_tokio::Error::KeyAuthFailed,
+                    ) => return Ok(None),
+                    Err(e) => return Err(e.to_string()),
+                };
+
+                // the enable command needs an interactive shell
+                let channel = client.get_channel().await.map_err(|e| e.to_string())?;
+                channel
+                    .request_pty(false, "vt100", 200, 24, 0, 0, &[])
+                    .await
+                    .map_err(|e| e.to_string())?;
+                channel
+                    .request_shell(false)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let mut cli = Cli::new(channel.into_stream(), false);
+                let data = tokio::time::timeout(timeout, self.session(&mut cli, creds))
+                    .await
+                    .map_err(|e| e.to_string())??
+                    .ok_or("unexpected login prompt after ssh authentication".to_owned())?;
+                let _ = client.disconnect().await;
+                Some((address, data))
+            }
+        };
+
+        Ok(data.map(|(address, data)| vec![Loot::new("cisco", &address, data)]))
+    }
+}
@@ -0,0 +1,20 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Transport {
+    #[default]
+    Ssh,
+    Telnet,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, value_enum, default_value_t = Transport::Ssh)]
+    /// Protocol used to reach the device command line.
+    pub cisco_transport: Transport,
+    #[clap(long)]
+    /// Enable password to try after login (defaults to the login password).
+    pub cisco_enable: Option<String>,
+}
@@ -58,6 +58,10 @@ impl Plugin for Command {
         "Command execution."
     }
 
+    fn requires_network(&self) -> bool {
+        false
+    }
+
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.opts = opts.cmd.clone();
         if self.opts.cmd_binary.is_empty() {
@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+use crate::utils::once::OncePerTarget;
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "consul" => Consul::new()
+}
+
+const TOKEN_HEADER: &str = "X-Consul-Token";
+
+// datacenter and version from the /v1/agent/self endpoint
+fn parse_agent(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    for (pointer, name) in [
+        ("/Config/Datacenter", "datacenter"),
+        ("/Config/NodeName", "node"),
+        ("/Config/Version", "version"),
+    ] {
+        if let Some(value) = json.pointer(pointer).and_then(|v| v.as_str()) {
+            info.push((name.to_owned(), value.to_owned()));
+        }
+    }
+    info
+}
+
+// description and policies of the token from the /v1/acl/token/self endpoint
+fn parse_token(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    if let Some(description) = json
+        .get("Description")
+        .and_then(|v| v.as_str())
+        .filter(|d| !d.is_empty())
+    {
+        info.push(("description".to_owned(), description.to_owned()));
+    }
+    if let Some(policies) = json.get("Policies").and_then(|v| v.as_array()) {
+        let names: Vec<&str> = policies
+            .iter()
+            .filter_map(|p| p.get("Name").and_then(|n| n.as_str()))
+            .collect();
+        if !names.is_empty() {
+            info.push(("policies".to_owned(), names.join(", ")));
+        }
+    }
+    info
+}
+
+#[derive(Clone)]
+pub(crate) struct Consul {
+    ssl: bool,
+    client: Client,
+    // targets already checked for unauthenticated access
+    open_checked: OncePerTarget,
+    // targets with ACLs disabled
+    open: OncePerTarget,
+}
+
+impl Consul {
+    pub fn new() -> Self {
+        Consul {
+            ssl: false,
+            client: Client::new(),
+            open_checked: OncePerTarget::new(),
+            open: OncePerTarget::new(),
+        }
+    }
+
+    fn request(&self, url: String, timeout: Duration) -> RequestBuilder {
+        self.client.get(url).timeout(timeout)
+    }
+
+    // returns the agent information if it's readable without a token
+    async fn check_open(
+        &self,
+        base: &str,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<Option<Loot>, Error> {
+        let response = self
+            .request(format!("{}/v1/agent/self", base), timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+        let agent = parse_agent(&response.text().await.unwrap_or_default());
+
+        let response = self
+            .request(format!("{}/v1/acl/token/self", base), timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let acl = if response
+            .text()
+            .await
+            .unwrap_or_default()
+            .contains("ACL support disabled")
+        {
+            "disabled"
+        } else {
+            // anonymous token with a permissive policy
+            "anonymous"
+        };
+
+        let mut data = vec![
+            ("authentication".to_owned(), "none".to_owned()),
+            ("acl".to_owned(), acl.to_owned()),
+        ];
+        data.extend(agent);
+        Ok(Some(Loot::new("consul", address, data)))
+    }
+}
+
+#[async_trait]
+impl Plugin for Consul {
+    fn description(&self) -> &'static str {
+        "Consul ACL token authentication and open agent detection."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.ssl = opts.consul.consul_ssl;
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8500)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.ssl || port == 8501 || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        if !self.open_checked.contains(&address) {
+            let loot = self.check_open(&base, &address, timeout).await?;
+            // marked only once the check completed, so transient errors are checked again, and
+            // reported by the first worker completing it
+            let first = self.open_checked.insert(&address);
+            if let Some(loot) = loot.filter(|_| first) {
+                if loot.get_value("acl") == Some("disabled") {
+                    self.open.insert(&address);
+                }
+                return Ok(Some(vec![loot]));
+            }
+        }
+
+        if self.open.contains(&address) {
+            // every token is accepted
+            return Ok(None);
+        }
+
+        let token = creds.single();
+        let response = self
+            .request(format!("{}/v1/acl/token/self", &base), timeout)
+            .header(TOKEN_HEADER, token)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+
+        let mut data = vec![("token".to_owned(), token.to_owned())];
+        data.extend(parse_token(&response.text().await.unwrap_or_default()));
+        Ok(Some(vec![Loot::new("consul", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_agent, parse_token};
+
+    #[test]
+    fn can_parse_agent() {
+        assert_eq!(
+            parse_agent(
+                r#"{"Config":{"Datacenter":"dc1","NodeName":"consul-0","Revision":"x","Version":"1.17.0"},"Member":{}}"#
+            ),
+            vec![
+                ("datacenter".to_owned(), "dc1".to_owned()),
+                ("node".to_owned(), "consul-0".to_owned()),
+                ("version".to_owned(), "1.17.0".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_token() {
+        assert_eq!(
+            parse_token(
+                r#"{"AccessorID":"6a1253d2","SecretID":"45a3bd52","Description":"Bootstrap Token (Global Management)","Policies":[{"ID":"00000000-0000-0000-0000-000000000001","Name":"global-management"}],"Local":false}"#
+            ),
+            vec![
+                (
+                    "description".to_owned(),
+                    "Bootstrap Token (Global Management)".to_owned()
+                ),
+                ("policies".to_owned(), "global-management".to_owned()),
+            ]
+        );
+    }
+}
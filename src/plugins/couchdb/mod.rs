@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "couchdb" => CouchDB::new()
+}
+
+// user roles from a _session response, either top level (login) or in the userCtx (GET)
+fn parse_roles(body: &str) -> Option<Vec<String>> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    let roles = json
+        .pointer("/userCtx/roles")
+        .or_else(|| json.get("roles"))?
+        .as_array()?;
+    Some(
+        roles
+            .iter()
+            .filter_map(|r| r.as_str().map(|r| r.to_owned()))
+            .collect(),
+    )
+}
+
+#[derive(Clone)]
+pub(crate) struct CouchDB {
+    opts: options::Options,
+    client: Client,
+    // targets already checked for the admin party mode
+    admin_party_checked: OncePerTarget,
+    // targets in admin party mode
+    admin_party: OncePerTarget,
+}
+
+impl CouchDB {
+    pub fn new() -> Self {
+        CouchDB {
+            opts: options::Options::default(),
+            client: Client::new(),
+            admin_party_checked: OncePerTarget::new(),
+            admin_party: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for CouchDB {
+    fn description(&self) -> &'static str {
+        "CouchDB password authentication and admin party detection."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.couchdb.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 5984)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.couchdb_ssl || port == 6984 || creds.target.starts_with("https://");
+        let url = format!(
+            "{}://{}/_session",
+            if ssl { "https" } else { "http" },
+            &address
+        );
+
+        if !self.admin_party_checked.contains(&address) {
+            let response = self
+                .client
+                .get(&url)
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let roles = parse_roles(&response.text().await.unwrap_or_default());
+            let loot = if roles.is_some_and(|roles| roles.iter().any(|r| r == "_admin")) {
+                self.admin_party.report(
+                    &address,
+                    Loot::new(
+                        "couchdb",
+                        &address,
+                        [("authentication".to_owned(), "admin party".to_owned())],
+                    ),
+                )
+            } else {
+                None
+            };
+            // marked only once the check completed, so transient errors are checked again
+            self.admin_party_checked.insert(&address);
+            if loot.is_some() {
+                return Ok(loot);
+            }
+        }
+
+        if self.admin_party.contains(&address) {
+            // everyone is an admin, no point in trying credentials
+            return Ok(None);
+        }
+
+        let request = if self.opts.couchdb_basic_auth {
+            self.client
+                .get(&url)
+                .basic_auth(&creds.username, Some(&creds.password))
+        } else {
+            self.client.post(&url).form(&[
+                ("name", creds.username.as_str()),
+                ("password", creds.password.as_str()),
+            ])
+        };
+
+        let response = request
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+
+        let roles = parse_roles(&response.text().await.unwrap_or_default()).unwrap_or_default();
+        // with basic auth _session returns 200 even for anonymous users
+        if self.opts.couchdb_basic_auth && roles.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(vec![Loot::new(
+            "couchdb",
+            &address,
+            [
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                (
+                    "admin".to_owned(),
+                    if roles.iter().any(|r| r == "_admin") {
+                        "yes"
+                    } else {
+                        "no"
+                    }
+                    .to_owned(),
+                ),
+                ("roles".to_owned(), roles.join(", ")),
+            ],
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_roles, CouchDB};
+    use crate::creds::Credentials;
+    use crate::utils::testing::http_server;
+    use crate::Plugin;
+
+    #[tokio::test]
+    async fn checks_admin_party_again_after_errors() {
+        let session = r#"{"ok":true,"userCtx":{"name":null,"roles":["_admin"]}}"#;
+        // the first probe fails, the second one finds the admin party
+        let target = http_server(vec![None, Some((200, session))]).await;
+        let couchdb = CouchDB::new();
+        let creds = Credentials {
+            target,
+            username: "admin".to_owned(),
+            password: "admin".to_owned(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        assert!(couchdb.attempt(&creds, timeout).await.is_err());
+        let loot = couchdb.attempt(&creds, timeout).await.unwrap().unwrap();
+        assert_eq!(loot[0].get_value("authentication"), Some("admin party"));
+        // reported once, then credentials are pointless
+        assert_eq!(couchdb.attempt(&creds, timeout).await, Ok(None));
+    }
+
+    #[test]
+    fn can_parse_session_roles() {
+        assert_eq!(
+            parse_roles(r#"{"ok":true,"name":"admin","roles":["_admin"]}"#),
+            Some(vec!["_admin".to_owned()])
+        );
+        assert_eq!(
+            parse_roles(
+                r#"{"ok":true,"userCtx":{"name":null,"roles":[]},"info":{"authentication_handlers":["cookie","default"]}}"#
+            ),
+            Some(vec![])
+        );
+        assert_eq!(parse_roles("Unauthorized"), None);
+    }
+}
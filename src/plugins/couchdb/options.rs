@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for CouchDB (automatically enabled for https:// targets and port 6984).
+    pub couchdb_ssl: bool,
+    #[clap(long, default_value_t = false)]
+    /// Authenticate with basic authentication instead of the _session endpoint.
+    pub couchdb_basic_auth: bool,
+}
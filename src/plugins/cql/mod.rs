@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+use crate::creds::Credentials;
+use crate::utils::once::OncePerTarget;
+
+pub(crate) mod options;
+
+const PROTOCOL_VERSION: u8 = 0x04;
+
+const OPCODE_ERROR: u8 = 0x00;
+const OPCODE_STARTUP: u8 = 0x01;
+const OPCODE_READY: u8 = 0x02;
+const OPCODE_AUTHENTICATE: u8 = 0x03;
+const OPCODE_AUTH_RESPONSE: u8 = 0x0f;
+const OPCODE_AUTH_CHALLENGE: u8 = 0x0e;
+const OPCODE_AUTH_SUCCESS: u8 = 0x10;
+
+const ERROR_BAD_CREDENTIALS: u32 = 0x0100;
+
+super::manager::register_plugin! {
+    "cql" => Cql::new()
+}
+
+fn frame(opcode: u8, body: &[u8]) -> Vec<u8> {
+    [
+        &[PROTOCOL_VERSION, 0x00, 0x00, 0x00, opcode][..],
+        &(body.len() as u32).to_be_bytes(),
+        body,
+    ]
+    .concat()
+}
+
+fn startup() -> Vec<u8> {
+    // [string map] with the CQL_VERSION only
+    let mut body = vec![0x00, 0x01];
+    for value in ["CQL_VERSION", "3.0.0"] {
+        body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        body.extend_from_slice(value.as_bytes());
+    }
+    frame(OPCODE_STARTUP, &body)
+}
+
+// PasswordAuthenticator SASL PLAIN token as [bytes]
+fn auth_response(username: &str, password: &str) -> Vec<u8> {
+    let token = [&[0], username.as_bytes(), &[0], password.as_bytes()].concat();
+    frame(
+        OPCODE_AUTH_RESPONSE,
+        &[&(token.len() as u32).to_be_bytes(), &token[..]].concat(),
+    )
+}
+
+// opcode and body of a response frame
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(u8, Vec<u8>), Error> {
+    let mut header = [0u8; 9];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    if header[0] & 0x7f != PROTOCOL_VERSION {
+        return Err(format!("unsupported protocol version 0x{:02x}", header[0]));
+    }
+
+    let len = u32::from_be_bytes(header[5..9].try_into().unwrap()) as usize;
+    let mut body = vec![0u8; len.min(1024 * 1024)];
+    stream
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((header[4], body))
+}
+
+// error code and message of an ERROR frame
+fn parse_error(body: &[u8]) -> (u32, String) {
+    if body.len() < 6 {
+        return (0, String::new());
+    }
+    let code = u32::from_be_bytes(body[..4].try_into().unwrap());
+    let len = u16::from_be_bytes([body[4], body[5]]) as usize;
+    let message = String::from_utf8_lossy(&body[6..(6 + len).min(body.len())]).to_string();
+    (code, message)
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    NoAuth,
+    Valid,
+    Invalid,
+}
+
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    stream
+        .write_all(&startup())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match read_frame(stream).await? {
+        (OPCODE_READY, _) => return Ok(Outcome::NoAuth),
+        (OPCODE_AUTHENTICATE, _) => {}
+        (OPCODE_ERROR, body) => return Err(parse_error(&body).1),
+        (opcode, _) => return Err(format!("unexpected opcode 0x{:02x}", opcode)),
+    }
+
+    stream
+        .write_all(&auth_response(username, password))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match read_frame(stream).await? {
+        (OPCODE_AUTH_SUCCESS, _) => Ok(Outcome::Valid),
+        (OPCODE_AUTH_CHALLENGE, _) => Err("unsupported authenticator challenge".to_owned()),
+        (OPCODE_ERROR, body) => {
+            let (code, message) = parse_error(&body);
+            if code == ERROR_BAD_CREDENTIALS {
+                Ok(Outcome::Invalid)
+            } else {
+                Err(message)
+            }
+        }
+        (opcode, _) => Err(format!("unexpected opcode 0x{:02x}", opcode)),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Cql {
+    ssl: bool,
+    // targets not requiring authentication, reported only once
+    no_auth: OncePerTarget,
+}
+
+impl Cql {
+    pub fn new() -> Self {
+        Cql {
+            ssl: false,
+            no_auth: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Cql {
+    fn description(&self) -> &'static str {
+        "Cassandra / ScyllaDB CQL native protocol password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.ssl = opts.cql.cql_ssl;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 9042)?;
+        if self.no_auth.contains(&address) {
+            return Ok(None);
+        }
+
+        let mut stream = crate::utils::net::async_tcp_stream(&address, timeout, self.ssl).await?;
+        let outcome = tokio::time::timeout(
+            timeout,
+            authenticate(&mut stream, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        match outcome {
+            Outcome::Valid => Ok(Some(vec![Loot::new(
+                "cql",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ],
+            )])),
+            Outcome::NoAuth => Ok(self.no_auth.report(
+                &address,
+                Loot::new(
+                    "cql",
+                    &address,
+                    [("authentication".to_owned(), "none".to_owned())],
+                ),
+            )),
+            Outcome::Invalid => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auth_response, authenticate, frame, startup, Outcome};
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let error = [
+            &[0x00, 0x00, 0x01, 0x00, 0x00, 0x03][..],
+            b"bad",
+        ]
+        .concat();
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&startup())
+            .read(&frame(0x03, b"\x00\x01x"))
+            .write(&auth_response("cassandra", "cassandra"))
+            .read(&frame(0x10, b"\xff\xff\xff\xff"))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "cassandra", "cassandra").await,
+            Ok(Outcome::Valid)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&startup())
+            .read(&frame(0x03, b"\x00\x01x"))
+            .write(&auth_response("cassandra", "wrong"))
+            .read(&frame(0x00, &error))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "cassandra", "wrong").await,
+            Ok(Outcome::Invalid)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&startup())
+            .read(&frame(0x02, b""))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "cassandra", "cassandra").await,
+            Ok(Outcome::NoAuth)
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+// commands
+const EXCSAT: u16 = 0x1041;
+const ACCSEC: u16 = 0x106d;
+const SECCHK: u16 = 0x106e;
+
+// replies
+const EXCSATRD: u16 = 0x1443;
+const ACCSECRD: u16 = 0x14ac;
+const SECCHKRM: u16 = 0x1219;
+const RDBNFNRM: u16 = 0x2211;
+
+// parameters
+const EXTNAM: u16 = 0x115e;
+const SRVNAM: u16 = 0x116d;
+const SRVCLSNM: u16 = 0x1147;
+const SRVRLSLV: u16 = 0x115a;
+const MGRLVLLS: u16 = 0x1404;
+const SECMEC: u16 = 0x11a2;
+const SECCHKCD: u16 = 0x11a4;
+const RDBNAM: u16 = 0x2110;
+const USRID: u16 = 0x11a0;
+const PASSWORD: u16 = 0x11a1;
+
+// user id and password in clear text
+const SECMEC_USRIDPWD: u16 = 3;
+// the other security mechanisms a server can require, all unsupported
+const SECMEC_NAMES: [(u16, &str); 7] = [
+    (7, "USRENCPWD"),
+    (8, "USRSBSPWD"),
+    (9, "EUSRIDPWD"),
+    (11, "KERSEC"),
+    (12, "EUSRIDDTA"),
+    (13, "EUSRPWDDTA"),
+    (15, "PLGIN"),
+];
+
+const SECCHKCD_OK: u8 = 0x00;
+const SECCHKCD_PASSWORD_EXPIRED: u8 = 0x0e;
+const SECCHKCD_PASSWORD_INVALID: u8 = 0x0f;
+const SECCHKCD_PASSWORD_MISSING: u8 = 0x10;
+const SECCHKCD_USERID_MISSING: u8 = 0x12;
+const SECCHKCD_USERID_INVALID: u8 = 0x13;
+const SECCHKCD_USERID_REVOKED: u8 = 0x14;
+
+// manager levels: AGENT, SQLAM, RDB, SECMGR and CMNTCPIP
+const MANAGER_LEVELS: [(u16, u16); 5] = [
+    (0x1403, 7),
+    (0x2407, 7),
+    (0x240f, 7),
+    (0x1440, 7),
+    (0x1474, 5),
+];
+
+const RDBNAM_SIZE: usize = 18;
+
+// code page 037 for the printable ASCII characters
+const EBCDIC: &[u8; 95] = b"\x40\x5a\x7f\x7b\x5b\x6c\x50\x7d\x4d\x5d\x5c\x4e\x6b\x60\x4b\x61\
+\xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9\x7a\x5e\x4c\x7e\x6e\x6f\
+\x7c\xc1\xc2\xc3\xc4\xc5\xc6\xc7\xc8\xc9\xd1\xd2\xd3\xd4\xd5\xd6\
+\xd7\xd8\xd9\xe2\xe3\xe4\xe5\xe6\xe7\xe8\xe9\xba\xe0\xbb\xb0\x6d\
+\x79\x81\x82\x83\x84\x85\x86\x87\x88\x89\x91\x92\x93\x94\x95\x96\
+\x97\x98\x99\xa2\xa3\xa4\xa5\xa6\xa7\xa8\xa9\xc0\x4f\xd0\xa1";
+
+fn to_ebcdic(value: &str) -> Vec<u8> {
+    value
+        .chars()
+        .map(|c| match c as u32 {
+            c @ 0x20..=0x7e => EBCDIC[(c - 0x20) as usize],
+            // SUB
+            _ => 0x3f,
+        })
+        .collect()
+}
+
+fn from_ebcdic(value: &[u8]) -> String {
+    value
+        .iter()
+        .map(|b| match EBCDIC.iter().position(|e| e == b) {
+            Some(pos) => (pos as u8 + 0x20) as char,
+            None => '?',
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // server product release level
+    Valid(Option<String>),
+    Expired(Option<String>),
+    Invalid,
+}
+
+fn param(out: &mut Vec<u8>, codepoint: u16, data: &[u8]) {
+    out.extend_from_slice(&((data.len() + 4) as u16).to_be_bytes());
+    out.extend_from_slice(&codepoint.to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+// request DSS wrapping a single DDM command
+fn request(correlation_id: u16, codepoint: u16, params: &[u8]) -> Vec<u8> {
+    let mut command = vec![];
+    param(&mut command, codepoint, params);
+
+    let mut dss = ((command.len() + 6) as u16).to_be_bytes().to_vec();
+    dss.extend_from_slice(&[0xd0, 0x01]);
+    dss.extend_from_slice(&correlation_id.to_be_bytes());
+    dss.extend(command);
+    dss
+}
+
+fn rdbnam(database: &str) -> Vec<u8> {
+    let mut name = to_ebcdic(database);
+    if name.len() < RDBNAM_SIZE {
+        name.resize(RDBNAM_SIZE, 0x40);
+    }
+    name
+}
+
+fn excsat() -> Vec<u8> {
+    let mut params = vec![];
+    param(&mut params, EXTNAM, &to_ebcdic("legba"));
+    let levels: Vec<u8> = MANAGER_LEVELS
+        .iter()
+        .flat_map(|(manager, level)| [manager.to_be_bytes(), level.to_be_bytes()].concat())
+        .collect();
+    param(&mut params, MGRLVLLS, &levels);
+    param(&mut params, SRVCLSNM, &to_ebcdic("legba"));
+    param(&mut params, SRVNAM, &to_ebcdic("legba"));
+    request(1, EXCSAT, &params)
+}
+
+fn accsec(database: &str) -> Vec<u8> {
+    let mut params = vec![];
+    param(&mut params, SECMEC, &SECMEC_USRIDPWD.to_be_bytes());
+    param(&mut params, RDBNAM, &rdbnam(database));
+    request(2, ACCSEC, &params)
+}
+
+fn secchk(database: &str, username: &str, password: &str) -> Vec<u8> {
+    let mut params = vec![];
+    param(&mut params, SECMEC, &SECMEC_USRIDPWD.to_be_bytes());
+    param(&mut params, RDBNAM, &rdbnam(database));
+    param(&mut params, USRID, &to_ebcdic(username));
+    param(&mut params, PASSWORD, &to_ebcdic(password));
+    request(3, SECCHK, &params)
+}
+
+// code point and value of each DDM parameter
+type Params = Vec<(u16, Vec<u8>)>;
+
+// splits a DDM object into its code point and parameters
+fn parse_object(data: &[u8]) -> Result<(u16, Params), Error> {
+    if data.len() < 4 {
+        return Err("truncated DDM object".to_owned());
+    }
+    let len = (u16::from_be_bytes([data[0], data[1]]) as usize).clamp(4, data.len());
+    let codepoint = u16::from_be_bytes([data[2], data[3]]);
+
+    let mut params = vec![];
+    let mut rest = &data[4..len];
+    while rest.len() >= 4 {
+        let plen = (u16::from_be_bytes([rest[0], rest[1]]) as usize).clamp(4, rest.len());
+        params.push((
+            u16::from_be_bytes([rest[2], rest[3]]),
+            rest[4..plen].to_vec(),
+        ));
+        rest = &rest[plen..];
+    }
+    Ok((codepoint, params))
+}
+
+// read a reply, following chained DSSs, and return its objects
+async fn read_reply<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> Result<Vec<(u16, Params)>, Error> {
+    let mut objects = vec![];
+    loop {
+        let mut header = [0u8; 6];
+        stream
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| e.to_string())?;
+        if header[2] != 0xd0 {
+            return Err("invalid DSS header".to_owned());
+        }
+        let len = (u16::from_be_bytes([header[0], header[1]]) & 0x7fff) as usize;
+        let mut body = vec![0u8; len.saturating_sub(6)];
+        stream
+            .read_exact(&mut body)
+            .await
+            .map_err(|e| e.to_string())?;
+        objects.push(parse_object(&body)?);
+
+        if header[3] & 0x40 == 0 {
+            return Ok(objects);
+        }
+    }
+}
+
+fn find(params: &[(u16, Vec<u8>)], codepoint: u16) -> Option<&[u8]> {
+    params
+        .iter()
+        .find(|(cp, _)| *cp == codepoint)
+        .map(|(_, data)| data.as_slice())
+}
+
+async fn call<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &[u8],
+    expected: u16,
+) -> Result<Params, Error> {
+    stream.write_all(request).await.map_err(|e| e.to_string())?;
+    for (codepoint, params) in read_reply(stream).await? {
+        if codepoint == expected {
+            return Ok(params);
+        } else if codepoint == RDBNFNRM {
+            return Err("database not found".to_owned());
+        }
+        log::debug!("unexpected DDM reply 0x{:04x}", codepoint);
+    }
+    Err(format!("no DDM reply 0x{:04x}", expected))
+}
+
+// exchange server attributes and check the credentials without accessing the database
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    database: &str,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    let attributes = call(stream, &excsat(), EXCSATRD).await?;
+    let server = find(&attributes, SRVRLSLV)
+        .or(find(&attributes, SRVCLSNM))
+        .map(|s| from_ebcdic(s).trim().to_owned());
+
+    let accsec = call(stream, &accsec(database), ACCSECRD).await?;
+    let mechanisms: Vec<u16> = find(&accsec, SECMEC)
+        .unwrap_or_default()
+        .chunks(2)
+        .filter(|c| c.len() == 2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    // the server rejected clear text credentials and replied with the mechanisms it accepts
+    if find(&accsec, SECCHKCD).is_some() || !mechanisms.contains(&SECMEC_USRIDPWD) {
+        let names: Vec<String> = mechanisms
+            .iter()
+            .map(|m| match SECMEC_NAMES.iter().find(|(code, _)| code == m) {
+                Some((_, name)) => name.to_string(),
+                None => m.to_string(),
+            })
+            .collect();
+        return Err(format!(
+            "unsupported security mechanism, the server requires {} while only USRIDPWD (clear text user id and password) is supported",
+            names.join(", ")
+        ));
+    }
+
+    let result = call(stream, &secchk(database, username, password), SECCHKRM).await?;
+    match find(&result, SECCHKCD).and_then(|c| c.first()).copied() {
+        Some(SECCHKCD_OK) => Ok(Outcome::Valid(server)),
+        Some(SECCHKCD_PASSWORD_EXPIRED) => Ok(Outcome::Expired(server)),
+        Some(SECCHKCD_PASSWORD_INVALID)
+        | Some(SECCHKCD_PASSWORD_MISSING)
+        | Some(SECCHKCD_USERID_MISSING)
+        | Some(SECCHKCD_USERID_INVALID) => Ok(Outcome::Invalid),
+        Some(SECCHKCD_USERID_REVOKED) => {
+            log::debug!("user {} is revoked", username);
+            Ok(Outcome::Invalid)
+        }
+        Some(code) => Err(format!("security check failed with code 0x{:02x}", code)),
+        None => Err("no security check code".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        accsec, authenticate, excsat, from_ebcdic, param, parse_object, secchk, to_ebcdic,
+        Outcome,
+    };
+
+    #[test]
+    fn can_convert_ebcdic() {
+        assert_eq!(to_ebcdic("SAMPLE db2"), b"\xe2\xc1\xd4\xd7\xd3\xc5\x40\x84\x82\xf2");
+        assert_eq!(from_ebcdic(&to_ebcdic("SQL11058 ~{}[]")), "SQL11058 ~{}[]");
+    }
+
+    #[test]
+    fn can_encode_commands() {
+        let request = excsat();
+        assert_eq!(&request[2..6], &[0xd0, 0x01, 0x00, 0x01]);
+        let (codepoint, params) = parse_object(&request[6..]).unwrap();
+        assert_eq!(codepoint, 0x1041);
+        assert_eq!(params[0], (0x115e, to_ebcdic("legba")));
+        assert_eq!(params[1].1.len(), 20);
+    }
+
+    fn reply(codepoint: u16, params: &[u8]) -> Vec<u8> {
+        let mut object = vec![];
+        param(&mut object, codepoint, params);
+        let mut dss = ((object.len() + 6) as u16).to_be_bytes().to_vec();
+        dss.extend_from_slice(&[0xd0, 0x02, 0x00, 0x01]);
+        dss.extend(object);
+        dss
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut attributes = vec![];
+        param(&mut attributes, 0x115a, &to_ebcdic("SQL11058"));
+        let mut secmec = vec![];
+        param(&mut secmec, 0x11a2, &[0, 3]);
+
+        for (code, outcome) in [
+            (0x00, Outcome::Valid(Some("SQL11058".to_owned()))),
+            (0x0e, Outcome::Expired(Some("SQL11058".to_owned()))),
+            (0x0f, Outcome::Invalid),
+        ] {
+            let mut result = vec![];
+            param(&mut result, 0x1149, &[0, 8]);
+            param(&mut result, 0x11a4, &[code]);
+
+            let mut stream = tokio_test::io::Builder::new()
+                .write(&excsat())
+                .read(&reply(0x1443, &attributes))
+                .write(&accsec("SAMPLE"))
+                .read(&reply(0x14ac, &secmec))
+                .write(&secchk("SAMPLE", "db2inst1", "password"))
+                .read(&reply(0x1219, &result))
+                .build();
+            assert_eq!(
+                authenticate(&mut stream, "SAMPLE", "db2inst1", "password").await,
+                Ok(outcome)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_encrypted_mechanisms() {
+        let mut secmec = vec![];
+        param(&mut secmec, 0x11a2, &[0, 9, 0, 13]);
+        param(&mut secmec, 0x11a4, &[0x01]);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&excsat())
+            .read(&reply(0x1443, &[]))
+            .write(&accsec("SAMPLE"))
+            .read(&reply(0x14ac, &secmec))
+            .build();
+        let err = authenticate(&mut stream, "SAMPLE", "db2inst1", "password")
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("unsupported security mechanism"));
+        assert!(err.contains("EUSRIDPWD, EUSRPWDDTA"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+mod drda;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "db2" => DB2::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct DB2 {
+    database: String,
+    ssl: bool,
+}
+
+impl DB2 {
+    pub fn new() -> Self {
+        DB2 {
+            database: String::new(),
+            ssl: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for DB2 {
+    fn description(&self) -> &'static str {
+        "IBM DB2 DRDA password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.database = opts.db2.db2_database.clone();
+        self.ssl = opts.db2.db2_ssl;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 50000)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, self.ssl).await?;
+
+        let outcome = tokio::time::timeout(
+            timeout,
+            drda::authenticate(
+                &mut stream,
+                &self.database,
+                &creds.username,
+                &creds.password,
+            ),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+
+        match outcome {
+            drda::Outcome::Invalid => Ok(None),
+            drda::Outcome::Valid(server) => {
+                if let Some(server) = server {
+                    data.push(("server".to_owned(), server));
+                }
+                Ok(Some(vec![Loot::new("db2", &address, data)]))
+            }
+            drda::Outcome::Expired(server) => {
+                if let Some(server) = server {
+                    data.push(("server".to_owned(), server));
+                }
+                data.push(("expired_password".to_owned(), "true".to_owned()));
+                Ok(Some(vec![Loot::new("db2", &address, data).set_partial()]))
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "SAMPLE")]
+    /// DB2 database name.
+    pub db2_database: String,
+    #[clap(long, default_value_t = false)]
+    /// Enable SSL for DB2.
+    pub db2_ssl: bool,
+}
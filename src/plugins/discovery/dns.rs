@@ -0,0 +1,211 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::session::Error;
+
+pub(crate) const TYPE_A: u16 = 1;
+pub(crate) const TYPE_PTR: u16 = 12;
+pub(crate) const TYPE_AAAA: u16 = 28;
+pub(crate) const TYPE_SRV: u16 = 33;
+
+// enough for any legit name
+const MAX_POINTERS: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Record {
+    pub name: String,
+    pub record_type: u16,
+    // decoded for the types we care about, empty otherwise
+    pub data: String,
+}
+
+// mDNS and LLMNR use the DNS message format
+pub(crate) fn query(id: u16, questions: &[(&str, u16)]) -> Vec<u8> {
+    let mut query = id.to_be_bytes().to_vec();
+    // no flags, no recursion
+    query.extend([0x00, 0x00]);
+    query.extend((questions.len() as u16).to_be_bytes());
+    query.extend([0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    for (name, question_type) in questions {
+        for label in name.trim_end_matches('.').split('.') {
+            query.push(label.len() as u8);
+            query.extend(label.as_bytes());
+        }
+        query.push(0x00);
+        query.extend(question_type.to_be_bytes());
+        // IN
+        query.extend([0x00, 0x01]);
+    }
+
+    query
+}
+
+// the name to query in order to resolve an address to its hostname
+pub(crate) fn reverse_name(ip: &IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let o = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", o[3], o[2], o[1], o[0])
+        }
+        IpAddr::V6(ip) => {
+            let mut nibbles = vec![];
+            for b in ip.octets().iter().rev() {
+                nibbles.push(format!("{:x}", b & 0x0f));
+                nibbles.push(format!("{:x}", b >> 4));
+            }
+            format!("{}.ip6.arpa", nibbles.join("."))
+        }
+    }
+}
+
+// read a possibly compressed name, returning it and the position right after it
+fn read_name(data: &[u8], mut pos: usize) -> Result<(String, usize), Error> {
+    let mut labels = vec![];
+    let mut next = None;
+    let mut pointers = 0;
+
+    loop {
+        let len = *data.get(pos).ok_or("truncated name")? as usize;
+        if len == 0 {
+            pos += 1;
+            break;
+        } else if len & 0xc0 == 0xc0 {
+            let low = *data.get(pos + 1).ok_or("truncated name")? as usize;
+            pointers += 1;
+            if pointers > MAX_POINTERS {
+                return Err("too many name pointers".to_owned());
+            }
+            if next.is_none() {
+                next = Some(pos + 2);
+            }
+            pos = ((len & 0x3f) << 8) | low;
+        } else {
+            let label = data.get(pos + 1..pos + 1 + len).ok_or("truncated label")?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            pos += 1 + len;
+        }
+    }
+
+    Ok((labels.join("."), next.unwrap_or(pos)))
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Result<u16, Error> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or("truncated message".to_owned())
+}
+
+// all the records of the answer, authority and additional sections
+pub(crate) fn parse_records(data: &[u8], id: u16) -> Result<Vec<Record>, Error> {
+    if data.len() < 12 {
+        return Err("truncated response".to_owned());
+    } else if u16_at(data, 0)? != id {
+        return Err("unexpected transaction id".to_owned());
+    } else if data[2] & 0x80 == 0 {
+        return Err("not a response".to_owned());
+    }
+
+    let questions = u16_at(data, 4)?;
+    let records = u16_at(data, 6)? as usize + u16_at(data, 8)? as usize + u16_at(data, 10)? as usize;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(data, pos)?.1 + 4;
+    }
+
+    let mut parsed = vec![];
+    for _ in 0..records {
+        let (name, next) = read_name(data, pos)?;
+        let record_type = u16_at(data, next)?;
+        let size = u16_at(data, next + 8)? as usize;
+        let start = next + 10;
+        let rdata = data.get(start..start + size).ok_or("truncated record")?;
+        pos = start + size;
+
+        let data = match record_type {
+            TYPE_PTR => read_name(data, start)?.0,
+            TYPE_SRV if size > 6 => format!(
+                "{}:{}",
+                read_name(data, start + 6)?.0,
+                u16::from_be_bytes([rdata[4], rdata[5]])
+            ),
+            TYPE_A if size == 4 => Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]).to_string(),
+            TYPE_AAAA if size == 16 => {
+                let octets: [u8; 16] = rdata.try_into().unwrap();
+                Ipv6Addr::from(octets).to_string()
+            }
+            _ => String::new(),
+        };
+
+        parsed.push(Record {
+            name,
+            record_type,
+            data,
+        });
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_records, query, reverse_name, Record, TYPE_PTR, TYPE_SRV};
+
+    #[test]
+    fn can_build_reverse_names() {
+        assert_eq!(
+            reverse_name(&"192.168.1.10".parse::<IpAddr>().unwrap()),
+            "10.1.168.192.in-addr.arpa"
+        );
+        let v6 = reverse_name(&"fe80::1".parse::<IpAddr>().unwrap());
+        assert!(v6.starts_with("1.0.0.0.") && v6.ends_with(".0.8.e.f.ip6.arpa"));
+        assert_eq!(v6.split('.').count(), 34);
+    }
+
+    #[test]
+    fn can_parse_compressed_records() {
+        let mut response = query(0x42, &[("_services._dns-sd._udp.local", TYPE_PTR)]);
+        // response with one answer and one additional record
+        response[2] = 0x84;
+        response[7] = 0x01;
+        response[11] = 0x01;
+
+        // _services._dns-sd._udp.local PTR _http._tcp.local
+        response.extend([0xc0, 0x0c, 0x00, 0x0c, 0x00, 0x01, 0, 0, 0x00, 0x0a, 0x00, 0x0d]);
+        response.extend([0x05]);
+        response.extend(b"_http");
+        response.extend([0x04]);
+        response.extend(b"_tcp");
+        // pointer to "local"
+        response.extend([0xc0, 0x23]);
+
+        // web._http._tcp.local SRV 0 0 8080 printer.local
+        response.extend([0x03]);
+        response.extend(b"web");
+        // pointer to "_http._tcp.local"
+        response.extend([0xc0, 0x3a]);
+        response.extend([0x00, 0x21, 0x00, 0x01, 0, 0, 0, 0x0a, 0x00, 0x10]);
+        response.extend([0, 0, 0, 0, 0x1f, 0x90, 0x07]);
+        response.extend(b"printer");
+        response.extend([0xc0, 0x23]);
+
+        assert_eq!(
+            parse_records(&response, 0x42).unwrap(),
+            vec![
+                Record {
+                    name: "_services._dns-sd._udp.local".to_owned(),
+                    record_type: TYPE_PTR,
+                    data: "_http._tcp.local".to_owned(),
+                },
+                Record {
+                    name: "web._http._tcp.local".to_owned(),
+                    record_type: TYPE_SRV,
+                    data: "printer.local:8080".to_owned(),
+                },
+            ]
+        );
+        assert!(parse_records(&response, 0x43).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+mod dns;
+mod nbns;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "discovery" => Discovery::new()
+}
+
+const NBNS_PORT: u16 = 137;
+const MDNS_PORT: u16 = 5353;
+const LLMNR_PORT: u16 = 5355;
+
+// lists the service types advertised by the host
+const MDNS_SERVICES: &str = "_services._dns-sd._udp.local";
+
+#[derive(Clone, PartialEq, Debug)]
+enum Protocol {
+    Nbns,
+    Mdns,
+    Llmnr,
+}
+
+#[derive(Default)]
+struct Mdns {
+    hostname: Option<String>,
+    services: Vec<String>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Discovery {
+    protocols: Vec<Protocol>,
+}
+
+impl Discovery {
+    pub fn new() -> Self {
+        Discovery { protocols: vec![] }
+    }
+
+    fn enabled(&self, protocol: Protocol) -> bool {
+        self.protocols.contains(&protocol)
+    }
+
+    async fn nbns(&self, ip: IpAddr, timeout: Duration) -> Result<nbns::NodeStatus, Error> {
+        let id = rand::random();
+        let response = exchange(
+            SocketAddr::new(ip, NBNS_PORT),
+            &nbns::node_status_request(id),
+            timeout,
+        )
+        .await?;
+        nbns::parse_node_status(&response, id)
+    }
+
+    async fn mdns(&self, ip: IpAddr, timeout: Duration) -> Result<Mdns, Error> {
+        // queries from a port other than 5353 get a legacy unicast response
+        let id = rand::random();
+        let reverse = dns::reverse_name(&ip);
+        let response = exchange(
+            SocketAddr::new(ip, MDNS_PORT),
+            &dns::query(
+                id,
+                &[(MDNS_SERVICES, dns::TYPE_PTR), (&reverse, dns::TYPE_PTR)],
+            ),
+            timeout,
+        )
+        .await?;
+
+        let mut mdns = Mdns::default();
+        for record in dns::parse_records(&response, id)? {
+            if record.record_type != dns::TYPE_PTR {
+                continue;
+            } else if record.name == reverse {
+                mdns.hostname = Some(record.data);
+            } else if record.name == MDNS_SERVICES && !mdns.services.contains(&record.data) {
+                mdns.services.push(record.data);
+            }
+        }
+
+        Ok(mdns)
+    }
+
+    async fn llmnr(&self, ip: IpAddr, timeout: Duration) -> Result<Option<String>, Error> {
+        let id = rand::random();
+        let reverse = dns::reverse_name(&ip);
+        let response = exchange(
+            SocketAddr::new(ip, LLMNR_PORT),
+            &dns::query(id, &[(&reverse, dns::TYPE_PTR)]),
+            timeout,
+        )
+        .await?;
+
+        Ok(dns::parse_records(&response, id)?
+            .into_iter()
+            .find(|r| r.record_type == dns::TYPE_PTR && r.name == reverse)
+            .map(|r| r.data))
+    }
+}
+
+async fn exchange(address: SocketAddr, request: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+    let socket = utils::net::async_udp_socket(&address.to_string()).await?;
+    socket.send(request).await.map_err(|e| e.to_string())?;
+
+    let mut buffer = vec![0u8; 9000];
+    let size = tokio::time::timeout(timeout, socket.recv(&mut buffer))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    buffer.truncate(size);
+
+    Ok(buffer)
+}
+
+#[async_trait]
+impl Plugin for Discovery {
+    fn description(&self) -> &'static str {
+        "Local network hosts and services discovery via NetBIOS, mDNS and LLMNR."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "survey".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.protocols = opts
+            .discovery
+            .discovery_protocols
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .map(|s| match s.as_str() {
+                "nbns" | "netbios" => Ok(Protocol::Nbns),
+                "mdns" => Ok(Protocol::Mdns),
+                "llmnr" => Ok(Protocol::Llmnr),
+                _ => Err(format!("unsupported discovery protocol '{}'", s)),
+            })
+            .collect::<Result<Vec<Protocol>, Error>>()?;
+
+        if self.protocols.is_empty() {
+            return Err("no --discovery-protocols specified".to_owned());
+        }
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, _) = utils::parse_target(&creds.target, 0)?;
+        let ip = match host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => tokio::net::lookup_host(format!("{}:0", host))
+                .await
+                .map_err(|e| e.to_string())?
+                .next()
+                .ok_or(format!("can't resolve {}", host))?
+                .ip(),
+        };
+
+        let (nbns, mdns, llmnr) = tokio::join!(
+            async {
+                if self.enabled(Protocol::Nbns) {
+                    self.nbns(ip, timeout).await.ok()
+                } else {
+                    None
+                }
+            },
+            async {
+                if self.enabled(Protocol::Mdns) {
+                    self.mdns(ip, timeout).await.ok()
+                } else {
+                    None
+                }
+            },
+            async {
+                if self.enabled(Protocol::Llmnr) {
+                    self.llmnr(ip, timeout).await.ok()
+                } else {
+                    None
+                }
+            }
+        );
+
+        let mut protocols = vec![];
+        let mut hostname = None;
+        let mut data = vec![];
+
+        if let Some(mdns) = mdns {
+            protocols.push("mdns");
+            hostname = mdns.hostname;
+            if !mdns.services.is_empty() {
+                data.push(("services".to_owned(), mdns.services.join(", ")));
+            }
+        }
+
+        if let Some(name) = llmnr {
+            protocols.push("llmnr");
+            hostname = hostname.or(name);
+        }
+
+        if let Some(status) = nbns {
+            protocols.push("nbns");
+            if let Some(name) = status.name {
+                hostname = hostname.or(Some(name.to_owned()));
+                data.push(("netbios_name".to_owned(), name));
+            }
+            if let Some(workgroup) = status.workgroup {
+                data.push(("workgroup".to_owned(), workgroup));
+            }
+            if !status.roles.is_empty() {
+                data.push(("roles".to_owned(), status.roles.join(", ")));
+            }
+            if let Some(mac) = status.mac {
+                data.push(("mac".to_owned(), mac));
+            }
+        }
+
+        if protocols.is_empty() {
+            return Ok(None);
+        }
+
+        let mut loot_data = vec![("address".to_owned(), ip.to_string())];
+        if let Some(hostname) = hostname {
+            loot_data.push(("hostname".to_owned(), hostname));
+        }
+        loot_data.extend(data);
+        loot_data.push(("protocols".to_owned(), protocols.join(", ")));
+
+        Ok(Some(vec![Loot::new("discovery", &ip.to_string(), loot_data)]))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::session::Error;
+
+// NBSTAT
+const TYPE_NODE_STATUS: u16 = 0x0021;
+// group names flag
+const GROUP: u16 = 0x8000;
+const NAME_SIZE: usize = 18;
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct NodeStatus {
+    pub name: Option<String>,
+    pub workgroup: Option<String>,
+    pub mac: Option<String>,
+    pub roles: Vec<String>,
+}
+
+// node status request for the '*' name, answered with every name registered by the host
+pub(crate) fn node_status_request(id: u16) -> Vec<u8> {
+    let mut request = id.to_be_bytes().to_vec();
+    // no flags, one question
+    request.extend([0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    // first level encoding, each nibble becomes a letter
+    let mut name = [0u8; 16];
+    name[0] = b'*';
+    request.push(0x20);
+    for b in name {
+        request.push(b'A' + (b >> 4));
+        request.push(b'A' + (b & 0x0f));
+    }
+    request.push(0x00);
+
+    request.extend(TYPE_NODE_STATUS.to_be_bytes());
+    // IN
+    request.extend([0x00, 0x01]);
+    request
+}
+
+fn skip_name(data: &[u8], mut pos: usize) -> Result<usize, Error> {
+    loop {
+        let len = *data.get(pos).ok_or("truncated name")? as usize;
+        if len == 0 {
+            return Ok(pos + 1);
+        } else if len & 0xc0 == 0xc0 {
+            // compression pointer
+            return Ok(pos + 2);
+        }
+        pos += 1 + len;
+    }
+}
+
+pub(crate) fn parse_node_status(data: &[u8], id: u16) -> Result<NodeStatus, Error> {
+    if data.len() < 12 {
+        return Err("truncated nbns response".to_owned());
+    } else if u16::from_be_bytes([data[0], data[1]]) != id {
+        return Err("unexpected nbns transaction id".to_owned());
+    } else if data[2] & 0x80 == 0 {
+        return Err("not a nbns response".to_owned());
+    }
+
+    let questions = u16::from_be_bytes([data[4], data[5]]);
+    let answers = u16::from_be_bytes([data[6], data[7]]);
+    if answers == 0 {
+        return Err("empty nbns response".to_owned());
+    }
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(data, pos)? + 4;
+    }
+
+    pos = skip_name(data, pos)?;
+    if data.len() < pos + 11 {
+        return Err("truncated nbns answer".to_owned());
+    } else if u16::from_be_bytes([data[pos], data[pos + 1]]) != TYPE_NODE_STATUS {
+        return Err("unexpected nbns answer type".to_owned());
+    }
+    // type, class, ttl and data length
+    pos += 10;
+
+    let count = data[pos] as usize;
+    pos += 1;
+
+    let mut status = NodeStatus::default();
+    for _ in 0..count {
+        let Some(entry) = data.get(pos..pos + NAME_SIZE) else {
+            return Err("truncated nbns names".to_owned());
+        };
+        pos += NAME_SIZE;
+
+        let name = String::from_utf8_lossy(&entry[..15]).trim_end().to_owned();
+        let suffix = entry[15];
+        let group = u16::from_be_bytes([entry[16], entry[17]]) & GROUP != 0;
+
+        match (suffix, group) {
+            (0x00, false) if status.name.is_none() => status.name = Some(name),
+            (0x00, true) if status.workgroup.is_none() => status.workgroup = Some(name),
+            (0x20, false) => status.roles.push("file server".to_owned()),
+            (0x1b, false) => status.roles.push("domain master browser".to_owned()),
+            (0x1c, true) => status.roles.push("domain controller".to_owned()),
+            (0x1d, false) => status.roles.push("master browser".to_owned()),
+            _ => {}
+        }
+    }
+
+    // the statistics start with the mac address, samba just sends zeros
+    if let Some(mac) = data.get(pos..pos + 6) {
+        if mac.iter().any(|b| *b != 0) {
+            status.mac = Some(
+                mac.iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<String>>()
+                    .join(":"),
+            );
+        }
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{node_status_request, parse_node_status, NodeStatus};
+
+    fn entry(name: &str, suffix: u8, flags: u16) -> Vec<u8> {
+        let mut entry = format!("{:<15}", name).into_bytes();
+        entry.push(suffix);
+        entry.extend(flags.to_be_bytes());
+        entry
+    }
+
+    #[test]
+    fn can_build_node_status_request() {
+        let request = node_status_request(0x1234);
+        assert_eq!(request.len(), 50);
+        assert_eq!(&request[..2], &[0x12, 0x34]);
+        assert_eq!(&request[13..17], b"CKAA");
+        assert_eq!(&request[46..], &[0x00, 0x21, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn can_parse_node_status() {
+        let request = node_status_request(7);
+        let mut response = vec![0x00, 0x07, 0x84, 0x00, 0x00, 0x00, 0x00, 0x01, 0, 0, 0, 0];
+        // answer name, NBSTAT, IN, ttl, data length
+        response.extend(&request[12..46]);
+        response.extend([0x00, 0x21, 0x00, 0x01, 0, 0, 0, 0, 0x00, 0x00]);
+        response.push(4);
+        response.extend(entry("FILESRV", 0x00, 0x0400));
+        response.extend(entry("CORP", 0x00, 0x8400));
+        response.extend(entry("FILESRV", 0x20, 0x0400));
+        response.extend(entry("CORP", 0x1c, 0x8400));
+        response.extend([0x00, 0x0c, 0x29, 0xaa, 0xbb, 0xcc]);
+
+        assert_eq!(
+            parse_node_status(&response, 7).unwrap(),
+            NodeStatus {
+                name: Some("FILESRV".to_owned()),
+                workgroup: Some("CORP".to_owned()),
+                mac: Some("00:0c:29:aa:bb:cc".to_owned()),
+                roles: vec!["file server".to_owned(), "domain controller".to_owned()],
+            }
+        );
+        assert!(parse_node_status(&response, 8).is_err());
+        assert!(parse_node_status(&response[..60], 7).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "nbns,mdns,llmnr")]
+    /// Comma separated discovery protocols to query each host with.
+    pub discovery_protocols: String,
+}
@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
+use rand::{distributions::Alphanumeric, Rng};
 use tokio::sync::Mutex;
+use tokio::task::JoinSet;
 use trust_dns_resolver::{config::*, AsyncResolver, TokioAsyncResolver};
 use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
 
@@ -18,27 +21,236 @@ use crate::creds::Credentials;
 use super::plugin::PayloadStrategy;
 
 pub(crate) mod options;
+mod zone;
 
 super::manager::register_plugin! {
-    "dns" => DNS::new()
+    "dns" => DNS::new(),
+    "dns.axfr" => zone::Zone::new(zone::Mode::Transfer),
+    "dns.records" => zone::Zone::new(zone::Mode::Records)
+}
+
+// random names resolved to detect wildcard records
+const WILDCARD_PROBES: usize = 3;
+
+// generate the candidates obtained by combining a discovered name with the permutation words
+fn permutations(name: &str, words: &[String]) -> Vec<String> {
+    let mut candidates = vec![];
+    for word in words {
+        for candidate in [format!("{}-{}", word, name), format!("{}-{}", name, word)] {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+// build the resolvers from --dns-resolvers, one per server if they have to be rotated
+fn build_resolvers(opts: &Options) -> Result<Vec<TokioAsyncResolver>, Error> {
+let resolvers = if let Some(resolvers) = opts.dns.dns_resolvers.as_ref() {
+        let ips: Vec<IpAddr> = resolvers
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<IpAddr>()
+                    .map_err(|e| format!("invalid resolver '{}': {}", s, e))
+            })
+            .collect::<Result<Vec<IpAddr>, Error>>()?;
+
+        log::info!("using resolvers: {:?}", &ips);
+
+        let mut options = ResolverOpts::default();
+
+        options.num_concurrent_reqs = opts.concurrency;
+        options.attempts = opts.dns.dns_attempts;
+        options.timeout = Duration::from_millis(opts.timeout);
+        options.shuffle_dns_servers = true;
+
+        let groups = if opts.dns.dns_rotate_resolvers {
+            log::info!("rotating resolvers");
+            // one resolver per server, used in round robin
+            ips.iter()
+                .map(|ip| NameServerConfigGroup::from_ips_clear(&[*ip], opts.dns.dns_port, true))
+                .collect()
+        } else {
+            vec![NameServerConfigGroup::from_ips_clear(
+                &ips,
+                opts.dns.dns_port,
+                true,
+            )]
+        };
+
+        groups
+            .into_iter()
+            .map(|group| {
+                AsyncResolver::tokio(
+                    ResolverConfig::from_parts(None, vec![], group),
+                    options,
+                )
+            })
+            .collect()
+    } else {
+        log::info!("using system resolver");
+
+        vec![AsyncResolver::tokio_from_system_conf().map_err(|e| e.to_string())?]
+    };
+
+    Ok(resolvers)
 }
 
 #[derive(Clone)]
 pub(crate) struct DNS {
-    resolver: Option<TokioAsyncResolver>,
+    resolvers: Vec<TokioAsyncResolver>,
+    next_resolver: Arc<AtomicUsize>,
     opts: options::Options,
+    permutation_words: Vec<String>,
     hits: Arc<Mutex<HashMap<IpAddr, usize>>>,
     domains: Arc<Mutex<HashMap<String, u8>>>,
+    wildcards: Arc<Mutex<HashMap<String, HashSet<IpAddr>>>>,
 }
 
 impl DNS {
     pub fn new() -> Self {
         DNS {
-            resolver: None,
+            resolvers: vec![],
+            next_resolver: Arc::new(AtomicUsize::new(0)),
             opts: options::Options::default(),
+            permutation_words: vec![],
             hits: Arc::new(Mutex::new(HashMap::default())),
             domains: Arc::new(Mutex::new(HashMap::default())),
+            wildcards: Arc::new(Mutex::new(HashMap::default())),
+        }
+    }
+
+    fn resolver(&self) -> &TokioAsyncResolver {
+        // round robin if resolvers are rotated, otherwise there's just one
+        let index = self.next_resolver.fetch_add(1, Ordering::Relaxed) % self.resolvers.len();
+        &self.resolvers[index]
+    }
+
+    async fn wildcard_addresses(&self, domain: &str) -> HashSet<IpAddr> {
+        // the lock is held while probing so that it only happens once per domain
+        let mut wildcards = self.wildcards.lock().await;
+        if let Some(addresses) = wildcards.get(domain) {
+            return addresses.clone();
+        }
+
+        let mut addresses = HashSet::new();
+        for _ in 0..WILDCARD_PROBES {
+            let label: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(16)
+                .map(|c| (c as char).to_ascii_lowercase())
+                .collect();
+            if let Ok(response) = self
+                .resolver()
+                .lookup_ip(format!("{}.{}", label, domain))
+                .await
+            {
+                addresses.extend(response.iter());
+            }
+        }
+
+        if !addresses.is_empty() {
+            log::warn!(
+                "wildcard dns detected for *.{} -> {:?}, these answers will be filtered out.",
+                domain,
+                &addresses
+            );
+        }
+
+        wildcards.insert(domain.to_owned(), addresses.clone());
+        addresses
+    }
+
+    async fn resolve(&self, domain: &str, subdomain: &str) -> Vec<IpAddr> {
+        let wildcards = if self.opts.dns_no_wildcard_check {
+            HashSet::new()
+        } else {
+            self.wildcard_addresses(domain).await
+        };
+
+        // attempt resolving this subdomain to a one or more IP addresses
+        if let Ok(response) = self.resolver().lookup_ip(subdomain).await {
+            // collect valid IPs
+            let addresses: Vec<IpAddr> = response
+                .iter()
+                .filter(|ip| !ip.is_loopback() && !wildcards.contains(ip))
+                .collect();
+            // Some domains are configured to resolve any subdomain, whatever it is, to the same IP. We do
+            // this filtering in order too many positives for an address and work around this behaviour.
+            self.filter(addresses).await
+        } else {
+            vec![]
+        }
+    }
+
+    fn format_addresses(&self, subdomain: &str, addresses: &[IpAddr]) -> String {
+        if self.opts.dns_ip_lookup {
+            // perform reverse lookup of the IPs if we have to
+            let mut parts = vec![];
+            for ip in addresses {
+                if let Ok(hostname) = dns_lookup::lookup_addr(ip) {
+                    if hostname != subdomain {
+                        parts.push(format!("{} ({})", ip, hostname));
+                    }
+                } else {
+                    parts.push(ip.to_string());
+                }
+            }
+
+            parts.join(", ")
+        } else {
+            // just join the IPs
+            addresses
+                .iter()
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
+    }
+
+    async fn get_permutations_loot(&self, domain: &str, name: &str) -> Vec<Loot> {
+        let mut tasks = JoinSet::new();
+        for candidate in permutations(name, &self.permutation_words) {
+            let subdomain = format!("{}.{}", candidate, domain);
+            // claim the candidate so that it's not resolved again
+            {
+                let mut domains = self.domains.lock().await;
+                if domains.contains_key(&subdomain) {
+                    continue;
+                }
+                domains.insert(subdomain.to_owned(), 1);
+            }
+
+            let dns = self.clone();
+            let domain = domain.to_owned();
+            tasks.spawn(async move {
+                let addresses = dns.resolve(&domain, &subdomain).await;
+                (subdomain, addresses)
+            });
         }
+
+        let mut loot = vec![];
+        while let Some(Ok((subdomain, addresses))) = tasks.join_next().await {
+            if !addresses.is_empty() {
+                log::debug!("found new domain from permutations: {}", &subdomain);
+                loot.push(Loot::new(
+                    "dns",
+                    &subdomain,
+                    vec![
+                        (
+                            "addresses".to_owned(),
+                            self.format_addresses(&subdomain, &addresses),
+                        ),
+                        ("permutation_of".to_owned(), format!("{}.{}", name, domain)),
+                    ],
+                ));
+            }
+        }
+
+        loot
     }
 
     async fn filter(&self, addresses: Vec<IpAddr>) -> Vec<IpAddr> {
@@ -119,8 +331,7 @@ impl DNS {
                 // skip domains that have already been processed
                 if !self.domains.lock().await.contains_key(&tls_domain) {
                     // try to resolve to ip
-                    if let Ok(response) =
-                        self.resolver.as_ref().unwrap().lookup_ip(&tls_domain).await
+                    if let Ok(response) = self.resolver().lookup_ip(&tls_domain).await
                     {
                         // collect valid IPs
                         let addresses: Vec<IpAddr> =
@@ -169,35 +380,15 @@ impl Plugin for DNS {
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.opts = opts.dns.clone();
-        self.resolver = Some(if let Some(resolvers) = opts.dns.dns_resolvers.as_ref() {
-            let ips: Vec<IpAddr> = resolvers
-                .split(',')
-                .map(|s| s.trim())
-                .filter(|s| !s.is_empty())
-                .map(|s| s.parse::<IpAddr>().unwrap())
-                .collect();
-
-            log::info!("using resolvers: {:?}", &ips);
-
-            let nameserver_group =
-                NameServerConfigGroup::from_ips_clear(&ips, opts.dns.dns_port, true);
+        self.permutation_words = opts
+            .dns
+            .dns_permutation_words
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
 
-            let mut options = ResolverOpts::default();
-
-            options.num_concurrent_reqs = opts.concurrency;
-            options.attempts = opts.dns.dns_attempts;
-            options.timeout = Duration::from_millis(opts.timeout);
-            options.shuffle_dns_servers = true;
-
-            AsyncResolver::tokio(
-                ResolverConfig::from_parts(None, vec![], nameserver_group),
-                options,
-            )
-        } else {
-            log::info!("using system resolver");
-
-            AsyncResolver::tokio_from_system_conf().map_err(|e| e.to_string())?
-        });
+        self.resolvers = build_resolvers(opts)?;
 
         Ok(())
     }
@@ -213,65 +404,62 @@ impl Plugin for DNS {
             return Ok(None);
         }
 
-        // attempt resolving this subdomain to a one or more IP addresses
-        if let Ok(response) = self.resolver.as_ref().unwrap().lookup_ip(&subdomain).await {
-            // collect valid IPs
-            let addresses: Vec<IpAddr> = response.iter().filter(|ip| !ip.is_loopback()).collect();
-            // Some domains are configured to resolve any subdomain, whatever it is, to the same IP. We do
-            // this filtering in order too many positives for an address and work around this behaviour.
-            let addresses = self.filter(addresses).await;
-            if !addresses.is_empty() {
-                let mut loot_data = vec![];
-                let addr_data = if self.opts.dns_ip_lookup {
-                    // perform reverse lookup of the IPs if we have to
-                    let mut parts = vec![];
-                    for ip in &addresses {
-                        if let Ok(hostname) = dns_lookup::lookup_addr(ip) {
-                            if hostname != subdomain {
-                                parts.push(format!("{} ({})", ip, hostname));
-                            }
-                        } else {
-                            parts.push(ip.to_string());
-                        }
-                    }
+        let addresses = self.resolve(&creds.target, &subdomain).await;
+        if !addresses.is_empty() {
+            let loot_data = vec![(
+                "addresses".to_owned(),
+                self.format_addresses(&subdomain, &addresses),
+            )];
 
-                    parts.join(", ")
-                } else {
-                    // just join the IPs
-                    addresses
-                        .iter()
-                        .map(|a| a.to_string())
-                        .collect::<Vec<String>>()
-                        .join(", ")
-                };
+            let mut loot = vec![Loot::new("dns", &subdomain, loot_data)];
 
-                loot_data.push(("addresses".to_owned(), addr_data));
+            // keep track of domains we processed already
+            self.domains.lock().await.insert(subdomain.to_owned(), 1);
 
-                let mut loot = vec![Loot::new("dns", &subdomain, loot_data)];
+            if !self.opts.dns_no_https {
+                let more_loot = self
+                    .get_additional_tls_loot(&creds.target, &subdomain, timeout)
+                    .await;
 
                 // keep track of domains we processed already
-                self.domains.lock().await.insert(subdomain.to_owned(), 1);
-
-                if !self.opts.dns_no_https {
-                    let more_loot = self
-                        .get_additional_tls_loot(&creds.target, &subdomain, timeout)
-                        .await;
-
-                    // keep track of domains we processed already
-                    for item in more_loot.iter() {
-                        self.domains
-                            .lock()
-                            .await
-                            .insert(item.get_target().to_string(), 1);
-                    }
-
-                    loot.extend(more_loot);
+                for item in more_loot.iter() {
+                    self.domains
+                        .lock()
+                        .await
+                        .insert(item.get_target().to_string(), 1);
                 }
 
-                return Ok(Some(loot));
+                loot.extend(more_loot);
+            }
+
+            if self.opts.dns_permutations {
+                loot.extend(
+                    self.get_permutations_loot(
+                        &creds.target.to_lowercase(),
+                        &creds.single().to_lowercase(),
+                    )
+                    .await,
+                );
             }
+
+            return Ok(Some(loot));
         }
 
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::permutations;
+
+    #[test]
+    fn can_generate_permutations() {
+        let words = vec!["dev".to_owned(), "api".to_owned()];
+        assert_eq!(
+            permutations("shop", &words),
+            vec!["dev-shop", "shop-dev", "api-shop", "shop-api"]
+        );
+        assert!(permutations("shop", &[]).is_empty());
+    }
+}
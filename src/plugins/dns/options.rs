@@ -22,4 +22,28 @@ pub(crate) struct Options {
     #[clap(long, default_value_t = false)]
     /// Do not fetch HTTPS certificates for new domains.
     pub dns_no_https: bool,
+    #[clap(long, default_value_t = false)]
+    /// Rotate the DNS resolvers in round robin instead of preferring the fastest ones.
+    pub dns_rotate_resolvers: bool,
+    #[clap(long, default_value_t = false)]
+    /// Do not probe random subdomains to detect and filter out wildcard DNS records.
+    pub dns_no_wildcard_check: bool,
+    #[clap(long, default_value_t = false)]
+    /// Also resolve permutations of the discovered names built with --dns-permutation-words.
+    pub dns_permutations: bool,
+    #[clap(
+        long,
+        default_value = "dev,staging,stage,test,qa,uat,prod,api,internal,admin,beta,old,new"
+    )]
+    /// Comma separated words used as prefixes and suffixes of discovered names with --dns-permutations.
+    pub dns_permutation_words: String,
+    #[clap(long, default_value = "ANY,SOA,NS,MX,TXT,CAA,SRV")]
+    /// Comma separated record types to query with dns.records.
+    pub dns_record_types: String,
+    #[clap(
+        long,
+        default_value = "_ldap._tcp,_kerberos._tcp,_kerberos._udp,_kpasswd._tcp,_gc._tcp,_sip._tcp,_sip._udp,_sips._tcp,_xmpp-server._tcp,_xmpp-client._tcp,_autodiscover._tcp,_submission._tcp,_imap._tcp,_imaps._tcp,_pop3._tcp,_pop3s._tcp,_caldav._tcp,_carddav._tcp,_http._tcp,_minecraft._tcp"
+    )]
+    /// Comma separated services to query SRV records of with dns.records.
+    pub dns_srv_services: String,
 }
@@ -0,0 +1,371 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use trust_dns_resolver::proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use trust_dns_resolver::proto::rr::{Name, Record, RecordType};
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::creds::{Credentials, Expression};
+use crate::plugins::plugin::PayloadStrategy;
+use crate::plugins::Plugin;
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+
+// transfers bigger than this are truncated
+const MAX_TRANSFER_MESSAGES: usize = 10000;
+
+#[derive(Clone, PartialEq)]
+pub(crate) enum Mode {
+    Transfer,
+    Records,
+}
+
+#[derive(Clone)]
+pub(crate) struct Zone {
+    mode: Mode,
+    resolver: Option<TokioAsyncResolver>,
+    port: u16,
+    record_types: Vec<RecordType>,
+    srv_services: Vec<String>,
+}
+
+impl Zone {
+    pub fn new(mode: Mode) -> Self {
+        Zone {
+            mode,
+            resolver: None,
+            port: 53,
+            record_types: vec![],
+            srv_services: vec![],
+        }
+    }
+
+    fn resolver(&self) -> &TokioAsyncResolver {
+        self.resolver.as_ref().unwrap()
+    }
+
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Vec<Record> {
+        match self.resolver().lookup(name, record_type).await {
+            Ok(response) => response.record_iter().cloned().collect(),
+            Err(e) => {
+                log::debug!("{} {} lookup failed: {}", name, record_type, e);
+                vec![]
+            }
+        }
+    }
+
+    async fn transfer_attempt(&self, domain: &str, timeout: Duration) -> Result<Vec<Loot>, Error> {
+        let nameservers: Vec<String> = self
+            .lookup(domain, RecordType::NS)
+            .await
+            .iter()
+            .filter_map(|r| r.data().and_then(|d| d.as_ns()).map(|ns| ns.to_utf8()))
+            .map(|ns| ns.trim_end_matches('.').to_owned())
+            .collect();
+        if nameservers.is_empty() {
+            return Err(format!("no authoritative nameservers found for {}", domain));
+        }
+
+        let mut loot = vec![];
+        for nameserver in nameservers {
+            let addresses: Vec<String> = match self.resolver().lookup_ip(&nameserver).await {
+                Ok(response) => response.iter().map(|ip| ip.to_string()).collect(),
+                Err(e) => {
+                    log::debug!("can't resolve nameserver {}: {}", &nameserver, e);
+                    continue;
+                }
+            };
+
+            for ip in addresses {
+                let address = utils::parse_target_address(&ip, self.port)?;
+                let records = match transfer_from(&address, domain, timeout).await {
+                    Ok(records) => records,
+                    Err(e) => {
+                        log::debug!("zone transfer from {} ({}) failed: {}", &nameserver, &ip, e);
+                        continue;
+                    }
+                };
+
+                log::warn!(
+                    "{} ({}) allows zone transfers of {}",
+                    &nameserver,
+                    &ip,
+                    domain
+                );
+                loot.push(Loot::new(
+                    "dns.axfr",
+                    domain,
+                    [
+                        ("nameserver".to_owned(), nameserver.to_owned()),
+                        ("address".to_owned(), ip.to_owned()),
+                        ("count".to_owned(), records.len().to_string()),
+                        (
+                            "records".to_owned(),
+                            records
+                                .iter()
+                                .map(|r| r.to_string())
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        ),
+                    ],
+                ));
+            }
+        }
+
+        Ok(loot)
+    }
+
+    async fn records_attempt(&self, domain: &str) -> Result<Vec<Loot>, Error> {
+        let mut loot = vec![];
+        for record_type in &self.record_types {
+            let records = if *record_type == RecordType::SRV {
+                let mut records = vec![];
+                for service in &self.srv_services {
+                    records.extend(
+                        self.lookup(&format!("{}.{}", service, domain), RecordType::SRV)
+                            .await,
+                    );
+                }
+                records
+            } else {
+                self.lookup(domain, *record_type).await
+            };
+
+            // resolvers follow CNAMEs, only keep the answers of the requested type (or anything for ANY)
+            let records: Vec<String> = records
+                .iter()
+                .filter(|r| *record_type == RecordType::ANY || r.record_type() == *record_type)
+                .map(|r| r.to_string())
+                .collect();
+
+            if !records.is_empty() {
+                loot.push(Loot::new(
+                    "dns.records",
+                    domain,
+                    [
+                        ("type".to_owned(), record_type.to_string()),
+                        ("records".to_owned(), records.join("\n")),
+                    ],
+                ));
+            }
+        }
+
+        Ok(loot)
+    }
+}
+
+fn transfer_query(domain: &str, id: u16) -> Result<Vec<u8>, Error> {
+    let name = Name::from_ascii(format!("{}.", domain.trim_end_matches('.')))
+        .map_err(|e| e.to_string())?;
+
+    let mut message = Message::new();
+    message
+        .set_id(id)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(false)
+        .add_query(Query::query(name, RecordType::AXFR));
+
+    let data = message.to_vec().map_err(|e| e.to_string())?;
+    // tcp messages are prefixed by their length
+    let mut framed = (data.len() as u16).to_be_bytes().to_vec();
+    framed.extend(data);
+    Ok(framed)
+}
+
+async fn transfer<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    domain: &str,
+    id: u16,
+) -> Result<Vec<Record>, Error> {
+    stream
+        .write_all(&transfer_query(domain, id)?)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // the zone is sent as one or more messages, starting and ending with the SOA record
+    let mut records = vec![];
+    let mut soas = 0;
+    for _ in 0..MAX_TRANSFER_MESSAGES {
+        let size = match stream.read_u16().await {
+            Ok(size) => size as usize,
+            // some servers just close the connection after the last message
+            Err(_) if soas > 0 => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut data = vec![0u8; size];
+        stream
+            .read_exact(&mut data)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let message = Message::from_vec(&data).map_err(|e| e.to_string())?;
+        if message.id() != id {
+            return Err("unexpected message id".to_owned());
+        } else if message.response_code() != ResponseCode::NoError {
+            return Err(format!("transfer refused: {}", message.response_code()));
+        }
+
+        for record in message.answers() {
+            if record.record_type() == RecordType::SOA {
+                soas += 1;
+            }
+            // the closing SOA repeats the opening one
+            if soas < 2 {
+                records.push(record.clone());
+            }
+        }
+
+        if soas >= 2 {
+            break;
+        }
+    }
+
+    if records.is_empty() {
+        Err("empty transfer".to_owned())
+    } else {
+        Ok(records)
+    }
+}
+
+async fn transfer_from(address: &str, domain: &str, timeout: Duration) -> Result<Vec<Record>, Error> {
+    let mut stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+    tokio::time::timeout(timeout, transfer(&mut stream, domain, rand::random()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[async_trait]
+impl Plugin for Zone {
+    fn description(&self) -> &'static str {
+        match self.mode {
+            Mode::Transfer => "DNS zone transfer (AXFR) from the authoritative nameservers.",
+            Mode::Records => "DNS records sweep (ANY, MX, TXT, SRV and more).",
+        }
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "survey".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.resolver = super::build_resolvers(opts)?.into_iter().next();
+        self.port = opts.dns.dns_port;
+        self.record_types = opts
+            .dns
+            .dns_record_types
+            .split(',')
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<RecordType>()
+                    .map_err(|e| format!("invalid record type '{}': {}", s, e))
+            })
+            .collect::<Result<Vec<RecordType>, Error>>()?;
+        self.srv_services = opts
+            .dns
+            .dns_srv_services
+            .split(',')
+            .map(|s| s.trim().to_owned())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let domain = creds.target.trim_end_matches('.').to_lowercase();
+        let loot = match self.mode {
+            Mode::Transfer => self.transfer_attempt(&domain, timeout).await?,
+            Mode::Records => self.records_attempt(&domain).await?,
+        };
+
+        Ok(if loot.is_empty() { None } else { Some(loot) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use trust_dns_resolver::proto::op::{Message, MessageType, ResponseCode};
+    use trust_dns_resolver::proto::rr::rdata::{A, SOA};
+    use trust_dns_resolver::proto::rr::{Name, RData, Record};
+
+    use super::{transfer, transfer_query};
+
+    fn response(id: u16, code: ResponseCode, answers: Vec<Record>) -> Vec<u8> {
+        let mut message = Message::new();
+        message
+            .set_id(id)
+            .set_message_type(MessageType::Response)
+            .set_response_code(code)
+            .add_answers(answers);
+        let data = message.to_vec().unwrap();
+        let mut framed = (data.len() as u16).to_be_bytes().to_vec();
+        framed.extend(data);
+        framed
+    }
+
+    fn soa(zone: &Name) -> Record {
+        Record::from_rdata(
+            zone.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns1.example.com.").unwrap(),
+                Name::from_str("admin.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        )
+    }
+
+    #[tokio::test]
+    async fn can_transfer_zone() {
+        let zone = Name::from_str("example.com.").unwrap();
+        let www = Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            300,
+            RData::A(A(Ipv4Addr::new(10, 0, 0, 1))),
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&transfer_query("example.com", 1234).unwrap())
+            .read(&response(
+                1234,
+                ResponseCode::NoError,
+                vec![soa(&zone), www.clone()],
+            ))
+            .read(&response(1234, ResponseCode::NoError, vec![soa(&zone)]))
+            .build();
+
+        let records = transfer(&mut stream, "example.com", 1234).await.unwrap();
+        assert_eq!(records, vec![soa(&zone), www]);
+    }
+
+    #[tokio::test]
+    async fn can_detect_refused_transfer() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&transfer_query("example.com", 1).unwrap())
+            .read(&response(1, ResponseCode::Refused, vec![]))
+            .build();
+
+        assert!(transfer(&mut stream, "example.com", 1).await.is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Identity, StatusCode};
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+mod registry;
+
+super::manager::register_plugin! {
+    "docker" => Docker::new(),
+    "docker.registry" => registry::DockerRegistry::new()
+}
+
+// engine details from the /version and /info endpoints
+fn parse_engine_info(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    for (key, name) in [
+        ("Version", "version"),
+        ("ApiVersion", "api_version"),
+        ("Os", "os"),
+        ("Name", "hostname"),
+        ("Containers", "containers"),
+        ("Images", "images"),
+    ] {
+        match json.get(key) {
+            Some(serde_json::Value::String(value)) => {
+                info.push((name.to_owned(), value.to_owned()))
+            }
+            Some(serde_json::Value::Number(value)) => {
+                info.push((name.to_owned(), value.to_string()))
+            }
+            _ => {}
+        }
+    }
+    info
+}
+
+#[derive(Clone)]
+pub(crate) struct Docker {
+    opts: options::Options,
+    client: Client,
+    // client authenticating with the --docker-cert certificate
+    cert_client: Option<Client>,
+}
+
+impl Docker {
+    pub fn new() -> Self {
+        Docker {
+            opts: options::Options::default(),
+            client: Client::new(),
+            cert_client: None,
+        }
+    }
+
+    async fn engine_info(
+        &self,
+        client: &Client,
+        base: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<(String, String)>>, Error> {
+        let response = client
+            .get(format!("{}/version", base))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+
+        let mut info = parse_engine_info(&response.text().await.unwrap_or_default());
+        if info.is_empty() {
+            // not a docker engine
+            return Ok(None);
+        }
+        if let Ok(response) = client
+            .get(format!("{}/info", base))
+            .timeout(timeout)
+            .send()
+            .await
+        {
+            info.extend(
+                parse_engine_info(&response.text().await.unwrap_or_default())
+                    .into_iter()
+                    .filter(|(key, _)| key != "version" && key != "os"),
+            );
+        }
+        Ok(Some(info))
+    }
+}
+
+#[async_trait]
+impl Plugin for Docker {
+    fn description(&self) -> &'static str {
+        "Docker Engine API unauthenticated access and client certificate detection."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    // there's no credential to test, only run once per target
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "version".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.docker.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        self.cert_client = match (&self.opts.docker_cert, &self.opts.docker_key) {
+            (Some(cert), Some(key)) => {
+                let cert = std::fs::read(cert).map_err(|e| e.to_string())?;
+                let key = std::fs::read(key).map_err(|e| e.to_string())?;
+                let identity = Identity::from_pkcs8_pem(&cert, &key).map_err(|e| e.to_string())?;
+                Some(
+                    Client::builder()
+                        .danger_accept_invalid_certs(true)
+                        .identity(identity)
+                        .build()
+                        .map_err(|e| e.to_string())?,
+                )
+            }
+            (None, None) => None,
+            _ => return Err("both --docker-cert and --docker-key are required".to_owned()),
+        };
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 2375)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.docker_ssl || port == 2376 || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        let (authentication, info) = match self.engine_info(&self.client, &base, timeout).await {
+            Ok(Some(info)) => ("none", info),
+            // TLS sockets usually require a client certificate
+            result => match (&self.cert_client, ssl) {
+                (Some(cert_client), true) => {
+                    if let Err(e) = result {
+                        log::debug!("docker engine {} without certificate: {}", &address, e);
+                    }
+                    match self.engine_info(cert_client, &base, timeout).await? {
+                        Some(info) => ("client certificate", info),
+                        None => return Ok(None),
+                    }
+                }
+                _ => return result.map(|_| None),
+            },
+        };
+
+        let mut data = vec![("authentication".to_owned(), authentication.to_owned())];
+        data.extend(info);
+        Ok(Some(vec![Loot::new("docker", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_engine_info;
+
+    #[test]
+    fn can_parse_engine_info() {
+        assert_eq!(
+            parse_engine_info(
+                r#"{"Platform":{"Name":"Docker Engine - Community"},"Version":"24.0.7","ApiVersion":"1.43","Os":"linux","Arch":"amd64"}"#
+            ),
+            vec![
+                ("version".to_owned(), "24.0.7".to_owned()),
+                ("api_version".to_owned(), "1.43".to_owned()),
+                ("os".to_owned(), "linux".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_engine_info(r#"{"Name":"build-01","Containers":12,"Images":40}"#),
+            vec![
+                ("hostname".to_owned(), "build-01".to_owned()),
+                ("containers".to_owned(), "12".to_owned()),
+                ("images".to_owned(), "40".to_owned()),
+            ]
+        );
+        assert!(parse_engine_info("404 page not found").is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for the Docker Engine API (automatically enabled for https:// targets and port 2376).
+    pub docker_ssl: bool,
+    #[clap(long)]
+    /// PEM client certificate to attempt Docker Engine TLS authentication with.
+    pub docker_cert: Option<String>,
+    #[clap(long)]
+    /// PEM PKCS#8 private key of the Docker Engine client certificate.
+    pub docker_key: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for the Docker Registry (automatically enabled for https:// targets and port 443).
+    pub docker_registry_ssl: bool,
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::WWW_AUTHENTICATE;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+// maximum number of repositories to report
+const MAX_REPOSITORIES: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Auth {
+    None,
+    Basic,
+    // token server realm and service
+    Bearer(String, Option<String>),
+}
+
+// parses the WWW-Authenticate header of the /v2/ endpoint
+fn parse_challenge(header: &str) -> Option<Auth> {
+    let (scheme, params) = header.trim().split_once(' ').unwrap_or((header, ""));
+    if scheme.eq_ignore_ascii_case("basic") {
+        return Some(Auth::Basic);
+    } else if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    let mut realm = None;
+    let mut service = None;
+    for param in params.split(',') {
+        if let Some((key, value)) = param.split_once('=') {
+            let value = value.trim().trim_matches('"').to_owned();
+            match key.trim() {
+                "realm" => realm = Some(value),
+                "service" => service = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    realm.map(|realm| Auth::Bearer(realm, service))
+}
+
+fn parse_token(body: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    json.get("token")
+        .or_else(|| json.get("access_token"))?
+        .as_str()
+        .map(|t| t.to_owned())
+}
+
+fn parse_repositories(body: &str) -> Vec<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.get("repositories")?.as_array().map(|repos| {
+                repos
+                    .iter()
+                    .filter_map(|r| r.as_str().map(|r| r.to_owned()))
+                    .collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Clone)]
+pub(crate) struct DockerRegistry {
+    ssl: bool,
+    client: Client,
+    // authentication scheme of each target, checked once
+    auth: Arc<Mutex<HashMap<String, Auth>>>,
+}
+
+impl DockerRegistry {
+    pub fn new() -> Self {
+        DockerRegistry {
+            ssl: false,
+            client: Client::new(),
+            auth: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn repositories(&self, request: RequestBuilder) -> Option<String> {
+        let response = request.send().await.ok()?;
+        if response.status() != StatusCode::OK {
+            return None;
+        }
+        let repositories = parse_repositories(&response.text().await.unwrap_or_default());
+        if repositories.is_empty() {
+            None
+        } else {
+            Some(repositories.join(", "))
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for DockerRegistry {
+    fn description(&self) -> &'static str {
+        "Docker Registry v2 basic and token authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.ssl = opts.docker.docker_registry_ssl;
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 5000)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.ssl || port == 443 || creds.target.starts_with("https://");
+        let base = format!("{}://{}/v2", if ssl { "https" } else { "http" }, &address);
+        let catalog = format!("{}/_catalog?n={}", &base, MAX_REPOSITORIES);
+
+        let auth = {
+            // hold the lock so that the target is only checked once
+            let mut cache = self.auth.lock().await;
+            if let Some(auth) = cache.get(&address) {
+                auth.clone()
+            } else {
+                let response = self
+                    .client
+                    .get(format!("{}/", &base))
+                    .timeout(timeout)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let auth = match response.status() {
+                    StatusCode::OK => Auth::None,
+                    StatusCode::UNAUTHORIZED => response
+                        .headers()
+                        .get(WWW_AUTHENTICATE)
+                        .and_then(|h| h.to_str().ok())
+                        .and_then(parse_challenge)
+                        .ok_or("unsupported docker registry authentication".to_owned())?,
+                    status => return Err(format!("unexpected docker registry status {}", status)),
+                };
+                cache.insert(address.clone(), auth.clone());
+
+                if auth == Auth::None {
+                    let mut data = vec![("authentication".to_owned(), "none".to_owned())];
+                    if let Some(repositories) = self
+                        .repositories(self.client.get(&catalog).timeout(timeout))
+                        .await
+                    {
+                        data.push(("repositories".to_owned(), repositories));
+                    }
+                    return Ok(Some(vec![Loot::new("docker.registry", &address, data)]));
+                }
+                auth
+            }
+        };
+
+        let catalog_request = match auth {
+            // no point in trying credentials
+            Auth::None => return Ok(None),
+            Auth::Basic => {
+                let response = self
+                    .client
+                    .get(format!("{}/", &base))
+                    .basic_auth(&creds.username, Some(&creds.password))
+                    .timeout(timeout)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if response.status() != StatusCode::OK {
+                    return Ok(None);
+                }
+                self.client
+                    .get(&catalog)
+                    .basic_auth(&creds.username, Some(&creds.password))
+            }
+            Auth::Bearer(realm, service) => {
+                let mut query = vec![("scope", "registry:catalog:*".to_owned())];
+                if let Some(service) = service {
+                    query.push(("service", service));
+                }
+                let response = self
+                    .client
+                    .get(&realm)
+                    .query(&query)
+                    .basic_auth(&creds.username, Some(&creds.password))
+                    .timeout(timeout)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if response.status() != StatusCode::OK {
+                    return Ok(None);
+                }
+                let Some(token) = parse_token(&response.text().await.unwrap_or_default()) else {
+                    return Ok(None);
+                };
+                self.client.get(&catalog).bearer_auth(token)
+            }
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if let Some(repositories) = self.repositories(catalog_request.timeout(timeout)).await {
+            data.push(("repositories".to_owned(), repositories));
+        }
+
+        Ok(Some(vec![Loot::new("docker.registry", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_challenge, parse_repositories, parse_token, Auth};
+
+    #[test]
+    fn can_parse_challenges() {
+        assert_eq!(
+            parse_challenge(
+                r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io""#
+            ),
+            Some(Auth::Bearer(
+                "https://auth.docker.io/token".to_owned(),
+                Some("registry.docker.io".to_owned())
+            ))
+        );
+        assert_eq!(
+            parse_challenge(r#"Basic realm="Registry Realm""#),
+            Some(Auth::Basic)
+        );
+        assert_eq!(parse_challenge("Negotiate"), None);
+    }
+
+    #[test]
+    fn can_parse_responses() {
+        assert_eq!(
+            parse_token(r#"{"token":"abc","expires_in":300}"#),
+            Some("abc".to_owned())
+        );
+        assert_eq!(
+            parse_repositories(r#"{"repositories":["library/nginx","internal/api"]}"#),
+            vec!["library/nginx".to_owned(), "internal/api".to_owned()]
+        );
+    }
+}
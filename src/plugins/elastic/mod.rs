@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use reqwest::{Client, RequestBuilder, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+use crate::utils::once::OncePerTarget;
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "elastic" => Elastic::new()
+}
+
+// cluster name and version from the Elasticsearch root or Kibana status endpoint
+fn parse_info(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    let name = json
+        .get("cluster_name")
+        .or_else(|| json.get("name"))
+        .and_then(|v| v.as_str());
+    if let Some(name) = name {
+        info.push(("cluster_name".to_owned(), name.to_owned()));
+    }
+    if let Some(version) = json
+        .pointer("/version/number")
+        .and_then(|v| v.as_str())
+    {
+        info.push(("version".to_owned(), version.to_owned()));
+    }
+    info
+}
+
+// API keys are either the base64 encoded id:key or the id:key itself
+fn api_key_header(key: &str) -> String {
+    if key.contains(':') {
+        format!("ApiKey {}", BASE64_STANDARD.encode(key))
+    } else {
+        format!("ApiKey {}", key)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Elastic {
+    opts: options::Options,
+    client: Client,
+    // targets already checked for unauthenticated access
+    unauthenticated_checked: OncePerTarget,
+    // targets not requiring authentication
+    open: OncePerTarget,
+}
+
+impl Elastic {
+    pub fn new() -> Self {
+        Elastic {
+            opts: options::Options::default(),
+            client: Client::new(),
+            unauthenticated_checked: OncePerTarget::new(),
+            open: OncePerTarget::new(),
+        }
+    }
+
+    fn request(&self, url: &str, timeout: Duration) -> RequestBuilder {
+        self.client.get(url).timeout(timeout)
+    }
+}
+
+#[async_trait]
+impl Plugin for Elastic {
+    fn description(&self) -> &'static str {
+        "Elasticsearch and Kibana password and API key authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.opts.elastic_api_keys {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.elastic.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 9200)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.elastic_ssl || creds.target.starts_with("https://");
+        let path = if self.opts.elastic_kibana || port == 5601 {
+            "/api/status"
+        } else {
+            "/"
+        };
+        let url = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            path
+        );
+
+        if !self.unauthenticated_checked.contains(&address) {
+            let response = self
+                .request(&url, timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let loot = if response.status() == StatusCode::OK {
+                let mut data = vec![("authentication".to_owned(), "none".to_owned())];
+                data.extend(parse_info(&response.text().await.unwrap_or_default()));
+                self.open
+                    .report(&address, Loot::new("elastic", &address, data))
+            } else {
+                None
+            };
+            // marked only once the check completed, so transient errors are checked again
+            self.unauthenticated_checked.insert(&address);
+            if loot.is_some() {
+                return Ok(loot);
+            }
+        }
+
+        if self.open.contains(&address) {
+            // no point in trying credentials, every one of them would be accepted
+            return Ok(None);
+        }
+
+        let request = if self.opts.elastic_api_keys {
+            self.request(&url, timeout)
+                .header("Authorization", api_key_header(creds.single()))
+        } else {
+            self.request(&url, timeout)
+                .basic_auth(&creds.username, Some(&creds.password))
+        };
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if response.status() == StatusCode::OK {
+            let mut data = if self.opts.elastic_api_keys {
+                vec![("api_key".to_owned(), creds.single().to_owned())]
+            } else {
+                vec![
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ]
+            };
+            data.extend(parse_info(&response.text().await.unwrap_or_default()));
+            return Ok(Some(vec![Loot::new("elastic", &address, data)]));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{api_key_header, parse_info, Elastic};
+    use crate::creds::Credentials;
+    use crate::utils::testing::http_server;
+    use crate::Plugin;
+
+    #[tokio::test]
+    async fn skips_credentials_for_open_clusters() {
+        let info = r#"{"name":"node-1","cluster_name":"prod","version":{"number":"8.11.1"}}"#;
+        // the first probe fails, the second one finds the cluster open
+        let target = http_server(vec![None, Some((200, info))]).await;
+        let elastic = Elastic::new();
+        let creds = Credentials {
+            target,
+            username: "elastic".to_owned(),
+            password: "changeme".to_owned(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        assert!(elastic.attempt(&creds, timeout).await.is_err());
+        let loot = elastic.attempt(&creds, timeout).await.unwrap().unwrap();
+        assert_eq!(loot[0].get_value("authentication"), Some("none"));
+        // reported once, then every credential would be accepted
+        assert_eq!(elastic.attempt(&creds, timeout).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn can_find_valid_credentials() {
+        let info = r#"{"name":"node-1","cluster_name":"prod","version":{"number":"8.11.1"}}"#;
+        let target = http_server(vec![Some((401, "{}")), Some((200, info))]).await;
+        let elastic = Elastic::new();
+        let creds = Credentials {
+            target,
+            username: "elastic".to_owned(),
+            password: "changeme".to_owned(),
+            ..Default::default()
+        };
+
+        let loot = elastic
+            .attempt(&creds, Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loot[0].get_value("username"), Some("elastic"));
+    }
+
+    #[test]
+    fn can_parse_cluster_info() {
+        assert_eq!(
+            parse_info(
+                r#"{"name":"node-1","cluster_name":"prod","version":{"number":"8.11.1"},"tagline":"You Know, for Search"}"#
+            ),
+            vec![
+                ("cluster_name".to_owned(), "prod".to_owned()),
+                ("version".to_owned(), "8.11.1".to_owned())
+            ]
+        );
+        // kibana status
+        assert_eq!(
+            parse_info(r#"{"name":"kibana","version":{"number":"7.17.0"}}"#),
+            vec![
+                ("cluster_name".to_owned(), "kibana".to_owned()),
+                ("version".to_owned(), "7.17.0".to_owned())
+            ]
+        );
+        assert!(parse_info("<html>").is_empty());
+    }
+
+    #[test]
+    fn can_encode_api_keys() {
+        assert_eq!(api_key_header("id:key"), "ApiKey aWQ6a2V5");
+        assert_eq!(api_key_header("aWQ6a2V5"), "ApiKey aWQ6a2V5");
+    }
+}
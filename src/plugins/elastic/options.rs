@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Elasticsearch and Kibana (automatically enabled for https:// targets).
+    pub elastic_ssl: bool,
+    #[clap(long, default_value_t = false)]
+    /// Target is Kibana instead of Elasticsearch (automatically enabled for port 5601).
+    pub elastic_kibana: bool,
+    #[clap(long, default_value_t = false)]
+    /// Test API keys (base64 encoded or as id:key) as single payloads instead of username and password.
+    pub elastic_api_keys: bool,
+}
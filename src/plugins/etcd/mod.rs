@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Identity, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+use crate::utils::once::OncePerTarget;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "etcd" => Etcd::new()
+}
+
+// v3 gRPC gateway endpoints
+const AUTHENTICATE_PATH: &str = "/v3/auth/authenticate";
+const RANGE_PATH: &str = "/v3/kv/range";
+
+// counts every key, "\0" as both key and range end selects the whole keyspace
+const COUNT_KEYS: &str = r#"{"key":"AA==","range_end":"AA==","count_only":true}"#;
+
+fn authenticate_request(username: &str, password: &str) -> String {
+    serde_json::json!({ "name": username, "password": password }).to_string()
+}
+
+fn parse_token(body: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()?
+        .get("token")?
+        .as_str()
+        .map(|t| t.to_owned())
+}
+
+// the gateway omits zero values so an empty keyspace has no count
+fn parse_count(body: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    json.get("header")?;
+    Some(
+        json.get("count")
+            .and_then(|c| c.as_str())
+            .unwrap_or("0")
+            .to_owned(),
+    )
+}
+
+#[derive(Clone)]
+pub(crate) struct Etcd {
+    opts: options::Options,
+    client: Client,
+    // targets already checked for unauthenticated access
+    open_checked: OncePerTarget,
+    // targets with authentication disabled
+    open: OncePerTarget,
+}
+
+impl Etcd {
+    pub fn new() -> Self {
+        Etcd {
+            opts: options::Options::default(),
+            client: Client::new(),
+            open_checked: OncePerTarget::new(),
+            open: OncePerTarget::new(),
+        }
+    }
+
+    // number of keys readable with the optional token
+    async fn count_keys(
+        &self,
+        base: &str,
+        token: Option<&str>,
+        timeout: Duration,
+    ) -> Result<Option<String>, Error> {
+        let mut request = self
+            .client
+            .post(format!("{}{}", base, RANGE_PATH))
+            .header("Content-Type", "application/json")
+            .body(COUNT_KEYS)
+            .timeout(timeout);
+        if let Some(token) = token {
+            request = request.header("Authorization", token);
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+        Ok(parse_count(&response.text().await.unwrap_or_default()))
+    }
+}
+
+#[async_trait]
+impl Plugin for Etcd {
+    fn description(&self) -> &'static str {
+        "etcd v3 password and client certificate authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.etcd.clone();
+
+        let mut builder = Client::builder().danger_accept_invalid_certs(true);
+        match (&self.opts.etcd_cert, &self.opts.etcd_key) {
+            (Some(cert), Some(key)) => {
+                let cert = std::fs::read(cert).map_err(|e| e.to_string())?;
+                let key = std::fs::read(key).map_err(|e| e.to_string())?;
+                builder = builder
+                    .identity(Identity::from_pkcs8_pem(&cert, &key).map_err(|e| e.to_string())?);
+            }
+            (None, None) => {}
+            _ => return Err("both --etcd-cert and --etcd-key are required".to_owned()),
+        }
+        self.client = builder.build().map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 2379)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.etcd_ssl
+            || self.opts.etcd_cert.is_some()
+            || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        if !self.open_checked.contains(&address) {
+            let keys = self.count_keys(&base, None, timeout).await?;
+            // marked only once the check completed, so transient errors are checked again, and
+            // reported by the first worker completing it
+            let first = self.open_checked.insert(&address);
+            if let Some(keys) = keys.filter(|_| first) {
+                self.open.insert(&address);
+                let authentication = if self.opts.etcd_cert.is_some() {
+                    "client certificate"
+                } else {
+                    "none"
+                };
+                return Ok(Some(vec![Loot::new(
+                    "etcd",
+                    &address,
+                    [
+                        ("authentication".to_owned(), authentication.to_owned()),
+                        ("keys".to_owned(), keys),
+                    ],
+                )]));
+            }
+        }
+
+        if self.open.contains(&address) {
+            // no point in trying credentials
+            return Ok(None);
+        }
+
+        let response = self
+            .client
+            .post(format!("{}{}", base, AUTHENTICATE_PATH))
+            .header("Content-Type", "application/json")
+            .body(authenticate_request(&creds.username, &creds.password))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+        let Some(token) = parse_token(&response.text().await.unwrap_or_default()) else {
+            return Ok(None);
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if let Ok(Some(keys)) = self.count_keys(&base, Some(&token), timeout).await {
+            data.push(("keys".to_owned(), keys));
+        }
+
+        Ok(Some(vec![Loot::new("etcd", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{authenticate_request, parse_count, parse_token, Etcd};
+    use crate::creds::Credentials;
+    use crate::Plugin;
+
+    #[tokio::test]
+    async fn checks_open_targets_again_after_errors() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            // the first connection is dropped
+            drop(listener.accept().await.unwrap());
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = [0u8; 1024];
+                let _ = stream.read(&mut buffer).await.unwrap();
+                let body = r#"{"header":{"cluster_id":"1"},"count":"42"}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let etcd = Etcd::new();
+        let creds = Credentials {
+            target,
+            username: "root".to_owned(),
+            password: "root".to_owned(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        assert!(etcd.attempt(&creds, timeout).await.is_err());
+        let loot = etcd.attempt(&creds, timeout).await.unwrap().unwrap();
+        assert_eq!(loot[0].get_value("authentication"), Some("none"));
+        assert_eq!(loot[0].get_value("keys"), Some("42"));
+        // reported once, then credentials are pointless
+        assert_eq!(etcd.attempt(&creds, timeout).await, Ok(None));
+    }
+
+    #[test]
+    fn can_build_requests() {
+        assert_eq!(
+            authenticate_request("root", "pa\"ss"),
+            r#"{"name":"root","password":"pa\"ss"}"#
+        );
+    }
+
+    #[test]
+    fn can_parse_responses() {
+        assert_eq!(
+            parse_token(r#"{"header":{"cluster_id":"1","member_id":"2","revision":"3","raft_term":"4"},"token":"abc.123"}"#),
+            Some("abc.123".to_owned())
+        );
+        assert_eq!(
+            parse_count(r#"{"header":{"cluster_id":"1","revision":"9"},"count":"42"}"#),
+            Some("42".to_owned())
+        );
+        assert_eq!(
+            parse_count(r#"{"header":{"cluster_id":"1","revision":"1"}}"#),
+            Some("0".to_owned())
+        );
+        assert_eq!(
+            parse_count(r#"{"error":"etcdserver: user name is empty","code":3}"#),
+            None
+        );
+    }
+}
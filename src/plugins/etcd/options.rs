@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for etcd (automatically enabled for https:// targets and when a client certificate is set).
+    pub etcd_ssl: bool,
+    #[clap(long)]
+    /// PEM client certificate to authenticate to etcd with.
+    pub etcd_cert: Option<String>,
+    #[clap(long)]
+    /// PEM PKCS#8 private key of the etcd client certificate.
+    pub etcd_key: Option<String>,
+}
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use reqwest::{redirect, Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "exchange" => Exchange::new()
+}
+
+// the NTLM token of a "WWW-Authenticate: NTLM <token>" or "Negotiate <token>" header
+fn challenge_token<'a, I: Iterator<Item = &'a str>>(values: I) -> Result<Vec<u8>, Error> {
+    let token = values
+        .filter_map(|h| h.strip_prefix("NTLM ").or_else(|| h.strip_prefix("Negotiate ")))
+        .next()
+        .ok_or("response missing NTLM challenge header")?;
+    BASE64_STANDARD.decode(token.trim()).map_err(|e| e.to_string())
+}
+
+// DOMAIN\user overrides the domain option
+fn split_domain<'a>(username: &'a str, default: &'a str) -> (&'a str, &'a str) {
+    match username.split_once('\\') {
+        Some((domain, user)) => (domain, user),
+        None => (default, username),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Exchange {
+    opts: options::Options,
+    // targets whose internal names have already been reported
+    disclosed: OncePerTarget,
+}
+
+impl Exchange {
+    pub fn new() -> Self {
+        Exchange {
+            opts: options::Options::default(),
+            disclosed: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Exchange {
+    fn description(&self) -> &'static str {
+        "Exchange OWA and EWS NTLM authentication with internal domain disclosure."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.exchange.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 443)?;
+        let url = format!("https://{}{}", &address, &self.opts.exchange_path);
+
+        // NTLM authenticates the connection, so the client must not open new ones
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .pool_max_idle_per_host(1)
+            .redirect(redirect::Policy::none())
+            .timeout(timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let negotiate = utils::ntlm::negotiate(&self.opts.exchange_workstation)?;
+        let response = client
+            .get(&url)
+            .header(
+                "Authorization",
+                format!("NTLM {}", BASE64_STANDARD.encode(negotiate)),
+            )
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let challenge = challenge_token(
+            response
+                .headers()
+                .get_all("www-authenticate")
+                .iter()
+                .filter_map(|h| h.to_str().ok()),
+        )?;
+
+        let disclosure = utils::ntlm::disclosure(&challenge)?;
+
+        let netbios_domain = disclosure
+            .iter()
+            .find(|(key, _)| key == "netbios_domain")
+            .map(|(_, value)| value.as_str())
+            .unwrap_or_default();
+        let (domain, username) = split_domain(
+            &creds.username,
+            if self.opts.exchange_domain.is_empty() {
+                netbios_domain
+            } else {
+                &self.opts.exchange_domain
+            },
+        );
+
+        let hash = creds.ntlm_hash()?;
+        let authenticate = utils::ntlm::authenticate(
+            &challenge,
+            username,
+            domain,
+            &self.opts.exchange_workstation,
+            &creds.password,
+            hash.as_deref(),
+        )?;
+        let response = client
+            .get(&url)
+            .header(
+                "Authorization",
+                format!("NTLM {}", BASE64_STANDARD.encode(authenticate)),
+            )
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // reported only along with a completed attempt, failed ones will be retried
+        let mut loot = self
+            .disclosed
+            .report(
+                &address,
+                Loot::new("exchange", &address, disclosure).set_partial(),
+            )
+            .unwrap_or_default();
+        if response.status() != StatusCode::UNAUTHORIZED {
+            let mut data = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ];
+            if let Some(version) = response
+                .headers()
+                .get("x-owa-version")
+                .and_then(|v| v.to_str().ok())
+            {
+                data.push(("version".to_owned(), version.to_owned()));
+            }
+            loot.push(Loot::new("exchange", &address, data));
+        }
+
+        Ok(if loot.is_empty() { None } else { Some(loot) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::prelude::{Engine, BASE64_STANDARD};
+
+    use super::{challenge_token, split_domain};
+
+    #[test]
+    fn can_parse_challenge_header() {
+        let token = BASE64_STANDARD.encode(b"NTLMSSP\0");
+        let ntlm = format!("NTLM {}", token);
+        assert_eq!(
+            challenge_token(["Negotiate", ntlm.as_str()].into_iter()).unwrap(),
+            b"NTLMSSP\0"
+        );
+        assert!(challenge_token(["Basic realm=\"mail\""].into_iter()).is_err());
+
+        assert_eq!(split_domain("CORP\\jdoe", "OTHER"), ("CORP", "jdoe"));
+        assert_eq!(split_domain("jdoe@corp.local", "CORP"), ("CORP", "jdoe@corp.local"));
+    }
+}
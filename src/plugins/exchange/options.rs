@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "/EWS/Exchange.asmx")]
+    /// NTLM protected path (/EWS/Exchange.asmx, /autodiscover/autodiscover.xml, /mapi/, /rpc/, ...).
+    pub exchange_path: String,
+    #[clap(long, default_value = "")]
+    /// Domain for NTLM authentication (defaults to the NetBIOS domain disclosed by the server).
+    pub exchange_domain: String,
+    #[clap(long, default_value = "LEGBA")]
+    /// Workstation name for NTLM authentication.
+    pub exchange_workstation: String,
+}
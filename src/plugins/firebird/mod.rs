@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+pub(crate) mod options;
+mod srp;
+mod wire;
+
+super::manager::register_plugin! {
+    "firebird" => Firebird::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct Firebird {
+    database: String,
+    legacy: bool,
+}
+
+impl Firebird {
+    pub fn new() -> Self {
+        Firebird {
+            database: String::new(),
+            legacy: false,
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Firebird {
+    fn description(&self) -> &'static str {
+        "Firebird legacy and SRP password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.database = opts.firebird.firebird_database.clone();
+        self.legacy = opts.firebird.firebird_legacy;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 3050)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+
+        let outcome = tokio::time::timeout(
+            timeout,
+            wire::authenticate(
+                &mut stream,
+                &self.database,
+                &creds.username,
+                &creds.password,
+                self.legacy,
+            ),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        match outcome {
+            wire::Outcome::Invalid => Ok(None),
+            wire::Outcome::Valid(method, protocol) => Ok(Some(vec![Loot::new(
+                "firebird",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                    ("authentication".to_owned(), method),
+                    ("protocol".to_owned(), protocol.to_string()),
+                ],
+            )])),
+        }
+    }
+}
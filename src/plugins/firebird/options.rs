@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "employee")]
+    /// Firebird database path or alias.
+    pub firebird_database: String,
+    #[clap(long, default_value_t = false)]
+    /// Only use the legacy authentication of protocols 10 to 12 instead of SRP.
+    pub firebird_legacy: bool,
+}
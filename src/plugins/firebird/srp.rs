@@ -0,0 +1,126 @@
+use num_bigint::BigUint;
+use rand::Rng;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+// group used by the Firebird Srp and Srp256 plugins
+const PRIME: &str = "E67D2E994B2F900C3F41F08F5BB2627ED0D49EE1FE767A52EFCD565CD6E768812C3E1E9CE8F0A8BEA6CB13CD29DDEBF7A96D4A93B55D488DF099A15C89DCB0640738EB2CBDD9A8F7BAB561AB1B0DC1C6CDABF303264A08D1BCA932D1F1EE428B619D970F342ABA9A65793B8B2F041AE5364350C16F735F56ECBCA87BD57B29E7";
+const GENERATOR: u32 = 2;
+
+fn prime() -> BigUint {
+    BigUint::parse_bytes(PRIME.as_bytes(), 16).unwrap()
+}
+
+fn sha1(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+fn sha1_int(parts: &[&BigUint]) -> BigUint {
+    let bytes: Vec<Vec<u8>> = parts.iter().map(|n| n.to_bytes_be()).collect();
+    let parts: Vec<&[u8]> = bytes.iter().map(|b| b.as_slice()).collect();
+    BigUint::from_bytes_be(&sha1(&parts))
+}
+
+// k = H(N, PAD(g))
+fn multiplier() -> BigUint {
+    let n = prime().to_bytes_be();
+    let mut g = vec![0u8; n.len()];
+    *g.last_mut().unwrap() = GENERATOR as u8;
+    BigUint::from_bytes_be(&sha1(&[&n, &g]))
+}
+
+pub(crate) struct Client {
+    private: BigUint,
+    pub public: BigUint,
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self::with_private(BigUint::from_bytes_be(&rand::thread_rng().gen::<[u8; 16]>()))
+    }
+
+    fn with_private(private: BigUint) -> Self {
+        let public = BigUint::from(GENERATOR).modpow(&private, &prime());
+        Client { private, public }
+    }
+
+    // client proof for the server salt and public key, the Srp256 plugin only changes its hash
+    pub fn proof(
+        &self,
+        account: &str,
+        password: &str,
+        salt: &[u8],
+        server: &BigUint,
+        sha256: bool,
+    ) -> Vec<u8> {
+        let n = prime();
+        let g = BigUint::from(GENERATOR);
+
+        let u = sha1_int(&[&self.public, server]);
+        let x = BigUint::from_bytes_be(&sha1(&[
+            salt,
+            &sha1(&[format!("{}:{}", account, password).as_bytes()]),
+        ]));
+
+        let kgx = (multiplier() * g.modpow(&x, &n)) % &n;
+        let diff = ((server % &n) + &n - kgx) % &n;
+        let exponent = (&self.private + u * &x) % &n;
+        let session_key = sha1(&[&diff.modpow(&exponent, &n).to_bytes_be()]);
+
+        let n1 = sha1_int(&[&n]).modpow(&sha1_int(&[&g]), &n);
+        let n2 = BigUint::from_bytes_be(&sha1(&[account.as_bytes()]));
+        let parts: [&[u8]; 6] = [
+            &n1.to_bytes_be(),
+            &n2.to_bytes_be(),
+            salt,
+            &self.public.to_bytes_be(),
+            &server.to_bytes_be(),
+            &session_key,
+        ];
+
+        if sha256 {
+            let mut hasher = Sha256::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        } else {
+            sha1(&parts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{multiplier, Client};
+
+    #[test]
+    fn has_the_right_group() {
+        assert_eq!(
+            multiplier().to_string(),
+            "1277432915985975349439481660349303019122249719989"
+        );
+    }
+
+    #[test]
+    fn proof_depends_on_password_and_hash() {
+        let client = Client::with_private(BigUint::from(12345u32));
+        let server = BigUint::from(0xdeadbeefu32);
+        let proof = client.proof("SYSDBA", "masterkey", b"salt", &server, false);
+        assert_eq!(proof.len(), 20);
+        assert_ne!(
+            proof,
+            client.proof("SYSDBA", "wrong", b"salt", &server, false)
+        );
+        assert_eq!(
+            client.proof("SYSDBA", "masterkey", b"salt", &server, true).len(),
+            32
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use num_bigint::BigUint;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+use super::srp;
+
+const OP_CONNECT: u32 = 1;
+const OP_ACCEPT: u32 = 3;
+const OP_REJECT: u32 = 4;
+const OP_RESPONSE: u32 = 9;
+const OP_ATTACH: u32 = 19;
+const OP_CONT_AUTH: u32 = 92;
+const OP_ACCEPT_DATA: u32 = 94;
+const OP_COND_ACCEPT: u32 = 98;
+
+const CONNECT_VERSION3: u32 = 3;
+const ARCH_GENERIC: u32 = 1;
+const PTYPE_LAZY_SEND: u32 = 5;
+
+// protocol versions 11 and up have the FB_PROTOCOL_FLAG set
+const PROTOCOL_10: u32 = 10;
+const PROTOCOL_11: u32 = 0xffff800b;
+const PROTOCOL_12: u32 = 0xffff800c;
+const PROTOCOL_13: u32 = 0xffff800d;
+
+const CNCT_USER: u8 = 1;
+const CNCT_HOST: u8 = 4;
+const CNCT_USER_VERIFICATION: u8 = 6;
+const CNCT_SPECIFIC_DATA: u8 = 7;
+const CNCT_PLUGIN_NAME: u8 = 8;
+const CNCT_LOGIN: u8 = 9;
+const CNCT_PLUGIN_LIST: u8 = 10;
+const CNCT_CLIENT_CRYPT: u8 = 11;
+
+const ISC_DPB_VERSION1: u8 = 1;
+const ISC_DPB_USER_NAME: u8 = 28;
+const ISC_DPB_PASSWORD: u8 = 29;
+const ISC_DPB_LC_CTYPE: u8 = 48;
+const ISC_DPB_SPECIFIC_AUTH_DATA: u8 = 84;
+
+const ISC_ARG_END: u32 = 0;
+const ISC_ARG_GDS: u32 = 1;
+const ISC_ARG_NUMBER: u32 = 4;
+
+// Your user name and password are not defined
+const ISC_LOGIN: u32 = 335544472;
+
+const PLUGIN_SRP256: &str = "Srp256";
+const PLUGIN_SRP: &str = "Srp";
+
+const MAX_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // authentication method and accepted protocol version
+    Valid(String, u32),
+    Invalid,
+}
+
+fn int(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+// XDR opaque data, padded to 4 bytes
+fn bytes(out: &mut Vec<u8>, value: &[u8]) {
+    int(out, value.len() as u32);
+    out.extend_from_slice(value);
+    out.resize(out.len() + (4 - value.len() % 4) % 4, 0);
+}
+
+fn cnct_param(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    if tag == CNCT_SPECIFIC_DATA {
+        // split in numbered chunks of 254 bytes
+        for (i, chunk) in value.chunks(254).enumerate() {
+            out.extend_from_slice(&[tag, chunk.len() as u8 + 1, i as u8]);
+            out.extend_from_slice(chunk);
+        }
+    } else {
+        out.extend_from_slice(&[tag, value.len().min(255) as u8]);
+        out.extend_from_slice(&value[..value.len().min(255)]);
+    }
+}
+
+fn connect(database: &str, login: &str, srp: Option<&srp::Client>) -> Vec<u8> {
+    let mut uid = vec![];
+    let mut protocols = vec![(PROTOCOL_10, 2), (PROTOCOL_11, 4), (PROTOCOL_12, 6)];
+    if let Some(srp) = srp {
+        cnct_param(&mut uid, CNCT_LOGIN, login.as_bytes());
+        cnct_param(&mut uid, CNCT_PLUGIN_NAME, PLUGIN_SRP256.as_bytes());
+        cnct_param(
+            &mut uid,
+            CNCT_PLUGIN_LIST,
+            format!("{}, {}", PLUGIN_SRP256, PLUGIN_SRP).as_bytes(),
+        );
+        cnct_param(
+            &mut uid,
+            CNCT_SPECIFIC_DATA,
+            hex::encode(srp.public.to_bytes_be()).as_bytes(),
+        );
+        // wire encryption disabled
+        cnct_param(&mut uid, CNCT_CLIENT_CRYPT, &0u32.to_le_bytes());
+        protocols.push((PROTOCOL_13, 8));
+    }
+    cnct_param(&mut uid, CNCT_USER, b"legba");
+    cnct_param(&mut uid, CNCT_HOST, b"legba");
+    cnct_param(&mut uid, CNCT_USER_VERIFICATION, b"");
+
+    let mut msg = vec![];
+    int(&mut msg, OP_CONNECT);
+    int(&mut msg, OP_ATTACH);
+    int(&mut msg, CONNECT_VERSION3);
+    int(&mut msg, ARCH_GENERIC);
+    bytes(&mut msg, database.as_bytes());
+    int(&mut msg, protocols.len() as u32);
+    bytes(&mut msg, &uid);
+    for (version, weight) in protocols {
+        for value in [version, ARCH_GENERIC, 0, PTYPE_LAZY_SEND, weight] {
+            int(&mut msg, value);
+        }
+    }
+    msg
+}
+
+fn dpb_param(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.extend_from_slice(&[tag, value.len().min(255) as u8]);
+    out.extend_from_slice(&value[..value.len().min(255)]);
+}
+
+fn attach(database: &str, username: &str, password: Option<&str>, auth_data: &[u8]) -> Vec<u8> {
+    let mut dpb = vec![ISC_DPB_VERSION1];
+    dpb_param(&mut dpb, ISC_DPB_LC_CTYPE, b"UTF8");
+    dpb_param(&mut dpb, ISC_DPB_USER_NAME, username.as_bytes());
+    if let Some(password) = password {
+        dpb_param(&mut dpb, ISC_DPB_PASSWORD, password.as_bytes());
+    }
+    if !auth_data.is_empty() {
+        dpb_param(
+            &mut dpb,
+            ISC_DPB_SPECIFIC_AUTH_DATA,
+            hex::encode(auth_data).as_bytes(),
+        );
+    }
+
+    let mut msg = vec![];
+    int(&mut msg, OP_ATTACH);
+    int(&mut msg, 0);
+    bytes(&mut msg, database.as_bytes());
+    bytes(&mut msg, &dpb);
+    msg
+}
+
+fn cont_auth(proof: &[u8], plugin: &str) -> Vec<u8> {
+    let mut msg = vec![];
+    int(&mut msg, OP_CONT_AUTH);
+    bytes(&mut msg, hex::encode(proof).as_bytes());
+    bytes(&mut msg, plugin.as_bytes());
+    bytes(&mut msg, format!("{}, {}", PLUGIN_SRP256, PLUGIN_SRP).as_bytes());
+    bytes(&mut msg, b"");
+    msg
+}
+
+async fn read_int<S: AsyncRead + Unpin>(stream: &mut S) -> Result<u32, Error> {
+    stream.read_u32().await.map_err(|e| e.to_string())
+}
+
+async fn read_bytes<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, Error> {
+    let len = read_int(stream).await? as usize;
+    if len > MAX_BUFFER_SIZE {
+        return Err(format!("invalid buffer size {}", len));
+    }
+    let mut data = vec![0u8; len + (4 - len % 4) % 4];
+    stream
+        .read_exact(&mut data)
+        .await
+        .map_err(|e| e.to_string())?;
+    data.truncate(len);
+    Ok(data)
+}
+
+// op_response status vector, returns the first error code if any
+async fn read_response<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Option<u32>, Error> {
+    let op = read_int(stream).await?;
+    if op != OP_RESPONSE {
+        return Err(format!("unexpected operation {}", op));
+    }
+    // object handle, object id and data
+    read_int(stream).await?;
+    let mut id = [0u8; 8];
+    stream
+        .read_exact(&mut id)
+        .await
+        .map_err(|e| e.to_string())?;
+    read_bytes(stream).await?;
+
+    let mut error = None;
+    loop {
+        match read_int(stream).await? {
+            ISC_ARG_END => return Ok(error),
+            ISC_ARG_GDS => {
+                let code = read_int(stream).await?;
+                if code != 0 && error.is_none() {
+                    error = Some(code);
+                }
+            }
+            ISC_ARG_NUMBER => {
+                read_int(stream).await?;
+            }
+            // strings, interpreted messages and sql states
+            _ => {
+                read_bytes(stream).await?;
+            }
+        }
+    }
+}
+
+// salt and server public key from the Srp plugin data
+fn parse_srp_data(data: &[u8]) -> Result<(Vec<u8>, BigUint), Error> {
+    let invalid = || "invalid SRP server data".to_owned();
+    let salt_len = u16::from_le_bytes(data.get(..2).ok_or_else(invalid)?.try_into().unwrap());
+    let salt = data
+        .get(2..2 + salt_len as usize)
+        .ok_or_else(invalid)?
+        .to_vec();
+    let key = data.get(4 + salt_len as usize..).ok_or_else(invalid)?;
+    let key = hex::decode(key).map_err(|e| e.to_string())?;
+    Ok((salt, BigUint::from_bytes_be(&key)))
+}
+
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    database: &str,
+    username: &str,
+    password: &str,
+    legacy: bool,
+) -> Result<Outcome, Error> {
+    // unquoted user names are case insensitive
+    let login = username.to_uppercase();
+    let client = if legacy { None } else { Some(srp::Client::new()) };
+
+    stream
+        .write_all(&connect(database, &login, client.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let op = read_int(stream).await?;
+    if op == OP_REJECT {
+        return Err("connection rejected".to_owned());
+    } else if op != OP_ACCEPT && op != OP_ACCEPT_DATA && op != OP_COND_ACCEPT {
+        return Err(format!("unexpected operation {}", op));
+    }
+    let version = read_int(stream).await? & 0xff;
+    // architecture and protocol type
+    read_int(stream).await?;
+    read_int(stream).await?;
+
+    let (method, password, auth_data) = if op == OP_ACCEPT {
+        // protocols up to 12, the server checks the password on attach
+        ("legacy".to_owned(), Some(password), vec![])
+    } else {
+        let data = read_bytes(stream).await?;
+        let plugin = String::from_utf8_lossy(&read_bytes(stream).await?).to_string();
+        // authenticated flag and keys
+        read_int(stream).await?;
+        read_bytes(stream).await?;
+
+        let Some(client) = &client else {
+            return Err("unexpected authentication plugin data".to_owned());
+        };
+        if plugin != PLUGIN_SRP256 && plugin != PLUGIN_SRP {
+            return Err(format!(
+                "unsupported authentication plugin {}, try the legacy mode",
+                plugin
+            ));
+        }
+
+        let (salt, server) = parse_srp_data(&data)?;
+        let proof = client.proof(&login, password, &salt, &server, plugin == PLUGIN_SRP256);
+
+        if op == OP_COND_ACCEPT {
+            stream
+                .write_all(&cont_auth(&proof, &plugin))
+                .await
+                .map_err(|e| e.to_string())?;
+            match read_response(stream).await? {
+                None => {}
+                Some(ISC_LOGIN) => return Ok(Outcome::Invalid),
+                Some(code) => return Err(format!("authentication error {}", code)),
+            }
+        }
+
+        (plugin, None, proof)
+    };
+
+    stream
+        .write_all(&attach(database, &login, password, &auth_data))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match read_response(stream).await? {
+        None => Ok(Outcome::Valid(method, version)),
+        Some(ISC_LOGIN) => Ok(Outcome::Invalid),
+        // authenticated, but the database can't be attached
+        Some(code) => {
+            log::debug!("can't attach {}: error {}", database, code);
+            Ok(Outcome::Valid(method, version))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attach, authenticate, bytes, connect, int, parse_srp_data, Outcome};
+
+    fn response(error: u32) -> Vec<u8> {
+        let mut msg = vec![];
+        for value in [9, 0, 0, 0, 0] {
+            int(&mut msg, value);
+        }
+        if error != 0 {
+            int(&mut msg, 1);
+            int(&mut msg, error);
+        }
+        int(&mut msg, 0);
+        msg
+    }
+
+    #[test]
+    fn can_encode_xdr() {
+        let mut out = vec![];
+        bytes(&mut out, b"employee!");
+        assert_eq!(out, b"\x00\x00\x00\x09employee!\x00\x00\x00");
+
+        let msg = connect("employee", "SYSDBA", None);
+        assert_eq!(&msg[..16], &[0, 0, 0, 1, 0, 0, 0, 19, 0, 0, 0, 3, 0, 0, 0, 1]);
+        // three legacy protocols
+        assert_eq!(&msg[28..32], &[0, 0, 0, 3]);
+    }
+
+    #[test]
+    fn can_parse_srp_data() {
+        let mut data = vec![4, 0];
+        data.extend_from_slice(b"abcd");
+        data.extend_from_slice(&[4, 0]);
+        data.extend_from_slice(b"0102");
+        let (salt, key) = parse_srp_data(&data).unwrap();
+        assert_eq!(salt, b"abcd");
+        assert_eq!(key, 0x0102u32.into());
+    }
+
+    #[tokio::test]
+    async fn can_authenticate_legacy() {
+        let mut accept = vec![];
+        for value in [3, 0xffff800c, 1, 5] {
+            int(&mut accept, value);
+        }
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&connect("employee", "SYSDBA", None))
+            .read(&accept)
+            .write(&attach("employee", "SYSDBA", Some("masterkey"), &[]))
+            .read(&response(0))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "employee", "sysdba", "masterkey", true).await,
+            Ok(Outcome::Valid("legacy".to_owned(), 12))
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&connect("employee", "SYSDBA", None))
+            .read(&accept)
+            .write(&attach("employee", "SYSDBA", Some("wrong"), &[]))
+            .read(&response(335544472))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "employee", "sysdba", "wrong", true).await,
+            Ok(Outcome::Invalid)
+        );
+    }
+}
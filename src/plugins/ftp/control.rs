@@ -0,0 +1,157 @@
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::session::Error;
+
+const MAX_REPLY_LINES: usize = 256;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Reply {
+    pub code: u32,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    // text of the reply without the status codes
+    pub fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| {
+                line.get(4..)
+                    .filter(|_| line.starts_with(&self.code.to_string()))
+                    .unwrap_or(line)
+                    .trim()
+            })
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+}
+
+// reads a single or multi line reply, the latter ends with "<code> <text>"
+pub(crate) async fn read_reply<S: AsyncRead + Unpin>(
+    reader: &mut BufReader<S>,
+) -> Result<Reply, Error> {
+    let mut lines = vec![];
+    loop {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            return Err("connection closed".to_owned());
+        }
+        let line = line.trim_end().to_owned();
+
+        if lines.is_empty() {
+            let code = line
+                .get(..3)
+                .and_then(|c| c.parse::<u32>().ok())
+                .ok_or_else(|| format!("invalid FTP reply: {}", &line))?;
+            let last = line.as_bytes().get(3) != Some(&b'-');
+            lines.push(line);
+            if last {
+                return Ok(Reply { code, lines });
+            }
+        } else {
+            let code = lines[0][..3].to_owned();
+            let last = line.starts_with(&code) && line.as_bytes().get(3) == Some(&b' ');
+            lines.push(line);
+            if last {
+                let code = code.parse().unwrap();
+                return Ok(Reply { code, lines });
+            } else if lines.len() > MAX_REPLY_LINES {
+                return Err("FTP reply too long".to_owned());
+            }
+        }
+    }
+}
+
+pub(crate) async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    command: &str,
+) -> Result<Reply, Error> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    read_reply(reader).await
+}
+
+// returns true if the credentials are valid
+pub(crate) async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    let reply = command(reader, &format!("USER {}", username)).await?;
+    let reply = match reply.code {
+        // no password required
+        230 => return Ok(true),
+        331 | 332 => command(reader, &format!("PASS {}", password)).await?,
+        // transient errors such as 421 too many connections
+        400..=499 => return Err(reply.text()),
+        _ => return Ok(false),
+    };
+
+    match reply.code {
+        202 | 230 => Ok(true),
+        400..=499 => Err(reply.text()),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::{login, read_reply, Reply};
+
+    #[tokio::test]
+    async fn can_read_multiline_replies() {
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .read(b"211-Features:\r\n AUTH TLS\r\n UTF8\r\n211 End\r\n")
+                .build(),
+        );
+        let reply = read_reply(&mut reader).await.unwrap();
+        assert_eq!(
+            reply,
+            Reply {
+                code: 211,
+                lines: vec![
+                    "211-Features:".to_owned(),
+                    " AUTH TLS".to_owned(),
+                    " UTF8".to_owned(),
+                    "211 End".to_owned()
+                ]
+            }
+        );
+        assert_eq!(reply.text(), "Features:\nAUTH TLS\nUTF8\nEnd");
+    }
+
+    #[tokio::test]
+    async fn can_login() {
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .write(b"USER admin\r\n")
+                .read(b"331 Please specify the password.\r\n")
+                .write(b"PASS admin\r\n")
+                .read(b"230 Login successful.\r\n")
+                .build(),
+        );
+        assert_eq!(login(&mut reader, "admin", "admin").await, Ok(true));
+
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .write(b"USER admin\r\n")
+                .read(b"331 Please specify the password.\r\n")
+                .write(b"PASS wrong\r\n")
+                .read(b"530 Login incorrect.\r\n")
+                .build(),
+        );
+        assert_eq!(login(&mut reader, "admin", "wrong").await, Ok(false));
+    }
+}
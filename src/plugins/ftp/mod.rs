@@ -1,8 +1,7 @@
-use async_ftp::FtpStream;
-
 use std::time::Duration;
 
 use async_trait::async_trait;
+use tokio::io::BufReader;
 
 use crate::session::{Error, Loot};
 use crate::utils;
@@ -11,26 +10,34 @@ use crate::Plugin;
 
 use crate::creds::Credentials;
 
+use self::options::Tls;
+
+mod control;
+pub(crate) mod options;
+
 super::manager::register_plugin! {
     "ftp" => FTP::new()
 }
 
 #[derive(Clone)]
-pub(crate) struct FTP {}
+pub(crate) struct FTP {
+    tls: Tls,
+}
 
 impl FTP {
     pub fn new() -> Self {
-        FTP {}
+        FTP { tls: Tls::Off }
     }
 }
 
 #[async_trait]
 impl Plugin for FTP {
     fn description(&self) -> &'static str {
-        "FTP password authentication."
+        "FTP and FTPS password authentication."
     }
 
-    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.tls = opts.ftp.ftp_tls;
         Ok(())
     }
 
@@ -39,24 +46,57 @@ impl Plugin for FTP {
         creds: &Credentials,
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
-        let address = utils::parse_target_address(&creds.target, 21)?;
-
-        let mut stream = tokio::time::timeout(timeout, FtpStream::connect(&address))
-            .await
-            .map_err(|e| e.to_string())?
-            .map_err(|e| e.to_string())?;
-
-        if stream.login(&creds.username, &creds.password).await.is_ok() {
-            Ok(Some(vec![Loot::new(
-                "ftp",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]))
-        } else {
-            Ok(None)
-        }
+        let default_port = if self.tls == Tls::Implicit { 990 } else { 21 };
+        let address = utils::parse_target_address(&creds.target, default_port)?;
+
+        let mut stream =
+            utils::net::async_tcp_stream(&address, timeout, self.tls == Tls::Implicit).await?;
+
+        tokio::time::timeout(timeout, async {
+            let mut reader = BufReader::new(stream);
+            let welcome = control::read_reply(&mut reader).await?;
+            if welcome.code != 220 {
+                return Err(format!("unexpected FTP greeting: {}", welcome.text()));
+            }
+
+            if self.tls == Tls::Explicit {
+                let reply = control::command(&mut reader, "AUTH TLS").await?;
+                if reply.code != 234 {
+                    return Err(format!("AUTH TLS failed: {}", reply.text()));
+                }
+                stream = utils::net::upgrade_tcp_stream_to_ssl(reader.into_inner(), timeout).await?;
+                reader = BufReader::new(stream);
+            }
+
+            if !control::login(&mut reader, &creds.username, &creds.password).await? {
+                return Ok(None);
+            }
+
+            let mut loot = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                ("banner".to_owned(), welcome.text()),
+            ];
+            // best effort post login information
+            if let Ok(reply) = control::command(&mut reader, "SYST").await {
+                if reply.code == 215 {
+                    loot.push(("system".to_owned(), reply.text()));
+                }
+            }
+            if let Ok(reply) = control::command(&mut reader, "FEAT").await {
+                if reply.code == 211 {
+                    let features: Vec<String> = reply.lines[1..reply.lines.len().saturating_sub(1)]
+                        .iter()
+                        .map(|line| line.trim().to_owned())
+                        .collect();
+                    loot.push(("features".to_owned(), features.join(", ")));
+                }
+            }
+            let _ = control::command(&mut reader, "QUIT").await;
+
+            Ok(Some(vec![Loot::new("ftp", &address, loot)]))
+        })
+        .await
+        .map_err(|e| e.to_string())?
     }
 }
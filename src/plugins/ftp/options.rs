@@ -0,0 +1,18 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Tls {
+    #[default]
+    Off,
+    Explicit,
+    Implicit,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, value_enum, default_value_t = Tls::Off)]
+    /// FTPS mode, explicit upgrades the connection with AUTH TLS while implicit connects over TLS (default port 990).
+    pub ftp_tls: Tls,
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "git" => Git::new()
+}
+
+const ADVERTISEMENT_CONTENT_TYPE: &str = "application/x-git-upload-pack-advertisement";
+
+fn repositories(opts: &options::Options, target: &str) -> Result<Vec<String>, Error> {
+    let repos: Vec<String> = match opts.git_repos.as_ref() {
+        Some(repos) => repos
+            .split(',')
+            .map(|repo| repo.trim().trim_matches('/').to_owned())
+            .filter(|repo| !repo.is_empty())
+            .collect(),
+        None => utils::parse_target_path(target).into_iter().collect(),
+    };
+    if repos.is_empty() {
+        Err("no repository to check, use --git-repos or a target url with the repository path".to_owned())
+    } else {
+        Ok(repos)
+    }
+}
+
+// the ref advertisement is only returned to clients that can fetch the repository
+fn is_readable(status: StatusCode, content_type: Option<&str>) -> bool {
+    status == StatusCode::OK
+        && content_type
+            .map(|ct| ct.starts_with(ADVERTISEMENT_CONTENT_TYPE))
+            .unwrap_or(false)
+}
+
+#[derive(Clone)]
+pub(crate) struct Git {
+    opts: options::Options,
+    client: Client,
+    // repositories urls and whether they can be cloned anonymously
+    public: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl Git {
+    pub fn new() -> Self {
+        Git {
+            opts: options::Options::default(),
+            client: Client::new(),
+            public: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn readable(
+        &self,
+        url: &str,
+        creds: Option<&Credentials>,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let mut request = self
+            .client
+            .get(format!("{}/info/refs?service=git-upload-pack", url))
+            .timeout(timeout);
+        if let Some(creds) = creds {
+            request = request.basic_auth(&creds.username, Some(&creds.password));
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        Ok(is_readable(
+            response.status(),
+            response
+                .headers()
+                .get("content-type")
+                .and_then(|ct| ct.to_str().ok()),
+        ))
+    }
+
+    // public repositories accept any credential, they're checked once
+    async fn is_public(&self, url: &str, timeout: Duration) -> Result<bool, Error> {
+        let mut public = self.public.lock().await;
+        if let Some(is_public) = public.get(url) {
+            return Ok(*is_public);
+        }
+        let is_public = self.readable(url, None, timeout).await?;
+        if is_public {
+            log::info!("{} can be cloned anonymously", url);
+        }
+        public.insert(url.to_owned(), is_public);
+        Ok(is_public)
+    }
+}
+
+#[async_trait]
+impl Plugin for Git {
+    fn description(&self) -> &'static str {
+        "Git smart HTTP authentication with repository read access check."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.git.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 80)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.git_ssl || port == 443 || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        let mut readable = vec![];
+        for repo in repositories(&self.opts, &creds.target)? {
+            let url = format!("{}/{}", &base, &repo);
+            if !self.is_public(&url, timeout).await?
+                && self.readable(&url, Some(creds), timeout).await?
+            {
+                readable.push(repo);
+            }
+        }
+
+        if readable.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(vec![Loot::new(
+            "git",
+            &address,
+            [
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                ("repositories".to_owned(), readable.join(", ")),
+            ],
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{is_readable, options, repositories};
+
+    #[test]
+    fn can_check_repositories() {
+        let opts = options::Options {
+            git_repos: Some("org/a.git, /org/b.git/,".to_owned()),
+            git_ssl: false,
+        };
+        assert_eq!(
+            repositories(&opts, "git.corp.local").unwrap(),
+            vec!["org/a.git".to_owned(), "org/b.git".to_owned()]
+        );
+        assert_eq!(
+            repositories(&options::Options::default(), "https://git.corp.local/org/c.git")
+                .unwrap(),
+            vec!["org/c.git".to_owned()]
+        );
+        assert!(repositories(&options::Options::default(), "git.corp.local").is_err());
+
+        assert!(is_readable(
+            StatusCode::OK,
+            Some("application/x-git-upload-pack-advertisement")
+        ));
+        // dumb http servers and login pages
+        assert!(!is_readable(StatusCode::OK, Some("text/html")));
+        assert!(!is_readable(StatusCode::UNAUTHORIZED, None));
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long)]
+    /// Comma separated list of repository paths to check (defaults to the path of the target url).
+    pub git_repos: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS (automatically enabled for port 443 and https:// targets).
+    pub git_ssl: bool,
+}
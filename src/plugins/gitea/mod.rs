@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{redirect, Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "gitea" => Gitea::new()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Valid,
+    // valid credentials, second factor required
+    TwoFactor,
+    Invalid,
+}
+
+// failed logins render the form again, successful ones redirect
+fn login_outcome(status: StatusCode, location: Option<&str>) -> Outcome {
+    match location {
+        Some(location) if status.is_redirection() => {
+            if location.contains("two_factor") || location.contains("webauthn") {
+                Outcome::TwoFactor
+            } else if location.contains("/user/login") {
+                Outcome::Invalid
+            } else {
+                Outcome::Valid
+            }
+        }
+        _ => Outcome::Invalid,
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Gitea {
+    opts: options::Options,
+    client: Client,
+}
+
+impl Gitea {
+    pub fn new() -> Self {
+        Gitea {
+            opts: options::Options::default(),
+            client: Client::new(),
+        }
+    }
+
+    // the version endpoint doesn't require authentication
+    async fn version(&self, base: &str, timeout: Duration) -> Option<String> {
+        let response = self
+            .client
+            .get(format!("{}/api/v1/version", base))
+            .timeout(timeout)
+            .send()
+            .await
+            .ok()?;
+        let json: serde_json::Value =
+            serde_json::from_str(&response.text().await.ok()?).ok()?;
+        json.get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_owned())
+    }
+}
+
+#[async_trait]
+impl Plugin for Gitea {
+    fn description(&self) -> &'static str {
+        "Gitea form login authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.gitea.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 3000)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.gitea_ssl || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.gitea_path.trim_end_matches('/')
+        );
+        let url = format!("{}/user/login", &base);
+
+        // the CSRF token is bound to the session cookie
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .cookie_store(true)
+            .redirect(redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let page = client
+            .get(&url)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+        let csrf = utils::html::input_value(&page, "_csrf")
+            .ok_or("no CSRF token in the gitea login page".to_owned())?;
+
+        let response = client
+            .post(&url)
+            .form(&[
+                ("_csrf", csrf.as_str()),
+                ("user_name", creds.username.as_str()),
+                ("password", creds.password.as_str()),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|l| l.to_str().ok());
+
+        let outcome = login_outcome(response.status(), location);
+        if outcome == Outcome::Invalid {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if let Some(version) = self.version(&base, timeout).await {
+            data.push(("version".to_owned(), version));
+        }
+        let loot = Loot::new("gitea", &address, data);
+        Ok(Some(vec![if outcome == Outcome::TwoFactor {
+            loot.set_partial()
+        } else {
+            loot
+        }]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{login_outcome, Outcome};
+
+    #[test]
+    fn can_detect_login_outcome() {
+        assert_eq!(login_outcome(StatusCode::SEE_OTHER, Some("/")), Outcome::Valid);
+        assert_eq!(
+            login_outcome(StatusCode::SEE_OTHER, Some("/user/two_factor")),
+            Outcome::TwoFactor
+        );
+        assert_eq!(login_outcome(StatusCode::OK, None), Outcome::Invalid);
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Gitea (automatically enabled for https:// targets).
+    pub gitea_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// Gitea base path when served from a sub path, e.g. /gitea.
+    pub gitea_path: String,
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{redirect, Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "gitlab" => GitLab::new()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Valid,
+    // valid credentials, second factor required
+    TwoFactor,
+    Invalid,
+}
+
+// successful logins redirect away from the sign in page, accounts with 2FA get the OTP form
+fn login_outcome(status: StatusCode, location: Option<&str>, body: &str) -> Outcome {
+    if status.is_redirection() {
+        if location.is_some_and(|l| !l.contains("/users/sign_in")) {
+            Outcome::Valid
+        } else {
+            Outcome::Invalid
+        }
+    } else if status == StatusCode::OK && body.contains("user[otp_attempt]") {
+        Outcome::TwoFactor
+    } else {
+        Outcome::Invalid
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct GitLab {
+    opts: options::Options,
+}
+
+impl GitLab {
+    pub fn new() -> Self {
+        GitLab {
+            opts: options::Options::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for GitLab {
+    fn description(&self) -> &'static str {
+        "GitLab form login authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.gitlab.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 443)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.gitlab_ssl || port == 443 || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.gitlab_path.trim_end_matches('/')
+        );
+        let url = format!("{}/users/sign_in", &base);
+
+        // the authenticity token is bound to the session cookie
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .cookie_store(true)
+            .redirect(redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let page = client
+            .get(&url)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+        let token = utils::html::input_value(&page, "authenticity_token")
+            .or_else(|| utils::html::meta_content(&page, "csrf-token"))
+            .ok_or("no authenticity token in the gitlab sign in page".to_owned())?;
+
+        let response = client
+            .post(&url)
+            .form(&[
+                ("authenticity_token", token.as_str()),
+                ("user[login]", creds.username.as_str()),
+                ("user[password]", creds.password.as_str()),
+                ("user[remember_me]", "0"),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            // rack attack kicked in, retry later
+            return Err("rate limited".to_owned());
+        }
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|l| l.to_str().ok())
+            .map(|l| l.to_owned());
+        let body = response.text().await.unwrap_or_default();
+
+        let outcome = login_outcome(status, location.as_deref(), &body);
+        if outcome == Outcome::Invalid {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if outcome == Outcome::Valid {
+            // the session cookie authenticates API requests
+            if let Ok(response) = client
+                .get(format!("{}/api/v4/version", &base))
+                .timeout(timeout)
+                .send()
+                .await
+            {
+                if let Some(version) = serde_json::from_str::<serde_json::Value>(
+                    &response.text().await.unwrap_or_default(),
+                )
+                .ok()
+                .and_then(|json| json.get("version")?.as_str().map(|v| v.to_owned()))
+                {
+                    data.push(("version".to_owned(), version));
+                }
+            }
+        }
+
+        let loot = Loot::new("gitlab", &address, data);
+        Ok(Some(vec![if outcome == Outcome::TwoFactor {
+            loot.set_partial()
+        } else {
+            loot
+        }]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{login_outcome, Outcome};
+
+    #[test]
+    fn can_detect_login_outcome() {
+        assert_eq!(
+            login_outcome(StatusCode::FOUND, Some("https://gitlab.local/"), ""),
+            Outcome::Valid
+        );
+        assert_eq!(
+            login_outcome(
+                StatusCode::FOUND,
+                Some("https://gitlab.local/users/sign_in"),
+                ""
+            ),
+            Outcome::Invalid
+        );
+        assert_eq!(
+            login_outcome(
+                StatusCode::OK,
+                None,
+                r#"<input name="user[otp_attempt]" id="user_otp_attempt">"#
+            ),
+            Outcome::TwoFactor
+        );
+        assert_eq!(
+            login_outcome(StatusCode::OK, None, "Invalid login or password."),
+            Outcome::Invalid
+        );
+    }
+}
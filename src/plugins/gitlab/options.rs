@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for GitLab (automatically enabled for https:// targets).
+    pub gitlab_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// GitLab base path when served from a sub path, e.g. /gitlab.
+    pub gitlab_path: String,
+}
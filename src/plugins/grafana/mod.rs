@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "grafana" => Grafana::new()
+}
+
+fn login_body(username: &str, password: &str) -> String {
+    serde_json::json!({ "user": username, "password": password }).to_string()
+}
+
+#[derive(Clone)]
+pub(crate) struct Grafana {
+    opts: options::Options,
+    client: Client,
+}
+
+impl Grafana {
+    pub fn new() -> Self {
+        Grafana {
+            opts: options::Options::default(),
+            client: Client::new(),
+        }
+    }
+
+    // the health endpoint doesn't require authentication
+    async fn version(&self, base: &str, timeout: Duration) -> Option<String> {
+        let response = self
+            .client
+            .get(format!("{}/api/health", base))
+            .timeout(timeout)
+            .send()
+            .await
+            .ok()?;
+        let json: serde_json::Value =
+            serde_json::from_str(&response.text().await.ok()?).ok()?;
+        json.get("version")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_owned())
+    }
+}
+
+#[async_trait]
+impl Plugin for Grafana {
+    fn description(&self) -> &'static str {
+        "Grafana password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.grafana.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 3000)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.grafana_ssl || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.grafana_path.trim_end_matches('/')
+        );
+
+        let response = self
+            .client
+            .post(format!("{}/login", base))
+            .header("Content-Type", "application/json")
+            .body(login_body(&creds.username, &creds.password))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let mut data = vec![
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ];
+                if let Some(version) = self.version(&base, timeout).await {
+                    data.push(("version".to_owned(), version));
+                }
+                Ok(Some(vec![Loot::new("grafana", &address, data)]))
+            }
+            // brute force protection kicked in, retry later
+            StatusCode::TOO_MANY_REQUESTS => Err(format!(
+                "rate limited: {}",
+                response.text().await.unwrap_or_default()
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::login_body;
+
+    #[test]
+    fn can_escape_login_body() {
+        assert_eq!(
+            login_body("admin", "pa\"ss"),
+            r#"{"user":"admin","password":"pa\"ss"}"#
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Grafana (automatically enabled for https:// targets).
+    pub grafana_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// Grafana base path when served from a sub path, e.g. /grafana.
+    pub grafana_path: String,
+}
@@ -1,17 +1,19 @@
+use std::net::SocketAddr;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use rand::seq::SliceRandom;
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, COOKIE, HOST, USER_AGENT},
-    multipart, redirect, Client, Method, RequestBuilder, Response,
+    multipart, redirect, Client, Method, RequestBuilder, Response, StatusCode,
 };
 use url::Url;
 
 use crate::session::{Error, Loot};
+use crate::utils::resolver;
 use crate::Options;
 
-use crate::creds::Credentials;
+use crate::creds::{Credentials, Expression};
 use crate::plugins::Plugin;
 
 use super::plugin::PayloadStrategy;
@@ -19,6 +21,7 @@ use super::plugin::PayloadStrategy;
 mod csrf;
 mod ntlm;
 pub(crate) mod options;
+mod otp;
 mod payload;
 mod placeholders;
 mod ua;
@@ -28,12 +31,16 @@ const HTTP_USERNAME_VAR: &str = "{$username}";
 const HTTP_PASSWORD_VAR: &str = "{$password}";
 const HTTP_PAYLOAD_VAR: &str = "{$payload}";
 
+// how many times an OTP code is submitted again after rate limiting or session loss
+const OTP_MAX_SUBMISSIONS: usize = 5;
+
 super::manager::register_plugin! {
     "http" => HTTP::new(Strategy::Request),
     "http.form" => HTTP::new(Strategy::Form),
     "http.basic" => HTTP::new(Strategy::BasicAuth),
     "http.ntlm1" => HTTP::new(Strategy::NLTMv1),
     "http.ntlm2" => HTTP::new(Strategy::NLTMv2),
+    "http.otp" => HTTP::new(Strategy::Otp),
     "http.enum" => HTTP::new(Strategy::Enumeration),
     "http.vhost" => HTTP::new(Strategy::VHostEnum)
 }
@@ -49,6 +56,7 @@ pub(crate) enum Strategy {
     BasicAuth,
     NLTMv1,
     NLTMv2,
+    Otp,
     Enumeration,
     VHostEnum,
 }
@@ -65,6 +73,8 @@ pub(crate) struct HTTP {
     client: Client,
 
     csrf: Option<csrf::Config>,
+    otp: Option<otp::Config>,
+    otp_codes: Option<Expression>,
 
     domain: String,
     workstation: String,
@@ -94,6 +104,8 @@ impl HTTP {
             strategy,
             client: Client::builder().no_proxy().build().unwrap(),
             csrf: None,
+            otp: None,
+            otp_codes: None,
             domain: String::new(),
             workstation: String::new(),
             success_codes: vec![200],
@@ -390,6 +402,65 @@ impl HTTP {
         }
     }
 
+    async fn http_otp_attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let otp = self.otp.as_ref().unwrap();
+        let target = self.get_target_url(creds)?;
+
+        // the same code is submitted again if it was not evaluated because of rate limiting or an expired session
+        for _ in 0..OTP_MAX_SUBMISSIONS {
+            let cookie = otp
+                .session(&self.client, self.setup_headers(), creds, timeout)
+                .await?;
+
+            let mut headers = self.setup_headers();
+            headers.append(
+                COOKIE,
+                HeaderValue::from_str(&cookie).map_err(|e| e.to_string())?,
+            );
+
+            let mut request = self
+                .client
+                .request(self.method.clone(), &target)
+                .headers(headers)
+                .timeout(timeout);
+            request = self.setup_request_body(creds, None, request);
+
+            let res = request.send().await.map_err(|e| e.to_string())?;
+            let status = res.status();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                otp.rate_limited(&creds.target, res.headers()).await;
+                continue;
+            } else if otp.is_session_lost(status, res.headers()) {
+                log::debug!("[{}] otp session lost", &creds.target);
+                otp.invalidate(&creds.target, &cookie).await;
+                continue;
+            }
+
+            return Ok(if self.is_success_response(creds, res).await.is_some() {
+                Some(vec![Loot::new(
+                    "http.otp",
+                    &target,
+                    [
+                        ("otp".to_owned(), creds.single().to_owned()),
+                        ("window".to_owned(), otp.window()),
+                        ("cookie".to_owned(), cookie),
+                    ],
+                )])
+            } else {
+                None
+            });
+        }
+
+        Err(format!(
+            "code {} could not be submitted: rate limited or session lost",
+            creds.single()
+        ))
+    }
+
     async fn http_enum_attempt(
         &self,
         creds: &Credentials,
@@ -510,6 +581,7 @@ impl Plugin for HTTP {
             Strategy::BasicAuth => "HTTP basic authentication.",
             Strategy::NLTMv1 => "NTLMv1 authentication over HTTP.",
             Strategy::NLTMv2 => "NTLMv2 authentication over HTTP.",
+            Strategy::Otp => "HTTP OTP brute force within an authenticated session.",
             Strategy::Enumeration => "HTTP pages enumeration.",
             Strategy::VHostEnum => "HTTP virtual host enumeration.",
         }
@@ -517,11 +589,15 @@ impl Plugin for HTTP {
 
     fn payload_strategy(&self) -> PayloadStrategy {
         match self.strategy {
-            Strategy::Enumeration | Strategy::VHostEnum => PayloadStrategy::Single,
+            Strategy::Enumeration | Strategy::VHostEnum | Strategy::Otp => PayloadStrategy::Single,
             _ => PayloadStrategy::UsernamePassword,
         }
     }
 
+    fn override_payload(&self) -> Option<Expression> {
+        self.otp_codes.clone()
+    }
+
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.user_agent = opts.http.http_ua.clone();
 
@@ -531,6 +607,35 @@ impl Plugin for HTTP {
             None
         };
 
+        if self.strategy == Strategy::Otp {
+            let Some(login_page) = opts.http.http_otp_login_page.as_ref() else {
+                return Err("no --http-otp-login-page specified".to_owned());
+            };
+            self.otp = Some(otp::Config::new(
+                login_page,
+                opts.http.http_otp_login_payload.clone(),
+                opts.http.http_otp_session_attempts,
+                opts.http.http_otp_rate_limit_wait,
+                opts.http.http_otp_period,
+            )?);
+
+            // try every code unless a payload was specified
+            if opts.username.is_none() && opts.password.is_none() {
+                let digits = opts.http.http_otp_digits;
+                if digits == 0 || digits > 10 {
+                    return Err("--http-otp-digits must be between 1 and 10".to_owned());
+                }
+                self.otp_codes = Some(otp::codes(digits));
+                // time based codes rotate, a full pass only hits if it completes within the validity window
+                log::info!(
+                    "{} codes rotate every {}s, about {} requests per second are needed for a full pass within a window",
+                    10usize.pow(digits as u32),
+                    opts.http.http_otp_period,
+                    10usize.pow(digits as u32) / opts.http.http_otp_period.max(1) as usize
+                );
+            }
+        }
+
         if matches!(self.strategy, Strategy::NLTMv1 | Strategy::NLTMv2) {
             self.workstation = opts.http.http_ntlm_workstation.clone();
             if let Some(domain) = &opts.http.http_ntlm_domain {
@@ -616,7 +721,7 @@ impl Plugin for HTTP {
                 );
             }
 
-            reqwest::Client::builder()
+            client_builder()
                 .proxy(proxy) // sets auto_sys_proxy to false, see https://github.com/evilsocket/legba/issues/8
                 .danger_accept_invalid_certs(true)
                 .redirect(redirect_policy)
@@ -624,7 +729,7 @@ impl Plugin for HTTP {
                 .map_err(|e| e.to_string())?
         } else {
             // plain client
-            reqwest::Client::builder()
+            client_builder()
                 .no_proxy() // used to set auto_sys_proxy to false, see https://github.com/evilsocket/legba/issues/8
                 .danger_accept_invalid_certs(true)
                 .redirect(redirect_policy)
@@ -643,11 +748,23 @@ impl Plugin for HTTP {
         match self.strategy {
             Strategy::Enumeration => self.http_enum_attempt(creds, timeout).await,
             Strategy::VHostEnum => self.http_vhost_enum_attempt(creds, timeout).await,
+            Strategy::Otp => self.http_otp_attempt(creds, timeout).await,
             _ => self.http_request_attempt(creds, timeout).await,
         }
     }
 }
 
+// client builder using the pre-resolved addresses of the targets
+fn client_builder() -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder();
+    for (host, ips) in resolver::cached() {
+        // the port is ignored, the one from the url is used
+        let addrs: Vec<SocketAddr> = ips.into_iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+        builder = builder.resolve_to_addrs(&host, &addrs);
+    }
+    builder
+}
+
 // TODO: add more tests
 #[cfg(test)]
 mod tests {
@@ -670,6 +787,7 @@ mod tests {
             target: "localhost:3000".to_owned(),
             username: String::new(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -684,6 +802,7 @@ mod tests {
             target: "localhost:3000/somepath".to_owned(),
             username: String::new(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -698,6 +817,7 @@ mod tests {
             target: "https://localhost:3000".to_owned(),
             username: String::new(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -712,6 +832,7 @@ mod tests {
             target: "localhost:3000/?foo=bar".to_owned(),
             username: String::new(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -726,6 +847,7 @@ mod tests {
             target: "localhost:3000/?username={USERNAME}".to_owned(),
             username: "bob".to_owned(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -740,6 +862,7 @@ mod tests {
             target: "localhost:3000/?p={PASSWORD}".to_owned(),
             username: String::new(),
             password: "f00b4r".to_owned(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -754,6 +877,7 @@ mod tests {
             target: "localhost:3000/?p={PAYLOAD}".to_owned(),
             username: "something".to_owned(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -768,6 +892,7 @@ mod tests {
             target: "localhost:3000/?p=%7BPAYLOAD%7D".to_owned(),
             username: "something".to_owned(),
             password: String::new(),
+            ..Default::default()
         };
         let http = HTTP::new(Strategy::Request);
         assert_eq!(
@@ -1064,6 +1189,7 @@ mod tests {
             target: String::new(),
             username: "foo".to_owned(),
             password: String::new(),
+            ..Default::default()
         };
 
         let status = 200;
@@ -1095,6 +1221,7 @@ mod tests {
             target: String::new(),
             username: "foo".to_owned(),
             password: "p4ssw0rd".to_owned(),
+            ..Default::default()
         };
 
         let status = 200;
@@ -1126,6 +1253,7 @@ mod tests {
             target: String::new(),
             username: "<svg onload=alert(1)>".to_owned(),
             password: String::new(),
+            ..Default::default()
         };
 
         let status = 200;
@@ -1143,4 +1271,36 @@ mod tests {
             .await
             .is_some());
     }
+
+    #[test]
+    fn test_otp_requires_login_page() {
+        let mut http = HTTP::new(Strategy::Otp);
+        let opts = Options::default();
+
+        assert!(http.setup(&opts).is_err());
+    }
+
+    #[test]
+    fn test_otp_default_codes() {
+        let mut http = HTTP::new(Strategy::Otp);
+        let mut opts = Options::default();
+
+        opts.http.http_method = "POST".to_owned();
+        opts.http.http_otp_login_page = Some("http://localhost/login".to_owned());
+        opts.http.http_otp_digits = 4;
+        opts.http.http_otp_period = 30;
+
+        assert_eq!(Ok(()), http.setup(&opts));
+        assert_eq!(
+            Some("mask:?d?d?d?d".to_owned()),
+            http.override_payload().map(|e| e.as_string())
+        );
+
+        // an explicit payload takes precedence
+        let mut http = HTTP::new(Strategy::Otp);
+        opts.username = Some("codes.txt".to_owned());
+
+        assert_eq!(Ok(()), http.setup(&opts));
+        assert!(http.override_payload().is_none());
+    }
 }
@@ -36,7 +36,25 @@ pub(crate) struct Options {
     /// Regular expression to use to grab the CSRF token name and value.
     pub http_csrf_regexp: String,
     #[clap(long)]
-    /// Request payload (query string, post body or form data) for HTTP based plugins.
+    /// For http.otp, page to log in with the known credentials before submitting the codes.
+    pub http_otp_login_page: Option<String>,
+    #[clap(long)]
+    /// For http.otp, login payload with the known credentials, {USERNAME}, {PASSWORD}, {TARGET}, {DOMAIN} and {PORT} placeholders are interpolated.
+    pub http_otp_login_payload: Option<String>,
+    #[clap(long, default_value_t = 6)]
+    /// For http.otp, number of digits of the codes to try if no payload is specified.
+    pub http_otp_digits: usize,
+    #[clap(long, default_value_t = 0)]
+    /// For http.otp, number of codes to submit within a session before logging in again (0 to reuse the session until it expires).
+    pub http_otp_session_attempts: usize,
+    #[clap(long, default_value_t = 30000)]
+    /// For http.otp, milliseconds to wait when rate limited and no Retry-After header is returned.
+    pub http_otp_rate_limit_wait: u64,
+    #[clap(long, default_value_t = 30)]
+    /// For http.otp, code rotation period in seconds.
+    pub http_otp_period: u64,
+    #[clap(long)]
+    /// Request payload (query string, post body or form data) for HTTP based plugins, {USERNAME}, {PASSWORD}, {TARGET}, {DOMAIN} and {PORT} placeholders are interpolated per attempt.
     pub http_payload: Option<String>,
     #[clap(long, default_value = "php")]
     /// File extension for HTTP enumeration.
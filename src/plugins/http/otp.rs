@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, TimeZone};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, LOCATION, RETRY_AFTER, SET_COOKIE},
+    Client, StatusCode,
+};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::creds::{Credentials, Expression};
+use crate::session::Error;
+
+use super::payload;
+
+#[derive(Default)]
+struct Session {
+    cookie: String,
+    attempts: usize,
+    blocked_until: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Config {
+    login_page: String,
+    login_payload: Option<String>,
+    session_attempts: usize,
+    rate_limit_wait: Duration,
+    period: u64,
+    // login sessions and rate limit state shared by all workers, by target
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+}
+
+impl Config {
+    pub fn new(
+        login_page: &str,
+        login_payload: Option<String>,
+        session_attempts: usize,
+        rate_limit_wait: u64,
+        period: u64,
+    ) -> Result<Self, Error> {
+        let login_page = Url::parse(login_page).map_err(|e| e.to_string())?.to_string();
+        if period == 0 {
+            return Err("--http-otp-period must be greater than zero".to_owned());
+        }
+
+        Ok(Self {
+            login_page,
+            login_payload,
+            session_attempts,
+            rate_limit_wait: Duration::from_millis(rate_limit_wait),
+            period,
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    async fn login(
+        &self,
+        client: &Client,
+        mut headers: HeaderMap<HeaderValue>,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        // the login form is always url encoded, regardless of the content type of the otp step
+        headers.remove(CONTENT_TYPE);
+        let fields = payload::parse_fields(self.login_payload.as_ref(), creds).unwrap_or_default();
+
+        let res = client
+            .post(&self.login_page)
+            .headers(headers)
+            .form(&fields)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = res.status();
+        if status.is_client_error() || status.is_server_error() {
+            return Err(format!("login page returned status {}", status));
+        }
+
+        let cookie = cookies(res.headers());
+        if cookie.is_empty() {
+            return Err("login page did not set any session cookie".to_owned());
+        }
+
+        log::debug!("[{}] new otp session: {}", &creds.target, &cookie);
+
+        Ok(cookie)
+    }
+
+    // return the session cookie to submit the next code with, logging in again if needed
+    pub async fn session(
+        &self,
+        client: &Client,
+        headers: HeaderMap<HeaderValue>,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        // wait for the rate limit to expire before taking the lock, so that the other workers can check it too
+        let blocked_until = self
+            .sessions
+            .lock()
+            .await
+            .get(&creds.target)
+            .and_then(|s| s.blocked_until);
+        if let Some(until) = blocked_until {
+            tokio::time::sleep_until(until.into()).await;
+        }
+
+        // the lock is held while logging in so that workers share the same session
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.entry(creds.target.to_owned()).or_default();
+        if session.cookie.is_empty()
+            || (self.session_attempts > 0 && session.attempts >= self.session_attempts)
+        {
+            session.cookie = self.login(client, headers, creds, timeout).await?;
+            session.attempts = 0;
+        }
+
+        session.attempts += 1;
+
+        Ok(session.cookie.to_owned())
+    }
+
+    // drop the session so that the next attempt logs in again
+    pub async fn invalidate(&self, target: &str, cookie: &str) {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get_mut(target) {
+            // another worker might have logged in already
+            if session.cookie == cookie {
+                session.cookie.clear();
+            }
+        }
+    }
+
+    pub async fn rate_limited(&self, target: &str, headers: &HeaderMap<HeaderValue>) {
+        let wait = retry_after(headers).unwrap_or(self.rate_limit_wait);
+        log::warn!("[{}] rate limited, waiting {:?}", target, wait);
+
+        let mut sessions = self.sessions.lock().await;
+        sessions.entry(target.to_owned()).or_default().blocked_until = Some(Instant::now() + wait);
+    }
+
+    // servers usually send the user back to the login page (or reply 401) once the session is gone
+    pub fn is_session_lost(&self, status: StatusCode, headers: &HeaderMap<HeaderValue>) -> bool {
+        if status == StatusCode::UNAUTHORIZED {
+            return true;
+        }
+
+        if status.is_redirection() {
+            if let Some(location) = headers.get(LOCATION).and_then(|l| l.to_str().ok()) {
+                let login = Url::parse(&self.login_page).unwrap();
+                if let Ok(location) = login.join(location) {
+                    return location.path() == login.path();
+                }
+            }
+        }
+
+        false
+    }
+
+    // the rotation window the accepted code belongs to
+    pub fn window(&self) -> String {
+        let now = Local::now().timestamp();
+        let period = self.period as i64;
+        let start = now - now.rem_euclid(period);
+        let format = |ts: i64| {
+            Local
+                .timestamp_opt(ts, 0)
+                .single()
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .unwrap_or_default()
+        };
+
+        format!("{} - {}", format(start), format(start + period))
+    }
+}
+
+// every code of the given length, zero padded
+pub(crate) fn codes(digits: usize) -> Expression {
+    Expression::Mask {
+        mask: "?d".repeat(digits),
+        charsets: vec![],
+    }
+}
+
+fn cookies(headers: &HeaderMap<HeaderValue>) -> String {
+    headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|c| c.to_str().ok())
+        .filter_map(|c| c.split(';').next())
+        .map(|c| c.trim())
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<&str>>()
+        .join("; ")
+}
+
+fn retry_after(headers: &HeaderMap<HeaderValue>) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{
+        header::{HeaderMap, HeaderValue, LOCATION, RETRY_AFTER, SET_COOKIE},
+        StatusCode,
+    };
+
+    use super::{cookies, retry_after, Config};
+
+    #[test]
+    fn can_collect_session_cookies() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            SET_COOKIE,
+            HeaderValue::from_static("PHPSESSID=abc; path=/; HttpOnly"),
+        );
+        headers.append(SET_COOKIE, HeaderValue::from_static("mfa_pending=1"));
+        assert_eq!(cookies(&headers), "PHPSESSID=abc; mfa_pending=1");
+        assert_eq!(cookies(&HeaderMap::new()), "");
+    }
+
+    #[test]
+    fn can_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+        // http dates are not worth the trouble, the configured wait is used instead
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn can_detect_lost_session() {
+        let config = Config::new("http://localhost/login", None, 0, 30000, 30).unwrap();
+        let mut headers = HeaderMap::new();
+        assert!(config.is_session_lost(StatusCode::UNAUTHORIZED, &headers));
+        assert!(!config.is_session_lost(StatusCode::OK, &headers));
+
+        headers.insert(LOCATION, HeaderValue::from_static("/login?expired=1"));
+        assert!(config.is_session_lost(StatusCode::FOUND, &headers));
+
+        headers.insert(LOCATION, HeaderValue::from_static("/dashboard"));
+        assert!(!config.is_session_lost(StatusCode::FOUND, &headers));
+    }
+}
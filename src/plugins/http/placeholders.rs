@@ -1,14 +1,12 @@
-use crate::creds::Credentials;
+use crate::creds::{self, Credentials, PASSWORD, TEMPLATES, USERNAME};
 
-const USERNAME: &str = "{USERNAME}";
-const PASSWORD: &str = "{PASSWORD}";
 const PAYLOAD: &str = "{PAYLOAD}";
 
 pub(crate) fn interpolate(data: &str, creds: &Credentials) -> String {
     let mut parsed = data.to_owned();
 
     // undo query encoding of interpolation params
-    for placeholder in [USERNAME, PASSWORD, PAYLOAD] {
+    for placeholder in [PASSWORD, PAYLOAD].into_iter().chain(TEMPLATES) {
         let encoded_lwr = placeholder.replace('{', "%7b").replace('}', "%7d");
         let encoded_upr = placeholder.replace('{', "%7B").replace('}', "%7D");
 
@@ -18,8 +16,11 @@ pub(crate) fn interpolate(data: &str, creds: &Credentials) -> String {
     }
 
     // interpolate placeholders
-    parsed
+    let parsed = parsed
         .replace(USERNAME, &creds.username)
         .replace(PAYLOAD, &creds.username)
-        .replace(PASSWORD, &creds.password)
+        .replace(PASSWORD, &creds.password);
+
+    // and the {TARGET}, {DOMAIN} and {PORT} templates
+    creds::interpolate(&parsed, &creds.username, &creds.target)
 }
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "ifcfg.co")]
+    /// Remote address to test the HTTP proxy CONNECT method for.
+    pub http_proxy_address: String,
+    #[clap(long, default_value_t = 443)]
+    /// Remote port to test the HTTP proxy CONNECT method for.
+    pub http_proxy_port: u16,
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use crate::plugins::plugin::PayloadStrategy;
+
+const MAX_PACKET_SIZE: usize = 1500;
+const READ_PROPERTY: u8 = 0x0c;
+const COMPLEX_ACK: u8 = 0x03;
+// device object with the wildcard instance number
+const WILDCARD_DEVICE: [u8; 4] = [0x02, 0x3f, 0xff, 0xff];
+const DEVICE_PROPERTIES: &[(u8, &str)] = &[
+    (75, "device_instance"),
+    (77, "object_name"),
+    (121, "vendor_name"),
+    (120, "vendor_id"),
+    (70, "model_name"),
+    (44, "firmware_revision"),
+    (12, "application_version"),
+    (58, "location"),
+    (28, "description"),
+];
+
+fn read_property_request(invoke_id: u8, property: u8) -> Vec<u8> {
+    let mut apdu = vec![0x00, 0x05, invoke_id, READ_PROPERTY, 0x0c];
+    apdu.extend_from_slice(&WILDCARD_DEVICE);
+    apdu.extend_from_slice(&[0x19, property]);
+
+    // BVLC original unicast + NPDU expecting a reply
+    let mut packet = vec![0x81, 0x0a];
+    packet.extend_from_slice(&(apdu.len() as u16 + 6).to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x04]);
+    packet.extend(apdu);
+    packet
+}
+
+// the APDU of a BACnet/IP packet, if any
+fn apdu(packet: &[u8]) -> Option<&[u8]> {
+    if packet.len() < 6 || packet[0] != 0x81 || packet[4] != 0x01 {
+        return None;
+    }
+    let control = packet[5];
+    // network layer messages carry no APDU
+    if control & 0x80 != 0 {
+        return None;
+    }
+    let mut pos = 6;
+    if control & 0x20 != 0 {
+        pos += 3 + *packet.get(pos + 2)? as usize;
+    }
+    if control & 0x08 != 0 {
+        pos += 3 + *packet.get(pos + 2)? as usize;
+    }
+    if control & 0x20 != 0 {
+        // hop count
+        pos += 1;
+    }
+    packet.get(pos..)
+}
+
+// decodes the application tagged value of a ReadProperty complex ack
+fn parse_read_property_ack(packet: &[u8], invoke_id: u8) -> Option<String> {
+    let apdu = apdu(packet)?;
+    if apdu.len() < 3
+        || apdu[0] >> 4 != COMPLEX_ACK
+        || apdu[1] != invoke_id
+        || apdu[2] != READ_PROPERTY
+    {
+        return None;
+    }
+
+    // object identifier, property identifier and optional array index
+    let mut pos = 3;
+    while *apdu.get(pos)? != 0x3e {
+        let tag = apdu[pos];
+        if tag & 0x08 == 0 {
+            return None;
+        }
+        pos += 1 + (tag & 0x07) as usize;
+    }
+    pos += 1;
+
+    let tag = *apdu.get(pos)?;
+    let (len, start) = match tag & 0x07 {
+        5 => (*apdu.get(pos + 1)? as usize, pos + 2),
+        len => (len as usize, pos + 1),
+    };
+    let value = apdu.get(start..start + len)?;
+    match tag >> 4 {
+        // unsigned
+        2 if len <= 4 => Some(
+            value
+                .iter()
+                .fold(0u32, |acc, b| (acc << 8) | *b as u32)
+                .to_string(),
+        ),
+        // character string, skipping the charset
+        7 if len > 0 => Some(
+            String::from_utf8_lossy(&value[1..])
+                .trim_matches(char::from(0))
+                .trim()
+                .to_owned(),
+        ),
+        // object identifier, only the instance number is relevant
+        12 if len == 4 => Some(
+            (u32::from_be_bytes([value[0], value[1], value[2], value[3]]) & 0x3fffff).to_string(),
+        ),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Bacnet {}
+
+impl Bacnet {
+    pub fn new() -> Self {
+        Bacnet {}
+    }
+}
+
+#[async_trait]
+impl Plugin for Bacnet {
+    fn description(&self) -> &'static str {
+        "BACnet/IP unauthenticated device property read."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    // bacnet has no authentication, only run once per target
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "survey".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 47808)?;
+        let socket = utils::net::async_udp_socket(&address).await?;
+
+        let mut data = vec![];
+        let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+        for (invoke_id, (property, name)) in DEVICE_PROPERTIES.iter().enumerate() {
+            let invoke_id = invoke_id as u8;
+            socket
+                .send(&read_property_request(invoke_id, *property))
+                .await
+                .map_err(|e| e.to_string())?;
+
+            // optional properties are answered with an error pdu
+            let deadline = tokio::time::Instant::now() + timeout;
+            let value = loop {
+                let size = match tokio::time::timeout_at(deadline, socket.recv(&mut buffer)).await
+                {
+                    Ok(size) => size.map_err(|e| e.to_string())?,
+                    Err(_) if data.is_empty() => return Err("no response".to_owned()),
+                    Err(_) => break None,
+                };
+                match apdu(&buffer[..size]) {
+                    Some(apdu) if apdu.get(1) == Some(&invoke_id) => {
+                        break parse_read_property_ack(&buffer[..size], invoke_id)
+                    }
+                    _ => continue,
+                }
+            };
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                data.push((name.to_string(), value));
+            }
+        }
+
+        if data.is_empty() {
+            return Ok(None);
+        }
+        data.insert(0, ("authentication".to_owned(), "none".to_owned()));
+        Ok(Some(vec![Loot::new("bacnet", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_read_property_ack, read_property_request};
+
+    #[test]
+    fn can_build_read_property_request() {
+        assert_eq!(
+            read_property_request(3, 77),
+            vec![
+                0x81, 0x0a, 0x00, 0x11, 0x01, 0x04, 0x00, 0x05, 0x03, 0x0c, 0x0c, 0x02, 0x3f,
+                0xff, 0xff, 0x19, 0x4d
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_read_property_ack() {
+        let object_name = [
+            0x81, 0x0a, 0x00, 0x1a, 0x01, 0x00, 0x30, 0x01, 0x0c, 0x0c, 0x02, 0x00, 0x04, 0xd2,
+            0x19, 0x4d, 0x3e, 0x75, 0x06, 0x00, b'A', b'H', b'U', b'-', b'1', 0x3f,
+        ];
+        assert_eq!(
+            parse_read_property_ack(&object_name, 1),
+            Some("AHU-1".to_owned())
+        );
+        assert_eq!(parse_read_property_ack(&object_name, 2), None);
+
+        // routed through a BACnet router, with the source network
+        let object_identifier = [
+            0x81, 0x0a, 0x00, 0x1c, 0x01, 0x08, 0x00, 0x05, 0x01, 0x07, 0x30, 0x00, 0x0c, 0x0c,
+            0x02, 0x00, 0x04, 0xd2, 0x19, 0x4b, 0x3e, 0xc4, 0x02, 0x00, 0x04, 0xd2, 0x3f,
+        ];
+        assert_eq!(
+            parse_read_property_ack(&object_identifier, 0),
+            Some("1234".to_owned())
+        );
+
+        // error pdu for an unknown property
+        let error = [
+            0x81, 0x0a, 0x00, 0x0d, 0x01, 0x00, 0x50, 0x02, 0x0c, 0x91, 0x02, 0x91, 0x20,
+        ];
+        assert_eq!(parse_read_property_ack(&error, 2), None);
+    }
+}
@@ -0,0 +1,11 @@
+pub(crate) mod options;
+
+mod bacnet;
+mod modbus;
+mod s7;
+
+super::manager::register_plugin! {
+    "modbus" => modbus::Modbus::new(),
+    "s7" => s7::S7::new(),
+    "bacnet" => bacnet::Bacnet::new()
+}
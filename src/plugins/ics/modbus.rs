@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use crate::plugins::plugin::PayloadStrategy;
+
+// only read functions are tested, writing to a live process is never safe
+const READ_FUNCTIONS: &[(u8, &str)] = &[
+    (0x01, "read_coils"),
+    (0x02, "read_discrete_inputs"),
+    (0x03, "read_holding_registers"),
+    (0x04, "read_input_registers"),
+];
+const READ_DEVICE_IDENTIFICATION: [u8; 4] = [0x2b, 0x0e, 0x01, 0x00];
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const DEVICE_OBJECTS: &[&str] = &["vendor", "product_code", "revision"];
+
+#[derive(Debug, PartialEq)]
+enum Response {
+    Normal(Vec<u8>),
+    Exception(u8),
+}
+
+// sends a request pdu with its MBAP header and returns the response pdu
+async fn transact<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    transaction: u16,
+    unit: u8,
+    pdu: &[u8],
+) -> Result<Response, Error> {
+    let mut request = transaction.to_be_bytes().to_vec();
+    request.extend_from_slice(&[0, 0]);
+    request.extend_from_slice(&(pdu.len() as u16 + 1).to_be_bytes());
+    request.push(unit);
+    request.extend_from_slice(pdu);
+    stream
+        .write_all(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut header = [0u8; 7];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    if header[0..2] != transaction.to_be_bytes() || header[2..4] != [0, 0] {
+        return Err("not a modbus response".to_owned());
+    }
+    let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+    if len < 2 {
+        return Err(format!("invalid modbus response length {}", len));
+    }
+    let mut response = vec![0u8; len - 1];
+    stream
+        .read_exact(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if response[0] == pdu[0] | 0x80 {
+        Ok(Response::Exception(response.get(1).copied().unwrap_or(0)))
+    } else if response[0] == pdu[0] {
+        Ok(Response::Normal(response))
+    } else {
+        Err(format!("unexpected modbus function 0x{:02x}", response[0]))
+    }
+}
+
+// basic objects of a read device identification response
+fn parse_device_identification(pdu: &[u8]) -> Vec<(String, String)> {
+    let mut info = vec![];
+    let Some(count) = pdu.get(6) else {
+        return info;
+    };
+    let mut pos = 7;
+    for _ in 0..*count {
+        let (Some(id), Some(len)) = (pdu.get(pos), pdu.get(pos + 1)) else {
+            break;
+        };
+        let Some(value) = pdu.get(pos + 2..pos + 2 + *len as usize) else {
+            break;
+        };
+        if let Some(name) = DEVICE_OBJECTS.get(*id as usize) {
+            info.push((
+                name.to_string(),
+                String::from_utf8_lossy(value).trim().to_owned(),
+            ));
+        }
+        pos += 2 + *len as usize;
+    }
+    info
+}
+
+async fn survey<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    unit: u8,
+) -> Result<Vec<(String, String)>, Error> {
+    let mut data = vec![];
+    if let Response::Normal(pdu) = transact(stream, 1, unit, &READ_DEVICE_IDENTIFICATION).await? {
+        data.extend(parse_device_identification(&pdu));
+    }
+
+    let mut functions = vec![];
+    for (i, (code, name)) in READ_FUNCTIONS.iter().enumerate() {
+        // one item at address zero
+        let pdu = [*code, 0, 0, 0, 1];
+        match transact(stream, i as u16 + 2, unit, &pdu).await? {
+            // an illegal address still means the function is allowed
+            Response::Normal(_) => functions.push(*name),
+            Response::Exception(code) if code != ILLEGAL_FUNCTION => functions.push(*name),
+            Response::Exception(_) => {}
+        }
+    }
+    if !functions.is_empty() {
+        data.push(("functions".to_owned(), functions.join(", ")));
+    }
+
+    Ok(data)
+}
+
+#[derive(Clone)]
+pub(crate) struct Modbus {
+    unit: u8,
+}
+
+impl Modbus {
+    pub fn new() -> Self {
+        Modbus { unit: 1 }
+    }
+}
+
+#[async_trait]
+impl Plugin for Modbus {
+    fn description(&self) -> &'static str {
+        "Modbus TCP unauthenticated device identification and read access detection."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    // modbus has no authentication, only run once per target
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "survey".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.unit = opts.ics.modbus_unit;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 502)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let info = tokio::time::timeout(timeout, survey(&mut stream, self.unit))
+            .await
+            .map_err(|e| e.to_string())??;
+        if info.is_empty() {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("authentication".to_owned(), "none".to_owned()),
+            ("unit".to_owned(), self.unit.to_string()),
+        ];
+        data.extend(info);
+        Ok(Some(vec![Loot::new("modbus", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::survey;
+
+    #[tokio::test]
+    async fn can_survey_device() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[0, 1, 0, 0, 0, 5, 1, 0x2b, 0x0e, 0x01, 0x00])
+            .read(&[0, 1, 0, 0, 0, 0x1c, 1])
+            .read(&[
+                0x2b, 0x0e, 0x01, 0x01, 0x00, 0x00, 0x03, 0x00, 0x06, b'V', b'e', b'n', b'd', b'o',
+                b'r', 0x01, 0x04, b'P', b'L', b'C', b'1', 0x02, 0x04, b'v', b'1', b'.', b'0',
+            ])
+            .write(&[0, 2, 0, 0, 0, 6, 1, 0x01, 0, 0, 0, 1])
+            .read(&[0, 2, 0, 0, 0, 4, 1, 0x01, 0x01, 0x00])
+            .write(&[0, 3, 0, 0, 0, 6, 1, 0x02, 0, 0, 0, 1])
+            .read(&[0, 3, 0, 0, 0, 3, 1, 0x82, 0x01])
+            .write(&[0, 4, 0, 0, 0, 6, 1, 0x03, 0, 0, 0, 1])
+            .read(&[0, 4, 0, 0, 0, 5, 1, 0x03, 0x02, 0x00, 0x2a])
+            .write(&[0, 5, 0, 0, 0, 6, 1, 0x04, 0, 0, 0, 1])
+            .read(&[0, 5, 0, 0, 0, 3, 1, 0x84, 0x02])
+            .build();
+        assert_eq!(
+            survey(&mut stream, 1).await,
+            Ok(vec![
+                ("vendor".to_owned(), "Vendor".to_owned()),
+                ("product_code".to_owned(), "PLC1".to_owned()),
+                ("revision".to_owned(), "v1.0".to_owned()),
+                (
+                    "functions".to_owned(),
+                    "read_coils, read_holding_registers, read_input_registers".to_owned()
+                ),
+            ])
+        );
+    }
+}
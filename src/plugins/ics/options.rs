@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = 1)]
+    /// Modbus unit identifier.
+    pub modbus_unit: u8,
+    #[clap(long, default_value_t = 0)]
+    /// Rack of the S7 CPU.
+    pub s7_rack: u8,
+    #[clap(long, default_value_t = 2)]
+    /// Slot of the S7 CPU (2 for S7-300, 3 for S7-400 and the likes).
+    pub s7_slot: u8,
+}
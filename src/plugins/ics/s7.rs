@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use crate::plugins::plugin::PayloadStrategy;
+
+const MAX_TPKT_SIZE: usize = 4096;
+const COTP_DATA: [u8; 3] = [0x02, 0xf0, 0x80];
+const ROSCTR_JOB: u8 = 0x01;
+const ROSCTR_ACK_DATA: u8 = 0x03;
+const ROSCTR_USERDATA: u8 = 0x07;
+const SZL_IDENTIFICATION: u16 = 0x001c;
+const SZL_PROTECTION: u16 = 0x0232;
+const INVALID_PASSWORD: u16 = 0xd602;
+
+// protection levels from the 0x0232 SZL
+#[derive(Debug, PartialEq)]
+struct Protection {
+    key_switch: u16,
+    password: u16,
+    effective: u16,
+}
+
+impl Protection {
+    fn access(&self) -> &'static str {
+        match self.effective {
+            0 | 1 => "full",
+            2 => "read",
+            _ => "none",
+        }
+    }
+
+    // anything goes without a password
+    fn is_open(&self) -> bool {
+        self.effective <= 1 || self.password == 0
+    }
+}
+
+fn tpkt(payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x03, 0x00];
+    packet.extend_from_slice(&(payload.len() as u16 + 4).to_be_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+fn s7_request(rosctr: u8, params: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut pdu = COTP_DATA.to_vec();
+    pdu.extend_from_slice(&[0x32, rosctr, 0x00, 0x00, 0x00, 0x01]);
+    pdu.extend_from_slice(&(params.len() as u16).to_be_bytes());
+    pdu.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    pdu.extend_from_slice(params);
+    pdu.extend_from_slice(data);
+    tpkt(&pdu)
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+}
+
+async fn read_tpkt<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, Error> {
+    let mut header = [0u8; 4];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+    if header[0] != 0x03 || !(7..=MAX_TPKT_SIZE).contains(&len) {
+        return Err("not an ISO-on-TCP response".to_owned());
+    }
+    let mut payload = vec![0u8; len - 4];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(payload)
+}
+
+// skips the COTP header
+fn s7_pdu(payload: &[u8]) -> Result<&[u8], Error> {
+    let pdu = payload
+        .get(payload[0] as usize + 1..)
+        .filter(|pdu| pdu.len() >= 10 && pdu[0] == 0x32)
+        .ok_or("not an S7 response")?;
+    Ok(pdu)
+}
+
+// parameters and data of a userdata response
+fn userdata_response(payload: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    let pdu = s7_pdu(payload)?;
+    if pdu[1] != ROSCTR_USERDATA {
+        return Err(format!("unexpected S7 message type 0x{:02x}", pdu[1]));
+    }
+    let params_len = be16(pdu, 6).unwrap() as usize;
+    let data_len = be16(pdu, 8).unwrap() as usize;
+    let params = pdu
+        .get(10..10 + params_len)
+        .ok_or("truncated S7 parameters")?;
+    let data = pdu
+        .get(10 + params_len..10 + params_len + data_len)
+        .ok_or("truncated S7 data")?;
+    Ok((params, data))
+}
+
+async fn connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    rack: u8,
+    slot: u8,
+) -> Result<(), Error> {
+    // COTP connection request to the PG TSAP of the CPU
+    let request = tpkt(&[
+        0x11,
+        0xe0,
+        0x00,
+        0x00,
+        0x00,
+        0x01,
+        0x00,
+        0xc0,
+        0x01,
+        0x0a,
+        0xc1,
+        0x02,
+        0x01,
+        0x00,
+        0xc2,
+        0x02,
+        0x01,
+        rack * 0x20 + slot,
+    ]);
+    stream
+        .write_all(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let response = read_tpkt(stream).await?;
+    if response.get(1) != Some(&0xd0) {
+        return Err("connection refused by the CPU, check the rack and slot".to_owned());
+    }
+
+    // setup communication with a 480 bytes pdu
+    stream
+        .write_all(&s7_request(
+            ROSCTR_JOB,
+            &[0xf0, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0xe0],
+            &[],
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    let response = read_tpkt(stream).await?;
+    let pdu = s7_pdu(&response)?;
+    if pdu[1] != ROSCTR_ACK_DATA || be16(pdu, 10) != Some(0) {
+        return Err("S7 setup communication failed".to_owned());
+    }
+    Ok(())
+}
+
+// record length and records of a system status list
+async fn read_szl<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    id: u16,
+    index: u16,
+) -> Result<(usize, Vec<u8>), Error> {
+    let mut data = vec![0xff, 0x09, 0x00, 0x04];
+    data.extend_from_slice(&id.to_be_bytes());
+    data.extend_from_slice(&index.to_be_bytes());
+    stream
+        .write_all(&s7_request(
+            ROSCTR_USERDATA,
+            &[0x00, 0x01, 0x12, 0x04, 0x11, 0x44, 0x01, 0x00],
+            &data,
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let response = read_tpkt(stream).await?;
+    let (_, data) = userdata_response(&response)?;
+    if data.len() < 12 || data[0] != 0xff {
+        return Err(format!("SZL 0x{:04x} not available", id));
+    }
+    let record_len = be16(data, 8).unwrap() as usize;
+    Ok((record_len, data[12..].to_vec()))
+}
+
+fn parse_protection(records: &[u8]) -> Option<Protection> {
+    Some(Protection {
+        key_switch: be16(records, 2)?,
+        password: be16(records, 4)?,
+        effective: be16(records, 6)?,
+    })
+}
+
+fn parse_identification(record_len: usize, records: &[u8]) -> Vec<(String, String)> {
+    if record_len < 2 {
+        return vec![];
+    }
+    records
+        .chunks_exact(record_len)
+        .filter_map(|record| {
+            let name = match be16(record, 0)? {
+                1 => "system",
+                2 => "module",
+                3 => "plant",
+                5 => "serial",
+                7 => "module_type",
+                _ => return None,
+            };
+            let value = String::from_utf8_lossy(&record[2..])
+                .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+                .to_owned();
+            if value.is_empty() {
+                None
+            } else {
+                Some((name.to_owned(), value))
+            }
+        })
+        .collect()
+}
+
+// legacy S7-300/400 password obfuscation
+fn encode_password(password: &str) -> [u8; 8] {
+    let mut encoded = [b' '; 8];
+    for (i, b) in password.bytes().take(8).enumerate() {
+        encoded[i] = b;
+    }
+    encoded[0] ^= 0x55;
+    encoded[1] ^= 0x55;
+    for i in 2..8 {
+        encoded[i] = encoded[i] ^ 0x55 ^ encoded[i - 2];
+    }
+    encoded
+}
+
+async fn send_password<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    password: &str,
+) -> Result<bool, Error> {
+    let mut data = vec![0xff, 0x09, 0x00, 0x08];
+    data.extend_from_slice(&encode_password(password));
+    stream
+        .write_all(&s7_request(
+            ROSCTR_USERDATA,
+            &[0x00, 0x01, 0x12, 0x04, 0x11, 0x45, 0x01, 0x00],
+            &data,
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let response = read_tpkt(stream).await?;
+    let (params, _) = userdata_response(&response)?;
+    match be16(params, 10).ok_or("truncated S7 parameters")? {
+        0 => Ok(true),
+        INVALID_PASSWORD => Ok(false),
+        code => Err(format!("S7 password request failed with error 0x{:04x}", code)),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct S7 {
+    rack: u8,
+    slot: u8,
+    // targets and whether they are unprotected, surveyed once
+    surveyed: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl S7 {
+    pub fn new() -> Self {
+        S7 {
+            rack: 0,
+            slot: 2,
+            surveyed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // identification and protection level of the CPU
+    async fn survey<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: &mut S,
+        address: &str,
+    ) -> (bool, Loot) {
+        let mut data = vec![];
+        match read_szl(stream, SZL_IDENTIFICATION, 0).await {
+            Ok((record_len, records)) => data.extend(parse_identification(record_len, &records)),
+            Err(e) => log::debug!("{}: {}", address, e),
+        }
+
+        let protection = match read_szl(stream, SZL_PROTECTION, 4).await {
+            Ok((_, records)) => parse_protection(&records),
+            Err(e) => {
+                log::debug!("{}: {}", address, e);
+                None
+            }
+        };
+        let open = protection.as_ref().map(|p| p.is_open()).unwrap_or(false);
+        if let Some(protection) = protection {
+            data.extend([
+                ("key_switch_level".to_owned(), protection.key_switch.to_string()),
+                ("password_level".to_owned(), protection.password.to_string()),
+                ("protection_level".to_owned(), protection.effective.to_string()),
+                ("access".to_owned(), protection.access().to_owned()),
+            ]);
+        }
+        if open {
+            data.push(("authentication".to_owned(), "none".to_owned()));
+        }
+
+        let loot = Loot::new("s7", address, data);
+        (open, if open { loot } else { loot.set_partial() })
+    }
+}
+
+#[async_trait]
+impl Plugin for S7 {
+    fn description(&self) -> &'static str {
+        "Siemens S7-300/400 password and protection level check."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.rack = opts.ics.s7_rack;
+        self.slot = opts.ics.s7_slot;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 102)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        tokio::time::timeout(timeout, connect(&mut stream, self.rack, self.slot))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        let mut found = vec![];
+        {
+            let mut surveyed = self.surveyed.lock().await;
+            let open = match surveyed.get(&address) {
+                Some(open) => *open,
+                None => {
+                    let (open, loot) =
+                        tokio::time::timeout(timeout, self.survey(&mut stream, &address))
+                            .await
+                            .map_err(|e| e.to_string())?;
+                    surveyed.insert(address.clone(), open);
+                    found.push(loot);
+                    open
+                }
+            };
+            // no password to find
+            if open {
+                return Ok(if found.is_empty() { None } else { Some(found) });
+            }
+        }
+
+        // being this plugin single credentials, this is going to be the password
+        let password = creds.single();
+        if tokio::time::timeout(timeout, send_password(&mut stream, password))
+            .await
+            .map_err(|e| e.to_string())??
+        {
+            found.push(Loot::new(
+                "s7",
+                &address,
+                [("password".to_owned(), password.to_owned())],
+            ));
+        }
+
+        Ok(if found.is_empty() { None } else { Some(found) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{connect, encode_password, parse_identification, send_password, tpkt};
+
+    fn userdata_response(params: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut pdu = vec![0x02, 0xf0, 0x80, 0x32, 0x07, 0x00, 0x00, 0x00, 0x01];
+        pdu.extend_from_slice(&(params.len() as u16).to_be_bytes());
+        pdu.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        pdu.extend_from_slice(params);
+        pdu.extend_from_slice(data);
+        tpkt(&pdu)
+    }
+
+    #[tokio::test]
+    async fn can_connect_and_send_password() {
+        let password_request = |password: &str| {
+            let mut data = vec![0xff, 0x09, 0x00, 0x08];
+            data.extend_from_slice(&encode_password(password));
+            super::s7_request(
+                0x07,
+                &[0x00, 0x01, 0x12, 0x04, 0x11, 0x45, 0x01, 0x00],
+                &data,
+            )
+        };
+        let password_response = |error: u16| {
+            let mut params = vec![0x00, 0x01, 0x12, 0x08, 0x12, 0x85, 0x01, 0x00, 0x00, 0x00];
+            params.extend_from_slice(&error.to_be_bytes());
+            userdata_response(&params, &[0x0a, 0x00, 0x00, 0x00])
+        };
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[
+                0x03, 0x00, 0x00, 0x16, 0x11, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc0, 0x01,
+                0x0a, 0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01, 0x02,
+            ])
+            .read(&[
+                0x03, 0x00, 0x00, 0x16, 0x11, 0xd0, 0x00, 0x01, 0x00, 0x02, 0x00, 0xc0, 0x01,
+                0x0a, 0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01, 0x02,
+            ])
+            .write(&super::s7_request(
+                0x01,
+                &[0xf0, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0xe0],
+                &[],
+            ))
+            .read(&[
+                0x03, 0x00, 0x00, 0x1b, 0x02, 0xf0, 0x80, 0x32, 0x03, 0x00, 0x00, 0x00, 0x01,
+                0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00,
+                0xf0,
+            ])
+            .write(&password_request("wrong"))
+            .read(&password_response(0xd602))
+            .write(&password_request("secret"))
+            .read(&password_response(0))
+            .build();
+        assert_eq!(connect(&mut stream, 0, 2).await, Ok(()));
+        assert_eq!(send_password(&mut stream, "wrong").await, Ok(false));
+        assert_eq!(send_password(&mut stream, "secret").await, Ok(true));
+    }
+
+    #[test]
+    fn can_parse_szl_records() {
+        assert_eq!(
+            encode_password("password"),
+            [0x25, 0x34, 0x03, 0x12, 0x21, 0x28, 0x06, 0x19]
+        );
+
+        let record = |index: u16, value: &str| {
+            let mut record = index.to_be_bytes().to_vec();
+            let mut text = value.as_bytes().to_vec();
+            text.resize(32, 0);
+            record.extend(text);
+            record
+        };
+        let records = [
+            record(1, "SIMATIC 300(1)"),
+            record(2, "CPU 315-2 PN/DP"),
+            record(4, "Original Siemens Equipment"),
+            record(5, "S C-X4U421302009"),
+        ]
+        .concat();
+        assert_eq!(
+            parse_identification(34, &records),
+            vec![
+                ("system".to_owned(), "SIMATIC 300(1)".to_owned()),
+                ("module".to_owned(), "CPU 315-2 PN/DP".to_owned()),
+                ("serial".to_owned(), "S C-X4U421302009".to_owned()),
+            ]
+        );
+    }
+}
@@ -9,26 +9,49 @@ use crate::Plugin;
 use crate::creds::Credentials;
 use crate::utils;
 
+use self::options::Mechanism;
+
+pub(crate) mod options;
+
 super::manager::register_plugin! {
     "imap" => IMAP::new()
 }
 
+// answers the first challenge with the initial response and any following one, usually
+// a JSON error for OAuth mechanisms, with an empty response so that the server fails
+struct Authenticator {
+    response: Option<String>,
+}
+
+impl async_imap::Authenticator for Authenticator {
+    type Response = String;
+
+    fn process(&mut self, _challenge: &[u8]) -> Self::Response {
+        self.response.take().unwrap_or_default()
+    }
+}
+
 #[derive(Clone)]
-pub(crate) struct IMAP {}
+pub(crate) struct IMAP {
+    mechanism: Mechanism,
+}
 
 impl IMAP {
     pub fn new() -> Self {
-        IMAP {}
+        IMAP {
+            mechanism: Mechanism::Login,
+        }
     }
 }
 
 #[async_trait]
 impl Plugin for IMAP {
     fn description(&self) -> &'static str {
-        "IMAP password authentication."
+        "IMAP password and OAuth token authentication."
     }
 
-    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.mechanism = opts.imap.imap_mechanism;
         Ok(())
     }
 
@@ -40,17 +63,70 @@ impl Plugin for IMAP {
         let address = utils::parse_target_address(&creds.target, 993)?;
         let stream = crate::utils::net::async_tcp_stream(&address, timeout, true).await?;
         let client = async_imap::Client::new(stream);
-        if client.login(&creds.username, &creds.password).await.is_ok() {
-            return Ok(Some(vec![Loot::new(
-                "imap",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]));
+
+        let (name, response) = match self.mechanism {
+            Mechanism::Login => ("", String::new()),
+            Mechanism::Plain => (
+                "PLAIN",
+                format!("\x00{}\x00{}", &creds.username, &creds.password),
+            ),
+            Mechanism::Xoauth2 => (
+                "XOAUTH2",
+                utils::oauth::xoauth2(&creds.username, &creds.password),
+            ),
+            Mechanism::Oauthbearer => (
+                "OAUTHBEARER",
+                utils::oauth::oauthbearer(&creds.username, &creds.password),
+            ),
+        };
+
+        let session = tokio::time::timeout(timeout, async {
+            if self.mechanism == Mechanism::Login {
+                client.login(&creds.username, &creds.password).await
+            } else {
+                client
+                    .authenticate(
+                        name,
+                        Authenticator {
+                            response: Some(response),
+                        },
+                    )
+                    .await
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let Ok(mut session) = session else {
+            return Ok(None);
+        };
+
+        let secret = if matches!(self.mechanism, Mechanism::Xoauth2 | Mechanism::Oauthbearer) {
+            "token"
+        } else {
+            "password"
+        };
+        let mut loot = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            (secret.to_owned(), creds.password.to_owned()),
+        ];
+
+        // best effort, capabilities after authentication
+        if let Ok(Ok(capabilities)) =
+            tokio::time::timeout(timeout, session.capabilities()).await
+        {
+            let capabilities: Vec<String> = capabilities
+                .iter()
+                .map(|c| match c {
+                    async_imap::types::Capability::Imap4rev1 => "IMAP4rev1".to_owned(),
+                    async_imap::types::Capability::Auth(mechanism) => format!("AUTH={}", mechanism),
+                    async_imap::types::Capability::Atom(atom) => atom.to_owned(),
+                })
+                .collect();
+            loot.push(("capabilities".to_owned(), capabilities.join(" ")));
         }
+        let _ = tokio::time::timeout(timeout, session.logout()).await;
 
-        Ok(None)
+        Ok(Some(vec![Loot::new("imap", &address, loot)]))
     }
 }
@@ -0,0 +1,19 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Mechanism {
+    #[default]
+    Login,
+    Plain,
+    Xoauth2,
+    Oauthbearer,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, value_enum, default_value_t = Mechanism::Login)]
+    /// IMAP authentication mechanism, with XOAUTH2 and OAUTHBEARER the passwords are OAuth access tokens.
+    pub imap_mechanism: Mechanism,
+}
@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "influxdb" => InfluxDB::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct InfluxDB {
+    opts: options::Options,
+    client: Client,
+    // targets already checked for disabled authentication
+    no_auth_checked: OncePerTarget,
+    // targets with authentication disabled
+    no_auth: OncePerTarget,
+}
+
+impl InfluxDB {
+    pub fn new() -> Self {
+        InfluxDB {
+            opts: options::Options::default(),
+            client: Client::new(),
+            no_auth_checked: OncePerTarget::new(),
+            no_auth: OncePerTarget::new(),
+        }
+    }
+
+    // the /ping endpoint doesn't require authentication and reports the version in a header
+    async fn version(&self, base: &str, timeout: Duration) -> Option<String> {
+        let response = self
+            .client
+            .get(format!("{}/ping", base))
+            .timeout(timeout)
+            .send()
+            .await
+            .ok()?;
+        response
+            .headers()
+            .get("x-influxdb-version")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned())
+    }
+}
+
+#[async_trait]
+impl Plugin for InfluxDB {
+    fn description(&self) -> &'static str {
+        "InfluxDB v1 password and v2 token authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.opts.influxdb_tokens {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.influxdb.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8086)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.influxdb_ssl || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        let (url, request) = if self.opts.influxdb_tokens {
+            let url = format!("{}/api/v2/orgs", base);
+            let request = self
+                .client
+                .get(&url)
+                .header("Authorization", format!("Token {}", creds.single()));
+            (url, request)
+        } else {
+            let url = format!("{}/query?q=SHOW%20DATABASES", base);
+            let request = self
+                .client
+                .get(&url)
+                .basic_auth(&creds.username, Some(&creds.password));
+            (url, request)
+        };
+
+        if !self.no_auth_checked.contains(&address) {
+            let response = self
+                .client
+                .get(&url)
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            let loot = if response.status() == StatusCode::OK {
+                let mut data = vec![("authentication".to_owned(), "none".to_owned())];
+                if let Some(version) = self.version(&base, timeout).await {
+                    data.push(("version".to_owned(), version));
+                }
+                self.no_auth
+                    .report(&address, Loot::new("influxdb", &address, data))
+            } else {
+                None
+            };
+            // marked only once the check completed, so transient errors are checked again
+            self.no_auth_checked.insert(&address);
+            if loot.is_some() {
+                return Ok(loot);
+            }
+        }
+
+        if self.no_auth.contains(&address) {
+            // no point in trying credentials, every one of them would be accepted
+            return Ok(None);
+        }
+
+        let response = request
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+
+        let mut data = if self.opts.influxdb_tokens {
+            vec![("token".to_owned(), creds.single().to_owned())]
+        } else {
+            vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ]
+        };
+        if let Some(version) = self.version(&base, timeout).await {
+            data.push(("version".to_owned(), version));
+        }
+
+        Ok(Some(vec![Loot::new("influxdb", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::InfluxDB;
+    use crate::creds::Credentials;
+    use crate::utils::testing::http_server;
+    use crate::Plugin;
+
+    fn creds(target: String) -> Credentials {
+        Credentials {
+            target,
+            username: "admin".to_owned(),
+            password: "admin".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_credentials_without_authentication() {
+        let databases = r#"{"results":[{"statement_id":0}]}"#;
+        // the first probe fails, the second one finds authentication disabled
+        let target = http_server(vec![None, Some((200, databases))]).await;
+        let influxdb = InfluxDB::new();
+        let creds = creds(target);
+        let timeout = Duration::from_secs(5);
+
+        assert!(influxdb.attempt(&creds, timeout).await.is_err());
+        let loot = influxdb.attempt(&creds, timeout).await.unwrap().unwrap();
+        assert_eq!(loot[0].get_value("authentication"), Some("none"));
+        // reported once, then every credential would be accepted
+        assert_eq!(influxdb.attempt(&creds, timeout).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn can_find_valid_credentials() {
+        let unauthorized = r#"{"error":"unable to parse authentication credentials"}"#;
+        let databases = r#"{"results":[{"statement_id":0}]}"#;
+        let target = http_server(vec![
+            Some((401, unauthorized)),
+            Some((401, unauthorized)),
+            Some((200, databases)),
+        ])
+        .await;
+        let influxdb = InfluxDB::new();
+        let timeout = Duration::from_secs(5);
+
+        assert_eq!(influxdb.attempt(&creds(target.clone()), timeout).await, Ok(None));
+        let loot = influxdb
+            .attempt(&creds(target), timeout)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loot[0].get_value("username"), Some("admin"));
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for InfluxDB (automatically enabled for https:// targets).
+    pub influxdb_ssl: bool,
+    #[clap(long, default_value_t = false)]
+    /// Test InfluxDB v2 API tokens as single payloads instead of username and password.
+    pub influxdb_tokens: bool,
+}
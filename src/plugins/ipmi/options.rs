@@ -0,0 +1,10 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Report the RAKP HMAC-SHA1 hash of every existing user in hashcat format (mode 7300) for offline cracking.
+    pub ipmi_dump_hashes: bool,
+}
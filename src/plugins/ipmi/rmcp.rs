@@ -0,0 +1,187 @@
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+use crate::session::Error;
+
+// RMCP version 1.0, no acknowledge, IPMI class
+const RMCP_HEADER: [u8; 4] = [0x06, 0x00, 0xff, 0x07];
+const AUTH_TYPE_RMCP_PLUS: u8 = 0x06;
+
+pub(crate) const PAYLOAD_OPEN_SESSION_REQUEST: u8 = 0x10;
+pub(crate) const PAYLOAD_OPEN_SESSION_RESPONSE: u8 = 0x11;
+pub(crate) const PAYLOAD_RAKP1: u8 = 0x12;
+pub(crate) const PAYLOAD_RAKP2: u8 = 0x13;
+
+// administrator privilege with name only lookup
+const ROLE_ADMIN_NAME_ONLY: u8 = 0x14;
+
+pub(crate) const STATUS_OK: u8 = 0x00;
+pub(crate) const STATUS_UNAUTHORIZED_NAME: u8 = 0x0d;
+
+pub(crate) fn packet(payload_type: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = RMCP_HEADER.to_vec();
+    out.extend_from_slice(&[AUTH_TYPE_RMCP_PLUS, payload_type]);
+    // session id and sequence number are zero outside of a session
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+// payload type and payload of a RMCP+ packet
+pub(crate) fn parse(data: &[u8]) -> Result<(u8, &[u8]), Error> {
+    if data.len() < 16 || data[..4] != RMCP_HEADER || data[4] != AUTH_TYPE_RMCP_PLUS {
+        return Err("invalid RMCP+ packet".to_owned());
+    }
+    let size = u16::from_le_bytes([data[14], data[15]]) as usize;
+    let payload = data
+        .get(16..16 + size)
+        .ok_or_else(|| "truncated RMCP+ packet".to_owned())?;
+    Ok((data[5] & 0x3f, payload))
+}
+
+// requests HMAC-SHA1 authentication, HMAC-SHA1-96 integrity and AES-CBC-128 confidentiality,
+// or no algorithms at all for cipher suite zero
+pub(crate) fn open_session_request(tag: u8, console_id: u32, cipher_zero: bool) -> Vec<u8> {
+    let algorithm = if cipher_zero { 0x00 } else { 0x01 };
+    let mut out = vec![tag, 0x00, 0x00, 0x00];
+    out.extend_from_slice(&console_id.to_le_bytes());
+    for kind in 0..3 {
+        out.extend_from_slice(&[kind, 0x00, 0x00, 0x08, algorithm, 0x00, 0x00, 0x00]);
+    }
+    out
+}
+
+// status and BMC session id of an open session response
+pub(crate) fn parse_open_session_response(payload: &[u8]) -> Result<(u8, u32), Error> {
+    if payload.len() < 2 {
+        return Err("truncated open session response".to_owned());
+    }
+    if payload[1] != STATUS_OK {
+        return Ok((payload[1], 0));
+    }
+    let id = payload
+        .get(8..12)
+        .ok_or_else(|| "truncated open session response".to_owned())?;
+    Ok((STATUS_OK, u32::from_le_bytes(id.try_into().unwrap())))
+}
+
+pub(crate) fn rakp1(tag: u8, bmc_id: u32, console_random: &[u8; 16], username: &str) -> Vec<u8> {
+    let username = &username.as_bytes()[..username.len().min(16)];
+    let mut out = vec![tag, 0x00, 0x00, 0x00];
+    out.extend_from_slice(&bmc_id.to_le_bytes());
+    out.extend_from_slice(console_random);
+    out.extend_from_slice(&[ROLE_ADMIN_NAME_ONLY, 0x00, 0x00, username.len() as u8]);
+    out.extend_from_slice(username);
+    out
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Rakp2 {
+    pub status: u8,
+    pub bmc_random: Vec<u8>,
+    pub bmc_guid: Vec<u8>,
+    pub hmac: Vec<u8>,
+}
+
+pub(crate) fn parse_rakp2(payload: &[u8]) -> Result<Rakp2, Error> {
+    if payload.len() < 2 {
+        return Err("truncated RAKP message 2".to_owned());
+    }
+    let status = payload[1];
+    if status != STATUS_OK {
+        return Ok(Rakp2 {
+            status,
+            bmc_random: vec![],
+            bmc_guid: vec![],
+            hmac: vec![],
+        });
+    }
+    if payload.len() < 40 {
+        return Err("truncated RAKP message 2".to_owned());
+    }
+    Ok(Rakp2 {
+        status,
+        bmc_random: payload[8..24].to_vec(),
+        bmc_guid: payload[24..40].to_vec(),
+        hmac: payload[40..].to_vec(),
+    })
+}
+
+// data authenticated by the RAKP message 2 HMAC, used as salt by hashcat
+pub(crate) fn rakp2_salt(
+    console_id: u32,
+    bmc_id: u32,
+    console_random: &[u8],
+    rakp2: &Rakp2,
+    username: &str,
+) -> Vec<u8> {
+    let username = &username.as_bytes()[..username.len().min(16)];
+    let mut out = console_id.to_le_bytes().to_vec();
+    out.extend_from_slice(&bmc_id.to_le_bytes());
+    out.extend_from_slice(console_random);
+    out.extend_from_slice(&rakp2.bmc_random);
+    out.extend_from_slice(&rakp2.bmc_guid);
+    out.extend_from_slice(&[ROLE_ADMIN_NAME_ONLY, username.len() as u8]);
+    out.extend_from_slice(username);
+    out
+}
+
+pub(crate) fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// hashcat mode 7300
+pub(crate) fn hashcat(salt: &[u8], hmac: &[u8]) -> String {
+    format!("{}:{}", hex::encode(salt), hex::encode(hmac))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hmac_sha1, open_session_request, packet, parse, parse_open_session_response, parse_rakp2,
+        rakp1, rakp2_salt, PAYLOAD_OPEN_SESSION_REQUEST, PAYLOAD_RAKP1,
+    };
+
+    #[test]
+    fn can_build_requests() {
+        let request = packet(
+            PAYLOAD_OPEN_SESSION_REQUEST,
+            &open_session_request(0, 0x11223344, false),
+        );
+        assert_eq!(request.len(), 16 + 32);
+        assert_eq!(&request[..6], &[0x06, 0x00, 0xff, 0x07, 0x06, 0x10]);
+        assert_eq!(&request[14..16], &[32, 0]);
+        assert_eq!(&request[20..24], &[0x44, 0x33, 0x22, 0x11]);
+
+        let request = packet(PAYLOAD_RAKP1, &rakp1(1, 2, &[7; 16], "ADMIN"));
+        let (kind, payload) = parse(&request).unwrap();
+        assert_eq!(kind, PAYLOAD_RAKP1);
+        assert_eq!(&payload[24..28], &[0x14, 0, 0, 5]);
+        assert_eq!(&payload[28..], b"ADMIN");
+    }
+
+    #[test]
+    fn can_verify_rakp2() {
+        let mut open = vec![0, 0, 4, 0, 0x44, 0x33, 0x22, 0x11, 0x78, 0x56, 0x34, 0x12];
+        open.extend_from_slice(&[0; 24]);
+        assert_eq!(parse_open_session_response(&open), Ok((0, 0x12345678)));
+
+        let mut payload = vec![0, 0, 0, 0, 0x44, 0x33, 0x22, 0x11];
+        payload.extend_from_slice(&[0xaa; 16]);
+        payload.extend_from_slice(&[0xbb; 16]);
+        let rakp2 = parse_rakp2(&payload).unwrap();
+        let salt = rakp2_salt(0x11223344, 0x12345678, &[7; 16], &rakp2, "ADMIN");
+        assert_eq!(salt.len(), 4 + 4 + 16 + 16 + 16 + 2 + 5);
+
+        let mut payload = payload.clone();
+        payload.extend(hmac_sha1(b"ADMIN", &salt));
+        let rakp2 = parse_rakp2(&payload).unwrap();
+        assert_eq!(rakp2.hmac, hmac_sha1(b"ADMIN", &salt));
+        assert_ne!(rakp2.hmac, hmac_sha1(b"admin", &salt));
+
+        assert_eq!(parse_rakp2(&[0, 0x0d, 0, 0]).unwrap().status, 0x0d);
+    }
+}
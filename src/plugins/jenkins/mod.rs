@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{redirect, Client, Response, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "jenkins" => Jenkins::new()
+}
+
+const VERSION_HEADER: &str = "X-Jenkins";
+
+// request field and value of the CSRF crumb
+fn parse_crumb(body: &str) -> Option<(String, String)> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    Some((
+        json.get("crumbRequestField")?.as_str()?.to_owned(),
+        json.get("crumb")?.as_str()?.to_owned(),
+    ))
+}
+
+// failed logins are redirected to /loginError
+fn is_login_successful(status: StatusCode, location: Option<&str>) -> bool {
+    status.is_redirection() && location.is_some_and(|l| !l.contains("loginError"))
+}
+
+// the whoAmI endpoint reports anonymous for invalid credentials when anonymous read is allowed
+fn parse_who_am_i(body: &str) -> Option<String> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    let name = json.get("name")?.as_str()?;
+    if name == "anonymous" {
+        None
+    } else {
+        Some(name.to_owned())
+    }
+}
+
+fn version(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_owned())
+}
+
+#[derive(Clone)]
+pub(crate) struct Jenkins {
+    opts: options::Options,
+    client: Client,
+}
+
+impl Jenkins {
+    pub fn new() -> Self {
+        Jenkins {
+            opts: options::Options::default(),
+            client: Client::new(),
+        }
+    }
+
+    // returns the version if the API token is valid
+    async fn check_api_token(
+        &self,
+        base: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Option<String>>, Error> {
+        let response = self
+            .client
+            .get(format!("{}/whoAmI/api/json", base))
+            .basic_auth(&creds.username, Some(&creds.password))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+        let version = version(&response);
+        Ok(parse_who_am_i(&response.text().await.unwrap_or_default()).map(|_| version))
+    }
+
+    // returns the version if the form login is successful
+    async fn check_login(
+        &self,
+        base: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Option<String>>, Error> {
+        // the crumb is bound to the session cookie
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .cookie_store(true)
+            .redirect(redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let mut form = vec![
+            ("j_username".to_owned(), creds.username.to_owned()),
+            ("j_password".to_owned(), creds.password.to_owned()),
+            ("from".to_owned(), "/".to_owned()),
+            ("Submit".to_owned(), "Sign in".to_owned()),
+        ];
+        let mut request = client.post(format!("{}/j_spring_security_check", base));
+
+        let response = client
+            .get(format!("{}/crumbIssuer/api/json", base))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut version = version(&response);
+        // the crumb issuer is disabled or not readable anonymously
+        if let Some((field, crumb)) = parse_crumb(&response.text().await.unwrap_or_default()) {
+            request = request.header(&field, &crumb);
+            form.push((field, crumb));
+        }
+
+        let response = request
+            .form(&form)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|l| l.to_str().ok());
+        if !is_login_successful(response.status(), location) {
+            return Ok(None);
+        }
+        version = version.or_else(|| self::version(&response));
+        Ok(Some(version))
+    }
+}
+
+#[async_trait]
+impl Plugin for Jenkins {
+    fn description(&self) -> &'static str {
+        "Jenkins form login and API token authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.jenkins.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8080)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.jenkins_ssl || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.jenkins_path.trim_end_matches('/')
+        );
+
+        let result = if self.opts.jenkins_api_token {
+            self.check_api_token(&base, creds, timeout).await?
+        } else {
+            self.check_login(&base, creds, timeout).await?
+        };
+        let Some(version) = result else {
+            return Ok(None);
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            (
+                if self.opts.jenkins_api_token {
+                    "token"
+                } else {
+                    "password"
+                }
+                .to_owned(),
+                creds.password.to_owned(),
+            ),
+        ];
+        if let Some(version) = version {
+            data.push(("version".to_owned(), version));
+        }
+        Ok(Some(vec![Loot::new("jenkins", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{is_login_successful, parse_crumb, parse_who_am_i};
+
+    #[test]
+    fn can_parse_crumb() {
+        assert_eq!(
+            parse_crumb(
+                r#"{"_class":"hudson.security.csrf.DefaultCrumbIssuer","crumb":"7f1e3b","crumbRequestField":"Jenkins-Crumb"}"#
+            ),
+            Some(("Jenkins-Crumb".to_owned(), "7f1e3b".to_owned()))
+        );
+        assert_eq!(parse_crumb("<html>403</html>"), None);
+    }
+
+    #[test]
+    fn can_detect_login_outcome() {
+        assert!(is_login_successful(StatusCode::FOUND, Some("http://ci:8080/")));
+        assert!(!is_login_successful(
+            StatusCode::FOUND,
+            Some("http://ci:8080/loginError")
+        ));
+        assert!(!is_login_successful(StatusCode::OK, None));
+        assert_eq!(
+            parse_who_am_i(r#"{"anonymous":false,"authenticated":true,"name":"admin"}"#),
+            Some("admin".to_owned())
+        );
+        assert_eq!(
+            parse_who_am_i(r#"{"anonymous":true,"authenticated":true,"name":"anonymous"}"#),
+            None
+        );
+    }
+}
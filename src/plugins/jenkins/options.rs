@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Jenkins (automatically enabled for https:// targets).
+    pub jenkins_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// Jenkins base path when served from a sub path, e.g. /jenkins.
+    pub jenkins_path: String,
+    #[clap(long, default_value_t = false)]
+    /// Test passwords as API tokens with basic authentication instead of the login form.
+    pub jenkins_api_token: bool,
+}
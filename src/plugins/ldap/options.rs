@@ -1,10 +1,26 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+#[derive(Default, Clone, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Bind {
+    #[default]
+    Simple,
+    DigestMd5,
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
 pub(crate) struct Options {
     #[clap(long)]
     /// LDAP domain.
     pub ldap_domain: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Upgrade the connection with StartTLS (ldaps:// targets and port 636 use TLS directly).
+    pub ldap_starttls: bool,
+    #[clap(long, value_enum, default_value_t = Bind::Simple)]
+    /// LDAP bind method (GSSAPI binds are not supported, use the kerberos plugin for Active Directory accounts).
+    pub ldap_bind: Bind,
+    #[clap(long, default_value_t = false)]
+    /// Do not check whether each target allows anonymous binds.
+    pub ldap_no_anonymous: bool,
 }
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+use crate::utils::{self, digest_md5, net::StreamLike};
+
+const RESULT_SUCCESS: u32 = 0;
+const RESULT_SASL_BIND_IN_PROGRESS: u32 = 14;
+
+const STARTTLS_OID: &[u8] = b"1.3.6.1.4.1.1466.20037";
+
+fn ber(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len <= 0xff {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        out.push(0x82);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+// splits a BER element into its tag, content and the remaining data
+fn ber_parse(data: &[u8]) -> Result<(u8, &[u8], &[u8]), Error> {
+    if data.len() < 2 {
+        return Err("truncated LDAP message".to_owned());
+    }
+    let (len, offset) = match data[1] {
+        n if n < 0x80 => (n as usize, 2),
+        n => {
+            let size = (n & 0x7f) as usize;
+            if size > 4 || data.len() < 2 + size {
+                return Err("invalid LDAP message length".to_owned());
+            }
+            let len = data[2..2 + size]
+                .iter()
+                .fold(0usize, |acc, b| acc << 8 | *b as usize);
+            (len, 2 + size)
+        }
+    };
+    if data.len() < offset + len {
+        return Err("truncated LDAP message".to_owned());
+    }
+    Ok((data[0], &data[offset..offset + len], &data[offset + len..]))
+}
+
+fn message(id: u8, op: Vec<u8>) -> Vec<u8> {
+    ber(0x30, &[ber(0x02, &[id]), op].concat())
+}
+
+async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, Error> {
+    let mut header = [0u8; 2];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut data = header.to_vec();
+    let len = if header[1] < 0x80 {
+        header[1] as usize
+    } else {
+        let mut size = vec![0u8; (header[1] & 0x7f).min(4) as usize];
+        stream
+            .read_exact(&mut size)
+            .await
+            .map_err(|e| e.to_string())?;
+        data.extend_from_slice(&size);
+        size.iter().fold(0usize, |acc, b| acc << 8 | *b as usize)
+    };
+
+    let mut content = vec![0u8; len];
+    stream
+        .read_exact(&mut content)
+        .await
+        .map_err(|e| e.to_string())?;
+    data.extend(content);
+
+    Ok(data)
+}
+
+// result code and server SASL credentials of a bind or extended response
+fn parse_response(data: &[u8]) -> Result<(u32, Option<Vec<u8>>), Error> {
+    let (_, msg, _) = ber_parse(data)?;
+    // skip the message id
+    let (_, _, op) = ber_parse(msg)?;
+    let (_, mut fields, _) = ber_parse(op)?;
+
+    let (_, code, rest) = ber_parse(fields)?;
+    let code = code.iter().fold(0u32, |acc, b| acc << 8 | *b as u32);
+    fields = rest;
+
+    let mut server_creds = None;
+    while !fields.is_empty() {
+        let (tag, value, rest) = ber_parse(fields)?;
+        if tag == 0x87 {
+            server_creds = Some(value.to_vec());
+        }
+        fields = rest;
+    }
+
+    Ok((code, server_creds))
+}
+
+async fn request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    msg: Vec<u8>,
+) -> Result<(u32, Option<Vec<u8>>), Error> {
+    stream.write_all(&msg).await.map_err(|e| e.to_string())?;
+    parse_response(&read_message(stream).await?)
+}
+
+fn sasl_bind_request(id: u8, mechanism: &str, credentials: Option<&[u8]>) -> Vec<u8> {
+    let mut sasl = ber(0x04, mechanism.as_bytes());
+    if let Some(credentials) = credentials {
+        sasl.extend(ber(0x04, credentials));
+    }
+    message(
+        id,
+        ber(
+            0x60,
+            &[ber(0x02, &[3]), ber(0x04, b""), ber(0xa3, &sasl)].concat(),
+        ),
+    )
+}
+
+// connect to the server, optionally over TLS or upgrading the connection with StartTLS
+pub(crate) async fn connect(
+    address: &str,
+    ssl: bool,
+    starttls: bool,
+    timeout: Duration,
+) -> Result<Box<dyn StreamLike>, Error> {
+    let mut stream = utils::net::async_tcp_stream(address, timeout, ssl).await?;
+    if starttls && !ssl {
+        let (code, _) = request(
+            &mut stream,
+            message(1, ber(0x77, &ber(0x80, STARTTLS_OID))),
+        )
+        .await?;
+        if code != RESULT_SUCCESS {
+            return Err(format!("StartTLS failed with result code {}", code));
+        }
+        stream = utils::net::upgrade_tcp_stream_to_ssl(stream, timeout).await?;
+    }
+    Ok(stream)
+}
+
+// returns true if the DIGEST-MD5 bind was successful
+pub(crate) async fn digest_md5_bind<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    host: &str,
+    realm: &str,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    let (code, challenge) = request(stream, sasl_bind_request(2, "DIGEST-MD5", None)).await?;
+    if code != RESULT_SASL_BIND_IN_PROGRESS {
+        return Err(format!("DIGEST-MD5 not supported (result code {})", code));
+    }
+    let response = digest_md5::client_response(
+        &challenge.unwrap_or_default(),
+        realm,
+        &format!("ldap/{}", host),
+        username,
+        password,
+    )?;
+
+    let (code, _) = request(
+        stream,
+        sasl_bind_request(3, "DIGEST-MD5", Some(response.as_bytes())),
+    )
+    .await?;
+
+    Ok(code == RESULT_SUCCESS || code == RESULT_SASL_BIND_IN_PROGRESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_response, sasl_bind_request};
+
+    #[test]
+    fn can_encode_and_parse_binds() {
+        assert_eq!(
+            sasl_bind_request(2, "DIGEST-MD5", None),
+            b"\x30\x18\x02\x01\x02\x60\x13\x02\x01\x03\x04\x00\xa3\x0c\x04\x0aDIGEST-MD5"
+        );
+        // bind response, saslBindInProgress with server credentials
+        assert_eq!(
+            parse_response(b"\x30\x10\x02\x01\x02\x61\x0b\x0a\x01\x0e\x04\x00\x04\x00\x87\x02hi")
+                .unwrap(),
+            (14, Some(b"hi".to_vec()))
+        );
+    }
+}
@@ -8,7 +8,9 @@ use rand::Rng;
 use std::sync::Arc;
 use tokio::task;
 
+use crate::creds;
 use crate::session::{Error, Session};
+use crate::utils::Targets;
 use crate::Plugin;
 use crate::{report, Options};
 
@@ -68,19 +70,44 @@ pub(crate) fn list() {
     }
 }
 
-pub(crate) fn setup(options: &Options) -> Result<&'static mut dyn Plugin, Error> {
+// whether the selected plugin connects to the targets, the auto plugin always does
+pub(crate) fn requires_network(options: &Options) -> bool {
     let Some(plugin_name) = options.plugin.as_ref() else {
+        return true;
+    };
+    let mut inventory = INVENTORY.lock().unwrap();
+    if !inventory.contains_key(plugin_name.as_str()) {
+        // the plugin might have been used already by a previous --then stage
+        super::add_defaults(&mut *inventory);
+    }
+    inventory
+        .get(plugin_name.as_str())
+        .is_none_or(|plugin| plugin.requires_network())
+}
+
+pub(crate) fn setup(
+    options: &Options,
+    targets: &Targets,
+) -> Result<&'static mut dyn Plugin, Error> {
+    let plugin_name = if let Some(plugin_name) = options.plugin.as_ref() {
+        plugin_name.as_str()
+    } else if options.target.is_some() {
+        // select the plugins from the targets uri scheme or port
+        log::info!("no plugin selected, using auto");
+        "auto"
+    } else {
         return Err("no plugin selected".to_owned());
     };
-    let Some(plugin) = INVENTORY
-        .lock()
-        .unwrap()
-        .remove(plugin_name.as_str())
-        .map(Box::leak)
-    else {
+    let mut inventory = INVENTORY.lock().unwrap();
+    if !inventory.contains_key(plugin_name) {
+        // the plugin might have been used already by a previous --then stage
+        super::add_defaults(&mut *inventory);
+    }
+    let Some(plugin) = inventory.remove(plugin_name).map(Box::leak) else {
         return Err(format!("{} is not a valid plugin name, run with --list-plugins to see the list of available plugins", plugin_name));
     };
 
+    plugin.set_targets(targets)?;
     plugin.setup(options)?;
 
     Ok(plugin)
@@ -92,7 +119,7 @@ pub(crate) async fn run(
 ) -> Result<(), Error> {
     let single = matches!(plugin.payload_strategy(), PayloadStrategy::Single);
     let override_payload = plugin.override_payload();
-    let combinations = session.combinations(override_payload, single)?;
+    let mut combinations = session.combinations(override_payload, single)?;
     let unreachables: Arc<RwLock<HashSet<String>>> = Arc::new(RwLock::new(HashSet::default()));
 
     // spawn worker threads
@@ -109,7 +136,7 @@ pub(crate) async fn run(
     }
 
     // loop credentials for this session
-    for creds in combinations {
+    for creds in combinations.by_ref() {
         // exit on ctrl-c if we have to, otherwise send the new credentials to the workers
         if session.is_stop() {
             log::debug!("exiting loop");
@@ -119,6 +146,16 @@ pub(crate) async fn run(
         }
     }
 
+    // the search space size is an upper bound if some payloads have been rejected
+    session.set_total(combinations.dispatched());
+
+    if session.options.dedup_payloads {
+        log::info!(
+            "skipped {} duplicated payloads",
+            creds::skipped_duplicates()
+        );
+    }
+
     Ok(())
 }
 
@@ -186,7 +223,8 @@ async fn worker(
                     Ok(loot) => {
                         // do we have new loot?
                         if let Some(loots) = loot {
-                            for loot in loots {
+                            for mut loot in loots {
+                                loot.set_tags(creds.tags.clone());
                                 session.add_loot(loot).await.unwrap();
                             }
                         }
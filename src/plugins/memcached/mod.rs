@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+super::manager::register_plugin! {
+    "memcached" => Memcached::new()
+}
+
+// reported statistics
+const STATS: &[&str] = &[
+    "version",
+    "uptime",
+    "curr_connections",
+    "curr_items",
+    "total_items",
+    "bytes",
+];
+
+const MAX_LINES: usize = 1024;
+
+// returns the server statistics if no authentication is required
+async fn stats<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+) -> Result<Option<Vec<(String, String)>>, Error> {
+    stream
+        .write_all(b"stats\r\n")
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut reader = BufReader::new(stream);
+    let mut stats = vec![];
+    for _ in 0..MAX_LINES {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            // binary only servers with SASL enabled close the connection
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line == "END" {
+            return Ok(Some(stats));
+        } else if line.ends_with("ERROR") || line.contains("_ERROR ") {
+            // CLIENT_ERROR unauthenticated and the likes
+            log::debug!("memcached: {}", line);
+            return Ok(None);
+        } else if let Some(stat) = line.strip_prefix("STAT ") {
+            if let Some((name, value)) = stat.split_once(' ') {
+                if STATS.contains(&name) {
+                    stats.push((name.to_owned(), value.to_owned()));
+                }
+            }
+        }
+    }
+
+    Err("too many memcached statistics".to_owned())
+}
+
+#[derive(Clone)]
+pub(crate) struct Memcached {}
+
+impl Memcached {
+    pub fn new() -> Self {
+        Memcached {}
+    }
+}
+
+#[async_trait]
+impl Plugin for Memcached {
+    fn description(&self) -> &'static str {
+        "Memcached unauthenticated access detection."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    // there's no credential to test, only run once per target
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "stats".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 11211)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let stats = tokio::time::timeout(timeout, stats(&mut stream))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        Ok(stats.map(|stats| {
+            let mut data = vec![("authentication".to_owned(), "none".to_owned())];
+            data.extend(stats);
+            vec![Loot::new("memcached", &address, data)]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stats;
+
+    #[tokio::test]
+    async fn can_read_stats() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"stats\r\n")
+            .read(b"STAT pid 1\r\nSTAT uptime 42\r\nSTAT version 1.6.21\r\nSTAT curr_items 3\r\nEND\r\n")
+            .build();
+        assert_eq!(
+            stats(&mut stream).await,
+            Ok(Some(vec![
+                ("uptime".to_owned(), "42".to_owned()),
+                ("version".to_owned(), "1.6.21".to_owned()),
+                ("curr_items".to_owned(), "3".to_owned()),
+            ]))
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"stats\r\n")
+            .read(b"CLIENT_ERROR unauthenticated\r\n")
+            .build();
+        assert_eq!(stats(&mut stream).await, Ok(None));
+    }
+}
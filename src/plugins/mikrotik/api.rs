@@ -0,0 +1,255 @@
+use md5::{Digest, Md5};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const MAX_WORD_SIZE: usize = 1024 * 1024;
+const MAX_SENTENCES: usize = 1024;
+
+// a reply sentence, like !done, !re or !trap, and its attributes
+#[derive(Debug, PartialEq)]
+pub(crate) struct Sentence {
+    pub reply: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+impl Sentence {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn encode_length(len: usize) -> Vec<u8> {
+    let len = len as u32;
+    if len < 0x80 {
+        vec![len as u8]
+    } else if len < 0x4000 {
+        (len | 0x8000).to_be_bytes()[2..].to_vec()
+    } else if len < 0x200000 {
+        (len | 0xC00000).to_be_bytes()[1..].to_vec()
+    } else if len < 0x10000000 {
+        (len | 0xE0000000).to_be_bytes().to_vec()
+    } else {
+        [&[0xF0], &len.to_be_bytes()[..]].concat()
+    }
+}
+
+pub(crate) fn encode_sentence(words: &[&str]) -> Vec<u8> {
+    let mut data = vec![];
+    for word in words {
+        data.extend(encode_length(word.len()));
+        data.extend_from_slice(word.as_bytes());
+    }
+    // sentences are terminated by an empty word
+    data.push(0);
+    data
+}
+
+async fn read_length<S: AsyncRead + Unpin>(stream: &mut S) -> Result<usize, Error> {
+    let first = stream.read_u8().await.map_err(|e| e.to_string())? as usize;
+    let (value, extra) = if first & 0x80 == 0 {
+        (first, 0)
+    } else if first & 0xC0 == 0x80 {
+        (first & 0x3F, 1)
+    } else if first & 0xE0 == 0xC0 {
+        (first & 0x1F, 2)
+    } else if first & 0xF0 == 0xE0 {
+        (first & 0x0F, 3)
+    } else {
+        (0, 4)
+    };
+
+    let mut len = value;
+    for _ in 0..extra {
+        len = (len << 8) | stream.read_u8().await.map_err(|e| e.to_string())? as usize;
+    }
+    Ok(len)
+}
+
+async fn read_sentence<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Sentence, Error> {
+    let mut words = vec![];
+    loop {
+        let len = read_length(stream).await?;
+        if len == 0 {
+            break;
+        } else if len > MAX_WORD_SIZE {
+            return Err(format!("word too big: {} bytes", len));
+        }
+        let mut word = vec![0; len];
+        stream
+            .read_exact(&mut word)
+            .await
+            .map_err(|e| e.to_string())?;
+        words.push(String::from_utf8_lossy(&word).to_string());
+    }
+
+    let mut words = words.into_iter();
+    let reply = words.next().ok_or("empty sentence")?;
+    // =name=value attributes, .tag and the likes are ignored
+    let attributes = words
+        .filter_map(|word| {
+            word.strip_prefix('=')
+                .and_then(|attr| attr.split_once('='))
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        })
+        .collect();
+
+    Ok(Sentence { reply, attributes })
+}
+
+// sends a command and returns its reply sentences up to !done
+pub(crate) async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    words: &[&str],
+) -> Result<Vec<Sentence>, Error> {
+    stream
+        .write_all(&encode_sentence(words))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut sentences = vec![];
+    for _ in 0..MAX_SENTENCES {
+        let sentence = read_sentence(stream).await?;
+        match sentence.reply.as_str() {
+            "!done" => {
+                sentences.push(sentence);
+                return Ok(sentences);
+            }
+            "!fatal" => {
+                return Err(format!(
+                    "fatal error: {}",
+                    sentence.attributes.first().map(|(_, v)| v.as_str()).unwrap_or_default()
+                ))
+            }
+            _ => sentences.push(sentence),
+        }
+    }
+
+    Err("too many reply sentences".to_owned())
+}
+
+fn trap(sentences: &[Sentence]) -> Option<&Sentence> {
+    sentences.iter().find(|s| s.reply == "!trap")
+}
+
+// legacy (pre 6.43) challenge response: 00 followed by md5(0 + password + challenge)
+fn challenge_response(password: &str, challenge: &str) -> Result<String, Error> {
+    let challenge = hex::decode(challenge).map_err(|e| e.to_string())?;
+    let digest = Md5::new()
+        .chain_update([0])
+        .chain_update(password.as_bytes())
+        .chain_update(challenge)
+        .finalize();
+    Ok(format!("00{}", hex::encode(digest)))
+}
+
+pub(crate) async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    let name = format!("=name={}", username);
+    let replies = command(
+        stream,
+        &["/login", &name, &format!("=password={}", password)],
+    )
+    .await?;
+    if let Some(trap) = trap(&replies) {
+        log::debug!("login failed: {}", trap.get("message").unwrap_or_default());
+        return Ok(false);
+    }
+
+    // older versions ignore the password and reply with a challenge
+    let challenge = replies.last().and_then(|done| done.get("ret"));
+    if let Some(challenge) = challenge {
+        let response = format!("=response={}", challenge_response(password, challenge)?);
+        let replies = command(stream, &["/login", &name, &response]).await?;
+        return Ok(trap(&replies).is_none());
+    }
+
+    Ok(true)
+}
+
+// attributes of the first item returned by a print command
+pub(crate) async fn print<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    path: &str,
+) -> Result<Vec<(String, String)>, Error> {
+    let replies = command(stream, &[&format!("{}/print", path)]).await?;
+    Ok(replies
+        .into_iter()
+        .find(|s| s.reply == "!re")
+        .map(|s| s.attributes)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{challenge_response, encode_length, encode_sentence, login, print};
+
+    #[test]
+    fn can_encode_lengths() {
+        assert_eq!(encode_length(0x7f), vec![0x7f]);
+        assert_eq!(encode_length(0x80), vec![0x80, 0x80]);
+        assert_eq!(encode_length(0x3fff), vec![0xbf, 0xff]);
+        assert_eq!(encode_length(0x4000), vec![0xc0, 0x40, 0x00]);
+        assert_eq!(encode_length(0x200000), vec![0xe0, 0x20, 0x00, 0x00]);
+        assert_eq!(
+            encode_length(0x10000000),
+            vec![0xf0, 0x10, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            challenge_response("secret", "0123456789abcdef0123456789abcdef").unwrap(),
+            "00ebbe7c3df6b3d902bfd1f355c6e63289"
+        );
+    }
+
+    #[tokio::test]
+    async fn can_login() {
+        let request = encode_sentence(&["/login", "=name=admin", "=password=secret"]);
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&request)
+            .read(&encode_sentence(&["!done"]))
+            .build();
+        assert_eq!(login(&mut stream, "admin", "secret").await, Ok(true));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&request)
+            .read(&encode_sentence(&[
+                "!trap",
+                "=message=invalid user name or password (6)",
+            ]))
+            .read(&encode_sentence(&["!done"]))
+            .build();
+        assert_eq!(login(&mut stream, "admin", "secret").await, Ok(false));
+
+        // pre 6.43 challenge response
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&request)
+            .read(&encode_sentence(&[
+                "!done",
+                "=ret=0123456789abcdef0123456789abcdef",
+            ]))
+            .write(&encode_sentence(&[
+                "/login",
+                "=name=admin",
+                "=response=00ebbe7c3df6b3d902bfd1f355c6e63289",
+            ]))
+            .read(&encode_sentence(&["!done"]))
+            .build();
+        assert_eq!(login(&mut stream, "admin", "secret").await, Ok(true));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&encode_sentence(&["/system/identity/print"]))
+            .read(&encode_sentence(&["!re", "=name=core-router"]))
+            .read(&encode_sentence(&["!done"]))
+            .build();
+        assert_eq!(
+            print(&mut stream, "/system/identity").await,
+            Ok(vec![("name".to_owned(), "core-router".to_owned())])
+        );
+    }
+}
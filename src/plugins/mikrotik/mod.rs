@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod api;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "mikrotik" => Mikrotik::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct Mikrotik {
+    opts: options::Options,
+}
+
+impl Mikrotik {
+    pub fn new() -> Self {
+        Mikrotik {
+            opts: options::Options::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Mikrotik {
+    fn description(&self) -> &'static str {
+        "MikroTik RouterOS API password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.mikrotik.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8728)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.mikrotik_ssl || port == 8729;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, ssl).await?;
+
+        let valid = tokio::time::timeout(
+            timeout,
+            api::login(&mut stream, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+        if !valid {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        // the device details are a best effort, the account might lack the read policy
+        if let Ok(Ok(identity)) =
+            tokio::time::timeout(timeout, api::print(&mut stream, "/system/identity")).await
+        {
+            data.extend(
+                identity
+                    .into_iter()
+                    .filter(|(k, _)| k == "name")
+                    .map(|(_, v)| ("identity".to_owned(), v)),
+            );
+        }
+        if let Ok(Ok(resource)) =
+            tokio::time::timeout(timeout, api::print(&mut stream, "/system/resource")).await
+        {
+            data.extend(
+                resource
+                    .into_iter()
+                    .filter(|(k, _)| k == "version" || k == "board-name"),
+            );
+        }
+
+        Ok(Some(vec![Loot::new("mikrotik", &address, data)]))
+    }
+}
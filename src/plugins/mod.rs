@@ -2,10 +2,9 @@ pub(crate) mod manager;
 
 mod plugin;
 
-pub(crate) use plugin::Plugin;
+pub(crate) use plugin::{PayloadStrategy, Plugin};
 
 // TODO: AFP
-// TODO: SNMP
 // TODO: network discovery
 
 macro_rules! plug {
@@ -24,54 +23,165 @@ macro_rules! plug {
 }
 
 plug! {
+    pub(crate) auto;
     pub(crate) cmd;
 
+    #[cfg(feature = "adfs")]
+    pub(crate) adfs;
     #[cfg(feature = "amqp")]
     pub(crate) amqp;
+    #[cfg(feature = "archive")]
+    pub(crate) archive;
+    #[cfg(feature = "cisco")]
+    pub(crate) cisco;
+    #[cfg(feature = "consul")]
+    pub(crate) consul;
+    #[cfg(feature = "couchdb")]
+    pub(crate) couchdb;
+    #[cfg(feature = "cql")]
+    pub(crate) cql;
+    #[cfg(feature = "db2")]
+    pub(crate) db2;
+    #[cfg(feature = "discovery")]
+    pub(crate) discovery;
     #[cfg(feature = "dns")]
     pub(crate) dns;
+    #[cfg(feature = "docker")]
+    pub(crate) docker;
+    #[cfg(feature = "elastic")]
+    pub(crate) elastic;
+    #[cfg(feature = "etcd")]
+    pub(crate) etcd;
+    #[cfg(feature = "exchange")]
+    pub(crate) exchange;
+    #[cfg(feature = "firebird")]
+    pub(crate) firebird;
     #[cfg(feature = "ftp")]
-    ftp;
+    pub(crate) ftp;
+    #[cfg(feature = "git")]
+    pub(crate) git;
+    #[cfg(feature = "gitea")]
+    pub(crate) gitea;
+    #[cfg(feature = "gitlab")]
+    pub(crate) gitlab;
+    #[cfg(feature = "grafana")]
+    pub(crate) grafana;
     #[cfg(feature = "http")]
     pub(crate) http;
+    #[cfg(feature = "http_proxy")]
+    pub(crate) http_proxy;
+    #[cfg(feature = "ics")]
+    pub(crate) ics;
     #[cfg(feature = "imap")]
-    imap;
+    pub(crate) imap;
+    #[cfg(feature = "influxdb")]
+    pub(crate) influxdb;
+    #[cfg(feature = "ipmi")]
+    pub(crate) ipmi;
+    #[cfg(feature = "jenkins")]
+    pub(crate) jenkins;
+    #[cfg(feature = "jwt")]
+    pub(crate) jwt;
+    #[cfg(feature = "k8s")]
+    pub(crate) k8s;
+    #[cfg(feature = "kafka")]
+    pub(crate) kafka;
     #[cfg(feature = "kerberos")]
     pub(crate) kerberos;
     #[cfg(feature = "ldap")]
     pub(crate) ldap;
+    #[cfg(feature = "memcached")]
+    pub(crate) memcached;
+    #[cfg(feature = "mikrotik")]
+    pub(crate) mikrotik;
     #[cfg(feature = "mongodb")]
     pub(crate) mongodb;
+    #[cfg(feature = "mumble")]
+    pub(crate) mumble;
+    #[cfg(feature = "msol")]
+    pub(crate) msol;
     #[cfg(feature = "mqtt")]
     pub(crate) mqtt;
     #[cfg(feature = "mssql")]
     mssql;
+    #[cfg(feature = "nats")]
+    pub(crate) nats;
+    #[cfg(feature = "nntp")]
+    pub(crate) nntp;
+    #[cfg(feature = "neo4j")]
+    pub(crate) neo4j;
+    #[cfg(feature = "onvif")]
+    pub(crate) onvif;
+    #[cfg(feature = "oracle_sid")]
+    pub(crate) oracle_sid;
     #[cfg(feature = "oracle")]
     pub(crate) oracle; // optional as it requires libclntsh that's a pain to install and configure
     #[cfg(feature = "pop3")]
     pub(crate) pop3;
     #[cfg(feature = "port_scanner")]
     pub(crate) port_scanner;
+    #[cfg(feature = "radius")]
+    pub(crate) radius;
+    #[cfg(feature = "raw")]
+    pub(crate) raw;
+    #[cfg(feature = "rcon")]
+    pub(crate) rcon;
     #[cfg(feature = "rdp")]
     pub(crate) rdp;
     #[cfg(feature = "redis")]
     pub(crate) redis;
+    #[cfg(feature = "rexec")]
+    pub(crate) rexec;
+    #[cfg(feature = "rlogin")]
+    pub(crate) rlogin;
+    #[cfg(feature = "rsync")]
+    pub(crate) rsync;
+    #[cfg(feature = "rtsp")]
+    pub(crate) rtsp;
     #[cfg(feature = "samba")]
     pub(crate) samba;
     #[cfg(feature = "scylla")]
     pub(crate) scylla;
+    #[cfg(feature = "script")]
+    pub(crate) script;
     #[cfg(feature = "smtp")]
     pub(crate) smtp;
+    #[cfg(feature = "snmp")]
+    pub(crate) snmp;
     #[cfg(feature = "socks5")]
     pub(crate) socks5;
     #[cfg(feature = "sql")]
     sql;
     #[cfg(feature = "ssh")]
     pub(crate) ssh;
+    #[cfg(feature = "sslvpn")]
+    pub(crate) sslvpn;
     #[cfg(feature = "stomp")]
     pub(crate) stomp;
+    #[cfg(feature = "svn")]
+    pub(crate) svn;
+    #[cfg(feature = "tacacs")]
+    pub(crate) tacacs;
+    #[cfg(feature = "teamspeak")]
+    pub(crate) teamspeak;
     #[cfg(feature = "telnet")]
     pub(crate) telnet;
+    #[cfg(feature = "tls")]
+    pub(crate) tls;
+    #[cfg(feature = "tomcat")]
+    pub(crate) tomcat;
+    #[cfg(feature = "vault")]
+    pub(crate) vault;
     #[cfg(feature = "vnc")]
     pub(crate) vnc;
+    #[cfg(feature = "wildfly")]
+    pub(crate) wildfly;
+    #[cfg(feature = "wasm")]
+    pub(crate) wasm;
+    #[cfg(feature = "winrm")]
+    pub(crate) winrm;
+    #[cfg(feature = "wordpress")]
+    pub(crate) wordpress;
+    #[cfg(feature = "zookeeper")]
+    pub(crate) zookeeper;
 }
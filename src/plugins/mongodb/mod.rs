@@ -1,7 +1,9 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use mongodb::options::Credential;
+use mongodb::bson::doc;
+use mongodb::error::ErrorKind;
+use mongodb::options::{AuthMechanism, Credential, Tls, TlsOptions};
 
 use crate::session::{Error, Loot};
 use crate::Plugin;
@@ -9,26 +11,54 @@ use crate::{utils, Options};
 
 use crate::creds::Credentials;
 
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+use options::Mechanism;
+
 super::manager::register_plugin! {
     "mongodb" => MongoDB::new()
 }
 
+fn is_auth_error(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Authentication { .. } => true,
+        // AuthenticationFailed
+        ErrorKind::Command(command) => command.code == 18,
+        _ => false,
+    }
+}
+
 #[derive(Clone)]
-pub(crate) struct MongoDB {}
+pub(crate) struct MongoDB {
+    opts: options::Options,
+}
 
 impl MongoDB {
     pub fn new() -> Self {
-        MongoDB {}
+        MongoDB {
+            opts: options::Options::default(),
+        }
     }
 }
 
 #[async_trait]
 impl Plugin for MongoDB {
     fn description(&self) -> &'static str {
-        "MongoDB password authentication."
+        "MongoDB SCRAM, PLAIN and x.509 authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.opts.mongodb_mechanism == Mechanism::X509 {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
     }
 
-    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.mongodb.clone();
         Ok(())
     }
 
@@ -38,35 +68,73 @@ impl Plugin for MongoDB {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let (host, port) = utils::parse_target(&creds.target, 27017)?;
+        let x509 = self.opts.mongodb_mechanism == Mechanism::X509;
 
         let mut opts = mongodb::options::ClientOptions::default();
         let mut cred = Credential::default();
 
-        cred.username = Some(creds.username.to_owned());
-        cred.password = Some(creds.password.to_owned());
+        cred.mechanism = match self.opts.mongodb_mechanism {
+            Mechanism::Auto => None,
+            Mechanism::ScramSha1 => Some(AuthMechanism::ScramSha1),
+            Mechanism::ScramSha256 => Some(AuthMechanism::ScramSha256),
+            Mechanism::Plain => Some(AuthMechanism::Plain),
+            Mechanism::X509 => Some(AuthMechanism::MongoDbX509),
+        };
+        if x509 {
+            // the username is taken from the certificate subject
+            cred.source = Some("$external".to_owned());
+        } else {
+            cred.username = Some(creds.username.to_owned());
+            cred.password = Some(creds.password.to_owned());
+            cred.source = Some(self.opts.mongodb_auth_db.to_owned());
+        }
+
+        if x509 || self.opts.mongodb_ssl {
+            let mut tls = TlsOptions::default();
+            tls.allow_invalid_certificates = Some(true);
+            if x509 {
+                tls.cert_key_file_path = Some(creds.single().into());
+            }
+            opts.tls = Some(Tls::Enabled(tls));
+        }
 
         opts.hosts = vec![mongodb::options::ServerAddress::Tcp {
             host: host.to_owned(),
             port: Some(port),
         }];
         opts.connect_timeout = Some(timeout);
+        opts.server_selection_timeout = Some(timeout);
         opts.credential = Some(cred);
 
         let cli = mongodb::Client::with_options(opts).map_err(|e| e.to_string())?;
-        let dbs = cli.list_database_names(None, None).await;
-
-        if let Ok(dbs) = dbs {
-            Ok(Some(vec![Loot::new(
-                "mongodb",
-                &host,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                    ("databases".to_owned(), dbs.join(", ")),
-                ],
-            )]))
+
+        // any command authenticates the connection first
+        let build_info = match cli
+            .database("admin")
+            .run_command(doc! { "buildInfo": 1 }, None)
+            .await
+        {
+            Ok(build_info) => build_info,
+            Err(e) if is_auth_error(&e) => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let mut data = if x509 {
+            vec![("certificate".to_owned(), creds.single().to_owned())]
         } else {
-            Ok(None)
+            vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ]
+        };
+        if let Ok(version) = build_info.get_str("version") {
+            data.push(("version".to_owned(), version.to_owned()));
         }
+        // the user might not have the listDatabases privilege
+        if let Ok(dbs) = cli.list_database_names(None, None).await {
+            data.push(("databases".to_owned(), dbs.join(", ")));
+        }
+
+        Ok(Some(vec![Loot::new("mongodb", &host, data)]))
     }
 }
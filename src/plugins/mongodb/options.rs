@@ -0,0 +1,29 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Mechanism {
+    // negotiated with the server, SCRAM-SHA-256 when supported
+    #[default]
+    Auto,
+    #[value(name = "scram-sha-1")]
+    ScramSha1,
+    #[value(name = "scram-sha-256")]
+    ScramSha256,
+    Plain,
+    X509,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "admin")]
+    /// MongoDB authentication database.
+    pub mongodb_auth_db: String,
+    #[clap(long, value_enum, default_value_t = Mechanism::Auto)]
+    /// MongoDB authentication mechanism, with x509 the payloads are paths of PEM files containing certificate and key.
+    pub mongodb_mechanism: Mechanism,
+    #[clap(long, default_value_t = false)]
+    /// Enable SSL for MongoDB (always enabled with x509).
+    pub mongodb_ssl: bool,
+}
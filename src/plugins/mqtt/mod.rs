@@ -16,10 +16,40 @@ super::manager::register_plugin! {
     "mqtt" => Mqtt::new()
 }
 
+// broker address and paho server URI for the target, supporting tcp, ssl and websocket transports
+fn server_uri(target: &str, ssl: bool) -> Result<(String, String), Error> {
+    let scheme = target.split_once("://").map(|(s, _)| s).unwrap_or("mqtt");
+    let (scheme, default_port) = match (scheme, ssl) {
+        ("ws", false) => ("ws", 80),
+        ("ws", true) | ("wss", _) => ("wss", 443),
+        ("ssl", _) | ("mqtts", _) | (_, true) => ("ssl", 8883),
+        _ => ("tcp", 1883),
+    };
+
+    let address = utils::parse_target_address(target, default_port)?;
+    let uri = if scheme.starts_with("ws") {
+        let path = target
+            .split_once("://")
+            .map(|(_, t)| t)
+            .unwrap_or(target)
+            .split_once('/')
+            .map(|(_, p)| p)
+            .unwrap_or("mqtt");
+        format!("{}://{}/{}", scheme, &address, path)
+    } else {
+        format!("{}://{}", scheme, &address)
+    };
+
+    Ok((address, uri))
+}
+
 #[derive(Clone)]
 pub(crate) struct Mqtt {
     client_id: String,
     use_v5: bool,
+    ssl: bool,
+    cert: Option<String>,
+    key: Option<String>,
 }
 
 impl Mqtt {
@@ -27,8 +57,24 @@ impl Mqtt {
         Mqtt {
             client_id: "legba".to_string(),
             use_v5: false,
+            ssl: false,
+            cert: None,
+            key: None,
         }
     }
+
+    fn ssl_options(&self) -> Result<mqtt::SslOptions, Error> {
+        let mut builder = mqtt::SslOptionsBuilder::new();
+        // we're not interested in verifying the broker
+        builder.verify(false).enable_server_cert_auth(false);
+        if let Some(cert) = &self.cert {
+            builder.key_store(cert).map_err(|e| e.to_string())?;
+        }
+        if let Some(key) = &self.key {
+            builder.private_key(key).map_err(|e| e.to_string())?;
+        }
+        Ok(builder.finalize())
+    }
 }
 
 #[async_trait]
@@ -40,6 +86,9 @@ impl Plugin for Mqtt {
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.client_id = opts.mqtt.mqtt_client_id.clone();
         self.use_v5 = opts.mqtt.mqtt_v5;
+        self.cert = opts.mqtt.mqtt_cert.clone();
+        self.key = opts.mqtt.mqtt_key.clone();
+        self.ssl = opts.mqtt.mqtt_ssl || self.cert.is_some();
         Ok(())
     }
 
@@ -48,28 +97,37 @@ impl Plugin for Mqtt {
         creds: &Credentials,
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
-        let address = utils::parse_target_address(&creds.target, 1883)?;
-        let uri = format!("mqtt://{}", address);
+        let (address, uri) = server_uri(&creds.target, self.ssl)?;
+        let ssl = uri.starts_with("ssl://") || uri.starts_with("wss://");
 
         let create_opts = mqtt::CreateOptionsBuilder::new()
             .server_uri(uri)
             .client_id(self.client_id.to_owned())
+            // the client must be created for v5 in order to connect with v5 options
+            .mqtt_version(if self.use_v5 {
+                mqtt::MQTT_VERSION_5
+            } else {
+                mqtt::MQTT_VERSION_DEFAULT
+            })
             .finalize();
 
         let cli = mqtt::AsyncClient::new(create_opts).map_err(|e| e.to_string())?;
 
-        let conn_opts = if self.use_v5 {
+        let mut conn_opts = if self.use_v5 {
             mqtt::ConnectOptionsBuilder::new_v5()
         } else {
             mqtt::ConnectOptionsBuilder::new() // v3.x
+        };
+        conn_opts
+            .connect_timeout(timeout)
+            .user_name(&creds.username)
+            .password(&creds.password);
+        if ssl {
+            conn_opts.ssl_options(self.ssl_options()?);
         }
-        .connect_timeout(timeout)
-        .user_name(&creds.username)
-        .password(&creds.password)
-        .finalize();
 
-        if let Err(err) = cli.connect(conn_opts).await {
-            match err {
+        match cli.connect(conn_opts.finalize()).await {
+            Err(err) => match err {
                 paho_mqtt::Error::Paho(n) | paho_mqtt::Error::PahoDescr(n, _) => {
                     // Timeouts and failed connections are reported with n=-1, in which case we return the error
                     // as we want to retry --retry times.
@@ -83,16 +141,46 @@ impl Plugin for Mqtt {
                 }
                 // other protocol errors
                 _ => Ok(None),
-            }
-        } else {
-            Ok(Some(vec![Loot::new(
-                "mqtt",
-                &address,
-                [
+            },
+            Ok(response) => {
+                let mut data = vec![
                     ("username".to_owned(), creds.username.to_owned()),
                     ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]))
+                ];
+                if let Some(connect) = response.connect_response() {
+                    data.push(("mqtt_version".to_owned(), connect.mqtt_version.to_string()));
+                }
+                data.push(("reason_code".to_owned(), response.reason_code().to_string()));
+
+                let _ = cli.disconnect(None).await;
+
+                Ok(Some(vec![Loot::new("mqtt", &address, data)]))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::server_uri;
+
+    #[test]
+    fn can_build_server_uri() {
+        assert_eq!(
+            server_uri("10.0.0.1", false).unwrap(),
+            ("10.0.0.1:1883".to_owned(), "tcp://10.0.0.1:1883".to_owned())
+        );
+        assert_eq!(
+            server_uri("mqtts://broker", false).unwrap(),
+            ("broker:8883".to_owned(), "ssl://broker:8883".to_owned())
+        );
+        assert_eq!(
+            server_uri("ws://broker:9001", false).unwrap(),
+            ("broker:9001".to_owned(), "ws://broker:9001/mqtt".to_owned())
+        );
+        assert_eq!(
+            server_uri("ws://broker:8083/ws", true).unwrap(),
+            ("broker:8083".to_owned(), "wss://broker:8083/ws".to_owned())
+        );
+    }
+}
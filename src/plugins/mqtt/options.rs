@@ -10,4 +10,13 @@ pub(crate) struct Options {
     #[clap(long, default_value_t = false)]
     /// use v5 of the MQTT protocol.
     pub mqtt_v5: bool,
+    #[clap(long, default_value_t = false)]
+    /// Enable SSL for MQTT (automatically enabled for ssl://, mqtts:// and wss:// targets).
+    pub mqtt_ssl: bool,
+    #[clap(long)]
+    /// Client certificate PEM file to present to the broker, enables SSL.
+    pub mqtt_cert: Option<String>,
+    #[clap(long)]
+    /// Private key PEM file of the client certificate.
+    pub mqtt_key: Option<String>,
 }
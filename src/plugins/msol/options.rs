@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "organizations")]
+    /// Azure AD tenant id or domain to authenticate against.
+    pub msol_tenant: String,
+    #[clap(long, default_value = "1b730954-1685-4b74-9bfd-dac224a7b894")]
+    /// OAuth client id used for the resource owner password credentials flow.
+    pub msol_client_id: String,
+    #[clap(long, default_value = "https://graph.windows.net")]
+    /// Resource to request the access token for.
+    pub msol_resource: String,
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+super::manager::register_plugin! {
+    "mumble" => Mumble::new()
+}
+
+const ICE_MAGIC: &[u8; 4] = b"IceP";
+const HEADER_SIZE: usize = 14;
+const MAX_MESSAGE_SIZE: usize = 0x10000;
+const REQUEST: u8 = 0;
+const REPLY: u8 = 2;
+const VALIDATE_CONNECTION: u8 = 3;
+// Meta::getAllServers only throws InvalidSecretException
+const REPLY_OK: u8 = 0;
+const REPLY_USER_EXCEPTION: u8 = 1;
+const REPLY_OBJECT_NOT_EXIST: u8 = 2;
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    if value.len() < 255 {
+        buffer.push(value.len() as u8);
+    } else {
+        buffer.push(255);
+        buffer.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    }
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn message(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = ICE_MAGIC.to_vec();
+    // protocol and encoding 1.0, uncompressed
+    message.extend_from_slice(&[1, 0, 1, 0, kind, 0]);
+    message.extend_from_slice(&((HEADER_SIZE + body.len()) as i32).to_le_bytes());
+    message.extend_from_slice(body);
+    message
+}
+
+// Meta::getAllServers with the ice secret in the request context
+fn get_all_servers(request_id: i32, secret: Option<&str>) -> Vec<u8> {
+    let mut body = request_id.to_le_bytes().to_vec();
+    // identity name and category
+    write_string(&mut body, "Meta");
+    write_string(&mut body, "");
+    // no facet
+    body.push(0);
+    write_string(&mut body, "getAllServers");
+    // normal mode
+    body.push(0);
+    match secret {
+        Some(secret) => {
+            body.push(1);
+            write_string(&mut body, "secret");
+            write_string(&mut body, secret);
+        }
+        None => body.push(0),
+    }
+    // empty encapsulation for the parameters
+    body.extend_from_slice(&6i32.to_le_bytes());
+    body.extend_from_slice(&[1, 0]);
+    message(REQUEST, &body)
+}
+
+// returns the message type and body
+async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(u8, Vec<u8>), Error> {
+    let mut header = [0u8; HEADER_SIZE];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+    if &header[..4] != ICE_MAGIC {
+        return Err("not a ZeroC Ice endpoint".to_owned());
+    }
+    let size = i32::from_le_bytes([header[10], header[11], header[12], header[13]]) as usize;
+    if !(HEADER_SIZE..=MAX_MESSAGE_SIZE).contains(&size) {
+        return Err(format!("invalid Ice message size {}", size));
+    }
+    let mut body = vec![0u8; size - HEADER_SIZE];
+    stream
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((header[8], body))
+}
+
+async fn validate_connection<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(), Error> {
+    match read_message(stream).await? {
+        (VALIDATE_CONNECTION, _) => Ok(()),
+        (kind, _) => Err(format!("unexpected Ice message type {}", kind)),
+    }
+}
+
+async fn check_secret<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request_id: i32,
+    secret: Option<&str>,
+) -> Result<bool, Error> {
+    stream
+        .write_all(&get_all_servers(request_id, secret))
+        .await
+        .map_err(|e| e.to_string())?;
+    loop {
+        let (kind, body) = read_message(stream).await?;
+        if kind != REPLY || body.len() < 5 || body[..4] != request_id.to_le_bytes() {
+            continue;
+        }
+        return match body[4] {
+            REPLY_OK => Ok(true),
+            REPLY_USER_EXCEPTION => Ok(false),
+            REPLY_OBJECT_NOT_EXIST => Err("no Meta object, not a Mumble server?".to_owned()),
+            status => Err(format!("unexpected Ice reply status {}", status)),
+        };
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Mumble {
+    // targets and whether their Ice interface has no secret, checked once
+    open: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl Mumble {
+    pub fn new() -> Self {
+        Mumble {
+            open: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Mumble {
+    fn description(&self) -> &'static str {
+        "Mumble server Ice administration secret check."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 6502)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        tokio::time::timeout(timeout, validate_connection(&mut stream))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        {
+            let mut open = self.open.lock().await;
+            match open.get(&address) {
+                // any secret would do
+                Some(true) => return Ok(None),
+                Some(false) => {}
+                None => {
+                    let no_secret =
+                        tokio::time::timeout(timeout, check_secret(&mut stream, 1, None))
+                            .await
+                            .map_err(|e| e.to_string())??;
+                    open.insert(address.clone(), no_secret);
+                    if no_secret {
+                        return Ok(Some(vec![Loot::new(
+                            "mumble",
+                            &address,
+                            [("authentication".to_owned(), "none".to_owned())],
+                        )]));
+                    }
+                }
+            }
+        }
+
+        // being this plugin single credentials, this is going to be the secret
+        let secret = creds.single();
+        if tokio::time::timeout(timeout, check_secret(&mut stream, 2, Some(secret)))
+            .await
+            .map_err(|e| e.to_string())??
+        {
+            Ok(Some(vec![Loot::new(
+                "mumble",
+                &address,
+                [("secret".to_owned(), secret.to_owned())],
+            )]))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_secret, get_all_servers, message, validate_connection};
+
+    #[test]
+    fn can_build_request() {
+        let request = get_all_servers(2, Some("s3cret"));
+        assert_eq!(&request[..10], b"IceP\x01\x00\x01\x00\x00\x00");
+        assert_eq!(request.len(), 61);
+        assert_eq!(&request[10..14], &61i32.to_le_bytes());
+        assert_eq!(
+            &request[14..],
+            b"\x02\x00\x00\x00\x04Meta\x00\x00\x0dgetAllServers\x00\x01\x06secret\x06s3cret\x06\x00\x00\x00\x01\x00"
+        );
+    }
+
+    #[tokio::test]
+    async fn can_check_secret() {
+        let reply = |request_id: i32, status: u8| {
+            let mut body = request_id.to_le_bytes().to_vec();
+            body.push(status);
+            message(2, &body)
+        };
+        let mut stream = tokio_test::io::Builder::new()
+            .read(&message(3, &[]))
+            .write(&get_all_servers(1, None))
+            .read(&reply(1, 1))
+            .write(&get_all_servers(2, Some("s3cret")))
+            .read(&reply(2, 0))
+            .build();
+        assert_eq!(validate_connection(&mut stream).await, Ok(()));
+        assert_eq!(check_secret(&mut stream, 1, None).await, Ok(false));
+        assert_eq!(
+            check_secret(&mut stream, 2, Some("s3cret")).await,
+            Ok(true)
+        );
+    }
+}
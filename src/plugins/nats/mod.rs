@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+use crate::creds::Credentials;
+use crate::utils::once::OncePerTarget;
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "nats" => Nats::new()
+}
+
+const MAX_LINE_SIZE: usize = 64 * 1024;
+
+async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, Error> {
+    let mut line = vec![];
+    loop {
+        let byte = stream.read_u8().await.map_err(|e| e.to_string())?;
+        if byte == b'\n' {
+            break;
+        }
+        line.push(byte);
+        if line.len() > MAX_LINE_SIZE {
+            return Err("NATS protocol line too long".to_owned());
+        }
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_owned())
+}
+
+fn parse_info(line: &str) -> Result<serde_json::Value, Error> {
+    let json = line
+        .strip_prefix("INFO ")
+        .ok_or(format!("unexpected NATS greeting: {}", line))?;
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+// server details worth reporting from the INFO message
+fn info_metadata(info: &serde_json::Value) -> Vec<(String, String)> {
+    ["server_name", "version", "cluster"]
+        .into_iter()
+        .filter_map(|key| {
+            info.get(key)
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .map(|v| (key.to_owned(), v.to_owned()))
+        })
+        .collect()
+}
+
+fn connect_message(info: &serde_json::Value, creds: &Credentials, token: bool) -> String {
+    let mut connect = serde_json::json!({
+        "verbose": false,
+        "pedantic": false,
+        "tls_required": info.get("tls_required").and_then(|v| v.as_bool()).unwrap_or(false),
+        "name": "legba",
+        "lang": "rust",
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": 1,
+    });
+    if token {
+        connect["auth_token"] = creds.single().into();
+    } else {
+        connect["user"] = creds.username.as_str().into();
+        connect["pass"] = creds.password.as_str().into();
+    }
+    format!("CONNECT {}\r\nPING\r\n", connect)
+}
+
+// send the CONNECT message followed by a PING, a PONG means we're authenticated
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    connect: &str,
+) -> Result<bool, Error> {
+    stream
+        .write_all(connect.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    loop {
+        let line = read_line(stream).await?;
+        if line == "PONG" {
+            return Ok(true);
+        } else if let Some(error) = line.strip_prefix("-ERR") {
+            let error = error.trim().trim_matches('\'');
+            let lower = error.to_lowercase();
+            return if lower.contains("authorization") || lower.contains("authentication") {
+                Ok(false)
+            } else {
+                Err(error.to_owned())
+            };
+        }
+        // +OK, INFO updates and the likes
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Nats {
+    opts: options::Options,
+    // targets not requiring authentication, reported only once
+    no_auth: OncePerTarget,
+}
+
+impl Nats {
+    pub fn new() -> Self {
+        Nats {
+            opts: options::Options::default(),
+            no_auth: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Nats {
+    fn description(&self) -> &'static str {
+        "NATS password and token authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.opts.nats_token {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.nats.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 4222)?;
+        if self.no_auth.contains(&address) {
+            return Ok(None);
+        }
+
+        let mut stream =
+            utils::net::async_tcp_stream(&address, timeout, self.opts.nats_ssl).await?;
+        let info = tokio::time::timeout(timeout, read_line(&mut stream))
+            .await
+            .map_err(|e| e.to_string())??;
+        let info = parse_info(&info)?;
+        let mut data = info_metadata(&info);
+
+        if !info
+            .get("auth_required")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            data.insert(0, ("authentication".to_owned(), "none".to_owned()));
+            return Ok(self
+                .no_auth
+                .report(&address, Loot::new("nats", &address, data)));
+        }
+
+        if !self.opts.nats_ssl
+            && info
+                .get("tls_required")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        {
+            stream = utils::net::upgrade_tcp_stream_to_ssl(stream, timeout).await?;
+        }
+
+        let connect = connect_message(&info, creds, self.opts.nats_token);
+        if !tokio::time::timeout(timeout, authenticate(&mut stream, &connect))
+            .await
+            .map_err(|e| e.to_string())??
+        {
+            return Ok(None);
+        }
+
+        let mut loot = if self.opts.nats_token {
+            vec![("token".to_owned(), creds.single().to_owned())]
+        } else {
+            vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ]
+        };
+        loot.append(&mut data);
+
+        Ok(Some(vec![Loot::new("nats", &address, loot)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, info_metadata, parse_info};
+
+    #[test]
+    fn can_parse_info() {
+        let info = parse_info(
+            r#"INFO {"server_id":"NAB","server_name":"nats-1","version":"2.10.4","auth_required":true,"max_payload":1048576}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            info_metadata(&info),
+            vec![
+                ("server_name".to_owned(), "nats-1".to_owned()),
+                ("version".to_owned(), "2.10.4".to_owned()),
+            ]
+        );
+        assert!(parse_info("-ERR 'Stale Connection'").is_err());
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let connect = "CONNECT {}\r\nPING\r\n";
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(connect.as_bytes())
+            .read(b"+OK\r\nPONG\r\n")
+            .build();
+        assert_eq!(authenticate(&mut stream, connect).await, Ok(true));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(connect.as_bytes())
+            .read(b"-ERR 'Authorization Violation'\r\n")
+            .build();
+        assert_eq!(authenticate(&mut stream, connect).await, Ok(false));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(connect.as_bytes())
+            .read(b"-ERR 'Maximum Connections Exceeded'\r\n")
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, connect).await,
+            Err("Maximum Connections Exceeded".to_owned())
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Enable TLS for NATS (automatically enabled when the server requires it).
+    pub nats_ssl: bool,
+    #[clap(long, default_value_t = false)]
+    /// Test authentication tokens as single payloads instead of username and password.
+    pub nats_token: bool,
+}
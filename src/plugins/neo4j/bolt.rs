@@ -0,0 +1,322 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const MAGIC: [u8; 4] = [0x60, 0x60, 0xb0, 0x17];
+
+// 5.0, 4.4 to 4.2, 4.1 and 3, newer versions moved the credentials from HELLO to LOGON
+const VERSIONS: [[u8; 4]; 4] = [
+    [0, 0, 0, 5],
+    [0, 2, 4, 4],
+    [0, 0, 1, 4],
+    [0, 0, 0, 3],
+];
+
+const MSG_HELLO: u8 = 0x01;
+const MSG_SUCCESS: u8 = 0x70;
+const MSG_FAILURE: u8 = 0x7f;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    Struct(u8, Vec<Value>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+fn pack_string(out: &mut Vec<u8>, value: &str) {
+    let len = value.len();
+    if len < 16 {
+        out.push(0x80 | len as u8);
+    } else if len <= 0xff {
+        out.extend_from_slice(&[0xd0, len as u8]);
+    } else {
+        out.push(0xd1);
+        out.extend_from_slice(&(len.min(0xffff) as u16).to_be_bytes());
+    }
+    out.extend_from_slice(&value.as_bytes()[..len.min(0xffff)]);
+}
+
+// HELLO message with the basic authentication token
+fn hello(username: &str, password: &str) -> Vec<u8> {
+    let mut msg = vec![0xb1, MSG_HELLO, 0xa4];
+    for (key, value) in [
+        ("user_agent", "legba/1.0"),
+        ("scheme", "basic"),
+        ("principal", username),
+        ("credentials", password),
+    ] {
+        pack_string(&mut msg, key);
+        pack_string(&mut msg, value);
+    }
+    msg
+}
+
+struct Unpacker<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Unpacker<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() < n {
+            return Err("truncated PackStream value".to_owned());
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn size(&mut self, bytes: usize) -> Result<usize, Error> {
+        Ok(self
+            .take(bytes)?
+            .iter()
+            .fold(0usize, |acc, b| acc << 8 | *b as usize))
+    }
+
+    fn values(&mut self, n: usize) -> Result<Vec<Value>, Error> {
+        (0..n).map(|_| self.unpack()).collect()
+    }
+
+    fn map(&mut self, n: usize) -> Result<Value, Error> {
+        let mut entries = vec![];
+        for _ in 0..n {
+            let Value::String(key) = self.unpack()? else {
+                return Err("invalid PackStream map key".to_owned());
+            };
+            entries.push((key, self.unpack()?));
+        }
+        Ok(Value::Map(entries))
+    }
+
+    fn string(&mut self, n: usize) -> Result<Value, Error> {
+        Ok(Value::String(
+            String::from_utf8_lossy(self.take(n)?).to_string(),
+        ))
+    }
+
+    fn unpack(&mut self) -> Result<Value, Error> {
+        let marker = self.take(1)?[0];
+        match marker {
+            0x00..=0x7f => Ok(Value::Int(marker as i64)),
+            0xf0..=0xff => Ok(Value::Int(marker as i8 as i64)),
+            0x80..=0x8f => self.string((marker & 0x0f) as usize),
+            0x90..=0x9f => Ok(Value::List(self.values((marker & 0x0f) as usize)?)),
+            0xa0..=0xaf => self.map((marker & 0x0f) as usize),
+            0xb0..=0xbf => {
+                let signature = self.take(1)?[0];
+                Ok(Value::Struct(
+                    signature,
+                    self.values((marker & 0x0f) as usize)?,
+                ))
+            }
+            0xc0 => Ok(Value::Null),
+            0xc1 => Ok(Value::Float(f64::from_be_bytes(
+                self.take(8)?.try_into().unwrap(),
+            ))),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xc8 => Ok(Value::Int(self.take(1)?[0] as i8 as i64)),
+            0xc9 => Ok(Value::Int(
+                i16::from_be_bytes(self.take(2)?.try_into().unwrap()) as i64,
+            )),
+            0xca => Ok(Value::Int(
+                i32::from_be_bytes(self.take(4)?.try_into().unwrap()) as i64,
+            )),
+            0xcb => Ok(Value::Int(i64::from_be_bytes(
+                self.take(8)?.try_into().unwrap(),
+            ))),
+            0xd0..=0xd2 => {
+                let n = self.size(1 << (marker - 0xd0))?;
+                self.string(n)
+            }
+            0xd4..=0xd6 => {
+                let n = self.size(1 << (marker - 0xd4))?;
+                Ok(Value::List(self.values(n)?))
+            }
+            0xd8..=0xda => {
+                let n = self.size(1 << (marker - 0xd8))?;
+                self.map(n)
+            }
+            other => Err(format!("unsupported PackStream marker 0x{:02x}", other)),
+        }
+    }
+}
+
+pub(crate) fn unpack(data: &[u8]) -> Result<Value, Error> {
+    Unpacker { data }.unpack()
+}
+
+fn chunk(msg: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    for part in msg.chunks(0xffff) {
+        out.extend_from_slice(&(part.len() as u16).to_be_bytes());
+        out.extend_from_slice(part);
+    }
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+async fn read_message<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, Error> {
+    let mut msg = vec![];
+    loop {
+        let size = stream.read_u16().await.map_err(|e| e.to_string())? as usize;
+        if size == 0 {
+            if msg.is_empty() {
+                // NOOP chunk
+                continue;
+            }
+            return Ok(msg);
+        }
+        let mut part = vec![0u8; size];
+        stream
+            .read_exact(&mut part)
+            .await
+            .map_err(|e| e.to_string())?;
+        msg.extend(part);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // server agent and whether the password must be changed
+    Valid(Option<String>, bool),
+    Invalid,
+}
+
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    let handshake = [&MAGIC[..], &VERSIONS.concat()].concat();
+    stream
+        .write_all(&handshake)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut version = [0u8; 4];
+    stream
+        .read_exact(&mut version)
+        .await
+        .map_err(|e| e.to_string())?;
+    if version == [0; 4] {
+        return Err("no supported Bolt protocol version".to_owned());
+    }
+
+    stream
+        .write_all(&chunk(&hello(username, password)))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let Value::Struct(signature, fields) = unpack(&read_message(stream).await?)? else {
+        return Err("invalid Bolt response".to_owned());
+    };
+    let metadata = fields.into_iter().next().unwrap_or(Value::Null);
+
+    match signature {
+        MSG_SUCCESS => Ok(Outcome::Valid(
+            metadata.get("server").and_then(|s| s.as_str()).map(|s| s.to_owned()),
+            metadata.get("credentials_expired") == Some(&Value::Bool(true)),
+        )),
+        MSG_FAILURE => {
+            let code = metadata
+                .get("code")
+                .and_then(|c| c.as_str())
+                .unwrap_or_default();
+            if code.contains("Security.Unauthorized") {
+                Ok(Outcome::Invalid)
+            } else if code.contains("Security.CredentialsExpired") {
+                Ok(Outcome::Valid(None, true))
+            } else {
+                // rate limiting and the likes
+                Err(format!(
+                    "{}: {}",
+                    code,
+                    metadata
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .unwrap_or_default()
+                ))
+            }
+        }
+        other => Err(format!("unexpected Bolt message 0x{:02x}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, chunk, hello, unpack, Outcome, Value, MAGIC, VERSIONS};
+
+    #[test]
+    fn can_pack_and_unpack() {
+        let msg = hello("neo4j", "a very long password");
+        let Value::Struct(0x01, fields) = unpack(&msg).unwrap() else {
+            panic!("not a struct");
+        };
+        assert_eq!(fields[0].get("scheme").unwrap().as_str(), Some("basic"));
+        assert_eq!(
+            fields[0].get("credentials").unwrap().as_str(),
+            Some("a very long password")
+        );
+
+        assert_eq!(
+            unpack(&[0x93, 0x01, 0xc9, 0x01, 0x00, 0xc3]).unwrap(),
+            Value::List(vec![Value::Int(1), Value::Int(256), Value::Bool(true)])
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut success = vec![0xb1, 0x70, 0xa2];
+        super::pack_string(&mut success, "server");
+        super::pack_string(&mut success, "Neo4j/4.4.26");
+        super::pack_string(&mut success, "credentials_expired");
+        success.push(0xc3);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[&MAGIC[..], &VERSIONS.concat()].concat())
+            .read(&[0, 0, 4, 4])
+            .write(&chunk(&hello("neo4j", "neo4j")))
+            .read(&chunk(&success))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "neo4j", "neo4j").await,
+            Ok(Outcome::Valid(Some("Neo4j/4.4.26".to_owned()), true))
+        );
+
+        let mut failure = vec![0xb1, 0x7f, 0xa1];
+        super::pack_string(&mut failure, "code");
+        super::pack_string(&mut failure, "Neo.ClientError.Security.Unauthorized");
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[&MAGIC[..], &VERSIONS.concat()].concat())
+            .read(&[0, 0, 0, 5])
+            .write(&chunk(&hello("neo4j", "wrong")))
+            .read(&chunk(&failure))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "neo4j", "wrong").await,
+            Ok(Outcome::Invalid)
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+use crate::creds::Credentials;
+
+mod bolt;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "neo4j" => Neo4j::new()
+}
+
+fn is_credentials_expired(body: &str) -> bool {
+    body.contains("CredentialsExpired") || body.contains("password_change")
+}
+
+#[derive(Clone)]
+pub(crate) struct Neo4j {
+    opts: options::Options,
+    client: Client,
+}
+
+impl Neo4j {
+    pub fn new() -> Self {
+        Neo4j {
+            opts: options::Options::default(),
+            client: Client::new(),
+        }
+    }
+
+    async fn attempt_http(
+        &self,
+        base: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<bolt::Outcome>, Error> {
+        // the discovery endpoint doesn't require authentication
+        let version = match self.client.get(base).timeout(timeout).send().await {
+            Ok(res) => serde_json::from_str::<serde_json::Value>(
+                &res.text().await.unwrap_or_default(),
+            )
+            .ok()
+            .and_then(|json| {
+                    json.get("neo4j_version")
+                        .and_then(|v| v.as_str())
+                        .map(|v| format!("Neo4j/{}", v))
+                }),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        // 4.x and newer, then 3.x
+        for path in ["/db/neo4j/tx/commit", "/db/data/transaction/commit"] {
+            let response = self
+                .client
+                .post(format!("{}{}", base.trim_end_matches('/'), path))
+                .basic_auth(&creds.username, Some(&creds.password))
+                .header("Content-Type", "application/json")
+                .body(r#"{"statements":[]}"#)
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let status = response.status();
+            if status == StatusCode::NOT_FOUND {
+                continue;
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            return Ok(match status {
+                StatusCode::OK => Some(bolt::Outcome::Valid(
+                    version,
+                    is_credentials_expired(&body),
+                )),
+                StatusCode::FORBIDDEN if is_credentials_expired(&body) => {
+                    Some(bolt::Outcome::Valid(version, true))
+                }
+                _ => None,
+            });
+        }
+
+        Err("no transaction endpoint found".to_owned())
+    }
+}
+
+#[async_trait]
+impl Plugin for Neo4j {
+    fn description(&self) -> &'static str {
+        "Neo4j Bolt protocol and HTTP API password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.neo4j.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 7687)?;
+        let address = format!("{}:{}", host, port);
+        let http = self.opts.neo4j_http
+            || port == 7474
+            || port == 7473
+            || creds.target.starts_with("http://")
+            || creds.target.starts_with("https://");
+
+        let outcome = if http {
+            let ssl = self.opts.neo4j_ssl || port == 7473 || creds.target.starts_with("https://");
+            let base = format!("{}://{}/", if ssl { "https" } else { "http" }, &address);
+            self.attempt_http(&base, creds, timeout).await?
+        } else {
+            let mut stream =
+                crate::utils::net::async_tcp_stream(&address, timeout, self.opts.neo4j_ssl)
+                    .await?;
+            match tokio::time::timeout(
+                timeout,
+                bolt::authenticate(&mut stream, &creds.username, &creds.password),
+            )
+            .await
+            .map_err(|e| e.to_string())??
+            {
+                bolt::Outcome::Invalid => None,
+                valid => Some(valid),
+            }
+        };
+
+        let Some(bolt::Outcome::Valid(version, credentials_expired)) = outcome else {
+            return Ok(None);
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        if let Some(version) = version {
+            data.push(("version".to_owned(), version));
+        }
+        if credentials_expired {
+            data.push(("password_change_required".to_owned(), "yes".to_owned()));
+        }
+
+        Ok(Some(vec![Loot::new("neo4j", &address, data)]))
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Enable SSL for Bolt and HTTPS for the HTTP API.
+    pub neo4j_ssl: bool,
+    #[clap(long, default_value_t = false)]
+    /// Use the HTTP API instead of Bolt (automatically enabled for http(s):// targets and ports 7474 and 7473).
+    pub neo4j_http: bool,
+}
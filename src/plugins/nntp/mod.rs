@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "nntp" => Nntp::new()
+}
+
+// RFC 4643 response codes
+const AUTH_ACCEPTED: u16 = 281;
+const PASSWORD_REQUIRED: u16 = 381;
+
+async fn read_reply<S: AsyncRead + Unpin>(
+    reader: &mut BufReader<S>,
+) -> Result<(u16, String), Error> {
+    let mut line = String::new();
+    if reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?
+        == 0
+    {
+        return Err("connection closed".to_owned());
+    }
+    let line = line.trim_end();
+    let (code, text) = line.split_once(' ').unwrap_or((line, ""));
+    let code = code
+        .parse::<u16>()
+        .map_err(|_| format!("unexpected NNTP response: {}", line))?;
+    Ok((code, text.to_owned()))
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    command: &str,
+) -> Result<(u16, String), Error> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    read_reply(reader).await
+}
+
+// reads the greeting and returns the server banner
+async fn greeting<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<String, Error> {
+    match read_reply(reader).await? {
+        // posting allowed or not
+        (200 | 201, banner) => Ok(banner),
+        (code, text) => Err(format!("service unavailable ({}): {}", code, text)),
+    }
+}
+
+// AUTHINFO USER / PASS
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    match command(reader, &format!("AUTHINFO USER {}", username)).await? {
+        (AUTH_ACCEPTED, _) => return Ok(true),
+        (PASSWORD_REQUIRED, _) => {}
+        (481, _) => return Ok(false),
+        (code, text) => return Err(format!("AUTHINFO USER failed ({}): {}", code, text)),
+    }
+
+    match command(reader, &format!("AUTHINFO PASS {}", password)).await? {
+        (AUTH_ACCEPTED, _) => Ok(true),
+        (481, _) => Ok(false),
+        (code, text) => Err(format!("AUTHINFO PASS failed ({}): {}", code, text)),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Nntp {
+    ssl: bool,
+}
+
+impl Nntp {
+    pub fn new() -> Self {
+        Nntp { ssl: false }
+    }
+}
+
+#[async_trait]
+impl Plugin for Nntp {
+    fn description(&self) -> &'static str {
+        "NNTP AUTHINFO USER/PASS authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.ssl = opts.nntp.nntp_ssl;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 119)?;
+        let address = format!("{}:{}", host, port);
+        let stream = utils::net::async_tcp_stream(&address, timeout, self.ssl || port == 563).await?;
+        let mut reader = BufReader::new(stream);
+
+        let (banner, valid) = tokio::time::timeout(timeout, async {
+            let banner = greeting(&mut reader).await?;
+            let valid = authenticate(&mut reader, &creds.username, &creds.password).await?;
+            Ok::<_, Error>((banner, valid))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        if !valid {
+            return Ok(None);
+        }
+
+        let _ = command(&mut reader, "QUIT").await;
+        Ok(Some(vec![Loot::new(
+            "nntp",
+            &address,
+            [
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                ("banner".to_owned(), banner),
+            ],
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::{authenticate, greeting};
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"200 news.example.com InterNetNews NNRP server INN 2.6.4 ready (posting ok)\r\n")
+            .write(b"AUTHINFO USER joe\r\n")
+            .read(b"381 Enter password\r\n")
+            .write(b"AUTHINFO PASS secret\r\n")
+            .read(b"281 Authentication succeeded\r\n")
+            .build();
+        let mut reader = BufReader::new(stream);
+        assert_eq!(
+            greeting(&mut reader).await,
+            Ok("news.example.com InterNetNews NNRP server INN 2.6.4 ready (posting ok)".to_owned())
+        );
+        assert_eq!(authenticate(&mut reader, "joe", "secret").await, Ok(true));
+
+        let stream = tokio_test::io::Builder::new()
+            .write(b"AUTHINFO USER joe\r\n")
+            .read(b"381 Enter password\r\n")
+            .write(b"AUTHINFO PASS wrong\r\n")
+            .read(b"481 Authentication failed\r\n")
+            .build();
+        let mut reader = BufReader::new(stream);
+        assert_eq!(authenticate(&mut reader, "joe", "wrong").await, Ok(false));
+
+        let stream = tokio_test::io::Builder::new()
+            .read(b"502 You have no permission to talk.  Goodbye.\r\n")
+            .build();
+        assert!(greeting(&mut BufReader::new(stream)).await.is_err());
+    }
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::Rng;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use sha1::{Digest, Sha1};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::xml::xml_escape;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "onvif" => Onvif::new()
+}
+
+const SOAP_CONTENT_TYPE: &str = "application/soap+xml; charset=utf-8";
+const GET_SYSTEM_DATE_AND_TIME: &str = "<tds:GetSystemDateAndTime/>";
+const GET_DEVICE_INFORMATION: &str = "<tds:GetDeviceInformation/>";
+const DEVICE_INFORMATION: &[(&str, &str)] = &[
+    ("Manufacturer", "manufacturer"),
+    ("Model", "model"),
+    ("FirmwareVersion", "firmware"),
+    ("SerialNumber", "serial"),
+    ("HardwareId", "hardware_id"),
+];
+
+// WS-Security PasswordDigest = base64(sha1(nonce + created + password))
+fn password_digest(nonce: &[u8], created: &str, password: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(nonce);
+    hasher.update(created.as_bytes());
+    hasher.update(password.as_bytes());
+    BASE64_STANDARD.encode(hasher.finalize())
+}
+
+fn security_header(username: &str, password: &str, created: &str) -> String {
+    let nonce: [u8; 16] = rand::thread_rng().gen();
+    format!(
+        r#"<wsse:Security s:mustUnderstand="1" xmlns:wsse="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd" xmlns:wsu="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd"><wsse:UsernameToken><wsse:Username>{}</wsse:Username><wsse:Password Type="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest">{}</wsse:Password><wsse:Nonce EncodingType="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary">{}</wsse:Nonce><wsu:Created>{}</wsu:Created></wsse:UsernameToken></wsse:Security>"#,
+        xml_escape(username),
+        password_digest(&nonce, created, password),
+        BASE64_STANDARD.encode(nonce),
+        created
+    )
+}
+
+fn envelope(header: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:tds="http://www.onvif.org/ver10/device/wsdl"><s:Header>{}</s:Header><s:Body>{}</s:Body></s:Envelope>"#,
+        header, body
+    )
+}
+
+// text of the first element with this local name, whatever its namespace prefix
+fn element(body: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"<(?:[\w-]+:)?{}(?:\s[^>]*)?>([^<]*)</", name)).unwrap();
+    let found = re.captures(body).map(|caps| caps[1].trim().to_owned());
+    found
+}
+
+fn parse_device_information(body: &str) -> Vec<(String, String)> {
+    DEVICE_INFORMATION
+        .iter()
+        .filter_map(|(element_name, key)| {
+            element(body, element_name)
+                .filter(|value| !value.is_empty())
+                .map(|value| (key.to_string(), value))
+        })
+        .collect()
+}
+
+fn parse_device_time(body: &str) -> Option<DateTime<Utc>> {
+    let utc = &body[body.find("UTCDateTime>")?..];
+    let field = |name: &str| element(utc, name).and_then(|value| value.parse::<u32>().ok());
+    let time = NaiveDate::from_ymd_opt(field("Year")? as i32, field("Month")?, field("Day")?)?
+        .and_hms_opt(field("Hour")?, field("Minute")?, field("Second")?)?;
+    Some(DateTime::from_naive_utc_and_offset(time, Utc))
+}
+
+fn is_device_information(status: StatusCode, body: &str) -> bool {
+    status == StatusCode::OK && body.contains("GetDeviceInformationResponse")
+}
+
+// devices reject credentials with a SOAP fault or a plain 401
+fn is_not_authorized(status: StatusCode, body: &str) -> bool {
+    status == StatusCode::UNAUTHORIZED || body.contains("NotAuthorized")
+}
+
+#[derive(Clone, Copy)]
+struct Device {
+    // the Created timestamp must be close to the device clock
+    clock_offset: chrono::Duration,
+    unauthenticated: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct Onvif {
+    opts: options::Options,
+    client: Client,
+    devices: Arc<Mutex<HashMap<String, Device>>>,
+}
+
+impl Onvif {
+    pub fn new() -> Self {
+        Onvif {
+            opts: options::Options::default(),
+            client: Client::new(),
+            devices: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn call(
+        &self,
+        url: &str,
+        header: &str,
+        body: &str,
+        timeout: Duration,
+    ) -> Result<(StatusCode, String), Error> {
+        let response = self
+            .client
+            .post(url)
+            .header("Content-Type", SOAP_CONTENT_TYPE)
+            .body(envelope(header, body))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        Ok((status, body))
+    }
+
+    // clock offset and unauthenticated access are checked once per device
+    async fn device(
+        &self,
+        url: &str,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<(Device, Option<Loot>), Error> {
+        let mut devices = self.devices.lock().await;
+        if let Some(device) = devices.get(url) {
+            return Ok((*device, None));
+        }
+
+        let (_, body) = self
+            .call(url, "", GET_SYSTEM_DATE_AND_TIME, timeout)
+            .await?;
+        let clock_offset = parse_device_time(&body)
+            .map(|device_time| device_time - Utc::now())
+            .unwrap_or_else(chrono::Duration::zero);
+
+        let (status, body) = self.call(url, "", GET_DEVICE_INFORMATION, timeout).await?;
+        let unauthenticated = is_device_information(status, &body);
+        let loot = if unauthenticated {
+            let mut data = vec![("authentication".to_owned(), "none".to_owned())];
+            data.extend(parse_device_information(&body));
+            Some(Loot::new("onvif", address, data))
+        } else {
+            None
+        };
+
+        let device = Device {
+            clock_offset,
+            unauthenticated,
+        };
+        devices.insert(url.to_owned(), device);
+        Ok((device, loot))
+    }
+}
+
+#[async_trait]
+impl Plugin for Onvif {
+    fn description(&self) -> &'static str {
+        "ONVIF WS-Security UsernameToken authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.onvif.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 80)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.onvif_ssl || port == 443 || creds.target.starts_with("https://");
+        let url = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            &self.opts.onvif_path
+        );
+
+        let (device, loot) = self.device(&url, &address, timeout).await?;
+        // any credential would do
+        if device.unauthenticated {
+            return Ok(loot.map(|loot| vec![loot]));
+        }
+
+        let created = (Utc::now() + device.clock_offset)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let (status, body) = self
+            .call(
+                &url,
+                &security_header(&creds.username, &creds.password, &created),
+                GET_DEVICE_INFORMATION,
+                timeout,
+            )
+            .await?;
+
+        if is_device_information(status, &body) {
+            let mut data = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ];
+            data.extend(parse_device_information(&body));
+            Ok(Some(vec![Loot::new("onvif", &address, data)]))
+        } else if is_not_authorized(status, &body) {
+            Ok(None)
+        } else {
+            Err(format!("unexpected response status {}", status))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{
+        is_device_information, is_not_authorized, parse_device_information, parse_device_time,
+        password_digest,
+    };
+
+    #[test]
+    fn can_compute_password_digest() {
+        assert_eq!(
+            password_digest(b"0123456789abcdef", "2024-01-01T00:00:00Z", "admin"),
+            "famYcfqCUhkoZi23xwMJELSaaVQ="
+        );
+    }
+
+    #[test]
+    fn can_parse_device_responses() {
+        let information = r#"<env:Envelope><env:Body><tds:GetDeviceInformationResponse><tds:Manufacturer>HIKVISION</tds:Manufacturer><tds:Model>DS-2CD2143G0-I</tds:Model><tds:FirmwareVersion>V5.5.82 build 190909</tds:FirmwareVersion><tds:SerialNumber>DS-2CD2143G0-I20190101AAWR123456789</tds:SerialNumber><tds:HardwareId>88</tds:HardwareId></tds:GetDeviceInformationResponse></env:Body></env:Envelope>"#;
+        assert!(is_device_information(StatusCode::OK, information));
+        assert_eq!(
+            parse_device_information(information),
+            vec![
+                ("manufacturer".to_owned(), "HIKVISION".to_owned()),
+                ("model".to_owned(), "DS-2CD2143G0-I".to_owned()),
+                ("firmware".to_owned(), "V5.5.82 build 190909".to_owned()),
+                (
+                    "serial".to_owned(),
+                    "DS-2CD2143G0-I20190101AAWR123456789".to_owned()
+                ),
+                ("hardware_id".to_owned(), "88".to_owned()),
+            ]
+        );
+
+        let fault = r#"<env:Envelope><env:Body><env:Fault><env:Code><env:Value>env:Sender</env:Value><env:Subcode><env:Value>ter:NotAuthorized</env:Value></env:Subcode></env:Code></env:Fault></env:Body></env:Envelope>"#;
+        assert!(!is_device_information(StatusCode::BAD_REQUEST, fault));
+        assert!(is_not_authorized(StatusCode::BAD_REQUEST, fault));
+        assert!(is_not_authorized(StatusCode::UNAUTHORIZED, ""));
+
+        // the local time comes first and must be skipped
+        let time = r#"<tds:SystemDateAndTime><tt:DateTimeType>NTP</tt:DateTimeType><tt:LocalDateTime><tt:Time><tt:Hour>10</tt:Hour><tt:Minute>0</tt:Minute><tt:Second>0</tt:Second></tt:Time></tt:LocalDateTime><tt:UTCDateTime><tt:Time><tt:Hour>8</tt:Hour><tt:Minute>30</tt:Minute><tt:Second>15</tt:Second></tt:Time><tt:Date><tt:Year>2024</tt:Year><tt:Month>3</tt:Month><tt:Day>7</tt:Day></tt:Date></tt:UTCDateTime></tds:SystemDateAndTime>"#;
+        assert_eq!(
+            parse_device_time(time).unwrap().to_rfc3339(),
+            "2024-03-07T08:30:15+00:00"
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "/onvif/device_service")]
+    /// ONVIF device service path.
+    pub onvif_path: String,
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS (automatically enabled for port 443 and https:// targets).
+    pub onvif_ssl: bool,
+}
@@ -15,15 +15,33 @@ super::manager::register_plugin! {
     "oracle" => Oracle::new()
 }
 
+// invalid username/password
+const ORA_INVALID_CREDENTIALS: &str = "ORA-01017";
+const ORA_ACCOUNT_LOCKED: &str = "ORA-28000";
+const ORA_PASSWORD_EXPIRED: &str = "ORA-28001";
+
+fn connect_string(host: &str, port: u16, database: &str, sid: Option<&str>) -> String {
+    if let Some(sid) = sid {
+        format!(
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA=(SID={})))",
+            host, port, sid
+        )
+    } else {
+        format!("//{}:{}/{}", host, port, database)
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct Oracle {
     database: String,
+    sid: Option<String>,
 }
 
 impl Oracle {
     pub fn new() -> Self {
         Oracle {
             database: String::new(),
+            sid: None,
         }
     }
 }
@@ -36,6 +54,7 @@ impl Plugin for Oracle {
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.database = opts.oracle.oracle_database.clone();
+        self.sid = opts.oracle.oracle_sid.clone();
         Ok(())
     }
 
@@ -44,29 +63,57 @@ impl Plugin for Oracle {
         creds: &Credentials,
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
-        let address = utils::parse_target_address(&creds.target, 1521)?;
+        let (host, port) = utils::parse_target(&creds.target, 1521)?;
+        let address = format!("{}:{}", &host, port);
+        let database = connect_string(&host, port, &self.database, self.sid.as_deref());
         let oracle = oracle::env().map_err(|e| e.to_string())?;
 
         let op = tokio::time::timeout(
             timeout,
-            oracle.connect(&self.database, &creds.username, &creds.password),
+            oracle.connect(&database, &creds.username, &creds.password),
         )
-        .await;
-
-        if op.is_err() {
-            // timeout
-            Err("timed out".to_owned())
-        } else if let Ok(_) = op.unwrap() {
-            Ok(Some(vec![Loot::from(
-                "oracle",
-                &self.host,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]))
-        } else {
-            Ok(None)
+        .await
+        .map_err(|_| "timed out".to_owned())?;
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+
+        match op {
+            Ok(_) => Ok(Some(vec![Loot::new("oracle", &address, data)])),
+            Err(e) => {
+                let error = e.to_string();
+                if error.contains(ORA_PASSWORD_EXPIRED) {
+                    // valid credentials, but the password must be changed
+                    data.push(("expired_password".to_owned(), "true".to_owned()));
+                    Ok(Some(vec![Loot::new("oracle", &address, data).set_partial()]))
+                } else if error.contains(ORA_INVALID_CREDENTIALS) {
+                    Ok(None)
+                } else if error.contains(ORA_ACCOUNT_LOCKED) {
+                    log::debug!("{}: account {} is locked", &address, &creds.username);
+                    Ok(None)
+                } else {
+                    Err(error)
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::connect_string;
+
+    #[test]
+    fn can_build_connect_string() {
+        assert_eq!(
+            connect_string("10.0.0.1", 1521, "XEPDB1", None),
+            "//10.0.0.1:1521/XEPDB1"
+        );
+        assert_eq!(
+            connect_string("10.0.0.1", 1522, "XEPDB1", Some("XE")),
+            "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=10.0.0.1)(PORT=1522))(CONNECT_DATA=(SID=XE)))"
+        );
+    }
+}
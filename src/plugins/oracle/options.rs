@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 #[group(skip)]
 pub(crate) struct Options {
     #[clap(long, default_value = "SYSTEM")]
-    /// Database name.
+    /// Database service name.
     pub oracle_database: String,
+    #[clap(long)]
+    /// Connect to this SID instead of the service name (see the oracle.sid plugin to enumerate them).
+    pub oracle_sid: Option<String>,
 }
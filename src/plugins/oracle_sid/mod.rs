@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::Options;
+use crate::Plugin;
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "oracle.sid" => OracleSID::new()
+}
+
+const PACKET_CONNECT: u8 = 1;
+const PACKET_ACCEPT: u8 = 2;
+const PACKET_REFUSE: u8 = 4;
+const PACKET_REDIRECT: u8 = 5;
+const PACKET_RESEND: u8 = 11;
+
+// listener errors for unknown SIDs and service names
+const ERR_UNKNOWN_SID: u32 = 12505;
+const ERR_UNKNOWN_SERVICE: u32 = 12514;
+
+// offset of the connect data, header included
+const CONNECT_DATA_OFFSET: u16 = 0x3a;
+
+fn connect_descriptor(host: &str, port: u16, key: &str, value: &str) -> String {
+    format!(
+        "(DESCRIPTION=(CONNECT_DATA=({}={})(CID=(PROGRAM=legba)(HOST=legba)(USER=legba)))(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={})))",
+        key, value, host, port
+    )
+}
+
+// TNS CONNECT packet with the same layout used by tnscmd
+fn connect_packet(data: &str) -> Vec<u8> {
+    let mut body = vec![];
+    body.extend_from_slice(&0x0136u16.to_be_bytes()); // version
+    body.extend_from_slice(&0x012cu16.to_be_bytes()); // lowest compatible version
+    body.extend_from_slice(&0u16.to_be_bytes()); // service options
+    body.extend_from_slice(&0x0800u16.to_be_bytes()); // session data unit size
+    body.extend_from_slice(&0x7fffu16.to_be_bytes()); // maximum transmission data unit size
+    body.extend_from_slice(&0x7f08u16.to_be_bytes()); // NT protocol characteristics
+    body.extend_from_slice(&0u16.to_be_bytes()); // line turnaround value
+    body.extend_from_slice(&1u16.to_be_bytes()); // value of 1 in hardware
+    body.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    body.extend_from_slice(&CONNECT_DATA_OFFSET.to_be_bytes());
+    body.resize(CONNECT_DATA_OFFSET as usize - 8, 0);
+    body.extend_from_slice(data.as_bytes());
+
+    let mut packet = ((body.len() + 8) as u16).to_be_bytes().to_vec();
+    packet.extend_from_slice(&[0, 0, PACKET_CONNECT, 0, 0, 0]);
+    packet.extend(body);
+    packet
+}
+
+// ERR=<code> from a refuse packet description
+fn refuse_error(body: &[u8]) -> Option<u32> {
+    let text = String::from_utf8_lossy(body);
+    let (_, rest) = text.split_once("(ERR=")?;
+    rest.split(')').next()?.parse().ok()
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Found,
+    // known to the listener but refused, usually because no handler is available
+    Refused(u32),
+    NotFound,
+}
+
+async fn probe<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    packet: &[u8],
+) -> Result<Outcome, Error> {
+    // the listener can ask to resend the connect packet once
+    for _ in 0..2 {
+        stream.write_all(packet).await.map_err(|e| e.to_string())?;
+
+        let mut header = [0u8; 8];
+        stream
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| e.to_string())?;
+        let len = (u16::from_be_bytes([header[0], header[1]]) as usize).saturating_sub(8);
+        let mut body = vec![0u8; len];
+        stream
+            .read_exact(&mut body)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        return match header[4] {
+            PACKET_ACCEPT | PACKET_REDIRECT => Ok(Outcome::Found),
+            PACKET_RESEND => continue,
+            PACKET_REFUSE => match refuse_error(&body) {
+                Some(ERR_UNKNOWN_SID) | Some(ERR_UNKNOWN_SERVICE) | None => Ok(Outcome::NotFound),
+                Some(code) => Ok(Outcome::Refused(code)),
+            },
+            other => Err(format!("unexpected TNS packet type {}", other)),
+        };
+    }
+    Err("TNS listener keeps asking to resend".to_owned())
+}
+
+#[derive(Clone)]
+pub(crate) struct OracleSID {
+    service: bool,
+}
+
+impl OracleSID {
+    pub fn new() -> Self {
+        OracleSID { service: false }
+    }
+}
+
+#[async_trait]
+impl Plugin for OracleSID {
+    fn description(&self) -> &'static str {
+        "Oracle TNS listener SID and service name enumeration."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.service = opts.oracle_sid.oracle_sid_service;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 1521)?;
+        let address = format!("{}:{}", &host, port);
+        let key = if self.service { "SERVICE_NAME" } else { "SID" };
+        let packet = connect_packet(&connect_descriptor(&host, port, key, creds.single()));
+
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let outcome = tokio::time::timeout(timeout, probe(&mut stream, &packet))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        let mut data = vec![(key.to_lowercase(), creds.single().to_owned())];
+        match outcome {
+            Outcome::NotFound => return Ok(None),
+            Outcome::Found => {}
+            Outcome::Refused(code) => data.push(("listener_error".to_owned(), code.to_string())),
+        }
+
+        Ok(Some(vec![Loot::new("oracle.sid", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{connect_packet, probe, refuse_error, Outcome};
+
+    #[test]
+    fn can_build_connect_packet() {
+        let packet = connect_packet("(CONNECT_DATA=(SID=XE))");
+        assert_eq!(packet.len(), 0x3a + 23);
+        assert_eq!(&packet[..2], &((0x3a + 23) as u16).to_be_bytes());
+        assert_eq!(packet[4], 1);
+        assert_eq!(&packet[24..28], &[0, 23, 0, 0x3a]);
+        assert_eq!(&packet[0x3a..], b"(CONNECT_DATA=(SID=XE))");
+    }
+
+    #[tokio::test]
+    async fn can_probe() {
+        let packet = connect_packet("(CONNECT_DATA=(SID=XE))");
+
+        let refuse = b"\x22\x00\x00\x3a(DESCRIPTION=(TMP=)(VSNNUM=0)(ERR=12505)(ERROR_STACK=(ERROR=(CODE=12505)(EMFI=4))))";
+        let mut response = ((refuse.len() + 8) as u16).to_be_bytes().to_vec();
+        response.extend_from_slice(&[0, 0, 4, 0, 0, 0]);
+        response.extend_from_slice(refuse);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&packet)
+            .read(&response)
+            .build();
+        assert_eq!(probe(&mut stream, &packet).await, Ok(Outcome::NotFound));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&packet)
+            .read(&[0, 8, 0, 0, 11, 0, 0, 0])
+            .write(&packet)
+            .read(&[0, 10, 0, 0, 2, 0, 0, 0, 1, 0x36])
+            .build();
+        assert_eq!(probe(&mut stream, &packet).await, Ok(Outcome::Found));
+
+        assert_eq!(refuse_error(b"(ERR=12519)(ERROR_STACK=)"), Some(12519));
+    }
+}
@@ -4,6 +4,7 @@ use async_trait::async_trait;
 
 use crate::creds::{Credentials, Expression};
 use crate::session::{Error, Loot};
+use crate::utils::Targets;
 use crate::Options;
 
 /// What type of payload is consumed by a plugin.
@@ -42,6 +43,17 @@ pub(crate) trait Plugin: Sync + Send {
         None
     }
 
+    // plugins working offline (on files, local commands ...) don't need the targets to be resolved
+    fn requires_network(&self) -> bool {
+        true
+    }
+
+    // plugins depending on the targets (like auto, selecting a plugin for each one) can override
+    // this method, called before setup with the targets already loaded by the session
+    fn set_targets(&mut self, _targets: &Targets) -> Result<(), Error> {
+        Ok(())
+    }
+
     // configure the plugin initial state
     fn setup(&mut self, options: &Options) -> Result<(), Error>;
 
@@ -1,6 +1,8 @@
 use std::time::Duration;
 
+use async_pop::response::capability::Capability;
 use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite};
 
 use crate::session::{Error, Loot};
 use crate::Options;
@@ -9,31 +11,131 @@ use crate::Plugin;
 use crate::creds::Credentials;
 use crate::utils;
 
+use self::options::Mechanism;
+
 pub(crate) mod options;
 
 super::manager::register_plugin! {
     "pop3" => POP3::new()
 }
 
+// SASL mechanism sending its initial response along with the AUTH command
+struct Authenticator {
+    mechanism: &'static str,
+    response: String,
+}
+
+impl async_pop::sasl::Authenticator for Authenticator {
+    fn mechanism(&self) -> &str {
+        self.mechanism
+    }
+
+    fn auth(&self) -> Option<String> {
+        Some(self.response.clone())
+    }
+}
+
+fn capability_name(capability: &Capability) -> String {
+    match capability {
+        Capability::Top => "TOP".to_owned(),
+        Capability::User => "USER".to_owned(),
+        Capability::Sasl(mechanisms) => format!(
+            "SASL {}",
+            mechanisms
+                .iter()
+                .map(|m| String::from_utf8_lossy(m).to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        ),
+        Capability::RespCodes => "RESP-CODES".to_owned(),
+        Capability::LoginDelay(delay) => format!("LOGIN-DELAY {}", delay),
+        Capability::Pipelining => "PIPELINING".to_owned(),
+        Capability::Expire(_) => "EXPIRE".to_owned(),
+        Capability::Uidl => "UIDL".to_owned(),
+        Capability::Implementation(text) => format!("IMPLEMENTATION {}", text),
+        Capability::Stls => "STLS".to_owned(),
+        Capability::Other(text) => text.to_string(),
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct POP3 {
     ssl: bool,
+    mechanism: Mechanism,
 }
 
 impl POP3 {
     pub fn new() -> Self {
-        POP3 { ssl: false }
+        POP3 {
+            ssl: false,
+            mechanism: Mechanism::User,
+        }
+    }
+
+    async fn login<S: AsyncRead + AsyncWrite + Unpin + Send>(
+        &self,
+        client: &mut async_pop::Client<S>,
+        address: &str,
+        creds: &Credentials,
+    ) -> Option<Vec<Loot>> {
+        let (mechanism, response) = match self.mechanism {
+            Mechanism::User => {
+                client.login(&creds.username, &creds.password).await.ok()?;
+                ("", String::new())
+            }
+            Mechanism::Plain => (
+                "PLAIN",
+                format!("\x00{}\x00{}", &creds.username, &creds.password),
+            ),
+            Mechanism::Xoauth2 => (
+                "XOAUTH2",
+                utils::oauth::xoauth2(&creds.username, &creds.password),
+            ),
+            Mechanism::Oauthbearer => (
+                "OAUTHBEARER",
+                utils::oauth::oauthbearer(&creds.username, &creds.password),
+            ),
+        };
+        if self.mechanism != Mechanism::User {
+            client
+                .auth(Authenticator {
+                    mechanism,
+                    response,
+                })
+                .await
+                .ok()?;
+        }
+
+        let secret = if matches!(self.mechanism, Mechanism::Xoauth2 | Mechanism::Oauthbearer) {
+            "token"
+        } else {
+            "password"
+        };
+        let mut loot = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            (secret.to_owned(), creds.password.to_owned()),
+        ];
+        // refreshed by the client after authentication
+        if !client.capabilities().is_empty() {
+            let capabilities: Vec<String> =
+                client.capabilities().iter().map(capability_name).collect();
+            loot.push(("capabilities".to_owned(), capabilities.join(", ")));
+        }
+        let _ = client.quit().await;
+
+        Some(vec![Loot::new("pop3", address, loot)])
     }
 }
 
 #[async_trait]
 impl Plugin for POP3 {
     fn description(&self) -> &'static str {
-        "POP3 password authentication."
+        "POP3 password and OAuth token authentication."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.ssl = opts.pop3.pop3_ssl;
+        self.mechanism = opts.pop3.pop3_mechanism;
         Ok(())
     }
 
@@ -55,34 +157,18 @@ impl Plugin for POP3 {
                 .map_err(|e| e.to_string())?
                 .map_err(|e| e.to_string())?;
 
-            if client.login(&creds.username, &creds.password).await.is_ok() {
-                return Ok(Some(vec![Loot::new(
-                    "pop3",
-                    &address.0,
-                    [
-                        ("username".to_owned(), creds.username.to_owned()),
-                        ("password".to_owned(), creds.password.to_owned()),
-                    ],
-                )]));
-            }
+            tokio::time::timeout(timeout, self.login(&mut client, &address.0, creds))
+                .await
+                .map_err(|e| e.to_string())
         } else {
             let mut client = tokio::time::timeout(timeout, async_pop::connect_plain(&address))
                 .await
                 .map_err(|e| e.to_string())?
                 .map_err(|e| e.to_string())?;
 
-            if client.login(&creds.username, &creds.password).await.is_ok() {
-                return Ok(Some(vec![Loot::new(
-                    "pop3",
-                    &address.0,
-                    [
-                        ("username".to_owned(), creds.username.to_owned()),
-                        ("password".to_owned(), creds.password.to_owned()),
-                    ],
-                )]));
-            }
+            tokio::time::timeout(timeout, self.login(&mut client, &address.0, creds))
+                .await
+                .map_err(|e| e.to_string())
         }
-
-        Ok(None)
     }
 }
@@ -1,10 +1,22 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Mechanism {
+    #[default]
+    User,
+    Plain,
+    Xoauth2,
+    Oauthbearer,
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
 pub(crate) struct Options {
     #[clap(long, default_value_t = false)]
     /// Enable SSL for POP3.
     pub pop3_ssl: bool,
+    #[clap(long, value_enum, default_value_t = Mechanism::User)]
+    /// POP3 authentication mechanism, with XOAUTH2 and OAUTHBEARER the passwords are OAuth access tokens.
+    pub pop3_mechanism: Mechanism,
 }
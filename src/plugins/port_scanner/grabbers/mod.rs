@@ -8,8 +8,9 @@ use super::options;
 pub(crate) mod dns;
 
 mod http;
-mod line;
 mod mysql;
+mod probes;
+pub(crate) mod udp;
 
 pub(crate) type Banner = HashMap<String, String>;
 
@@ -27,11 +28,7 @@ pub(crate) async fn grab_tcp_banner(
     } else if let (true, with_ssl) = http::is_http_port(opts, port) {
         http::http_grabber(opts, address, port, stream, with_ssl, timeout).await
     } else {
-        // default to an attempt at line grabbing
-        line::line_grabber(address, port, stream, timeout).await
+        // default to protocol probes and line grabbing
+        probes::probe_grabber(address, port, stream, timeout).await
     }
 }
-
-pub(crate) async fn grab_udp_banner(response: &[u8]) -> Banner {
-    dns::parse_maybe_chaos_response(response).await
-}
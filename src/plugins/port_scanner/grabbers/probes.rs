@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use lazy_regex::{bytes_lazy_regex, Lazy};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::Banner;
+use crate::utils::net::StreamLike;
+
+const MAX_RESPONSE_SIZE: usize = 4096;
+
+// sent to services that wait for the client to talk first
+const GENERIC_PROBE: &[u8] = b"hello\r\n\r\n";
+static PROBES: &[(&[u16], &[u8])] = &[
+    (&[6379], b"PING\r\n"),
+    (&[11211], b"version\r\n"),
+    (&[554, 8554], b"OPTIONS * RTSP/1.0\r\nCSeq: 1\r\n\r\n"),
+    (&[2181], b"srvr"),
+];
+
+// protocols (as nmap service names) recognized from the first response bytes
+type Signature = (&'static str, &'static Lazy<regex::bytes::Regex>);
+
+static SSH: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^SSH-\d\.\d+-");
+static SMTP: Lazy<regex::bytes::Regex> =
+    bytes_lazy_regex!(r"(?i)^220[ -][^\r\n]*(smtp|mail|postfix|exim|sendmail)");
+static FTP: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^220[ -]");
+static POP3: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^\+OK");
+static IMAP: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^\* (OK|PREAUTH)");
+static REDIS: Lazy<regex::bytes::Regex> =
+    bytes_lazy_regex!(r"^(\+PONG|-NOAUTH|-DENIED|-ERR unknown command)");
+static MEMCACHED: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^VERSION \d");
+static RTSP: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^RTSP/1\.\d ");
+static HTTP: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^HTTP/\d(\.\d)? \d{3}");
+static VNC: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^RFB \d{3}\.\d{3}");
+static TELNET: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"(?-u)^\xff[\xfb-\xfe]");
+static ZOOKEEPER: Lazy<regex::bytes::Regex> = bytes_lazy_regex!(r"^Zookeeper version: ");
+
+static SIGNATURES: &[Signature] = &[
+    ("ssh", &SSH),
+    // smtp goes before ftp since both greet with 220
+    ("smtp", &SMTP),
+    ("ftp", &FTP),
+    ("pop3", &POP3),
+    ("imap", &IMAP),
+    ("redis", &REDIS),
+    ("memcache", &MEMCACHED),
+    ("rtsp", &RTSP),
+    ("http", &HTTP),
+    ("vnc", &VNC),
+    ("telnet", &TELNET),
+    ("zookeeper", &ZOOKEEPER),
+];
+
+pub(crate) fn identify(response: &[u8]) -> Option<&'static str> {
+    SIGNATURES
+        .iter()
+        .find(|(_, regex)| regex.is_match(response))
+        .map(|(protocol, _)| *protocol)
+}
+
+fn probe_for_port(port: u16) -> &'static [u8] {
+    PROBES
+        .iter()
+        .find(|(ports, _)| ports.contains(&port))
+        .map(|(_, probe)| *probe)
+        .unwrap_or(GENERIC_PROBE)
+}
+
+// first printable line of the response
+fn first_line(response: &[u8]) -> String {
+    String::from_utf8_lossy(response)
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|c| !c.is_control())
+                .collect::<String>()
+                .trim()
+                .to_owned()
+        })
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+}
+
+async fn read_response(stream: &mut Box<dyn StreamLike>, timeout: Duration) -> Vec<u8> {
+    let mut buf = vec![0u8; MAX_RESPONSE_SIZE];
+    match tokio::time::timeout(timeout, stream.read(&mut buf)).await {
+        Ok(Ok(read)) => {
+            buf.truncate(read);
+            buf
+        }
+        _ => vec![],
+    }
+}
+
+pub(crate) async fn probe_grabber(
+    address: &str,
+    port: u16,
+    mut stream: Box<dyn StreamLike>,
+    timeout: Duration,
+) -> Banner {
+    log::debug!("probing {}:{} ...", address, port);
+
+    let mut banner = Banner::default();
+    let timeout = std::time::Duration::from_millis((timeout.as_millis() / 2) as u64);
+
+    // wait for a greeting first, then probe silent services
+    let mut response = read_response(&mut stream, timeout).await;
+    if response.is_empty() {
+        let _ = stream.write_all(probe_for_port(port)).await;
+        response = read_response(&mut stream, timeout).await;
+    }
+
+    if let Some(protocol) = identify(&response) {
+        banner.insert("protocol".to_owned(), protocol.to_owned());
+    }
+
+    let line = first_line(&response);
+    if !line.is_empty() {
+        banner.insert("line".to_owned(), line);
+    }
+
+    banner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_line, identify, probe_for_port};
+
+    #[test]
+    fn can_identify_services() {
+        assert_eq!(identify(b"SSH-2.0-OpenSSH_9.6\r\n"), Some("ssh"));
+        assert_eq!(identify(b"220 mx.example.com ESMTP Postfix\r\n"), Some("smtp"));
+        assert_eq!(identify(b"220 (vsFTPd 3.0.5)\r\n"), Some("ftp"));
+        assert_eq!(identify(b"+OK Dovecot ready.\r\n"), Some("pop3"));
+        assert_eq!(identify(b"* OK [CAPABILITY IMAP4rev1] ready\r\n"), Some("imap"));
+        assert_eq!(identify(b"-NOAUTH Authentication required.\r\n"), Some("redis"));
+        assert_eq!(identify(b"VERSION 1.6.21\r\n"), Some("memcache"));
+        assert_eq!(identify(b"HTTP/1.1 400 Bad Request\r\n"), Some("http"));
+        assert_eq!(identify(b"RFB 003.008\n"), Some("vnc"));
+        assert_eq!(identify(&[0xff, 0xfd, 0x18, 0xff, 0xfd, 0x20]), Some("telnet"));
+        assert_eq!(identify(b"hello"), None);
+
+        assert_eq!(probe_for_port(6379), b"PING\r\n");
+        assert_eq!(probe_for_port(1234), b"hello\r\n\r\n");
+        assert_eq!(first_line(b"\r\n\x1b220 ready\r\nmore"), "220 ready");
+    }
+}
@@ -0,0 +1,333 @@
+use super::{dns, Banner};
+
+const NTP_PORTS: &[u16] = &[123];
+const SNMP_PORTS: &[u16] = &[161];
+const IKE_PORTS: &[u16] = &[500, 4500];
+const TFTP_PORTS: &[u16] = &[69];
+
+// sysDescr.0
+const SYS_DESCR: &[u8] = &[0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+const SNMP_COMMUNITY: &str = "public";
+
+// well known IKE vendor ids
+const IKE_VENDORS: &[(&str, &str)] = &[
+    ("4048b7d56ebce88525e7de7f00d6c2d3", "IKE fragmentation"),
+    ("afcad71368a1f1c96b8696fc77570100", "Dead Peer Detection v1.0"),
+    ("4a131c81070358455c5728f20e95452f", "RFC 3947 NAT-T"),
+    ("90cb80913ebb696e086381b5ec427b1f", "draft-ietf-ipsec-nat-t-ike-02"),
+    ("09002689dfd6b712", "XAUTH"),
+    ("12f5f28c457168a9702d9fe274cc0100", "Cisco Unity"),
+    ("4f45", "OpenSwan / Libreswan"),
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Probe {
+    Dns,
+    Ntp,
+    Snmp,
+    Ike,
+    Tftp,
+}
+
+impl Probe {
+    pub fn for_port(port: u16) -> Self {
+        if NTP_PORTS.contains(&port) {
+            Self::Ntp
+        } else if SNMP_PORTS.contains(&port) {
+            Self::Snmp
+        } else if IKE_PORTS.contains(&port) {
+            Self::Ike
+        } else if TFTP_PORTS.contains(&port) {
+            Self::Tftp
+        } else {
+            // also the default for unknown ports
+            Self::Dns
+        }
+    }
+
+    pub fn payload(&self) -> Vec<u8> {
+        match self {
+            Self::Dns => dns::CHAOS_BIND_VERSION_QUERY.to_vec(),
+            // client mode, version 3
+            Self::Ntp => {
+                let mut request = vec![0u8; 48];
+                request[0] = 0x1b;
+                request
+            }
+            Self::Snmp => snmp_request(rand::random()),
+            Self::Ike => ike_request(rand::random()),
+            Self::Tftp => {
+                let mut request = vec![0x00, 0x01];
+                request.extend(format!("legba-{:08x}", rand::random::<u32>()).as_bytes());
+                request.extend(b"\x00octet\x00");
+                request
+            }
+        }
+    }
+
+    pub async fn parse(&self, request: &[u8], response: &[u8]) -> Banner {
+        match self {
+            Self::Dns => dns::parse_maybe_chaos_response(response).await,
+            Self::Ntp => parse_ntp(response),
+            Self::Snmp => parse_snmp(response),
+            Self::Ike => parse_ike(request, response),
+            Self::Tftp => parse_tftp(response),
+        }
+    }
+}
+
+fn banner(protocol: &str, values: Vec<(&str, String)>) -> Banner {
+    let mut banner = Banner::default();
+    banner.insert("protocol".to_owned(), protocol.to_owned());
+    for (key, value) in values {
+        banner.insert(format!("{}.{}", protocol, key), value);
+    }
+    banner
+}
+
+fn parse_ntp(response: &[u8]) -> Banner {
+    // server mode
+    if response.len() < 48 || response[0] & 0x07 != 4 {
+        return Banner::default();
+    }
+
+    let stratum = response[1];
+    let reference = &response[12..16];
+    let reference = if stratum <= 1 {
+        String::from_utf8_lossy(reference)
+            .trim_end_matches('\0')
+            .to_owned()
+    } else {
+        format!(
+            "{}.{}.{}.{}",
+            reference[0], reference[1], reference[2], reference[3]
+        )
+    };
+
+    banner(
+        "ntp",
+        vec![
+            ("version", ((response[0] >> 3) & 0x07).to_string()),
+            ("stratum", stratum.to_string()),
+            ("reference", reference),
+        ],
+    )
+}
+
+// v2c get request of sysDescr.0
+fn snmp_request(request_id: u32) -> Vec<u8> {
+    let mut varbind = SYS_DESCR.to_vec();
+    varbind.extend([0x05, 0x00]);
+
+    let mut pdu = vec![0x02, 0x04];
+    pdu.extend(request_id.to_be_bytes());
+    pdu.extend([0x02, 0x01, 0x00, 0x02, 0x01, 0x00]);
+    pdu.extend([0x30, varbind.len() as u8 + 2, 0x30, varbind.len() as u8]);
+    pdu.extend(varbind);
+
+    let mut message = vec![0x02, 0x01, 0x01, 0x04, SNMP_COMMUNITY.len() as u8];
+    message.extend(SNMP_COMMUNITY.as_bytes());
+    // GetRequest
+    message.extend([0xa0, pdu.len() as u8]);
+    message.extend(pdu);
+
+    let mut request = vec![0x30, message.len() as u8];
+    request.extend(message);
+    request
+}
+
+fn parse_snmp(response: &[u8]) -> Banner {
+    if response.first() != Some(&0x30) {
+        return Banner::default();
+    }
+
+    let mut values = vec![("community", SNMP_COMMUNITY.to_owned())];
+    if let Some(pos) = response
+        .windows(SYS_DESCR.len())
+        .position(|w| w == SYS_DESCR)
+    {
+        let value = &response[pos + SYS_DESCR.len()..];
+        let descr = match value {
+            [0x04, 0x81, size, data @ ..] | [0x04, size, data @ ..] => {
+                data.get(..*size as usize).map(|d| String::from_utf8_lossy(d))
+            }
+            _ => None,
+        };
+        if let Some(descr) = descr {
+            values.push(("sys_descr", descr.trim().replace(['\r', '\n'], " ")));
+        }
+    }
+
+    banner("snmp", values)
+}
+
+// IKEv1 main mode with a single common transform (3DES, SHA1, PSK, group 2)
+fn ike_request(initiator: u64) -> Vec<u8> {
+    let attributes: &[u8] = &[
+        0x80, 0x01, 0x00, 0x05, 0x80, 0x02, 0x00, 0x02, 0x80, 0x03, 0x00, 0x01, 0x80, 0x04, 0x00,
+        0x02, 0x80, 0x0b, 0x00, 0x01, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x00, 0x70, 0x80,
+    ];
+    let transform_size = 8 + attributes.len() as u16;
+    let proposal_size = 8 + transform_size;
+    let sa_size = 12 + proposal_size;
+
+    let mut request = initiator.to_be_bytes().to_vec();
+    request.extend([0; 8]);
+    // SA payload, version 1.0, main mode
+    request.extend([0x01, 0x10, 0x02, 0x00, 0, 0, 0, 0]);
+    request.extend((28 + sa_size as u32).to_be_bytes());
+
+    request.extend([0x00, 0x00]);
+    request.extend(sa_size.to_be_bytes());
+    // IPSEC DOI, identity only situation
+    request.extend([0, 0, 0, 1, 0, 0, 0, 1]);
+
+    request.extend([0x00, 0x00]);
+    request.extend(proposal_size.to_be_bytes());
+    // proposal 1, ISAKMP, no SPI, one transform
+    request.extend([0x01, 0x01, 0x00, 0x01]);
+
+    request.extend([0x00, 0x00]);
+    request.extend(transform_size.to_be_bytes());
+    // transform 1, KEY_IKE
+    request.extend([0x01, 0x01, 0x00, 0x00]);
+    request.extend(attributes);
+
+    request
+}
+
+fn parse_ike(request: &[u8], response: &[u8]) -> Banner {
+    if response.len() < 28 || response[..8] != request[..8] {
+        return Banner::default();
+    }
+
+    let mut values = vec![(
+        "version",
+        format!("{}.{}", response[17] >> 4, response[17] & 0x0f),
+    )];
+
+    let mut vendors = vec![];
+    let mut next = response[16];
+    let mut pos = 28;
+    while next != 0 && pos + 4 <= response.len() {
+        let size = u16::from_be_bytes([response[pos + 2], response[pos + 3]]) as usize;
+        let Some(body) = response.get(pos + 4..pos + size.max(4)) else {
+            break;
+        };
+
+        match next {
+            1 => values.push(("response", "transform accepted".to_owned())),
+            11 if body.len() >= 8 => values.push((
+                "response",
+                format!("notify {}", u16::from_be_bytes([body[6], body[7]])),
+            )),
+            13 => {
+                let id = body
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                vendors.push(
+                    IKE_VENDORS
+                        .iter()
+                        .find(|(prefix, _)| id.starts_with(prefix))
+                        .map(|(_, name)| name.to_string())
+                        .unwrap_or(id),
+                );
+            }
+            _ => {}
+        }
+
+        next = response[pos];
+        pos += size.max(4);
+    }
+
+    if !vendors.is_empty() {
+        values.push(("vendors", vendors.join(", ")));
+    }
+
+    banner("ike", values)
+}
+
+fn parse_tftp(response: &[u8]) -> Banner {
+    match response {
+        [0x00, 0x05, _, _, message @ ..] => banner(
+            "tftp",
+            vec![(
+                "error",
+                String::from_utf8_lossy(message)
+                    .trim_end_matches('\0')
+                    .to_owned(),
+            )],
+        ),
+        [0x00, 0x03, ..] => banner("tftp", vec![("read", "allowed".to_owned())]),
+        _ => Banner::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ike, parse_ntp, parse_snmp, parse_tftp, snmp_request, Probe};
+
+    #[test]
+    fn can_select_probes() {
+        assert_eq!(Probe::for_port(123), Probe::Ntp);
+        assert_eq!(Probe::for_port(161), Probe::Snmp);
+        assert_eq!(Probe::for_port(500), Probe::Ike);
+        assert_eq!(Probe::for_port(69), Probe::Tftp);
+        assert_eq!(Probe::for_port(53), Probe::Dns);
+        assert_eq!(Probe::for_port(31337), Probe::Dns);
+        assert_eq!(Probe::Ike.payload().len(), 84);
+    }
+
+    #[test]
+    fn can_build_snmp_request() {
+        assert_eq!(
+            snmp_request(0x01020304),
+            vec![
+                0x30, 0x29, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xa0,
+                0x1c, 0x02, 0x04, 0x01, 0x02, 0x03, 0x04, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30,
+                0x0e, 0x30, 0x0c, 0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00,
+                0x05, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn can_parse_responses() {
+        let mut ntp = vec![0u8; 48];
+        ntp[0] = 0x1c;
+        ntp[1] = 1;
+        ntp[12..16].copy_from_slice(b"GPS\0");
+        let banner = parse_ntp(&ntp);
+        assert_eq!(banner.get("protocol").unwrap(), "ntp");
+        assert_eq!(banner.get("ntp.stratum").unwrap(), "1");
+        assert_eq!(banner.get("ntp.reference").unwrap(), "GPS");
+
+        let mut snmp = vec![0x30, 0x30, 0x02, 0x01, 0x01];
+        snmp.extend([0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00]);
+        snmp.extend([0x04, 0x05]);
+        snmp.extend(b"Linux");
+        assert_eq!(parse_snmp(&snmp).get("snmp.sys_descr").unwrap(), "Linux");
+
+        let request = Probe::Ike.payload();
+        let mut ike = request[..8].to_vec();
+        ike.extend([0xaa; 8]);
+        // vendor id, version 1.0, main mode
+        ike.extend([13, 0x10, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 48]);
+        ike.extend([0, 0, 0, 20]);
+        ike.extend([
+            0xaf, 0xca, 0xd7, 0x13, 0x68, 0xa1, 0xf1, 0xc9, 0x6b, 0x86, 0x96, 0xfc, 0x77, 0x57,
+            0x01, 0x00,
+        ]);
+        let banner = parse_ike(&request, &ike);
+        assert_eq!(banner.get("ike.version").unwrap(), "1.0");
+        assert_eq!(
+            banner.get("ike.vendors").unwrap(),
+            "Dead Peer Detection v1.0"
+        );
+        assert!(parse_ike(&request, &ike[1..]).is_empty());
+
+        let banner = parse_tftp(b"\x00\x05\x00\x01File not found\x00");
+        assert_eq!(banner.get("tftp.error").unwrap(), "File not found");
+    }
+}
@@ -2,7 +2,6 @@ use std::net::{SocketAddr, ToSocketAddrs};
 use std::time::Duration;
 
 use async_trait::async_trait;
-use grabbers::grab_udp_banner;
 use tokio::net::UdpSocket;
 
 use crate::session::{Error, Loot};
@@ -16,22 +15,66 @@ use super::plugin::PayloadStrategy;
 
 mod grabbers;
 pub(crate) mod options;
+mod syn;
 
 super::manager::register_plugin! {
-    "port.scanner" => PortScanner::new()
+    "port.scanner" => PortScanner::new(),
+    "udp.probe" => PortScanner::udp()
 }
 
 #[derive(Clone)]
 pub(crate) struct PortScanner {
+    name: &'static str,
+    // UDP payload probes only
+    udp_only: bool,
     ports: Expression,
     opts: options::Options,
+    syn: Option<syn::SynScanner>,
 }
 
 impl PortScanner {
     pub fn new() -> Self {
         PortScanner {
+            name: "port.scanner",
+            udp_only: false,
             ports: Expression::default(),
             opts: options::Options::default(),
+            syn: None,
+        }
+    }
+
+    pub fn udp() -> Self {
+        PortScanner {
+            name: "udp.probe",
+            udp_only: true,
+            ..Self::new()
+        }
+    }
+
+    fn default_ports(&self) -> &'static str {
+        if self.udp_only {
+            options::DEFAULT_UDP_PORTS
+        } else {
+            options::DEFAULT_PORTS
+        }
+    }
+
+    // SYN scan of the port if enabled and the target is IPv4, returns None otherwise
+    async fn syn_attempt(
+        &self,
+        target: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<bool>, Error> {
+        let Some(scanner) = self.syn.as_ref() else {
+            return Ok(None);
+        };
+
+        match self.get_socket_address(target, creds)? {
+            SocketAddr::V4(address) => Ok(Some(
+                scanner.probe(address, timeout).await? == Some(syn::State::Open),
+            )),
+            SocketAddr::V6(_) => Ok(None),
         }
     }
 
@@ -44,38 +87,54 @@ impl PortScanner {
         let address = format!("{}:{}", &target, &creds.username); // username is the port
         let start: std::time::Instant = std::time::Instant::now();
 
-        if let Ok(stream) = crate::utils::net::async_tcp_stream(&address, timeout, false).await {
-            let mut data = vec![
-                ("transport".to_owned(), "tcp".to_owned()),
-                ("port".to_owned(), creds.username.to_owned()),
-                ("time".to_owned(), format!("{:?}", start.elapsed())),
-            ];
-
-            if !self.opts.port_scanner_no_banners {
-                let banner = grabbers::grab_tcp_banner(
-                    &self.opts,
-                    &target,
-                    creds.username.parse::<u16>().unwrap(),
-                    stream,
-                    std::time::Duration::from_millis(self.opts.port_scanner_banner_timeout),
-                )
-                .await;
-
-                for (key, val) in banner {
-                    if key == "proto" || key == "protocol" {
-                        data.push(("protocol".to_owned(), val));
-                    } else if key.starts_with("certificate.") {
-                        data.push((key, val));
-                    } else {
-                        data.push((format!("banner.{}", key), val));
-                    }
-                }
+        let (stream, time) = match self.syn_attempt(&target, creds, timeout).await? {
+            Some(false) => return Ok(None),
+            Some(true) => {
+                let time = start.elapsed();
+                // the banner grabbing still needs a full connection
+                let stream = if self.opts.port_scanner_no_banners {
+                    None
+                } else {
+                    crate::utils::net::async_tcp_stream(&address, timeout, false)
+                        .await
+                        .ok()
+                };
+                (stream, time)
             }
+            None => match crate::utils::net::async_tcp_stream(&address, timeout, false).await {
+                Ok(stream) => (Some(stream), start.elapsed()),
+                Err(_) => return Ok(None),
+            },
+        };
 
-            Ok(Some(Loot::new("port.scanner", &target, data)))
-        } else {
-            Ok(None)
+        let mut data = vec![
+            ("transport".to_owned(), "tcp".to_owned()),
+            ("port".to_owned(), creds.username.to_owned()),
+            ("time".to_owned(), format!("{:?}", time)),
+        ];
+
+        if let Some(stream) = stream.filter(|_| !self.opts.port_scanner_no_banners) {
+            let banner = grabbers::grab_tcp_banner(
+                &self.opts,
+                &target,
+                creds.username.parse::<u16>().unwrap(),
+                stream,
+                std::time::Duration::from_millis(self.opts.port_scanner_banner_timeout),
+            )
+            .await;
+
+            for (key, val) in banner {
+                if key == "proto" || key == "protocol" {
+                    data.push(("protocol".to_owned(), val));
+                } else if key.starts_with("certificate.") {
+                    data.push((key, val));
+                } else {
+                    data.push((format!("banner.{}", key), val));
+                }
+            }
         }
+
+        Ok(Some(Loot::new(self.name, &target, data)))
     }
 
     fn get_socket_address(&self, target: &str, creds: &Credentials) -> Result<SocketAddr, Error> {
@@ -118,33 +177,34 @@ impl PortScanner {
         if let Ok(Ok(udp_socket)) =
             tokio::time::timeout(timeout, UdpSocket::bind(&local_addr)).await
         {
-            let mut buf = [0u8; 1024];
+            let probe = grabbers::udp::Probe::for_port(socket.port());
+            let request = probe.payload();
 
-            tokio::time::timeout(timeout, udp_socket.connect(socket))
+            tokio::time::timeout(timeout, udp_socket.send_to(&request, socket))
                 .await
                 .map_err(|e| e.to_string())?
                 .map_err(|e| e.to_string())?;
 
-            tokio::time::timeout(
-                timeout,
-                udp_socket.send(grabbers::dns::CHAOS_BIND_VERSION_QUERY),
-            )
-            .await
-            .map_err(|e| e.to_string())?
-            .map_err(|e| e.to_string())?;
+            // not connected since some services (like tftp) reply from another port
+            let mut buf = [0u8; 2048];
+            while let Ok(Ok((size, from))) =
+                tokio::time::timeout(timeout, udp_socket.recv_from(&mut buf)).await
+            {
+                if from.ip() != socket.ip() {
+                    continue;
+                }
 
-            if let Ok(Ok(size)) = tokio::time::timeout(timeout, udp_socket.recv(&mut buf)).await {
                 let mut data = vec![
                     ("transport".to_owned(), "udp".to_owned()),
                     ("port".to_owned(), creds.username.to_owned()),
                     ("time".to_owned(), format!("{:?}", start.elapsed())),
                 ];
 
-                for (name, value) in grab_udp_banner(&buf[0..size]).await {
+                for (name, value) in probe.parse(&request, &buf[0..size]).await {
                     data.push((name, value));
                 }
 
-                return Ok(Some(Loot::new("port.scanner", &target, data)));
+                return Ok(Some(Loot::new(self.name, &target, data)));
             }
         }
 
@@ -155,7 +215,11 @@ impl PortScanner {
 #[async_trait]
 impl Plugin for PortScanner {
     fn description(&self) -> &'static str {
-        "TCP and UDP ports scanner."
+        if self.udp_only {
+            "UDP services probe (DNS, NTP, SNMP, IKE and TFTP payloads)."
+        } else {
+            "TCP (connect or SYN) and UDP ports scanner with banner grabbing."
+        }
     }
 
     fn payload_strategy(&self) -> PayloadStrategy {
@@ -165,7 +229,7 @@ impl Plugin for PortScanner {
     fn override_payload(&self) -> Option<Expression> {
         if self.ports.is_default() {
             Some(creds::parse_expression(Some(
-                &options::DEFAULT_PORTS.to_owned(),
+                &self.default_ports().to_owned(),
             )))
         } else {
             Some(self.ports.clone())
@@ -175,6 +239,8 @@ impl Plugin for PortScanner {
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.ports = if opts.username.is_some() {
             creds::parse_expression(opts.username.as_ref())
+        } else if self.udp_only && opts.port_scanner.port_scanner_ports == options::DEFAULT_PORTS {
+            creds::parse_expression(Some(&options::DEFAULT_UDP_PORTS.to_owned()))
         } else {
             creds::parse_expression(Some(&opts.port_scanner.port_scanner_ports))
         };
@@ -195,6 +261,20 @@ impl Plugin for PortScanner {
         }
 
         self.opts = opts.port_scanner.clone();
+        if self.udp_only {
+            self.opts.port_scanner_no_tcp = true;
+            self.opts.port_scanner_no_udp = false;
+        }
+
+        if self.opts.port_scanner_syn && !self.opts.port_scanner_no_tcp {
+            self.syn = match syn::SynScanner::new() {
+                Ok(scanner) => Some(scanner),
+                Err(e) => {
+                    log::warn!("can't open raw socket ({}), falling back to connect scan", e);
+                    None
+                }
+            };
+        }
 
         if self.opts.port_scanner_no_tcp && self.opts.port_scanner_no_udp {
             Err("both TCP and UDP port scanning are disabled".to_string())
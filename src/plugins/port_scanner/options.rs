@@ -2,6 +2,8 @@ use clap::Parser;
 use serde::{Deserialize, Serialize};
 
 pub(crate) const DEFAULT_PORTS: &str = "[1-65535]";
+// ports with a payload probe, used by udp.probe
+pub(crate) const DEFAULT_UDP_PORTS: &str = "[53, 69, 123, 161, 500, 4500, 5353]";
 
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
@@ -21,6 +23,9 @@ pub(crate) struct Options {
     /// Do not perform TCP scan.
     #[clap(long, default_value_t = false)]
     pub port_scanner_no_tcp: bool,
+    /// Use a raw socket SYN scan for IPv4 TCP ports (requires root or CAP_NET_RAW, falls back to connect scan otherwise).
+    #[clap(long, default_value_t = false)]
+    pub port_scanner_syn: bool,
     #[clap(long, default_value_t = 1500)]
     /// Timeout in milliseconds for banner grabbing.
     pub port_scanner_banner_timeout: u64,
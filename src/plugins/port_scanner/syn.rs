@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use tokio::sync::oneshot;
+
+use crate::session::Error;
+
+const SYN: u8 = 0x02;
+const RST: u8 = 0x04;
+const ACK: u8 = 0x10;
+const MSS: u16 = 1460;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum State {
+    Open,
+    Closed,
+}
+
+type Pending = Mutex<HashMap<(Ipv4Addr, u16), oneshot::Sender<State>>>;
+
+fn checksum(data: &[u8], mut sum: u32) -> u16 {
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 {
+            u16::from_be_bytes([chunk[0], chunk[1]])
+        } else {
+            u16::from_be_bytes([chunk[0], 0])
+        };
+        sum += word as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// tcp header of a SYN segment with the MSS option, the kernel adds the ip header
+pub(crate) fn syn_segment(
+    source: Ipv4Addr,
+    destination: Ipv4Addr,
+    source_port: u16,
+    destination_port: u16,
+    sequence: u32,
+) -> Vec<u8> {
+    let mut segment = vec![];
+    segment.extend(source_port.to_be_bytes());
+    segment.extend(destination_port.to_be_bytes());
+    segment.extend(sequence.to_be_bytes());
+    // ack number
+    segment.extend([0, 0, 0, 0]);
+    // 24 bytes header
+    segment.extend([6 << 4, SYN]);
+    // window, checksum, urgent pointer
+    segment.extend([0x04, 0x00, 0, 0, 0, 0]);
+    segment.extend([0x02, 0x04]);
+    segment.extend(MSS.to_be_bytes());
+
+    // pseudo header: addresses, protocol and tcp length
+    let mut pseudo = vec![];
+    pseudo.extend(source.octets());
+    pseudo.extend(destination.octets());
+    pseudo.extend([0, 6]);
+    pseudo.extend((segment.len() as u16).to_be_bytes());
+    let sum = pseudo
+        .chunks(2)
+        .map(|w| u16::from_be_bytes([w[0], w[1]]) as u32)
+        .sum();
+
+    let checksum = checksum(&segment, sum);
+    segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+    segment
+}
+
+// parses an ip packet received on the raw socket, returns the address, port and state of
+// the probed port if it's a reply to one of our segments
+pub(crate) fn parse_reply(packet: &[u8], source_port: u16) -> Option<(Ipv4Addr, u16, State)> {
+    let header_size = (*packet.first()? & 0x0f) as usize * 4;
+    if packet.first()? >> 4 != 4 || *packet.get(9)? != 6 {
+        return None;
+    }
+
+    let address = Ipv4Addr::new(
+        *packet.get(12)?,
+        *packet.get(13)?,
+        *packet.get(14)?,
+        *packet.get(15)?,
+    );
+    let segment = packet.get(header_size..header_size + 14)?;
+    let port = u16::from_be_bytes([segment[0], segment[1]]);
+    if u16::from_be_bytes([segment[2], segment[3]]) != source_port {
+        return None;
+    }
+
+    let flags = segment[13];
+    if flags & (SYN | ACK) == SYN | ACK {
+        Some((address, port, State::Open))
+    } else if flags & RST != 0 {
+        Some((address, port, State::Closed))
+    } else {
+        None
+    }
+}
+
+// local address used to reach the destination
+fn source_address(destination: &SocketAddrV4) -> Result<Ipv4Addr, Error> {
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.connect(destination).map_err(|e| e.to_string())?;
+    match socket.local_addr().map_err(|e| e.to_string())?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(ip) => Err(format!("unexpected source address {}", ip)),
+    }
+}
+
+// dispatches the replies to the pending probes until the scanner is dropped
+fn receive(socket: Arc<Socket>, pending: Weak<Pending>, source_port: u16) {
+    let mut buf = [0u8; 1500];
+    loop {
+        let Some(pending) = pending.upgrade() else {
+            break;
+        };
+        let read = match (&*socket).read(&mut buf) {
+            Ok(read) => read,
+            // read timeout
+            Err(_) => continue,
+        };
+
+        if let Some((address, port, state)) = parse_reply(&buf[..read], source_port) {
+            if let Some(sender) = pending.lock().unwrap().remove(&(address, port)) {
+                let _ = sender.send(state);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct SynScanner {
+    socket: Arc<Socket>,
+    source_port: u16,
+    pending: Arc<Pending>,
+}
+
+impl SynScanner {
+    // requires root or CAP_NET_RAW
+    pub fn new() -> Result<Self, Error> {
+        let socket = Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::TCP))
+            .map_err(|e| e.to_string())?;
+        socket
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .map_err(|e| e.to_string())?;
+
+        let socket = Arc::new(socket);
+        let pending = Arc::new(Pending::default());
+        let source_port = 40000 + rand::random::<u16>() % 20000;
+
+        let receiver = socket.clone();
+        let weak = Arc::downgrade(&pending);
+        std::thread::spawn(move || receive(receiver, weak, source_port));
+
+        Ok(Self {
+            socket,
+            source_port,
+            pending,
+        })
+    }
+
+    // returns None if the port is filtered
+    pub async fn probe(
+        &self,
+        address: SocketAddrV4,
+        timeout: Duration,
+    ) -> Result<Option<State>, Error> {
+        let source = source_address(&address)?;
+        let key = (*address.ip(), address.port());
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(key, sender);
+
+        let segment = syn_segment(
+            source,
+            *address.ip(),
+            self.source_port,
+            address.port(),
+            rand::random(),
+        );
+        let destination = SockAddr::from(SocketAddr::V4(SocketAddrV4::new(*address.ip(), 0)));
+        if let Err(e) = self.socket.send_to(&segment, &destination) {
+            self.pending.lock().unwrap().remove(&key);
+            return Err(e.to_string());
+        }
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(state)) => Ok(Some(state)),
+            _ => {
+                self.pending.lock().unwrap().remove(&key);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::{checksum, parse_reply, syn_segment, State};
+
+    #[test]
+    fn can_build_syn_segment() {
+        let (source, destination) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let segment = syn_segment(source, destination, 40000, 22, 0x01020304);
+        assert_eq!(segment.len(), 24);
+        assert_eq!(&segment[..4], &[0x9c, 0x40, 0x00, 0x16]);
+        assert_eq!(segment[13], 0x02);
+
+        // the checksum of the pseudo header and segment including its checksum is zero
+        let mut data = vec![10, 0, 0, 1, 10, 0, 0, 2, 0, 6, 0, 24];
+        data.extend(&segment);
+        assert_eq!(checksum(&data, 0), 0);
+    }
+
+    #[test]
+    fn can_parse_replies() {
+        let mut packet = vec![0x45, 0, 0, 40, 0, 0, 0, 0, 64, 6, 0, 0, 10, 0, 0, 2, 10, 0, 0, 1];
+        // from port 22 to our source port, SYN ACK
+        packet.extend([0x00, 0x16, 0x9c, 0x40, 0, 0, 0, 1, 0x01, 0x02, 0x03, 0x05, 0x50, 0x12]);
+        packet.extend([0; 6]);
+        assert_eq!(
+            parse_reply(&packet, 40000),
+            Some((Ipv4Addr::new(10, 0, 0, 2), 22, State::Open))
+        );
+        assert_eq!(parse_reply(&packet, 40001), None);
+
+        // RST ACK
+        packet[33] = 0x14;
+        assert_eq!(
+            parse_reply(&packet, 40000),
+            Some((Ipv4Addr::new(10, 0, 0, 2), 22, State::Closed))
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use self::options::Auth;
+use self::packet::*;
+
+mod mschap;
+pub(crate) mod options;
+mod packet;
+
+const MAX_PACKET_SIZE: usize = 4096;
+
+super::manager::register_plugin! {
+    "radius" => Radius::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct Radius {
+    secret: String,
+    auth: Auth,
+    nas_identifier: String,
+}
+
+impl Radius {
+    pub fn new() -> Self {
+        Radius {
+            secret: String::new(),
+            auth: Auth::Pap,
+            nas_identifier: String::new(),
+        }
+    }
+
+    fn request(&self, identifier: u8, username: &str, password: &str) -> Result<Packet, Error> {
+        let mut rng = rand::thread_rng();
+        let mut request = Packet::new(ACCESS_REQUEST, identifier, rng.gen());
+        request.add(USER_NAME, username.as_bytes())?;
+        request.add(NAS_IDENTIFIER, self.nas_identifier.as_bytes())?;
+
+        match self.auth {
+            Auth::Pap => {
+                let hidden = hide_password(password, &self.secret, &request.authenticator);
+                request.add(USER_PASSWORD, &hidden)?;
+            }
+            Auth::Chap => {
+                let challenge: [u8; 16] = rng.gen();
+                let ident: u8 = rng.gen();
+                let mut value = vec![ident];
+                value.extend(chap_response(ident, password, &challenge));
+                request.add(CHAP_PASSWORD, &value)?;
+                request.add(CHAP_CHALLENGE, &challenge)?;
+            }
+            Auth::Mschapv2 => {
+                let challenge: [u8; 16] = rng.gen();
+                let peer: [u8; 16] = rng.gen();
+                // ident, flags, peer challenge, reserved and NT response
+                let mut value = vec![rng.gen(), 0];
+                value.extend_from_slice(&peer);
+                value.extend_from_slice(&[0; 8]);
+                value.extend(mschap::nt_response(&challenge, &peer, username, password));
+                request.add(
+                    VENDOR_SPECIFIC,
+                    &vendor_attribute(VENDOR_MICROSOFT, MS_CHAP_CHALLENGE, &challenge)?,
+                )?;
+                request.add(
+                    VENDOR_SPECIFIC,
+                    &vendor_attribute(VENDOR_MICROSOFT, MS_CHAP2_RESPONSE, &value)?,
+                )?;
+            }
+        }
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl Plugin for Radius {
+    fn description(&self) -> &'static str {
+        "RADIUS PAP, CHAP and MS-CHAPv2 authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        if opts.radius.radius_secret.is_empty() {
+            return Err("please provide the NAS shared secret with --radius-secret".to_owned());
+        }
+        self.secret = opts.radius.radius_secret.clone();
+        self.auth = opts.radius.radius_auth;
+        self.nas_identifier = opts.radius.radius_nas_identifier.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 1812)?;
+        let socket = utils::net::async_udp_socket(&address).await?;
+
+        let identifier: u8 = rand::thread_rng().gen();
+        let mut request = self.request(identifier, &creds.username, &creds.password)?;
+        socket
+            .send(&request.encode_request(&self.secret))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // servers silently drop requests from unknown clients or with a wrong secret
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+        let response = loop {
+            let size = tokio::time::timeout_at(deadline, socket.recv(&mut buffer))
+                .await
+                .map_err(|_| "no response, wrong secret or unknown NAS?".to_owned())?
+                .map_err(|e| e.to_string())?;
+            match Packet::decode(&buffer[..size]) {
+                Ok(response) if response.identifier == identifier => break response,
+                _ => continue,
+            }
+        };
+
+        if !response.verify_response(&request.authenticator, &self.secret) {
+            return Err("invalid response authenticator, wrong --radius-secret?".to_owned());
+        }
+
+        let valid = |partial: bool| {
+            let mut data = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                ("auth".to_owned(), format!("{:?}", self.auth).to_uppercase()),
+            ];
+            let messages: Vec<String> = response
+                .get(REPLY_MESSAGE)
+                .map(|m| String::from_utf8_lossy(m).to_string())
+                .collect();
+            if !messages.is_empty() {
+                data.push(("reply_message".to_owned(), messages.join(" ")));
+            }
+            let loot = Loot::new("radius", &address, data);
+            Some(vec![if partial { loot.set_partial() } else { loot }])
+        };
+
+        match response.code {
+            ACCESS_ACCEPT => Ok(valid(false)),
+            // the password is accepted but a further factor is required
+            ACCESS_CHALLENGE => Ok(valid(true)),
+            ACCESS_REJECT => Ok(None),
+            other => Err(format!("unexpected RADIUS response code {}", other)),
+        }
+    }
+}
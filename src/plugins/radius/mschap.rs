@@ -0,0 +1,79 @@
+use des::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use md4::Md4;
+use sha1::{Digest, Sha1};
+
+// RFC 2759 NtPasswordHash
+fn nt_password_hash(password: &str) -> Vec<u8> {
+    let password: Vec<u8> = password
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    Md4::digest(password).to_vec()
+}
+
+// RFC 2759 ChallengeHash
+fn challenge_hash(peer: &[u8], authenticator: &[u8], username: &str) -> Vec<u8> {
+    let mut hasher = Sha1::new();
+    hasher.update(peer);
+    hasher.update(authenticator);
+    hasher.update(username.as_bytes());
+    hasher.finalize()[..8].to_vec()
+}
+
+// expands 56 bits to a DES key, parity bits are ignored
+fn des_key(key: &[u8]) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out[0] = key[0];
+    for i in 1..7 {
+        out[i] = (key[i - 1] << (8 - i)) | (key[i] >> i);
+    }
+    out[7] = key[6] << 1;
+    out
+}
+
+fn des_encrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let cipher = des::Des::new_from_slice(&des_key(key)).unwrap();
+    let mut block = GenericArray::clone_from_slice(data);
+    cipher.encrypt_block(&mut block);
+    block.to_vec()
+}
+
+// RFC 2759 GenerateNTResponse
+pub(crate) fn nt_response(
+    authenticator: &[u8],
+    peer: &[u8],
+    username: &str,
+    password: &str,
+) -> Vec<u8> {
+    let challenge = challenge_hash(peer, authenticator, username);
+    let mut hash = nt_password_hash(password);
+    hash.resize(21, 0);
+    hash.chunks(7)
+        .flat_map(|key| des_encrypt(key, &challenge))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{challenge_hash, nt_password_hash, nt_response};
+
+    #[test]
+    fn can_generate_nt_response() {
+        // RFC 2759 section 9.2
+        let authenticator = hex::decode("5B5D7C7D7B3F2F3E3C2C602132262628").unwrap();
+        let peer = hex::decode("21402324255E262A28295F2B3A337C7E").unwrap();
+
+        assert_eq!(
+            hex::encode(challenge_hash(&peer, &authenticator, "User")),
+            "d02e4386bce91226"
+        );
+        assert_eq!(
+            hex::encode(nt_password_hash("clientPass")),
+            "44ebba8d5312b8d611474411f56989ae"
+        );
+        assert_eq!(
+            hex::encode(nt_response(&authenticator, &peer, "User", "clientPass")),
+            "82309ecd8d708b5ea08faa3981cd83544233114a3d85d6df"
+        );
+    }
+}
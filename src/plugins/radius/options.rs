@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Auth {
+    #[default]
+    Pap,
+    Chap,
+    Mschapv2,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "")]
+    /// Shared secret between the RADIUS server and the NAS.
+    pub radius_secret: String,
+    #[clap(long, value_enum, default_value_t = Auth::Pap)]
+    /// RADIUS authentication protocol.
+    pub radius_auth: Auth,
+    #[clap(long, default_value = "legba")]
+    /// NAS-Identifier attribute sent with each request.
+    pub radius_nas_identifier: String,
+}
@@ -0,0 +1,222 @@
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+
+use crate::session::Error;
+
+pub(crate) const ACCESS_REQUEST: u8 = 1;
+pub(crate) const ACCESS_ACCEPT: u8 = 2;
+pub(crate) const ACCESS_REJECT: u8 = 3;
+pub(crate) const ACCESS_CHALLENGE: u8 = 11;
+
+pub(crate) const USER_NAME: u8 = 1;
+pub(crate) const USER_PASSWORD: u8 = 2;
+pub(crate) const CHAP_PASSWORD: u8 = 3;
+pub(crate) const REPLY_MESSAGE: u8 = 18;
+pub(crate) const VENDOR_SPECIFIC: u8 = 26;
+pub(crate) const NAS_IDENTIFIER: u8 = 32;
+pub(crate) const CHAP_CHALLENGE: u8 = 60;
+pub(crate) const MESSAGE_AUTHENTICATOR: u8 = 80;
+
+pub(crate) const VENDOR_MICROSOFT: u32 = 311;
+pub(crate) const MS_CHAP_CHALLENGE: u8 = 11;
+pub(crate) const MS_CHAP2_RESPONSE: u8 = 25;
+
+const HEADER_SIZE: usize = 20;
+const MAX_PASSWORD_SIZE: usize = 128;
+// the length octet also counts the type and itself
+const MAX_ATTRIBUTE_SIZE: usize = 253;
+
+fn md5(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Md5::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+// RFC 2865 User-Password hiding
+pub(crate) fn hide_password(password: &str, secret: &str, authenticator: &[u8]) -> Vec<u8> {
+    let mut data = password.as_bytes()[..password.len().min(MAX_PASSWORD_SIZE)].to_vec();
+    data.resize(data.len().div_ceil(16).max(1) * 16, 0);
+
+    let mut previous = authenticator.to_vec();
+    for block in data.chunks_mut(16) {
+        let key = md5(&[secret.as_bytes(), &previous]);
+        for (b, k) in block.iter_mut().zip(key) {
+            *b ^= k;
+        }
+        previous = block.to_vec();
+    }
+    data
+}
+
+// RFC 1994 CHAP response
+pub(crate) fn chap_response(ident: u8, password: &str, challenge: &[u8]) -> Vec<u8> {
+    md5(&[&[ident], password.as_bytes(), challenge])
+}
+
+pub(crate) fn vendor_attribute(vendor: u32, kind: u8, value: &[u8]) -> Result<Vec<u8>, Error> {
+    // vendor id, type and length must fit in the outer attribute
+    if value.len() > MAX_ATTRIBUTE_SIZE - 6 {
+        return Err(format!(
+            "RADIUS vendor attribute {} is {} bytes, at most {} are allowed",
+            kind,
+            value.len(),
+            MAX_ATTRIBUTE_SIZE - 6
+        ));
+    }
+    let mut out = vendor.to_be_bytes().to_vec();
+    out.extend_from_slice(&[kind, value.len() as u8 + 2]);
+    out.extend_from_slice(value);
+    Ok(out)
+}
+
+pub(crate) struct Packet {
+    pub code: u8,
+    pub identifier: u8,
+    pub authenticator: [u8; 16],
+    pub attributes: Vec<(u8, Vec<u8>)>,
+}
+
+impl Packet {
+    pub fn new(code: u8, identifier: u8, authenticator: [u8; 16]) -> Self {
+        Packet {
+            code,
+            identifier,
+            authenticator,
+            attributes: vec![],
+        }
+    }
+
+    pub fn add(&mut self, kind: u8, value: &[u8]) -> Result<(), Error> {
+        if value.len() > MAX_ATTRIBUTE_SIZE {
+            return Err(format!(
+                "RADIUS attribute {} is {} bytes, at most {} are allowed",
+                kind,
+                value.len(),
+                MAX_ATTRIBUTE_SIZE
+            ));
+        }
+        self.attributes.push((kind, value.to_vec()));
+        Ok(())
+    }
+
+    pub fn get(&self, kind: u8) -> impl Iterator<Item = &[u8]> {
+        self.attributes
+            .iter()
+            .filter(move |(k, _)| *k == kind)
+            .map(|(_, v)| v.as_slice())
+    }
+
+    fn encode_with(&self, authenticator: &[u8]) -> Vec<u8> {
+        let mut attributes = vec![];
+        for (kind, value) in &self.attributes {
+            attributes.extend_from_slice(&[*kind, value.len() as u8 + 2]);
+            attributes.extend_from_slice(value);
+        }
+
+        let mut out = vec![self.code, self.identifier];
+        out.extend_from_slice(&((HEADER_SIZE + attributes.len()) as u16).to_be_bytes());
+        out.extend_from_slice(authenticator);
+        out.extend(attributes);
+        out
+    }
+
+    // encodes a request signed with a RFC 3579 Message-Authenticator
+    pub fn encode_request(&mut self, secret: &str) -> Vec<u8> {
+        self.attributes.retain(|(k, _)| *k != MESSAGE_AUTHENTICATOR);
+        self.attributes.push((MESSAGE_AUTHENTICATOR, vec![0; 16]));
+
+        let mut data = self.encode_with(&self.authenticator);
+        let mut mac = Hmac::<Md5>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(&data);
+        let signature = mac.finalize().into_bytes();
+
+        let offset = data.len() - 16;
+        data[offset..].copy_from_slice(&signature);
+        self.attributes.last_mut().unwrap().1 = signature.to_vec();
+        data
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < HEADER_SIZE {
+            return Err("truncated RADIUS packet".to_owned());
+        }
+        let length = u16::from_be_bytes([data[2], data[3]]) as usize;
+        if length < HEADER_SIZE || length > data.len() {
+            return Err("invalid RADIUS packet length".to_owned());
+        }
+
+        let mut packet = Packet::new(data[0], data[1], data[4..20].try_into().unwrap());
+        let mut rest = &data[HEADER_SIZE..length];
+        while rest.len() >= 2 {
+            let size = rest[1] as usize;
+            if size < 2 || size > rest.len() {
+                return Err("invalid RADIUS attribute length".to_owned());
+            }
+            packet.add(rest[0], &rest[2..size])?;
+            rest = &rest[size..];
+        }
+        Ok(packet)
+    }
+
+    // RFC 2865 Response Authenticator, a mismatch means the shared secret is wrong
+    pub fn verify_response(&self, request_authenticator: &[u8], secret: &str) -> bool {
+        let data = self.encode_with(request_authenticator);
+        md5(&[&data, secret.as_bytes()]) == self.authenticator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hide_password, vendor_attribute, Packet, ACCESS_ACCEPT, ACCESS_REQUEST, MS_CHAP2_RESPONSE,
+        REPLY_MESSAGE, USER_NAME, VENDOR_MICROSOFT,
+    };
+
+    #[test]
+    fn can_hide_passwords() {
+        let authenticator = [0x11u8; 16];
+        let hidden = hide_password("password", "secret", &authenticator);
+        assert_eq!(hidden.len(), 16);
+        // hiding is symmetric
+        let mut clear = hidden.clone();
+        let key = super::md5(&[b"secret", &authenticator]);
+        for (b, k) in clear.iter_mut().zip(key) {
+            *b ^= k;
+        }
+        assert_eq!(&clear[..8], b"password");
+        assert_eq!(hide_password(&"a".repeat(17), "secret", &authenticator).len(), 32);
+    }
+
+    #[test]
+    fn can_encode_and_verify() {
+        let mut request = Packet::new(ACCESS_REQUEST, 7, [0x42; 16]);
+        request.add(USER_NAME, b"admin").unwrap();
+        let data = request.encode_request("secret");
+        // header, User-Name and Message-Authenticator
+        assert_eq!(data.len(), 20 + 7 + 18);
+
+        let decoded = Packet::decode(&data).unwrap();
+        assert_eq!(decoded.get(USER_NAME).next(), Some(&b"admin"[..]));
+
+        // sign a response as the server would
+        let mut response = Packet::new(ACCESS_ACCEPT, 7, [0; 16]);
+        response.add(REPLY_MESSAGE, b"Welcome").unwrap();
+        let unsigned = response.encode_with(&request.authenticator);
+        response.authenticator = super::md5(&[&unsigned, b"secret"]).try_into().unwrap();
+
+        let decoded = Packet::decode(&response.encode_with(&response.authenticator)).unwrap();
+        assert!(decoded.verify_response(&request.authenticator, "secret"));
+        assert!(!decoded.verify_response(&request.authenticator, "wrong"));
+    }
+
+    #[test]
+    fn refuses_oversized_attributes() {
+        let mut request = Packet::new(ACCESS_REQUEST, 7, [0x42; 16]);
+        assert!(request.add(USER_NAME, &[b'a'; 253]).is_ok());
+        assert!(request.add(USER_NAME, &[b'a'; 254]).is_err());
+        assert!(vendor_attribute(VENDOR_MICROSOFT, MS_CHAP2_RESPONSE, &[0; 247]).is_ok());
+        assert!(vendor_attribute(VENDOR_MICROSOFT, MS_CHAP2_RESPONSE, &[0; 248]).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+mod template;
+
+super::manager::register_plugin! {
+    "tcp" => Raw::new(Transport::Tcp),
+    "udp" => Raw::new(Transport::Udp)
+}
+
+const MAX_RESPONSE_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Transport {
+    Tcp,
+    Udp,
+}
+
+#[derive(Clone, Default)]
+struct Matcher {
+    success: Option<template::Pattern>,
+    failure: Option<template::Pattern>,
+}
+
+impl Matcher {
+    // None while the response doesn't match either pattern
+    fn decide(&self, response: &[u8]) -> Option<bool> {
+        if self.success.as_ref().is_some_and(|p| p.is_match(response)) {
+            Some(true)
+        } else if self.failure.as_ref().is_some_and(|p| p.is_match(response)) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    // with only a failure pattern, any other response is a positive match
+    fn verdict(&self, response: &[u8]) -> bool {
+        self.decide(response)
+            .unwrap_or(self.success.is_none() && !response.is_empty())
+    }
+}
+
+// sends the request and reads until the response can be classified, the server closes the
+// connection or stops sending data
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &[u8],
+    banner: bool,
+    matcher: &Matcher,
+    timeout: Duration,
+) -> Result<bool, Error> {
+    let mut buffer = [0u8; 4096];
+    if banner {
+        tokio::time::timeout(timeout, stream.read(&mut buffer))
+            .await
+            .map_err(|_| "no banner received".to_owned())?
+            .map_err(|e| e.to_string())?;
+    }
+
+    stream.write_all(request).await.map_err(|e| e.to_string())?;
+
+    let mut response = vec![];
+    while response.len() < MAX_RESPONSE_SIZE {
+        match tokio::time::timeout(timeout, stream.read(&mut buffer)).await {
+            Ok(Ok(0)) | Err(_) => break,
+            Ok(Ok(read)) => {
+                response.extend_from_slice(&buffer[..read]);
+                if let Some(decision) = matcher.decide(&response) {
+                    return Ok(decision);
+                }
+            }
+            Ok(Err(e)) if response.is_empty() => return Err(e.to_string()),
+            Ok(Err(_)) => break,
+        }
+    }
+
+    Ok(matcher.verdict(&response))
+}
+
+#[derive(Clone)]
+pub(crate) struct Raw {
+    transport: Transport,
+    opts: options::Options,
+    request: template::Template,
+    matcher: Matcher,
+}
+
+impl Raw {
+    pub fn new(transport: Transport) -> Self {
+        Raw {
+            transport,
+            opts: options::Options::default(),
+            request: template::Template::default(),
+            matcher: Matcher::default(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.transport {
+            Transport::Tcp => "tcp",
+            Transport::Udp => "udp",
+        }
+    }
+
+    async fn udp_exchange(
+        &self,
+        address: &str,
+        request: &[u8],
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let socket = utils::net::async_udp_socket(address).await?;
+        socket.send(request).await.map_err(|e| e.to_string())?;
+
+        let mut buffer = vec![0u8; MAX_RESPONSE_SIZE];
+        let size = match tokio::time::timeout(timeout, socket.recv(&mut buffer)).await {
+            Ok(res) => res.map_err(|e| e.to_string())?,
+            // no response at all
+            Err(_) => 0,
+        };
+
+        Ok(self.matcher.verdict(&buffer[..size]))
+    }
+}
+
+#[async_trait]
+impl Plugin for Raw {
+    fn description(&self) -> &'static str {
+        match self.transport {
+            Transport::Tcp => "Custom TCP protocol with hex request templates.",
+            Transport::Udp => "Custom UDP protocol with hex request templates.",
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.raw.clone();
+        if self.opts.raw_request.is_empty() {
+            return Err("no --raw-request provided".to_owned());
+        }
+        self.request = template::Template::parse(&self.opts.raw_request)?;
+
+        self.matcher = Matcher {
+            success: self
+                .opts
+                .raw_success
+                .as_ref()
+                .map(|p| template::Pattern::parse(p))
+                .transpose()?,
+            failure: self
+                .opts
+                .raw_failure
+                .as_ref()
+                .map(|p| template::Pattern::parse(p))
+                .transpose()?,
+        };
+        if self.matcher.success.is_none() && self.matcher.failure.is_none() {
+            return Err("at least one of --raw-success and --raw-failure is required".to_owned());
+        }
+
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 0)?;
+        if port == 0 {
+            return Err(format!("no port specified for {}", &creds.target));
+        }
+        let address = format!("{}:{}", host, port);
+        let request = self
+            .request
+            .render(&creds.username, &creds.password, &host)?;
+
+        let success = match self.transport {
+            Transport::Tcp => {
+                let mut stream =
+                    utils::net::async_tcp_stream(&address, timeout, self.opts.raw_ssl).await?;
+                exchange(
+                    &mut stream,
+                    &request,
+                    self.opts.raw_banner,
+                    &self.matcher,
+                    timeout,
+                )
+                .await?
+            }
+            Transport::Udp => self.udp_exchange(&address, &request, timeout).await?,
+        };
+
+        if success {
+            Ok(Some(vec![Loot::new(
+                self.name(),
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ],
+            )]))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::template::Pattern;
+    use super::{exchange, Matcher};
+
+    #[tokio::test]
+    async fn can_classify_responses() {
+        let matcher = Matcher {
+            success: Some(Pattern::parse("00 00").unwrap()),
+            failure: Some(Pattern::parse("ff ??").unwrap()),
+        };
+        let timeout = Duration::from_secs(1);
+
+        // response split in two reads
+        let mut stream = tokio_test::io::Builder::new()
+            .read(b"hello\n")
+            .write(&[0x01, 0x02])
+            .read(&[0x10])
+            .read(&[0x00, 0x00])
+            .build();
+        assert_eq!(
+            exchange(&mut stream, &[0x01, 0x02], true, &matcher, timeout).await,
+            Ok(true)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[0x01])
+            .read(&[0xff, 0x01])
+            .build();
+        assert_eq!(
+            exchange(&mut stream, &[0x01], false, &matcher, timeout).await,
+            Ok(false)
+        );
+
+        // only a failure pattern, anything else is a match but the connection being closed
+        let matcher = Matcher {
+            success: None,
+            failure: Some(Pattern::parse("ff").unwrap()),
+        };
+        assert!(matcher.verdict(&[0x00]));
+        assert!(!matcher.verdict(&[]));
+    }
+}
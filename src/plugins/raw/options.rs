@@ -0,0 +1,26 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "")]
+    /// Hex encoded request. {USERNAME}, {PASSWORD} and {TARGET} can be used as placeholders, {LEN8}, {LEN16} and {LEN32} (with an optional LE suffix and +/- adjustment, e.g. {LEN16LE+2}) are set to the size of what follows up to the matching {END}.
+    pub raw_request: String,
+
+    #[clap(long)]
+    /// Hex byte pattern to look for in the response to be considered as a positive match, ?? matches any byte.
+    pub raw_success: Option<String>,
+
+    #[clap(long)]
+    /// Hex byte pattern to look for in the response to be considered as a failed attempt, ?? matches any byte.
+    pub raw_failure: Option<String>,
+
+    #[clap(long, default_value_t = false)]
+    /// Read the server greeting before sending the request.
+    pub raw_banner: bool,
+
+    #[clap(long, default_value_t = false)]
+    /// Use TLS (tcp only).
+    pub raw_ssl: bool,
+}
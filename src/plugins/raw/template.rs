@@ -0,0 +1,233 @@
+use crate::session::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Bytes(Vec<u8>),
+    Username,
+    Password,
+    Target,
+    // length of what follows up to the matching {END}, or the end of the request
+    Length {
+        size: usize,
+        little_endian: bool,
+        adjust: i64,
+    },
+    End,
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    let digits: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() % 2 == 1 {
+        return Err(format!("odd number of hex digits in '{}'", hex.trim()));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex byte '{}'", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+fn parse_placeholder(name: &str) -> Result<Token, Error> {
+    match name {
+        "USERNAME" => return Ok(Token::Username),
+        "PASSWORD" => return Ok(Token::Password),
+        "TARGET" => return Ok(Token::Target),
+        "END" => return Ok(Token::End),
+        _ => {}
+    }
+
+    // LEN<bits>[LE][+-adjust]
+    let invalid = || format!("unknown placeholder {{{}}}", name);
+    let spec = name.strip_prefix("LEN").ok_or_else(invalid)?;
+    let (spec, adjust) = match spec.find(['+', '-']) {
+        Some(idx) => (
+            &spec[..idx],
+            spec[idx..]
+                .trim_start_matches('+')
+                .parse::<i64>()
+                .map_err(|_| invalid())?,
+        ),
+        None => (spec, 0),
+    };
+    let (bits, little_endian) = match spec.strip_suffix("LE") {
+        Some(bits) => (bits, true),
+        None => (spec, false),
+    };
+    let size = match bits {
+        "8" => 1,
+        "16" => 2,
+        "32" => 4,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Token::Length {
+        size,
+        little_endian,
+        adjust,
+    })
+}
+
+// hex request with placeholders, e.g. "01 {LEN16} {USERNAME} 00 {PASSWORD} {END} ff"
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Template {
+    tokens: Vec<Token>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let mut tokens = vec![];
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let bytes = parse_hex(&rest[..start])?;
+            if !bytes.is_empty() {
+                tokens.push(Token::Bytes(bytes));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or(format!("unterminated placeholder in '{}'", template))?;
+            tokens.push(parse_placeholder(
+                &rest[start + 1..start + end].trim().to_uppercase(),
+            )?);
+            rest = &rest[start + end + 1..];
+        }
+        let bytes = parse_hex(rest)?;
+        if !bytes.is_empty() {
+            tokens.push(Token::Bytes(bytes));
+        }
+
+        if tokens.is_empty() {
+            return Err("empty request template".to_owned());
+        }
+
+        Ok(Self { tokens })
+    }
+
+    pub fn render(&self, username: &str, password: &str, target: &str) -> Result<Vec<u8>, Error> {
+        let mut data = vec![];
+        // offset, size, endianness and adjustment of the length fields not closed yet
+        let mut open: Vec<(usize, usize, bool, i64)> = vec![];
+
+        let close = |data: &mut Vec<u8>, (offset, size, little_endian, adjust)| {
+            let length = (data.len() - offset - size) as i64 + adjust;
+            if length < 0 || length >= 1i64 << (size * 8) {
+                return Err(format!("length {} doesn't fit in {} byte(s)", length, size));
+            }
+            let bytes = (length as u32).to_be_bytes();
+            let mut field = bytes[4 - size..].to_vec();
+            if little_endian {
+                field.reverse();
+            }
+            data[offset..offset + size].copy_from_slice(&field);
+            Ok(())
+        };
+
+        for token in &self.tokens {
+            match token {
+                Token::Bytes(bytes) => data.extend(bytes),
+                Token::Username => data.extend(username.as_bytes()),
+                Token::Password => data.extend(password.as_bytes()),
+                Token::Target => data.extend(target.as_bytes()),
+                Token::Length {
+                    size,
+                    little_endian,
+                    adjust,
+                } => {
+                    open.push((data.len(), *size, *little_endian, *adjust));
+                    data.extend(vec![0; *size]);
+                }
+                Token::End => close(
+                    &mut data,
+                    open.pop().ok_or("{END} without a length placeholder")?,
+                )?,
+            }
+        }
+
+        while let Some(field) = open.pop() {
+            close(&mut data, field)?;
+        }
+
+        Ok(data)
+    }
+}
+
+// hex byte pattern where ?? matches any byte
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Self, Error> {
+        let digits: String = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.is_empty() || digits.len() % 2 == 1 {
+            return Err(format!("invalid byte pattern '{}'", pattern));
+        }
+        let bytes = (0..digits.len())
+            .step_by(2)
+            .map(|i| match &digits[i..i + 2] {
+                "??" => Ok(None),
+                byte => u8::from_str_radix(byte, 16)
+                    .map(Some)
+                    .map_err(|_| format!("invalid byte pattern '{}'", pattern)),
+            })
+            .collect::<Result<Vec<Option<u8>>, Error>>()?;
+
+        Ok(Self { bytes })
+    }
+
+    pub fn is_match(&self, data: &[u8]) -> bool {
+        data.windows(self.bytes.len()).any(|window| {
+            window
+                .iter()
+                .zip(self.bytes.iter())
+                .all(|(b, p)| p.is_none_or(|p| p == *b))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, Template};
+
+    #[test]
+    fn can_render_templates() {
+        let template = Template::parse("01 {LEN16} {USERNAME} 00 {LEN8} {PASSWORD} {END} ff").unwrap();
+        assert_eq!(
+            template.render("root", "toor", "").unwrap(),
+            [
+                &[0x01, 0x00, 0x0b][..],
+                b"root",
+                &[0x00, 0x04],
+                b"toor",
+                &[0xff]
+            ]
+            .concat()
+        );
+
+        // little endian, including the field itself
+        let template = Template::parse("{len32le+4}{password}").unwrap();
+        assert_eq!(
+            template.render("", "abc", "").unwrap(),
+            [7, 0, 0, 0, b'a', b'b', b'c']
+        );
+
+        assert!(Template::parse("01 0").is_err());
+        assert!(Template::parse("{LEN12}").is_err());
+        assert!(Template::parse("01 {USERNAME").is_err());
+        assert!(Template::parse("{LEN8}{PASSWORD}")
+            .unwrap()
+            .render("", &"a".repeat(300), "")
+            .is_err());
+    }
+
+    #[test]
+    fn can_match_patterns() {
+        let pattern = Pattern::parse("00 ?? 4f 4b").unwrap();
+        assert!(pattern.is_match(&[0x05, 0x00, 0x02, b'O', b'K']));
+        assert!(!pattern.is_match(&[0x00, 0x02, b'N', b'O']));
+        assert!(!pattern.is_match(&[0x00]));
+        assert!(Pattern::parse("4").is_err());
+    }
+}
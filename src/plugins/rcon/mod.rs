@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+super::manager::register_plugin! {
+    "rcon" => Rcon::new()
+}
+
+// Source RCON protocol, also used by Minecraft
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const MAX_PACKET_SIZE: usize = 4096;
+const AUTH_ID: i32 = 0x1337;
+const AUTH_FAILED_ID: i32 = -1;
+
+fn packet(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    let mut packet = vec![];
+    packet.extend_from_slice(&(body.len() as i32 + 10).to_le_bytes());
+    packet.extend_from_slice(&id.to_le_bytes());
+    packet.extend_from_slice(&kind.to_le_bytes());
+    packet.extend_from_slice(body.as_bytes());
+    packet.extend_from_slice(&[0, 0]);
+    packet
+}
+
+// returns the id and type of the next packet, the body is not needed
+async fn read_packet<S: AsyncRead + Unpin>(stream: &mut S) -> Result<(i32, i32), Error> {
+    let size = stream.read_i32_le().await.map_err(|e| e.to_string())?;
+    if !(10..=MAX_PACKET_SIZE as i32).contains(&size) {
+        return Err(format!("invalid RCON packet size {}", size));
+    }
+    let mut payload = vec![0u8; size as usize];
+    stream
+        .read_exact(&mut payload)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((
+        i32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        i32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]),
+    ))
+}
+
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    password: &str,
+) -> Result<bool, Error> {
+    stream
+        .write_all(&packet(AUTH_ID, SERVERDATA_AUTH, password))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Source servers send an empty SERVERDATA_RESPONSE_VALUE first, Minecraft doesn't
+    loop {
+        match read_packet(stream).await? {
+            (AUTH_ID, SERVERDATA_AUTH_RESPONSE) => return Ok(true),
+            (AUTH_FAILED_ID, SERVERDATA_AUTH_RESPONSE) => return Ok(false),
+            (_, SERVERDATA_AUTH_RESPONSE) => return Err("unexpected RCON auth response id".to_owned()),
+            _ => continue,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Rcon {}
+
+impl Rcon {
+    pub fn new() -> Self {
+        Rcon {}
+    }
+}
+
+#[async_trait]
+impl Plugin for Rcon {
+    fn description(&self) -> &'static str {
+        "Source and Minecraft RCON password authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 27015)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+
+        // being this plugin single credentials, this is going to be the password
+        let password = creds.single();
+        if tokio::time::timeout(timeout, authenticate(&mut stream, password))
+            .await
+            .map_err(|e| e.to_string())??
+        {
+            Ok(Some(vec![Loot::new(
+                "rcon",
+                &address,
+                [("password".to_owned(), password.to_owned())],
+            )]))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, packet};
+
+    #[tokio::test]
+    async fn can_authenticate_source() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&packet(0x1337, 3, "wrong"))
+            .read(&packet(0x1337, 0, ""))
+            .read(&packet(-1, 2, ""))
+            .write(&packet(0x1337, 3, "rc0n"))
+            .read(&packet(0x1337, 0, ""))
+            .read(&packet(0x1337, 2, ""))
+            .build();
+        assert_eq!(authenticate(&mut stream, "wrong").await, Ok(false));
+        assert_eq!(authenticate(&mut stream, "rc0n").await, Ok(true));
+    }
+
+    #[tokio::test]
+    async fn can_authenticate_minecraft() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[
+                0x0f, 0x00, 0x00, 0x00, 0x37, 0x13, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, b'w',
+                b'r', b'o', b'n', b'g', 0x00, 0x00,
+            ])
+            .read(&[
+                0x0a, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x02, 0x00, 0x00, 0x00, 0x00,
+                0x00,
+            ])
+            .build();
+        assert_eq!(authenticate(&mut stream, "wrong").await, Ok(false));
+    }
+}
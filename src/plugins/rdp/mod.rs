@@ -12,6 +12,7 @@ use crate::{utils, Options};
 
 use crate::creds::Credentials;
 
+mod nla;
 pub(crate) mod options;
 
 super::manager::register_plugin! {
@@ -34,7 +35,7 @@ impl RDP {
 #[async_trait]
 impl Plugin for RDP {
     fn description(&self) -> &'static str {
-        "Microsoft Remote Desktop password authentication."
+        "Microsoft Remote Desktop password authentication (with NLA/CredSSP checks when enforced)."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
@@ -52,6 +53,51 @@ impl Plugin for RDP {
             .parse::<SocketAddr>()
             .map_err(|e| e.to_string())?;
 
+        // ntlm:LM:NT payloads are always used as hashes
+        let hash = creds.ntlm_hash()?;
+        let password = hash.clone().unwrap_or_else(|| creds.password.to_owned());
+
+        // with network level authentication the credentials can be checked without a session
+        let nt_hash = if self.options.rdp_ntlm {
+            Some(password.as_str())
+        } else {
+            hash.as_deref()
+        };
+        match nla::check(
+            &address.to_string(),
+            &self.options.rdp_domain,
+            &creds.username,
+            &creds.password,
+            nt_hash,
+            timeout,
+        )
+        .await?
+        {
+            nla::Outcome::NotEnforced => {}
+            nla::Outcome::Invalid => return Ok(None),
+            nla::Outcome::Valid => {
+                return Ok(Some(vec![Loot::new(
+                    "rdp",
+                    &address.to_string(),
+                    [
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("password".to_owned(), creds.password.to_owned()),
+                    ],
+                )]))
+            }
+            nla::Outcome::Expired => {
+                return Ok(Some(vec![Loot::new(
+                    "rdp",
+                    &address.to_string(),
+                    [
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("expired_password".to_owned(), creds.password.to_owned()),
+                    ],
+                )
+                .set_partial()]))
+            }
+        }
+
         let stream = TcpStream::connect_timeout(&address, timeout).map_err(|e| e.to_string())?;
 
         let mut rdp_connector = Connector::new()
@@ -59,16 +105,16 @@ impl Plugin for RDP {
             .credentials(
                 self.options.rdp_domain.to_owned(),
                 creds.username.to_owned(),
-                creds.password.to_owned(),
+                password.clone(),
             )
             .layout(KeyboardLayout::US)
             .set_restricted_admin_mode(self.options.rdp_admin_mode)
             .auto_logon(self.options.rdp_auto_logon)
             .check_certificate(false);
 
-        if self.options.rdp_ntlm {
+        if self.options.rdp_ntlm || hash.is_some() {
             rdp_connector = rdp_connector.set_password_hash(
-                hex::decode(&creds.password)
+                hex::decode(&password)
                     .map_err(|e| format!("cannot parse the input hash [{}]", e))?,
             );
         }
@@ -0,0 +1,343 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::session::Error;
+use crate::utils::{self, ntlm};
+
+const PROTOCOL_SSL: u32 = 0x01;
+const PROTOCOL_HYBRID: u32 = 0x02;
+const PROTOCOL_HYBRID_EX: u32 = 0x08;
+
+// TSRequest version, the first one reporting the NTSTATUS of failed authentications
+const CREDSSP_VERSION: u8 = 3;
+
+// TPKT header plus the fixed part of the X.224 connection confirm
+const MIN_CONFIRM_SIZE: usize = 7;
+const MAX_TS_REQUEST_SIZE: usize = 64 * 1024;
+
+const STATUS_LOGON_FAILURE: u32 = 0xC000006D;
+const STATUS_PASSWORD_EXPIRED: u32 = 0xC0000071;
+const STATUS_PASSWORD_MUST_CHANGE: u32 = 0xC0000224;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // the server doesn't enforce network level authentication
+    NotEnforced,
+    Valid,
+    Expired,
+    Invalid,
+}
+
+// TPKT + X.224 connection request with a RDP_NEG_REQ for the given protocols
+fn connection_request(protocols: u32) -> Vec<u8> {
+    let mut x224 = vec![14, 0xe0, 0, 0, 0, 0, 0];
+    x224.extend_from_slice(&[0x01, 0x00, 0x08, 0x00]);
+    x224.extend_from_slice(&protocols.to_le_bytes());
+
+    let mut tpkt = vec![3, 0];
+    tpkt.extend_from_slice(&((x224.len() + 4) as u16).to_be_bytes());
+    tpkt.extend(x224);
+    tpkt
+}
+
+// protocol selected by the server in the X.224 connection confirm
+fn selected_protocol(confirm: &[u8]) -> Result<u32, Error> {
+    if confirm.len() < 7 || confirm[0] != 3 || confirm[5] & 0xf0 != 0xd0 {
+        return Err("invalid X.224 connection confirm".to_owned());
+    }
+
+    let negotiation = &confirm[11.min(confirm.len())..];
+    if negotiation.len() < 8 {
+        // no negotiation data, standard RDP security
+        return Ok(0);
+    }
+
+    let value = u32::from_le_bytes(negotiation[4..8].try_into().unwrap());
+    match negotiation[0] {
+        0x02 => Ok(value),
+        0x03 => Err(format!("negotiation failure code {}", value)),
+        other => Err(format!("unexpected negotiation message type {}", other)),
+    }
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else if len <= 0xff {
+        out.extend_from_slice(&[0x81, len as u8]);
+    } else {
+        out.push(0x82);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+// splits a DER element into its tag, content and the remaining data
+fn der_parse(data: &[u8]) -> Result<(u8, &[u8], &[u8]), Error> {
+    if data.len() < 2 {
+        return Err("truncated DER element".to_owned());
+    }
+    let (len, offset) = match data[1] {
+        n if n < 0x80 => (n as usize, 2),
+        n => {
+            let size = (n & 0x7f) as usize;
+            if size > 4 || data.len() < 2 + size {
+                return Err("invalid DER length".to_owned());
+            }
+            let len = data[2..2 + size]
+                .iter()
+                .fold(0usize, |acc, b| acc << 8 | *b as usize);
+            (len, 2 + size)
+        }
+    };
+    if data.len() < offset + len {
+        return Err("truncated DER element".to_owned());
+    }
+    Ok((data[0], &data[offset..offset + len], &data[offset + len..]))
+}
+
+fn ts_request(nego_token: &[u8], pub_key_auth: Option<&[u8]>) -> Vec<u8> {
+    let mut content = der(0xa0, &der(0x02, &[CREDSSP_VERSION]));
+    content.extend(der(
+        0xa1,
+        &der(0x30, &der(0x30, &der(0xa0, &der(0x04, nego_token)))),
+    ));
+    if let Some(pub_key_auth) = pub_key_auth {
+        content.extend(der(0xa3, &der(0x04, pub_key_auth)));
+    }
+    der(0x30, &content)
+}
+
+#[derive(Debug, Default)]
+struct TsResponse {
+    nego_token: Option<Vec<u8>>,
+    pub_key_auth: Option<Vec<u8>>,
+    error_code: Option<u32>,
+}
+
+fn parse_ts_request(data: &[u8]) -> Result<TsResponse, Error> {
+    let (tag, mut fields, _) = der_parse(data)?;
+    if tag != 0x30 {
+        return Err("invalid TSRequest".to_owned());
+    }
+
+    let mut response = TsResponse::default();
+    while !fields.is_empty() {
+        let (tag, value, rest) = der_parse(fields)?;
+        fields = rest;
+        match tag {
+            // negoTokens: SEQUENCE OF SEQUENCE { [0] OCTET STRING }
+            0xa1 => {
+                let (_, tokens, _) = der_parse(value)?;
+                let (_, token, _) = der_parse(tokens)?;
+                let (_, token, _) = der_parse(token)?;
+                let (_, token, _) = der_parse(token)?;
+                response.nego_token = Some(token.to_vec());
+            }
+            0xa3 => response.pub_key_auth = Some(der_parse(value)?.1.to_vec()),
+            0xa4 => {
+                let (_, code, _) = der_parse(value)?;
+                response.error_code = Some(
+                    code.iter()
+                        .fold(0u32, |acc, b| acc.wrapping_shl(8) | *b as u32),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Ok(response)
+}
+
+async fn read_ts_request<R: AsyncRead + Unpin>(stream: &mut R) -> Result<TsResponse, Error> {
+    let mut header = [0u8; 2];
+    stream
+        .read_exact(&mut header)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut data = header.to_vec();
+    let len = if header[1] < 0x80 {
+        header[1] as usize
+    } else {
+        let size_bytes = (header[1] & 0x7f) as usize;
+        if size_bytes > 4 {
+            return Err(format!("invalid TSRequest length of {} bytes", size_bytes));
+        }
+        let mut size = vec![0u8; size_bytes];
+        stream
+            .read_exact(&mut size)
+            .await
+            .map_err(|e| e.to_string())?;
+        data.extend_from_slice(&size);
+        size.iter().fold(0usize, |acc, b| acc << 8 | *b as usize)
+    };
+
+    if len > MAX_TS_REQUEST_SIZE {
+        return Err(format!("invalid TSRequest size {}", len));
+    }
+    let mut content = vec![0u8; len];
+    stream
+        .read_exact(&mut content)
+        .await
+        .map_err(|e| e.to_string())?;
+    data.extend(content);
+
+    parse_ts_request(&data)
+}
+
+async fn read_confirm<R: AsyncRead + Unpin>(stream: &mut R) -> Result<Vec<u8>, Error> {
+    let mut tpkt = [0u8; 4];
+    stream
+        .read_exact(&mut tpkt)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let size = u16::from_be_bytes([tpkt[2], tpkt[3]]) as usize;
+    if size < MIN_CONFIRM_SIZE {
+        return Err(format!("invalid TPKT size {}", size));
+    }
+
+    let mut confirm = tpkt.to_vec();
+    confirm.resize(size, 0);
+    stream
+        .read_exact(&mut confirm[4..])
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(confirm)
+}
+
+// perform the CredSSP handshake up to the public key verification, without sending the
+// credentials nor establishing a session
+pub(crate) async fn check(
+    address: &str,
+    domain: &str,
+    username: &str,
+    password: &str,
+    nt_hash: Option<&str>,
+    timeout: Duration,
+) -> Result<Outcome, Error> {
+    let mut stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+
+    stream
+        .write_all(&connection_request(
+            PROTOCOL_SSL | PROTOCOL_HYBRID | PROTOCOL_HYBRID_EX,
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let confirm = read_confirm(&mut stream).await?;
+    if selected_protocol(&confirm)? & (PROTOCOL_HYBRID | PROTOCOL_HYBRID_EX) == 0 {
+        return Ok(Outcome::NotEnforced);
+    }
+
+    let mut stream = utils::net::upgrade_tcp_stream_to_tls(stream, timeout).await?;
+    let certificate = stream
+        .peer_certificate()
+        .map_err(|e| e.to_string())?
+        .ok_or("no peer certificate".to_owned())?
+        .to_der()
+        .map_err(|e| e.to_string())?;
+    let (_, certificate) =
+        x509_parser::parse_x509_certificate(&certificate).map_err(|e| e.to_string())?;
+    let public_key = certificate
+        .tbs_certificate
+        .subject_pki
+        .subject_public_key
+        .data
+        .to_vec();
+
+    stream
+        .write_all(&ts_request(&ntlm::negotiate_sealed("")?, None))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let challenge = read_ts_request(&mut stream)
+        .await?
+        .nego_token
+        .ok_or("no NTLM challenge in TSRequest".to_owned())?;
+
+    let (authenticate, mut sealing) =
+        ntlm::authenticate_sealed(&challenge, username, domain, "", password, nt_hash)?;
+    stream
+        .write_all(&ts_request(&authenticate, Some(&sealing.wrap(&public_key))))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // older servers just close the connection if the authentication failed
+    let Ok(response) = read_ts_request(&mut stream).await else {
+        return Ok(Outcome::Invalid);
+    };
+
+    Ok(match response.error_code {
+        None if response.pub_key_auth.is_some() => Outcome::Valid,
+        Some(STATUS_PASSWORD_EXPIRED) | Some(STATUS_PASSWORD_MUST_CHANGE) => Outcome::Expired,
+        Some(STATUS_LOGON_FAILURE) | None => Outcome::Invalid,
+        Some(code) => {
+            log::debug!("{}: CredSSP error code 0x{:08x}", address, code);
+            Outcome::Invalid
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        connection_request, parse_ts_request, read_confirm, read_ts_request, selected_protocol,
+        ts_request,
+    };
+
+    #[test]
+    fn can_negotiate_protocols() {
+        assert_eq!(
+            connection_request(0x0b),
+            [3, 0, 0, 19, 14, 0xe0, 0, 0, 0, 0, 0, 1, 0, 8, 0, 0x0b, 0, 0, 0]
+        );
+        assert_eq!(
+            selected_protocol(&[3, 0, 0, 19, 14, 0xd0, 0, 0, 0x12, 0x34, 0, 2, 0x1f, 8, 0, 2, 0, 0, 0]),
+            Ok(2)
+        );
+        assert_eq!(
+            selected_protocol(&[3, 0, 0, 11, 6, 0xd0, 0, 0, 0x12, 0x34, 0]),
+            Ok(0)
+        );
+        assert!(selected_protocol(&[3, 0, 0, 19, 14, 0xd0, 0, 0, 0, 0, 0, 3, 0, 8, 0, 5, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn can_encode_and_parse_ts_requests() {
+        let request = ts_request(b"token", Some(b"auth"));
+        let parsed = parse_ts_request(&request).unwrap();
+        assert_eq!(parsed.nego_token, Some(b"token".to_vec()));
+        assert_eq!(parsed.pub_key_auth, Some(b"auth".to_vec()));
+        assert_eq!(parsed.error_code, None);
+
+        // version 3 with errorCode STATUS_PASSWORD_MUST_CHANGE
+        let parsed = parse_ts_request(&[
+            0x30, 0x0e, 0xa0, 0x03, 0x02, 0x01, 0x03, 0xa4, 0x07, 0x02, 0x05, 0x00, 0xc0, 0x00,
+            0x02, 0x24,
+        ])
+        .unwrap();
+        assert_eq!(parsed.error_code, Some(0xC0000224));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_sizes() {
+        let mut stream = tokio_test::io::Builder::new().read(&[3, 0, 0, 2]).build();
+        assert!(read_confirm(&mut stream).await.is_err());
+
+        // 16 MiB TSRequest
+        let mut stream = tokio_test::io::Builder::new()
+            .read(&[0x30, 0x83, 0xff, 0xff, 0xff])
+            .build();
+        assert!(read_ts_request(&mut stream).await.is_err());
+
+        // too many length bytes
+        let mut stream = tokio_test::io::Builder::new().read(&[0x30, 0x85]).build();
+        assert!(read_ts_request(&mut stream).await.is_err());
+    }
+}
@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
 use crate::Plugin;
 use crate::{utils, Options};
 
@@ -15,14 +16,157 @@ super::manager::register_plugin! {
     "redis" => Redis::new()
 }
 
+const MAX_LINE_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Status(String),
+    Error(String),
+    Bulk(Option<String>),
+    Other(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    // authenticated with AUTH <username> <password>
+    Acl,
+    // authenticated with AUTH <password>
+    Legacy,
+    Invalid,
+}
+
+// RESP array of bulk strings, so that spaces and newlines in the credentials are preserved
+fn command(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+async fn read_line<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, Error> {
+    let mut line = vec![];
+    loop {
+        let byte = stream.read_u8().await.map_err(|e| e.to_string())?;
+        if byte == b'\n' {
+            break;
+        }
+        line.push(byte);
+        if line.len() > MAX_LINE_SIZE {
+            return Err("Redis reply too long".to_owned());
+        }
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_owned())
+}
+
+async fn read_reply<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Reply, Error> {
+    let line = read_line(stream).await?;
+    if let Some(status) = line.strip_prefix('+') {
+        Ok(Reply::Status(status.to_owned()))
+    } else if let Some(error) = line.strip_prefix('-') {
+        Ok(Reply::Error(error.to_owned()))
+    } else if let Some(len) = line.strip_prefix('$') {
+        let Ok(len) = len.parse::<usize>() else {
+            // $-1, null bulk string
+            return Ok(Reply::Bulk(None));
+        };
+        let mut data = vec![0u8; len.min(MAX_LINE_SIZE) + 2];
+        stream
+            .read_exact(&mut data)
+            .await
+            .map_err(|e| e.to_string())?;
+        data.truncate(len);
+        Ok(Reply::Bulk(Some(String::from_utf8_lossy(&data).to_string())))
+    } else {
+        Ok(Reply::Other(line))
+    }
+}
+
+async fn call<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    args: &[&str],
+) -> Result<Reply, Error> {
+    stream
+        .write_all(&command(args))
+        .await
+        .map_err(|e| e.to_string())?;
+    read_reply(stream).await
+}
+
+// try ACL authentication first (Redis 6+), then the legacy requirepass one
+async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    match call(stream, &["AUTH", username, password]).await? {
+        Reply::Status(_) => return Ok(Outcome::Acl),
+        Reply::Error(error) if error.starts_with("WRONGPASS") || error.contains("arguments") => {}
+        Reply::Error(error) => return Err(error),
+        other => return Err(format!("unexpected reply: {:?}", other)),
+    }
+
+    match call(stream, &["AUTH", password]).await? {
+        Reply::Status(_) => Ok(Outcome::Legacy),
+        Reply::Error(error) if error.starts_with("WRONGPASS") || error.starts_with("ERR") => {
+            Ok(Outcome::Invalid)
+        }
+        Reply::Error(error) => Err(error),
+        other => Err(format!("unexpected reply: {:?}", other)),
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct Redis {
     ssl: bool,
+    // targets already checked for missing authentication or protected mode
+    checked: OncePerTarget,
+    // targets not requiring authentication or in protected mode, no need to test them
+    skip: OncePerTarget,
 }
 
 impl Redis {
     pub fn new() -> Self {
-        Redis { ssl: false }
+        Redis {
+            ssl: false,
+            checked: OncePerTarget::new(),
+            skip: OncePerTarget::new(),
+        }
+    }
+
+    async fn check_no_auth(
+        &self,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let mut stream = crate::utils::net::async_tcp_stream(address, timeout, self.ssl).await?;
+        let reply = tokio::time::timeout(timeout, call(&mut stream, &["PING"]))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        Ok(match reply {
+            Reply::Status(_) => self.skip.report(
+                address,
+                Loot::new(
+                    "redis",
+                    address,
+                    [("authentication".to_owned(), "none".to_owned())],
+                ),
+            ),
+            // no password set but connections from non loopback interfaces are refused
+            Reply::Error(error) if error.starts_with("DENIED") => self.skip.report(
+                address,
+                Loot::new(
+                    "redis",
+                    address,
+                    [("protected_mode".to_owned(), "yes".to_owned())],
+                )
+                .set_partial(),
+            ),
+            _ => None,
+        })
     }
 }
 
@@ -44,31 +188,126 @@ impl Plugin for Redis {
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 6379)?;
 
+        if !self.checked.contains(&address) {
+            let loot = self.check_no_auth(&address, timeout).await?;
+            // marked only once the check completed, so transient errors are checked again
+            self.checked.insert(&address);
+            if loot.is_some() {
+                return Ok(loot);
+            }
+        }
+        if self.skip.contains(&address) {
+            return Ok(None);
+        }
+
         let mut stream = crate::utils::net::async_tcp_stream(&address, timeout, self.ssl).await?;
+        let outcome = tokio::time::timeout(
+            timeout,
+            authenticate(&mut stream, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
 
-        stream
-            .write_all(format!("AUTH {} {}\n", &creds.username, &creds.password).as_bytes())
-            .await
-            .map_err(|e| e.to_string())?;
+        let mut data = match outcome {
+            Outcome::Invalid => return Ok(None),
+            Outcome::Acl => vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ],
+            Outcome::Legacy => vec![("password".to_owned(), creds.password.to_owned())],
+        };
 
-        let mut buffer = [0_u8; 3];
+        // not available before Redis 6
+        if let Ok(Ok(Reply::Bulk(Some(user)))) =
+            tokio::time::timeout(timeout, call(&mut stream, &["ACL", "WHOAMI"])).await
+        {
+            data.push(("whoami".to_owned(), user));
+        }
 
-        stream
-            .read_exact(&mut buffer)
-            .await
-            .map_err(|e| e.to_string())?;
+        Ok(Some(vec![Loot::new("redis", &address, data)]))
+    }
+}
 
-        if buffer.starts_with(&[b'+', b'O', b'K']) {
-            Ok(Some(vec![Loot::new(
-                "redis",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]))
-        } else {
-            Ok(None)
-        }
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{authenticate, command, Outcome, Redis};
+    use crate::creds::Credentials;
+    use crate::Plugin;
+
+    #[test]
+    fn can_encode_commands() {
+        assert_eq!(
+            command(&["AUTH", "default", "pass word"]),
+            b"*3\r\n$4\r\nAUTH\r\n$7\r\ndefault\r\n$9\r\npass word\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&command(&["AUTH", "admin", "secret"]))
+            .read(b"+OK\r\n")
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "admin", "secret").await,
+            Ok(Outcome::Acl)
+        );
+
+        // Redis 5 doesn't support ACL authentication
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&command(&["AUTH", "admin", "secret"]))
+            .read(b"-ERR wrong number of arguments for 'auth' command\r\n")
+            .write(&command(&["AUTH", "secret"]))
+            .read(b"+OK\r\n")
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "admin", "secret").await,
+            Ok(Outcome::Legacy)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&command(&["AUTH", "admin", "wrong"]))
+            .read(b"-WRONGPASS invalid username-password pair or user is disabled.\r\n")
+            .write(&command(&["AUTH", "wrong"]))
+            .read(b"-WRONGPASS invalid username-password pair or user is disabled.\r\n")
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "admin", "wrong").await,
+            Ok(Outcome::Invalid)
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_authentication_again_after_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            // the first connection is dropped, the next ones don't require authentication
+            drop(listener.accept().await.unwrap());
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 64];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream.write_all(b"+PONG\r\n").await;
+            }
+        });
+
+        let redis = Redis::new();
+        let creds = Credentials {
+            target: address,
+            username: "admin".to_owned(),
+            password: "secret".to_owned(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        assert!(redis.attempt(&creds, timeout).await.is_err());
+        assert!(redis.attempt(&creds, timeout).await.unwrap().is_some());
+        assert!(redis.attempt(&creds, timeout).await.unwrap().is_none());
     }
 }
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "rexec" => Rexec::new()
+}
+
+const MAX_OUTPUT: u64 = 4096;
+
+// runs the command and returns its output, or None if the credentials are rejected
+async fn execute<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+    command: &str,
+) -> Result<Option<String>, Error> {
+    // no port for the standard error stream
+    stream
+        .write_all(format!("0\0{}\0{}\0{}\0", username, password, command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = stream.read_u8().await.map_err(|e| e.to_string())?;
+    let mut output = vec![];
+    stream
+        .take(MAX_OUTPUT)
+        .read_to_end(&mut output)
+        .await
+        .map_err(|e| e.to_string())?;
+    let output = String::from_utf8_lossy(&output).trim().to_owned();
+
+    if status == 0 {
+        Ok(Some(output))
+    } else {
+        // Login incorrect., Password incorrect., Permission denied. and the likes
+        log::debug!("rexec: {}", output);
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Rexec {
+    command: String,
+}
+
+impl Rexec {
+    pub fn new() -> Self {
+        Rexec {
+            command: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Rexec {
+    fn description(&self) -> &'static str {
+        "rexec password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.command.clone_from(&opts.rexec.rexec_command);
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 512)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let output = tokio::time::timeout(
+            timeout,
+            execute(&mut stream, &creds.username, &creds.password, &self.command),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        Ok(output.map(|output| {
+            let mut data = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+            ];
+            if !output.is_empty() {
+                data.push(("output".to_owned(), output));
+            }
+            vec![Loot::new("rexec", &address, data)]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::execute;
+
+    #[tokio::test]
+    async fn can_execute() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"0\0root\0toor\0id\0")
+            .read(b"\0uid=0(root) gid=0(root) groups=0(root)\n")
+            .build();
+        assert_eq!(
+            execute(&mut stream, "root", "toor", "id").await,
+            Ok(Some("uid=0(root) gid=0(root) groups=0(root)".to_owned()))
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"0\0root\0wrong\0id\0")
+            .read(b"\x01Password incorrect.\n")
+            .build();
+        assert_eq!(execute(&mut stream, "root", "wrong", "id").await, Ok(None));
+    }
+}
@@ -0,0 +1,266 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "rlogin" => Rlogin::new()
+}
+
+const MAX_OUTPUT: usize = 16 * 1024;
+
+#[derive(Debug, PartialEq)]
+enum Access {
+    // the host or user is trusted by .rhosts or hosts.equiv
+    Trusted,
+    Password,
+}
+
+#[derive(Debug, PartialEq)]
+enum Screen {
+    PasswordPrompt,
+    LoginPrompt,
+    Shell,
+    Denied,
+}
+
+fn screen(output: &str) -> Option<Screen> {
+    let lower = output.to_lowercase();
+    if lower.contains("incorrect") || lower.contains("denied") {
+        return Some(Screen::Denied);
+    }
+    let last = lower.lines().last().unwrap_or_default().trim_end();
+    if last.ends_with("password:") {
+        Some(Screen::PasswordPrompt)
+    } else if last.ends_with("login:") {
+        Some(Screen::LoginPrompt)
+    } else if last.ends_with('$') || last.ends_with('#') || last.ends_with('>') || last.ends_with('%')
+    {
+        Some(Screen::Shell)
+    } else {
+        None
+    }
+}
+
+async fn read_screen<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    output: &mut String,
+) -> Result<Screen, Error> {
+    loop {
+        if let Some(screen) = screen(output) {
+            output.clear();
+            return Ok(screen);
+        }
+
+        let mut buf = [0u8; 1024];
+        let read = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(Screen::Denied);
+        }
+        output.push_str(&String::from_utf8_lossy(&buf[..read]));
+        if output.len() > MAX_OUTPUT {
+            return Err("too much output without a prompt".to_owned());
+        }
+    }
+}
+
+async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    local_user: &str,
+    username: &str,
+    password: &str,
+    terminal: &str,
+) -> Result<Option<Access>, Error> {
+    stream
+        .write_all(format!("\0{}\0{}\0{}\0", local_user, username, terminal).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // a null byte acknowledges the connection, anything else is an error message
+    let ack = stream.read_u8().await.map_err(|e| e.to_string())?;
+    if ack != 0 {
+        let mut message = vec![ack];
+        let _ = stream.take(1024).read_to_end(&mut message).await;
+        return Err(String::from_utf8_lossy(&message).trim().to_owned());
+    }
+
+    let mut output = String::new();
+    match read_screen(stream, &mut output).await? {
+        Screen::Shell => return Ok(Some(Access::Trusted)),
+        Screen::PasswordPrompt => {}
+        _ => return Ok(None),
+    }
+
+    stream
+        .write_all(format!("{}\r", password).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    match read_screen(stream, &mut output).await? {
+        Screen::Shell => Ok(Some(Access::Password)),
+        _ => Ok(None),
+    }
+}
+
+// rlogind only trusts connections from privileged ports, which require root
+async fn connect(address: &str, timeout: Duration) -> Result<TcpStream, Error> {
+    let target: SocketAddr = tokio::net::lookup_host(utils::resolver::resolve_address(address))
+        .await
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or(format!("can't resolve {}", address))?;
+
+    for port in (512..1024).rev() {
+        let socket = if target.is_ipv4() {
+            TcpSocket::new_v4()
+        } else {
+            TcpSocket::new_v6()
+        }
+        .map_err(|e| e.to_string())?;
+
+        let local = SocketAddr::new(
+            if target.is_ipv4() {
+                std::net::Ipv4Addr::UNSPECIFIED.into()
+            } else {
+                std::net::Ipv6Addr::UNSPECIFIED.into()
+            },
+            port,
+        );
+        match socket.bind(local) {
+            Ok(()) => {
+                return tokio::time::timeout(timeout, socket.connect(target))
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .map_err(|e| e.to_string());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => continue,
+            Err(e) => {
+                log::debug!("can't bind a privileged port: {}", e);
+                break;
+            }
+        }
+    }
+
+    tokio::time::timeout(timeout, TcpStream::connect(target))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Clone)]
+pub(crate) struct Rlogin {
+    opts: options::Options,
+}
+
+impl Rlogin {
+    pub fn new() -> Self {
+        Rlogin {
+            opts: options::Options::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Rlogin {
+    fn description(&self) -> &'static str {
+        "rlogin password and trusted host authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.rlogin.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 513)?;
+        let mut stream = connect(&address, timeout).await?;
+        let local_user = self
+            .opts
+            .rlogin_local_user
+            .as_ref()
+            .unwrap_or(&creds.username);
+
+        let access = tokio::time::timeout(
+            timeout,
+            login(
+                &mut stream,
+                local_user,
+                &creds.username,
+                &creds.password,
+                &self.opts.rlogin_terminal,
+            ),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        Ok(access.map(|access| {
+            let data = match access {
+                Access::Password => vec![
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ],
+                Access::Trusted => vec![
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("local_user".to_owned(), local_user.to_owned()),
+                    ("authentication".to_owned(), "trusted".to_owned()),
+                ],
+            };
+            vec![Loot::new("rlogin", &address, data)]
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{login, Access};
+
+    #[tokio::test]
+    async fn can_login() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"\0root\0root\0xterm/38400\0")
+            .read(b"\0")
+            .read(b"Password: ")
+            .write(b"toor\r")
+            .read(b"\r\nLast login: Mon Jan  1 00:00:00 from 10.0.0.1\r\n[root@legacy ~]# ")
+            .build();
+        assert_eq!(
+            login(&mut stream, "root", "root", "toor", "xterm/38400").await,
+            Ok(Some(Access::Password))
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"\0root\0root\0xterm/38400\0")
+            .read(b"\0")
+            .read(b"Password: ")
+            .write(b"wrong\r")
+            .read(b"\r\nLogin incorrect\r\nlogin: ")
+            .build();
+        assert_eq!(
+            login(&mut stream, "root", "root", "wrong", "xterm/38400").await,
+            Ok(None)
+        );
+
+        // .rhosts
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"\0bin\0bin\0xterm/38400\0")
+            .read(b"\0")
+            .read(b"$ ")
+            .build();
+        assert_eq!(
+            login(&mut stream, "bin", "bin", "", "xterm/38400").await,
+            Ok(Some(Access::Trusted))
+        );
+    }
+}
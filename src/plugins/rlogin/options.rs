@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long)]
+    /// Local username sent to the server, checked against .rhosts (defaults to the remote username).
+    pub rlogin_local_user: Option<String>,
+    #[clap(long, default_value = "xterm/38400")]
+    /// Terminal type and speed.
+    pub rlogin_terminal: String,
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::BufReader;
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::net::StreamLike;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use self::protocol::Access;
+
+pub(crate) mod options;
+mod protocol;
+
+super::manager::register_plugin! {
+    "rsync" => Rsync::new()
+}
+
+async fn connect(
+    address: &str,
+    timeout: Duration,
+) -> Result<BufReader<Box<dyn StreamLike>>, Error> {
+    let stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+    let mut reader = BufReader::new(stream);
+    tokio::time::timeout(timeout, protocol::handshake(&mut reader))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(reader)
+}
+
+#[derive(Clone)]
+pub(crate) struct Rsync {
+    module: Option<String>,
+    // modules requiring authentication of each target, listed once
+    modules: Arc<Mutex<HashMap<String, Vec<String>>>>,
+}
+
+impl Rsync {
+    pub fn new() -> Self {
+        Rsync {
+            module: None,
+            modules: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // lists the modules of the target reporting the ones not requiring authentication
+    async fn enumerate(
+        &self,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<(Vec<String>, Vec<Loot>), Error> {
+        let mut reader = connect(address, timeout).await?;
+        let modules = tokio::time::timeout(timeout, protocol::list_modules(&mut reader))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        let mut protected = vec![];
+        let mut open = vec![];
+        for (name, comment) in modules {
+            let mut reader = connect(address, timeout).await?;
+            match tokio::time::timeout(timeout, protocol::select_module(&mut reader, &name))
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                Ok(Access::Protected(_)) => protected.push(name),
+                Ok(Access::Open) => {
+                    log::info!("rsync module {} on {} requires no authentication", &name, address);
+                    let mut data = vec![
+                        ("module".to_owned(), name),
+                        ("authentication".to_owned(), "none".to_owned()),
+                    ];
+                    if !comment.is_empty() {
+                        data.push(("comment".to_owned(), comment));
+                    }
+                    open.push(Loot::new("rsync", address, data));
+                }
+                Err(e) => log::debug!("rsync module {} on {}: {}", &name, address, e),
+            }
+        }
+
+        Ok((protected, open))
+    }
+}
+
+#[async_trait]
+impl Plugin for Rsync {
+    fn description(&self) -> &'static str {
+        "Rsync daemon modules enumeration and password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.module = opts.rsync.rsync_module.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 873)?;
+        let mut found = vec![];
+
+        let modules = if let Some(module) = &self.module {
+            vec![module.to_owned()]
+        } else {
+            // hold the lock so that the target is only enumerated once
+            let mut cache = self.modules.lock().await;
+            if let Some(modules) = cache.get(&address) {
+                modules.clone()
+            } else {
+                let (protected, open) = self.enumerate(&address, timeout).await?;
+                cache.insert(address.clone(), protected.clone());
+                found.extend(open);
+                protected
+            }
+        };
+
+        for module in modules {
+            let mut reader = connect(&address, timeout).await?;
+            let valid = tokio::time::timeout(timeout, async {
+                match protocol::select_module(&mut reader, &module).await? {
+                    // any credential works, already reported during the enumeration
+                    Access::Open => Ok(false),
+                    Access::Protected(challenge) => {
+                        protocol::authenticate(
+                            &mut reader,
+                            &challenge,
+                            &creds.username,
+                            &creds.password,
+                        )
+                        .await
+                    }
+                }
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+
+            if valid {
+                found.push(Loot::new(
+                    "rsync",
+                    &address,
+                    [
+                        ("module".to_owned(), module),
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("password".to_owned(), creds.password.to_owned()),
+                    ],
+                ));
+            }
+        }
+
+        Ok(if found.is_empty() { None } else { Some(found) })
+    }
+}
@@ -0,0 +1,174 @@
+use base64::Engine;
+use md5::{Digest, Md5};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::session::Error;
+
+const GREETING: &str = "@RSYNCD: ";
+// from protocol 30 the challenge response is MD5 based
+const CLIENT_VERSION: &str = "@RSYNCD: 30.0\n";
+const MAX_LINES: usize = 1024;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Access {
+    Open,
+    // challenge to authenticate with
+    Protected(String),
+}
+
+async fn read_line<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<String, Error> {
+    let mut line = String::new();
+    if reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?
+        == 0
+    {
+        return Err("connection closed".to_owned());
+    }
+    Ok(line.trim_end().to_owned())
+}
+
+async fn write_line<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    line: &str,
+) -> Result<(), Error> {
+    reader
+        .get_mut()
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// exchanges the protocol versions, returns the server one
+pub(crate) async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+) -> Result<String, Error> {
+    let line = read_line(reader).await?;
+    let version = line
+        .strip_prefix(GREETING)
+        .ok_or_else(|| format!("not a rsync daemon: {}", line))?;
+    let version = version.split_whitespace().next().unwrap_or_default().to_owned();
+    write_line(reader, CLIENT_VERSION).await?;
+    Ok(version)
+}
+
+// module names and comments
+pub(crate) async fn list_modules<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+) -> Result<Vec<(String, String)>, Error> {
+    write_line(reader, "#list\n").await?;
+    let mut modules = vec![];
+    for _ in 0..MAX_LINES {
+        let line = read_line(reader).await?;
+        if line.starts_with("@RSYNCD: EXIT") {
+            return Ok(modules);
+        } else if let Some(error) = line.strip_prefix("@ERROR") {
+            return Err(error.trim_start_matches(':').trim().to_owned());
+        } else if line.starts_with('@') || line.is_empty() {
+            continue;
+        }
+        let (name, comment) = line.split_once('\t').unwrap_or((&line, ""));
+        modules.push((name.trim().to_owned(), comment.trim().to_owned()));
+    }
+    Err("too many rsync modules".to_owned())
+}
+
+pub(crate) async fn select_module<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    module: &str,
+) -> Result<Access, Error> {
+    write_line(reader, &format!("{}\n", module)).await?;
+    // skip the message of the day
+    for _ in 0..MAX_LINES {
+        let line = read_line(reader).await?;
+        if line.starts_with("@RSYNCD: OK") {
+            return Ok(Access::Open);
+        } else if let Some(challenge) = line.strip_prefix("@RSYNCD: AUTHREQD ") {
+            return Ok(Access::Protected(challenge.trim().to_owned()));
+        } else if let Some(error) = line.strip_prefix("@ERROR") {
+            return Err(error.trim_start_matches(':').trim().to_owned());
+        }
+    }
+    Err("unexpected rsync response".to_owned())
+}
+
+pub(crate) fn challenge_response(password: &str, challenge: &str) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(password.as_bytes());
+    hasher.update(challenge.as_bytes());
+    base64::engine::general_purpose::STANDARD_NO_PAD.encode(hasher.finalize())
+}
+
+// returns true if the credentials are valid for the selected module
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    challenge: &str,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    write_line(
+        reader,
+        &format!("{} {}\n", username, challenge_response(password, challenge)),
+    )
+    .await?;
+
+    let line = read_line(reader).await?;
+    if line.starts_with("@RSYNCD: OK") {
+        Ok(true)
+    } else if line.starts_with("@ERROR") && line.contains("auth failed") {
+        Ok(false)
+    } else {
+        Err(format!("unexpected rsync response: {}", line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::{authenticate, challenge_response, handshake, list_modules, select_module, Access};
+
+    #[tokio::test]
+    async fn can_list_modules() {
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .read(b"@RSYNCD: 31.0 sha512 sha256 sha1 md5 md4\n")
+                .write(b"@RSYNCD: 30.0\n")
+                .write(b"#list\n")
+                .read(b"backup         \tNightly backups\npublic\t\n@RSYNCD: EXIT\n")
+                .build(),
+        );
+        assert_eq!(handshake(&mut reader).await, Ok("31.0".to_owned()));
+        assert_eq!(
+            list_modules(&mut reader).await,
+            Ok(vec![
+                ("backup".to_owned(), "Nightly backups".to_owned()),
+                ("public".to_owned(), "".to_owned())
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let response = challenge_response("secret", "nonce");
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .write(b"backup\n")
+                .read(b"Welcome!\n@RSYNCD: AUTHREQD nonce\n")
+                .write(format!("admin {}\n", &response).as_bytes())
+                .read(b"@ERROR: auth failed on module backup\n")
+                .build(),
+        );
+        assert_eq!(
+            select_module(&mut reader, "backup").await,
+            Ok(Access::Protected("nonce".to_owned()))
+        );
+        assert_eq!(
+            authenticate(&mut reader, "nonce", "admin", "secret").await,
+            Ok(false)
+        );
+        // 22 characters, unpadded
+        assert_eq!(response.len(), 22);
+    }
+}
@@ -0,0 +1,167 @@
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::session::Error;
+use crate::utils::http_auth::Challenge;
+
+const MAX_HEADERS: usize = 64;
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn challenge(&self) -> Option<Challenge> {
+        Challenge::parse(
+            self.headers
+                .iter()
+                .filter(|(k, _)| k.eq_ignore_ascii_case("www-authenticate"))
+                .map(|(_, v)| v.as_str()),
+        )
+    }
+
+    // SDP session description in the body, if any
+    pub fn sdp(&self) -> Option<String> {
+        let body = String::from_utf8_lossy(&self.body);
+        if body.starts_with("v=0") {
+            Some(body.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) async fn describe<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    uri: &str,
+    cseq: usize,
+    authorization: Option<&str>,
+) -> Result<Response, Error> {
+    let mut request = format!(
+        "DESCRIBE {} RTSP/1.0\r\nCSeq: {}\r\nUser-Agent: legba\r\nAccept: application/sdp\r\n",
+        uri, cseq
+    );
+    if let Some(authorization) = authorization {
+        request += &format!("Authorization: {}\r\n", authorization);
+    }
+    request += "\r\n";
+    reader
+        .get_mut()
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = line
+        .strip_prefix("RTSP/1.0 ")
+        .and_then(|s| s.get(..3))
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| format!("invalid RTSP response: {}", line.trim_end()))?;
+
+    let mut headers = vec![];
+    loop {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            return Err("connection closed".to_owned());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() >= MAX_HEADERS {
+            return Err("too many RTSP headers".to_owned());
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+
+    let mut response = Response {
+        status,
+        headers,
+        body: vec![],
+    };
+    let size = response
+        .header("content-length")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(0);
+    if size > MAX_BODY_SIZE {
+        return Err(format!("invalid RTSP body size {}", size));
+    }
+    response.body = vec![0u8; size];
+    reader
+        .read_exact(&mut response.body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::{describe, Challenge, Response};
+
+    #[test]
+    fn can_parse_challenges() {
+        let response = Response {
+            status: 401,
+            headers: vec![
+                ("WWW-Authenticate".to_owned(), "Basic realm=\"IPCAM\"".to_owned()),
+                (
+                    "WWW-Authenticate".to_owned(),
+                    "Digest realm=\"IPCAM\", nonce=\"abc123\"".to_owned(),
+                ),
+            ],
+            body: vec![],
+        };
+        assert_eq!(
+            response.challenge(),
+            Some(Challenge::Digest {
+                realm: "IPCAM".to_owned(),
+                nonce: "abc123".to_owned(),
+                opaque: None,
+                qop: false,
+            })
+        );
+        assert_eq!(
+            Challenge::Basic.authorization("DESCRIBE", "rtsp://cam/", "admin", "12345"),
+            "Basic YWRtaW46MTIzNDU="
+        );
+    }
+
+    #[tokio::test]
+    async fn can_describe() {
+        let mut reader = BufReader::new(
+            tokio_test::io::Builder::new()
+                .write(b"DESCRIBE rtsp://cam:554/ RTSP/1.0\r\nCSeq: 1\r\nUser-Agent: legba\r\nAccept: application/sdp\r\n\r\n")
+                .read(b"RTSP/1.0 200 OK\r\nCSeq: 1\r\nContent-Type: application/sdp\r\nContent-Length: 13\r\n\r\nv=0\r\ns=Live\r\n")
+                .build(),
+        );
+        let response = describe(&mut reader, "rtsp://cam:554/", 1, None)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.sdp(), Some("v=0\r\ns=Live\r\n".to_owned()));
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::BufReader;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+use crate::utils::once::OncePerTarget;
+
+use self::client::Response;
+
+mod client;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "rtsp" => Rtsp::new()
+}
+
+// session name of the SDP description
+fn session_name(response: &Response) -> Option<String> {
+    response
+        .sdp()?
+        .lines()
+        .find_map(|line| line.strip_prefix("s=").map(|s| s.trim().to_owned()))
+        .filter(|s| !s.is_empty() && s != "-")
+}
+
+#[derive(Clone)]
+pub(crate) struct Rtsp {
+    path: String,
+    verify_sdp: bool,
+    // streams that don't require authentication
+    no_auth: OncePerTarget,
+}
+
+impl Rtsp {
+    pub fn new() -> Self {
+        Rtsp {
+            path: String::new(),
+            verify_sdp: false,
+            no_auth: OncePerTarget::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Rtsp {
+    fn description(&self) -> &'static str {
+        "RTSP basic and digest authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.path = if opts.rtsp.rtsp_path.starts_with('/') {
+            opts.rtsp.rtsp_path.clone()
+        } else {
+            format!("/{}", &opts.rtsp.rtsp_path)
+        };
+        self.verify_sdp = opts.rtsp.rtsp_verify_sdp;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 554)?;
+        let uri = format!("rtsp://{}{}", &address, &self.path);
+        if self.no_auth.contains(&uri) {
+            return Ok(None);
+        }
+
+        let stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let mut reader = BufReader::new(stream);
+
+        tokio::time::timeout(timeout, async {
+            let response = client::describe(&mut reader, &uri, 1, None).await?;
+            if response.status == 200 {
+                // report once, any credential would be accepted
+                if !self.no_auth.insert(&uri) {
+                    return Ok(None);
+                }
+                let mut data = vec![
+                    ("path".to_owned(), self.path.to_owned()),
+                    ("authentication".to_owned(), "none".to_owned()),
+                ];
+                if let Some(name) = session_name(&response) {
+                    data.push(("session".to_owned(), name));
+                }
+                return Ok(Some(vec![Loot::new("rtsp", &address, data)]));
+            } else if response.status != 401 {
+                return Err(format!("unexpected RTSP status {} for {}", response.status, &uri));
+            }
+
+            let challenge = response
+                .challenge()
+                .ok_or_else(|| "no supported authentication scheme".to_owned())?;
+            let authorization =
+                challenge.authorization("DESCRIBE", &uri, &creds.username, &creds.password);
+            let response = client::describe(&mut reader, &uri, 2, Some(&authorization)).await?;
+
+            match response.status {
+                200 => {
+                    if self.verify_sdp && response.sdp().is_none() {
+                        return Ok(None);
+                    }
+                    let mut data = vec![
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("password".to_owned(), creds.password.to_owned()),
+                        ("path".to_owned(), self.path.to_owned()),
+                        ("authentication".to_owned(), challenge.scheme().to_owned()),
+                    ];
+                    if let Some(name) = session_name(&response) {
+                        data.push(("session".to_owned(), name));
+                    }
+                    Ok(Some(vec![Loot::new("rtsp", &address, data)]))
+                }
+                401 | 403 => Ok(None),
+                // a wrong path, the credentials can't be verified
+                status => Err(format!("unexpected RTSP status {} for {}", status, &uri)),
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "/")]
+    /// Path of the RTSP stream to DESCRIBE.
+    pub rtsp_path: String,
+    #[clap(long, default_value_t = false)]
+    /// Only consider a credential valid if the server returns a SDP session description.
+    pub rtsp_verify_sdp: bool,
+}
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
+use std::ffi::{c_int, c_void};
 use std::sync::LazyLock;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use pavao::{SmbClient, SmbCredentials, SmbDirentType, SmbOptions};
+use pavao::{SmbClient, SmbCredentials, SmbDirentType, SmbMode, SmbOptions};
+use rand::Rng;
 use tokio::sync::Mutex;
 
 use crate::creds::Credentials;
 use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
 use crate::Plugin;
 use crate::{utils, Options};
 
@@ -15,8 +18,16 @@ pub(crate) mod options;
 
 static SHARE_CACHE: LazyLock<Mutex<HashMap<String, String>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
+extern "C" {
+    // not exposed by pavao
+    fn smbc_setOptionUseNTHash(c: *mut c_void, b: c_int);
+}
+
 static PAVAO_LOCK: Mutex<()> = Mutex::const_new(());
 
+// administrative shares that allow remote code execution when writable
+const ADMIN_SHARES: [&str; 2] = ["ADMIN$", "C$"];
+
 super::manager::register_plugin! {
     "smb" => SMB::new()
 }
@@ -25,6 +36,12 @@ super::manager::register_plugin! {
 pub(crate) struct SMB {
     share: Option<String>,
     workgroup: String,
+    null_session: bool,
+    skip_guest: bool,
+    // targets already checked for null session and guest access
+    checked: OncePerTarget,
+    // targets allowing null session or guest access, no need to test them
+    skip: OncePerTarget,
 }
 
 impl SMB {
@@ -32,6 +49,10 @@ impl SMB {
         SMB {
             share: None,
             workgroup: String::default(),
+            null_session: false,
+            skip_guest: false,
+            checked: OncePerTarget::new(),
+            skip: OncePerTarget::new(),
         }
     }
 
@@ -57,6 +78,87 @@ impl SMB {
         .map_err(|e| format!("error creating client for {}: {}", share, e))
     }
 
+    // readable shares and writable administrative shares for valid credentials
+    fn enumerate_shares(
+        &self,
+        server: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(Vec<String>, Vec<String>), Error> {
+        let root_cli = self.get_samba_client(server, &self.workgroup, "", username, password)?;
+        let entries = root_cli
+            .list_dir("")
+            .map_err(|e| format!("can't list shares of {}: {}", server, e))?;
+
+        let mut readable = vec![];
+        let mut writable = vec![];
+        for entry in entries {
+            if !matches!(
+                entry.get_type(),
+                SmbDirentType::FileShare | SmbDirentType::Dir
+            ) {
+                continue;
+            }
+
+            let name = entry.name().to_owned();
+            let share = format!("/{}", &name);
+            let cli = self.get_samba_client(server, &self.workgroup, &share, username, password)?;
+            if cli.list_dir("").is_err() {
+                continue;
+            }
+
+            if ADMIN_SHARES.contains(&name.to_uppercase().as_str()) {
+                // create and remove a random directory to check for write access
+                let probe = format!("/legba-{}", hex::encode(rand::thread_rng().gen::<[u8; 4]>()));
+                if cli.mkdir(&probe, SmbMode::from(0o755)).is_ok() {
+                    if let Err(e) = cli.rmdir(&probe) {
+                        log::warn!("could not remove {}{}{}: {}", server, &share, &probe, e);
+                    }
+                    writable.push(name.clone());
+                }
+            }
+
+            readable.push(name);
+        }
+
+        Ok((readable, writable))
+    }
+
+    // null session and guest access, must be called while holding PAVAO_LOCK
+    fn check_null_session(&self, address: &str, server: &str) -> Result<Vec<Loot>, Error> {
+        let mut loot = vec![];
+        for (access, username) in [("null_session", ""), ("guest", "guest")] {
+            let client = self.get_samba_client(server, &self.workgroup, "", username, "")?;
+            let ctx = client.ctx().map_err(|e| e.to_string())?;
+            unsafe { smbc_setOptionUseNTHash(ctx as *mut c_void, 0) };
+
+            if let Ok(entries) = client.list_dir("") {
+                log::info!("{} allows {} access", server, access);
+                let shares: Vec<String> = entries
+                    .iter()
+                    .filter(|e| {
+                        matches!(e.get_type(), SmbDirentType::FileShare | SmbDirentType::Dir)
+                    })
+                    .map(|e| e.name().to_owned())
+                    .collect();
+                loot.push(Loot::new(
+                    "smb",
+                    address,
+                    [
+                        ("access".to_owned(), access.to_owned()),
+                        ("shares".to_owned(), shares.join(", ")),
+                    ],
+                ));
+            }
+        }
+
+        if self.skip_guest && !loot.is_empty() {
+            self.skip.insert(address);
+        }
+
+        Ok(loot)
+    }
+
     async fn get_share_for(&self, target: &str) -> Result<String, Error> {
         if let Some(share) = self.share.as_ref() {
             // return from arguments
@@ -106,12 +208,14 @@ impl SMB {
 #[async_trait]
 impl Plugin for SMB {
     fn description(&self) -> &'static str {
-        "Samba password authentication."
+        "Samba password and pass-the-hash authentication."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.share = opts.smb.smb_share.clone();
         self.workgroup = opts.smb.smb_workgroup.clone();
+        self.null_session = opts.smb.smb_null_session;
+        self.skip_guest = opts.smb.smb_skip_guest;
         Ok(())
     }
 
@@ -122,6 +226,21 @@ impl Plugin for SMB {
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 445)?;
         let server = format!("smb://{}", &address);
+
+        let mut found = vec![];
+        if self.null_session && !self.checked.contains(&address) {
+            let _guard = PAVAO_LOCK.lock().await;
+            // another worker might have checked it while we were waiting for the lock
+            if !self.checked.contains(&address) {
+                found = self.check_null_session(&address, &server)?;
+                // marked only once the check completed, so transient errors are checked again
+                self.checked.insert(&address);
+            }
+        }
+        if self.skip.contains(&address) {
+            return Ok(if found.is_empty() { None } else { Some(found) });
+        }
+
         let share = tokio::time::timeout(timeout, self.get_share_for(&address))
             .await
             .map_err(|e: tokio::time::error::Elapsed| e.to_string())?
@@ -131,25 +250,45 @@ impl Plugin for SMB {
         // Sadly this decreases performances, but it appears that there are no alternatives
         // for rust :/
         let _guard = PAVAO_LOCK.lock().await;
+        // for ntlm:LM:NT payloads pass the NT hash to libsmbclient instead of the password
+        let hash = creds.ntlm_hash()?;
         let client = self.get_samba_client(
             &server,
             &self.workgroup,
             &share,
             &creds.username,
-            &creds.password,
+            hash.as_deref().unwrap_or(&creds.password),
         )?;
+        // the context is shared, so the option must be reset for cleartext passwords
+        let ctx = client.ctx().map_err(|e| e.to_string())?;
+        unsafe { smbc_setOptionUseNTHash(ctx as *mut c_void, hash.is_some() as c_int) };
+
+        if client.list_dir("/").is_err() {
+            return Ok(if found.is_empty() { None } else { Some(found) });
+        }
+
+        let mut loot = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+
+        // best effort, a valid credential is reported anyway
+        match self.enumerate_shares(
+            &server,
+            &creds.username,
+            hash.as_deref().unwrap_or(&creds.password),
+        ) {
+            Ok((readable, writable)) => {
+                loot.push(("shares".to_owned(), readable.join(", ")));
+                loot.push(("admin".to_owned(), (!writable.is_empty()).to_string()));
+                if !writable.is_empty() {
+                    loot.push(("writable_admin_shares".to_owned(), writable.join(", ")));
+                }
+            }
+            Err(e) => log::debug!("{}", e),
+        }
 
-        return if client.list_dir("/").is_ok() {
-            Ok(Some(vec![Loot::new(
-                "smb",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]))
-        } else {
-            Ok(None)
-        };
+        found.push(Loot::new("smb", &address, loot));
+        Ok(Some(found))
     }
 }
@@ -10,4 +10,10 @@ pub(crate) struct Options {
     #[clap(long, default_value = "IPC$", help_heading = "SMB")]
     /// Explicitly set Samba private share to test.
     pub smb_share: Option<String>,
+    #[clap(long, default_value_t = false, help_heading = "SMB")]
+    /// Check every target for null session and guest access before testing credentials.
+    pub smb_null_session: bool,
+    #[clap(long, default_value_t = false, help_heading = "SMB")]
+    /// Skip credentials testing on targets allowing null session or guest access.
+    pub smb_skip_guest: bool,
 }
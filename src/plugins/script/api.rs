@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::Engine as _;
+use rhai::{Blob, Dynamic, Engine, EvalAltResult, Map};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use crate::utils;
+use crate::utils::net::StreamLike;
+
+const MAX_READ_SIZE: usize = 64 * 1024;
+
+type Result<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+fn error(e: impl ToString) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+// scripts run on a blocking thread of the runtime, so the async primitives can be awaited
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Handle::current().block_on(future)
+}
+
+// a tcp or tls connection, each read and write is bound by the session timeout
+#[derive(Clone)]
+pub(crate) struct Connection {
+    stream: Arc<Mutex<Box<dyn StreamLike>>>,
+    timeout: Duration,
+}
+
+impl Connection {
+    fn open(address: &str, default_port: u16, ssl: bool, timeout: Duration) -> Result<Self> {
+        let address = utils::parse_target_address(address, default_port).map_err(error)?;
+        let stream = block_on(utils::net::async_tcp_stream(&address, timeout, ssl)).map_err(error)?;
+        Ok(Self {
+            stream: Arc::new(Mutex::new(stream)),
+            timeout,
+        })
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<()> {
+        block_on(async {
+            let mut stream = self.stream.lock().await;
+            tokio::time::timeout(self.timeout, stream.write_all(data))
+                .await
+                .map_err(error)?
+                .map_err(error)
+        })
+    }
+
+    // empty on timeout or once the server closed the connection
+    fn recv(&mut self) -> Result<Blob> {
+        block_on(async {
+            let mut stream = self.stream.lock().await;
+            let mut buffer = vec![0u8; MAX_READ_SIZE];
+            match tokio::time::timeout(self.timeout, stream.read(&mut buffer)).await {
+                Ok(read) => {
+                    buffer.truncate(read.map_err(error)?);
+                    Ok(buffer)
+                }
+                Err(_) => Ok(vec![]),
+            }
+        })
+    }
+
+    fn recv_until(&mut self, delimiter: &str) -> Result<String> {
+        let mut data = vec![];
+        while data.len() < MAX_READ_SIZE {
+            let read = self.recv()?;
+            if read.is_empty() {
+                break;
+            }
+            data.extend(read);
+            if data
+                .windows(delimiter.len().max(1))
+                .any(|w| w == delimiter.as_bytes())
+            {
+                break;
+            }
+        }
+        Ok(String::from_utf8_lossy(&data).to_string())
+    }
+}
+
+fn http_request(
+    client: &reqwest::Client,
+    method: &str,
+    url: &str,
+    headers: Map,
+    body: &str,
+) -> Result<Map> {
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).map_err(error)?;
+    let mut request = client.request(method, url);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.to_string());
+    }
+    if !body.is_empty() {
+        request = request.body(body.to_owned());
+    }
+
+    block_on(async {
+        let response = request.send().await.map_err(error)?;
+
+        let mut response_headers = Map::new();
+        for (name, value) in response.headers() {
+            response_headers.insert(
+                name.as_str().into(),
+                String::from_utf8_lossy(value.as_bytes()).to_string().into(),
+            );
+        }
+
+        let mut map = Map::new();
+        map.insert("status".into(), (response.status().as_u16() as i64).into());
+        map.insert("headers".into(), response_headers.into());
+        map.insert(
+            "body".into(),
+            response.text().await.map_err(error)?.into(),
+        );
+        Ok(map)
+    })
+}
+
+// the sandboxed engine: no modules, no eval, bounded resources and only these primitives
+pub(crate) fn engine(timeout: Duration, max_operations: u64) -> std::result::Result<Engine, String> {
+    let mut engine = Engine::new();
+
+    engine
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .set_max_operations(max_operations)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1024 * 1024)
+        .set_max_array_size(64 * 1024)
+        .set_max_map_size(4096);
+    engine.disable_symbol("eval");
+    engine.on_print(|text| log::info!("{}", text));
+    engine.on_debug(|text, _, _| log::debug!("{}", text));
+
+    // connections
+    engine
+        .register_type_with_name::<Connection>("Connection")
+        .register_fn("connect", move |address: &str| {
+            Connection::open(address, 0, false, timeout)
+        })
+        .register_fn("connect", move |address: &str, port: i64| {
+            Connection::open(address, port as u16, false, timeout)
+        })
+        .register_fn("connect_tls", move |address: &str| {
+            Connection::open(address, 0, true, timeout)
+        })
+        .register_fn("connect_tls", move |address: &str, port: i64| {
+            Connection::open(address, port as u16, true, timeout)
+        })
+        .register_fn("send", |conn: &mut Connection, data: &str| {
+            conn.send(data.as_bytes())
+        })
+        .register_fn("send", |conn: &mut Connection, data: Blob| conn.send(&data))
+        .register_fn("recv", |conn: &mut Connection| {
+            conn.recv()
+                .map(|data| String::from_utf8_lossy(&data).to_string())
+        })
+        .register_fn("recv_bytes", |conn: &mut Connection| conn.recv())
+        .register_fn("recv_until", |conn: &mut Connection, delimiter: &str| {
+            conn.recv_until(delimiter)
+        });
+
+    // http
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .danger_accept_invalid_certs(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let with_body = client.clone();
+    engine
+        .register_fn("http", move |method: &str, url: &str| {
+            http_request(&client, method, url, Map::new(), "")
+        })
+        .register_fn(
+            "http",
+            move |method: &str, url: &str, headers: Map, body: &str| {
+                http_request(&with_body, method, url, headers, body)
+            },
+        );
+
+    // encoding helpers
+    engine
+        .register_fn("base64_encode", |data: &str| {
+            base64::engine::general_purpose::STANDARD.encode(data)
+        })
+        .register_fn("base64_decode", |data: &str| -> Result<Blob> {
+            base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(error)
+        })
+        .register_fn("hex_decode", |data: &str| -> Result<Blob> {
+            let digits: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            (0..digits.len())
+                .step_by(2)
+                .map(|i| {
+                    digits
+                        .get(i..i + 2)
+                        .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                        .ok_or_else(|| error(format!("invalid hex string '{}'", data)))
+                })
+                .collect()
+        });
+
+    Ok(engine)
+}
+
+// loot data from the value returned by the script, None if the attempt failed
+pub(crate) fn outcome(value: Dynamic) -> std::result::Result<Option<BTreeMap<String, String>>, String> {
+    if value.is_unit() {
+        Ok(None)
+    } else if let Some(valid) = value.clone().try_cast::<bool>() {
+        Ok(valid.then(BTreeMap::new))
+    } else if let Some(map) = value.try_cast::<Map>() {
+        Ok(Some(
+            map.into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        ))
+    } else {
+        Err("attempt must return a bool, a map or nothing".to_owned())
+    }
+}
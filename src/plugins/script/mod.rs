@@ -0,0 +1,208 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Options;
+use crate::Plugin;
+
+mod api;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "script" => Script::new()
+}
+
+const ENTRY_POINT: &str = "attempt";
+
+#[derive(Clone)]
+pub(crate) struct Script {
+    engine: Arc<Engine>,
+    ast: Arc<AST>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Script {
+            engine: Arc::new(Engine::new_raw()),
+            ast: Arc::new(AST::empty()),
+        }
+    }
+
+    fn compile(&mut self, source: &str, timeout: Duration, max_operations: u64) -> Result<(), Error> {
+        let engine = api::engine(timeout, max_operations)?;
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == ENTRY_POINT && f.params.len() == 3)
+        {
+            return Err(format!(
+                "the script must define a {}(target, username, password) function",
+                ENTRY_POINT
+            ));
+        }
+
+        self.engine = Arc::new(engine);
+        self.ast = Arc::new(ast);
+        Ok(())
+    }
+
+    async fn run(&self, creds: &Credentials) -> Result<Dynamic, Error> {
+        let engine = self.engine.clone();
+        let ast = self.ast.clone();
+        let args = (
+            creds.target.to_owned(),
+            creds.username.to_owned(),
+            creds.password.to_owned(),
+        );
+
+        // the engine is synchronous, its primitives block on the runtime
+        tokio::task::spawn_blocking(move || {
+            engine
+                .call_fn::<Dynamic>(&mut Scope::new(), &ast, ENTRY_POINT, args)
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+}
+
+#[async_trait]
+impl Plugin for Script {
+    fn description(&self) -> &'static str {
+        "Custom protocols implemented as Rhai scripts."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        let path = opts
+            .script
+            .script
+            .as_ref()
+            .ok_or("no --script provided".to_owned())?;
+        let source =
+            std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+
+        self.compile(
+            &source,
+            Duration::from_millis(opts.timeout),
+            opts.script.script_max_operations,
+        )
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        _timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let Some(data) = api::outcome(self.run(creds).await?)? else {
+            return Ok(None);
+        };
+
+        let mut loot_data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        loot_data.extend(data);
+
+        Ok(Some(vec![Loot::new("script", &creds.target, loot_data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::Script;
+    use crate::creds::Credentials;
+    use crate::Plugin;
+
+    const SCRIPT: &str = r#"
+        fn attempt(target, username, password) {
+            let conn = connect(target);
+            conn.recv_until("\n");
+            conn.send(username + ":" + password + "\n");
+            let reply = conn.recv_until("\n");
+            if reply.starts_with("OK") {
+                let role = reply.sub_string(3);
+                role.trim();
+                #{ role: role }
+            } else {
+                false
+            }
+        }
+    "#;
+
+    fn creds(target: &str, password: &str) -> Credentials {
+        Credentials {
+            target: target.to_owned(),
+            username: "admin".to_owned(),
+            password: password.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn requires_entry_point() {
+        let mut script = Script::new();
+        assert!(script
+            .compile("fn login(u, p) { true }", Duration::from_secs(1), 1000)
+            .is_err());
+        assert!(script
+            .compile("fn attempt(t, u, p) { true ", Duration::from_secs(1), 1000)
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn can_run_scripts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                stream.write_all(b"HELLO\n").await.unwrap();
+                let mut buffer = [0u8; 128];
+                let read = stream.read(&mut buffer).await.unwrap();
+                let reply: &[u8] = if &buffer[..read] == b"admin:secret\n" {
+                    b"OK operator\n"
+                } else {
+                    b"ERR\n"
+                };
+                stream.write_all(reply).await.unwrap();
+            }
+        });
+
+        let mut script = Script::new();
+        script
+            .compile(SCRIPT, Duration::from_secs(5), 100000)
+            .unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            script.attempt(&creds(&target, "wrong"), timeout).await,
+            Ok(None)
+        );
+
+        let loot = script
+            .attempt(&creds(&target, "secret"), timeout)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loot[0].get_value("password"), Some("secret"));
+        assert_eq!(loot[0].get_value("role"), Some("operator"));
+
+        // runaway scripts are stopped
+        script
+            .compile(
+                "fn attempt(t, u, p) { loop {} }",
+                Duration::from_secs(1),
+                1000,
+            )
+            .unwrap();
+        assert!(script.attempt(&creds(&target, "x"), timeout).await.is_err());
+    }
+}
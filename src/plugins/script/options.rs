@@ -0,0 +1,14 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long)]
+    /// Rhai script defining an attempt(target, username, password) function returning true, false or a map of loot data.
+    pub script: Option<String>,
+
+    #[clap(long, default_value_t = 1000000)]
+    /// Maximum number of operations a script can run for each attempt.
+    pub script_max_operations: u64,
+}
@@ -39,7 +39,7 @@ impl Plugin for Scylla {
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address: String = utils::parse_target_address(&creds.target, 9042)?;
         let session = scylla::SessionBuilder::new()
-            .known_node(&address)
+            .known_node(utils::resolver::resolve_address(&address))
             .connection_timeout(timeout)
             .user(&creds.username, &creds.password)
             .build()
@@ -0,0 +1,151 @@
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::session::Error;
+
+use super::options::Enumeration;
+
+const MAX_REPLY_LINES: usize = 256;
+
+// status code and last line of a single or multi line reply
+async fn read_reply<S: AsyncRead + Unpin>(
+    reader: &mut BufReader<S>,
+) -> Result<(u32, String), Error> {
+    for _ in 0..MAX_REPLY_LINES {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| e.to_string())?
+            == 0
+        {
+            return Err("connection closed".to_owned());
+        }
+        let code = line
+            .get(..3)
+            .and_then(|c| c.parse::<u32>().ok())
+            .ok_or_else(|| format!("invalid SMTP reply: {}", line.trim_end()))?;
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, line.trim_end().to_owned()));
+        }
+    }
+    Err("SMTP reply too long".to_owned())
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    command: &str,
+) -> Result<(u32, String), Error> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    read_reply(reader).await
+}
+
+// returns true if the server reports the mailbox as existing
+pub(crate) async fn probe<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    mode: Enumeration,
+    username: &str,
+    from: &str,
+    domain: Option<&str>,
+) -> Result<bool, Error> {
+    let mut reader = BufReader::new(stream);
+
+    let (code, line) = read_reply(&mut reader).await?;
+    if code != 220 {
+        return Err(format!("unexpected SMTP greeting: {}", line));
+    }
+    if command(&mut reader, "EHLO legba").await?.0 != 250 {
+        command(&mut reader, "HELO legba").await?;
+    }
+
+    let (code, line) = match mode {
+        Enumeration::Vrfy => command(&mut reader, &format!("VRFY {}", username)).await?,
+        Enumeration::Expn => command(&mut reader, &format!("EXPN {}", username)).await?,
+        Enumeration::Rcpt => {
+            let (code, line) = command(&mut reader, &format!("MAIL FROM:<{}>", from)).await?;
+            if code != 250 {
+                return Err(format!("MAIL FROM rejected: {}", line));
+            }
+            let recipient = match domain {
+                Some(domain) if !username.contains('@') => format!("{}@{}", username, domain),
+                _ => username.to_owned(),
+            };
+            command(&mut reader, &format!("RCPT TO:<{}>", recipient)).await?
+        }
+    };
+    let _ = command(&mut reader, "QUIT").await;
+
+    match code {
+        250 | 251 => Ok(true),
+        // mailbox unavailable, not local or name not allowed
+        550 | 551 | 553 => Ok(false),
+        // 252 (cannot verify), 500 and 502 (command disabled) or transient errors
+        _ => Err(line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::probe;
+    use crate::plugins::smtp::options::Enumeration;
+
+    #[tokio::test]
+    async fn can_enumerate_with_vrfy() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"220 mail.example.com ESMTP\r\n")
+            .write(b"EHLO legba\r\n")
+            .read(b"250-mail.example.com\r\n250 VRFY\r\n")
+            .write(b"VRFY root\r\n")
+            .read(b"252 2.0.0 root\r\n")
+            .write(b"QUIT\r\n")
+            .read(b"221 Bye\r\n")
+            .build();
+        assert_eq!(
+            probe(stream, Enumeration::Vrfy, "root", "", None).await,
+            Err("252 2.0.0 root".to_owned())
+        );
+
+        let stream = tokio_test::io::Builder::new()
+            .read(b"220 mail.example.com ESMTP\r\n")
+            .write(b"EHLO legba\r\n")
+            .read(b"250 mail.example.com\r\n")
+            .write(b"VRFY nobody\r\n")
+            .read(b"550 5.1.1 <nobody>: Recipient address rejected\r\n")
+            .write(b"QUIT\r\n")
+            .read(b"221 Bye\r\n")
+            .build();
+        assert_eq!(
+            probe(stream, Enumeration::Vrfy, "nobody", "", None).await,
+            Ok(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn can_enumerate_with_rcpt() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"220 mail.example.com ESMTP\r\n")
+            .write(b"EHLO legba\r\n")
+            .read(b"250 mail.example.com\r\n")
+            .write(b"MAIL FROM:<legba@example.com>\r\n")
+            .read(b"250 2.1.0 Ok\r\n")
+            .write(b"RCPT TO:<admin@example.com>\r\n")
+            .read(b"250 2.1.5 Ok\r\n")
+            .write(b"QUIT\r\n")
+            .read(b"221 Bye\r\n")
+            .build();
+        assert_eq!(
+            probe(
+                stream,
+                Enumeration::Rcpt,
+                "admin",
+                "legba@example.com",
+                Some("example.com")
+            )
+            .await,
+            Ok(true)
+        );
+    }
+}
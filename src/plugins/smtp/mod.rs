@@ -11,6 +11,11 @@ use crate::Plugin;
 use crate::creds::Credentials;
 use crate::utils;
 
+use super::plugin::PayloadStrategy;
+
+use self::options::StartTls;
+
+mod enumeration;
 pub(crate) mod options;
 
 super::manager::register_plugin! {
@@ -20,12 +25,22 @@ super::manager::register_plugin! {
 #[derive(Clone)]
 pub(crate) struct SMTP {
     mechanism: authentication::Mechanism,
+    ssl: bool,
+    starttls: StartTls,
+    enumeration: Option<options::Enumeration>,
+    enum_from: String,
+    enum_domain: Option<String>,
 }
 
 impl SMTP {
     pub fn new() -> Self {
         SMTP {
             mechanism: authentication::Mechanism::Plain,
+            ssl: false,
+            starttls: StartTls::Off,
+            enumeration: None,
+            enum_from: String::new(),
+            enum_domain: None,
         }
     }
 }
@@ -33,7 +48,15 @@ impl SMTP {
 #[async_trait]
 impl Plugin for SMTP {
     fn description(&self) -> &'static str {
-        "SMTP password authentication."
+        "SMTP password authentication and user enumeration."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.enumeration.is_some() {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
@@ -45,6 +68,14 @@ impl Plugin for SMTP {
                 return Err(format!("'{}' is not a valid authentication mechanism, only PLAIN., LOGIN or XOAUTH2 are accepted.", &opts.smtp.smtp_mechanism));
             }
         };
+        self.ssl = opts.smtp.smtp_ssl;
+        self.starttls = opts.smtp.smtp_starttls;
+        if self.ssl && self.starttls != StartTls::Off {
+            return Err("--smtp-ssl and --smtp-starttls can't be used together".to_owned());
+        }
+        self.enumeration = opts.smtp.smtp_enum;
+        self.enum_from = opts.smtp.smtp_enum_from.clone();
+        self.enum_domain = opts.smtp.smtp_enum_domain.clone();
 
         Ok(())
     }
@@ -54,8 +85,38 @@ impl Plugin for SMTP {
         creds: &Credentials,
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
-        let address = utils::parse_target_address(&creds.target, 25)?;
-        let stream = crate::utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let address =
+            utils::parse_target_address(&creds.target, if self.ssl { 465 } else { 25 })?;
+        let stream = crate::utils::net::async_tcp_stream(&address, timeout, self.ssl).await?;
+
+        if let Some(mode) = self.enumeration {
+            let username = creds.single();
+            let found = tokio::time::timeout(
+                timeout,
+                enumeration::probe(
+                    stream,
+                    mode,
+                    username,
+                    &self.enum_from,
+                    self.enum_domain.as_deref(),
+                ),
+            )
+            .await
+            .map_err(|e| e.to_string())??;
+
+            return Ok(if found {
+                Some(vec![Loot::new(
+                    "smtp",
+                    &address,
+                    [
+                        ("username".to_owned(), username.to_owned()),
+                        ("method".to_owned(), format!("{:?}", mode).to_uppercase()),
+                    ],
+                )])
+            } else {
+                None
+            });
+        }
 
         let client = SmtpClient::new();
         let mut transport =
@@ -64,16 +125,64 @@ impl Plugin for SMTP {
                 .map_err(|e: tokio::time::error::Elapsed| e.to_string())?
                 .map_err(|e| e.to_string())?;
 
+        if self.starttls != StartTls::Off {
+            match tokio::time::timeout(timeout, transport.starttls()).await {
+                Ok(Ok(stream)) => {
+                    let stream =
+                        utils::net::upgrade_tcp_stream_to_ssl(stream.into_inner(), timeout)
+                            .await?;
+                    // the greeting was already received before STARTTLS
+                    transport = tokio::time::timeout(
+                        timeout,
+                        SmtpTransport::new(
+                            SmtpClient::new().without_greeting(),
+                            BufStream::new(stream),
+                        ),
+                    )
+                    .await
+                    .map_err(|e: tokio::time::error::Elapsed| e.to_string())?
+                    .map_err(|e| e.to_string())?;
+                }
+                Ok(Err(e)) if self.starttls == StartTls::Required => {
+                    return Err(format!("STARTTLS failed: {}", e));
+                }
+                Err(e) => return Err(e.to_string()),
+                Ok(Err(e)) => {
+                    log::debug!("STARTTLS failed, continuing in cleartext: {}", e);
+                    // starttls consumed the transport, reconnect
+                    let stream =
+                        crate::utils::net::async_tcp_stream(&address, timeout, false).await?;
+                    transport = tokio::time::timeout(
+                        timeout,
+                        SmtpTransport::new(SmtpClient::new(), BufStream::new(stream)),
+                    )
+                    .await
+                    .map_err(|e: tokio::time::error::Elapsed| e.to_string())?
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+
         let credentials =
             authentication::Credentials::new(creds.username.clone(), creds.password.clone());
 
-        if transport.auth(self.mechanism, &credentials).await.is_ok() {
+        let result = tokio::time::timeout(timeout, transport.auth(self.mechanism, &credentials))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if result.is_ok() {
+            // for XOAUTH2 the password is an OAuth access token
+            let secret = if self.mechanism == authentication::Mechanism::Xoauth2 {
+                "token"
+            } else {
+                "password"
+            };
             Ok(Some(vec![Loot::new(
                 "smtp",
                 &address,
                 [
                     ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
+                    (secret.to_owned(), creds.password.to_owned()),
                 ],
             )]))
         } else {
@@ -1,10 +1,40 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Enumeration {
+    Vrfy,
+    Expn,
+    Rcpt,
+}
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum StartTls {
+    #[default]
+    Off,
+    Opportunistic,
+    Required,
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
 pub(crate) struct Options {
     /// SMTP authentication mechanism, can be PLAIN (RFC4616), LOGIN (obsolete but needed for some providers like office365) or XOAUTH2.
     #[clap(long, default_value = "PLAIN")]
     pub smtp_mechanism: String,
+    /// Connect over implicit TLS (SMTPS, default port 465).
+    #[clap(long, default_value_t = false)]
+    pub smtp_ssl: bool,
+    /// STARTTLS mode, opportunistic upgrades the connection if advertised by the server while required fails otherwise.
+    #[clap(long, value_enum, default_value_t = StartTls::Off)]
+    pub smtp_starttls: StartTls,
+    /// User enumeration mode: iterate the usernames and report the mailboxes accepted by VRFY, EXPN or RCPT TO.
+    #[clap(long, value_enum)]
+    pub smtp_enum: Option<Enumeration>,
+    /// Sender address used by the RCPT TO enumeration mode.
+    #[clap(long, default_value = "legba@example.com")]
+    pub smtp_enum_from: String,
+    /// Domain appended to the usernames without one in the RCPT TO enumeration mode.
+    #[clap(long)]
+    pub smtp_enum_domain: Option<String>,
 }
@@ -0,0 +1,164 @@
+use crate::session::Error;
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+
+pub(crate) const PDU_GET: u8 = 0xa0;
+pub(crate) const PDU_RESPONSE: u8 = 0xa2;
+pub(crate) const PDU_SET: u8 = 0xa3;
+pub(crate) const PDU_REPORT: u8 = 0xa8;
+
+fn length(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        vec![len as u8]
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|b| *b == 0)
+            .collect();
+        [vec![0x80 | bytes.len() as u8], bytes].concat()
+    }
+}
+
+pub(crate) fn tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    [vec![tag], length(value.len()), value.to_vec()].concat()
+}
+
+pub(crate) fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // minimal two's complement encoding
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(TAG_INTEGER, &bytes[start..])
+}
+
+pub(crate) fn octet_string(value: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, value)
+}
+
+pub(crate) fn null() -> Vec<u8> {
+    tlv(TAG_NULL, &[])
+}
+
+pub(crate) fn oid(arcs: &[u32]) -> Vec<u8> {
+    let mut value = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for arc in &arcs[2..] {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.insert(0, 0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        value.extend(chunk);
+    }
+    tlv(TAG_OID, &value)
+}
+
+pub(crate) fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+pub(crate) fn parse_integer(value: &[u8]) -> i64 {
+    let mut result: i64 = if value.first().is_some_and(|b| b & 0x80 != 0) {
+        -1
+    } else {
+        0
+    };
+    for b in value {
+        result = (result << 8) | *b as i64;
+    }
+    result
+}
+
+// sequential reader of the TLV elements of a buffer
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let b = *self.data.get(self.pos).ok_or("unexpected end of data")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    // read the next element, returning its tag and value
+    pub fn read(&mut self) -> Result<(u8, &'a [u8]), Error> {
+        let tag = self.byte()?;
+        let first = self.byte()?;
+        let len = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let mut len = 0usize;
+            for _ in 0..(first & 0x7f) {
+                len = (len << 8) | self.byte()? as usize;
+            }
+            len
+        };
+
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("invalid element length")?;
+        let value = &self.data[self.pos..end];
+        self.pos = end;
+        Ok((tag, value))
+    }
+
+    // read the next element and check its tag
+    pub fn expect(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        let (got, value) = self.read()?;
+        if got != tag {
+            return Err(format!("expected tag 0x{:02x}, got 0x{:02x}", tag, got));
+        }
+        Ok(value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{integer, oid, parse_integer, sequence, Reader, TAG_INTEGER, TAG_SEQUENCE};
+
+    #[test]
+    fn can_encode_and_decode() {
+        assert_eq!(integer(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(integer(128), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(integer(-1), vec![0x02, 0x01, 0xff]);
+        assert_eq!(
+            oid(&[1, 3, 6, 1, 2, 1, 1, 1, 0]),
+            vec![0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00]
+        );
+        assert_eq!(
+            oid(&[1, 3, 6, 1, 4, 1, 311]),
+            vec![0x06, 0x07, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37]
+        );
+
+        let data = sequence(&[integer(65507), integer(-2), vec![0x04; 200]]);
+        let mut reader = Reader::new(&data);
+        let inner = reader.expect(TAG_SEQUENCE).unwrap();
+        assert!(reader.is_empty());
+
+        let mut reader = Reader::new(inner);
+        assert_eq!(parse_integer(reader.expect(TAG_INTEGER).unwrap()), 65507);
+        assert_eq!(parse_integer(reader.expect(TAG_INTEGER).unwrap()), -2);
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+mod ber;
+pub(crate) mod options;
+mod usm;
+
+use ber::{integer, null, octet_string, oid, sequence, Reader};
+
+const SYS_DESCR: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 1, 0];
+const SYS_CONTACT: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 4, 0];
+// reported by the agent when the engine boots and time of the request are out of sync
+const USM_STATS_NOT_IN_TIME_WINDOWS: &[u32] = &[1, 3, 6, 1, 6, 3, 15, 1, 1, 2, 0];
+
+const MAX_MESSAGE_SIZE: i64 = 65507;
+const FLAG_AUTH: u8 = 0x01;
+const FLAG_PRIV: u8 = 0x02;
+const FLAG_REPORTABLE: u8 = 0x04;
+const SECURITY_MODEL_USM: i64 = 3;
+
+// authoritative engine of each SNMPv3 target, discovered once
+static ENGINES: LazyLock<Mutex<HashMap<String, Engine>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+super::manager::register_plugin! {
+    "snmp1" => Snmp::new(Version::V1),
+    "snmp2" => Snmp::new(Version::V2c),
+    "snmp3" => Snmp::new(Version::V3)
+}
+
+#[derive(Clone, PartialEq)]
+enum Version {
+    V1,
+    V2c,
+    V3,
+}
+
+#[derive(Clone)]
+struct Engine {
+    id: Vec<u8>,
+    boots: i64,
+    time: i64,
+    discovered_at: Instant,
+}
+
+impl Engine {
+    // the engine time keeps increasing after the discovery
+    fn time(&self) -> i64 {
+        self.time + self.discovered_at.elapsed().as_secs() as i64
+    }
+}
+
+struct Pdu {
+    kind: u8,
+    request_id: i64,
+    error_status: i64,
+    // oid (without tag and length), value tag and value
+    varbinds: Vec<(Vec<u8>, u8, Vec<u8>)>,
+}
+
+impl Pdu {
+    fn encode(kind: u8, request_id: i64, varbinds: &[(&[u32], Vec<u8>)]) -> Vec<u8> {
+        let varbinds: Vec<Vec<u8>> = varbinds
+            .iter()
+            .map(|(name, value)| sequence(&[oid(name), value.clone()]))
+            .collect();
+        ber::tlv(
+            kind,
+            &[
+                integer(request_id),
+                integer(0),
+                integer(0),
+                sequence(&varbinds),
+            ]
+            .concat(),
+        )
+    }
+
+    fn parse(kind: u8, data: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(data);
+        let request_id = ber::parse_integer(reader.expect(ber::TAG_INTEGER)?);
+        let error_status = ber::parse_integer(reader.expect(ber::TAG_INTEGER)?);
+        reader.expect(ber::TAG_INTEGER)?;
+
+        let mut varbinds = vec![];
+        let mut list = Reader::new(reader.expect(ber::TAG_SEQUENCE)?);
+        while !list.is_empty() {
+            let mut varbind = Reader::new(list.expect(ber::TAG_SEQUENCE)?);
+            let name = varbind.expect(ber::TAG_OID)?.to_vec();
+            let (tag, value) = varbind.read()?;
+            varbinds.push((name, tag, value.to_vec()));
+        }
+
+        Ok(Self {
+            kind,
+            request_id,
+            error_status,
+            varbinds,
+        })
+    }
+
+    fn first_oid_is(&self, name: &[u32]) -> bool {
+        self.varbinds
+            .first()
+            .is_some_and(|(got, _, _)| got[..] == oid(name)[2..])
+    }
+}
+
+struct V3Response {
+    engine_id: Vec<u8>,
+    boots: i64,
+    time: i64,
+    pdu: Pdu,
+}
+
+impl V3Response {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let mut message = Reader::new(Reader::new(data).expect(ber::TAG_SEQUENCE)?);
+        message.expect(ber::TAG_INTEGER)?;
+
+        let mut global = Reader::new(message.expect(ber::TAG_SEQUENCE)?);
+        global.expect(ber::TAG_INTEGER)?;
+        global.expect(ber::TAG_INTEGER)?;
+        let flags = global.expect(ber::TAG_OCTET_STRING)?;
+        if flags.first().is_some_and(|f| f & FLAG_PRIV != 0) {
+            return Err("encrypted SNMPv3 responses are not supported".to_owned());
+        }
+
+        let security = message.expect(ber::TAG_OCTET_STRING)?;
+        let mut security = Reader::new(Reader::new(security).expect(ber::TAG_SEQUENCE)?);
+        let engine_id = security.expect(ber::TAG_OCTET_STRING)?.to_vec();
+        let boots = ber::parse_integer(security.expect(ber::TAG_INTEGER)?);
+        let time = ber::parse_integer(security.expect(ber::TAG_INTEGER)?);
+
+        let mut scoped = Reader::new(message.expect(ber::TAG_SEQUENCE)?);
+        scoped.expect(ber::TAG_OCTET_STRING)?;
+        scoped.expect(ber::TAG_OCTET_STRING)?;
+        let (kind, pdu) = scoped.read()?;
+
+        Ok(Self {
+            engine_id,
+            boots,
+            time,
+            pdu: Pdu::parse(kind, pdu)?,
+        })
+    }
+}
+
+fn request_id() -> i64 {
+    (rand::random::<u32>() & 0x7fffffff) as i64
+}
+
+// send the request and wait for a response accepted by the check, agents silently drop the
+// requests with a wrong community or user so None is returned on timeout
+async fn exchange<T>(
+    socket: &UdpSocket,
+    request: &[u8],
+    timeout: Duration,
+    check: impl Fn(&[u8]) -> Option<T>,
+) -> Result<Option<T>, Error> {
+    socket.send(request).await.map_err(|e| e.to_string())?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buffer = vec![0u8; MAX_MESSAGE_SIZE as usize];
+    loop {
+        let size = match tokio::time::timeout_at(deadline, socket.recv(&mut buffer)).await {
+            Err(_) => return Ok(None),
+            Ok(res) => res.map_err(|e| e.to_string())?,
+        };
+        if let Some(response) = check(&buffer[..size]) {
+            return Ok(Some(response));
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Snmp {
+    version: Version,
+    write_check: bool,
+    auth_protocol: options::AuthProtocol,
+}
+
+impl Snmp {
+    fn new(version: Version) -> Self {
+        Snmp {
+            version,
+            write_check: false,
+            auth_protocol: options::AuthProtocol::default(),
+        }
+    }
+
+    async fn community_request(
+        &self,
+        socket: &UdpSocket,
+        community: &str,
+        kind: u8,
+        name: &[u32],
+        value: Vec<u8>,
+        timeout: Duration,
+    ) -> Result<Option<Pdu>, Error> {
+        let id = request_id();
+        let version = if self.version == Version::V1 { 0 } else { 1 };
+        let request = sequence(&[
+            integer(version),
+            octet_string(community.as_bytes()),
+            Pdu::encode(kind, id, &[(name, value)]),
+        ]);
+
+        exchange(socket, &request, timeout, |data| {
+            let mut message = Reader::new(Reader::new(data).expect(ber::TAG_SEQUENCE).ok()?);
+            message.expect(ber::TAG_INTEGER).ok()?;
+            message.expect(ber::TAG_OCTET_STRING).ok()?;
+            let (kind, pdu) = message.read().ok()?;
+            Pdu::parse(kind, pdu)
+                .ok()
+                .filter(|pdu| pdu.kind == ber::PDU_RESPONSE && pdu.request_id == id)
+        })
+        .await
+    }
+
+    // set sysContact.0 to its current value to check if the community can write
+    async fn can_write(
+        &self,
+        socket: &UdpSocket,
+        community: &str,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let Some(current) = self
+            .community_request(socket, community, ber::PDU_GET, SYS_CONTACT, null(), timeout)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let Some((_, tag, value)) = current.varbinds.first() else {
+            return Ok(false);
+        };
+        if current.error_status != 0 || *tag != ber::TAG_OCTET_STRING {
+            return Ok(false);
+        }
+
+        Ok(self
+            .community_request(
+                socket,
+                community,
+                ber::PDU_SET,
+                SYS_CONTACT,
+                octet_string(value),
+                timeout,
+            )
+            .await?
+            .is_some_and(|res| res.error_status == 0))
+    }
+
+    async fn attempt_community(
+        &self,
+        address: &str,
+        community: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let socket = utils::net::async_udp_socket(address).await?;
+        let Some(response) = self
+            .community_request(&socket, community, ber::PDU_GET, SYS_DESCR, null(), timeout)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let mut data = vec![("community".to_owned(), community.to_owned())];
+        if self.write_check {
+            let access = if self.can_write(&socket, community, timeout).await? {
+                "read-write"
+            } else {
+                "read"
+            };
+            data.push(("access".to_owned(), access.to_owned()));
+        }
+        if let Some((_, ber::TAG_OCTET_STRING, descr)) = response.varbinds.first() {
+            data.push((
+                "description".to_owned(),
+                String::from_utf8_lossy(descr).trim().to_owned(),
+            ));
+        }
+
+        Ok(Some(vec![Loot::new("snmp", address, data)]))
+    }
+
+    fn v3_request(
+        &self,
+        engine: &Engine,
+        username: &str,
+        key: Option<&[u8]>,
+        pdu: Vec<u8>,
+    ) -> (i64, Vec<u8>) {
+        let id = request_id();
+        let flags = if key.is_some() {
+            FLAG_AUTH | FLAG_REPORTABLE
+        } else {
+            FLAG_REPORTABLE
+        };
+        let global = sequence(&[
+            integer(id),
+            integer(MAX_MESSAGE_SIZE),
+            octet_string(&[flags]),
+            integer(SECURITY_MODEL_USM),
+        ]);
+        let security = |auth_params: &[u8]| {
+            octet_string(&sequence(&[
+                octet_string(&engine.id),
+                integer(engine.boots),
+                integer(engine.time()),
+                octet_string(username.as_bytes()),
+                octet_string(auth_params),
+                octet_string(&[]),
+            ]))
+        };
+        let scoped = sequence(&[octet_string(&engine.id), octet_string(&[]), pdu]);
+
+        let Some(key) = key else {
+            return (
+                id,
+                sequence(&[integer(3), global, security(&[]), scoped]),
+            );
+        };
+
+        // the message is signed with zeroed authentication parameters, which are then replaced
+        // by the digest of the same size
+        let unsigned = sequence(&[
+            integer(3),
+            global.clone(),
+            security(&[0; usm::AUTH_PARAMS_SIZE]),
+            scoped.clone(),
+        ]);
+        let digest = usm::sign(&self.auth_protocol, key, &unsigned);
+        (
+            id,
+            sequence(&[integer(3), global, security(&digest), scoped]),
+        )
+    }
+
+    async fn v3_exchange(
+        &self,
+        socket: &UdpSocket,
+        request: &[u8],
+        timeout: Duration,
+    ) -> Result<Option<V3Response>, Error> {
+        exchange(socket, request, timeout, |data| V3Response::parse(data).ok()).await
+    }
+
+    // get the authoritative engine id, boots and time of the target
+    async fn discover_engine(
+        &self,
+        socket: &UdpSocket,
+        address: &str,
+        timeout: Duration,
+    ) -> Result<Engine, Error> {
+        if let Some(engine) = ENGINES.lock().unwrap().get(address) {
+            return Ok(engine.clone());
+        }
+
+        let unknown = Engine {
+            id: vec![],
+            boots: 0,
+            time: 0,
+            discovered_at: Instant::now(),
+        };
+        let (_, request) =
+            self.v3_request(&unknown, "", None, Pdu::encode(ber::PDU_GET, request_id(), &[]));
+        let response = self
+            .v3_exchange(socket, &request, timeout)
+            .await?
+            .ok_or(format!("no SNMPv3 engine discovery response from {}", address))?;
+        if response.engine_id.is_empty() {
+            return Err(format!("{} didn't report its SNMPv3 engine id", address));
+        }
+
+        let engine = Engine {
+            id: response.engine_id,
+            boots: response.boots,
+            time: response.time,
+            discovered_at: Instant::now(),
+        };
+        ENGINES
+            .lock()
+            .unwrap()
+            .insert(address.to_owned(), engine.clone());
+        Ok(engine)
+    }
+
+    async fn attempt_user(
+        &self,
+        address: &str,
+        username: &str,
+        password: &str,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        // the key can't be derived from an empty password
+        if password.is_empty() {
+            return Ok(None);
+        }
+
+        let socket = utils::net::async_udp_socket(address).await?;
+        let mut engine = self.discover_engine(&socket, address, timeout).await?;
+        let key = usm::localized_key(&self.auth_protocol, password, &engine.id);
+
+        // retry once if the engine boots and time need to be synchronized
+        for _ in 0..2 {
+            let pdu = Pdu::encode(ber::PDU_GET, request_id(), &[(SYS_DESCR, null())]);
+            let (_, request) = self.v3_request(&engine, username, Some(&key), pdu);
+            let Some(response) = self.v3_exchange(&socket, &request, timeout).await? else {
+                return Ok(None);
+            };
+
+            match response.pdu.kind {
+                ber::PDU_RESPONSE => {
+                    return Ok(Some(vec![Loot::new(
+                        "snmp",
+                        address,
+                        [
+                            ("username".to_owned(), username.to_owned()),
+                            ("password".to_owned(), password.to_owned()),
+                        ],
+                    )]));
+                }
+                ber::PDU_REPORT if response.pdu.first_oid_is(USM_STATS_NOT_IN_TIME_WINDOWS) => {
+                    engine.boots = response.boots;
+                    engine.time = response.time;
+                    engine.discovered_at = Instant::now();
+                    ENGINES
+                        .lock()
+                        .unwrap()
+                        .insert(address.to_owned(), engine.clone());
+                }
+                // unknown user, wrong digest, unsupported security level, ...
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl Plugin for Snmp {
+    fn description(&self) -> &'static str {
+        match self.version {
+            Version::V1 => "SNMPv1 community strings.",
+            Version::V2c => "SNMPv2c community strings.",
+            Version::V3 => "SNMPv3 user authentication (MD5 or SHA).",
+        }
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.version == Version::V3 {
+            PayloadStrategy::UsernamePassword
+        } else {
+            PayloadStrategy::Single
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.write_check = opts.snmp.snmp_write_check;
+        self.auth_protocol = opts.snmp.snmp_auth_protocol.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 161)?;
+        if self.version == Version::V3 {
+            self.attempt_user(&address, &creds.username, &creds.password, timeout)
+                .await
+        } else {
+            // being this plugin single credentials, this is going to be the community
+            self.attempt_community(&address, creds.single(), timeout)
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Engine, Pdu, V3Response, Version, Snmp, SYS_DESCR, USM_STATS_NOT_IN_TIME_WINDOWS,
+    };
+    use crate::plugins::snmp::ber::{self, integer, null};
+
+    #[test]
+    fn can_build_and_parse_v3_messages() {
+        let snmp = Snmp::new(Version::V3);
+        let engine = Engine {
+            id: hex::decode("000000000000000000000002").unwrap(),
+            boots: 7,
+            time: 1000,
+            discovered_at: std::time::Instant::now(),
+        };
+        let key = vec![1u8; 16];
+
+        let get = Pdu::encode(ber::PDU_GET, 1, &[(SYS_DESCR, null())]);
+        let (_, signed) = snmp.v3_request(&engine, "admin", Some(&key), get.clone());
+        let (_, unsigned) = snmp.v3_request(&engine, "admin", None, get);
+        assert_eq!(signed.len(), unsigned.len() + 12);
+
+        let report = Pdu::encode(
+            ber::PDU_REPORT,
+            42,
+            &[(USM_STATS_NOT_IN_TIME_WINDOWS, integer(1))],
+        );
+        let (_, report) = snmp.v3_request(&engine, "", None, report);
+        let response = V3Response::parse(&report).unwrap();
+
+        assert_eq!(response.engine_id, engine.id);
+        assert_eq!(response.boots, 7);
+        assert!(response.time >= 1000);
+        assert_eq!(response.pdu.kind, ber::PDU_REPORT);
+        assert_eq!(response.pdu.request_id, 42);
+        assert!(response.pdu.first_oid_is(USM_STATS_NOT_IN_TIME_WINDOWS));
+        assert!(!response.pdu.first_oid_is(SYS_DESCR));
+    }
+}
@@ -0,0 +1,20 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Serialize, Deserialize, Debug, ValueEnum)]
+pub(crate) enum AuthProtocol {
+    #[default]
+    Md5,
+    Sha,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Check if the SNMP communities found have write access by setting sysContact.0 to its current value.
+    pub snmp_write_check: bool,
+    #[clap(long, value_enum, default_value_t = AuthProtocol::Md5)]
+    /// SNMPv3 authentication protocol.
+    pub snmp_auth_protocol: AuthProtocol,
+}
@@ -0,0 +1,79 @@
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use sha1::Sha1;
+
+use super::options::AuthProtocol;
+
+// number of bytes of the expanded password that are hashed (RFC 3414 A.2)
+const EXPANDED_PASSWORD_SIZE: usize = 1048576;
+// HMAC-MD5-96 and HMAC-SHA-96 truncate the digest to 12 bytes
+pub(crate) const AUTH_PARAMS_SIZE: usize = 12;
+
+// the password must not be empty
+fn password_to_key<D: Digest>(password: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut expanded = password.iter().cycle();
+    let mut block = [0u8; 64];
+    for _ in 0..EXPANDED_PASSWORD_SIZE / block.len() {
+        for b in block.iter_mut() {
+            *b = *expanded.next().unwrap();
+        }
+        hasher.update(block);
+    }
+    hasher.finalize().to_vec()
+}
+
+fn localize<D: Digest>(key: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(key);
+    hasher.update(engine_id);
+    hasher.update(key);
+    hasher.finalize().to_vec()
+}
+
+// derive the authentication key of the password for this engine
+pub(crate) fn localized_key(protocol: &AuthProtocol, password: &str, engine_id: &[u8]) -> Vec<u8> {
+    match protocol {
+        AuthProtocol::Md5 => localize::<Md5>(&password_to_key::<Md5>(password.as_bytes()), engine_id),
+        AuthProtocol::Sha => {
+            localize::<Sha1>(&password_to_key::<Sha1>(password.as_bytes()), engine_id)
+        }
+    }
+}
+
+// compute the authentication parameters of the whole message
+pub(crate) fn sign(protocol: &AuthProtocol, key: &[u8], message: &[u8]) -> Vec<u8> {
+    let digest = match protocol {
+        AuthProtocol::Md5 => {
+            let mut mac = Hmac::<Md5>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        AuthProtocol::Sha => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    };
+    digest[..AUTH_PARAMS_SIZE].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::localized_key;
+    use crate::plugins::snmp::options::AuthProtocol;
+
+    // RFC 3414 A.3.1 and A.3.2
+    #[test]
+    fn can_localize_keys() {
+        let engine_id = hex::decode("000000000000000000000002").unwrap();
+        assert_eq!(
+            hex::encode(localized_key(&AuthProtocol::Md5, "maplesyrup", &engine_id)),
+            "526f5eed9fcce26f8964c2930787d82b"
+        );
+        assert_eq!(
+            hex::encode(localized_key(&AuthProtocol::Sha, "maplesyrup", &engine_id)),
+            "6695febc9288e36282235fc7151f128497b38f3f"
+        );
+    }
+}
@@ -10,6 +10,7 @@ use crate::Plugin;
 use crate::creds::Credentials;
 
 pub(crate) mod options;
+mod protocol;
 
 super::manager::register_plugin! {
     "socks5" => Socks5::new()
@@ -49,31 +50,42 @@ impl Plugin for Socks5 {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address: String = utils::parse_target_address(&creds.target, 1080)?;
-        let res = tokio::time::timeout(
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let valid = tokio::time::timeout(
             timeout,
-            fast_socks5::client::Socks5Stream::connect_with_password(
-                address.clone(),
-                self.remote_address.clone(),
-                self.remote_port,
-                creds.username.clone(),
-                creds.password.clone(),
-                fast_socks5::client::Config::default(),
-            ),
+            protocol::authenticate(&mut stream, &creds.username, &creds.password),
         )
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())??;
+        if !valid {
+            return Ok(None);
+        }
+
+        // check if the proxy allows connecting to the remote address
+        let connect = match tokio::time::timeout(
+            timeout,
+            protocol::connect(&mut stream, &self.remote_address, self.remote_port),
+        )
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|res| res)
+        {
+            Ok(None) => "allowed".to_owned(),
+            Ok(Some(reason)) => format!("denied ({})", reason),
+            Err(e) => format!("denied ({})", e),
+        };
 
-        return Ok(if res.is_ok() {
-            Some(vec![Loot::new(
-                "socks5",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )])
-        } else {
-            None
-        });
+        Ok(Some(vec![Loot::new(
+            "socks5",
+            &address,
+            [
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                (
+                    "connect".to_owned(),
+                    format!("{}:{} {}", &self.remote_address, self.remote_port, connect),
+                ),
+            ],
+        )]))
     }
 }
@@ -0,0 +1,129 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const VERSION: u8 = 0x05;
+const METHOD_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+
+// RFC 1928 reply field
+fn reply_reason(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+async fn read_pair<S: AsyncRead + Unpin>(stream: &mut S) -> Result<[u8; 2], Error> {
+    let mut buf = [0u8; 2];
+    stream
+        .read_exact(&mut buf)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(buf)
+}
+
+// RFC 1929 username and password authentication, returns true if the credentials are valid
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    if username.len() > 255 || password.len() > 255 {
+        return Err("username and password can't be longer than 255 bytes".to_owned());
+    }
+
+    stream
+        .write_all(&[VERSION, 1, METHOD_PASSWORD])
+        .await
+        .map_err(|e| e.to_string())?;
+    match read_pair(stream).await? {
+        [VERSION, METHOD_PASSWORD] => {}
+        [VERSION, METHOD_NONE_ACCEPTABLE] => {
+            return Err("password authentication not supported".to_owned())
+        }
+        reply => return Err(format!("unexpected SOCKS5 method selection: {:?}", reply)),
+    }
+
+    let mut request = vec![0x01, username.len() as u8];
+    request.extend_from_slice(username.as_bytes());
+    request.push(password.len() as u8);
+    request.extend_from_slice(password.as_bytes());
+    stream
+        .write_all(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // some servers close the connection right away on failure
+    Ok(matches!(read_pair(stream).await, Ok([_, 0x00])))
+}
+
+// requests a connection to the remote host, returns an error message if it was refused
+pub(crate) async fn connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+) -> Result<Option<String>, Error> {
+    if host.len() > 255 {
+        return Err("remote host can't be longer than 255 bytes".to_owned());
+    }
+
+    let mut request = vec![VERSION, CMD_CONNECT, 0x00, ATYP_DOMAIN, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream
+        .write_all(&request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match read_pair(stream).await? {
+        [VERSION, 0x00] => Ok(None),
+        [VERSION, code] => Ok(Some(reply_reason(code).to_owned())),
+        reply => Err(format!("unexpected SOCKS5 reply: {:?}", reply)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, connect};
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[0x05, 0x01, 0x02])
+            .read(&[0x05, 0x02])
+            .write(b"\x01\x05admin\x06secret")
+            .read(&[0x01, 0x00])
+            .build();
+        assert_eq!(authenticate(&mut stream, "admin", "secret").await, Ok(true));
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&[0x05, 0x01, 0x02])
+            .read(&[0x05, 0x02])
+            .write(b"\x01\x05admin\x05wrong")
+            .read(&[0x01, 0x01])
+            .build();
+        assert_eq!(authenticate(&mut stream, "admin", "wrong").await, Ok(false));
+    }
+
+    #[tokio::test]
+    async fn can_connect() {
+        let mut stream = tokio_test::io::Builder::new()
+            .write(b"\x05\x01\x00\x03\x08ifcfg.co\x00\x50")
+            .read(&[0x05, 0x02])
+            .build();
+        assert_eq!(
+            connect(&mut stream, "ifcfg.co", 80).await,
+            Ok(Some("connection not allowed by ruleset".to_owned()))
+        );
+    }
+}
@@ -61,7 +61,11 @@ impl SQL {
             timeout,
             PoolOptions::<DB>::new().connect(&format!(
                 "{}://{}:{}@{}/{}",
-                scheme, &creds.username, &creds.password, &address, db
+                scheme,
+                &creds.username,
+                &creds.password,
+                utils::resolver::resolve_address(&address),
+                db
             )),
         )
         .await
@@ -0,0 +1,267 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use russh::client;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::session::{Error, Loot};
+use crate::utils;
+use crate::utils::once::OncePerTarget;
+
+const IDENTIFICATION: &str = "SSH-2.0-legba";
+const MSG_KEXINIT: u8 = 20;
+// servers can send other lines before their identification
+const MAX_BANNER_LINES: usize = 32;
+const MAX_PACKET_SIZE: usize = 35000;
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Algorithms {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub macs: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+fn name_list(payload: &[u8], pos: &mut usize) -> Result<Vec<String>, Error> {
+    let size = payload
+        .get(*pos..*pos + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or("truncated kexinit")?;
+    let list = payload
+        .get(*pos + 4..*pos + 4 + size)
+        .ok_or("truncated kexinit")?;
+    *pos += 4 + size;
+
+    Ok(String::from_utf8_lossy(list)
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned())
+        .collect())
+}
+
+pub(crate) fn parse_kexinit(payload: &[u8]) -> Result<Algorithms, Error> {
+    if payload.first() != Some(&MSG_KEXINIT) {
+        return Err("not a kexinit message".to_owned());
+    }
+
+    // message type and cookie
+    let mut pos = 17;
+    let kex = name_list(payload, &mut pos)?;
+    let host_key = name_list(payload, &mut pos)?;
+    // client to server lists, the server to client ones are the same in practice
+    let ciphers = name_list(payload, &mut pos)?;
+    name_list(payload, &mut pos)?;
+    let macs = name_list(payload, &mut pos)?;
+    name_list(payload, &mut pos)?;
+    let compression = name_list(payload, &mut pos)?;
+
+    Ok(Algorithms {
+        kex,
+        host_key,
+        ciphers,
+        macs,
+        compression,
+    })
+}
+
+// exchange identifications and read the server KEXINIT, no key exchange is performed
+pub(crate) async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+) -> Result<(String, Algorithms), Error> {
+    let mut stream = BufReader::new(stream);
+    stream
+        .write_all(format!("{}\r\n", IDENTIFICATION).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut banner = None;
+    for _ in 0..MAX_BANNER_LINES {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        if line.starts_with("SSH-") {
+            banner = Some(line.trim_end().to_owned());
+            break;
+        }
+    }
+    let banner = banner.ok_or("no ssh identification received")?;
+
+    let size = stream.read_u32().await.map_err(|e| e.to_string())? as usize;
+    if !(2..=MAX_PACKET_SIZE).contains(&size) {
+        return Err(format!("invalid packet size {}", size));
+    }
+    let mut packet = vec![0u8; size];
+    stream
+        .read_exact(&mut packet)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let padding = packet[0] as usize;
+    let payload = packet
+        .get(1..size.saturating_sub(padding))
+        .ok_or("invalid packet padding")?;
+
+    Ok((banner, parse_kexinit(payload)?))
+}
+
+struct KeyRecorder(Arc<Mutex<Option<String>>>);
+
+#[async_trait]
+impl client::Handler for KeyRecorder {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &russh_keys::key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        *self.0.lock().unwrap() = Some(format!(
+            "{} SHA256:{}",
+            server_public_key.name(),
+            server_public_key.fingerprint()
+        ));
+        Ok(true)
+    }
+}
+
+// fingerprint of the host key negotiated by a full key exchange
+async fn host_key(address: &str) -> Result<String, Error> {
+    let key = Arc::new(Mutex::new(None));
+    let handle = client::connect(
+        Arc::new(client::Config::default()),
+        utils::resolver::resolve_address(address),
+        KeyRecorder(key.clone()),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    drop(handle);
+
+    let key = key.lock().unwrap().take();
+    key.ok_or("no host key received".to_owned())
+}
+
+#[derive(Clone)]
+pub(crate) struct Fingerprinter {
+    enabled: bool,
+    // targets whose fingerprint was reported
+    done: OncePerTarget,
+}
+
+impl Fingerprinter {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            done: OncePerTarget::new(),
+        }
+    }
+
+    async fn probe(address: &str, timeout: Duration) -> Result<Loot, Error> {
+        let stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+        let (banner, algorithms) = tokio::time::timeout(timeout, handshake(stream))
+            .await
+            .map_err(|e| e.to_string())??;
+
+        let mut data = vec![
+            ("type".to_owned(), "info".to_owned()),
+            ("banner".to_owned(), banner),
+        ];
+        match tokio::time::timeout(timeout, host_key(address)).await {
+            Ok(Ok(key)) => data.push(("host_key".to_owned(), key)),
+            Ok(Err(e)) => log::debug!("can't get the host key of {}: {}", address, e),
+            Err(e) => log::debug!("can't get the host key of {}: {}", address, e),
+        }
+        data.extend([
+            ("host_key_algorithms".to_owned(), algorithms.host_key.join(",")),
+            ("kex".to_owned(), algorithms.kex.join(",")),
+            ("ciphers".to_owned(), algorithms.ciphers.join(",")),
+            ("macs".to_owned(), algorithms.macs.join(",")),
+            ("compression".to_owned(), algorithms.compression.join(",")),
+        ]);
+
+        Ok(Loot::new("ssh", address, data).set_partial())
+    }
+
+    // the loot the first time the target info is reported, nothing afterwards
+    pub fn report(&self, address: &str, info: Loot) -> Option<Vec<Loot>> {
+        self.done.report(address, info)
+    }
+
+    // info loot for the target until it's reported
+    pub async fn fingerprint(&self, address: &str, timeout: Duration) -> Option<Loot> {
+        if !self.enabled || self.done.contains(address) {
+            return None;
+        }
+
+        match Self::probe(address, timeout).await {
+            Ok(loot) => Some(loot),
+            Err(e) => {
+                log::debug!("can't fingerprint {}: {}", address, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handshake, Algorithms, IDENTIFICATION, MSG_KEXINIT};
+
+    fn kexinit(lists: &[&str]) -> Vec<u8> {
+        let mut payload = vec![MSG_KEXINIT];
+        payload.extend([0xaa; 16]);
+        for list in lists {
+            payload.extend((list.len() as u32).to_be_bytes());
+            payload.extend(list.as_bytes());
+        }
+        // first kex packet follows and reserved
+        payload.extend([0, 0, 0, 0, 0]);
+
+        let padding = 8 - (payload.len() + 5) % 8 + 4;
+        let mut packet = ((payload.len() + padding + 1) as u32).to_be_bytes().to_vec();
+        packet.push(padding as u8);
+        packet.extend(payload);
+        packet.extend(vec![0u8; padding]);
+        packet
+    }
+
+    #[tokio::test]
+    async fn can_parse_server_kexinit() {
+        let stream = tokio_test::io::Builder::new()
+            .write(format!("{}\r\n", IDENTIFICATION).as_bytes())
+            .read(b"Welcome\r\nSSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13\r\n")
+            .read(&kexinit(&[
+                "curve25519-sha256,diffie-hellman-group14-sha256",
+                "rsa-sha2-512,ssh-ed25519",
+                "chacha20-poly1305@openssh.com,aes256-ctr",
+                "chacha20-poly1305@openssh.com,aes256-ctr",
+                "hmac-sha2-256",
+                "hmac-sha2-256",
+                "none,zlib@openssh.com",
+                "none,zlib@openssh.com",
+                "",
+                "",
+            ]))
+            .build();
+
+        let (banner, algorithms) = handshake(stream).await.unwrap();
+        assert_eq!(banner, "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13");
+        assert_eq!(
+            algorithms,
+            Algorithms {
+                kex: vec![
+                    "curve25519-sha256".to_owned(),
+                    "diffie-hellman-group14-sha256".to_owned()
+                ],
+                host_key: vec!["rsa-sha2-512".to_owned(), "ssh-ed25519".to_owned()],
+                ciphers: vec![
+                    "chacha20-poly1305@openssh.com".to_owned(),
+                    "aes256-ctr".to_owned()
+                ],
+                macs: vec!["hmac-sha2-256".to_owned()],
+                compression: vec!["none".to_owned(), "zlib@openssh.com".to_owned()],
+            }
+        );
+    }
+}
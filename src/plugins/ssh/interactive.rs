@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rand::{distributions::Alphanumeric, Rng};
+use russh::client::{self, Handle, KeyboardInteractiveAuthResponse};
+use tokio::sync::Mutex;
+
+use crate::session::Error;
+
+// give up on servers asking questions forever
+const MAX_ROUNDS: usize = 8;
+
+// prompts of the usual second factor PAM modules (google authenticator, duo, oath, radius ...)
+const OTP_HINTS: &[&str] = &[
+    "verification code",
+    "one-time",
+    "one time",
+    "otp",
+    "token",
+    "passcode",
+    "authenticator",
+    "2fa",
+    "two-factor",
+    "second factor",
+    "duo",
+    "totp",
+];
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Valid,
+    // valid password, the server is asking for a second factor with this prompt
+    MfaRequired(String),
+    Invalid,
+}
+
+#[derive(Debug, PartialEq)]
+enum Prompt {
+    Password,
+    Otp,
+    Other,
+}
+
+fn classify(prompt: &str) -> Prompt {
+    let prompt = prompt.to_lowercase();
+    // checked first since "one-time password" is not a password prompt
+    if OTP_HINTS.iter().any(|hint| prompt.contains(hint)) {
+        Prompt::Otp
+    } else if prompt.contains("password") || prompt.contains("passphrase") {
+        Prompt::Password
+    } else {
+        Prompt::Other
+    }
+}
+
+pub(crate) struct AcceptAny;
+
+#[async_trait]
+impl client::Handler for AcceptAny {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        _server_public_key: &russh_keys::key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Authenticator {
+    keyboard_interactive: bool,
+    // targets asking for the second factor whatever the password, where it can't be used as a signal
+    otp_always: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl Authenticator {
+    pub fn new(keyboard_interactive: bool) -> Self {
+        Self {
+            keyboard_interactive,
+            otp_always: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn connect(address: &str) -> Result<Handle<AcceptAny>, Error> {
+        let address = crate::utils::resolver::resolve_address(address);
+        client::connect(Arc::new(client::Config::default()), address, AcceptAny)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // answer the prompts until the server makes up its mind
+    async fn keyboard_interactive(
+        handle: &mut Handle<AcceptAny>,
+        username: &str,
+        password: &str,
+        otp_always: bool,
+    ) -> Result<Outcome, Error> {
+        let mut response = handle
+            .authenticate_keyboard_interactive_start(username, None)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for _ in 0..MAX_ROUNDS {
+            match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(Outcome::Valid),
+                KeyboardInteractiveAuthResponse::Failure => return Ok(Outcome::Invalid),
+                KeyboardInteractiveAuthResponse::InfoRequest { prompts, .. } => {
+                    if let Some(otp) = prompts.iter().find(|p| classify(&p.prompt) == Prompt::Otp)
+                    {
+                        // asked after the password was accepted, either by a previous round or
+                        // by the password method (partial success)
+                        return Ok(if otp_always {
+                            Outcome::Invalid
+                        } else {
+                            Outcome::MfaRequired(otp.prompt.trim().to_owned())
+                        });
+                    }
+
+                    let mut responses = vec![];
+                    for prompt in prompts.iter() {
+                        responses.push(if classify(&prompt.prompt) == Prompt::Password {
+                            password.to_owned()
+                        } else {
+                            String::new()
+                        });
+                    }
+
+                    response = handle
+                        .authenticate_keyboard_interactive_respond(responses)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+
+        Ok(Outcome::Invalid)
+    }
+
+    // check once per target whether the second factor is asked regardless of the password, as with
+    // OTP first or non requisite PAM modules
+    async fn is_otp_always(&self, address: &str) -> bool {
+        let mut otp_always = self.otp_always.lock().await;
+        if let Some(value) = otp_always.get(address) {
+            return *value;
+        }
+
+        let random = || -> String {
+            rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(12)
+                .map(char::from)
+                .collect()
+        };
+        let (username, password) = (random(), random());
+
+        let value = match Self::connect(address).await {
+            Ok(mut handle) => {
+                matches!(handle.authenticate_password(&username, &password).await, Ok(false))
+                    && matches!(
+                        Self::keyboard_interactive(&mut handle, &username, &password, false).await,
+                        Ok(Outcome::MfaRequired(_))
+                    )
+            }
+            Err(_) => false,
+        };
+
+        if value {
+            log::warn!(
+                "{} asks for a second factor even for invalid credentials, it will not be reported",
+                address
+            );
+        }
+
+        otp_always.insert(address.to_owned(), value);
+        value
+    }
+
+    // the session handle is returned to run commands once authenticated
+    pub async fn authenticate(
+        &self,
+        address: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<(Outcome, Handle<AcceptAny>), Error> {
+        let mut handle = Self::connect(address).await?;
+        match handle.authenticate_password(username, password).await {
+            Ok(true) => return Ok((Outcome::Valid, handle)),
+            Ok(false) => {}
+            // the server does not accept any other method
+            Err(russh::Error::NoAuthMethod) => return Ok((Outcome::Invalid, handle)),
+            Err(e) => return Err(e.to_string()),
+        }
+
+        if !self.keyboard_interactive {
+            return Ok((Outcome::Invalid, handle));
+        }
+
+        let otp_always = self.is_otp_always(address).await;
+        let outcome =
+            match Self::keyboard_interactive(&mut handle, username, password, otp_always).await {
+                Err(e) if e == russh::Error::NoAuthMethod.to_string() => Outcome::Invalid,
+                res => res?,
+            };
+        Ok((outcome, handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, Prompt};
+
+    #[test]
+    fn can_classify_prompts() {
+        assert_eq!(classify("Password: "), Prompt::Password);
+        assert_eq!(classify("(root@host) Password:"), Prompt::Password);
+        assert_eq!(classify("Verification code: "), Prompt::Otp);
+        assert_eq!(classify("One-time password (OATH) for `root': "), Prompt::Otp);
+        assert_eq!(
+            classify("Passcode or option (1-3): "),
+            Prompt::Otp
+        );
+        assert_eq!(classify("Enter PASSCODE:"), Prompt::Otp);
+        assert_eq!(classify("Do you accept? "), Prompt::Other);
+    }
+}
@@ -4,13 +4,16 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 
-use crate::creds::Credentials;
+use crate::creds::{self, Credentials, Expression};
 use crate::session::{Error, Loot};
 use crate::utils;
 use crate::Options;
 use crate::Plugin;
 
+mod fingerprint;
+mod interactive;
 pub(crate) mod options;
+mod verify;
 
 super::manager::register_plugin! {
     "ssh" => SSH::new(),
@@ -20,14 +23,110 @@ super::manager::register_plugin! {
 #[derive(Clone)]
 pub(crate) struct SSH {
     mode: options::Mode,
-    passphrase: Option<String>,
+    // passphrases to try in order, None for unencrypted keys
+    passphrases: Vec<Option<String>>,
+    authenticator: interactive::Authenticator,
+    fingerprinter: fingerprint::Fingerprinter,
+    verify_cmd: Option<String>,
 }
 
 impl SSH {
     pub fn new() -> Self {
         SSH {
             mode: options::Mode::default(),
-            passphrase: None,
+            passphrases: vec![None],
+            authenticator: interactive::Authenticator::new(true),
+            fingerprinter: fingerprint::Fingerprinter::new(true),
+            verify_cmd: None,
+        }
+    }
+
+    async fn password_attempt(
+        &self,
+        address: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (outcome, handle) = tokio::time::timeout(
+            timeout,
+            self.authenticator
+                .authenticate(address, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        Ok(match outcome {
+            interactive::Outcome::Valid => {
+                if let Some(command) = self.verify_cmd.as_ref() {
+                    let channel = handle
+                        .channel_open_session()
+                        .await
+                        .map_err(|e| e.to_string());
+                    data.extend(verify::verify(channel, command, &creds.username, timeout).await);
+                }
+                Some(vec![Loot::new("ssh", address, data)])
+            }
+            interactive::Outcome::MfaRequired(prompt) => Some(vec![Loot::new(
+                "ssh",
+                address,
+                data.into_iter().chain([
+                    ("status".to_owned(), "mfa required".to_owned()),
+                    ("prompt".to_owned(), prompt),
+                ]),
+            )
+            .set_partial()]),
+            interactive::Outcome::Invalid => None,
+        })
+    }
+
+    async fn key_attempt(
+        &self,
+        address: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let mut res = Err(async_ssh2_tokio::Error::KeyAuthFailed);
+        for passphrase in &self.passphrases {
+            let method = AuthMethod::with_key_file(&creds.password, passphrase.as_deref());
+
+            res = tokio::time::timeout(
+                timeout,
+                Client::connect(
+                    utils::resolver::resolve_address(address),
+                    &creds.username,
+                    method,
+                    ServerCheckMethod::NoCheck,
+                ),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            // the key could not be decrypted with this passphrase, try the next one
+            match res {
+                Err(async_ssh2_tokio::Error::KeyInvalid(_)) => continue,
+                _ => break,
+            }
+        }
+
+        if let Ok(client) = res {
+            let mut data = vec![
+                ("username".to_owned(), creds.username.to_owned()),
+                ("key".to_owned(), creds.password.to_owned()),
+            ];
+            if let Some(command) = self.verify_cmd.as_ref() {
+                let channel = client.get_channel().await.map_err(|e| e.to_string());
+                data.extend(verify::verify(channel, command, &creds.username, timeout).await);
+            }
+            Ok(Some(vec![Loot::new("ssh", address, data)]))
+        } else if let Err(async_ssh2_tokio::Error::KeyAuthFailed) = res
+        {
+            Ok(None)
+        } else {
+            Err(res.err().unwrap().to_string())
         }
     }
 }
@@ -35,12 +134,27 @@ impl SSH {
 #[async_trait]
 impl Plugin for SSH {
     fn description(&self) -> &'static str {
-        "SSH/SFTP password and private key authentication."
+        "SSH/SFTP password, keyboard-interactive and private key authentication."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
         self.mode = opts.ssh.ssh_auth_mode.clone();
-        self.passphrase.clone_from(&opts.ssh.ssh_key_passphrase);
+        self.authenticator =
+            interactive::Authenticator::new(!opts.ssh.ssh_no_keyboard_interactive);
+        self.fingerprinter = fingerprint::Fingerprinter::new(!opts.ssh.ssh_no_fingerprint);
+        self.verify_cmd = opts.ssh.ssh_verify_cmd.clone();
+        // --password keys:... implies key based authentication
+        if let Expression::Keys { .. } = creds::parse_expression(opts.password.as_ref()) {
+            self.mode = options::Mode::Key;
+        }
+
+        self.passphrases = vec![opts.ssh.ssh_key_passphrase.clone()];
+        if let Some(path) = opts.ssh.ssh_key_passphrases.as_ref() {
+            let data = std::fs::read_to_string(path)
+                .map_err(|e| format!("can't read {}: {}", path, e))?;
+            self.passphrases
+                .extend(data.lines().map(|line| Some(line.to_owned())));
+        }
         Ok(())
     }
 
@@ -50,42 +164,22 @@ impl Plugin for SSH {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 22)?;
-        let (method, key_label) = match self.mode {
-            options::Mode::Password => (
-                AuthMethod::with_password(&creds.password),
-                "password".to_owned(),
-            ),
-            options::Mode::Key => (
-                AuthMethod::with_key_file(&creds.password, self.passphrase.as_deref()),
-                "key".to_owned(),
-            ),
+        let info = self.fingerprinter.fingerprint(&address, timeout).await;
+        let res = match self.mode {
+            options::Mode::Password => self.password_attempt(&address, creds, timeout).await,
+            options::Mode::Key => self.key_attempt(&address, creds, timeout).await,
         };
 
-        let res = tokio::time::timeout(
-            timeout,
-            Client::connect(
-                address.clone(),
-                &creds.username,
-                method,
-                ServerCheckMethod::NoCheck,
-            ),
-        )
-        .await
-        .map_err(|e| e.to_string())?;
-
-        if res.is_ok() {
-            Ok(Some(vec![Loot::new(
-                "ssh",
-                &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    (key_label, creds.password.to_owned()),
-                ],
-            )]))
-        } else if let Err(async_ssh2_tokio::Error::PasswordWrong) = res {
-            Ok(None)
-        } else {
-            Err(res.err().unwrap().to_string())
+        // reported only along with a completed attempt, failed ones will be retried and so will
+        // the fingerprinting
+        match (info, res) {
+            (Some(info), Ok(loot)) => match self.fingerprinter.report(&address, info) {
+                Some(info) => Ok(Some(
+                    info.into_iter().chain(loot.unwrap_or_default()).collect(),
+                )),
+                None => Ok(loot),
+            },
+            (_, res) => res,
         }
     }
 }
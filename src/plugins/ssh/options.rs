@@ -17,4 +17,16 @@ pub(crate) struct Options {
     #[clap(long)]
     /// Optional private key passphrase for key based authentication.
     pub ssh_key_passphrase: Option<String>,
+    #[clap(long)]
+    /// File with the passphrases to try, one per line, for encrypted private keys.
+    pub ssh_key_passphrases: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Do not fall back to keyboard-interactive authentication (and second factor detection) when the password is rejected.
+    pub ssh_no_keyboard_interactive: bool,
+    #[clap(long, default_value_t = false)]
+    /// Do not record the banner, host key and offered algorithms of each target.
+    pub ssh_no_fingerprint: bool,
+    #[clap(long)]
+    /// Harmless command to run after a successful authentication (for instance 'id') to tell real shells from restricted accounts and honeypots.
+    pub ssh_verify_cmd: Option<String>,
 }
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use russh::client::Msg;
+use russh::{Channel, ChannelMsg};
+
+use crate::session::Error;
+
+// enough for id, whoami, uname and the like
+const MAX_OUTPUT_SIZE: usize = 4096;
+const MAX_REPORTED_SIZE: usize = 512;
+
+// messages printed by nologin, false and the sftp/scp only wrappers
+const RESTRICTED_HINTS: &[&str] = &[
+    "account is currently not available",
+    "not allowed",
+    "sftp connections only",
+    "only scp",
+    "restricted",
+    "forced command",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Execution {
+    pub output: String,
+    pub exit_status: Option<u32>,
+}
+
+pub(crate) async fn execute(mut channel: Channel<Msg>, command: &str) -> Result<Execution, Error> {
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut output = vec![];
+    let mut exit_status = None;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Failure => return Err("command execution refused".to_owned()),
+            ChannelMsg::Data { ref data } | ChannelMsg::ExtendedData { ref data, .. }
+                if output.len() < MAX_OUTPUT_SIZE =>
+            {
+                output.extend_from_slice(data)
+            }
+            ChannelMsg::ExitStatus { exit_status: status } => exit_status = Some(status),
+            _ => {}
+        }
+    }
+
+    Ok(Execution {
+        output: String::from_utf8_lossy(&output).trim().to_owned(),
+        exit_status,
+    })
+}
+
+// what the command output tells about the account
+pub(crate) fn verdict(execution: &Execution, username: &str) -> &'static str {
+    let output = execution.output.to_lowercase();
+    if RESTRICTED_HINTS.iter().any(|hint| output.contains(hint)) {
+        "restricted"
+    } else if execution.exit_status != Some(0) {
+        // /bin/false and forced commands rejecting the request
+        if output.is_empty() {
+            "no shell"
+        } else {
+            "restricted"
+        }
+    } else if output.starts_with("uid=") && !output.contains(&format!("({})", username.to_lowercase()))
+    {
+        // id reporting another user, as honeypots mapping every login to the same account do
+        "suspicious"
+    } else {
+        "shell"
+    }
+}
+
+// loot entries for the verification command, errors are reported as such
+pub(crate) async fn verify(
+    channel: Result<Channel<Msg>, Error>,
+    command: &str,
+    username: &str,
+    timeout: Duration,
+) -> Vec<(String, String)> {
+    let mut data = vec![("command".to_owned(), command.to_owned())];
+    let execution = match channel {
+        Ok(channel) => tokio::time::timeout(timeout, execute(channel, command))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|res| res),
+        Err(e) => Err(e),
+    };
+
+    match execution {
+        Ok(execution) => {
+            let shell = verdict(&execution, username);
+            let output: String = execution
+                .output
+                .lines()
+                .collect::<Vec<&str>>()
+                .join(" | ")
+                .chars()
+                .take(MAX_REPORTED_SIZE)
+                .collect();
+            data.push(("output".to_owned(), output));
+            if let Some(status) = execution.exit_status {
+                data.push(("exit_status".to_owned(), status.to_string()));
+            }
+            data.push(("shell".to_owned(), shell.to_owned()));
+        }
+        Err(e) => {
+            log::debug!("can't run '{}' as {}: {}", command, username, e);
+            data.push(("shell".to_owned(), "no exec".to_owned()));
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verdict, Execution};
+
+    fn execution(output: &str, exit_status: Option<u32>) -> Execution {
+        Execution {
+            output: output.to_owned(),
+            exit_status,
+        }
+    }
+
+    #[test]
+    fn can_tell_shells_apart() {
+        assert_eq!(
+            verdict(
+                &execution("uid=1000(admin) gid=1000(admin) groups=1000(admin)", Some(0)),
+                "admin"
+            ),
+            "shell"
+        );
+        assert_eq!(
+            verdict(&execution("uid=0(root) gid=0(root) groups=0(root)", Some(0)), "admin"),
+            "suspicious"
+        );
+        assert_eq!(
+            verdict(
+                &execution("This account is currently not available.", Some(1)),
+                "www-data"
+            ),
+            "restricted"
+        );
+        assert_eq!(
+            verdict(&execution("This service allows sftp connections only.", Some(1)), "backup"),
+            "restricted"
+        );
+        assert_eq!(verdict(&execution("", Some(1)), "nobody"), "no shell");
+        assert_eq!(verdict(&execution("", None), "nobody"), "no shell");
+        assert_eq!(verdict(&execution("Linux", Some(0)), "nobody"), "shell");
+    }
+}
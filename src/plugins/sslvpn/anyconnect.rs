@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::Client;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::xml::xml_escape;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::Outcome;
+
+const CLIENT_VERSION: &str = "4.10.07061";
+
+fn init_request(base: &str, group: Option<&str>) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><config-auth client="vpn" type="init" aggregate-auth-version="2"><version who="vpn">{}</version><device-id>win</device-id><group-access>{}/{}</group-access></config-auth>"#,
+        CLIENT_VERSION,
+        base,
+        xml_escape(group.unwrap_or_default())
+    )
+}
+
+// the opaque element of the auth request must be sent back as is
+fn auth_reply(opaque: &str, group: Option<&str>, username: &str, password: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><config-auth client="vpn" type="auth-reply" aggregate-auth-version="2"><version who="vpn">{}</version><device-id>win</device-id>{}<auth><username>{}</username><password>{}</password></auth>{}</config-auth>"#,
+        CLIENT_VERSION,
+        opaque,
+        xml_escape(username),
+        xml_escape(password),
+        group
+            .map(|group| format!("<group-select>{}</group-select>", xml_escape(group)))
+            .unwrap_or_default()
+    )
+}
+
+fn find(body: &str, pattern: &str) -> Option<String> {
+    let re = Regex::new(pattern).unwrap();
+    let found = re.captures(body).map(|caps| caps[1].to_owned());
+    found
+}
+
+fn classify(body: &str) -> Result<Outcome, Error> {
+    if body.contains(r#"type="complete""#) {
+        Ok(Outcome::Valid)
+    } else if body.contains(r#"id="passwordexpired""#) || body.contains("new_password") {
+        Ok(Outcome::PasswordExpired)
+    } else if body.contains("<error") {
+        // <error id="88" param1="" param2="">Login failed.</error>
+        Ok(Outcome::Invalid)
+    } else if body.contains(r#"id="challenge""#) || body.contains(r#"name="answer""#) {
+        Ok(Outcome::MfaRequired)
+    } else if body.contains(r#"id="main""#) {
+        Ok(Outcome::Invalid)
+    } else {
+        Err("unexpected auth reply response".to_owned())
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct AnyConnect {
+    group: Option<String>,
+}
+
+impl AnyConnect {
+    pub fn new() -> Self {
+        AnyConnect { group: None }
+    }
+
+    async fn post(
+        &self,
+        client: &Client,
+        base: &str,
+        body: String,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        client
+            .post(format!("{}/", base))
+            .header("User-Agent", format!("AnyConnect Windows {}", CLIENT_VERSION))
+            .header("X-Transcend-Version", "1")
+            .header("X-Aggregate-Auth", "1")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl Plugin for AnyConnect {
+    fn description(&self) -> &'static str {
+        "Cisco AnyConnect SSL-VPN aggregate authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.group.clone_from(&opts.sslvpn.anyconnect_group);
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 443)?;
+        let base = format!("https://{}", &address);
+        let client = super::client()?;
+        let group = self.group.as_deref();
+
+        let request = self
+            .post(&client, &base, init_request(&base, group), timeout)
+            .await?;
+        if !request.contains("<config-auth") {
+            return Err(format!("{} is not an AnyConnect server", &address));
+        } else if request.contains("<sso-v2-login>") {
+            return Err("the tunnel group requires SAML authentication".to_owned());
+        }
+        let opaque = find(&request, r"(?s)(<opaque\b.*?</opaque>)").unwrap_or_default();
+
+        let reply = self
+            .post(
+                &client,
+                &base,
+                auth_reply(&opaque, group, &creds.username, &creds.password),
+                timeout,
+            )
+            .await?;
+        let outcome = classify(&reply)?;
+
+        let mut extra = vec![];
+        if let Some(group) = group {
+            extra.push(("group".to_owned(), group.to_owned()));
+        }
+        if let Some(version) = find(&request, r#"<version who="sg">([^<]+)</version>"#) {
+            extra.push(("version".to_owned(), version));
+        }
+        if let Some(token) = find(&reply, r"<session-token>([^<]+)</session-token>") {
+            // free the session
+            let logout = format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><config-auth client="vpn" type="logout"><session-token>{}</session-token></config-auth>"#,
+                token
+            );
+            let _ = self.post(&client, &base, logout, timeout).await;
+        }
+
+        Ok(super::loot("anyconnect", &address, creds, &outcome, extra))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auth_reply, classify, find, Outcome};
+
+    #[test]
+    fn can_classify_auth_replies() {
+        let request = r#"<?xml version="1.0" encoding="UTF-8"?>
+<config-auth client="vpn" type="auth-request" aggregate-auth-version="2">
+<opaque is-for="sg">
+<tunnel-group>Employees</tunnel-group>
+<config-hash>1595829378234</config-hash>
+</opaque>
+<auth id="main"><form><input type="text" name="username" label="Username:"></input>
+<input type="password" name="password" label="Password:"></input></form></auth>
+<version who="sg">9.16(3)19</version>
+</config-auth>"#;
+        let opaque = find(request, r"(?s)(<opaque\b.*?</opaque>)").unwrap();
+        assert!(opaque.starts_with(r#"<opaque is-for="sg">"#));
+        assert_eq!(
+            find(request, r#"<version who="sg">([^<]+)</version>"#),
+            Some("9.16(3)19".to_owned())
+        );
+        assert!(auth_reply(&opaque, Some("Employees"), "a&b", "<p>")
+            .contains("<username>a&amp;b</username><password>&lt;p&gt;</password></auth><group-select>Employees</group-select>"));
+
+        assert_eq!(
+            classify(r#"<config-auth client="vpn" type="complete"><session-token>ABC</session-token></config-auth>"#),
+            Ok(Outcome::Valid)
+        );
+        assert_eq!(
+            classify(&request.replace(
+                "<auth id=\"main\">",
+                "<auth id=\"main\"><error id=\"88\" param1=\"\" param2=\"\">Login failed.</error>"
+            )),
+            Ok(Outcome::Invalid)
+        );
+        assert_eq!(
+            classify(r#"<config-auth type="auth-request"><auth id="challenge"><form><input type="password" name="answer"></input></form></auth></config-auth>"#),
+            Ok(Outcome::MfaRequired)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::Outcome;
+
+// classifies the ret=...,redir=... body of /remote/logincheck
+fn classify(body: &str, session_cookie: bool) -> Result<Outcome, Error> {
+    let body = body.trim();
+    if body.contains("tokeninfo=") || body.starts_with("ret=2") {
+        // FortiToken, email or sms code requested
+        Ok(Outcome::MfaRequired)
+    } else if body.contains("pass_expired") || body.contains("chg_pwd") {
+        Ok(Outcome::PasswordExpired)
+    } else if body.contains("max_attempts") || body.contains("login_blocked") {
+        Err("too many failed logins, the source address is temporarily blocked".to_owned())
+    } else if session_cookie
+        || (body.starts_with("ret=1") && !body.contains("redir=/remote/login"))
+    {
+        Ok(Outcome::Valid)
+    } else if body.starts_with("ret=0") || body.contains("permission_denied") {
+        Ok(Outcome::Invalid)
+    } else {
+        Err(format!(
+            "unexpected logincheck response: {}",
+            body.lines().next().unwrap_or_default()
+        ))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Fortinet {
+    realm: String,
+}
+
+impl Fortinet {
+    pub fn new() -> Self {
+        Fortinet {
+            realm: String::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Fortinet {
+    fn description(&self) -> &'static str {
+        "FortiGate SSL-VPN portal authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.realm.clone_from(&opts.sslvpn.fortinet_realm);
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 443)?;
+        let base = format!("https://{}", &address);
+        let client = super::client()?;
+
+        let response = client
+            .post(format!("{}/remote/logincheck", &base))
+            .form(&[
+                ("ajax", "1"),
+                ("username", creds.username.as_str()),
+                ("realm", self.realm.as_str()),
+                ("credential", creds.password.as_str()),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        // an empty SVPNCOOKIE clears the session
+        let session_cookie = response
+            .cookies()
+            .any(|c| c.name() == "SVPNCOOKIE" && !c.value().is_empty());
+        let body = response.text().await.map_err(|e| e.to_string())?;
+
+        let outcome = classify(&body, session_cookie)?;
+        if outcome == Outcome::Valid {
+            // free the tunnel slot
+            let _ = client
+                .get(format!("{}/remote/logout", &base))
+                .timeout(timeout)
+                .send()
+                .await;
+        }
+
+        Ok(super::loot("fortinet", &address, creds, &outcome, vec![]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, Outcome};
+
+    #[test]
+    fn can_classify_logincheck_responses() {
+        assert_eq!(
+            classify("ret=1,redir=/remote/fortisslvpn_xml", true),
+            Ok(Outcome::Valid)
+        );
+        assert_eq!(
+            classify("ret=1,redir=/remote/hostcheck_install?auth_type=1", false),
+            Ok(Outcome::Valid)
+        );
+        assert_eq!(
+            classify(
+                "ret=0,redir=/remote/login?&err=sslvpn_login_permission_denied&lang=en",
+                false
+            ),
+            Ok(Outcome::Invalid)
+        );
+        assert_eq!(
+            classify(
+                "ret=2,actionurl=/remote/logincheck,method=post,tokeninfo=fac_radius_token",
+                false
+            ),
+            Ok(Outcome::MfaRequired)
+        );
+        assert!(classify("<html>", false).is_err());
+    }
+}
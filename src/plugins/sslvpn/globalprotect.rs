@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::StatusCode;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::Outcome;
+
+fn classify(status: StatusCode, body: &str) -> Result<Outcome, Error> {
+    let compact: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.contains("respStatus=\"Challenge\"") {
+        // the javascript challenge page asks for an OTP
+        Ok(Outcome::MfaRequired)
+    } else if body.contains("<policy") || body.contains("<jnlp") {
+        Ok(Outcome::Valid)
+    } else if body.to_lowercase().contains("password expired") {
+        Ok(Outcome::PasswordExpired)
+    } else if status.as_u16() == 512
+        || body.contains("Invalid username or password")
+        || body.contains("Authentication failed")
+        || compact.contains("respStatus=\"Error\"")
+    {
+        Ok(Outcome::Invalid)
+    } else if body.contains("saml-auth-method") || body.contains("prelogin-cookie") {
+        Err("the portal requires SAML authentication".to_owned())
+    } else {
+        Err(format!("unexpected response ({})", status))
+    }
+}
+
+// external gateways listed by the portal configuration
+fn parse_gateways(config: &str) -> Vec<String> {
+    let Some(start) = config.find("<gateways>") else {
+        return vec![];
+    };
+    let end = config[start..]
+        .find("</gateways>")
+        .map(|end| start + end)
+        .unwrap_or(config.len());
+    let re = Regex::new(r#"<entry\s+name="([^"]+)""#).unwrap();
+    let found = re
+        .captures_iter(&config[start..end])
+        .map(|caps| caps[1].to_owned())
+        .collect();
+    found
+}
+
+#[derive(Clone)]
+pub(crate) struct GlobalProtect {
+    gateway: bool,
+}
+
+impl GlobalProtect {
+    pub fn new() -> Self {
+        GlobalProtect { gateway: false }
+    }
+}
+
+#[async_trait]
+impl Plugin for GlobalProtect {
+    fn description(&self) -> &'static str {
+        "Palo Alto GlobalProtect portal and gateway authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.gateway = opts.sslvpn.globalprotect_gateway;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 443)?;
+        let address = format!("{}:{}", host, port);
+        let path = if self.gateway {
+            "/ssl-vpn/login.esp"
+        } else {
+            "/global-protect/getconfig.esp"
+        };
+
+        let response = super::client()?
+            .post(format!("https://{}{}", &address, path))
+            .form(&[
+                ("prot", "https:"),
+                ("server", host.as_str()),
+                ("inputStr", ""),
+                ("jnlpReady", "jnlpReady"),
+                ("user", creds.username.as_str()),
+                ("passwd", creds.password.as_str()),
+                ("computer", "LEGBA"),
+                ("ok", "Login"),
+                ("direct", "yes"),
+                ("clientVer", "4100"),
+                ("os-version", "Microsoft Windows 10 Pro , 64-bit"),
+                ("clientos", "Windows"),
+                ("portal-userauthcookie", "empty"),
+                ("portal-prelogonuserauthcookie", "empty"),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+
+        let outcome = classify(status, &body)?;
+        let mut extra = vec![(
+            "endpoint".to_owned(),
+            if self.gateway { "gateway" } else { "portal" }.to_owned(),
+        )];
+        let gateways = parse_gateways(&body);
+        if !gateways.is_empty() {
+            extra.push(("gateways".to_owned(), gateways.join(", ")));
+        }
+
+        Ok(super::loot("globalprotect", &address, creds, &outcome, extra))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{classify, parse_gateways, Outcome};
+
+    #[test]
+    fn can_classify_login_responses() {
+        let config = r#"<?xml version="1.0" encoding="UTF-8" ?>
+        <policy><portal-name>GP-Portal</portal-name><gateways><external><list>
+            <entry name="vpn1.corp.com"><priority>1</priority></entry>
+            <entry name="vpn2.corp.com"><priority>2</priority></entry>
+        </list></external></gateways></policy>"#;
+        assert_eq!(classify(StatusCode::OK, config), Ok(Outcome::Valid));
+        assert_eq!(
+            parse_gateways(config),
+            vec!["vpn1.corp.com".to_owned(), "vpn2.corp.com".to_owned()]
+        );
+        assert_eq!(
+            classify(
+                StatusCode::OK,
+                r#"var respStatus = "Challenge"; var respMsg = "Enter the OTP"; thisForm.inputStr.value = "5ef64e83";"#
+            ),
+            Ok(Outcome::MfaRequired)
+        );
+        assert_eq!(
+            classify(
+                StatusCode::from_u16(512).unwrap(),
+                "Invalid username or password"
+            ),
+            Ok(Outcome::Invalid)
+        );
+        assert!(classify(StatusCode::OK, "<prelogin-response><saml-auth-method>REDIRECT</saml-auth-method></prelogin-response>").is_err());
+    }
+}
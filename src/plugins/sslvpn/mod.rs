@@ -0,0 +1,66 @@
+use reqwest::{redirect, Client};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+
+pub(crate) mod options;
+
+mod anyconnect;
+mod fortinet;
+mod globalprotect;
+
+super::manager::register_plugin! {
+    "fortinet" => fortinet::Fortinet::new(),
+    "globalprotect" => globalprotect::GlobalProtect::new(),
+    "anyconnect" => anyconnect::AnyConnect::new()
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Valid,
+    // valid password, a second factor is required
+    MfaRequired,
+    PasswordExpired,
+    Invalid,
+}
+
+impl Outcome {
+    fn status(&self) -> &'static str {
+        match self {
+            Outcome::Valid => "valid",
+            Outcome::MfaRequired => "mfa required",
+            Outcome::PasswordExpired => "password expired",
+            Outcome::Invalid => "invalid",
+        }
+    }
+}
+
+// portals use self signed certificates and session cookies, each attempt needs its own client
+fn client() -> Result<Client, Error> {
+    Client::builder()
+        .danger_accept_invalid_certs(true)
+        .cookie_store(true)
+        .redirect(redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn loot(
+    plugin: &str,
+    address: &str,
+    creds: &Credentials,
+    outcome: &Outcome,
+    extra: Vec<(String, String)>,
+) -> Option<Vec<Loot>> {
+    if *outcome == Outcome::Invalid {
+        return None;
+    }
+
+    let mut data = vec![
+        ("username".to_owned(), creds.username.to_owned()),
+        ("password".to_owned(), creds.password.to_owned()),
+        ("status".to_owned(), outcome.status().to_owned()),
+    ];
+    data.extend(extra);
+    Some(vec![Loot::new(plugin, address, data)])
+}
@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "")]
+    /// FortiGate SSL-VPN realm.
+    pub fortinet_realm: String,
+    #[clap(long, default_value_t = false)]
+    /// Authenticate against the GlobalProtect gateway instead of the portal.
+    pub globalprotect_gateway: bool,
+    #[clap(long)]
+    /// AnyConnect tunnel group to authenticate to (defaults to the one selected by the server).
+    pub anyconnect_group: Option<String>,
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+mod svnserve;
+
+super::manager::register_plugin! {
+    "svn" => Svn::new()
+}
+
+const SVNSERVE_PORT: u16 = 3690;
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><propfind xmlns="DAV:"><prop><resourcetype/></prop></propfind>"#;
+
+fn repositories(opts: &options::Options, target: &str) -> Result<Vec<String>, Error> {
+    let repos: Vec<String> = match opts.svn_repos.as_ref() {
+        Some(repos) => repos
+            .split(',')
+            .map(|repo| repo.trim().trim_matches('/').to_owned())
+            .filter(|repo| !repo.is_empty())
+            .collect(),
+        None => utils::parse_target_path(target).into_iter().collect(),
+    };
+    if repos.is_empty() {
+        Err("no repository to check, use --svn-repos or a target url with the repository path".to_owned())
+    } else {
+        Ok(repos)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Svn {
+    opts: options::Options,
+    client: Client,
+    // WebDAV repositories urls and whether they can be read anonymously
+    public: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl Svn {
+    pub fn new() -> Self {
+        Svn {
+            opts: options::Options::default(),
+            client: Client::new(),
+            public: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn dav_readable(
+        &self,
+        url: &str,
+        creds: Option<&Credentials>,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let mut request = self
+            .client
+            .request(Method::from_bytes(b"PROPFIND").unwrap(), url)
+            .header("Depth", "0")
+            .header("Content-Type", "text/xml")
+            .body(PROPFIND_BODY)
+            .timeout(timeout);
+        if let Some(creds) = creds {
+            request = request.basic_auth(&creds.username, Some(&creds.password));
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        Ok(response.status() == StatusCode::MULTI_STATUS)
+    }
+
+    // public repositories accept any credential, they're checked once
+    async fn dav_is_public(&self, url: &str, timeout: Duration) -> Result<bool, Error> {
+        let mut public = self.public.lock().await;
+        if let Some(is_public) = public.get(url) {
+            return Ok(*is_public);
+        }
+        let is_public = self.dav_readable(url, None, timeout).await?;
+        if is_public {
+            log::info!("{} can be read anonymously", url);
+        }
+        public.insert(url.to_owned(), is_public);
+        Ok(is_public)
+    }
+
+    async fn svnserve_readable(
+        &self,
+        address: &str,
+        repo: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let url = format!("svn://{}/{}", address, repo);
+        let stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+        let access = tokio::time::timeout(
+            timeout,
+            svnserve::Connection::new(stream).check(&url, &creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        if access == svnserve::Access::Anonymous {
+            log::debug!("{} can only be read anonymously", url);
+        }
+        Ok(access == svnserve::Access::Authenticated)
+    }
+}
+
+#[async_trait]
+impl Plugin for Svn {
+    fn description(&self) -> &'static str {
+        "Subversion WebDAV and svnserve authentication with repository read access check."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.svn.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let default_port = if creds.target.starts_with("https://") {
+            443
+        } else if creds.target.starts_with("http://") {
+            80
+        } else {
+            SVNSERVE_PORT
+        };
+        let (host, port) = utils::parse_target(&creds.target, default_port)?;
+        let address = format!("{}:{}", host, port);
+        // svn:// targets and port 3690 use the svnserve protocol, anything else WebDAV
+        let svnserve = port == SVNSERVE_PORT || creds.target.starts_with("svn://");
+        let ssl = self.opts.svn_ssl || port == 443 || creds.target.starts_with("https://");
+        let base = format!("{}://{}", if ssl { "https" } else { "http" }, &address);
+
+        let mut readable = vec![];
+        for repo in repositories(&self.opts, &creds.target)? {
+            let is_readable = if svnserve {
+                self.svnserve_readable(&address, &repo, creds, timeout)
+                    .await?
+            } else {
+                let url = format!("{}/{}/", &base, &repo);
+                !self.dav_is_public(&url, timeout).await?
+                    && self.dav_readable(&url, Some(creds), timeout).await?
+            };
+            if is_readable {
+                readable.push(repo);
+            }
+        }
+
+        if readable.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(vec![Loot::new(
+            "svn",
+            &address,
+            [
+                ("username".to_owned(), creds.username.to_owned()),
+                ("password".to_owned(), creds.password.to_owned()),
+                ("repositories".to_owned(), readable.join(", ")),
+            ],
+        )]))
+    }
+}
@@ -0,0 +1,335 @@
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+const CLIENT_CAPABILITIES: &str = "edit-pipeline svndiff1 absent-entries depth mergeinfo log-revprops";
+
+// ra_svn protocol data items
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Item {
+    Word(String),
+    Number(u64),
+    String(Vec<u8>),
+    List(Vec<Item>),
+}
+
+impl Item {
+    fn word(&self) -> Option<&str> {
+        match self {
+            Item::Word(word) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> Option<&[Item]> {
+        match self {
+            Item::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<String> {
+        match self {
+            Item::String(data) => Some(String::from_utf8_lossy(data).to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Access {
+    // the repository can be read without credentials
+    Anonymous,
+    Authenticated,
+    Denied,
+}
+
+fn string(value: &str) -> String {
+    format!("{}:{}", value.len(), value)
+}
+
+// parses an item from the beginning of data, returns None if more data is needed
+pub(crate) fn parse(data: &[u8]) -> Result<Option<(Item, usize)>, Error> {
+    let mut pos = 0;
+    while pos < data.len() && data[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    if pos == data.len() {
+        return Ok(None);
+    }
+
+    let token_end = |from: usize| {
+        data[from..]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .map(|end| from + end)
+    };
+
+    match data[pos] {
+        b'(' => {
+            pos += 1;
+            let mut items = vec![];
+            loop {
+                while pos < data.len() && data[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                match data.get(pos) {
+                    None => return Ok(None),
+                    Some(b')') => return Ok(Some((Item::List(items), pos + 1))),
+                    Some(_) => match parse(&data[pos..])? {
+                        None => return Ok(None),
+                        Some((item, used)) => {
+                            items.push(item);
+                            pos += used;
+                        }
+                    },
+                }
+            }
+        }
+        b'0'..=b'9' => {
+            let digits = data[pos..]
+                .iter()
+                .position(|b| !b.is_ascii_digit())
+                .map(|end| pos + end);
+            let Some(end) = digits else {
+                return Ok(None);
+            };
+            let number: u64 = std::str::from_utf8(&data[pos..end])
+                .unwrap()
+                .parse()
+                .map_err(|e: std::num::ParseIntError| e.to_string())?;
+            if data[end] == b':' {
+                let len = number as usize;
+                if len > MAX_MESSAGE_SIZE {
+                    return Err(format!("string too big: {} bytes", len));
+                }
+                let start = end + 1;
+                if data.len() < start + len {
+                    return Ok(None);
+                }
+                Ok(Some((Item::String(data[start..start + len].to_vec()), start + len)))
+            } else {
+                Ok(Some((Item::Number(number), end)))
+            }
+        }
+        b if b.is_ascii_alphabetic() => match token_end(pos) {
+            Some(end) => Ok(Some((
+                Item::Word(String::from_utf8_lossy(&data[pos..end]).to_string()),
+                end,
+            ))),
+            None => Ok(None),
+        },
+        b => Err(format!("unexpected byte in svnserve response: 0x{:02x}", b)),
+    }
+}
+
+pub(crate) struct Connection<S> {
+    stream: S,
+    buffer: Vec<u8>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            buffer: vec![],
+        }
+    }
+
+    async fn read(&mut self) -> Result<Item, Error> {
+        loop {
+            if let Some((item, used)) = parse(&self.buffer)? {
+                self.buffer.drain(..used);
+                return Ok(item);
+            }
+            if self.buffer.len() > MAX_MESSAGE_SIZE {
+                return Err("svnserve message too big".to_owned());
+            }
+
+            let mut chunk = [0u8; 4096];
+            let read = self
+                .stream
+                .read(&mut chunk)
+                .await
+                .map_err(|e| e.to_string())?;
+            if read == 0 {
+                return Err("connection closed".to_owned());
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    // reads a ( success ( params ) ) response, returns the params or None on failure
+    async fn read_response(&mut self) -> Result<Option<Vec<Item>>, Error> {
+        let item = self.read().await?;
+        let items = item.list().ok_or("unexpected svnserve response")?;
+        match items.first().and_then(|w| w.word()) {
+            Some("success") | Some("step") => Ok(Some(
+                items
+                    .get(1)
+                    .and_then(|p| p.list())
+                    .map(|p| p.to_vec())
+                    .unwrap_or_default(),
+            )),
+            Some("failure") => {
+                log::debug!("svnserve failure: {:?}", items.get(1));
+                Ok(None)
+            }
+            _ => Err(format!("unexpected svnserve response: {:?}", item)),
+        }
+    }
+
+    async fn write(&mut self, data: &str) -> Result<(), Error> {
+        self.stream
+            .write_all(data.as_bytes())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // handshakes for the repository url and authenticates with CRAM-MD5
+    pub async fn check(
+        &mut self,
+        url: &str,
+        username: &str,
+        password: &str,
+    ) -> Result<Access, Error> {
+        self.read_response()
+            .await?
+            .ok_or("svnserve greeting failed")?;
+        self.write(&format!(
+            "( 2 ( {} ) {} ) ",
+            CLIENT_CAPABILITIES,
+            string(url)
+        ))
+        .await?;
+
+        // ( success ( ( mechanisms ) realm ) )
+        let Some(params) = self.read_response().await? else {
+            return Err(format!("{} is not a valid repository", url));
+        };
+        let mechanisms: Vec<&str> = params
+            .first()
+            .and_then(|m| m.list())
+            .map(|m| m.iter().filter_map(|m| m.word()).collect())
+            .unwrap_or_default();
+
+        let access = if mechanisms.contains(&"CRAM-MD5") {
+            self.write("( CRAM-MD5 ( ) ) ").await?;
+            let challenge = self
+                .read_response()
+                .await?
+                .and_then(|p| p.first().and_then(|c| c.string()))
+                .ok_or("missing CRAM-MD5 challenge")?;
+            self.write(&format!("{} ", string(&cram_md5(username, password, &challenge))))
+                .await?;
+            if self.read_response().await?.is_none() {
+                return Ok(Access::Denied);
+            }
+            Access::Authenticated
+        } else if mechanisms.contains(&"ANONYMOUS") {
+            self.write("( ANONYMOUS ( 0: ) ) ").await?;
+            if self.read_response().await?.is_none() {
+                return Ok(Access::Denied);
+            }
+            Access::Anonymous
+        } else {
+            return Err(format!("unsupported mechanisms: {:?}", mechanisms));
+        };
+
+        // repository information, the authorization rules might still deny the access
+        Ok(match self.read_response().await? {
+            Some(_) => access,
+            None => Access::Denied,
+        })
+    }
+}
+
+fn cram_md5(username: &str, password: &str, challenge: &str) -> String {
+    let mut mac = Hmac::<Md5>::new_from_slice(password.as_bytes()).unwrap();
+    mac.update(challenge.as_bytes());
+    format!("{} {}", username, hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cram_md5, parse, Access, Connection, Item};
+
+    #[test]
+    fn can_parse_items() {
+        assert_eq!(
+            parse(b"( success ( 2 2 ( ) ( edit-pipeline ) ) ) ").unwrap(),
+            Some((
+                Item::List(vec![
+                    Item::Word("success".to_owned()),
+                    Item::List(vec![
+                        Item::Number(2),
+                        Item::Number(2),
+                        Item::List(vec![]),
+                        Item::List(vec![Item::Word("edit-pipeline".to_owned())]),
+                    ]),
+                ]),
+                41
+            ))
+        );
+        assert_eq!(parse(b"( success ( 5:ab").unwrap(), None);
+        assert_eq!(
+            parse(b"5:a b) ").unwrap(),
+            Some((Item::String(b"a b) ".to_vec()), 7))
+        );
+
+        // RFC 2195 test vector
+        assert_eq!(
+            cram_md5(
+                "tim",
+                "tanstaaftanstaaf",
+                "<1896.697170952@postoffice.reston.mci.net>"
+            ),
+            "tim b913a602c7eda7a495b4e6e7334d3890"
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let url = "svn://127.0.0.1/repo";
+        let greeting = b"( success ( 2 2 ( ) ( edit-pipeline svndiff1 ) ) ) ";
+        let auth_request = b"( success ( ( ANONYMOUS CRAM-MD5 ) 36:e0c3b1e4-2a4b-4c1d-9d3e-6c0a1b2c3d4e ) ) ";
+        let client_response = format!(
+            "( 2 ( {} ) {}:{} ) ",
+            super::CLIENT_CAPABILITIES,
+            url.len(),
+            url
+        );
+        let response = format!("{} ", super::string(&cram_md5("harry", "secret", "<1.2@host>")));
+
+        let stream = tokio_test::io::Builder::new()
+            .read(greeting)
+            .write(client_response.as_bytes())
+            .read(auth_request)
+            .write(b"( CRAM-MD5 ( ) ) ")
+            .read(b"( step ( 10:<1.2@host> ) ) ")
+            .write(response.as_bytes())
+            .read(b"( success ( ) ) ( success ( 36:e0c3b1e4-2a4b-4c1d-9d3e-6c0a1b2c3d4e 20:svn://127.0.0.1/repo ( mergeinfo ) ) ) ")
+            .build();
+        assert_eq!(
+            Connection::new(stream).check(url, "harry", "secret").await,
+            Ok(Access::Authenticated)
+        );
+
+        let stream = tokio_test::io::Builder::new()
+            .read(greeting)
+            .write(client_response.as_bytes())
+            .read(auth_request)
+            .write(b"( CRAM-MD5 ( ) ) ")
+            .read(b"( step ( 10:<1.2@host> ) ) ")
+            .write(response.as_bytes())
+            .read(b"( failure ( 21:incorrect credentials ) ) ")
+            .build();
+        assert_eq!(
+            Connection::new(stream).check(url, "harry", "secret").await,
+            Ok(Access::Denied)
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use self::options::Auth;
+
+pub(crate) mod options;
+mod protocol;
+
+super::manager::register_plugin! {
+    "tacacs" => Tacacs::new()
+}
+
+#[derive(Clone)]
+pub(crate) struct Tacacs {
+    secret: String,
+    auth: Auth,
+}
+
+impl Tacacs {
+    pub fn new() -> Self {
+        Tacacs {
+            secret: String::new(),
+            auth: Auth::Ascii,
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for Tacacs {
+    fn description(&self) -> &'static str {
+        "TACACS+ ASCII and PAP authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.secret = opts.tacacs.tacacs_secret.clone();
+        self.auth = opts.tacacs.tacacs_auth;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 49)?;
+        let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let session_id: u32 = rand::thread_rng().gen();
+
+        let outcome = tokio::time::timeout(
+            timeout,
+            protocol::authenticate(
+                &mut stream,
+                session_id,
+                &self.secret,
+                self.auth,
+                &creds.username,
+                &creds.password,
+            ),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        match outcome {
+            protocol::Outcome::Invalid => Ok(None),
+            protocol::Outcome::Valid(message) => {
+                let mut data = vec![
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                    ("auth".to_owned(), format!("{:?}", self.auth).to_uppercase()),
+                ];
+                if let Some(message) = message {
+                    data.push(("server_message".to_owned(), message));
+                }
+                Ok(Some(vec![Loot::new("tacacs", &address, data)]))
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Auth {
+    #[default]
+    Ascii,
+    Pap,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "")]
+    /// Shared secret between the TACACS+ server and the client, leave empty for unencrypted sessions.
+    pub tacacs_secret: String,
+    #[clap(long, value_enum, default_value_t = Auth::Ascii)]
+    /// TACACS+ authentication type.
+    pub tacacs_auth: Auth,
+}
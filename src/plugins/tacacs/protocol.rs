@@ -0,0 +1,277 @@
+use md5::{Digest, Md5};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+use super::options::Auth;
+
+const VERSION_DEFAULT: u8 = 0xc0;
+const VERSION_ONE: u8 = 0xc1;
+const TYPE_AUTHEN: u8 = 0x01;
+const FLAG_UNENCRYPTED: u8 = 0x01;
+
+const ACTION_LOGIN: u8 = 0x01;
+const PRIV_LVL_USER: u8 = 0x01;
+const AUTHEN_TYPE_ASCII: u8 = 0x01;
+const AUTHEN_TYPE_PAP: u8 = 0x02;
+const AUTHEN_SVC_LOGIN: u8 = 0x01;
+
+const STATUS_PASS: u8 = 0x01;
+const STATUS_FAIL: u8 = 0x02;
+const STATUS_GETDATA: u8 = 0x03;
+const STATUS_GETUSER: u8 = 0x04;
+const STATUS_GETPASS: u8 = 0x05;
+const STATUS_ERROR: u8 = 0x07;
+
+const HEADER_SIZE: usize = 12;
+const MAX_BODY_SIZE: usize = 64 * 1024;
+// bound the ASCII login conversation
+const MAX_CONTINUES: usize = 5;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // server message, if any
+    Valid(Option<String>),
+    Invalid,
+}
+
+// RFC 8907 body obfuscation, applying it twice returns the clear text
+fn obfuscate(body: &mut [u8], session_id: u32, secret: &str, version: u8, seq_no: u8) {
+    if secret.is_empty() {
+        return;
+    }
+    let mut previous: Vec<u8> = vec![];
+    for chunk in body.chunks_mut(16) {
+        let mut hasher = Md5::new();
+        hasher.update(session_id.to_be_bytes());
+        hasher.update(secret.as_bytes());
+        hasher.update([version, seq_no]);
+        hasher.update(&previous);
+        previous = hasher.finalize().to_vec();
+        for (b, p) in chunk.iter_mut().zip(&previous) {
+            *b ^= p;
+        }
+    }
+}
+
+fn field(value: &str) -> &[u8] {
+    &value.as_bytes()[..value.len().min(255)]
+}
+
+struct Session<'a, S> {
+    stream: &'a mut S,
+    id: u32,
+    secret: &'a str,
+    version: u8,
+    seq_no: u8,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<'_, S> {
+    async fn send(&mut self, mut body: Vec<u8>) -> Result<(), Error> {
+        obfuscate(&mut body, self.id, self.secret, self.version, self.seq_no);
+        let flags = if self.secret.is_empty() {
+            FLAG_UNENCRYPTED
+        } else {
+            0
+        };
+        let mut packet = vec![self.version, TYPE_AUTHEN, self.seq_no, flags];
+        packet.extend_from_slice(&self.id.to_be_bytes());
+        packet.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        packet.extend(body);
+        self.stream
+            .write_all(&packet)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // returns status, server message and data of an authentication REPLY
+    async fn receive(&mut self) -> Result<(u8, String, Vec<u8>), Error> {
+        let mut header = [0u8; HEADER_SIZE];
+        self.stream
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| e.to_string())?;
+        let size = u32::from_be_bytes(header[8..12].try_into().unwrap()) as usize;
+        if size > MAX_BODY_SIZE {
+            return Err(format!("invalid TACACS+ body size {}", size));
+        }
+        let mut body = vec![0u8; size];
+        self.stream
+            .read_exact(&mut body)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        self.seq_no = header[2];
+        if header[3] & FLAG_UNENCRYPTED == 0 {
+            obfuscate(&mut body, self.id, self.secret, header[0], header[2]);
+        }
+        // the next client packet
+        self.seq_no = self.seq_no.wrapping_add(1);
+
+        // status, flags, server message length and data length
+        let invalid = || "invalid TACACS+ reply, wrong secret?".to_owned();
+        if body.len() < 6 {
+            return Err(invalid());
+        }
+        let msg_len = u16::from_be_bytes([body[2], body[3]]) as usize;
+        let data_len = u16::from_be_bytes([body[4], body[5]]) as usize;
+        if body.len() != 6 + msg_len + data_len {
+            return Err(invalid());
+        }
+        let message = String::from_utf8_lossy(&body[6..6 + msg_len]).to_string();
+        Ok((body[0], message, body[6 + msg_len..].to_vec()))
+    }
+
+    async fn start(&mut self, auth: Auth, username: &str, data: &str) -> Result<(), Error> {
+        let kind = match auth {
+            Auth::Ascii => AUTHEN_TYPE_ASCII,
+            Auth::Pap => AUTHEN_TYPE_PAP,
+        };
+        let (user, port, rem_addr, data) = (field(username), b"tty0", b"legba", field(data));
+        let mut body = vec![
+            ACTION_LOGIN,
+            PRIV_LVL_USER,
+            kind,
+            AUTHEN_SVC_LOGIN,
+            user.len() as u8,
+            port.len() as u8,
+            rem_addr.len() as u8,
+            data.len() as u8,
+        ];
+        body.extend_from_slice(user);
+        body.extend_from_slice(port);
+        body.extend_from_slice(rem_addr);
+        body.extend_from_slice(data);
+        self.send(body).await
+    }
+
+    async fn continue_with(&mut self, message: &str) -> Result<(), Error> {
+        let message = &message.as_bytes()[..message.len().min(0xffff)];
+        let mut body = (message.len() as u16).to_be_bytes().to_vec();
+        // no data and no abort flag
+        body.extend_from_slice(&[0, 0, 0]);
+        body.extend_from_slice(message);
+        self.send(body).await
+    }
+}
+
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    session_id: u32,
+    secret: &str,
+    auth: Auth,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    let mut session = Session {
+        stream,
+        id: session_id,
+        secret,
+        // PAP requires the minor version one
+        version: if auth == Auth::Pap {
+            VERSION_ONE
+        } else {
+            VERSION_DEFAULT
+        },
+        seq_no: 1,
+    };
+
+    match auth {
+        Auth::Pap => session.start(auth, username, password).await?,
+        Auth::Ascii => session.start(auth, username, "").await?,
+    }
+
+    for _ in 0..MAX_CONTINUES {
+        let (status, message, _) = session.receive().await?;
+        match status {
+            STATUS_PASS => {
+                return Ok(Outcome::Valid(if message.is_empty() {
+                    None
+                } else {
+                    Some(message)
+                }))
+            }
+            STATUS_FAIL => return Ok(Outcome::Invalid),
+            STATUS_GETUSER => session.continue_with(username).await?,
+            STATUS_GETPASS | STATUS_GETDATA => session.continue_with(password).await?,
+            STATUS_ERROR => return Err(format!("TACACS+ error: {}", message)),
+            other => return Err(format!("unexpected TACACS+ status 0x{:02x}", other)),
+        }
+    }
+
+    Err("too many TACACS+ continue requests".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, obfuscate, Outcome};
+    use crate::plugins::tacacs::options::Auth;
+
+    fn packet(version: u8, seq_no: u8, secret: &str, mut body: Vec<u8>) -> Vec<u8> {
+        obfuscate(&mut body, 0x01020304, secret, version, seq_no);
+        let mut out = vec![version, 1, seq_no, 0];
+        out.extend_from_slice(&0x01020304u32.to_be_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend(body);
+        out
+    }
+
+    fn reply(status: u8, message: &[u8]) -> Vec<u8> {
+        let mut body = vec![status, 0];
+        body.extend_from_slice(&(message.len() as u16).to_be_bytes());
+        body.extend_from_slice(&[0, 0]);
+        body.extend_from_slice(message);
+        body
+    }
+
+    #[test]
+    fn obfuscation_is_symmetric() {
+        let mut body = b"a body longer than a single md5 block".to_vec();
+        obfuscate(&mut body, 1, "secret", 0xc0, 1);
+        assert_ne!(&body[..], b"a body longer than a single md5 block");
+        obfuscate(&mut body, 1, "secret", 0xc0, 1);
+        assert_eq!(&body[..], b"a body longer than a single md5 block");
+    }
+
+    #[tokio::test]
+    async fn can_authenticate_ascii() {
+        let start = [
+            &[1u8, 1, 1, 1, 5, 4, 5, 0][..],
+            b"admin",
+            b"tty0",
+            b"legba",
+        ]
+        .concat();
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&packet(0xc0, 1, "key", start))
+            .read(&packet(0xc0, 2, "key", reply(5, b"Password: ")))
+            .write(&packet(0xc0, 3, "key", [&[0u8, 6, 0, 0, 0][..], b"cisco!"].concat()))
+            .read(&packet(0xc0, 4, "key", reply(1, b"")))
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, 0x01020304, "key", Auth::Ascii, "admin", "cisco!").await,
+            Ok(Outcome::Valid(None))
+        );
+    }
+
+    #[tokio::test]
+    async fn detects_wrong_secret() {
+        let start = [
+            &[1u8, 1, 2, 1, 5, 4, 5, 5][..],
+            b"admin",
+            b"tty0",
+            b"legba",
+            b"wrong",
+        ]
+        .concat();
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&packet(0xc1, 1, "key", start))
+            .read(&packet(0xc1, 2, "other", reply(2, b"")))
+            .build();
+        assert!(
+            authenticate(&mut stream, 0x01020304, "key", Auth::Pap, "admin", "wrong")
+                .await
+                .is_err()
+        );
+    }
+}
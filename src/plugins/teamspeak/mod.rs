@@ -0,0 +1,246 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+super::manager::register_plugin! {
+    "teamspeak" => TeamSpeak::new()
+}
+
+const OK: u32 = 0;
+const INVALID_LOGIN: u32 = 520;
+const ESCAPES: &[(char, &str)] = &[
+    ('\\', "\\\\"),
+    ('/', "\\/"),
+    (' ', "\\s"),
+    ('|', "\\p"),
+    ('\x07', "\\a"),
+    ('\x08', "\\b"),
+    ('\x0c', "\\f"),
+    ('\n', "\\n"),
+    ('\r', "\\r"),
+    ('\t', "\\t"),
+    ('\x0b', "\\v"),
+];
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match ESCAPES.iter().find(|(from, _)| *from == c) {
+            Some((_, to)) => escaped.push_str(to),
+            None => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            break;
+        };
+        match ESCAPES
+            .iter()
+            .find(|(_, to)| to.chars().nth(1) == Some(next))
+        {
+            Some((from, _)) => unescaped.push(*from),
+            None => unescaped.push(next),
+        }
+    }
+    unescaped
+}
+
+// key=value pairs of a ServerQuery line
+fn parse_properties(line: &str) -> Vec<(String, String)> {
+    line.split(' ')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            Some((key.to_owned(), unescape(value)))
+        })
+        .collect()
+}
+
+// lines are terminated by \n\r, so the \r ends up at the start of the next one
+async fn read_line<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<String, Error> {
+    let mut line = String::new();
+    if reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?
+        == 0
+    {
+        return Err("connection closed".to_owned());
+    }
+    Ok(line.trim_matches(|c| c == '\r' || c == '\n').to_owned())
+}
+
+async fn greeting<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<(), Error> {
+    let banner = read_line(reader).await?;
+    if banner != "TS3" {
+        return Err(format!("not a TeamSpeak 3 ServerQuery interface: {}", banner));
+    }
+    // welcome message
+    read_line(reader).await?;
+    Ok(())
+}
+
+// returns the data lines and the error id of a command
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    command: &str,
+) -> Result<(Vec<String>, u32, String), Error> {
+    reader
+        .get_mut()
+        .write_all(format!("{}\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut lines = vec![];
+    loop {
+        let line = read_line(reader).await?;
+        if let Some(status) = line.strip_prefix("error ") {
+            let properties = parse_properties(status);
+            let get = |key: &str| {
+                properties
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.to_owned())
+            };
+            let id = get("id")
+                .and_then(|id| id.parse::<u32>().ok())
+                .ok_or(format!("unexpected ServerQuery status: {}", line))?;
+            return Ok((lines, id, get("msg").unwrap_or_default()));
+        } else if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+}
+
+async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    reader: &mut BufReader<S>,
+    username: &str,
+    password: &str,
+) -> Result<bool, Error> {
+    match command(
+        reader,
+        &format!(
+            "login client_login_name={} client_login_password={}",
+            escape(username),
+            escape(password)
+        ),
+    )
+    .await?
+    {
+        (_, OK, _) => Ok(true),
+        (_, INVALID_LOGIN, _) => Ok(false),
+        // flood protection bans, etc
+        (_, id, msg) => Err(format!("ServerQuery error {}: {}", id, msg)),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct TeamSpeak {}
+
+impl TeamSpeak {
+    pub fn new() -> Self {
+        TeamSpeak {}
+    }
+}
+
+#[async_trait]
+impl Plugin for TeamSpeak {
+    fn description(&self) -> &'static str {
+        "TeamSpeak 3 ServerQuery authentication."
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 10011)?;
+        let stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let mut reader = BufReader::new(stream);
+
+        let version = tokio::time::timeout(timeout, async {
+            greeting(&mut reader).await?;
+            if !login(&mut reader, &creds.username, &creds.password).await? {
+                return Ok(None);
+            }
+            let (lines, _, _) = command(&mut reader, "version").await?;
+            Ok::<_, Error>(Some(
+                lines
+                    .first()
+                    .map(|line| parse_properties(line))
+                    .unwrap_or_default(),
+            ))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let Some(version) = version else {
+            return Ok(None);
+        };
+
+        let _ = command(&mut reader, "quit").await;
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        data.extend(version);
+        Ok(Some(vec![Loot::new("teamspeak", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::BufReader;
+
+    use super::{escape, greeting, login, parse_properties};
+
+    #[test]
+    fn can_escape_values() {
+        assert_eq!(escape(r"p4ss w/rd|\"), r"p4ss\sw\/rd\p\\");
+        assert_eq!(
+            parse_properties(r"version=3.13.7 build=1655727713 platform=Linux\sx86"),
+            vec![
+                ("version".to_owned(), "3.13.7".to_owned()),
+                ("build".to_owned(), "1655727713".to_owned()),
+                ("platform".to_owned(), "Linux x86".to_owned()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn can_login() {
+        let stream = tokio_test::io::Builder::new()
+            .read(b"TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r")
+            .write(b"login client_login_name=serveradmin client_login_password=wrong\n")
+            .read(b"error id=520 msg=invalid\\sloginname\\sor\\spassword\n\r")
+            .write(b"login client_login_name=serveradmin client_login_password=s3cr\\set\n")
+            .read(b"error id=0 msg=ok\n\r")
+            .build();
+        let mut reader = BufReader::new(stream);
+        assert_eq!(greeting(&mut reader).await, Ok(()));
+        assert_eq!(login(&mut reader, "serveradmin", "wrong").await, Ok(false));
+        assert_eq!(
+            login(&mut reader, "serveradmin", "s3cr et").await,
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const MAX_OUTPUT: usize = 64 * 1024;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const BINARY: u8 = 0;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const TERMINAL_TYPE: u8 = 24;
+const WINDOW_SIZE: u8 = 31;
+
+// terminal type subnegotiation
+const TTYPE_IS: u8 = 0;
+const TTYPE_SEND: u8 = 1;
+const TERMINAL: &[u8] = b"VT100";
+
+pub(crate) const DEFAULT_LOGIN: &str = r"(?i)(login|user\s*name|user|account)\s*:\s*$";
+pub(crate) const DEFAULT_PASSWORD: &str = r"(?i)pass(word|code|phrase)?\s*:\s*$";
+pub(crate) const DEFAULT_SUCCESS: &str = r"[$#>%]\s*$";
+pub(crate) const DEFAULT_FAILURE: &str =
+    r"(?i)(incorrect|invalid|fail(ed|ure)|denied|bad (password|user)|wrong|not allowed)";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Prompt {
+    Login,
+    Password,
+    Success,
+    Failure,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Prompts {
+    pub login: Vec<Regex>,
+    pub password: Vec<Regex>,
+    pub success: Vec<Regex>,
+    pub failure: Vec<Regex>,
+}
+
+impl Default for Prompts {
+    fn default() -> Self {
+        Self {
+            login: vec![Regex::new(DEFAULT_LOGIN).unwrap()],
+            password: vec![Regex::new(DEFAULT_PASSWORD).unwrap()],
+            success: vec![Regex::new(DEFAULT_SUCCESS).unwrap()],
+            failure: vec![Regex::new(DEFAULT_FAILURE).unwrap()],
+        }
+    }
+}
+
+impl Prompts {
+    // state of the session given the (ansi stripped) output received so far, the prompts
+    // are matched against the last line while failures anywhere in the output, once the
+    // credentials are sent since banners often contain the same words
+    pub fn detect(&self, output: &str, sent_credentials: bool) -> Option<Prompt> {
+        let last = output.lines().last().unwrap_or_default();
+        let matches = |regexes: &[Regex], text: &str| regexes.iter().any(|r| r.is_match(text));
+
+        if sent_credentials && matches(&self.failure, output) {
+            Some(Prompt::Failure)
+        } else if matches(&self.password, last) {
+            Some(Prompt::Password)
+        } else if matches(&self.login, last) {
+            Some(Prompt::Login)
+        } else if matches(&self.success, last) {
+            Some(Prompt::Success)
+        } else {
+            None
+        }
+    }
+}
+
+// telnet options state, to reply only once to each request and avoid negotiation loops
+#[derive(Default)]
+struct Negotiation {
+    replied: HashSet<(u8, u8)>,
+}
+
+impl Negotiation {
+    fn reply(&mut self, command: u8, option: u8) -> Vec<u8> {
+        let reply = match command {
+            WILL if matches!(option, BINARY | ECHO | SUPPRESS_GO_AHEAD) => DO,
+            WILL => DONT,
+            DO if matches!(option, BINARY | SUPPRESS_GO_AHEAD | TERMINAL_TYPE | WINDOW_SIZE) => {
+                WILL
+            }
+            DO => WONT,
+            // acknowledge the disabling only if the option was enabled
+            WONT if self.replied.contains(&(WILL, option)) => DONT,
+            DONT if self.replied.contains(&(DO, option)) => WONT,
+            _ => return vec![],
+        };
+
+        if !self.replied.insert((command, option)) {
+            return vec![];
+        }
+
+        let mut data = vec![IAC, reply, option];
+        if command == DO && option == WINDOW_SIZE {
+            // 80x24
+            data.extend([IAC, SB, WINDOW_SIZE, 0, 80, 0, 24, IAC, SE]);
+        }
+        data
+    }
+
+    fn subnegotiation(&mut self, data: &[u8]) -> Vec<u8> {
+        match data {
+            [TERMINAL_TYPE, TTYPE_SEND, ..] => {
+                let mut reply = vec![IAC, SB, TERMINAL_TYPE, TTYPE_IS];
+                reply.extend(TERMINAL);
+                reply.extend([IAC, SE]);
+                reply
+            }
+            _ => vec![],
+        }
+    }
+}
+
+pub(crate) struct Client<S> {
+    stream: S,
+    prompts: Prompts,
+    negotiation: Negotiation,
+    // received bytes, including incomplete telnet commands
+    raw: Vec<u8>,
+    output: String,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
+    pub fn new(stream: S, prompts: Prompts) -> Self {
+        Self {
+            stream,
+            prompts,
+            negotiation: Negotiation::default(),
+            raw: vec![],
+            output: String::new(),
+        }
+    }
+
+    // strips the telnet commands from the received data, returns the replies to send
+    fn negotiate(&mut self) -> Vec<u8> {
+        let mut data = vec![];
+        let mut replies = vec![];
+        let mut i = 0;
+        while i < self.raw.len() {
+            let byte = self.raw[i];
+            if byte != IAC {
+                data.push(byte);
+                i += 1;
+                continue;
+            }
+
+            let Some(&command) = self.raw.get(i + 1) else {
+                break;
+            };
+            match command {
+                IAC => {
+                    data.push(IAC);
+                    i += 2;
+                }
+                WILL | WONT | DO | DONT => {
+                    let Some(&option) = self.raw.get(i + 2) else {
+                        break;
+                    };
+                    replies.extend(self.negotiation.reply(command, option));
+                    i += 3;
+                }
+                SB => match self.raw[i..].windows(2).position(|w| w == [IAC, SE]) {
+                    Some(end) => {
+                        replies.extend(self.negotiation.subnegotiation(&self.raw[i + 2..i + end]));
+                        i += end + 2;
+                    }
+                    None => break,
+                },
+                _ => i += 2,
+            }
+        }
+
+        self.raw.drain(..i);
+        // NUL bytes follow carriage returns
+        data.retain(|b| *b != 0);
+        self.output.push_str(&String::from_utf8_lossy(&data));
+        replies
+    }
+
+    async fn read_more(&mut self) -> Result<(), Error> {
+        let mut buf = [0u8; 1024];
+        let read = self
+            .stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed".to_owned());
+        }
+        self.raw.extend_from_slice(&buf[..read]);
+        let replies = self.negotiate();
+        if !replies.is_empty() {
+            self.stream
+                .write_all(&replies)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        if self.output.len() > MAX_OUTPUT {
+            return Err("too much output without a prompt".to_owned());
+        }
+        Ok(())
+    }
+
+    async fn read_prompt(&mut self, sent_credentials: bool) -> Result<Prompt, Error> {
+        loop {
+            let output = strip_ansi_escapes::strip_str(&self.output);
+            if let Some(prompt) = self.prompts.detect(&output, sent_credentials) {
+                self.output.clear();
+                return Ok(prompt);
+            }
+            self.read_more().await?;
+        }
+    }
+
+    async fn send(&mut self, line: &str) -> Result<(), Error> {
+        // escape IAC bytes in the data
+        let mut data = line.as_bytes().to_vec();
+        if data.contains(&IAC) {
+            data = data
+                .into_iter()
+                .flat_map(|b| if b == IAC { vec![IAC, IAC] } else { vec![b] })
+                .collect();
+        }
+        data.extend(b"\r\n");
+        self.stream
+            .write_all(&data)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // answers the login and password prompts, returns true if the shell prompt is reached
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<bool, Error> {
+        let (mut sent_username, mut sent_password) = (false, false);
+        loop {
+            let prompt = match self.read_prompt(sent_username || sent_password).await {
+                Ok(prompt) => prompt,
+                // servers often hang up after a failed attempt
+                Err(e) if sent_password && e == "connection closed" => return Ok(false),
+                Err(e) => return Err(e),
+            };
+            match prompt {
+                Prompt::Login if !sent_username => {
+                    self.send(username).await?;
+                    sent_username = true;
+                }
+                Prompt::Password if !sent_password => {
+                    self.send(password).await?;
+                    sent_password = true;
+                }
+                // some banners end like shell prompts
+                Prompt::Success if !sent_password => {}
+                Prompt::Success => return Ok(true),
+                // failure message or asked again
+                _ => return Ok(false),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Client, Prompt, Prompts};
+
+    #[test]
+    fn can_detect_prompts() {
+        let prompts = Prompts::default();
+        assert_eq!(
+            prompts.detect("\r\nUbuntu 22.04\r\nhost login: ", false),
+            Some(Prompt::Login)
+        );
+        assert_eq!(
+            prompts.detect("Unauthorized access denied\r\nUsername:", false),
+            Some(Prompt::Login)
+        );
+        assert_eq!(prompts.detect("Password: ", true), Some(Prompt::Password));
+        assert_eq!(prompts.detect("\r\nuser@host:~$ ", true), Some(Prompt::Success));
+        assert_eq!(
+            prompts.detect("\r\nBusyBox v1.30\r\n# ", true),
+            Some(Prompt::Success)
+        );
+        assert_eq!(
+            prompts.detect("\r\nLogin incorrect\r\nhost login: ", true),
+            Some(Prompt::Failure)
+        );
+        assert_eq!(prompts.detect("\r\nWelcome to ", false), None);
+    }
+
+    #[tokio::test]
+    async fn can_negotiate_and_login() {
+        let stream = tokio_test::io::Builder::new()
+            // will echo, will suppress go ahead, do terminal type, do linemode
+            .read(&[255, 251, 1, 255, 251, 3, 255, 253, 24, 255, 253, 34])
+            .write(&[255, 253, 1, 255, 253, 3, 255, 251, 24, 255, 252, 34])
+            // terminal type send
+            .read(&[255, 250, 24, 1, 255, 240])
+            .write(&[255, 250, 24, 0, b'V', b'T', b'1', b'0', b'0', 255, 240])
+            .read(b"\x1b[2J\x1b[1;1HRouter login: ")
+            .write(b"admin\r\n")
+            .read(b"Password: ")
+            .write(b"admin\r\n")
+            .read(b"\r\n\x1b[1;32mRouter\x1b[0m> ")
+            .build();
+        let mut client = Client::new(stream, Prompts::default());
+        assert_eq!(client.login("admin", "admin").await, Ok(true));
+
+        let stream = tokio_test::io::Builder::new()
+            .read(b"login: ")
+            .write(b"admin\r\n")
+            .read(b"Password: ")
+            .write(b"wrong\r\n")
+            .read(b"\r\nLogin incorrect\r\n")
+            .build();
+        let mut client = Client::new(stream, Prompts::default());
+        assert_eq!(client.login("admin", "wrong").await, Ok(false));
+    }
+}
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
+use regex::Regex;
 
 use crate::creds::Credentials;
 use crate::session::{Error, Loot};
@@ -8,6 +9,7 @@ use crate::utils;
 use crate::Options;
 use crate::Plugin;
 
+pub(crate) mod client;
 pub(crate) mod options;
 
 super::manager::register_plugin! {
@@ -16,31 +18,77 @@ super::manager::register_plugin! {
 
 #[derive(Clone)]
 pub(crate) struct Telnet {
-    user_prompt: String,
-    pass_prompt: String,
-    shell_prompt: String,
+    prompts: client::Prompts,
 }
 
 impl Telnet {
     pub fn new() -> Self {
         Telnet {
-            user_prompt: String::new(),
-            pass_prompt: String::new(),
-            shell_prompt: String::new(),
+            prompts: client::Prompts::default(),
         }
     }
 }
 
+fn build_prompts(opts: &options::Options) -> Result<client::Prompts, Error> {
+    let mut prompts = client::Prompts::default();
+    let mut custom = client::Prompts {
+        login: vec![],
+        password: vec![],
+        success: vec![],
+        failure: vec![],
+    };
+
+    for keyvalue in &opts.telnet_prompts {
+        let (kind, regex) = keyvalue
+            .split_once('=')
+            .ok_or(format!("invalid telnet prompt '{}', expected kind=regex", keyvalue))?;
+        let regex = Regex::new(regex).map_err(|e| format!("invalid {} regex: {}", kind, e))?;
+        match kind.trim().to_lowercase().as_str() {
+            "login" | "user" | "username" => custom.login.push(regex),
+            "password" | "pass" => custom.password.push(regex),
+            "success" | "prompt" | "shell" => custom.success.push(regex),
+            "failure" | "fail" => custom.failure.push(regex),
+            _ => return Err(format!("unknown telnet prompt kind '{}'", kind)),
+        }
+    }
+
+    // custom regexes replace the defaults of the same kind
+    for (default, custom) in [
+        (&mut prompts.login, custom.login),
+        (&mut prompts.password, custom.password),
+        (&mut prompts.success, custom.success),
+        (&mut prompts.failure, custom.failure),
+    ] {
+        if !custom.is_empty() {
+            *default = custom;
+        }
+    }
+
+    // literal prompts, matched at the end of the last line
+    for (regexes, literal) in [
+        (&mut prompts.login, &opts.telnet_user_prompt),
+        (&mut prompts.password, &opts.telnet_pass_prompt),
+        (&mut prompts.success, &opts.telnet_prompt),
+    ] {
+        if let Some(literal) = literal {
+            regexes.push(
+                Regex::new(&format!(r"{}\s*$", regex::escape(literal.trim_end())))
+                    .map_err(|e| e.to_string())?,
+            );
+        }
+    }
+
+    Ok(prompts)
+}
+
 #[async_trait]
 impl Plugin for Telnet {
     fn description(&self) -> &'static str {
-        "Telnet password authentication."
+        "Telnet password authentication with configurable prompts."
     }
 
     fn setup(&mut self, opts: &Options) -> Result<(), Error> {
-        self.user_prompt.clone_from(&opts.telnet.telnet_user_prompt);
-        self.pass_prompt.clone_from(&opts.telnet.telnet_pass_prompt);
-        self.shell_prompt.clone_from(&opts.telnet.telnet_prompt);
+        self.prompts = build_prompts(&opts.telnet)?;
         Ok(())
     }
 
@@ -50,16 +98,16 @@ impl Plugin for Telnet {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 23)?;
-        let mut client = mini_telnet::Telnet::builder()
-            .connect_timeout(Duration::from_secs(10))
-            .login_prompt(&self.user_prompt, &self.pass_prompt)
-            .prompt(&self.shell_prompt)
-            .timeout(timeout)
-            .connect(&address)
-            .await
-            .map_err(|e| e.to_string())?;
-
-        if client.login(&creds.username, &creds.password).await.is_ok() {
+        let stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+        let mut client = client::Client::new(stream, self.prompts.clone());
+        let logged_in = tokio::time::timeout(
+            timeout,
+            client.login(&creds.username, &creds.password),
+        )
+        .await
+        .map_err(|e| e.to_string())??;
+
+        if logged_in {
             Ok(Some(vec![Loot::new(
                 "telnet",
                 &address,
@@ -73,3 +121,34 @@ impl Plugin for Telnet {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::build_prompts;
+    use super::client::Prompt;
+    use super::options;
+
+    #[test]
+    fn can_build_custom_prompts() {
+        let opts = options::Options {
+            telnet_prompt: Some("router> ".to_owned()),
+            telnet_prompts: vec![
+                "login=(?i)enter user:\\s*$".to_owned(),
+                "failure=(?i)try again".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let prompts = build_prompts(&opts).unwrap();
+        assert_eq!(prompts.detect("Enter user: ", false), Some(Prompt::Login));
+        assert_eq!(prompts.detect("login: ", false), None);
+        assert_eq!(prompts.detect("Password: ", false), Some(Prompt::Password));
+        assert_eq!(prompts.detect("\r\nrouter>", true), Some(Prompt::Success));
+        assert_eq!(prompts.detect("Please try again\r\nEnter user: ", true), Some(Prompt::Failure));
+
+        assert!(build_prompts(&options::Options {
+            telnet_prompts: vec!["banner=.*".to_owned()],
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -4,13 +4,16 @@ use serde::{Deserialize, Serialize};
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 #[group(skip)]
 pub(crate) struct Options {
-    #[clap(long, default_value = "login: ")]
-    /// Telnet server username login prompt string.
-    pub telnet_user_prompt: String,
-    #[clap(long, default_value = "Password: ")]
-    /// Telnet server password login prompt string.
-    pub telnet_pass_prompt: String,
-    #[clap(long, default_value = ":~$ ")]
-    /// Telnet server shell prompt after successful login.
-    pub telnet_prompt: String,
+    #[clap(long)]
+    /// Telnet server username login prompt string, in addition to the login regexes.
+    pub telnet_user_prompt: Option<String>,
+    #[clap(long)]
+    /// Telnet server password login prompt string, in addition to the password regexes.
+    pub telnet_pass_prompt: Option<String>,
+    #[clap(long)]
+    /// Telnet server shell prompt after successful login, in addition to the success regexes.
+    pub telnet_prompt: Option<String>,
+    #[clap(long, num_args = 1..)]
+    /// Prompt regexes as login=<regex>, password=<regex>, success=<regex> or failure=<regex>, replacing the default ones of the same kind.
+    pub telnet_prompts: Vec<String>,
 }
@@ -0,0 +1,357 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+pub(crate) const SSL3: u16 = 0x0300;
+pub(crate) const TLS10: u16 = 0x0301;
+pub(crate) const TLS11: u16 = 0x0302;
+pub(crate) const TLS12: u16 = 0x0303;
+pub(crate) const TLS13: u16 = 0x0304;
+
+const CHANGE_CIPHER_SPEC: u8 = 20;
+const ALERT: u8 = 21;
+const HANDSHAKE: u8 = 22;
+
+const CLIENT_HELLO: u8 = 1;
+const SERVER_HELLO: u8 = 2;
+const CERTIFICATE: u8 = 11;
+const SERVER_HELLO_DONE: u8 = 14;
+
+const EXT_SERVER_NAME: u16 = 0;
+const EXT_SUPPORTED_GROUPS: u16 = 10;
+const EXT_EC_POINT_FORMATS: u16 = 11;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+const EXT_KEY_SHARE: u16 = 51;
+
+// enough for long certificate chains
+const MAX_HANDSHAKE_SIZE: usize = 256 * 1024;
+
+// x25519, secp256r1, secp384r1, secp521r1
+const GROUPS: &[u16] = &[0x001d, 0x0017, 0x0018, 0x0019];
+const SIGNATURE_ALGORITHMS: &[u16] = &[
+    0x0403, 0x0503, 0x0603, 0x0804, 0x0805, 0x0806, 0x0401, 0x0501, 0x0601, 0x0201, 0x0203,
+];
+
+pub(crate) const TLS13_CIPHERS: &[u16] = &[0x1301, 0x1302, 0x1303];
+// the usual suites of modern and legacy servers
+pub(crate) const DEFAULT_CIPHERS: &[u16] = &[
+    0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0x009e, 0x009f, 0xc009, 0xc013, 0xc00a,
+    0xc014, 0x0033, 0x0039, 0x009c, 0x009d, 0x002f, 0x0035, 0x003c, 0x003d, 0xc012, 0x000a,
+    0x0005, 0x0004,
+];
+pub(crate) const WEAK_CIPHERS: &[(&str, &[u16])] = &[
+    ("NULL", &[0x0001, 0x0002, 0x003b, 0xc006, 0xc010, 0xc015]),
+    (
+        "EXPORT",
+        &[0x0003, 0x0006, 0x0008, 0x000b, 0x000e, 0x0011, 0x0014, 0x0017, 0x0019],
+    ),
+    (
+        "anonymous",
+        &[0x0018, 0x001a, 0x001b, 0x0034, 0x003a, 0x006c, 0x006d, 0xc016, 0xc017, 0xc018, 0xc019],
+    ),
+    ("RC4", &[0x0004, 0x0005, 0xc007, 0xc011, 0xc002, 0xc00c]),
+    (
+        "DES",
+        &[0x0009, 0x000c, 0x000f, 0x0012, 0x0015, 0x000a, 0x000d, 0x0010, 0x0013, 0x0016, 0xc008, 0xc012],
+    ),
+];
+
+pub(crate) fn version_name(version: u16) -> &'static str {
+    match version {
+        SSL3 => "SSLv3",
+        TLS10 => "TLSv1.0",
+        TLS11 => "TLSv1.1",
+        TLS12 => "TLSv1.2",
+        TLS13 => "TLSv1.3",
+        _ => "unknown",
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ServerHello {
+    pub version: u16,
+    pub cipher: u16,
+    // DER certificates, leaf first, empty with TLS 1.3 since they're encrypted
+    pub certificates: Vec<Vec<u8>>,
+}
+
+fn extension(extension_type: u16, data: &[u8]) -> Vec<u8> {
+    let mut ext = extension_type.to_be_bytes().to_vec();
+    ext.extend((data.len() as u16).to_be_bytes());
+    ext.extend(data);
+    ext
+}
+
+fn u16_list(values: &[u16], size_bytes: usize) -> Vec<u8> {
+    let len = values.len() * 2;
+    let mut data = if size_bytes == 1 {
+        vec![len as u8]
+    } else {
+        (len as u16).to_be_bytes().to_vec()
+    };
+    for value in values {
+        data.extend(value.to_be_bytes());
+    }
+    data
+}
+
+// client hello record offering the version (and nothing above) with the given suites
+pub(crate) fn client_hello(version: u16, ciphers: &[u16], server_name: Option<&str>) -> Vec<u8> {
+    let mut ciphers = ciphers.to_vec();
+    if version == TLS13 {
+        ciphers.splice(0..0, TLS13_CIPHERS.iter().copied());
+    }
+
+    let mut body = version.min(TLS12).to_be_bytes().to_vec();
+    body.extend(rand::random::<[u8; 32]>());
+    // no session id
+    body.push(0);
+    body.extend(u16_list(&ciphers, 2));
+    // null compression
+    body.extend([1, 0]);
+
+    if version > SSL3 {
+        let mut extensions = vec![];
+        if let Some(name) = server_name {
+            let mut entry = vec![0];
+            entry.extend((name.len() as u16).to_be_bytes());
+            entry.extend(name.as_bytes());
+            let mut list = (entry.len() as u16).to_be_bytes().to_vec();
+            list.extend(entry);
+            extensions.extend(extension(EXT_SERVER_NAME, &list));
+        }
+        extensions.extend(extension(EXT_SUPPORTED_GROUPS, &u16_list(GROUPS, 2)));
+        extensions.extend(extension(EXT_EC_POINT_FORMATS, &[1, 0]));
+        if version >= TLS12 {
+            extensions.extend(extension(
+                EXT_SIGNATURE_ALGORITHMS,
+                &u16_list(SIGNATURE_ALGORITHMS, 2),
+            ));
+        }
+        if version == TLS13 {
+            extensions.extend(extension(EXT_SUPPORTED_VERSIONS, &u16_list(&[TLS13], 1)));
+            // no shares, the server will ask for one of the supported groups
+            extensions.extend(extension(EXT_KEY_SHARE, &[0, 0]));
+        }
+        body.extend((extensions.len() as u16).to_be_bytes());
+        body.extend(extensions);
+    }
+
+    let mut handshake = vec![CLIENT_HELLO];
+    handshake.extend(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend(body);
+
+    let mut record = vec![HANDSHAKE];
+    record.extend(version.min(TLS10).to_be_bytes());
+    record.extend((handshake.len() as u16).to_be_bytes());
+    record.extend(handshake);
+    record
+}
+
+// version and cipher suite selected by the server
+pub(crate) fn parse_server_hello(body: &[u8]) -> Result<(u16, u16), Error> {
+    let truncated = || "truncated server hello".to_owned();
+    let mut version = u16::from_be_bytes([*body.first().ok_or_else(truncated)?, body[1]]);
+
+    // version and random
+    let mut pos = 34;
+    let session_id = *body.get(pos).ok_or_else(truncated)? as usize;
+    pos += 1 + session_id;
+    let cipher = body.get(pos..pos + 2).ok_or_else(truncated)?;
+    let cipher = u16::from_be_bytes([cipher[0], cipher[1]]);
+    // cipher and compression
+    pos += 3;
+
+    if let Some(size) = body.get(pos..pos + 2) {
+        let end = (pos + 2 + u16::from_be_bytes([size[0], size[1]]) as usize).min(body.len());
+        pos += 2;
+        while pos + 4 <= end {
+            let ext_type = u16::from_be_bytes([body[pos], body[pos + 1]]);
+            let ext_size = u16::from_be_bytes([body[pos + 2], body[pos + 3]]) as usize;
+            if ext_type == EXT_SUPPORTED_VERSIONS && ext_size == 2 && pos + 6 <= end {
+                version = u16::from_be_bytes([body[pos + 4], body[pos + 5]]);
+            }
+            pos += 4 + ext_size;
+        }
+    }
+
+    Ok((version, cipher))
+}
+
+fn parse_certificates(body: &[u8]) -> Vec<Vec<u8>> {
+    let mut certificates = vec![];
+    // total size
+    let mut pos = 3;
+    while pos + 3 <= body.len() {
+        let size = u32::from_be_bytes([0, body[pos], body[pos + 1], body[pos + 2]]) as usize;
+        let Some(der) = body.get(pos + 3..pos + 3 + size) else {
+            break;
+        };
+        certificates.push(der.to_vec());
+        pos += 3 + size;
+    }
+    certificates
+}
+
+// sends the hello and reads the server flight, returns None if the server refused it
+pub(crate) async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    hello: &[u8],
+) -> Result<Option<ServerHello>, Error> {
+    stream.write_all(hello).await.map_err(|e| e.to_string())?;
+
+    let mut server_hello: Option<ServerHello> = None;
+    let mut buffer = vec![];
+    let mut received = 0;
+    loop {
+        let mut header = [0u8; 5];
+        if stream.read_exact(&mut header).await.is_err() {
+            // refused by closing the connection, or done sending
+            return Ok(server_hello);
+        }
+        let size = u16::from_be_bytes([header[3], header[4]]) as usize;
+        let mut record = vec![0u8; size];
+        stream
+            .read_exact(&mut record)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        received += size;
+        if received > MAX_HANDSHAKE_SIZE {
+            return Err("handshake too large".to_owned());
+        }
+
+        match header[0] {
+            ALERT if server_hello.is_none() => return Ok(None),
+            // the rest is encrypted
+            ALERT | CHANGE_CIPHER_SPEC => return Ok(server_hello),
+            HANDSHAKE => buffer.extend(record),
+            other => return Err(format!("unexpected record type {}", other)),
+        }
+
+        // consume the complete handshake messages
+        while buffer.len() >= 4 {
+            let size = u32::from_be_bytes([0, buffer[1], buffer[2], buffer[3]]) as usize;
+            if buffer.len() < 4 + size {
+                break;
+            }
+            let message: Vec<u8> = buffer.drain(..4 + size).collect();
+            let body = &message[4..];
+            match message[0] {
+                SERVER_HELLO => {
+                    let (version, cipher) = parse_server_hello(body)?;
+                    let done = version == TLS13;
+                    server_hello = Some(ServerHello {
+                        version,
+                        cipher,
+                        certificates: vec![],
+                    });
+                    if done {
+                        return Ok(server_hello);
+                    }
+                }
+                CERTIFICATE => {
+                    if let Some(hello) = server_hello.as_mut() {
+                        hello.certificates = parse_certificates(body);
+                    }
+                }
+                SERVER_HELLO_DONE => return Ok(server_hello),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{client_hello, handshake, ServerHello, TLS12, TLS13};
+
+    fn record(content_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut record = vec![content_type, 0x03, 0x03];
+        record.extend((data.len() as u16).to_be_bytes());
+        record.extend(data);
+        record
+    }
+
+    fn message(message_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = vec![message_type];
+        message.extend(&(body.len() as u32).to_be_bytes()[1..]);
+        message.extend(body);
+        message
+    }
+
+    fn server_hello(cipher: u16, extensions: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x03, 0x03];
+        body.extend([0x11; 32]);
+        body.push(0);
+        body.extend(cipher.to_be_bytes());
+        body.push(0);
+        body.extend((extensions.len() as u16).to_be_bytes());
+        body.extend(extensions);
+        message(2, &body)
+    }
+
+    #[test]
+    fn can_build_client_hello() {
+        let hello = client_hello(TLS12, &[0xc02f], Some("example.com"));
+        assert_eq!(&hello[..3], &[22, 0x03, 0x01]);
+        assert_eq!(hello[5], 1);
+        assert_eq!(&hello[9..11], &[0x03, 0x03]);
+        assert!(hello.windows(11).any(|w| w == b"example.com"));
+
+        let hello = client_hello(TLS13, &[], None);
+        // supported versions with TLS 1.3 only
+        assert!(hello.windows(7).any(|w| w == [0x00, 0x2b, 0x00, 0x03, 0x02, 0x03, 0x04]));
+    }
+
+    #[tokio::test]
+    async fn can_read_certificate_chain() {
+        let hello = client_hello(TLS12, &[0xc02f], None);
+
+        // hello and certificates split across two records
+        let certificates = [0, 0, 13, 0, 0, 4, 1, 2, 3, 4, 0, 0, 3, 5, 6, 7];
+        let mut flight = server_hello(0xc02f, &[]);
+        flight.extend(message(11, &certificates));
+        flight.extend(message(14, &[]));
+        let (first, second) = flight.split_at(50);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&hello)
+            .read(&record(22, first))
+            .read(&record(22, second))
+            .build();
+        assert_eq!(
+            handshake(&mut stream, &hello).await,
+            Ok(Some(ServerHello {
+                version: TLS12,
+                cipher: 0xc02f,
+                certificates: vec![vec![1, 2, 3, 4], vec![5, 6, 7]],
+            }))
+        );
+
+        // handshake failure
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&hello)
+            .read(&record(21, &[2, 40]))
+            .build();
+        assert_eq!(handshake(&mut stream, &hello).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn can_detect_tls13() {
+        let hello = client_hello(TLS13, &[], None);
+        let mut stream = tokio_test::io::Builder::new()
+            .write(&hello)
+            // hello retry request selecting TLS 1.3
+            .read(&record(
+                22,
+                &server_hello(0x1301, &[0x00, 0x2b, 0x00, 0x02, 0x03, 0x04]),
+            ))
+            .build();
+        assert_eq!(
+            handshake(&mut stream, &hello).await.unwrap().unwrap().version,
+            TLS13
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use crate::creds::{Credentials, Expression};
+use crate::session::{Error, Loot};
+use crate::utils::net::upgrade_tcp_stream_to_tls;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+mod hello;
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "tls" => Tls::new()
+}
+
+const DEFAULT_PORT: u16 = 443;
+const SECONDS_PER_DAY: i64 = 86400;
+
+const VERSIONS: &[u16] = &[
+    hello::SSL3,
+    hello::TLS10,
+    hello::TLS11,
+    hello::TLS12,
+    hello::TLS13,
+];
+
+#[derive(Clone)]
+pub(crate) struct Tls {
+    opts: options::Options,
+}
+
+impl Tls {
+    pub fn new() -> Self {
+        Tls {
+            opts: options::Options::default(),
+        }
+    }
+
+    async fn probe(
+        &self,
+        address: &str,
+        hello: &[u8],
+        timeout: Duration,
+    ) -> Result<Option<hello::ServerHello>, Error> {
+        let mut stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+        tokio::time::timeout(timeout, hello::handshake(&mut stream, hello))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    // leaf certificate as negotiated by the system library, for servers only speaking TLS 1.3
+    async fn leaf_certificate(&self, address: &str, timeout: Duration) -> Option<Vec<u8>> {
+        let stream = utils::net::async_tcp_stream(address, timeout, false)
+            .await
+            .ok()?;
+        let tls = upgrade_tcp_stream_to_tls(stream, timeout).await.ok()?;
+        tls.peer_certificate().ok()??.to_der().ok()
+    }
+}
+
+fn certificate_data(chain: &[Vec<u8>]) -> Vec<(String, String)> {
+    let mut data = vec![];
+    let Some(Ok((_, leaf))) = chain.first().map(|der| X509Certificate::from_der(der)) else {
+        return data;
+    };
+
+    data.push(("subject".to_owned(), leaf.subject().to_string()));
+    data.push(("issuer".to_owned(), leaf.issuer().to_string()));
+    data.push(("serial".to_owned(), leaf.raw_serial_as_string()));
+
+    let algorithm = &leaf.signature_algorithm.algorithm;
+    data.push((
+        "signature".to_owned(),
+        oid2sn(algorithm, oid_registry())
+            .map(|name| name.to_owned())
+            .unwrap_or_else(|_| algorithm.to_id_string()),
+    ));
+
+    let validity = leaf.validity();
+    data.push(("valid_from".to_owned(), validity.not_before.to_string()));
+    data.push(("valid_to".to_owned(), validity.not_after.to_string()));
+    let days_left =
+        (validity.not_after.timestamp() - chrono::Utc::now().timestamp()) / SECONDS_PER_DAY;
+    data.push(("days_left".to_owned(), days_left.to_string()));
+    data.push(("expired".to_owned(), (!validity.is_valid()).to_string()));
+    data.push((
+        "self_signed".to_owned(),
+        (leaf.subject() == leaf.issuer()).to_string(),
+    ));
+
+    if let Ok(Some(alt_names)) = leaf.subject_alternative_name() {
+        let names: Vec<String> = alt_names
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(name) => Some(name.to_lowercase()),
+                _ => None,
+            })
+            .collect();
+        if !names.is_empty() {
+            data.push(("names".to_owned(), names.join(", ")));
+        }
+    }
+
+    if chain.len() > 1 {
+        let subjects: Vec<String> = chain
+            .iter()
+            .skip(1)
+            .filter_map(|der| X509Certificate::from_der(der).ok())
+            .map(|(_, cert)| cert.subject().to_string())
+            .collect();
+        data.push(("chain".to_owned(), subjects.join(" | ")));
+    }
+
+    data
+}
+
+#[async_trait]
+impl Plugin for Tls {
+    fn description(&self) -> &'static str {
+        "TLS certificates, protocol versions and weak cipher suites survey."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        PayloadStrategy::Single
+    }
+
+    fn override_payload(&self) -> Option<Expression> {
+        Some(Expression::Constant {
+            value: "survey".to_owned(),
+        })
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.tls.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, DEFAULT_PORT)?;
+        let address = format!("{}:{}", host, port);
+        let server_name = self.opts.tls_server_name.clone().or_else(|| {
+            host.trim_matches(|c| c == '[' || c == ']')
+                .parse::<IpAddr>()
+                .is_err()
+                .then(|| host.clone())
+        });
+
+        // highest version first so that an unreachable target fails right away
+        let mut supported = vec![];
+        let mut chain = vec![];
+        for (idx, version) in VERSIONS.iter().rev().enumerate() {
+            let hello = hello::client_hello(
+                *version,
+                hello::DEFAULT_CIPHERS,
+                server_name.as_deref(),
+            );
+            match self.probe(&address, &hello, timeout).await {
+                Ok(Some(server)) if server.version == *version => {
+                    supported.push(*version);
+                    if chain.is_empty() {
+                        chain = server.certificates;
+                    }
+                }
+                // refused or downgraded
+                Ok(_) => {}
+                Err(e) if idx == 0 => return Err(e),
+                Err(e) => log::debug!(
+                    "{} with {}: {}",
+                    hello::version_name(*version),
+                    &address,
+                    e
+                ),
+            }
+        }
+
+        if supported.is_empty() {
+            return Ok(None);
+        }
+
+        if chain.is_empty() {
+            if let Some(leaf) = self.leaf_certificate(&address, timeout).await {
+                chain.push(leaf);
+            }
+        }
+
+        let mut weak_ciphers = vec![];
+        let legacy = supported.iter().find(|v| **v <= hello::TLS12);
+        if let (Some(version), false) = (legacy, self.opts.tls_no_weak_checks) {
+            for (class, ciphers) in hello::WEAK_CIPHERS {
+                let hello = hello::client_hello(*version, ciphers, server_name.as_deref());
+                if let Ok(Some(server)) = self.probe(&address, &hello, timeout).await {
+                    if ciphers.contains(&server.cipher) {
+                        weak_ciphers.push(*class);
+                    }
+                }
+            }
+        }
+
+        supported.reverse();
+        let weak_protocols: Vec<&str> = supported
+            .iter()
+            .filter(|v| **v < hello::TLS12)
+            .map(|v| hello::version_name(*v))
+            .collect();
+
+        let mut data = vec![(
+            "protocols".to_owned(),
+            supported
+                .iter()
+                .map(|v| hello::version_name(*v))
+                .collect::<Vec<&str>>()
+                .join(", "),
+        )];
+        if !weak_protocols.is_empty() {
+            data.push(("weak_protocols".to_owned(), weak_protocols.join(", ")));
+        }
+        if !weak_ciphers.is_empty() {
+            data.push(("weak_ciphers".to_owned(), weak_ciphers.join(", ")));
+        }
+        data.extend(certificate_data(&chain));
+
+        Ok(Some(vec![Loot::new("tls", &address, data)]))
+    }
+}
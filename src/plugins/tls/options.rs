@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Do not check which weak cipher suites (NULL, EXPORT, anonymous, RC4, DES) are accepted.
+    pub tls_no_weak_checks: bool,
+    #[clap(long)]
+    /// Server name to send with SNI, by default the target host name if it's not an address.
+    pub tls_server_name: Option<String>,
+}
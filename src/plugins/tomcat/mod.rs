@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "tomcat" => Tomcat::new()
+}
+
+// manager endpoints and the role they require
+const ROLES: &[(&str, &str)] = &[
+    ("/manager/html", "manager-gui"),
+    ("/manager/text/serverinfo", "manager-script"),
+    ("/manager/jmxproxy/?qry=java.lang:type=Runtime", "manager-jmx"),
+    ("/manager/status", "manager-status"),
+    ("/host-manager/html", "admin-gui"),
+];
+
+// "Tomcat Version: [Apache Tomcat/9.0.80]" from the text interface serverinfo
+fn parse_version(body: &str) -> Option<String> {
+    body.lines()
+        .find_map(|line| line.strip_prefix("Tomcat Version:"))
+        .map(|v| v.trim().trim_matches(|c| c == '[' || c == ']').to_owned())
+}
+
+#[derive(Clone)]
+pub(crate) struct Tomcat {
+    opts: options::Options,
+    client: Client,
+    // targets already probed without credentials
+    checked: OncePerTarget,
+    // targets asking for credentials, where a 403 means authenticated without the role
+    protected: OncePerTarget,
+    // targets refusing the manager to our address before authentication (RemoteAddrValve)
+    blocked: OncePerTarget,
+}
+
+impl Tomcat {
+    pub fn new() -> Self {
+        Tomcat {
+            opts: options::Options::default(),
+            client: Client::new(),
+            checked: OncePerTarget::new(),
+            protected: OncePerTarget::new(),
+            blocked: OncePerTarget::new(),
+        }
+    }
+
+    // check once per target whether the manager asks for credentials or refuses our address
+    async fn probe(&self, address: &str, base: &str, timeout: Duration) -> Result<(), Error> {
+        if self.checked.contains(address) {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .get(format!("{}{}", base, ROLES[0].0))
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        match response.status() {
+            StatusCode::UNAUTHORIZED => {
+                self.protected.insert(address);
+            }
+            StatusCode::FORBIDDEN if self.blocked.insert(address) => {
+                log::warn!(
+                    "{}: the manager is not reachable from this address (403 before authentication)",
+                    address
+                );
+            }
+            _ => {}
+        }
+        // marked only once the check completed, so transient errors are checked again
+        self.checked.insert(address);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Plugin for Tomcat {
+    fn description(&self) -> &'static str {
+        "Apache Tomcat Manager password authentication with role detection."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.tomcat.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8080)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.tomcat_ssl || port == 8443 || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.tomcat_path.trim_end_matches('/')
+        );
+
+        self.probe(&address, &base, timeout).await?;
+        if self.blocked.contains(&address) {
+            return Ok(None);
+        }
+
+        let mut valid = false;
+        let mut roles = vec![];
+        let mut version = None;
+        for (path, role) in ROLES {
+            let response = self
+                .client
+                .get(format!("{}{}", &base, path))
+                .basic_auth(&creds.username, Some(&creds.password))
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            match response.status() {
+                StatusCode::OK => {
+                    valid = true;
+                    roles.push(*role);
+                    if *role == "manager-script" {
+                        version = parse_version(&response.text().await.unwrap_or_default());
+                    }
+                }
+                // authenticated but without the role, unless every request is refused
+                StatusCode::FORBIDDEN if self.protected.contains(&address) => valid = true,
+                // the manager gui is the first check, no need to go on
+                StatusCode::UNAUTHORIZED if !valid => {
+                    self.protected.insert(&address);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+
+        if !valid {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+            ("roles".to_owned(), roles.join(", ")),
+        ];
+        if let Some(version) = version {
+            data.push(("version".to_owned(), version));
+        }
+        Ok(Some(vec![Loot::new("tomcat", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_version, Tomcat};
+    use crate::creds::Credentials;
+    use crate::utils::testing::http_server;
+    use crate::Plugin;
+
+    fn creds(target: String) -> Credentials {
+        Credentials {
+            target,
+            username: "tomcat".to_owned(),
+            password: "s3cret".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn skips_targets_refusing_our_address() {
+        // default RemoteAddrValve, every request is refused before authentication
+        let target = http_server(vec![Some((403, "Access Denied"))]).await;
+        let tomcat = Tomcat::new();
+
+        assert_eq!(
+            tomcat
+                .attempt(&creds(target), Duration::from_secs(5))
+                .await,
+            Ok(None)
+        );
+    }
+
+    #[tokio::test]
+    async fn can_detect_roles() {
+        let target = http_server(vec![
+            // unauthenticated probe
+            Some((401, "")),
+            Some((403, "")),
+            Some((200, "OK - Server info\nTomcat Version: [Apache Tomcat/9.0.80]\n")),
+            Some((403, "")),
+        ])
+        .await;
+        let tomcat = Tomcat::new();
+
+        let loot = tomcat
+            .attempt(&creds(target), Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loot[0].get_value("roles"), Some("manager-script"));
+        assert_eq!(loot[0].get_value("version"), Some("Apache Tomcat/9.0.80"));
+    }
+
+    #[test]
+    fn can_parse_serverinfo() {
+        assert_eq!(
+            parse_version(
+                "OK - Server info\nTomcat Version: [Apache Tomcat/9.0.80]\nOS Name: [Linux]\nJVM Version: [17.0.8+7]\n"
+            ),
+            Some("Apache Tomcat/9.0.80".to_owned())
+        );
+        assert_eq!(parse_version("<html>403 Access Denied</html>"), None);
+    }
+}
@@ -0,0 +1,13 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Tomcat (automatically enabled for https:// targets and port 8443).
+    pub tomcat_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// Tomcat base path when behind a reverse proxy, e.g. /tomcat.
+    pub tomcat_path: String,
+}
@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+use super::plugin::PayloadStrategy;
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "vault" => Vault::new()
+}
+
+const TOKEN_HEADER: &str = "X-Vault-Token";
+
+fn join_strings(value: Option<&serde_json::Value>) -> Option<String> {
+    let values: Vec<&str> = value?.as_array()?.iter().filter_map(|v| v.as_str()).collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(", "))
+    }
+}
+
+// policies and name of a token from the lookup-self endpoint
+fn parse_lookup(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    if let Some(name) = json.pointer("/data/display_name").and_then(|v| v.as_str()) {
+        info.push(("display_name".to_owned(), name.to_owned()));
+    }
+    if let Some(policies) = join_strings(json.pointer("/data/policies")) {
+        info.push(("policies".to_owned(), policies));
+    }
+    info
+}
+
+// client token and policies of a login response
+fn parse_login(body: &str) -> Option<(String, Option<String>)> {
+    let json = serde_json::from_str::<serde_json::Value>(body).ok()?;
+    let token = json.pointer("/auth/client_token")?.as_str()?.to_owned();
+    Some((token, join_strings(json.pointer("/auth/policies"))))
+}
+
+#[derive(Clone)]
+pub(crate) struct Vault {
+    opts: options::Options,
+    client: Client,
+}
+
+impl Vault {
+    pub fn new() -> Self {
+        Vault {
+            opts: options::Options::default(),
+            client: Client::new(),
+        }
+    }
+
+    fn mount(&self) -> &str {
+        self.opts
+            .vault_mount
+            .as_deref()
+            .unwrap_or(match self.opts.vault_auth {
+                options::Auth::Ldap => "ldap",
+                options::Auth::Token | options::Auth::Userpass => "userpass",
+            })
+    }
+}
+
+#[async_trait]
+impl Plugin for Vault {
+    fn description(&self) -> &'static str {
+        "HashiCorp Vault token, userpass and LDAP authentication."
+    }
+
+    fn payload_strategy(&self) -> PayloadStrategy {
+        if self.opts.vault_auth == options::Auth::Token {
+            PayloadStrategy::Single
+        } else {
+            PayloadStrategy::UsernamePassword
+        }
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.vault.clone();
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 8200)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.vault_ssl || creds.target.starts_with("https://");
+        let base = format!("{}://{}/v1", if ssl { "https" } else { "http" }, &address);
+
+        if self.opts.vault_auth == options::Auth::Token {
+            let token = creds.single();
+            let response = self
+                .client
+                .get(format!("{}/auth/token/lookup-self", &base))
+                .header(TOKEN_HEADER, token)
+                .timeout(timeout)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if response.status() != StatusCode::OK {
+                return Ok(None);
+            }
+
+            let mut data = vec![("token".to_owned(), token.to_owned())];
+            data.extend(parse_lookup(&response.text().await.unwrap_or_default()));
+            return Ok(Some(vec![Loot::new("vault", &address, data)]));
+        }
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/auth/{}/login/{}",
+                &base,
+                self.mount(),
+                &creds.username
+            ))
+            .header("Content-Type", "application/json")
+            .body(serde_json::json!({ "password": &creds.password }).to_string())
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+        let Some((token, policies)) = parse_login(&response.text().await.unwrap_or_default())
+        else {
+            return Ok(None);
+        };
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+            ("token".to_owned(), token),
+        ];
+        if let Some(policies) = policies {
+            data.push(("policies".to_owned(), policies));
+        }
+        Ok(Some(vec![Loot::new("vault", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_login, parse_lookup};
+
+    #[test]
+    fn can_parse_token_lookup() {
+        assert_eq!(
+            parse_lookup(
+                r#"{"request_id":"1","data":{"accessor":"x","display_name":"token-ci","policies":["default","deploy"],"ttl":2764800}}"#
+            ),
+            vec![
+                ("display_name".to_owned(), "token-ci".to_owned()),
+                ("policies".to_owned(), "default, deploy".to_owned()),
+            ]
+        );
+        assert!(parse_lookup(r#"{"errors":["permission denied"]}"#).is_empty());
+    }
+
+    #[test]
+    fn can_parse_login() {
+        assert_eq!(
+            parse_login(
+                r#"{"auth":{"client_token":"hvs.CAESI","policies":["admins","default"],"lease_duration":2764800}}"#
+            ),
+            Some((
+                "hvs.CAESI".to_owned(),
+                Some("admins, default".to_owned())
+            ))
+        );
+        assert_eq!(parse_login(r#"{"errors":["invalid username or password"]}"#), None);
+    }
+}
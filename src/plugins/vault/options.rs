@@ -0,0 +1,24 @@
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Serialize, Deserialize, Debug, ValueEnum, PartialEq)]
+pub(crate) enum Auth {
+    #[default]
+    Token,
+    Userpass,
+    Ldap,
+}
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, value_enum, default_value_t = Auth::Token)]
+    /// Vault authentication method, tokens are tested as single payloads.
+    pub vault_auth: Auth,
+    #[clap(long)]
+    /// Mount path of the userpass or ldap auth method, if different from the method name.
+    pub vault_mount: Option<String>,
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for Vault (automatically enabled for https:// targets).
+    pub vault_ssl: bool,
+}
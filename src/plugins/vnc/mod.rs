@@ -1,33 +1,40 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use vnc::{PixelFormat, VncConnector};
 
 use crate::session::{Error, Loot};
 use crate::Plugin;
 use crate::{utils, Options};
 
 use crate::creds::Credentials;
+use crate::utils::once::OncePerTarget;
 
 use super::plugin::PayloadStrategy;
 
+mod rfb;
+
 super::manager::register_plugin! {
     "vnc" => VNC::new()
 }
 
 #[derive(Clone)]
-pub(crate) struct VNC {}
+pub(crate) struct VNC {
+    // targets not requiring authentication, reported only once
+    no_auth: OncePerTarget,
+}
 
 impl VNC {
     pub fn new() -> Self {
-        VNC {}
+        VNC {
+            no_auth: OncePerTarget::new(),
+        }
     }
 }
 
 #[async_trait]
 impl Plugin for VNC {
     fn description(&self) -> &'static str {
-        "VNC password authentication."
+        "VNC password authentication (VNC, TightVNC and no authentication)."
     }
 
     fn payload_strategy(&self) -> PayloadStrategy {
@@ -44,37 +51,32 @@ impl Plugin for VNC {
         timeout: Duration,
     ) -> Result<Option<Vec<Loot>>, Error> {
         let address = utils::parse_target_address(&creds.target, 5900)?;
-        let stream = crate::utils::net::async_tcp_stream(&address, timeout, false).await?;
+        if self.no_auth.contains(&address) {
+            return Ok(None);
+        }
+
+        let mut stream = crate::utils::net::async_tcp_stream(&address, timeout, false).await?;
         // being this plugin single credentials, this is going to be the password
-        let password = creds.single().to_owned();
-        let vnc = tokio::time::timeout(
-            timeout,
-            VncConnector::new(stream)
-                .set_auth_method(async move { Ok(password) })
-                .add_encoding(vnc::VncEncoding::Tight)
-                .add_encoding(vnc::VncEncoding::Zrle)
-                .add_encoding(vnc::VncEncoding::CopyRect)
-                .add_encoding(vnc::VncEncoding::Raw)
-                .allow_shared(false)
-                .set_pixel_format(PixelFormat::bgra())
-                .build()
-                .map_err(|e| e.to_string())?
-                .try_start(),
-        )
-        .await
-        .map_err(|e| e.to_string())?;
+        let password = creds.single();
+        let outcome = tokio::time::timeout(timeout, rfb::authenticate(&mut stream, password))
+            .await
+            .map_err(|e| e.to_string())??;
 
-        if vnc.is_ok() && vnc.unwrap().finish().is_ok() {
-            return Ok(Some(vec![Loot::new(
+        match outcome {
+            rfb::Outcome::Valid => Ok(Some(vec![Loot::new(
                 "vnc",
                 &address,
-                [
-                    ("username".to_owned(), creds.username.to_owned()),
-                    ("password".to_owned(), creds.password.to_owned()),
-                ],
-            )]));
+                [("password".to_owned(), password.to_owned())],
+            )])),
+            rfb::Outcome::NoAuth => Ok(self.no_auth.report(
+                &address,
+                Loot::new(
+                    "vnc",
+                    &address,
+                    [("authentication".to_owned(), "none".to_owned())],
+                ),
+            )),
+            rfb::Outcome::Invalid => Ok(None),
         }
-
-        Ok(None)
     }
 }
@@ -0,0 +1,242 @@
+use des::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+
+const SECURITY_NONE: u32 = 1;
+const SECURITY_VNC_AUTH: u32 = 2;
+const SECURITY_TIGHT: u32 = 16;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    // the server doesn't require authentication at all
+    NoAuth,
+    Valid,
+    Invalid,
+}
+
+// the protocol version we're going to use for the server one, up to 3.8
+fn negotiate_version(server: &[u8]) -> Result<(u32, &'static [u8]), Error> {
+    let version = std::str::from_utf8(server)
+        .ok()
+        .and_then(|v| v.strip_prefix("RFB "))
+        .and_then(|v| v.trim_end().split_once('.'))
+        .and_then(|(major, minor)| Some((major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?)))
+        .ok_or(format!("invalid RFB version: {:?}", server))?;
+
+    Ok(match version {
+        (3, minor) if minor < 7 => (3, b"RFB 003.003\n"),
+        (3, 7) => (7, b"RFB 003.007\n"),
+        _ => (8, b"RFB 003.008\n"),
+    })
+}
+
+// DES challenge response with the password bits reversed, as per the RFB specs
+fn vnc_auth_response(password: &str, challenge: &[u8; 16]) -> [u8; 16] {
+    let mut key = [0u8; 8];
+    for (k, b) in key.iter_mut().zip(password.bytes()) {
+        *k = b.reverse_bits();
+    }
+
+    let cipher = des::Des::new_from_slice(&key).unwrap();
+    let mut response = *challenge;
+    for block in response.chunks_mut(8) {
+        cipher.encrypt_block(GenericArray::from_mut_slice(block));
+    }
+    response
+}
+
+async fn read_u32<S: AsyncRead + Unpin>(stream: &mut S) -> Result<u32, Error> {
+    stream.read_u32().await.map_err(|e| e.to_string())
+}
+
+async fn read_reason<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, Error> {
+    let len = read_u32(stream).await? as usize;
+    let mut reason = vec![0u8; len.min(4096)];
+    stream
+        .read_exact(&mut reason)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(String::from_utf8_lossy(&reason).to_string())
+}
+
+// TightVNC security: skip tunneling and pick one of the standard authentication types
+async fn tight_auth<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<u32, Error> {
+    let tunnels = read_u32(stream).await?;
+    if tunnels > 0 {
+        let mut caps = vec![0u8; 16 * tunnels.min(256) as usize];
+        stream
+            .read_exact(&mut caps)
+            .await
+            .map_err(|e| e.to_string())?;
+        // NOTUNNEL
+        stream.write_u32(0).await.map_err(|e| e.to_string())?;
+    }
+
+    let auths = read_u32(stream).await?;
+    if auths == 0 {
+        return Ok(SECURITY_NONE);
+    }
+
+    let mut codes = vec![];
+    for _ in 0..auths.min(256) {
+        // code, vendor and name
+        let mut cap = [0u8; 16];
+        stream
+            .read_exact(&mut cap)
+            .await
+            .map_err(|e| e.to_string())?;
+        codes.push(u32::from_be_bytes(cap[..4].try_into().unwrap()));
+    }
+
+    let code = [SECURITY_NONE, SECURITY_VNC_AUTH]
+        .into_iter()
+        .find(|c| codes.contains(c))
+        .ok_or(format!("unsupported TightVNC authentication types {:?}", codes))?;
+    stream.write_u32(code).await.map_err(|e| e.to_string())?;
+
+    Ok(code)
+}
+
+// perform the RFB handshake up to the security result, without initializing a session
+pub(crate) async fn authenticate<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    password: &str,
+) -> Result<Outcome, Error> {
+    let mut server_version = [0u8; 12];
+    stream
+        .read_exact(&mut server_version)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (minor, client_version) = negotiate_version(&server_version)?;
+    stream
+        .write_all(client_version)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let security = if minor == 3 {
+        // the server decides
+        match read_u32(stream).await? {
+            0 => return Err(read_reason(stream).await?),
+            security => security,
+        }
+    } else {
+        let num = stream.read_u8().await.map_err(|e| e.to_string())?;
+        if num == 0 {
+            // usually too many authentication failures
+            return Err(read_reason(stream).await?);
+        }
+        let mut types = vec![0u8; num as usize];
+        stream
+            .read_exact(&mut types)
+            .await
+            .map_err(|e| e.to_string())?;
+        let types: Vec<u32> = types.into_iter().map(|t| t as u32).collect();
+
+        let security = [SECURITY_NONE, SECURITY_VNC_AUTH, SECURITY_TIGHT]
+            .into_iter()
+            .find(|t| types.contains(t))
+            .ok_or(format!("unsupported security types {:?}", types))?;
+        stream
+            .write_u8(security as u8)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if security == SECURITY_TIGHT {
+            tight_auth(stream).await?
+        } else {
+            security
+        }
+    };
+
+    match security {
+        SECURITY_NONE => return Ok(Outcome::NoAuth),
+        SECURITY_VNC_AUTH => {}
+        other => return Err(format!("unsupported security type {}", other)),
+    }
+
+    let mut challenge = [0u8; 16];
+    stream
+        .read_exact(&mut challenge)
+        .await
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(&vnc_auth_response(password, &challenge))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match read_u32(stream).await? {
+        0 => Ok(Outcome::Valid),
+        _ => {
+            if minor == 8 {
+                let reason = read_reason(stream).await.unwrap_or_default();
+                if reason.to_lowercase().contains("too many") {
+                    // blacklisted, retry later
+                    return Err(reason);
+                }
+            }
+            Ok(Outcome::Invalid)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticate, negotiate_version, vnc_auth_response, Outcome};
+
+    #[test]
+    fn can_negotiate_version() {
+        assert_eq!(negotiate_version(b"RFB 003.003\n").unwrap().0, 3);
+        assert_eq!(negotiate_version(b"RFB 003.007\n").unwrap().0, 7);
+        assert_eq!(negotiate_version(b"RFB 003.889\n").unwrap().0, 8);
+        assert_eq!(negotiate_version(b"RFB 004.001\n").unwrap().0, 8);
+        assert!(negotiate_version(b"SSH-2.0-Open").is_err());
+    }
+
+    #[test]
+    fn can_respond_to_challenge() {
+        assert_eq!(
+            hex::encode(vnc_auth_response("password", b"0123456789abcdef")),
+            "5645abeb5f1e6475e8feb11beb66ea19"
+        );
+        // only the first 8 characters are used
+        assert_eq!(
+            vnc_auth_response("password", b"0123456789abcdef"),
+            vnc_auth_response("password123", b"0123456789abcdef")
+        );
+    }
+
+    #[tokio::test]
+    async fn can_authenticate() {
+        let mut challenge = b"0123456789abcdef".to_vec();
+        let mut server = b"RFB 003.008\n".to_vec();
+        server.extend([1, 2]);
+        server.append(&mut challenge);
+        server.extend([0, 0, 0, 0]);
+
+        let mut stream = tokio_test::io::Builder::new()
+            .read(&server[..12])
+            .write(b"RFB 003.008\n")
+            .read(&server[12..14])
+            .write(&[2])
+            .read(&server[14..30])
+            .write(&vnc_auth_response("password", b"0123456789abcdef"))
+            .read(&server[30..])
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "password").await,
+            Ok(Outcome::Valid)
+        );
+
+        let mut stream = tokio_test::io::Builder::new()
+            .read(b"RFB 003.008\n")
+            .write(b"RFB 003.008\n")
+            .read(&[2, 2, 1])
+            .write(&[1])
+            .build();
+        assert_eq!(
+            authenticate(&mut stream, "password").await,
+            Ok(Outcome::NoAuth)
+        );
+    }
+}
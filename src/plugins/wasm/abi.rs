@@ -0,0 +1,432 @@
+// Version 1 of the plugin ABI.
+//
+// The module must export:
+//
+//   memory
+//   legba_abi_version() -> i32                      returns ABI_VERSION
+//   legba_alloc(size: i32) -> i32                   buffer for the host to write the inputs to
+//   legba_attempt(target_ptr, target_len,
+//                 username_ptr, username_len,
+//                 password_ptr, password_len) -> i32  1 if valid, 0 if not, negative on error
+//
+// and optionally legba_description() -> i64, with the pointer of an utf-8 string in the
+// upper 32 bits and its size in the lower ones.
+//
+// The host provides these imports in the "legba" module, each attempt runs in a new instance:
+//
+//   connect(address_ptr, address_len, tls: i32) -> i32   connection handle, -1 on error
+//   send(handle, ptr, len) -> i32                        bytes sent, -1 on error
+//   recv(handle, ptr, capacity) -> i32                   bytes read, 0 on timeout or eof, -1 on error
+//   close(handle)
+//   loot(key_ptr, key_len, value_ptr, value_len)         adds an entry to the loot of a valid attempt
+//   log(ptr, len)                                        debug message
+//   error(ptr, len)                                      message of a failed attempt
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use wasmi::{Caller, Engine, Instance, Linker, Memory, Module, Store};
+
+use crate::session::Error;
+use crate::utils;
+use crate::utils::net::StreamLike;
+
+pub(crate) const ABI_VERSION: i32 = 1;
+
+const HOST_MODULE: &str = "legba";
+// bounds what a plugin can make the host read or allocate
+const MAX_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Default)]
+pub(crate) struct State {
+    timeout: Duration,
+    connections: Vec<Option<Box<dyn StreamLike>>>,
+    loot: Vec<(String, String)>,
+    error: Option<String>,
+}
+
+// the host functions run on a blocking thread of the runtime
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Handle::current().block_on(future)
+}
+
+fn memory(caller: &Caller<'_, State>) -> Option<Memory> {
+    caller.get_export("memory")?.into_memory()
+}
+
+fn read_bytes(caller: &Caller<'_, State>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let len = usize::try_from(len).ok().filter(|len| *len <= MAX_BUFFER_SIZE)?;
+    let mut data = vec![0u8; len];
+    memory(caller)?
+        .read(caller, ptr as u32 as usize, &mut data)
+        .ok()?;
+    Some(data)
+}
+
+fn read_string(caller: &Caller<'_, State>, ptr: i32, len: i32) -> String {
+    read_bytes(caller, ptr, len)
+        .map(|data| String::from_utf8_lossy(&data).to_string())
+        .unwrap_or_default()
+}
+
+fn linker(engine: &Engine) -> Result<Linker<State>, Error> {
+    let mut linker = Linker::new(engine);
+    let map_err = |e: wasmi::errors::LinkerError| e.to_string();
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "connect",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32, tls: i32| -> i32 {
+                let address = read_string(&caller, ptr, len);
+                let timeout = caller.data().timeout;
+                match block_on(utils::net::async_tcp_stream(&address, timeout, tls != 0)) {
+                    Ok(stream) => {
+                        let connections = &mut caller.data_mut().connections;
+                        connections.push(Some(stream));
+                        connections.len() as i32 - 1
+                    }
+                    Err(e) => {
+                        log::debug!("can't connect to {}: {}", address, e);
+                        -1
+                    }
+                }
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "send",
+            |mut caller: Caller<'_, State>, handle: i32, ptr: i32, len: i32| -> i32 {
+                let Some(data) = read_bytes(&caller, ptr, len) else {
+                    return -1;
+                };
+                let timeout = caller.data().timeout;
+                let Some(Some(stream)) = caller.data_mut().connections.get_mut(handle as usize)
+                else {
+                    return -1;
+                };
+                match block_on(tokio::time::timeout(timeout, stream.write_all(&data))) {
+                    Ok(Ok(())) => data.len() as i32,
+                    _ => -1,
+                }
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "recv",
+            |mut caller: Caller<'_, State>, handle: i32, ptr: i32, capacity: i32| -> i32 {
+                let Ok(capacity) = usize::try_from(capacity) else {
+                    return -1;
+                };
+                let timeout = caller.data().timeout;
+                let Some(Some(stream)) = caller.data_mut().connections.get_mut(handle as usize)
+                else {
+                    return -1;
+                };
+
+                let mut buffer = vec![0u8; capacity.min(MAX_BUFFER_SIZE)];
+                let read = match block_on(tokio::time::timeout(timeout, stream.read(&mut buffer)))
+                {
+                    Ok(Ok(read)) => read,
+                    Ok(Err(_)) => return -1,
+                    Err(_) => 0,
+                };
+
+                match memory(&caller) {
+                    Some(memory)
+                        if memory
+                            .write(&mut caller, ptr as u32 as usize, &buffer[..read])
+                            .is_ok() =>
+                    {
+                        read as i32
+                    }
+                    _ => -1,
+                }
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "close",
+            |mut caller: Caller<'_, State>, handle: i32| {
+                if let Some(connection) = caller.data_mut().connections.get_mut(handle as usize) {
+                    connection.take();
+                }
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "loot",
+            |mut caller: Caller<'_, State>,
+             key_ptr: i32,
+             key_len: i32,
+             value_ptr: i32,
+             value_len: i32| {
+                let key = read_string(&caller, key_ptr, key_len);
+                let value = read_string(&caller, value_ptr, value_len);
+                caller.data_mut().loot.push((key, value));
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "log",
+            |caller: Caller<'_, State>, ptr: i32, len: i32| {
+                log::debug!("{}", read_string(&caller, ptr, len));
+            },
+        )
+        .map_err(map_err)?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "error",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32| {
+                let message = read_string(&caller, ptr, len);
+                caller.data_mut().error = Some(message);
+            },
+        )
+        .map_err(map_err)?;
+
+    Ok(linker)
+}
+
+fn instantiate(
+    engine: &Engine,
+    module: &Module,
+    state: State,
+    fuel: u64,
+) -> Result<(Store<State>, Instance), Error> {
+    let mut store = Store::new(engine, state);
+    store.set_fuel(fuel).map_err(|e| e.to_string())?;
+
+    let instance = linker(engine)?
+        .instantiate(&mut store, module)
+        .map_err(|e| e.to_string())?
+        .start(&mut store)
+        .map_err(|e| e.to_string())?;
+
+    let version = instance
+        .get_typed_func::<(), i32>(&store, "legba_abi_version")
+        .map_err(|e| e.to_string())?
+        .call(&mut store, ())
+        .map_err(|e| e.to_string())?;
+    if version != ABI_VERSION {
+        return Err(format!(
+            "unsupported ABI version {} (expected {})",
+            version, ABI_VERSION
+        ));
+    }
+
+    Ok((store, instance))
+}
+
+// copies the data to a buffer allocated by the plugin
+fn write_input(
+    store: &mut Store<State>,
+    instance: &Instance,
+    data: &[u8],
+) -> Result<(i32, i32), Error> {
+    let ptr = instance
+        .get_typed_func::<i32, i32>(&*store, "legba_alloc")
+        .map_err(|e| e.to_string())?
+        .call(&mut *store, data.len() as i32)
+        .map_err(|e| e.to_string())?;
+    instance
+        .get_memory(&*store, "memory")
+        .ok_or("no memory exported".to_owned())?
+        .write(&mut *store, ptr as u32 as usize, data)
+        .map_err(|e| e.to_string())?;
+    Ok((ptr, data.len() as i32))
+}
+
+// checks the exports and the ABI version, returns the plugin description if any
+pub(crate) fn validate(engine: &Engine, module: &Module, fuel: u64) -> Result<Option<String>, Error> {
+    let (mut store, instance) = instantiate(engine, module, State::default(), fuel)?;
+    instance
+        .get_typed_func::<(i32, i32, i32, i32, i32, i32), i32>(&store, "legba_attempt")
+        .map_err(|e| e.to_string())?;
+    instance
+        .get_typed_func::<i32, i32>(&store, "legba_alloc")
+        .map_err(|e| e.to_string())?;
+
+    let Ok(description) = instance.get_typed_func::<(), i64>(&store, "legba_description") else {
+        return Ok(None);
+    };
+    let packed = description
+        .call(&mut store, ())
+        .map_err(|e| e.to_string())?;
+    let size = (packed as u64 & 0xffffffff) as usize;
+    if size > MAX_BUFFER_SIZE {
+        return Err(format!(
+            "description of {} bytes exceeds the {} bytes limit",
+            size, MAX_BUFFER_SIZE
+        ));
+    }
+    let mut data = vec![0u8; size];
+    instance
+        .get_memory(&store, "memory")
+        .ok_or("no memory exported".to_owned())?
+        .read(&store, (packed as u64 >> 32) as usize, &mut data)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(String::from_utf8_lossy(&data).to_string()))
+}
+
+// runs an attempt in a new instance, returns the loot entries if valid
+pub(crate) fn attempt(
+    engine: &Engine,
+    module: &Module,
+    fuel: u64,
+    timeout: Duration,
+    (target, username, password): (&str, &str, &str),
+) -> Result<Option<Vec<(String, String)>>, Error> {
+    let state = State {
+        timeout,
+        ..Default::default()
+    };
+    let (mut store, instance) = instantiate(engine, module, state, fuel)?;
+
+    let (target_ptr, target_len) = write_input(&mut store, &instance, target.as_bytes())?;
+    let (username_ptr, username_len) = write_input(&mut store, &instance, username.as_bytes())?;
+    let (password_ptr, password_len) = write_input(&mut store, &instance, password.as_bytes())?;
+
+    let result = instance
+        .get_typed_func::<(i32, i32, i32, i32, i32, i32), i32>(&store, "legba_attempt")
+        .map_err(|e| e.to_string())?
+        .call(
+            &mut store,
+            (
+                target_ptr,
+                target_len,
+                username_ptr,
+                username_len,
+                password_ptr,
+                password_len,
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let state = store.into_data();
+    match result {
+        1 => Ok(Some(state.loot)),
+        0 => Ok(None),
+        code => Err(state
+            .error
+            .unwrap_or_else(|| format!("plugin returned {}", code))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use wasmi::{Config, Engine, Module};
+
+    use super::{attempt, validate};
+
+    // valid if the password is 6 bytes long, with the first byte of the username as loot
+    const PLUGIN: &str = r#"
+        (module
+            (import "legba" "loot" (func $loot (param i32 i32 i32 i32)))
+            (import "legba" "error" (func $error (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 16) "test pluginrole")
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "legba_abi_version") (result i32) (i32.const 1))
+            (func (export "legba_description") (result i64)
+                (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 11)))
+            (func (export "legba_alloc") (param $size i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $size)))
+                (local.get $ptr))
+            (func (export "legba_attempt")
+                (param $t i32) (param $tl i32) (param $u i32) (param $ul i32)
+                (param $p i32) (param $pl i32) (result i32)
+                (if (i32.eqz (local.get $ul))
+                    (then
+                        (call $error (i32.const 16) (i32.const 4))
+                        (return (i32.const -1))))
+                (if (result i32) (i32.eq (local.get $pl) (i32.const 6))
+                    (then
+                        (call $loot (i32.const 27) (i32.const 4) (local.get $u) (i32.const 1))
+                        (i32.const 1))
+                    (else (i32.const 0))))
+        )
+    "#;
+
+    fn engine() -> Engine {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Engine::new(&config)
+    }
+
+    #[test]
+    fn can_run_plugins() {
+        let engine = engine();
+        let module = Module::new(&engine, &wat::parse_str(PLUGIN).unwrap()).unwrap();
+        let timeout = Duration::from_secs(1);
+
+        assert_eq!(
+            validate(&engine, &module, 10000),
+            Ok(Some("test plugin".to_owned()))
+        );
+        assert_eq!(
+            attempt(&engine, &module, 10000, timeout, ("host:1", "admin", "secret")),
+            Ok(Some(vec![("role".to_owned(), "a".to_owned())]))
+        );
+        assert_eq!(
+            attempt(&engine, &module, 10000, timeout, ("host:1", "admin", "wrong")),
+            Ok(None)
+        );
+        assert_eq!(
+            attempt(&engine, &module, 10000, timeout, ("host:1", "", "wrong")),
+            Err("test".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_plugins() {
+        let engine = engine();
+
+        let other_version = PLUGIN.replace("(result i32) (i32.const 1))", "(result i32) (i32.const 2))");
+        let module = Module::new(&engine, &wat::parse_str(other_version).unwrap()).unwrap();
+        assert!(validate(&engine, &module, 10000).is_err());
+
+        let huge_description = PLUGIN.replace("(i64.const 11)", "(i64.const 0xffffffff)");
+        let module = Module::new(&engine, &wat::parse_str(huge_description).unwrap()).unwrap();
+        assert!(validate(&engine, &module, 10000).is_err());
+
+        let no_attempt = PLUGIN.replace("\"legba_attempt\"", "\"attempt\"");
+        let module = Module::new(&engine, &wat::parse_str(no_attempt).unwrap()).unwrap();
+        assert!(validate(&engine, &module, 10000).is_err());
+
+        // out of fuel
+        let endless = PLUGIN.replace(
+            "(if (i32.eqz (local.get $ul))",
+            "(loop $forever (br $forever)) (if (i32.eqz (local.get $ul))",
+        );
+        let module = Module::new(&engine, &wat::parse_str(endless).unwrap()).unwrap();
+        assert!(attempt(
+            &engine,
+            &module,
+            10000,
+            Duration::from_secs(1),
+            ("host:1", "admin", "secret")
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use wasmi::{Config, Engine, Module};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Options;
+use crate::Plugin;
+
+use super::manager::{PluginRegistrar, INVENTORY};
+
+mod abi;
+pub(crate) mod options;
+
+const DEFAULT_PLUGINS_DIR: &str = ".legba/plugins";
+// enough to validate the module and call its description export
+const SETUP_FUEL: u64 = 1000000;
+
+// plugins loaded from the plugins folder, registered again whenever the defaults are
+static LOADED: LazyLock<RwLock<Vec<Wasm>>> = LazyLock::new(|| RwLock::new(vec![]));
+
+pub(super) fn register(registrar: &mut impl PluginRegistrar) {
+    for plugin in LOADED.read().unwrap().iter() {
+        registrar.register(plugin.name, plugin.clone());
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Wasm {
+    name: &'static str,
+    description: &'static str,
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+}
+
+impl Wasm {
+    fn from_path(path: &Path, engine: &Engine) -> Result<Self, Error> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or(format!("invalid plugin file name {}", path.display()))?;
+        let wasm = std::fs::read(path).map_err(|e| e.to_string())?;
+        let module = Module::new(engine, &wasm).map_err(|e| e.to_string())?;
+        let description = abi::validate(engine, &module, SETUP_FUEL)?
+            .unwrap_or_else(|| format!("WASM plugin from {}.", path.display()));
+
+        // loaded once and kept for the whole process lifetime
+        Ok(Self {
+            name: Box::leak(name.to_owned().into_boxed_str()),
+            description: Box::leak(description.into_boxed_str()),
+            engine: engine.clone(),
+            module,
+            fuel: 0,
+        })
+    }
+}
+
+fn plugins_dir(opts: &options::Options) -> Option<PathBuf> {
+    match opts.wasm_plugins_dir.as_ref() {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(DEFAULT_PLUGINS_DIR))
+            .filter(|dir| dir.is_dir()),
+    }
+}
+
+// loads the plugins found in the plugins folder, broken ones are skipped
+pub(crate) fn load(opts: &Options) -> Result<(), Error> {
+    let Some(dir) = plugins_dir(&opts.wasm) else {
+        return Ok(());
+    };
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("can't read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+        .collect();
+    paths.sort();
+
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+
+    let mut loaded = vec![];
+    for path in paths {
+        match Wasm::from_path(&path, &engine) {
+            Ok(plugin) if INVENTORY.lock().unwrap().contains_key(plugin.name) => {
+                log::warn!(
+                    "skipping {}: a plugin named {} already exists",
+                    path.display(),
+                    plugin.name
+                );
+            }
+            Ok(plugin) => {
+                log::debug!("loaded {} from {}", plugin.name, path.display());
+                loaded.push(plugin);
+            }
+            Err(e) => log::warn!("skipping {}: {}", path.display(), e),
+        }
+    }
+
+    LOADED.write().unwrap().extend(loaded);
+    register(&mut *INVENTORY.lock().unwrap());
+
+    Ok(())
+}
+
+#[async_trait]
+impl Plugin for Wasm {
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.fuel = opts.wasm.wasm_fuel;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let plugin = self.clone();
+        let input = creds.clone();
+
+        // the interpreter is synchronous, the host functions block on the runtime
+        let data = tokio::task::spawn_blocking(move || {
+            abi::attempt(
+                &plugin.engine,
+                &plugin.module,
+                plugin.fuel,
+                timeout,
+                (&input.target, &input.username, &input.password),
+            )
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        let Some(data) = data else {
+            return Ok(None);
+        };
+
+        let mut loot_data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        loot_data.extend(data);
+
+        Ok(Some(vec![Loot::new(self.name, &creds.target, loot_data)]))
+    }
+}
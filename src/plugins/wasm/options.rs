@@ -0,0 +1,14 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long)]
+    /// Folder to load the WASM plugins (*.wasm files, named after the file) from, by default ~/.legba/plugins.
+    pub wasm_plugins_dir: Option<String>,
+
+    #[clap(long, default_value_t = 100000000)]
+    /// Maximum fuel (roughly the number of instructions) a WASM plugin can consume for each attempt.
+    pub wasm_fuel: u64,
+}
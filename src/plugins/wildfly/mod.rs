@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::http_auth::Challenge;
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+super::manager::register_plugin! {
+    "wildfly" => WildFly::new()
+}
+
+const MANAGEMENT_PATH: &str = "/management";
+
+// product name and version from the root resource of the management model
+fn parse_product(body: &str) -> Vec<(String, String)> {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return vec![];
+    };
+
+    let mut info = vec![];
+    for (key, name) in [
+        ("product-name", "product"),
+        ("product-version", "version"),
+        ("name", "server"),
+    ] {
+        if let Some(value) = json.get(key).and_then(|v| v.as_str()) {
+            info.push((name.to_owned(), value.to_owned()));
+        }
+    }
+    info
+}
+
+#[derive(Clone)]
+pub(crate) struct WildFly {
+    ssl: bool,
+    client: Client,
+}
+
+impl WildFly {
+    pub fn new() -> Self {
+        WildFly {
+            ssl: false,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for WildFly {
+    fn description(&self) -> &'static str {
+        "WildFly and JBoss EAP management interface digest authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.ssl = opts.wildfly.wildfly_ssl;
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 9990)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.ssl || port == 9993 || creds.target.starts_with("https://");
+        let url = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            MANAGEMENT_PATH
+        );
+
+        // every attempt needs a fresh nonce
+        let response = self
+            .client
+            .get(&url)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Err(format!(
+                "unexpected status {} from the management interface",
+                response.status()
+            ));
+        }
+        let challenge = Challenge::parse(
+            response
+                .headers()
+                .get_all(WWW_AUTHENTICATE)
+                .iter()
+                .filter_map(|v| v.to_str().ok()),
+        )
+        .ok_or("no supported authentication scheme".to_owned())?;
+
+        let response = self
+            .client
+            .get(&url)
+            .header(
+                AUTHORIZATION,
+                challenge.authorization("GET", MANAGEMENT_PATH, &creds.username, &creds.password),
+            )
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != StatusCode::OK {
+            return Ok(None);
+        }
+
+        let mut data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+        ];
+        data.extend(parse_product(&response.text().await.unwrap_or_default()));
+        Ok(Some(vec![Loot::new("wildfly", &address, data)]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_product;
+
+    #[test]
+    fn can_parse_product() {
+        assert_eq!(
+            parse_product(
+                r#"{"management-major-version":22,"name":"node1","product-name":"WildFly","product-version":"30.0.1.Final","release-codename":""}"#
+            ),
+            vec![
+                ("product".to_owned(), "WildFly".to_owned()),
+                ("version".to_owned(), "30.0.1.Final".to_owned()),
+                ("server".to_owned(), "node1".to_owned()),
+            ]
+        );
+    }
+}
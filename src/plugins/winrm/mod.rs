@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use reqwest::{Client, StatusCode};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+
+const SOAP_CONTENT_TYPE: &str = "application/soap+xml;charset=UTF-8";
+
+super::manager::register_plugin! {
+    "winrm" => WinRM::new()
+}
+
+fn envelope(url: &str, action: &str, headers: &str, body: &str) -> String {
+    format!(
+        r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd" xmlns:rsp="http://schemas.microsoft.com/wbem/wsman/1/windows/shell"><s:Header><a:To>{url}</a:To><a:ReplyTo><a:Address s:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:Address></a:ReplyTo><w:ResourceURI s:mustUnderstand="true">http://schemas.microsoft.com/wbem/wsman/1/windows/shell/cmd</w:ResourceURI><a:Action s:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2004/09/transfer/{action}</a:Action><w:MaxEnvelopeSize s:mustUnderstand="true">153600</w:MaxEnvelopeSize><a:MessageID>uuid:{id}</a:MessageID><w:OperationTimeout>PT20S</w:OperationTimeout>{headers}</s:Header><s:Body>{body}</s:Body></s:Envelope>"#,
+        url = url,
+        action = action,
+        id = uuid::Uuid::new_v4().to_string().to_uppercase(),
+        headers = headers,
+        body = body
+    )
+}
+
+// creating a shell requires the remote execution rights
+fn create_shell(url: &str) -> String {
+    envelope(
+        url,
+        "Create",
+        r#"<w:OptionSet><w:Option Name="WINRS_NOPROFILE">TRUE</w:Option><w:Option Name="WINRS_CODEPAGE">65001</w:Option></w:OptionSet>"#,
+        "<rsp:Shell><rsp:InputStreams>stdin</rsp:InputStreams><rsp:OutputStreams>stdout stderr</rsp:OutputStreams></rsp:Shell>",
+    )
+}
+
+fn delete_shell(url: &str, shell_id: &str) -> String {
+    envelope(
+        url,
+        "Delete",
+        &format!(
+            r#"<w:SelectorSet><w:Selector Name="ShellId">{}</w:Selector></w:SelectorSet>"#,
+            shell_id
+        ),
+        "",
+    )
+}
+
+fn shell_id(body: &str) -> Option<&str> {
+    let start = body.find("<rsp:ShellId>")? + "<rsp:ShellId>".len();
+    let end = start + body[start..].find("</rsp:ShellId>")?;
+    Some(&body[start..end])
+}
+
+// whether the authenticated user can execute commands from the shell creation response, any
+// response other than a shell or an access denied fault doesn't tell if the credentials are valid
+fn execution(status: StatusCode, body: &str) -> Result<bool, Error> {
+    if status.is_success() {
+        Ok(true)
+    } else if body.contains("Access is denied") {
+        Ok(false)
+    } else {
+        Err(format!(
+            "unexpected shell creation response: {} (unencrypted http might be refused, try --winrm-ssl)",
+            status
+        ))
+    }
+}
+
+// the NTLM token of a "WWW-Authenticate: Negotiate <token>" header
+fn challenge_token(response: &reqwest::Response) -> Result<Vec<u8>, Error> {
+    let header = response
+        .headers()
+        .get_all("www-authenticate")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .find_map(|h| h.strip_prefix("Negotiate "))
+        .ok_or("response missing Negotiate challenge header")?;
+    BASE64_STANDARD
+        .decode(header.trim())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Clone)]
+pub(crate) struct WinRM {
+    opts: options::Options,
+}
+
+impl WinRM {
+    pub fn new() -> Self {
+        WinRM {
+            opts: options::Options::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl Plugin for WinRM {
+    fn description(&self) -> &'static str {
+        "WinRM NTLM password and pass-the-hash authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.winrm.clone();
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 5985)?;
+        let ssl = self.opts.winrm_ssl || port == 5986 || creds.target.starts_with("https://");
+        let url = format!(
+            "{}://{}:{}/wsman",
+            if ssl { "https" } else { "http" },
+            host,
+            port
+        );
+
+        // NTLM authenticates the connection, so the client must not open new ones
+        let client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .pool_max_idle_per_host(1)
+            .timeout(timeout)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let negotiate = utils::ntlm::negotiate(&self.opts.winrm_workstation)?;
+        let response = client
+            .post(&url)
+            .header(
+                "Authorization",
+                format!("Negotiate {}", BASE64_STANDARD.encode(negotiate)),
+            )
+            .header("Content-Type", SOAP_CONTENT_TYPE)
+            .header("Content-Length", "0")
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let challenge = challenge_token(&response)?;
+        let hash = creds.ntlm_hash()?;
+        let authenticate = utils::ntlm::authenticate(
+            &challenge,
+            &creds.username,
+            &self.opts.winrm_domain,
+            &self.opts.winrm_workstation,
+            &creds.password,
+            hash.as_deref(),
+        )?;
+
+        let response = client
+            .post(&url)
+            .header(
+                "Authorization",
+                format!("Negotiate {}", BASE64_STANDARD.encode(authenticate)),
+            )
+            .header("Content-Type", SOAP_CONTENT_TYPE)
+            .body(create_shell(&url))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Ok(None);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let execution = execution(status, &body)?;
+        if execution {
+            // cleanup
+            if let Some(shell_id) = shell_id(&body) {
+                let _ = client
+                    .post(&url)
+                    .header("Content-Type", SOAP_CONTENT_TYPE)
+                    .body(delete_shell(&url, shell_id))
+                    .send()
+                    .await;
+            }
+        }
+
+        let data = vec![
+            ("username".to_owned(), creds.username.to_owned()),
+            ("password".to_owned(), creds.password.to_owned()),
+            (
+                "execution".to_owned(),
+                if execution { "yes" } else { "no" }.to_owned(),
+            ),
+        ];
+
+        Ok(Some(vec![Loot::new(
+            "winrm",
+            &format!("{}:{}", host, port),
+            data,
+        )]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{create_shell, execution, shell_id};
+
+    #[test]
+    fn can_check_execution_rights() {
+        assert_eq!(execution(StatusCode::OK, "<rsp:ShellId>AB-12</rsp:ShellId>"), Ok(true));
+        assert_eq!(
+            execution(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "<s:Fault><s:Text>Access is denied.</s:Text></s:Fault>"
+            ),
+            Ok(false)
+        );
+        for status in [
+            StatusCode::NOT_FOUND,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_GATEWAY,
+            StatusCode::PROXY_AUTHENTICATION_REQUIRED,
+        ] {
+            assert!(execution(status, "").is_err());
+        }
+    }
+
+    #[test]
+    fn can_parse_shell_id() {
+        assert!(create_shell("http://127.0.0.1:5985/wsman").contains("transfer/Create"));
+        assert_eq!(
+            shell_id("<rsp:Shell><rsp:ShellId>AB-12</rsp:ShellId></rsp:Shell>"),
+            Some("AB-12")
+        );
+        assert_eq!(shell_id("<s:Fault/>"), None);
+    }
+}
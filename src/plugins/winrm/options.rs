@@ -0,0 +1,16 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value = "")]
+    /// Domain for WinRM NTLM authentication (Kerberos is not supported, domain credentials can be checked against the KDC with the kerberos plugin).
+    pub winrm_domain: String,
+    #[clap(long, default_value = "LEGBA")]
+    /// Workstation name for WinRM NTLM authentication.
+    pub winrm_workstation: String,
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for WinRM (automatically enabled for port 5986 and https:// targets).
+    pub winrm_ssl: bool,
+}
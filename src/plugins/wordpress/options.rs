@@ -0,0 +1,19 @@
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
+#[group(skip)]
+pub(crate) struct Options {
+    #[clap(long, default_value_t = false)]
+    /// Use HTTPS for WordPress (automatically enabled for https:// targets and port 443).
+    pub wordpress_ssl: bool,
+    #[clap(long, default_value = "")]
+    /// WordPress base path when installed in a sub directory, e.g. /blog.
+    pub wordpress_path: String,
+    #[clap(long, default_value_t = false)]
+    /// Test credentials in batches with xmlrpc.php system.multicall instead of wp-login.php.
+    pub wordpress_xmlrpc: bool,
+    #[clap(long, default_value_t = 50)]
+    /// Maximum number of credentials per system.multicall request, bounded by --concurrency.
+    pub wordpress_batch: usize,
+}
@@ -0,0 +1,149 @@
+use regex::Regex;
+
+use crate::session::Error;
+use crate::utils::xml::xml_escape;
+
+const FAULT_INCORRECT_CREDENTIALS: u32 = 403;
+
+// system.multicall of wp.getUsersBlogs for each username and password
+pub(crate) fn multicall_request(creds: &[(String, String)]) -> String {
+    let calls: String = creds
+        .iter()
+        .map(|(username, password)| {
+            format!(
+                "<value><struct><member><name>methodName</name><value><string>wp.getUsersBlogs</string></value></member>\
+                <member><name>params</name><value><array><data><value><string>{}</string></value><value><string>{}</string></value></data></array></value></member></struct></value>",
+                xml_escape(username),
+                xml_escape(password)
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\"?><methodCall><methodName>system.multicall</methodName><params><param><value><array><data>{}</data></array></value></param></params></methodCall>",
+        calls
+    )
+}
+
+fn fault_code(value: &str) -> Option<u32> {
+    let re = Regex::new(r"(?s)faultCode</name>\s*<value>\s*<int>(\d+)</int>").unwrap();
+    let code = re.captures(value).map(|caps| caps[1].parse().unwrap_or_default());
+    code
+}
+
+// true for each call that authenticated successfully
+pub(crate) fn parse_multicall_response(body: &str) -> Result<Vec<bool>, Error> {
+    if body.contains("<fault>") && !body.contains("<params>") {
+        return Err(format!(
+            "xmlrpc fault {}",
+            fault_code(body).unwrap_or_default()
+        ));
+    }
+
+    // each call result is a top level value of the response array
+    let start = body
+        .find("<data>")
+        .ok_or("unexpected xmlrpc response".to_owned())?
+        + "<data>".len();
+    let re = Regex::new(r"</?value>|</data>").unwrap();
+    let mut results = vec![];
+    let mut depth = 0;
+    let mut value_start = 0;
+    for tag in re.find_iter(&body[start..]) {
+        match tag.as_str() {
+            "<value>" => {
+                if depth == 0 {
+                    value_start = tag.start();
+                }
+                depth += 1;
+            }
+            "</value>" => {
+                depth -= 1;
+                if depth == 0 {
+                    let value = &body[start + value_start..start + tag.end()];
+                    match fault_code(value) {
+                        None => results.push(true),
+                        Some(FAULT_INCORRECT_CREDENTIALS) => results.push(false),
+                        // 405 when xmlrpc is disabled, 429 when rate limited, etc
+                        Some(code) => return Err(format!("xmlrpc fault {}", code)),
+                    }
+                }
+            }
+            _ if depth == 0 => break,
+            _ => {}
+        }
+    }
+
+    Ok(results)
+}
+
+// WordPress 4.4 and later fail every login of a multicall after the first failure
+pub(crate) fn is_multicall_reliable(version: &str) -> bool {
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    major > 0 && (major, minor) < (4, 4)
+}
+
+// version from the generator meta tag of the homepage
+pub(crate) fn parse_generator(html: &str) -> Option<String> {
+    let re = Regex::new(r#"(?i)<meta\s+name="generator"\s+content="WordPress\s+([\d.]+)""#).unwrap();
+    let version = re.captures(html).map(|caps| caps[1].to_owned());
+    version
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_multicall_reliable, multicall_request, parse_generator, parse_multicall_response,
+    };
+
+    #[test]
+    fn can_build_multicall_requests() {
+        let request = multicall_request(&[("admin".to_owned(), "a<b&c".to_owned())]);
+        assert!(request.contains("<value><string>a&lt;b&amp;c</string></value>"));
+        assert!(request.contains("<methodName>system.multicall</methodName>"));
+    }
+
+    #[test]
+    fn can_parse_multicall_responses() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<methodResponse>
+  <params>
+    <param>
+      <value>
+      <array><data>
+  <value><struct>
+  <member><name>faultCode</name><value><int>403</int></value></member>
+  <member><name>faultString</name><value><string>Incorrect username or password.</string></value></member>
+</struct></value>
+  <value><array><data>
+  <value><array><data>
+  <value><struct>
+  <member><name>isAdmin</name><value><boolean>1</boolean></value></member>
+  <member><name>blogName</name><value><string>Blog</string></value></member>
+</struct></value>
+</data></array></value>
+</data></array></value>
+</data></array>
+      </value>
+    </param>
+  </params>
+</methodResponse>"#;
+        assert_eq!(parse_multicall_response(body), Ok(vec![false, true]));
+
+        let disabled = r#"<methodResponse><fault><value><struct><member><name>faultCode</name><value><int>405</int></value></member></struct></value></fault></methodResponse>"#;
+        assert!(parse_multicall_response(disabled).is_err());
+    }
+
+    #[test]
+    fn can_detect_reliable_versions() {
+        assert_eq!(
+            parse_generator(r#"<meta name="generator" content="WordPress 4.3.1" />"#),
+            Some("4.3.1".to_owned())
+        );
+        assert!(is_multicall_reliable("4.3.1"));
+        assert!(!is_multicall_reliable("4.4"));
+        assert!(!is_multicall_reliable("6.4.2"));
+        assert!(!is_multicall_reliable(""));
+    }
+}
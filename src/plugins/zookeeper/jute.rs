@@ -0,0 +1,170 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::session::Error;
+use crate::utils::digest_md5;
+
+const OP_SASL: i32 = 102;
+const ERR_AUTH_FAILED: i32 = -115;
+
+// default service and host of the java client
+const DIGEST_URI: &str = "zookeeper/zk-sasl-md5";
+const REALM: &str = "zk-sasl-md5";
+
+const MAX_PACKET_SIZE: usize = 0xfffff;
+
+fn buffer(data: &[u8]) -> Vec<u8> {
+    [&(data.len() as i32).to_be_bytes(), data].concat()
+}
+
+async fn write_packet<S: AsyncWrite + Unpin>(stream: &mut S, body: &[u8]) -> Result<(), Error> {
+    stream
+        .write_all(&[&(body.len() as i32).to_be_bytes(), body].concat())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn read_packet<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>, Error> {
+    let len = stream.read_u32().await.map_err(|e| e.to_string())? as usize;
+    if len > MAX_PACKET_SIZE {
+        return Err(format!("invalid zookeeper packet size {}", len));
+    }
+    let mut body = vec![0u8; len];
+    stream
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+pub(crate) fn connect_request(timeout_ms: i32) -> Vec<u8> {
+    let mut body = vec![];
+    // protocol version
+    body.extend_from_slice(&0i32.to_be_bytes());
+    // last zxid seen
+    body.extend_from_slice(&0i64.to_be_bytes());
+    body.extend_from_slice(&timeout_ms.to_be_bytes());
+    // session id
+    body.extend_from_slice(&0i64.to_be_bytes());
+    body.extend(buffer(&[0u8; 16]));
+    // read only
+    body.push(0);
+    body
+}
+
+pub(crate) fn sasl_request(xid: i32, token: &[u8]) -> Vec<u8> {
+    [
+        &xid.to_be_bytes()[..],
+        &OP_SASL.to_be_bytes(),
+        &buffer(token),
+    ]
+    .concat()
+}
+
+// error code and token of a sasl reply
+pub(crate) fn parse_sasl_reply(data: &[u8]) -> Result<(i32, Vec<u8>), Error> {
+    // xid, zxid and error code
+    if data.len() < 16 {
+        return Err("truncated zookeeper reply".to_owned());
+    }
+    let err = i32::from_be_bytes(data[12..16].try_into().unwrap());
+    if err != 0 || data.len() < 20 {
+        return Ok((err, vec![]));
+    }
+    let len = i32::from_be_bytes(data[16..20].try_into().unwrap());
+    if len <= 0 {
+        return Ok((err, vec![]));
+    }
+    let token = data
+        .get(20..20 + len as usize)
+        .ok_or("truncated zookeeper sasl token".to_owned())?;
+    Ok((err, token.to_vec()))
+}
+
+// establishes a session and returns its id
+pub(crate) async fn connect<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    timeout_ms: i32,
+) -> Result<i64, Error> {
+    write_packet(stream, &connect_request(timeout_ms)).await?;
+    let reply = read_packet(stream).await?;
+    if reply.len() < 16 {
+        return Err("truncated zookeeper connect response".to_owned());
+    }
+    Ok(i64::from_be_bytes(reply[8..16].try_into().unwrap()))
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    Valid,
+    Invalid,
+    // the server doesn't have SASL authentication configured
+    Unsupported,
+}
+
+pub(crate) async fn digest_md5_auth<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> Result<Outcome, Error> {
+    // DIGEST-MD5 has no initial response, an empty token requests the challenge
+    write_packet(stream, &sasl_request(1, &[])).await?;
+    let challenge = match read_packet(stream).await {
+        Ok(reply) => match parse_sasl_reply(&reply)? {
+            (0, challenge) if !challenge.is_empty() => challenge,
+            (err, _) => {
+                log::debug!("zookeeper sasl error {}", err);
+                return Ok(Outcome::Unsupported);
+            }
+        },
+        Err(_) => return Ok(Outcome::Unsupported),
+    };
+
+    let response =
+        digest_md5::client_response(&challenge, REALM, DIGEST_URI, username, password)?;
+    write_packet(stream, &sasl_request(2, response.as_bytes())).await?;
+
+    // the server closes the connection on failure
+    match read_packet(stream).await {
+        Ok(reply) => {
+            let (err, _) = parse_sasl_reply(&reply)?;
+            if err == 0 {
+                Ok(Outcome::Valid)
+            } else if err == ERR_AUTH_FAILED {
+                Ok(Outcome::Invalid)
+            } else {
+                Err(format!("unexpected zookeeper error {}", err))
+            }
+        }
+        Err(_) => Ok(Outcome::Invalid),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{connect_request, parse_sasl_reply, sasl_request};
+
+    #[test]
+    fn can_encode_requests() {
+        let connect = connect_request(30000);
+        assert_eq!(connect.len(), 45);
+        assert_eq!(&connect[12..16], &30000i32.to_be_bytes());
+
+        assert_eq!(
+            sasl_request(1, b"hi"),
+            b"\x00\x00\x00\x01\x00\x00\x00\x66\x00\x00\x00\x02hi"
+        );
+    }
+
+    #[test]
+    fn can_parse_sasl_replies() {
+        let mut reply = vec![0, 0, 0, 1];
+        reply.extend_from_slice(&7i64.to_be_bytes());
+        reply.extend_from_slice(&0i32.to_be_bytes());
+        reply.extend_from_slice(&5i32.to_be_bytes());
+        reply.extend_from_slice(b"nonce");
+        assert_eq!(parse_sasl_reply(&reply), Ok((0, b"nonce".to_vec())));
+
+        reply[12..16].copy_from_slice(&(-115i32).to_be_bytes());
+        assert_eq!(parse_sasl_reply(&reply[..16]), Ok((-115, vec![])));
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::creds::Credentials;
+use crate::session::{Error, Loot};
+use crate::utils::once::OncePerTarget;
+use crate::Plugin;
+use crate::{utils, Options};
+
+use self::jute::Outcome;
+
+mod jute;
+
+super::manager::register_plugin! {
+    "zookeeper" => Zookeeper::new()
+}
+
+// four letter words exposing the server state
+const COMMANDS: &[&str] = &["ruok", "srvr", "dump"];
+
+const SESSION_TIMEOUT_MS: i32 = 30000;
+
+// extracts the interesting fields of a four letter word response
+fn parse_four_letter_word(command: &str, response: &str) -> Vec<(String, String)> {
+    let response = response.trim();
+    if response.is_empty() || response.contains("not in the whitelist") {
+        return vec![];
+    }
+
+    match command {
+        "srvr" => response
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .filter_map(|(key, value)| match key.trim() {
+                "Zookeeper version" => Some(("version".to_owned(), value.trim().to_owned())),
+                "Mode" => Some(("mode".to_owned(), value.trim().to_owned())),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![(command.to_owned(), response.to_owned())],
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Zookeeper {
+    // targets already checked for four letter words exposure
+    checked: OncePerTarget,
+    // targets exposing four letter words, reported only once
+    exposed: OncePerTarget,
+    // targets without SASL authentication
+    no_sasl: OncePerTarget,
+}
+
+impl Zookeeper {
+    pub fn new() -> Self {
+        Zookeeper {
+            checked: OncePerTarget::new(),
+            exposed: OncePerTarget::new(),
+            no_sasl: OncePerTarget::new(),
+        }
+    }
+
+    async fn four_letter_word(
+        &self,
+        address: &str,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<String, Error> {
+        let mut stream = utils::net::async_tcp_stream(address, timeout, false).await?;
+        let mut response = vec![];
+        tokio::time::timeout(timeout, async {
+            stream
+                .write_all(command.as_bytes())
+                .await
+                .map_err(|e| e.to_string())?;
+            // the server closes the connection after responding
+            stream
+                .read_to_end(&mut response)
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+
+        Ok(String::from_utf8_lossy(&response).to_string())
+    }
+
+    // fails only if none of the commands got an answer
+    async fn check_exposure(&self, address: &str, timeout: Duration) -> Result<Option<Loot>, Error> {
+        let mut data = vec![];
+        let mut answered = false;
+        let mut error = None;
+        for command in COMMANDS {
+            match self.four_letter_word(address, command, timeout).await {
+                Ok(response) => {
+                    answered = true;
+                    data.extend(parse_four_letter_word(command, &response));
+                }
+                Err(e) => {
+                    log::debug!("zookeeper {} on {}: {}", command, address, e);
+                    error = Some(e);
+                }
+            }
+        }
+        if let (false, Some(e)) = (answered, error) {
+            return Err(e);
+        }
+
+        Ok(if data.is_empty() {
+            None
+        } else {
+            log::info!("zookeeper four letter words exposed on {}", address);
+            Some(Loot::new("zookeeper", address, data).set_partial())
+        })
+    }
+}
+
+#[async_trait]
+impl Plugin for Zookeeper {
+    fn description(&self) -> &'static str {
+        "Zookeeper four letter words exposure and SASL DIGEST-MD5 authentication."
+    }
+
+    fn setup(&mut self, _opts: &Options) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn attempt(
+        &self,
+        creds: &Credentials,
+        timeout: Duration,
+    ) -> Result<Option<Vec<Loot>>, Error> {
+        let address = utils::parse_target_address(&creds.target, 2181)?;
+        let mut found = vec![];
+
+        if !self.checked.contains(&address) {
+            match self.check_exposure(&address, timeout).await {
+                Ok(exposure) => {
+                    // marked only once the server answered, so transient errors are checked again
+                    self.checked.insert(&address);
+                    if let Some(loot) =
+                        exposure.and_then(|exposure| self.exposed.report(&address, exposure))
+                    {
+                        found.extend(loot);
+                    }
+                }
+                Err(e) => log::debug!("four letter words check for {} failed: {}", &address, e),
+            }
+        }
+
+        if !self.no_sasl.contains(&address) {
+            let mut stream = utils::net::async_tcp_stream(&address, timeout, false).await?;
+            let outcome = tokio::time::timeout(timeout, async {
+                jute::connect(&mut stream, SESSION_TIMEOUT_MS).await?;
+                jute::digest_md5_auth(&mut stream, &creds.username, &creds.password).await
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+
+            match outcome {
+                Outcome::Valid => found.push(Loot::new(
+                    "zookeeper",
+                    &address,
+                    [
+                        ("username".to_owned(), creds.username.to_owned()),
+                        ("password".to_owned(), creds.password.to_owned()),
+                    ],
+                )),
+                Outcome::Invalid => {}
+                Outcome::Unsupported => {
+                    if self.no_sasl.insert(&address) {
+                        log::info!("zookeeper on {} has no SASL authentication", &address);
+                    }
+                }
+            }
+        }
+
+        Ok(if found.is_empty() { None } else { Some(found) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_four_letter_word;
+
+    #[test]
+    fn can_parse_four_letter_words() {
+        assert_eq!(
+            parse_four_letter_word("ruok", "imok"),
+            vec![("ruok".to_owned(), "imok".to_owned())]
+        );
+        assert_eq!(
+            parse_four_letter_word(
+                "srvr",
+                "Zookeeper version: 3.8.4-9316c2a7a97e1666d8f4593f34dd6fc36ecc436c, built on 2024-02-12 22:16 UTC\nLatency min/avg/max: 0/0.0/0\nMode: standalone\nNode count: 5\n"
+            ),
+            vec![
+                (
+                    "version".to_owned(),
+                    "3.8.4-9316c2a7a97e1666d8f4593f34dd6fc36ecc436c, built on 2024-02-12 22:16 UTC"
+                        .to_owned()
+                ),
+                ("mode".to_owned(), "standalone".to_owned()),
+            ]
+        );
+        assert!(parse_four_letter_word(
+            "dump",
+            "dump is not executed because it is not in the whitelist.\n"
+        )
+        .is_empty());
+    }
+}
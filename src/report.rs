@@ -4,7 +4,11 @@ use std::time;
 use human_bytes::human_bytes;
 use memory_stats::memory_stats;
 
-use crate::Session;
+use crate::creds::Combinator;
+use crate::plugins::PayloadStrategy;
+use crate::session::Error;
+use crate::utils::Targets;
+use crate::{Options, Plugin, Session};
 
 pub(crate) fn statistics(session: Arc<Session>) {
     let one_sec = time::Duration::from_millis(1000);
@@ -13,7 +17,12 @@ pub(crate) fn statistics(session: Arc<Session>) {
 
         let total = session.get_total();
         let done = session.get_done();
-        let perc = (done as f32 / total as f32) * 100.0;
+        // the total is unknown until the end when reading from stdin
+        let perc = if total > 0 {
+            (done as f32 / total as f32) * 100.0
+        } else {
+            0.0
+        };
         let errors = session.get_errors();
         let speed = session.get_speed();
         let memory = if let Some(usage) = memory_stats() {
@@ -49,3 +58,128 @@ pub(crate) fn statistics(session: Arc<Session>) {
         }
     }
 }
+
+// human readable duration such as 2d 3h 4m 5s
+fn format_duration(duration: time::Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, mins, secs) = (
+        secs / 86400,
+        (secs % 86400) / 3600,
+        (secs % 3600) / 60,
+        secs % 60,
+    );
+
+    let mut parts = vec![];
+    for (value, unit) in [(days, "d"), (hours, "h"), (mins, "m")] {
+        if value > 0 || !parts.is_empty() {
+            parts.push(format!("{}{}", value, unit));
+        }
+    }
+    parts.push(format!("{}s", secs));
+    parts.join(" ")
+}
+
+// attempts per second allowed by the rate limit, wait time and jittering options, if any
+fn limited_speed(options: &Options) -> Option<f64> {
+    let mut limits = vec![];
+    if options.rate_limit > 0 {
+        limits.push(options.rate_limit as f64);
+    }
+    if options.wait > 0 {
+        // the wait time is applied before dispatching each attempt
+        limits.push(1000.0 / options.wait as f64);
+    }
+    if options.jitter_max > 0 {
+        // every worker sleeps the average jitter before each attempt
+        let jitter = (options.jitter_min + options.jitter_max) as f64 / 2.0;
+        limits.push(options.concurrency as f64 * 1000.0 / jitter);
+    }
+    limits.into_iter().reduce(f64::min)
+}
+
+fn eta(total: usize, speed: f64) -> String {
+    format_duration(time::Duration::from_secs_f64(total as f64 / speed))
+}
+
+// print the search space size, the first credentials and an estimated time without connecting
+// to the targets
+pub(crate) fn estimate(
+    options: &Options,
+    targets: &Targets,
+    plugin: &dyn Plugin,
+) -> Result<(), Error> {
+    let single = matches!(plugin.payload_strategy(), PayloadStrategy::Single);
+
+    // don't sleep while printing the first credentials
+    let mut sample_options = options.clone();
+    sample_options.rate_limit = 0;
+    sample_options.wait = 0;
+
+    let combinations = Combinator::create(
+        targets,
+        sample_options,
+        0,
+        single,
+        plugin.override_payload(),
+    )?;
+    let total = combinations.search_space_size();
+
+    if single {
+        log::info!("using -> {}", combinations.username_expression());
+    } else {
+        log::info!("username -> {}", combinations.username_expression());
+        log::info!("password -> {}", combinations.password_expression());
+    }
+    log::info!("targets={} attempts={}\n", targets.len(), total);
+
+    for creds in combinations.take(options.dry_run_samples) {
+        if single {
+            log::info!("  [{}] {}", creds.target, creds.single());
+        } else {
+            log::info!("  [{}] {} {}", creds.target, creds.username, creds.password);
+        }
+    }
+
+    if let Some(speed) = limited_speed(options) {
+        log::info!("speed={:.2} reqs/s eta={}", speed, eta(total, speed));
+    } else {
+        // without limits the speed depends on the targets, so only the worst case is known
+        let speed = options.concurrency as f64 * 1000.0 / options.timeout.max(1) as f64;
+        log::info!(
+            "no rate limit set, eta={} if every attempt times out",
+            eta(total, speed)
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time;
+
+    use super::{format_duration, limited_speed};
+
+    #[test]
+    fn can_estimate_speed() {
+        let mut opts = crate::Options::default();
+        assert_eq!(limited_speed(&opts), None);
+
+        opts.concurrency = 10;
+        opts.rate_limit = 20;
+        assert_eq!(limited_speed(&opts), Some(20.0));
+
+        opts.wait = 100;
+        assert_eq!(limited_speed(&opts), Some(10.0));
+
+        opts.jitter_min = 1000;
+        opts.jitter_max = 3000;
+        assert_eq!(limited_speed(&opts), Some(5.0));
+
+        assert_eq!(format_duration(time::Duration::from_secs(42)), "42s");
+        assert_eq!(
+            format_duration(time::Duration::from_secs(86400 + 60 + 1)),
+            "1d 0h 1m 1s"
+        );
+    }
+}
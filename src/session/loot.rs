@@ -22,6 +22,10 @@ pub(crate) enum OutputFormat {
 pub(crate) struct Loot {
     found_at: DateTime<Local>,
     target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
     plugin: String,
     data: IndexMap<String, String>,
     partial: bool,
@@ -41,6 +45,8 @@ impl Loot {
         Self {
             found_at,
             target,
+            hostname: None,
+            tags: vec![],
             plugin,
             data,
             partial,
@@ -51,6 +57,31 @@ impl Loot {
         &self.target
     }
 
+    pub fn get_plugin(&self) -> &str {
+        &self.plugin
+    }
+
+    pub fn get_value(&self, key: &str) -> Option<&str> {
+        self.data.get(key).map(|v| v.as_str())
+    }
+
+    pub fn set_hostname(&mut self, hostname: String) {
+        self.hostname = Some(hostname);
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    // target with the reverse dns hostname if available
+    fn target_string(&self) -> String {
+        if let Some(hostname) = self.hostname.as_ref() {
+            format!("{} ({})", &self.target, hostname)
+        } else {
+            self.target.to_owned()
+        }
+    }
+
     pub fn is_partial(&self) -> bool {
         self.partial
     }
@@ -68,11 +99,21 @@ impl Loot {
         serde_json::to_string(self).map_err(|e| e.to_string())
     }
 
+    // tags from the extended targets file if any
+    fn tags_string(&self) -> Option<String> {
+        if self.tags.is_empty() {
+            None
+        } else {
+            Some(format!("tags={}", self.tags.join(",")))
+        }
+    }
+
     fn to_text(&self) -> Result<String, Error> {
         let data = self
             .data
             .keys()
             .map(|k| format!("{}={}", k, self.data.get(k).unwrap()))
+            .chain(self.tags_string())
             .collect::<Vec<String>>()
             .join("\t");
 
@@ -83,7 +124,7 @@ impl Loot {
                 "[{}] ({}) <{}> {}",
                 self.found_at_string(),
                 &self.plugin,
-                &self.target,
+                self.target_string(),
                 data
             )
         })
@@ -97,10 +138,17 @@ impl Loot {
                 .map_err(|e| e.to_string())?;
         }
 
+        // hostname and tags go with the data to keep the same columns for every loot
         let data = self
-            .data
-            .keys()
-            .map(|k| format!("{}={}", k, self.data.get(k).unwrap()))
+            .hostname
+            .iter()
+            .map(|hostname| format!("hostname={}", hostname))
+            .chain(
+                self.data
+                    .keys()
+                    .map(|k| format!("{}={}", k, self.data.get(k).unwrap())),
+            )
+            .chain(self.tags_string())
             .collect::<Vec<String>>()
             .join(";");
 
@@ -135,6 +183,9 @@ impl fmt::Display for Loot {
                 str.push_str(&format!("{}={} ", key, Colour::Green.bold().paint(value)));
             }
         }
+        if let Some(tags) = self.tags_string() {
+            str.push_str(&tags);
+        }
 
         if self.target.is_empty() {
             write!(
@@ -150,9 +201,75 @@ impl fmt::Display for Loot {
                 "[{}] ({}) <{}> {}",
                 self.found_at_string(),
                 &self.plugin,
-                &self.target,
+                self.target_string(),
                 str.trim_end()
             )
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Loot;
+
+    fn loot() -> Loot {
+        Loot::new(
+            "ssh",
+            "10.0.0.1:22",
+            [
+                ("username".to_owned(), "root".to_owned()),
+                ("password".to_owned(), "toor".to_owned()),
+            ],
+        )
+    }
+
+    #[test]
+    fn can_format_targets() {
+        let mut loot = loot();
+        assert_eq!(loot.target_string(), "10.0.0.1:22");
+
+        loot.set_hostname("host.local".to_owned());
+        assert_eq!(loot.target_string(), "10.0.0.1:22 (host.local)");
+    }
+
+    #[test]
+    fn can_format_csv() {
+        let path = tempfile::NamedTempFile::new().unwrap();
+        let path = path.path().to_str().unwrap();
+
+        // existing file, no header
+        let mut loot = loot();
+        let csv = loot.to_csv(path).unwrap();
+        let fields: Vec<&str> = csv.trim().split(',').collect();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(
+            fields[1..],
+            ["ssh", "10.0.0.1:22", "username=root;password=toor"]
+        );
+
+        loot.set_hostname("host.local".to_owned());
+        let csv = loot.to_csv(path).unwrap();
+        let fields: Vec<&str> = csv.trim().split(',').collect();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[3], "hostname=host.local;username=root;password=toor");
+
+        loot.set_tags(vec!["prod".to_owned(), "dmz".to_owned()]);
+        let csv = loot.to_csv(path).unwrap();
+        let record = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes())
+            .records()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.len(), 4);
+        assert_eq!(
+            &record[3],
+            "hostname=host.local;username=root;password=toor;tags=prod,dmz"
+        );
+
+        // new file
+        let csv = loot.to_csv("/nonexistent/loot.csv").unwrap();
+        assert!(csv.starts_with("found_at,plugin,target,data\n"));
+    }
+}
@@ -5,22 +5,28 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use serde::{Deserialize, Serialize};
 
 use crate::creds::{Combinator, Expression};
+use crate::plugins;
 use crate::Options;
 
 pub(crate) mod loot;
+pub(crate) mod pipeline;
 mod runtime;
 
 use runtime::*;
 
-use crate::utils::{parse_multiple_targets, parse_target};
+use crate::utils::{resolver, Targets};
 pub(crate) use crate::Credentials;
 pub(crate) use loot::Loot;
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 use std::time;
 
 pub(crate) type Error = String;
 
+// the ctrl-c handler can only be set once, so it stops whichever session is running
+static CTRLC_HANDLER: Once = Once::new();
+static CURRENT_SESSION: Mutex<Option<Arc<Session>>> = Mutex::new(None);
+
 async fn periodic_saver(session: Arc<Session>) {
     let one_sec = time::Duration::from_millis(1000);
     let mut last_done: usize = 0;
@@ -54,7 +60,7 @@ async fn periodic_saver(session: Arc<Session>) {
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct Session {
     pub options: Options,
-    pub targets: Vec<String>,
+    pub targets: Targets,
     pub total: AtomicUsize,
     pub done: AtomicUsize,
     pub errors: AtomicUsize,
@@ -65,20 +71,14 @@ pub(crate) struct Session {
 }
 
 impl Session {
-    fn from_options(options: Options) -> Result<Arc<Self>, Error> {
-        let targets = if let Some(target) = options.target.as_ref() {
-            parse_multiple_targets(target)?
-        } else {
-            return Err("no --target/-T argument provided".to_owned());
-        };
-
-        if targets.is_empty() {
-            return Err("empty list of target(s) provided".to_owned());
-        }
-
-        // perform pre-emptive target validation
-        for target in &targets {
-            parse_target(target, 0)?;
+    fn from_options(mut options: Options) -> Result<Self, Error> {
+        let targets = Targets::from_options(&options)?;
+
+        // make sure the shuffling order and the samples can be reproduced when the session is restored
+        let random =
+            options.shuffle_targets || options.sample.is_some() || options.max_attempts.is_some();
+        if random && options.seed.is_none() {
+            options.seed = Some(rand::random());
         }
 
         let runtime = Runtime::new(options.concurrency);
@@ -87,7 +87,7 @@ impl Session {
         let errors = AtomicUsize::new(0);
         let results = Mutex::new(vec![]);
 
-        Ok(Arc::new(Self {
+        Ok(Self {
             options,
             targets,
             total,
@@ -95,10 +95,10 @@ impl Session {
             errors,
             results,
             runtime,
-        }))
+        })
     }
 
-    fn from_disk(path: &str, options: Options) -> Result<Arc<Self>, Error> {
+    fn from_disk(path: &str, options: Options) -> Result<Self, Error> {
         if Path::new(path).exists() {
             log::info!("restoring session from {}", path);
 
@@ -107,15 +107,15 @@ impl Session {
 
             session.runtime = Runtime::new(session.options.concurrency);
 
-            Ok(Arc::new(session))
+            Ok(session)
         } else {
             Self::from_options(options)
         }
     }
 
-    pub fn new(options: Options) -> Result<Arc<Self>, Error> {
+    pub async fn new(options: Options) -> Result<Arc<Self>, Error> {
         // if a session file has been specified
-        let session = if let Some(path) = options.session.as_ref() {
+        let mut session = if let Some(path) = options.session.as_ref() {
             // load from disk if file exists, or from options and save to disk
             Self::from_disk(path, options.clone())?
         } else {
@@ -123,6 +123,12 @@ impl Session {
             Self::from_options(options)?
         };
 
+        // resolve the targets hostnames once before starting, unless the plugin works offline
+        if plugins::manager::requires_network(&session.options) {
+            resolver::pre_resolve(&session.options, &mut session.targets).await?;
+        }
+        let session = Arc::new(session);
+
         let num_targets = session.targets.len();
         log::info!(
             "target{}: {}",
@@ -135,12 +141,16 @@ impl Session {
         );
 
         // set ctrl-c handler
-        let le_session = session.clone();
-        ctrlc::set_handler(move || {
-            log::info!("stopping ...");
-            le_session.set_stop();
-        })
-        .expect("error setting ctrl-c handler");
+        *CURRENT_SESSION.lock().unwrap() = Some(session.clone());
+        CTRLC_HANDLER.call_once(|| {
+            ctrlc::set_handler(move || {
+                log::info!("stopping ...");
+                if let Some(session) = CURRENT_SESSION.lock().unwrap().as_ref() {
+                    session.set_stop();
+                }
+            })
+            .expect("error setting ctrl-c handler");
+        });
 
         tokio::task::spawn(periodic_saver(session.clone()));
 
@@ -228,7 +238,15 @@ impl Session {
         Ok(combinator)
     }
 
-    pub async fn add_loot(&self, loot: Loot) -> Result<(), Error> {
+    pub async fn add_loot(&self, mut loot: Loot) -> Result<(), Error> {
+        // enrich with the target hostname if needed
+        if self.options.ptr_lookup {
+            if let Some(hostname) = resolver::reverse_lookup(&self.options, loot.get_target()).await
+            {
+                loot.set_hostname(hostname);
+            }
+        }
+
         // append to loot vector
         if let Ok(mut results) = self.results.lock() {
             if !results.contains(&loot) {
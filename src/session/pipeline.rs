@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use clap::Parser;
+use tempfile::NamedTempFile;
+
+use crate::session::{Error, Loot, Session};
+use crate::utils::{is_relevant_port, parse_target, plugin_for_port};
+use crate::Options;
+
+// a follow-up stage started with --then once the session is done
+pub(crate) struct Stage {
+    pub options: Options,
+    // wordlist with the usernames harvested by the previous stage if any, kept until the
+    // stage is dropped
+    _usernames_file: Option<NamedTempFile>,
+}
+
+// the host of the loot target, the port is dropped since the next plugin likely uses a different one
+fn loot_host(loot: &Loot) -> String {
+    match parse_target(loot.get_target(), 0) {
+        Ok((host, _)) if host.contains(':') => format!("[{}]", host),
+        Ok((host, _)) => host,
+        Err(_) => loot.get_target().to_owned(),
+    }
+}
+
+fn stage_options(
+    session: &Session,
+    then: &str,
+    targets: &[String],
+    username: Option<String>,
+) -> Result<Options, Error> {
+    let mut options = session.options.clone();
+    options.then = None;
+    options.session = None;
+    options.combinations = None;
+    options.target = Some(targets.join(","));
+    options.username = username;
+
+    // same as the command line: the plugin name followed by its arguments, these take
+    // precedence over the harvested targets and usernames
+    let mut argv = vec!["".to_owned()];
+    argv.extend(
+        shell_words::split(then).map_err(|e| format!("can't parse --then arguments: {}", e))?,
+    );
+    options.try_update_from(argv).map_err(|e| e.to_string())?;
+
+    Ok(options)
+}
+
+// open ports found by the port scanner that the follow-up plugin (or the auto plugin) can
+// handle, as host:port targets
+fn relevant_ports(
+    plugin: Option<&str>,
+    scanned: &BTreeSet<(String, u16, Option<String>)>,
+) -> Vec<String> {
+    let mut targets = vec![];
+    for (host, port, protocol) in scanned {
+        let relevant = match plugin {
+            None | Some("auto") => plugin_for_port(*port).is_some(),
+            plugin => is_relevant_port(plugin, *port, protocol.as_deref()),
+        };
+        let target = format!("{}:{}", host, port);
+        if relevant && !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+// names found in the certificates by the tls plugin, as name:port targets on the same port,
+// wildcards are skipped since they can't be connected to
+fn certificate_names(loot: &Loot) -> Vec<String> {
+    let Ok((_, port)) = parse_target(loot.get_target(), 443) else {
+        return vec![];
+    };
+    loot.get_value("names")
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty() && !name.contains('*'))
+        .map(|name| format!("{}:{}", name, port))
+        .collect()
+}
+
+// build the follow-up stages from the usernames confirmed on each target, targets sharing the
+// same set of usernames are grouped in a single stage, hosts found by the discovery plugin,
+// relevant ports found by the port scanner and certificate names found by the tls plugin
+// are grouped in stages of their own
+pub(crate) fn follow_up_stages(session: &Session) -> Result<Vec<Stage>, Error> {
+    let Some(then) = session.options.then.as_ref() else {
+        return Ok(vec![]);
+    };
+
+    let mut per_target: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut discovered: BTreeSet<String> = BTreeSet::new();
+    let mut scanned: BTreeSet<(String, u16, Option<String>)> = BTreeSet::new();
+    let mut virtual_hosts: BTreeSet<String> = BTreeSet::new();
+    for loot in session.results.lock().unwrap().iter() {
+        if let Some(username) = loot.get_value("username") {
+            per_target
+                .entry(loot_host(loot))
+                .or_default()
+                .insert(username.to_owned());
+        } else if loot.get_plugin() == "discovery" {
+            discovered.insert(loot_host(loot));
+        } else if loot.get_plugin() == "tls" {
+            virtual_hosts.extend(certificate_names(loot));
+        } else if matches!(loot.get_plugin(), "port.scanner" | "udp.probe") {
+            if let Some(port) = loot.get_value("port").and_then(|p| p.parse::<u16>().ok()) {
+                scanned.insert((
+                    loot_host(loot),
+                    port,
+                    loot.get_value("protocol").map(|p| p.to_owned()),
+                ));
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<BTreeSet<String>, Vec<String>> = BTreeMap::new();
+    for (target, usernames) in per_target {
+        groups.entry(usernames).or_default().push(target);
+    }
+
+    let mut stages = vec![];
+    for (usernames, targets) in groups {
+        let mut usernames_file = tempfile::Builder::new()
+            .prefix("legba-then-")
+            .suffix(".txt")
+            .tempfile()
+            .map_err(|e| format!("can't create the usernames file: {}", e))?;
+        for username in usernames.iter() {
+            writeln!(usernames_file, "{}", username)
+                .map_err(|e| format!("can't write {}: {}", usernames_file.path().display(), e))?;
+        }
+
+        let path = usernames_file
+            .path()
+            .to_str()
+            .ok_or(format!(
+                "invalid usernames file path {}",
+                usernames_file.path().display()
+            ))?
+            .to_owned();
+        let options = stage_options(session, then, &targets, Some(path))?;
+
+        log::info!(
+            "then: {} with {} username(s) against {}",
+            options.plugin.as_deref().unwrap_or("auto"),
+            usernames.len(),
+            options.target.as_deref().unwrap_or("?"),
+        );
+
+        stages.push(Stage {
+            options,
+            _usernames_file: Some(usernames_file),
+        });
+    }
+
+    if !discovered.is_empty() {
+        // nothing was harvested, the credentials come from the --then arguments
+        let targets: Vec<String> = discovered.into_iter().collect();
+        let options = stage_options(session, then, &targets, None)?;
+
+        log::info!(
+            "then: {} against {} discovered host(s)",
+            options.plugin.as_deref().unwrap_or("auto"),
+            targets.len(),
+        );
+
+        stages.push(Stage {
+            options,
+            _usernames_file: None,
+        });
+    }
+
+    if !scanned.is_empty() {
+        let plugin = stage_options(session, then, &[], None)?.plugin;
+        let targets = relevant_ports(plugin.as_deref(), &scanned);
+        if targets.is_empty() {
+            log::info!(
+                "then: no open port relevant for {}",
+                plugin.as_deref().unwrap_or("auto")
+            );
+        } else {
+            let options = stage_options(session, then, &targets, None)?;
+
+            log::info!(
+                "then: {} against {} open port(s)",
+                options.plugin.as_deref().unwrap_or("auto"),
+                targets.len(),
+            );
+
+            stages.push(Stage {
+                options,
+                _usernames_file: None,
+            });
+        }
+    }
+
+    if !virtual_hosts.is_empty() {
+        let targets: Vec<String> = virtual_hosts.into_iter().collect();
+        let options = stage_options(session, then, &targets, None)?;
+
+        log::info!(
+            "then: {} against {} certificate name(s)",
+            options.plugin.as_deref().unwrap_or("auto"),
+            targets.len(),
+        );
+
+        stages.push(Stage {
+            options,
+            _usernames_file: None,
+        });
+    }
+
+    if stages.is_empty() {
+        log::info!("then: no usernames or hosts found, nothing to do");
+    }
+
+    Ok(stages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::follow_up_stages;
+    use crate::session::{Loot, Session};
+    use crate::Options;
+
+    #[test]
+    fn can_build_stages_from_usernames() {
+        let options = Options {
+            plugin: Some("kerberos".to_owned()),
+            target: Some("10.0.0.1:88, 10.0.0.2:88".to_owned()),
+            then: Some("ldap --password 'my passwords.txt'".to_owned()),
+            concurrency: 1,
+            ..Default::default()
+        };
+        let session = Session::from_options(options).unwrap();
+        {
+            let mut results = session.results.lock().unwrap();
+            for (target, username) in [
+                ("10.0.0.1:88", "jdoe"),
+                ("10.0.0.1:88", "admin"),
+                ("10.0.0.2:88", "admin"),
+            ] {
+                results.push(Loot::new(
+                    "kerberos",
+                    target,
+                    [("username".to_owned(), username.to_owned())],
+                ));
+            }
+        }
+
+        let stages = follow_up_stages(&session).unwrap();
+        assert_eq!(stages.len(), 2);
+
+        let targets: Vec<&str> = stages
+            .iter()
+            .map(|s| s.options.target.as_deref().unwrap())
+            .collect();
+        assert!(targets.contains(&"10.0.0.1") && targets.contains(&"10.0.0.2"));
+
+        for stage in stages.iter() {
+            assert_eq!(stage.options.plugin.as_deref(), Some("ldap"));
+            assert_eq!(stage.options.password.as_deref(), Some("my passwords.txt"));
+            assert!(stage.options.then.is_none());
+
+            let usernames =
+                std::fs::read_to_string(stage.options.username.as_ref().unwrap()).unwrap();
+            if stage.options.target.as_deref() == Some("10.0.0.1") {
+                assert_eq!(usernames, "admin\njdoe\n");
+            } else {
+                assert_eq!(usernames, "admin\n");
+            }
+        }
+    }
+
+    #[test]
+    fn can_build_stage_from_discovered_hosts() {
+        let options = Options {
+            plugin: Some("discovery".to_owned()),
+            target: Some("192.168.1.0/30".to_owned()),
+            then: Some("smb -U users.txt -P passwords.txt".to_owned()),
+            concurrency: 1,
+            ..Default::default()
+        };
+        let session = Session::from_options(options).unwrap();
+        {
+            let mut results = session.results.lock().unwrap();
+            for target in ["192.168.1.2", "192.168.1.1"] {
+                results.push(Loot::new(
+                    "discovery",
+                    target,
+                    [("address".to_owned(), target.to_owned())],
+                ));
+            }
+        }
+
+        let stages = follow_up_stages(&session).unwrap();
+        assert_eq!(stages.len(), 1);
+
+        let stage = &stages[0];
+        assert_eq!(stage.options.plugin.as_deref(), Some("smb"));
+        assert_eq!(
+            stage.options.target.as_deref(),
+            Some("192.168.1.1,192.168.1.2")
+        );
+        assert_eq!(stage.options.username.as_deref(), Some("users.txt"));
+        assert_eq!(stage.options.password.as_deref(), Some("passwords.txt"));
+    }
+
+    #[test]
+    fn can_build_stage_from_open_ports() {
+        let options = Options {
+            plugin: Some("port.scanner".to_owned()),
+            target: Some("10.0.0.1".to_owned()),
+            then: Some("ssh --username root --password passwords.txt".to_owned()),
+            concurrency: 1,
+            ..Default::default()
+        };
+        let session = Session::from_options(options).unwrap();
+        {
+            let mut results = session.results.lock().unwrap();
+            for (port, protocol) in [("22", "ssh"), ("80", "http"), ("2222", "ssh"), ("8022", "")] {
+                results.push(Loot::new(
+                    "port.scanner",
+                    "10.0.0.1",
+                    [
+                        ("transport".to_owned(), "tcp".to_owned()),
+                        ("port".to_owned(), port.to_owned()),
+                        ("protocol".to_owned(), protocol.to_owned()),
+                    ],
+                ));
+            }
+        }
+
+        let stages = follow_up_stages(&session).unwrap();
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].options.plugin.as_deref(), Some("ssh"));
+        assert_eq!(
+            stages[0].options.target.as_deref(),
+            Some("10.0.0.1:22,10.0.0.1:2222")
+        );
+    }
+
+    #[test]
+    fn can_build_stage_from_certificate_names() {
+        let options = Options {
+            plugin: Some("tls".to_owned()),
+            target: Some("10.0.0.1:8443".to_owned()),
+            then: Some("http.enum --payloads paths.txt".to_owned()),
+            concurrency: 1,
+            ..Default::default()
+        };
+        let session = Session::from_options(options).unwrap();
+        session.results.lock().unwrap().push(Loot::new(
+            "tls",
+            "10.0.0.1:8443",
+            [(
+                "names".to_owned(),
+                "www.example.com, *.example.com, api.example.com, www.example.com".to_owned(),
+            )],
+        ));
+
+        let stages = follow_up_stages(&session).unwrap();
+        assert_eq!(stages.len(), 1);
+        assert_eq!(stages[0].options.plugin.as_deref(), Some("http.enum"));
+        assert_eq!(
+            stages[0].options.target.as_deref(),
+            Some("api.example.com:8443,www.example.com:8443")
+        );
+    }
+}
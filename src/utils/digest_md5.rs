@@ -0,0 +1,117 @@
+// RFC 2831 SASL DIGEST-MD5 client response
+use md5::{Digest, Md5};
+use rand::Rng;
+
+use crate::session::Error;
+
+// key="value" or key=value pairs of a DIGEST-MD5 challenge
+fn parse_challenge(challenge: &str) -> Vec<(String, String)> {
+    let mut pairs = vec![];
+    let mut rest = challenge.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+        } else {
+            after.split_once(',').unwrap_or((after, ""))
+        };
+        pairs.push((key.trim().to_owned(), value.to_owned()));
+        rest = after.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    pairs
+}
+
+fn md5(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Md5::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+// response value for qop=auth
+fn digest_response(
+    username: &str,
+    realm: &str,
+    password: &str,
+    nonce: &str,
+    cnonce: &str,
+    digest_uri: &str,
+) -> String {
+    let secret = md5(&[format!("{}:{}:{}", username, realm, password).as_bytes()]);
+    let a1 = md5(&[&secret, format!(":{}:{}", nonce, cnonce).as_bytes()]);
+    let a2 = md5(&[format!("AUTHENTICATE:{}", digest_uri).as_bytes()]);
+    hex::encode(md5(&[format!(
+        "{}:{}:00000001:{}:auth:{}",
+        hex::encode(a1),
+        nonce,
+        cnonce,
+        hex::encode(a2)
+    )
+    .as_bytes()]))
+}
+
+// client response to the server challenge, the realm is only used if the server doesn't provide one
+pub(crate) fn client_response(
+    challenge: &[u8],
+    realm: &str,
+    digest_uri: &str,
+    username: &str,
+    password: &str,
+) -> Result<String, Error> {
+    let challenge = parse_challenge(&String::from_utf8_lossy(challenge));
+    let get = |key: &str| {
+        challenge
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let nonce = get("nonce").ok_or("no nonce in DIGEST-MD5 challenge".to_owned())?;
+    let realm = get("realm").unwrap_or(realm);
+    let cnonce = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+
+    Ok(format!(
+        "username=\"{}\",realm=\"{}\",nonce=\"{}\",cnonce=\"{}\",nc=00000001,qop=auth,digest-uri=\"{}\",response={},charset=utf-8",
+        username,
+        realm,
+        nonce,
+        cnonce,
+        digest_uri,
+        digest_response(username, realm, password, nonce, &cnonce, digest_uri)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{client_response, digest_response, parse_challenge};
+
+    #[test]
+    fn can_compute_digest_md5_response() {
+        // RFC 2831 example
+        assert_eq!(
+            digest_response(
+                "chris",
+                "elwood.innosoft.com",
+                "secret",
+                "OA6MG9tEQGm2hh",
+                "OA6MHXh6VqTrRk",
+                "imap/elwood.innosoft.com"
+            ),
+            "d388dad90d4bbd760a152321f2143af7"
+        );
+        assert_eq!(
+            parse_challenge(
+                "realm=\"elwood.innosoft.com\",nonce=\"OA6MG9tEQGm2hh\",qop=\"auth\",algorithm=md5-sess,charset=utf-8"
+            ),
+            vec![
+                ("realm".to_owned(), "elwood.innosoft.com".to_owned()),
+                ("nonce".to_owned(), "OA6MG9tEQGm2hh".to_owned()),
+                ("qop".to_owned(), "auth".to_owned()),
+                ("algorithm".to_owned(), "md5-sess".to_owned()),
+                ("charset".to_owned(), "utf-8".to_owned()),
+            ]
+        );
+        assert!(client_response(b"realm=\"x\"", "", "imap/x", "chris", "secret").is_err());
+    }
+}
@@ -0,0 +1,60 @@
+// minimal html scraping for login forms
+use regex::Regex;
+
+// double or single quoted attributes of a tag
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let re = Regex::new(r#"(?i)([a-z_:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    re.captures_iter(tag)
+        .map(|caps| {
+            let value = caps.get(2).or_else(|| caps.get(3)).unwrap().as_str();
+            (caps[1].to_lowercase(), value.to_owned())
+        })
+        .collect()
+}
+
+fn find_tag(html: &str, tag: &str, key: &str, name: &str, value: &str) -> Option<String> {
+    let re = Regex::new(&format!(r"(?is)<{}\b[^>]*>", tag)).unwrap();
+    let found = re.find_iter(html).find_map(|m| {
+        let attrs = attributes(m.as_str());
+        if attrs.iter().any(|(k, v)| k == key && v == name) {
+            attrs.into_iter().find(|(k, _)| k == value).map(|(_, v)| v)
+        } else {
+            None
+        }
+    });
+    found
+}
+
+// value of the input field with the given name, usually a CSRF token
+pub(crate) fn input_value(html: &str, name: &str) -> Option<String> {
+    find_tag(html, "input", "name", name, "value")
+}
+
+// content of the meta tag with the given name
+pub(crate) fn meta_content(html: &str, name: &str) -> Option<String> {
+    find_tag(html, "meta", "name", name, "content")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{input_value, meta_content};
+
+    #[test]
+    fn can_extract_tokens() {
+        let html = r#"<form action="/user/login" method="post">
+            <input type="hidden" name="_csrf" value="abc123-_=">
+            <input type='hidden' value='tok+/=' name='authenticity_token' autocomplete="off" />
+            <input id="user_name" name="user_name" type="text">
+        </form>"#;
+        assert_eq!(input_value(html, "_csrf"), Some("abc123-_=".to_owned()));
+        assert_eq!(
+            input_value(html, "authenticity_token"),
+            Some("tok+/=".to_owned())
+        );
+        assert_eq!(input_value(html, "user_name"), None);
+        assert_eq!(
+            meta_content(r#"<meta name="csrf-token" content="xyz" />"#, "csrf-token"),
+            Some("xyz".to_owned())
+        );
+    }
+}
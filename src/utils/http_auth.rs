@@ -0,0 +1,176 @@
+// RFC 7617 Basic and RFC 2617 Digest authentication for HTTP-like protocols
+use md5::{Digest, Md5};
+use rand::Rng;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum Challenge {
+    Basic,
+    Digest {
+        realm: String,
+        nonce: String,
+        opaque: Option<String>,
+        qop: bool,
+    },
+}
+
+// key="value" or key=value pairs of a Digest challenge
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    let mut pairs = vec![];
+    let mut rest = params.trim();
+    while let Some((key, after)) = rest.split_once('=') {
+        let (value, after) = if let Some(quoted) = after.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted.get(end + 1..).unwrap_or(""))
+        } else {
+            after.split_once(',').unwrap_or((after, ""))
+        };
+        pairs.push((key.trim().to_lowercase(), value.to_owned()));
+        rest = after.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    pairs
+}
+
+impl Challenge {
+    // parses the WWW-Authenticate header values, prefers MD5 Digest when both schemes are offered
+    pub fn parse<'a, I: IntoIterator<Item = &'a str>>(values: I) -> Option<Self> {
+        let mut challenge = None;
+        for value in values {
+            let (scheme, params) = value.split_once(' ').unwrap_or((value, ""));
+            if scheme.eq_ignore_ascii_case("digest") {
+                let params = parse_params(params);
+                let get = |key: &str| {
+                    params
+                        .iter()
+                        .find(|(k, _)| k == key)
+                        .map(|(_, v)| v.to_owned())
+                };
+                if get("algorithm").is_some_and(|a| !a.eq_ignore_ascii_case("md5")) {
+                    // SHA-256 and the likes are usually offered alongside MD5
+                    continue;
+                }
+                return Some(Challenge::Digest {
+                    realm: get("realm").unwrap_or_default(),
+                    nonce: get("nonce")?,
+                    opaque: get("opaque"),
+                    qop: get("qop").is_some_and(|q| q.split(',').any(|q| q.trim() == "auth")),
+                });
+            } else if scheme.eq_ignore_ascii_case("basic") {
+                challenge = Some(Challenge::Basic);
+            }
+        }
+        challenge
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Challenge::Basic => "basic",
+            Challenge::Digest { .. } => "digest",
+        }
+    }
+
+    pub fn authorization(&self, method: &str, uri: &str, username: &str, password: &str) -> String {
+        match self {
+            Challenge::Basic => format!(
+                "Basic {}",
+                base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    format!("{}:{}", username, password)
+                )
+            ),
+            Challenge::Digest {
+                realm,
+                nonce,
+                opaque,
+                qop,
+            } => {
+                let cnonce = hex::encode(rand::thread_rng().gen::<[u8; 8]>());
+                let mut header = format!(
+                    "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", response=\"{}\"",
+                    username,
+                    realm,
+                    nonce,
+                    uri,
+                    digest_response(
+                        username,
+                        realm,
+                        password,
+                        method,
+                        uri,
+                        nonce,
+                        if *qop { Some(&cnonce) } else { None }
+                    )
+                );
+                if *qop {
+                    header += &format!(", qop=auth, nc=00000001, cnonce=\"{}\"", cnonce);
+                }
+                if let Some(opaque) = opaque {
+                    header += &format!(", opaque=\"{}\"", opaque);
+                }
+                header
+            }
+        }
+    }
+}
+
+fn md5_hex(value: &str) -> String {
+    hex::encode(Md5::digest(value.as_bytes()))
+}
+
+// RFC 2617 response, with qop=auth if a client nonce is provided
+fn digest_response(
+    username: &str,
+    realm: &str,
+    password: &str,
+    method: &str,
+    uri: &str,
+    nonce: &str,
+    cnonce: Option<&str>,
+) -> String {
+    let ha1 = md5_hex(&format!("{}:{}:{}", username, realm, password));
+    let ha2 = md5_hex(&format!("{}:{}", method, uri));
+    match cnonce {
+        Some(cnonce) => md5_hex(&format!(
+            "{}:{}:00000001:{}:auth:{}",
+            ha1, nonce, cnonce, ha2
+        )),
+        None => md5_hex(&format!("{}:{}:{}", ha1, nonce, ha2)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{digest_response, Challenge};
+
+    #[test]
+    fn can_compute_digest_response() {
+        // RFC 2617 section 3.5
+        assert_eq!(
+            digest_response(
+                "Mufasa",
+                "testrealm@host.com",
+                "Circle Of Life",
+                "GET",
+                "/dir/index.html",
+                "dcd98b7102dd2f0e8b11d0f600bfb0c093",
+                Some("0a4f113b")
+            ),
+            "6629fae49393a05397450978507c4ef1"
+        );
+    }
+
+    #[test]
+    fn can_skip_unsupported_digest_algorithms() {
+        assert_eq!(
+            Challenge::parse([
+                "Digest realm=\"ManagementRealm\", nonce=\"a\", algorithm=SHA-256, qop=auth",
+                "Digest realm=\"ManagementRealm\", nonce=\"b\", opaque=\"00\", algorithm=MD5, qop=auth",
+            ]),
+            Some(Challenge::Digest {
+                realm: "ManagementRealm".to_owned(),
+                nonce: "b".to_owned(),
+                opaque: Some("00".to_owned()),
+                qop: true,
+            })
+        );
+    }
+}
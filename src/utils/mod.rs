@@ -1,4 +1,45 @@
+#[cfg(any(feature = "ldap", feature = "zookeeper"))]
+pub(crate) mod digest_md5;
+#[cfg(any(feature = "gitea", feature = "gitlab"))]
+pub(crate) mod html;
+#[cfg(any(feature = "rtsp", feature = "wildfly"))]
+pub(crate) mod http_auth;
 pub(crate) mod net;
+#[cfg(any(feature = "winrm", feature = "rdp", feature = "exchange"))]
+pub(crate) mod ntlm;
+#[cfg(any(feature = "imap", feature = "pop3"))]
+pub(crate) mod oauth;
+#[cfg(any(
+    feature = "vnc",
+    feature = "elastic",
+    feature = "cql",
+    feature = "nats",
+    feature = "kafka",
+    feature = "rtsp",
+    feature = "http_proxy",
+    feature = "etcd",
+    feature = "consul",
+    feature = "influxdb",
+    feature = "tomcat",
+    feature = "couchdb",
+    feature = "k8s",
+    feature = "ldap",
+    feature = "ipmi",
+    feature = "redis",
+    feature = "zookeeper",
+    feature = "msol",
+    feature = "ssh",
+    feature = "kerberos",
+    feature = "amqp",
+    feature = "exchange",
+    feature = "samba"
+))]
+pub(crate) mod once;
+pub(crate) mod resolver;
 mod target;
+#[cfg(test)]
+pub(crate) mod testing;
+#[cfg(any(feature = "onvif", feature = "sslvpn", feature = "wordpress"))]
+pub(crate) mod xml;
 
 pub(crate) use target::*;
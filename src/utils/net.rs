@@ -42,7 +42,9 @@ pub(crate) async fn async_tcp_stream(
     timeout: Duration,
     ssl: bool,
 ) -> Result<Box<dyn StreamLike>, Error> {
-    let tcp_stream = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address))
+    // use the pre-resolved address if available
+    let address = super::resolver::resolve_address(address);
+    let tcp_stream = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(&address))
         .await
         .map_err(|e| e.to_string())?
         .map_err(|e| e.to_string())?;
@@ -53,3 +55,23 @@ pub(crate) async fn async_tcp_stream(
         Ok(Box::new(tcp_stream))
     }
 }
+
+// bind an udp socket of the same family of the target and connect it
+pub(crate) async fn async_udp_socket(address: &str) -> Result<tokio::net::UdpSocket, Error> {
+    // use the pre-resolved address if available
+    let remote = tokio::net::lookup_host(super::resolver::resolve_address(address))
+        .await
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or(format!("can't resolve {}", address))?;
+    let local = if remote.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = tokio::net::UdpSocket::bind(local)
+        .await
+        .map_err(|e| e.to_string())?;
+    socket.connect(remote).await.map_err(|e| e.to_string())?;
+    Ok(socket)
+}
@@ -0,0 +1,386 @@
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use rand::Rng;
+
+use crate::session::Error;
+
+fn utf16_le(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut mac = Hmac::<Md5>::new_from_slice(key).unwrap();
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn flags() -> ntlmclient::Flags {
+    ntlmclient::Flags::NEGOTIATE_UNICODE
+        | ntlmclient::Flags::REQUEST_TARGET
+        | ntlmclient::Flags::NEGOTIATE_NTLM
+        | ntlmclient::Flags::NEGOTIATE_WORKSTATION_SUPPLIED
+}
+
+// flags for protocols like CredSSP that require signing and sealing
+fn sealing_flags() -> ntlmclient::Flags {
+    ntlmclient::Flags::NEGOTIATE_UNICODE
+        | ntlmclient::Flags::REQUEST_TARGET
+        | ntlmclient::Flags::NEGOTIATE_SIGN
+        | ntlmclient::Flags::NEGOTIATE_SEAL
+        | ntlmclient::Flags::NEGOTIATE_NTLM
+        | ntlmclient::Flags::NEGOTIATE_ALWAYS_SIGN
+        | ntlmclient::Flags::NEGOTIATE_NTLM2_KEY
+        | ntlmclient::Flags::NEGOTIATE_128BIT
+        | ntlmclient::Flags::NEGOTIATE_KEY_EXCHANGE
+        | ntlmclient::Flags::NEGOTIATE_56BIT
+}
+
+pub(crate) fn negotiate(workstation: &str) -> Result<Vec<u8>, Error> {
+    negotiate_with_flags(workstation, flags())
+}
+
+pub(crate) fn negotiate_sealed(workstation: &str) -> Result<Vec<u8>, Error> {
+    negotiate_with_flags(
+        workstation,
+        sealing_flags() | ntlmclient::Flags::NEGOTIATE_WORKSTATION_SUPPLIED,
+    )
+}
+
+fn negotiate_with_flags(workstation: &str, flags: ntlmclient::Flags) -> Result<Vec<u8>, Error> {
+    ntlmclient::Message::Negotiate(ntlmclient::NegotiateMessage {
+        flags,
+        supplied_domain: String::new(),
+        supplied_workstation: workstation.to_owned(),
+        os_version: Default::default(),
+    })
+    .to_bytes()
+    .map_err(|e| e.to_string())
+}
+
+// NTLMv2 response computed from the NT hash, so that it works for both passwords and hashes
+fn respond_v2(
+    nt_hash: &[u8],
+    username: &str,
+    domain: &str,
+    server_challenge: &[u8],
+    target_info: &[u8],
+) -> ntlmclient::ChallengeResponse {
+    let client_challenge: [u8; 8] = rand::thread_rng().gen();
+    let key = hmac_md5(
+        nt_hash,
+        &[&utf16_le(&username.to_uppercase()), &utf16_le(domain)],
+    );
+
+    let mut blob = vec![0x01, 0x01, 0, 0, 0, 0, 0, 0];
+    blob.extend_from_slice(&ntlmclient::get_ntlm_time().to_le_bytes());
+    blob.extend_from_slice(&client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(target_info);
+    blob.extend_from_slice(&[0; 4]);
+
+    let proof = hmac_md5(&key, &[server_challenge, &blob]);
+    let lm_response = [
+        hmac_md5(&key, &[server_challenge, &client_challenge]),
+        client_challenge.to_vec(),
+    ]
+    .concat();
+    let session_key = hmac_md5(&key, &[&proof]);
+
+    ntlmclient::ChallengeResponse {
+        lm_response,
+        ntlm_response: [proof, blob].concat(),
+        session_key,
+    }
+}
+
+fn parse_challenge(challenge: &[u8]) -> Result<ntlmclient::ChallengeMessage, Error> {
+    match ntlmclient::Message::try_from(challenge).map_err(|e| e.to_string())? {
+        ntlmclient::Message::Challenge(c) => Ok(c),
+        other => Err(format!("wrong challenge message: {:?}", other)),
+    }
+}
+
+// internal names and os version disclosed by the target information of a challenge message
+pub(crate) fn disclosure(challenge: &[u8]) -> Result<Vec<(String, String)>, Error> {
+    let challenge = parse_challenge(challenge)?;
+    let mut info = vec![];
+    for entry in &challenge.target_information {
+        let key = match entry.entry_type {
+            ntlmclient::TargetInfoType::NtDomain => "netbios_domain",
+            ntlmclient::TargetInfoType::NtServer => "netbios_computer",
+            ntlmclient::TargetInfoType::DnsDomain => "dns_domain",
+            ntlmclient::TargetInfoType::DnsServer => "dns_computer",
+            ntlmclient::TargetInfoType::DnsForest => "dns_tree",
+            _ => continue,
+        };
+        if let Ok(value) = entry.to_string() {
+            info.push((key.to_owned(), value));
+        }
+    }
+
+    let version = &challenge.os_version;
+    if version.major_version > 0 {
+        info.push((
+            "os_version".to_owned(),
+            format!(
+                "{}.{}.{}",
+                version.major_version, version.minor_version, version.build_number
+            ),
+        ));
+    }
+
+    Ok(info)
+}
+
+// parse the challenge message and respond with either the password or its NT hash
+fn respond(
+    challenge: &[u8],
+    username: &str,
+    domain: &str,
+    password: &str,
+    nt_hash: Option<&str>,
+) -> Result<ntlmclient::ChallengeResponse, Error> {
+    let challenge = parse_challenge(challenge)?;
+    let target_info: Vec<u8> = challenge
+        .target_information
+        .iter()
+        .flat_map(|ie| ie.to_bytes())
+        .collect();
+
+    let nt_hash = match nt_hash {
+        Some(hash) => hex::decode(hash).map_err(|e| e.to_string())?,
+        None => ntlmclient::ntlm_v1_password_func(password).to_vec(),
+    };
+
+    Ok(respond_v2(
+        &nt_hash,
+        username,
+        domain,
+        &challenge.challenge,
+        &target_info,
+    ))
+}
+
+fn to_bytes(
+    response: &ntlmclient::ChallengeResponse,
+    username: &str,
+    domain: &str,
+    workstation: &str,
+    flags: ntlmclient::Flags,
+) -> Result<Vec<u8>, Error> {
+    let creds = ntlmclient::Credentials {
+        username: username.to_owned(),
+        password: String::new(),
+        domain: domain.to_owned(),
+    };
+    response
+        .to_message(&creds, workstation, flags)
+        .to_bytes()
+        .map_err(|e| format!("failed to encode NTLM authentication message: {}", e))
+}
+
+// create the authenticate message for the challenge with either the password or its NT hash
+pub(crate) fn authenticate(
+    challenge: &[u8],
+    username: &str,
+    domain: &str,
+    workstation: &str,
+    password: &str,
+    nt_hash: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    let response = respond(challenge, username, domain, password, nt_hash)?;
+    to_bytes(
+        &response,
+        username,
+        domain,
+        workstation,
+        ntlmclient::Flags::NEGOTIATE_UNICODE | ntlmclient::Flags::NEGOTIATE_NTLM,
+    )
+}
+
+// same as authenticate, with a random exported session key used to seal the following messages
+pub(crate) fn authenticate_sealed(
+    challenge: &[u8],
+    username: &str,
+    domain: &str,
+    workstation: &str,
+    password: &str,
+    nt_hash: Option<&str>,
+) -> Result<(Vec<u8>, Sealing), Error> {
+    let mut response = respond(challenge, username, domain, password, nt_hash)?;
+
+    // the session base key is the key exchange key for NTLMv2
+    let exported_session_key: [u8; 16] = rand::thread_rng().gen();
+    response.session_key = Rc4::new(&response.session_key).apply(&exported_session_key);
+
+    Ok((
+        to_bytes(&response, username, domain, workstation, sealing_flags())?,
+        Sealing::new(&exported_session_key),
+    ))
+}
+
+struct Rc4 {
+    state: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    fn new(key: &[u8]) -> Self {
+        let mut state = [0u8; 256];
+        for (i, b) in state.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
+        }
+        Self { state, i: 0, j: 0 }
+    }
+
+    fn apply(&mut self, data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .map(|b| {
+                self.i = self.i.wrapping_add(1);
+                self.j = self.j.wrapping_add(self.state[self.i as usize]);
+                self.state.swap(self.i as usize, self.j as usize);
+                let k = self.state[self.i as usize].wrapping_add(self.state[self.j as usize]);
+                b ^ self.state[k as usize]
+            })
+            .collect()
+    }
+}
+
+// client to server signing and sealing with extended session security
+pub(crate) struct Sealing {
+    sign_key: Vec<u8>,
+    handle: Rc4,
+    sequence: u32,
+}
+
+impl Sealing {
+    fn new(exported_session_key: &[u8]) -> Self {
+        let derive = |magic: &[u8]| {
+            Md5::new()
+                .chain_update(exported_session_key)
+                .chain_update(magic)
+                .finalize()
+                .to_vec()
+        };
+        let sign_key = derive(b"session key to client-to-server signing key magic constant\0");
+        let seal_key = derive(b"session key to client-to-server sealing key magic constant\0");
+
+        Self {
+            sign_key,
+            handle: Rc4::new(&seal_key),
+            sequence: 0,
+        }
+    }
+
+    // GSS_WrapEx: the signature followed by the sealed message
+    pub fn wrap(&mut self, message: &[u8]) -> Vec<u8> {
+        let sequence = self.sequence.to_le_bytes();
+        self.sequence += 1;
+
+        let sealed = self.handle.apply(message);
+        let checksum = self
+            .handle
+            .apply(&hmac_md5(&self.sign_key, &[&sequence, message])[..8]);
+
+        [&[1, 0, 0, 0], &checksum[..], &sequence, &sealed].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disclosure, respond_v2, Rc4, Sealing};
+
+    #[test]
+    fn can_seal_messages() {
+        assert_eq!(
+            hex::encode(Rc4::new(b"Key").apply(b"Plaintext")),
+            "bbf316e8d940af0ad3"
+        );
+
+        // MS-NLMP 4.2.4.4 test vectors
+        let mut sealing = Sealing::new(&[0x55; 16]);
+        let plaintext: Vec<u8> = "Plaintext"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes())
+            .collect();
+        assert_eq!(
+            hex::encode(sealing.wrap(&plaintext)),
+            "010000007fb38ec5c55d497600000000\
+             54e50165bf1936dc996020c1811b0f06fb5f"
+        );
+    }
+
+    #[test]
+    fn can_respond_with_hash_or_password() {
+        let password_hash = ntlmclient::ntlm_v1_password_func("Password1");
+        assert_eq!(
+            hex::encode(password_hash),
+            "64f12cddaa88057e06a81b54e73b949b"
+        );
+
+        // the same NT hash produces the same proof of the reference implementation
+        let creds = ntlmclient::Credentials {
+            username: "Administrator".to_owned(),
+            password: "Password1".to_owned(),
+            domain: "CORP".to_owned(),
+        };
+        let key = ntlmclient::ntlm_v2_password_func(&creds);
+        let response = respond_v2(&password_hash, "Administrator", "CORP", &[1; 8], &[]);
+        let blob = &response.ntlm_response[16..];
+        assert_eq!(
+            response.ntlm_response[..16],
+            super::hmac_md5(&key, &[&[1; 8], blob])[..]
+        );
+    }
+
+    #[test]
+    fn can_parse_challenge_disclosure() {
+        let entry = ntlmclient::TargetInfoEntry::from_string;
+        let target_info: Vec<u8> = [
+            entry(ntlmclient::TargetInfoType::NtDomain, "CORP"),
+            entry(ntlmclient::TargetInfoType::NtServer, "EXCH01"),
+            entry(ntlmclient::TargetInfoType::DnsDomain, "corp.local"),
+            entry(ntlmclient::TargetInfoType::DnsServer, "exch01.corp.local"),
+            entry(ntlmclient::TargetInfoType::DnsForest, "corp.local"),
+            entry(ntlmclient::TargetInfoType::Terminator, ""),
+        ]
+        .iter()
+        .flat_map(|e| e.to_bytes())
+        .collect();
+        let target_name = super::utf16_le("CORP");
+
+        // type 2 message with the payload right after the 56 bytes header
+        let mut challenge = b"NTLMSSP\0".to_vec();
+        challenge.extend_from_slice(&2u32.to_le_bytes());
+        challenge.extend_from_slice(&(target_name.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(target_name.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&56u32.to_le_bytes());
+        challenge.extend_from_slice(&0xe2898215u32.to_le_bytes());
+        challenge.extend_from_slice(&[1; 8]);
+        challenge.extend_from_slice(&[0; 8]);
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(56 + target_name.len() as u32).to_le_bytes());
+        challenge.extend_from_slice(&[10, 0, 0x63, 0x45, 0, 0, 0, 15]);
+        challenge.extend_from_slice(&target_name);
+        challenge.extend_from_slice(&target_info);
+
+        let info = disclosure(&challenge).unwrap();
+        let get = |key: &str| info.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("netbios_domain"), Some("CORP"));
+        assert_eq!(get("netbios_computer"), Some("EXCH01"));
+        assert_eq!(get("dns_domain"), Some("corp.local"));
+        assert_eq!(get("dns_computer"), Some("exch01.corp.local"));
+        assert_eq!(get("dns_tree"), Some("corp.local"));
+        assert_eq!(get("os_version"), Some("10.0.17763"));
+        assert!(disclosure(&super::negotiate("LEGBA").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+// SASL initial client responses for OAuth 2.0 access tokens
+
+// Google and Microsoft XOAUTH2
+pub(crate) fn xoauth2(username: &str, token: &str) -> String {
+    format!("user={}\x01auth=Bearer {}\x01\x01", username, token)
+}
+
+// RFC 7628 OAUTHBEARER
+pub(crate) fn oauthbearer(username: &str, token: &str) -> String {
+    format!("n,a={},\x01auth=Bearer {}\x01\x01", username, token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{oauthbearer, xoauth2};
+
+    #[test]
+    fn can_build_initial_responses() {
+        assert_eq!(
+            xoauth2("someuser@example.com", "ya29.vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg"),
+            "user=someuser@example.com\x01auth=Bearer ya29.vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg\x01\x01"
+        );
+        assert_eq!(
+            oauthbearer("user@example.com", "vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg=="),
+            "n,a=user@example.com,\x01auth=Bearer vF9dft4qmTc2Nvb3RlckBhdHRhdmlzdGEuY29tCg==\x01\x01"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, RwLock};
+
+use ahash::HashSet;
+
+use crate::session::Loot;
+
+// targets (or users) shared by the workers of a plugin, used to report findings that don't depend
+// on the credentials (like missing authentication) only once per target
+#[derive(Clone, Default)]
+pub(crate) struct OncePerTarget {
+    targets: Arc<RwLock<HashSet<String>>>,
+}
+
+impl OncePerTarget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // true only the first time the target is added, like HashSet::insert
+    pub fn insert(&self, target: &str) -> bool {
+        self.targets.write().unwrap().insert(target.to_owned())
+    }
+
+    pub fn contains(&self, target: &str) -> bool {
+        self.targets.read().unwrap().contains(target)
+    }
+
+    // the loot the first time the target is reported, nothing afterwards
+    pub fn report(&self, target: &str, loot: Loot) -> Option<Vec<Loot>> {
+        if self.insert(target) {
+            Some(vec![loot])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OncePerTarget;
+    use crate::session::Loot;
+
+    #[test]
+    fn reports_once_per_target() {
+        let once = OncePerTarget::new();
+        let copy = once.clone();
+        let loot = |target| {
+            Loot::new(
+                "test",
+                target,
+                [("authentication".to_owned(), "none".to_owned())],
+            )
+        };
+
+        assert!(once
+            .report("10.0.0.1:5900", loot("10.0.0.1:5900"))
+            .is_some());
+        assert!(copy
+            .report("10.0.0.1:5900", loot("10.0.0.1:5900"))
+            .is_none());
+        assert!(copy.contains("10.0.0.1:5900"));
+        assert!(!once.contains("10.0.0.2:5900"));
+        assert!(once.insert("10.0.0.2:5900"));
+        assert!(!once.insert("10.0.0.2:5900"));
+    }
+}
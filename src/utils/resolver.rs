@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+#[cfg(feature = "dns")]
+use std::sync::OnceLock;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+use tokio::task::JoinSet;
+#[cfg(feature = "dns")]
+use trust_dns_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+#[cfg(feature = "dns")]
+use trust_dns_resolver::TokioAsyncResolver;
+
+use crate::session::Error;
+use crate::Options;
+
+use super::{parse_target, Targets};
+
+// hostname -> A/AAAA records, populated once before the session starts
+static CACHE: LazyLock<RwLock<HashMap<String, Vec<IpAddr>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+// ip -> PTR record, populated on demand
+static PTR_CACHE: LazyLock<RwLock<HashMap<IpAddr, Option<String>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[cfg(feature = "dns")]
+static RESOLVER: OnceLock<TokioAsyncResolver> = OnceLock::new();
+
+#[cfg(feature = "dns")]
+type Resolver = TokioAsyncResolver;
+
+// without the dns feature the system resolver is used and --dns-server is not available
+#[cfg(not(feature = "dns"))]
+#[derive(Clone)]
+struct Resolver;
+
+#[cfg(feature = "dns")]
+fn build_resolver(options: &Options) -> Result<TokioAsyncResolver, Error> {
+    let mut opts = ResolverOpts::default();
+    opts.timeout = Duration::from_millis(options.dns_timeout);
+
+    if let Some(servers) = options.dns_server.as_ref() {
+        let mut group = NameServerConfigGroup::new();
+        for server in servers
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+        {
+            let addr = if let Ok(ip) = server.parse::<IpAddr>() {
+                SocketAddr::new(ip, 53)
+            } else {
+                server
+                    .parse::<SocketAddr>()
+                    .map_err(|_| format!("invalid dns server {}", server))?
+            };
+            group.merge(NameServerConfigGroup::from_ips_clear(
+                &[addr.ip()],
+                addr.port(),
+                true,
+            ));
+        }
+
+        log::info!("using dns server(s): {}", servers);
+
+        Ok(TokioAsyncResolver::tokio(
+            ResolverConfig::from_parts(None, vec![], group),
+            opts,
+        ))
+    } else {
+        let (config, _) =
+            trust_dns_resolver::system_conf::read_system_conf().map_err(|e| e.to_string())?;
+        Ok(TokioAsyncResolver::tokio(config, opts))
+    }
+}
+
+#[cfg(feature = "dns")]
+fn get_resolver(options: &Options) -> Result<Resolver, Error> {
+    if let Some(resolver) = RESOLVER.get() {
+        Ok(resolver.clone())
+    } else {
+        let resolver = build_resolver(options)?;
+        Ok(RESOLVER.get_or_init(|| resolver).clone())
+    }
+}
+
+#[cfg(not(feature = "dns"))]
+fn get_resolver(_: &Options) -> Result<Resolver, Error> {
+    Ok(Resolver)
+}
+
+#[cfg(not(feature = "dns"))]
+fn check_options(options: &Options) -> Result<(), Error> {
+    if options.dns_server.is_some() {
+        Err("--dns-server requires legba to be compiled with the dns feature".to_owned())
+    } else if options.ptr_lookup {
+        Err("--ptr-lookup requires legba to be compiled with the dns feature".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "dns")]
+async fn lookup_ip(resolver: &Resolver, host: &str) -> Result<Vec<IpAddr>, Error> {
+    resolver
+        .lookup_ip(host)
+        .await
+        .map(|lookup| lookup.iter().collect())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "dns"))]
+async fn lookup_ip(_: &Resolver, host: &str) -> Result<Vec<IpAddr>, Error> {
+    tokio::net::lookup_host((host, 0))
+        .await
+        .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+        .map_err(|e| e.to_string())
+}
+
+// return the host part of the target if it needs to be resolved
+fn hostname_of(target: &str) -> Option<String> {
+    let (host, _) = parse_target(target, 0).ok()?;
+    if host.is_empty() || host.parse::<IpAddr>().is_ok() {
+        None
+    } else {
+        Some(host.trim_end_matches('.').to_lowercase())
+    }
+}
+
+// resolve every unique target hostname once and cache the answers for the whole run
+pub(crate) async fn pre_resolve(options: &Options, targets: &mut Targets) -> Result<(), Error> {
+    // fail early for the options requiring the dns feature
+    #[cfg(not(feature = "dns"))]
+    check_options(options)?;
+
+    let mut hostnames: Vec<String> = targets
+        .iter()?
+        .filter_map(|t| hostname_of(&t.address))
+        .collect();
+    hostnames.sort();
+    hostnames.dedup();
+
+    if hostnames.is_empty() {
+        return Ok(());
+    }
+
+    log::info!("resolving {} hostname(s) ...", hostnames.len());
+
+    let resolver = get_resolver(options)?;
+    let timeout = Duration::from_millis(options.dns_timeout);
+    let mut pending = hostnames.into_iter();
+    let mut tasks = JoinSet::new();
+    let mut resolved = 0;
+
+    loop {
+        // keep at most --concurrency lookups in flight
+        while tasks.len() < options.concurrency.max(1) {
+            let Some(host) = pending.next() else {
+                break;
+            };
+            let resolver = resolver.clone();
+            tasks.spawn(async move {
+                let res = tokio::time::timeout(timeout, lookup_ip(&resolver, &host)).await;
+                (host, res)
+            });
+        }
+
+        let Some(res) = tasks.join_next().await else {
+            break;
+        };
+
+        match res.map_err(|e| e.to_string())? {
+            (host, Ok(Ok(ips))) => {
+                log::debug!("{} -> {:?}", &host, &ips);
+                resolved += 1;
+                CACHE.write().unwrap().insert(host, ips);
+            }
+            (host, Ok(Err(e))) => log::warn!("can't resolve {}: {}", host, e),
+            (host, Err(_)) => log::warn!("can't resolve {}: timeout", host),
+        }
+    }
+
+    log::info!("{} hostname(s) resolved", resolved);
+
+    // the hostnames are now expanded into their addresses
+    if resolved > 0 {
+        targets.reload()?;
+    }
+
+    Ok(())
+}
+
+// return the hostname of the target address from its PTR record, lookups are cached for the whole run
+#[cfg(feature = "dns")]
+pub(crate) async fn reverse_lookup(options: &Options, target: &str) -> Option<String> {
+    let (host, _) = parse_target(target, 0).ok()?;
+    let ip: IpAddr = host.parse().ok()?;
+
+    if let Some(cached) = PTR_CACHE.read().unwrap().get(&ip) {
+        return cached.clone();
+    }
+
+    let resolver = match get_resolver(options) {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            log::error!("can't create dns resolver: {}", e);
+            return None;
+        }
+    };
+
+    let timeout = Duration::from_millis(options.dns_timeout);
+    let hostname = match tokio::time::timeout(timeout, resolver.reverse_lookup(ip)).await {
+        Ok(Ok(lookup)) => lookup
+            .iter()
+            .next()
+            .map(|name| name.to_string().trim_end_matches('.').to_owned()),
+        Ok(Err(e)) => {
+            log::debug!("no PTR record for {}: {}", ip, e);
+            None
+        }
+        Err(_) => {
+            log::debug!("PTR lookup for {} timed out", ip);
+            None
+        }
+    };
+
+    PTR_CACHE.write().unwrap().insert(ip, hostname.clone());
+
+    hostname
+}
+
+// --ptr-lookup is rejected by pre_resolve without the dns feature
+#[cfg(not(feature = "dns"))]
+pub(crate) async fn reverse_lookup(_: &Options, _: &str) -> Option<String> {
+    None
+}
+
+// return the cached addresses for the hostname, if any
+pub(crate) fn lookup(host: &str) -> Option<Vec<IpAddr>> {
+    CACHE
+        .read()
+        .unwrap()
+        .get(&host.trim_end_matches('.').to_lowercase())
+        .filter(|ips| !ips.is_empty())
+        .cloned()
+}
+
+// every cached hostname with its addresses
+pub(crate) fn cached() -> Vec<(String, Vec<IpAddr>)> {
+    CACHE
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|(_, ips)| !ips.is_empty())
+        .map(|(host, ips)| (host.to_owned(), ips.clone()))
+        .collect()
+}
+
+// replace the hostname of a host:port address with its first cached address
+pub(crate) fn resolve_address(address: &str) -> String {
+    if let Ok((host, port)) = parse_target(address, 0) {
+        if let Some(ips) = lookup(&host) {
+            return SocketAddr::new(ips[0], port).to_string();
+        }
+    }
+    address.to_owned()
+}
+
+// expand a host or host:port target into one target for each of its cached addresses,
+// urls are left as they are since the hostname is needed for virtual hosts and tls
+pub(crate) fn expand_target(target: &str) -> Vec<String> {
+    if !target.contains("://") {
+        if let Ok((host, port)) = parse_target(target, 0) {
+            if let Some(ips) = lookup(&host) {
+                return ips
+                    .into_iter()
+                    .map(|ip| {
+                        if port == 0 {
+                            ip.to_string()
+                        } else {
+                            SocketAddr::new(ip, port).to_string()
+                        }
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    vec![target.to_owned()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_target, lookup, pre_resolve, resolve_address, CACHE};
+    use crate::utils::Targets;
+    use crate::Options;
+
+    #[tokio::test]
+    async fn can_pre_resolve_targets() {
+        // resolved from the hosts file
+        let options = Options {
+            target: Some("localhost:22, 10.0.0.1:22".to_owned()),
+            resolve_all: true,
+            ..Default::default()
+        };
+        let mut targets = Targets::from_options(&options).unwrap();
+        assert_eq!(targets.len(), 2);
+        pre_resolve(&options, &mut targets).await.unwrap();
+
+        let ips = lookup("LOCALHOST").unwrap();
+        assert!(ips.iter().all(|ip| ip.is_loopback()));
+        assert_eq!(
+            resolve_address("localhost:22"),
+            format!("{}", std::net::SocketAddr::new(ips[0], 22))
+        );
+
+        assert_eq!(targets.len(), ips.len() + 1);
+        let targets = targets.to_vec().unwrap();
+        assert!(!targets.contains(&"localhost:22".to_owned()));
+        assert!(targets.contains(&"10.0.0.1:22".to_owned()));
+    }
+
+    #[test]
+    fn can_use_cached_addresses() {
+        CACHE.write().unwrap().insert(
+            "cached.example.com".to_owned(),
+            vec!["10.0.0.1".parse().unwrap(), "2001:db8::1".parse().unwrap()],
+        );
+
+        assert_eq!(resolve_address("Cached.example.com:22"), "10.0.0.1:22");
+        assert_eq!(
+            resolve_address("other.example.com:22"),
+            "other.example.com:22"
+        );
+        assert_eq!(
+            expand_target("cached.example.com:22"),
+            vec!["10.0.0.1:22", "[2001:db8::1]:22"]
+        );
+        assert_eq!(
+            expand_target("cached.example.com"),
+            vec!["10.0.0.1", "2001:db8::1"]
+        );
+        assert_eq!(
+            expand_target("https://cached.example.com/"),
+            vec!["https://cached.example.com/"]
+        );
+    }
+}
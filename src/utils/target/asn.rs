@@ -0,0 +1,180 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{LazyLock, RwLock};
+
+use cidr_utils::cidr::IpCidr;
+
+use crate::session::Error;
+
+// path of the ASN to prefix database set by --asn-database
+static DATABASE: LazyLock<RwLock<Option<String>>> = LazyLock::new(|| RwLock::new(None));
+
+pub(crate) fn set_asn_database(path: Option<String>) {
+    *DATABASE.write().unwrap() = path;
+}
+
+// parse AS64500, as64500 or 64500
+fn parse_asn(asn: &str) -> Option<u32> {
+    let asn = asn.trim();
+    let asn = asn
+        .strip_prefix("AS")
+        .or_else(|| asn.strip_prefix("as"))
+        .unwrap_or(asn);
+    asn.parse().ok()
+}
+
+fn to_bits(ip: IpAddr) -> (u128, u32) {
+    match ip {
+        IpAddr::V4(ip) => (u32::from(ip) as u128, 32),
+        IpAddr::V6(ip) => (u128::from(ip), 128),
+    }
+}
+
+fn from_bits(bits: u128, width: u32) -> IpAddr {
+    if width == 32 {
+        IpAddr::V4(Ipv4Addr::from(bits as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(bits))
+    }
+}
+
+// convert an inclusive address range to the minimal list of covering networks
+fn range_to_cidrs(start: IpAddr, stop: IpAddr) -> Vec<String> {
+    let (mut start, width) = to_bits(start);
+    let (stop, stop_width) = to_bits(stop);
+    let mut cidrs = vec![];
+
+    if width != stop_width {
+        return cidrs;
+    }
+
+    while start <= stop {
+        // largest block aligned to start that doesn't go past stop
+        let mut size = if start == 0 {
+            width
+        } else {
+            start.trailing_zeros().min(width)
+        };
+        while size > 0 && (size == 128 || start + ((1u128 << size) - 1) > stop) {
+            size -= 1;
+        }
+
+        cidrs.push(format!("{}/{}", from_bits(start, width), width - size));
+
+        match start.checked_add(1u128 << size) {
+            Some(next) => start = next,
+            None => break,
+        }
+    }
+
+    cidrs
+}
+
+// parse a line of the database returning the networks announced by the ASN in it, supports:
+//  - pyasn / routeviews style: 192.0.2.0/24 64500
+//  - iptoasn style: 192.0.2.0 192.0.2.255 64500 US EXAMPLE
+//  - GeoLite2 ASN csv: 192.0.2.0/24,64500,"Example"
+fn parse_line(line: &str, asn: u32) -> Vec<String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return vec![];
+    }
+
+    let fields: Vec<&str> = if line.contains(',') {
+        line.split(',').map(|f| f.trim_matches('"')).collect()
+    } else {
+        line.split_whitespace().collect()
+    };
+
+    if fields.len() >= 3 {
+        if let (Ok(start), Ok(stop)) = (fields[0].parse::<IpAddr>(), fields[1].parse::<IpAddr>()) {
+            return if parse_asn(fields[2]) == Some(asn) {
+                range_to_cidrs(start, stop)
+            } else {
+                vec![]
+            };
+        }
+    }
+
+    if fields.len() >= 2 && IpCidr::from_str(fields[0]).is_ok() {
+        // multi origin prefixes are reported as 64500_64501
+        if fields[1].split('_').any(|a| parse_asn(a) == Some(asn)) {
+            return vec![fields[0].to_owned()];
+        }
+    }
+
+    vec![]
+}
+
+// return the networks announced by the ASN from the database file
+pub(crate) fn load_asn_prefixes(asn: &str) -> Result<Vec<String>, Error> {
+    let Some(number) = parse_asn(asn) else {
+        return Err(format!("invalid ASN {}", asn));
+    };
+    let Some(path) = DATABASE.read().unwrap().clone() else {
+        return Err(format!(
+            "asn:{} requires an ASN database to be specified with --asn-database",
+            asn
+        ));
+    };
+
+    let file = File::open(&path).map_err(|e| format!("can't open {}: {}", path, e))?;
+    let mut prefixes = vec![];
+    for line in BufReader::new(file).lines() {
+        prefixes.extend(parse_line(&line.map_err(|e| e.to_string())?, number));
+    }
+
+    if prefixes.is_empty() {
+        Err(format!("no prefixes found for {} in {}", asn, path))
+    } else {
+        Ok(prefixes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_line, range_to_cidrs};
+
+    #[test]
+    fn can_convert_ranges_to_cidrs() {
+        assert_eq!(
+            range_to_cidrs("192.0.2.0".parse().unwrap(), "192.0.2.255".parse().unwrap()),
+            vec!["192.0.2.0/24"]
+        );
+        assert_eq!(
+            range_to_cidrs("10.0.0.1".parse().unwrap(), "10.0.0.6".parse().unwrap()),
+            vec!["10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/31", "10.0.0.6/32"]
+        );
+        assert_eq!(
+            range_to_cidrs(
+                "2001:db8::".parse().unwrap(),
+                "2001:db8::ffff:ffff:ffff:ffff".parse().unwrap()
+            ),
+            vec!["2001:db8::/64"]
+        );
+    }
+
+    #[test]
+    fn can_parse_database_lines() {
+        assert_eq!(
+            parse_line("192.0.2.0/24\t64500", 64500),
+            vec!["192.0.2.0/24"]
+        );
+        assert_eq!(
+            parse_line("192.0.2.0/24 64500_64501", 64501),
+            vec!["192.0.2.0/24"]
+        );
+        assert!(parse_line("192.0.2.0/24 64501", 64500).is_empty());
+        assert_eq!(
+            parse_line("198.51.100.0\t198.51.100.255\t64500\tUS\tEXAMPLE-AS", 64500),
+            vec!["198.51.100.0/24"]
+        );
+        assert_eq!(
+            parse_line("203.0.113.0/25,64500,\"Example Org\"", 64500),
+            vec!["203.0.113.0/25"]
+        );
+        assert!(parse_line("network,autonomous_system_number", 64500).is_empty());
+        assert!(parse_line("# comment", 64500).is_empty());
+    }
+}
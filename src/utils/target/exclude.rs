@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::IpAddr;
+
+use ahash::HashSet;
+use cidr_utils::cidr::IpCidr;
+
+use crate::session::Error;
+
+use super::{parse_multiple_targets, parse_target, split_expression};
+
+/// Set of hosts, addresses and networks to be removed from the expanded targets.
+#[derive(Debug, Default)]
+pub(crate) struct Exclusions {
+    cidrs: Vec<IpCidr>,
+    hosts: HashSet<String>,
+}
+
+impl Exclusions {
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let mut exclusions = Self::default();
+        exclusions.add(expression)?;
+        Ok(exclusions)
+    }
+
+    fn add(&mut self, expression: &str) -> Result<(), Error> {
+        for atom in split_expression(expression) {
+            if let Some(path) = atom.strip_prefix('@') {
+                // each line of the file is an exclusion expression itself
+                let file = File::open(path).map_err(|e| e.to_string())?;
+                for line in BufReader::new(file).lines() {
+                    self.add(&line.map_err(|e| e.to_string())?)?;
+                }
+            } else if let Ok(cidr) = IpCidr::from_str(atom) {
+                // keep networks as they are instead of expanding them
+                self.cidrs.push(cidr);
+            } else {
+                for host in parse_multiple_targets(atom)? {
+                    if let Ok(cidr) = IpCidr::from_str(&host) {
+                        self.cidrs.push(cidr);
+                    } else {
+                        self.hosts.insert(host.to_lowercase());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn contains(&self, target: &str) -> bool {
+        if self.hosts.contains(&target.to_lowercase()) {
+            return true;
+        }
+
+        let Ok((host, _)) = parse_target(target, 0) else {
+            return false;
+        };
+
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            self.cidrs.iter().any(|cidr| cidr.contains(ip))
+        } else {
+            self.hosts.contains(&host.to_lowercase())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::Exclusions;
+
+    #[test]
+    fn can_exclude_ips_and_cidrs() {
+        let exclusions = Exclusions::parse("10.0.5.0/24, 10.0.0.1").unwrap();
+
+        assert!(exclusions.contains("10.0.5.12"));
+        assert!(exclusions.contains("10.0.5.12:22"));
+        assert!(exclusions.contains("10.0.0.1"));
+        assert!(!exclusions.contains("10.0.0.2"));
+        assert!(!exclusions.contains("10.0.6.1:22"));
+    }
+
+    #[test]
+    fn can_exclude_hostnames() {
+        let exclusions = Exclusions::parse("www.example.com, other.com:8080").unwrap();
+
+        assert!(exclusions.contains("www.example.com"));
+        assert!(exclusions.contains("WWW.example.com:443"));
+        assert!(exclusions.contains("other.com:8080"));
+        assert!(!exclusions.contains("other.com:80"));
+        assert!(!exclusions.contains("example.com"));
+    }
+
+    #[test]
+    fn can_exclude_from_file() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("exclude.txt");
+        let mut tmpfile = File::create(&tmppath).unwrap();
+
+        writeln!(tmpfile, "192.168.1.0/30").unwrap();
+        writeln!(tmpfile, "192.168.2.1").unwrap();
+        tmpfile.flush().unwrap();
+        drop(tmpfile);
+
+        let exclusions = Exclusions::parse(&format!("@{}", tmppath.to_str().unwrap())).unwrap();
+
+        assert!(exclusions.contains("192.168.1.3"));
+        assert!(exclusions.contains("192.168.2.1"));
+        assert!(!exclusions.contains("192.168.1.4"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::session::Error;
+
+use super::is_relevant_port;
+
+#[derive(Deserialize)]
+struct Port {
+    port: u16,
+    #[serde(default)]
+    proto: String,
+    #[serde(default)]
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct Record {
+    ip: String,
+    #[serde(default)]
+    ports: Vec<Port>,
+}
+
+fn parse_records(data: &str) -> Vec<Record> {
+    if let Ok(records) = serde_json::from_str::<Vec<Record>>(data) {
+        return records;
+    }
+
+    // older masscan versions produce broken json (trailing commas, finished markers, etc), parse one object per line
+    data.lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| line.starts_with('{'))
+        .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+        .collect()
+}
+
+// load host:port targets from a masscan -oJ output file, only open ports relevant for the plugin are selected
+pub(crate) fn load_masscan_targets(path: &str, plugin: Option<&str>) -> Result<Vec<String>, Error> {
+    let data = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let mut targets = vec![];
+
+    for record in parse_records(&data) {
+        for port in record.ports {
+            if (port.status.is_empty() || port.status == "open")
+                && (port.proto.is_empty() || port.proto == "tcp")
+                && is_relevant_port(plugin, port.port, None)
+            {
+                targets.push(if record.ip.contains(':') {
+                    format!("[{}]:{}", record.ip, port.port)
+                } else {
+                    format!("{}:{}", record.ip, port.port)
+                });
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::load_masscan_targets;
+
+    fn write_tmp(dir: &tempfile::TempDir, data: &str) -> String {
+        let path = dir.path().join("masscan.json");
+        let mut file = File::create(&path).unwrap();
+        write!(file, "{}", data).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn can_load_json() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(
+            &tmpdir,
+            r#"[
+{   "ip": "10.0.0.1",   "timestamp": "1700000000", "ports": [ {"port": 22, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "10.0.0.2",   "timestamp": "1700000000", "ports": [ {"port": 6379, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "2001:db8::1",   "timestamp": "1700000000", "ports": [ {"port": 22, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] }
+]"#,
+        );
+
+        assert_eq!(
+            load_masscan_targets(&path, None).unwrap(),
+            vec!["10.0.0.1:22", "10.0.0.2:6379", "[2001:db8::1]:22"]
+        );
+        assert_eq!(
+            load_masscan_targets(&path, Some("redis")).unwrap(),
+            vec!["10.0.0.2:6379"]
+        );
+    }
+
+    #[test]
+    fn can_load_broken_json() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(
+            &tmpdir,
+            r#"[
+{   "ip": "10.0.0.1",   "timestamp": "1700000000", "ports": [ {"port": 22, "proto": "tcp", "status": "open", "reason": "syn-ack", "ttl": 64} ] },
+{   "ip": "10.0.0.2",   "timestamp": "1700000000", "ports": [ {"port": 53, "proto": "udp", "status": "open", "reason": "none", "ttl": 64} ] },
+{finished: 1}
+]"#,
+        );
+
+        assert_eq!(
+            load_masscan_targets(&path, None).unwrap(),
+            vec!["10.0.0.1:22"]
+        );
+    }
+}
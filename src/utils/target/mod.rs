@@ -0,0 +1,19 @@
+mod asn;
+mod exclude;
+mod masscan;
+mod multi;
+mod nmap;
+mod overrides;
+mod services;
+mod single;
+mod targets;
+
+pub(crate) use asn::*;
+pub(crate) use exclude::*;
+pub(crate) use masscan::*;
+pub(crate) use multi::*;
+pub(crate) use nmap::*;
+pub(crate) use overrides::*;
+pub(crate) use services::*;
+pub(crate) use single::*;
+pub(crate) use targets::*;
@@ -0,0 +1,807 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use crate::session::Error;
+
+use super::{
+    load_asn_prefixes, load_extended_targets, load_masscan_targets, load_nmap_targets, Target,
+};
+
+use cidr_utils::cidr::IpCidr;
+use lazy_regex::{lazy_regex, Lazy};
+use regex::Regex;
+
+static IPV4_RANGE_PARSER: Lazy<Regex> = lazy_regex!(
+    r"^(\d+|\d+-\d+|\*)\.(\d+|\d+-\d+|\*)\.(\d+|\d+-\d+|\*)\.(\d+|\d+-\d+|\*):?(\d+)?$"
+);
+// avoid ranges too large to be counted and iterated in a reasonable time
+const MAX_IPV4_RANGE_SIZE: usize = 1 << 24;
+const MAX_IPV6_RANGE_SIZE: u128 = 1 << 24;
+
+static PORT_RANGE_PARSER: Lazy<Regex> = lazy_regex!(r"^([^:]+):(\d+-\d+)$");
+
+static BRACE_RANGE_PARSER: Lazy<Regex> = lazy_regex!(r"^(\d+)-(\d+)$|^([a-zA-Z])-([a-zA-Z])$");
+// avoid expanding hostname patterns that would exhaust memory
+const MAX_BRACE_EXPANSION_SIZE: usize = 1 << 24;
+
+// split a comma separated expression, ignoring the commas inside port lists and hostname patterns
+pub(super) fn split_expression(expression: &str) -> Vec<&str> {
+    let mut atoms = vec![];
+    let mut depth: usize = 0;
+    let mut start = 0;
+
+    for (i, c) in expression.char_indices() {
+        match c {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                atoms.push(&expression[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    atoms.push(&expression[start..]);
+
+    atoms
+        .into_iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// false if the expression is a single range, CIDR or pattern without lists, whose targets are
+// unique and don't need to be tracked for deduplication
+pub(crate) fn may_have_duplicates(expression: &str) -> bool {
+    match split_expression(expression).as_slice() {
+        [atom] => {
+            atom.contains(',')
+                || ["@", "nmap:", "masscan:", "asn:"]
+                    .iter()
+                    .any(|prefix| atom.starts_with(prefix))
+        }
+        _ => true,
+    }
+}
+
+// parse a port expression like 22, 8000-8100 or 22,2222,8000-8100
+fn parse_ports(expression: &str, ports: &str) -> Result<Vec<u16>, Error> {
+    let mut all = vec![];
+
+    for part in ports.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (start, stop) = part.split_once('-').unwrap_or((part, part));
+        let start: u16 = start
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid port {} in {}", start, expression))?;
+        let stop: u16 = stop
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid port {} in {}", stop, expression))?;
+
+        if stop < start {
+            return Err(format!(
+                "invalid port range {}, {} is greater than {}",
+                expression, start, stop
+            ));
+        }
+
+        all.extend(start..=stop);
+    }
+
+    if all.is_empty() {
+        return Err(format!("empty port list in {}", expression));
+    }
+
+    Ok(all)
+}
+
+// split the hosts part from the optional :[port, list] or :port-range part
+fn split_ports_part(expression: &str) -> Result<Option<(&str, Vec<u16>)>, Error> {
+    if expression.ends_with(']') {
+        if let Some((hosts, ports)) = expression.rsplit_once(":[") {
+            return Ok(Some((
+                hosts,
+                parse_ports(expression, ports.trim_end_matches(']'))?,
+            )));
+        }
+    } else if let Some(caps) = PORT_RANGE_PARSER.captures(expression) {
+        return Ok(Some((
+            caps.get(1).unwrap().as_str(),
+            parse_ports(expression, caps.get(2).unwrap().as_str())?,
+        )));
+    }
+
+    Ok(None)
+}
+
+fn with_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        // ipv6
+        format!("{}:[{}]", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+// parse a single nmap style octet expression like 10, 1-254 or *
+fn parse_ipv4_octet(expression: &str, octet: &str) -> Result<(u8, u8), Error> {
+    let (start, stop) = if octet == "*" {
+        return Ok((0, 255));
+    } else if let Some((start, stop)) = octet.split_once('-') {
+        (start, stop)
+    } else {
+        (octet, octet)
+    };
+
+    let start: u8 = start.parse().map_err(|_| {
+        format!(
+            "invalid ip range {}, {} is not a valid octet",
+            expression, start
+        )
+    })?;
+    let stop: u8 = stop.parse().map_err(|_| {
+        format!(
+            "invalid ip range {}, {} is not a valid octet",
+            expression, stop
+        )
+    })?;
+
+    if stop < start {
+        return Err(format!(
+            "invalid ip range {}, {} is greater than {}",
+            expression, start, stop
+        ));
+    }
+
+    Ok((start, stop))
+}
+
+// parse an ipv6 range like 2001:db8::1-2001:db8::ff or 2001:db8::1-ff
+fn parse_ipv6_range(expression: &str) -> Result<Option<(u128, u128)>, Error> {
+    let Some((start, stop)) = expression.split_once('-') else {
+        return Ok(None);
+    };
+    let Ok(start) = start.parse::<Ipv6Addr>() else {
+        return Ok(None);
+    };
+
+    let start = u128::from(start);
+    let stop = if let Ok(stop) = stop.parse::<Ipv6Addr>() {
+        u128::from(stop)
+    } else if let Ok(last) = u16::from_str_radix(stop, 16) {
+        // only the last group has been specified
+        (start & !0xffff) | last as u128
+    } else {
+        return Err(format!(
+            "invalid ipv6 range {}, {} is not a valid address or group",
+            expression, stop
+        ));
+    };
+
+    if stop < start {
+        return Err(format!(
+            "invalid ipv6 range {}, start is greater than end",
+            expression
+        ));
+    } else if stop - start >= MAX_IPV6_RANGE_SIZE {
+        return Err(format!(
+            "invalid ipv6 range {}, too many addresses",
+            expression
+        ));
+    }
+
+    Ok(Some((start, stop)))
+}
+
+// hosts generated on demand, so that large networks are never held in memory
+type Hosts = Box<dyn Iterator<Item = String>>;
+
+fn parse_hosts(expression: &str) -> Result<Hosts, Error> {
+    if let Some(caps) = IPV4_RANGE_PARSER.captures(expression) {
+        // nmap style ipv4 range like 192.168.1.1-10, 10.0.1-3.0-255 or 192.168.*.1 with optional :port
+        let mut octets = [(0, 0); 4];
+        for (i, octet) in octets.iter_mut().enumerate() {
+            *octet = parse_ipv4_octet(expression, caps.get(i + 1).unwrap().as_str())?;
+        }
+        let size: usize = octets
+            .iter()
+            .map(|(start, stop)| (stop - start) as usize + 1)
+            .product();
+        if size > MAX_IPV4_RANGE_SIZE {
+            return Err(format!(
+                "invalid ip range {}, too many addresses",
+                expression
+            ));
+        }
+
+        let port_part = if let Some(port) = caps.get(5) {
+            format!(":{}", port.as_str())
+        } else {
+            "".to_owned()
+        };
+
+        Ok(Box::new((0..size).map(move |mut index| {
+            // the last octet changes first
+            let mut ip = [0; 4];
+            for (octet, (start, stop)) in ip.iter_mut().zip(octets).rev() {
+                let span = (stop - start) as usize + 1;
+                *octet = start + (index % span) as u8;
+                index /= span;
+            }
+            format!("{}{}", Ipv4Addr::from(ip), port_part)
+        })))
+    } else if let Some((start, stop)) = parse_ipv6_range(expression)? {
+        Ok(Box::new(
+            (start..=stop).map(|ip| Ipv6Addr::from(ip).to_string()),
+        ))
+    } else if let Ok(cidr) = IpCidr::from_str(expression) {
+        // attempt as cidr
+        Ok(Box::new(cidr.iter().map(|ip| ip.to_string())))
+    } else {
+        // just return as it is
+        Ok(Box::new(std::iter::once(expression.to_string())))
+    }
+}
+
+// find the first {...} group and its matching closing brace
+fn find_brace_group(expression: &str) -> Option<(usize, usize)> {
+    let start = expression.find('{')?;
+    let mut depth: usize = 0;
+
+    for (i, c) in expression[start..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, start + i));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// expand the content of a brace group like 1-50, 01-20, a-f or a,b,c
+fn expand_brace_group(expression: &str, group: &str) -> Result<Vec<String>, Error> {
+    if let Some(caps) = BRACE_RANGE_PARSER.captures(group) {
+        if let (Some(start), Some(stop)) = (caps.get(1), caps.get(2)) {
+            // zero padded ranges like 01-20 keep their width
+            let width = if start.as_str().starts_with('0') {
+                start.as_str().len()
+            } else {
+                0
+            };
+            let start: usize = start
+                .as_str()
+                .parse()
+                .map_err(|_| format!("invalid range {} in {}", group, expression))?;
+            let stop: usize = stop
+                .as_str()
+                .parse()
+                .map_err(|_| format!("invalid range {} in {}", group, expression))?;
+            if stop < start {
+                return Err(format!(
+                    "invalid range {} in {}, {} is greater than {}",
+                    group, expression, start, stop
+                ));
+            } else if stop - start >= MAX_BRACE_EXPANSION_SIZE {
+                return Err(format!(
+                    "invalid range {} in {}, too many hosts",
+                    group, expression
+                ));
+            }
+
+            return Ok((start..=stop)
+                .map(|n| format!("{:0width$}", n, width = width))
+                .collect());
+        }
+
+        let start = caps.get(3).unwrap().as_str().chars().next().unwrap();
+        let stop = caps.get(4).unwrap().as_str().chars().next().unwrap();
+        if stop < start {
+            return Err(format!(
+                "invalid range {} in {}, {} is greater than {}",
+                group, expression, start, stop
+            ));
+        }
+
+        return Ok((start..=stop).map(|c| c.to_string()).collect());
+    }
+
+    // comma separated alternatives, each one can contain nested patterns
+    let mut all = vec![];
+    for alt in split_expression(group) {
+        all.extend(expand_braces(alt)?);
+    }
+    Ok(all)
+}
+
+// expand hostname patterns like srv{1-50}.example.com or db-{a,b,c}.prod.internal
+fn expand_braces(expression: &str) -> Result<Vec<String>, Error> {
+    let Some((start, stop)) = find_brace_group(expression) else {
+        return Ok(vec![expression.to_owned()]);
+    };
+
+    let prefix = &expression[..start];
+    let group = &expression[start + 1..stop];
+    let suffixes = expand_braces(&expression[stop + 1..])?;
+    let mut all = vec![];
+
+    for value in expand_brace_group(expression, group)? {
+        for suffix in &suffixes {
+            if all.len() >= MAX_BRACE_EXPANSION_SIZE {
+                return Err(format!("invalid pattern {}, too many hosts", expression));
+            }
+            all.push(format!("{}{}{}", prefix, value, suffix));
+        }
+    }
+
+    Ok(all)
+}
+
+fn parse_multiple_targets_atom(expression: &str) -> Result<Hosts, Error> {
+    if find_brace_group(expression).is_some() {
+        let mut all = vec![];
+        for expanded in expand_braces(expression)? {
+            all.push(parse_multiple_targets_atom(&expanded)?);
+        }
+        return Ok(Box::new(all.into_iter().flatten()));
+    }
+
+    if let Some((hosts, ports)) = split_ports_part(expression)? {
+        // cartesian product of hosts and ports
+        Ok(Box::new(parse_hosts(hosts)?.flat_map(move |host| {
+            ports
+                .clone()
+                .into_iter()
+                .map(move |port| with_port(&host, port))
+        })))
+    } else {
+        parse_hosts(expression)
+    }
+}
+
+pub(crate) type TargetsIterator = Box<dyn Iterator<Item = Result<Target, Error>>>;
+
+// lazily expand the expression, files are streamed line by line and each line is an expression itself,
+// the plugin name is used to select the relevant ports when importing from scanners output
+pub(crate) fn iterate_multiple_targets(
+    expression: &str,
+    plugin: Option<&str>,
+) -> Result<TargetsIterator, Error> {
+    let mut iters: Vec<TargetsIterator> = vec![];
+
+    for atom in split_expression(expression) {
+        if let Some(targets) = atom
+            .strip_prefix('@')
+            .map(|path| load_extended_targets(path, plugin))
+            .transpose()?
+            .flatten()
+        {
+            // yaml or csv file with per-target overrides
+            iters.push(targets);
+        } else if let Some(path) = atom.strip_prefix('@') {
+            // load from file
+            let file = File::open(path).map_err(|e| e.to_string())?;
+            let reader = BufReader::new(file);
+            let plugin = plugin.map(|p| p.to_owned());
+
+            iters.push(Box::new(reader.lines().flat_map(
+                move |line| -> TargetsIterator {
+                    match line
+                        .map_err(|e| e.to_string())
+                        .and_then(|line| iterate_multiple_targets(&line, plugin.as_deref()))
+                    {
+                        Ok(targets) => targets,
+                        Err(e) => Box::new(std::iter::once(Err(e))),
+                    }
+                },
+            )));
+        } else if let Some(path) = atom
+            .strip_prefix("nmap:")
+            .filter(|p| p.parse::<u16>().is_err())
+        {
+            // import from nmap output
+            iters.push(Box::new(
+                load_nmap_targets(path, plugin)?
+                    .into_iter()
+                    .map(|t| Ok(t.into())),
+            ));
+        } else if let Some(path) = atom
+            .strip_prefix("masscan:")
+            .filter(|p| p.parse::<u16>().is_err())
+        {
+            // import from masscan json output
+            iters.push(Box::new(
+                load_masscan_targets(path, plugin)?
+                    .into_iter()
+                    .map(|t| Ok(t.into())),
+            ));
+        } else if let Some(asn) = atom
+            .strip_prefix("asn:")
+            .filter(|a| a.to_lowercase().starts_with("as"))
+        {
+            // networks announced by the autonomous system, expanded lazily
+            iters.push(Box::new(load_asn_prefixes(asn)?.into_iter().flat_map(
+                |cidr| -> TargetsIterator {
+                    match parse_hosts(&cidr) {
+                        Ok(hosts) => Box::new(hosts.map(|t| Ok(t.into()))),
+                        Err(e) => Box::new(std::iter::once(Err(e))),
+                    }
+                },
+            )));
+        } else {
+            iters.push(Box::new(
+                parse_multiple_targets_atom(atom)?.map(|t| Ok(t.into())),
+            ));
+        }
+    }
+
+    Ok(Box::new(iters.into_iter().flatten()))
+}
+
+pub(crate) fn parse_multiple_targets(expression: &str) -> Result<Vec<String>, Error> {
+    iterate_multiple_targets(expression, None)?
+        .map(|target| target.map(|t| t.address))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::{iterate_multiple_targets, may_have_duplicates, parse_multiple_targets};
+
+    #[test]
+    fn can_parse_single() {
+        let expected = vec!["127.0.0.1:22".to_owned()];
+        let res = parse_multiple_targets("127.0.0.1:22").unwrap();
+        assert_eq!(res, expected);
+
+        let expected = vec!["http://www.something.it:8000".to_owned()];
+        let res = parse_multiple_targets("http://www.something.it:8000").unwrap();
+        assert_eq!(res, expected);
+
+        let expected = vec!["host:1234".to_owned()];
+        let res = parse_multiple_targets(",,host:1234,,,").unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_detect_possible_duplicates() {
+        assert!(!may_have_duplicates("10.0.0.0/8"));
+        assert!(!may_have_duplicates("2001:db8::/64:[22]"));
+        assert!(!may_have_duplicates("srv{01-20}.local:22-25"));
+        assert!(may_have_duplicates("10.0.0.0/8, 10.0.0.1"));
+        assert!(may_have_duplicates("10.0.0.1:[22, 22]"));
+        assert!(may_have_duplicates("db-{a,a}.local"));
+        assert!(may_have_duplicates("@targets.txt"));
+        assert!(may_have_duplicates("asn:AS64500"));
+    }
+
+    #[test]
+    fn can_parse_from_file() {
+        let num_items = 5;
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("targets.txt");
+        let mut tmptargets = File::create(&tmppath).unwrap();
+        let mut expected = vec![];
+
+        for i in 0..num_items {
+            writeln!(tmptargets, "127.0.0.1:{}", i).unwrap();
+            expected.push(format!("127.0.0.1:{}", i));
+        }
+        tmptargets.flush().unwrap();
+        drop(tmptargets);
+
+        let res = parse_multiple_targets(&format!("@{}", tmppath.to_str().unwrap())).unwrap();
+        assert_eq!(res, expected);
+
+        // plain lists with a csv extension
+        let csvpath = tmpdir.path().join("targets.csv");
+        std::fs::copy(&tmppath, &csvpath).unwrap();
+        let res = parse_multiple_targets(&format!("@{}", csvpath.to_str().unwrap())).unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn returns_error_for_wrong_filename() {
+        let res = parse_multiple_targets("@i-do-not-exist.lol");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn can_parse_comma_separated() {
+        let expected = Ok(vec![
+            "127.0.0.1:22".to_owned(),
+            "www.google.com".to_owned(),
+            "cnn.com".to_owned(),
+            "8.8.8.8:4444".to_owned(),
+        ]);
+        let res = parse_multiple_targets("127.0.0.1:22, www.google.com, cnn.com,, 8.8.8.8:4444");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ip_range_without_port() {
+        let expected = Ok(vec![
+            "192.168.1.1".to_owned(),
+            "192.168.1.2".to_owned(),
+            "192.168.1.3".to_owned(),
+            "192.168.1.4".to_owned(),
+            "192.168.1.5".to_owned(),
+        ]);
+        let res = parse_multiple_targets("192.168.1.1-5");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ip_range_with_port() {
+        let expected = Ok(vec![
+            "192.168.1.1:1234".to_owned(),
+            "192.168.1.2:1234".to_owned(),
+            "192.168.1.3:1234".to_owned(),
+            "192.168.1.4:1234".to_owned(),
+            "192.168.1.5:1234".to_owned(),
+        ]);
+        let res = parse_multiple_targets("192.168.1.1-5:1234");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ip_range_on_multiple_octets() {
+        let expected = Ok(vec![
+            "10.0.1.0".to_owned(),
+            "10.0.1.1".to_owned(),
+            "10.0.2.0".to_owned(),
+            "10.0.2.1".to_owned(),
+        ]);
+        let res = parse_multiple_targets("10.0.1-2.0-1");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ip_range_with_wildcard_and_port() {
+        let res = parse_multiple_targets("192.168.*.1:22").unwrap();
+        assert_eq!(res.len(), 256);
+        assert_eq!(res[0], "192.168.0.1:22");
+        assert_eq!(res[255], "192.168.255.1:22");
+    }
+
+    #[test]
+    fn returns_error_for_invalid_ip_range() {
+        assert!(parse_multiple_targets("10.0.3-1.1").is_err());
+        assert!(parse_multiple_targets("10.0.1-300.1").is_err());
+        // too many addresses
+        assert!(parse_multiple_targets("*.*.*.*").is_err());
+        assert!(parse_multiple_targets("10-11.*.*.*:22").is_err());
+    }
+
+    #[test]
+    fn can_parse_ipv4_cidr_without_port() {
+        let expected = Ok(vec![
+            "192.168.1.0".to_owned(),
+            "192.168.1.1".to_owned(),
+            "192.168.1.2".to_owned(),
+            "192.168.1.3".to_owned(),
+        ]);
+        let res = parse_multiple_targets("192.168.1.0/30");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ipv4_cidr_with_port() {
+        let expected = Ok(vec![
+            "192.168.1.0:1234".to_owned(),
+            "192.168.1.1:1234".to_owned(),
+            "192.168.1.2:1234".to_owned(),
+            "192.168.1.3:1234".to_owned(),
+        ]);
+        let res = parse_multiple_targets("192.168.1.0/30:[1234]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ipv6_cidr_without_port() {
+        let expected = Ok(vec![
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f0".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f1".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f2".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f3".to_owned(),
+        ]);
+        let res = parse_multiple_targets("2001:4f8:3:ba:2e0:81ff:fe22:d1f1/126");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_iterate_large_networks_lazily() {
+        let res: Vec<String> = iterate_multiple_targets("2001:db8::/64:[22], 10.0.0.0/8", None)
+            .unwrap()
+            .take(2)
+            .map(|t| t.unwrap().address)
+            .collect();
+        assert_eq!(res, vec!["2001:db8:::[22]", "2001:db8::1:[22]"]);
+    }
+
+    #[test]
+    fn can_parse_ipv6_cidr_with_port() {
+        let expected = Ok(vec![
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f0:[1234]".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f1:[1234]".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f2:[1234]".to_owned(),
+            "2001:4f8:3:ba:2e0:81ff:fe22:d1f3:[1234]".to_owned(),
+        ]);
+        let res = parse_multiple_targets("2001:4f8:3:ba:2e0:81ff:fe22:d1f1/126:[1234]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_combined() {
+        let num_items = 5;
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("targets.txt");
+        let mut tmptargets = File::create(&tmppath).unwrap();
+        let expected = vec![
+            "192.168.1.1",
+            "127.0.0.1:0",
+            "127.0.0.1:1",
+            "127.0.0.1:2",
+            "127.0.0.1:3",
+            "127.0.0.1:4",
+            "8.8.8.8",
+            "8.8.8.9",
+            "8.8.8.10",
+            "8.8.8.11",
+        ];
+
+        for i in 0..num_items {
+            writeln!(tmptargets, "127.0.0.1:{}", i).unwrap();
+        }
+        tmptargets.flush().unwrap();
+        drop(tmptargets);
+
+        let res = parse_multiple_targets(&format!(
+            "192.168.1.1, @{}, 8.8.8.8/30",
+            tmppath.to_str().unwrap()
+        ))
+        .unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_expressions_from_file() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("targets.txt");
+        let mut tmptargets = File::create(&tmppath).unwrap();
+        let expected = vec![
+            "www.example.com",
+            "10.0.0.1:22",
+            "10.0.0.2:22",
+            "192.168.1.0",
+            "192.168.1.1",
+            "host:1234",
+        ];
+
+        writeln!(tmptargets, "www.example.com").unwrap();
+        writeln!(tmptargets).unwrap();
+        writeln!(tmptargets, "10.0.0.1-2:22").unwrap();
+        writeln!(tmptargets, "192.168.1.0/31, host:1234").unwrap();
+        tmptargets.flush().unwrap();
+        drop(tmptargets);
+
+        let res = parse_multiple_targets(&format!("@{}", tmppath.to_str().unwrap())).unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_cidr_with_port_list() {
+        let expected = Ok(vec![
+            "192.168.1.0:22".to_owned(),
+            "192.168.1.0:2222".to_owned(),
+            "192.168.1.1:22".to_owned(),
+            "192.168.1.1:2222".to_owned(),
+        ]);
+        let res = parse_multiple_targets("192.168.1.0/31:[22, 2222]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_port_range() {
+        let expected = Ok(vec![
+            "10.0.0.1:8000".to_owned(),
+            "10.0.0.1:8001".to_owned(),
+            "10.0.0.1:8002".to_owned(),
+            "www.example.com:80".to_owned(),
+            "www.example.com:8000".to_owned(),
+            "www.example.com:8001".to_owned(),
+        ]);
+        let res = parse_multiple_targets("10.0.0.1:8000-8002, www.example.com:[80,8000-8001]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ip_range_with_port_range() {
+        let res = parse_multiple_targets("10.0.0.1-3:22-23").unwrap();
+        assert_eq!(res.len(), 6);
+        assert_eq!(res[0], "10.0.0.1:22");
+        assert_eq!(res[1], "10.0.0.1:23");
+        assert_eq!(res[5], "10.0.0.3:23");
+    }
+
+    #[test]
+    fn returns_error_for_invalid_ports() {
+        assert!(parse_multiple_targets("10.0.0.1:[22,99999]").is_err());
+        assert!(parse_multiple_targets("10.0.0.1:8100-8000").is_err());
+    }
+
+    #[test]
+    fn can_parse_ipv6_range() {
+        let expected = Ok(vec![
+            "2001:db8::fe".to_owned(),
+            "2001:db8::ff".to_owned(),
+            "2001:db8::100".to_owned(),
+        ]);
+        let res = parse_multiple_targets("2001:db8::fe-2001:db8::100");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_ipv6_range_with_last_group_and_port() {
+        let expected = Ok(vec![
+            "2001:db8::1:[22]".to_owned(),
+            "2001:db8::2:[22]".to_owned(),
+            "2001:db8::3:[22]".to_owned(),
+        ]);
+        let res = parse_multiple_targets("2001:db8::1-3:[22]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn returns_error_for_invalid_ipv6_range() {
+        assert!(parse_multiple_targets("2001:db8::ff-2001:db8::1").is_err());
+        assert!(parse_multiple_targets("2001:db8::1-zz").is_err());
+        assert!(parse_multiple_targets("2001:db8::1-2001:db9::1").is_err());
+    }
+
+    #[test]
+    fn can_parse_hostname_patterns() {
+        let expected = Ok(vec![
+            "web08.corp.local".to_owned(),
+            "web09.corp.local".to_owned(),
+            "web10.corp.local".to_owned(),
+            "db-a.prod.internal:5432".to_owned(),
+            "db-b.prod.internal:5432".to_owned(),
+            "db-c.prod.internal:5432".to_owned(),
+        ]);
+        let res = parse_multiple_targets("web{08-10}.corp.local, db-{a,b,c}.prod.internal:5432");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn can_parse_nested_hostname_patterns_with_ports() {
+        let expected = Ok(vec![
+            "srv1.eu.example.com:22".to_owned(),
+            "srv1.eu.example.com:2222".to_owned(),
+            "srv2.eu.example.com:22".to_owned(),
+            "srv2.eu.example.com:2222".to_owned(),
+            "bastion.example.com:22".to_owned(),
+            "bastion.example.com:2222".to_owned(),
+        ]);
+        let res = parse_multiple_targets("{srv{1-2}.eu,bastion}.example.com:[22, 2222]");
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn returns_error_for_invalid_hostname_pattern() {
+        assert!(parse_multiple_targets("srv{10-1}.example.com").is_err());
+        assert!(parse_multiple_targets("srv{0-99999999}.example.com").is_err());
+    }
+}
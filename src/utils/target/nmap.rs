@@ -0,0 +1,224 @@
+use std::fs;
+
+use crate::session::Error;
+
+use super::is_relevant_port;
+
+// an open port found by nmap
+#[derive(Debug, PartialEq)]
+struct OpenPort {
+    address: String,
+    port: u16,
+    service: Option<String>,
+}
+
+impl OpenPort {
+    fn to_target(&self) -> String {
+        if self.address.contains(':') {
+            format!("[{}]:{}", self.address, self.port)
+        } else {
+            format!("{}:{}", self.address, self.port)
+        }
+    }
+}
+
+// parse the output of nmap -oX
+fn parse_xml(data: &str) -> Result<Vec<OpenPort>, Error> {
+    let doc = roxmltree::Document::parse(data).map_err(|e| e.to_string())?;
+    let mut open = vec![];
+
+    for host in doc.descendants().filter(|n| n.has_tag_name("host")) {
+        // skip hosts that are down
+        if host
+            .children()
+            .find(|n| n.has_tag_name("status"))
+            .and_then(|n| n.attribute("state"))
+            .is_some_and(|state| state != "up")
+        {
+            continue;
+        }
+
+        let Some(address) = host
+            .children()
+            .filter(|n| n.has_tag_name("address"))
+            .find(|n| matches!(n.attribute("addrtype"), Some("ipv4") | Some("ipv6")))
+            .and_then(|n| n.attribute("addr"))
+        else {
+            continue;
+        };
+
+        for port in host.descendants().filter(|n| n.has_tag_name("port")) {
+            let is_open = port
+                .children()
+                .find(|n| n.has_tag_name("state"))
+                .and_then(|n| n.attribute("state"))
+                == Some("open");
+            if !is_open || port.attribute("protocol") != Some("tcp") {
+                continue;
+            }
+
+            let Some(portid) = port.attribute("portid").and_then(|p| p.parse().ok()) else {
+                continue;
+            };
+
+            open.push(OpenPort {
+                address: address.to_owned(),
+                port: portid,
+                service: port
+                    .children()
+                    .find(|n| n.has_tag_name("service"))
+                    .and_then(|n| n.attribute("name"))
+                    .map(|s| s.to_owned()),
+            });
+        }
+    }
+
+    Ok(open)
+}
+
+// parse the output of nmap -oG
+fn parse_greppable(data: &str) -> Vec<OpenPort> {
+    let mut open = vec![];
+
+    for line in data.lines() {
+        let Some(host) = line.strip_prefix("Host: ") else {
+            continue;
+        };
+        let Some(address) = host.split_whitespace().next() else {
+            continue;
+        };
+        let Some(ports) = line
+            .split('\t')
+            .find_map(|field| field.strip_prefix("Ports: "))
+        else {
+            continue;
+        };
+
+        // port/state/protocol/owner/service/rpc info/version
+        for port in ports.split(", ") {
+            let fields: Vec<&str> = port.split('/').collect();
+            if fields.len() < 5 || fields[1] != "open" || fields[2] != "tcp" {
+                continue;
+            }
+
+            let Ok(portid) = fields[0].trim().parse() else {
+                continue;
+            };
+
+            open.push(OpenPort {
+                address: address.to_owned(),
+                port: portid,
+                service: if fields[4].is_empty() {
+                    None
+                } else {
+                    Some(fields[4].to_owned())
+                },
+            });
+        }
+    }
+
+    open
+}
+
+// load host:port targets from a nmap XML or greppable output file, only open ports relevant for the plugin are selected
+pub(crate) fn load_nmap_targets(path: &str, plugin: Option<&str>) -> Result<Vec<String>, Error> {
+    let data = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let open = if data.trim_start().starts_with('<') {
+        parse_xml(&data)?
+    } else {
+        parse_greppable(&data)
+    };
+
+    Ok(open
+        .iter()
+        .filter(|p| is_relevant_port(plugin, p.port, p.service.as_deref()))
+        .map(|p| p.to_target())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::load_nmap_targets;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<nmaprun scanner="nmap" args="nmap -sV -oX scan.xml 10.0.0.0/24">
+<host><status state="up" reason="syn-ack"/>
+<address addr="10.0.0.1" addrtype="ipv4"/>
+<ports>
+<port protocol="tcp" portid="22"><state state="open" reason="syn-ack"/><service name="ssh"/></port>
+<port protocol="tcp" portid="80"><state state="open" reason="syn-ack"/><service name="http"/></port>
+<port protocol="tcp" portid="443"><state state="closed" reason="reset"/><service name="https"/></port>
+</ports>
+</host>
+<host><status state="up" reason="syn-ack"/>
+<address addr="10.0.0.2" addrtype="ipv4"/>
+<address addr="00:11:22:33:44:55" addrtype="mac"/>
+<ports>
+<port protocol="tcp" portid="2222"><state state="open" reason="syn-ack"/><service name="ssh"/></port>
+</ports>
+</host>
+<host><status state="down" reason="no-response"/>
+<address addr="10.0.0.3" addrtype="ipv4"/>
+</host>
+</nmaprun>
+"#;
+
+    const GREPPABLE: &str = "# Nmap 7.94 scan initiated as: nmap -sV -oG scan.gnmap 10.0.0.0/24
+Host: 10.0.0.1 (gw.local)\tStatus: Up
+Host: 10.0.0.1 (gw.local)\tPorts: 22/open/tcp//ssh//OpenSSH 9.6/, 80/open/tcp//http//nginx/, 443/closed/tcp//https///\tIgnored State: closed (997)
+Host: 10.0.0.2 ()\tPorts: 2222/open/tcp//ssh///, 53/open/udp//domain///
+# Nmap done at Mon Jan  1 00:00:00 2024 -- 256 IP addresses (2 hosts up) scanned in 10.00 seconds
+";
+
+    fn write_tmp(dir: &tempfile::TempDir, name: &str, data: &str) -> String {
+        let path = dir.path().join(name);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "{}", data).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn can_load_xml() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(&tmpdir, "scan.xml", XML);
+
+        assert_eq!(
+            load_nmap_targets(&path, None).unwrap(),
+            vec!["10.0.0.1:22", "10.0.0.1:80", "10.0.0.2:2222"]
+        );
+        assert_eq!(
+            load_nmap_targets(&path, Some("ssh")).unwrap(),
+            vec!["10.0.0.1:22", "10.0.0.2:2222"]
+        );
+        assert_eq!(
+            load_nmap_targets(&path, Some("http.form")).unwrap(),
+            vec!["10.0.0.1:80"]
+        );
+    }
+
+    #[test]
+    fn can_load_greppable() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(&tmpdir, "scan.gnmap", GREPPABLE);
+
+        assert_eq!(
+            load_nmap_targets(&path, None).unwrap(),
+            vec!["10.0.0.1:22", "10.0.0.1:80", "10.0.0.2:2222"]
+        );
+        assert_eq!(
+            load_nmap_targets(&path, Some("ssh")).unwrap(),
+            vec!["10.0.0.1:22", "10.0.0.2:2222"]
+        );
+    }
+
+    #[test]
+    fn returns_error_for_invalid_xml() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(&tmpdir, "scan.xml", "<nmaprun><host>");
+
+        assert!(load_nmap_targets(&path, None).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::session::Error;
+
+use super::{iterate_multiple_targets, parse_target, TargetsIterator};
+
+// plugins picking http or https from the scheme of the target, the only ones using the tls override
+const TLS_PLUGINS: &[&str] = &[
+    "consul",
+    "couchdb",
+    "docker",
+    "elastic",
+    "etcd",
+    "git",
+    "gitea",
+    "gitlab",
+    "grafana",
+    "influxdb",
+    "jenkins",
+    "neo4j",
+    "onvif",
+    "svn",
+    "tomcat",
+    "vault",
+    "wildfly",
+    "winrm",
+    "wordpress",
+];
+
+fn supports_tls(plugin: &str) -> bool {
+    plugin == "auto"
+        || plugin == "http"
+        || plugin.starts_with("http.")
+        || TLS_PLUGINS.contains(&plugin)
+}
+
+/// A target along with the per-target settings from an extended targets file, if any.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct Target {
+    /// Target string passed to the plugins.
+    pub address: String,
+    /// Username:password combinations file to use for this target instead of the global credentials.
+    #[serde(default)]
+    pub credentials: Option<String>,
+    /// Free form tags reported with the loot of this target.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl From<String> for Target {
+    fn from(address: String) -> Self {
+        Self {
+            address,
+            ..Default::default()
+        }
+    }
+}
+
+/// An entry of an extended YAML or CSV targets file, with optional per-target overrides.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct TargetEntry {
+    /// Host, IP address or URL.
+    pub address: String,
+    /// Port to use instead of the one in the address or the plugin default.
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Force https (true) or http (false) for this target.
+    #[serde(default)]
+    pub tls: Option<bool>,
+    /// HTTP path to use instead of the one in the address.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Username:password combinations file to use for this target instead of the global credentials.
+    #[serde(default)]
+    pub credentials: Option<String>,
+    /// Free form tags for this target.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// same as TargetEntry, with tags as a semicolon separated string since csv doesn't support lists
+#[derive(Deserialize)]
+struct CsvTarget {
+    address: String,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    tls: Option<bool>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    credentials: Option<String>,
+    #[serde(default)]
+    tags: Option<String>,
+}
+
+impl From<CsvTarget> for TargetEntry {
+    fn from(row: CsvTarget) -> Self {
+        Self {
+            address: row.address,
+            port: row.port,
+            tls: row.tls,
+            path: row.path.filter(|p| !p.is_empty()),
+            credentials: row.credentials.filter(|c| !c.is_empty()),
+            tags: row
+                .tags
+                .unwrap_or_default()
+                .split(';')
+                .map(|t| t.trim().to_owned())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl TargetEntry {
+    // build the target string passed to the plugins by applying the overrides to the address
+    pub fn to_target_string(&self) -> Result<String, Error> {
+        let (scheme, rest) = if let Some((scheme, rest)) = self.address.split_once("://") {
+            (Some(scheme.to_lowercase()), rest)
+        } else {
+            (None, self.address.as_str())
+        };
+
+        let (host_part, path) = if let Some(idx) = rest.find('/') {
+            rest.split_at(idx)
+        } else {
+            (rest, "")
+        };
+
+        let host_part = if let Some(port) = self.port {
+            let (host, _) = parse_target(host_part, 0)?;
+            if host.contains(':') {
+                format!("[{}]:{}", host, port)
+            } else {
+                format!("{}:{}", host, port)
+            }
+        } else {
+            host_part.to_owned()
+        };
+
+        let scheme = match (self.tls, scheme.as_deref()) {
+            (None, _) => scheme,
+            (Some(tls), None | Some("http") | Some("https")) => {
+                Some(if tls { "https" } else { "http" }.to_owned())
+            }
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "tls can't be set for {}, only http and https targets support it",
+                    &self.address
+                ))
+            }
+        };
+
+        let path = if let Some(path) = self.path.as_ref() {
+            if path.starts_with('/') {
+                path.to_owned()
+            } else {
+                format!("/{}", path)
+            }
+        } else {
+            path.to_owned()
+        };
+
+        Ok(if let Some(scheme) = scheme {
+            format!("{}://{}{}", scheme, host_part, path)
+        } else {
+            format!("{}{}", host_part, path)
+        })
+    }
+}
+
+// load targets from a YAML or CSV file with per-target overrides, returns None if the file
+// is not in one of these formats, the addresses are expanded like the lines of a targets file
+pub(crate) fn load_extended_targets(
+    path: &str,
+    plugin: Option<&str>,
+) -> Result<Option<TargetsIterator>, Error> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    let entries: Vec<TargetEntry> = match ext.as_deref() {
+        Some("yml") | Some("yaml") => {
+            let data =
+                fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+            serde_yaml::from_str(&data).map_err(|e| format!("can't parse {}: {}", path, e))?
+        }
+        Some("csv") => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_path(path)
+                .map_err(|e| format!("can't read {}: {}", path, e))?;
+            // plain host lists saved as csv are parsed line by line
+            if !reader
+                .headers()
+                .map_err(|e| format!("can't parse {}: {}", path, e))?
+                .iter()
+                .any(|h| h == "address")
+            {
+                return Ok(None);
+            }
+            let mut entries = vec![];
+            for row in reader.deserialize::<CsvTarget>() {
+                entries.push(
+                    row.map_err(|e| format!("can't parse {}: {}", path, e))?
+                        .into(),
+                );
+            }
+            entries
+        }
+        _ => return Ok(None),
+    };
+
+    if let Some(plugin) = plugin.filter(|p| !supports_tls(p)) {
+        if entries.iter().any(|e| e.tls.is_some()) {
+            log::warn!(
+                "the tls setting of the targets in {} is ignored by the {} plugin",
+                path,
+                plugin
+            );
+        }
+    }
+
+    let mut targets: Vec<TargetsIterator> = vec![];
+    for entry in entries {
+        // fail early for invalid overrides
+        entry.to_target_string()?;
+        let expanded = iterate_multiple_targets(&entry.address, plugin)?;
+        targets.push(Box::new(expanded.map(move |target| {
+            let entry = TargetEntry {
+                address: target?.address,
+                ..entry.clone()
+            };
+            Ok(Target {
+                address: entry.to_target_string()?,
+                credentials: entry.credentials,
+                tags: entry.tags,
+            })
+        })));
+    }
+
+    Ok(Some(Box::new(targets.into_iter().flatten())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::{load_extended_targets, Target, TargetEntry};
+    use crate::session::Error;
+
+    fn load(path: &str) -> Option<Vec<Target>> {
+        load_extended_targets(path, None)
+            .unwrap()
+            .map(|targets| targets.collect::<Result<Vec<Target>, Error>>().unwrap())
+    }
+
+    fn write_tmp(dir: &tempfile::TempDir, name: &str, data: &str) -> String {
+        let path = dir.path().join(name);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "{}", data).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn can_apply_overrides() {
+        let target = TargetEntry {
+            address: "intranet.corp".to_owned(),
+            port: Some(8443),
+            tls: Some(true),
+            path: Some("login".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            target.to_target_string().unwrap(),
+            "https://intranet.corp:8443/login"
+        );
+
+        let target = TargetEntry {
+            address: "ssh://10.0.0.1".to_owned(),
+            port: Some(2222),
+            ..Default::default()
+        };
+        assert_eq!(target.to_target_string().unwrap(), "ssh://10.0.0.1:2222");
+
+        // the plugin default is kept when the scheme is not set
+        let target = TargetEntry {
+            address: "intranet.corp".to_owned(),
+            path: Some("/login".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(target.to_target_string().unwrap(), "intranet.corp/login");
+
+        let target = TargetEntry {
+            address: "::1".to_owned(),
+            port: Some(22),
+            ..Default::default()
+        };
+        assert_eq!(target.to_target_string().unwrap(), "[::1]:22");
+    }
+
+    #[test]
+    fn returns_error_for_tls_without_http() {
+        let target = TargetEntry {
+            address: "ssh://10.0.0.1".to_owned(),
+            tls: Some(true),
+            ..Default::default()
+        };
+        assert!(target.to_target_string().is_err());
+    }
+
+    #[test]
+    fn can_load_yaml() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(
+            &tmpdir,
+            "targets.yml",
+            "- address: 10.0.0.1
+  port: 2222
+  tags: [prod, dmz]
+- address: Intranet.corp
+  tls: true
+  path: /admin
+  credentials: /tmp/intranet.txt
+- address: 10.0.1.1-2
+  port: 22
+",
+        );
+
+        assert_eq!(
+            load(&path),
+            Some(vec![
+                Target {
+                    address: "10.0.0.1:2222".to_owned(),
+                    credentials: None,
+                    tags: vec!["prod".to_owned(), "dmz".to_owned()],
+                },
+                Target {
+                    address: "https://Intranet.corp/admin".to_owned(),
+                    credentials: Some("/tmp/intranet.txt".to_owned()),
+                    tags: vec![],
+                },
+                Target::from("10.0.1.1:22".to_owned()),
+                Target::from("10.0.1.2:22".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn can_load_csv() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(
+            &tmpdir,
+            "targets.csv",
+            "address,port,tls,path,credentials,tags
+10.0.1.1,21,,,,ftp;legacy
+web.corp,,false,/login,,
+",
+        );
+
+        assert_eq!(
+            load(&path),
+            Some(vec![
+                Target {
+                    address: "10.0.1.1:21".to_owned(),
+                    credentials: None,
+                    tags: vec!["ftp".to_owned(), "legacy".to_owned()],
+                },
+                Target::from("http://web.corp/login".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn ignores_other_formats() {
+        assert_eq!(load("/tmp/targets.txt"), None);
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = write_tmp(&tmpdir, "hosts.csv", "10.0.0.1\n10.0.0.2:22\n");
+        assert_eq!(load(&path), None);
+    }
+}
@@ -0,0 +1,195 @@
+// nmap service names and default ports for each plugin, used to select the relevant
+// open ports when importing targets from scanners output
+static PLUGIN_SERVICES: &[(&str, &[&str], &[u16])] = &[
+    ("amqp", &["amqp"], &[5672]),
+    ("consul", &["consul", "fmtp"], &[8500, 8501]),
+    ("couchdb", &["couchdb"], &[5984, 6984]),
+    // cql goes first so that the native protocol plugin is the default one for port 9042
+    ("cql", &["cassandra"], &[9042]),
+    ("dns", &["domain"], &[53]),
+    ("db2", &["ibm-db2", "drda", "ddm-rdb"], &[50000, 446]),
+    // docker.registry goes before docker so that it gets its own ports
+    ("docker.registry", &["docker-registry"], &[5000]),
+    ("docker", &["docker", "docker-s"], &[2375, 2376]),
+    ("elastic", &["elasticsearch"], &[9200, 5601]),
+    ("etcd", &["etcd-client", "etcd"], &[2379]),
+    ("firebird", &["gds_db", "firebird"], &[3050]),
+    ("ftp", &["ftp", "ftps"], &[21, 990]),
+    ("grafana", &["grafana"], &[3000]),
+    // gitea goes after grafana so that it stays the default plugin for port 3000
+    ("gitea", &["gitea"], &[3000]),
+    // http.proxy goes before the http family so that it gets its own ports
+    ("http.proxy", &["http-proxy", "squid-http"], &[3128]),
+    (
+        "http",
+        &["http", "https", "http-proxy", "http-alt", "https-alt"],
+        &[80, 443, 8000, 8080, 8443],
+    ),
+    // gitlab goes after the http family so that http stays the default plugin for its ports
+    ("gitlab", &["gitlab"], &[80, 443]),
+    // web portals go after the http family so that http stays the default plugin for port 443
+    ("adfs", &[], &[443]),
+    ("exchange", &[], &[443]),
+    ("fortinet", &[], &[443, 10443]),
+    ("globalprotect", &[], &[443]),
+    ("anyconnect", &[], &[443]),
+    ("git", &[], &[80, 443]),
+    ("imap", &["imap", "imaps"], &[143, 993]),
+    ("influxdb", &["influxdb"], &[8086]),
+    ("ipmi", &["asf-rmcp", "ipmi"], &[623]),
+    (
+        "k8s",
+        &["kubernetes", "sun-sr-https"],
+        &[6443, 10250, 10255],
+    ),
+    ("kafka", &["kafka"], &[9092, 9093]),
+    ("kerberos", &["kerberos-sec", "kerberos"], &[88]),
+    ("ldap", &["ldap", "ldaps"], &[389, 636]),
+    ("memcached", &["memcache", "memcached"], &[11211]),
+    ("mikrotik", &[], &[8728, 8729]),
+    ("mongodb", &["mongodb", "mongod"], &[27017]),
+    ("mqtt", &["mqtt", "secure-mqtt"], &[1883, 8883]),
+    ("mssql", &["ms-sql-s"], &[1433]),
+    ("mysql", &["mysql"], &[3306]),
+    ("nats", &["nats"], &[4222]),
+    ("nntp", &["nntp", "nntps", "snews"], &[119, 563]),
+    ("neo4j", &["neo4j", "bolt"], &[7687, 7474, 7473]),
+    ("oracle", &["oracle-tns", "oracle"], &[1521]),
+    ("oracle.sid", &["oracle-tns", "oracle"], &[1521]),
+    ("pgsql", &["postgresql"], &[5432]),
+    ("pop3", &["pop3", "pop3s"], &[110, 995]),
+    ("radius", &["radius"], &[1812]),
+    ("rdp", &["ms-wbt-server"], &[3389]),
+    ("redis", &["redis"], &[6379]),
+    ("rexec", &["exec"], &[512]),
+    ("rlogin", &["login"], &[513]),
+    ("modbus", &["modbus", "mbap"], &[502]),
+    ("s7", &["iso-tsap"], &[102]),
+    ("bacnet", &["bacnet"], &[47808]),
+    ("rsync", &["rsync"], &[873]),
+    ("rtsp", &["rtsp"], &[554, 8554]),
+    ("onvif", &["onvif"], &[]),
+    ("scylla", &["cassandra"], &[9042]),
+    ("smb", &["microsoft-ds", "netbios-ssn"], &[445, 139]),
+    ("smtp", &["smtp", "smtps", "submission"], &[25, 465, 587]),
+    // snmp2 goes first so that it's the default plugin for port 161
+    ("snmp2", &["snmp"], &[161]),
+    ("snmp1", &["snmp"], &[161]),
+    ("snmp3", &["snmp"], &[161]),
+    ("socks5", &["socks5", "socks"], &[1080]),
+    // ssh goes before sftp so that it's the default plugin for port 22
+    ("ssh", &["ssh"], &[22]),
+    ("sftp", &["ssh"], &[22]),
+    ("stomp", &["stomp"], &[61613]),
+    ("svn", &["svn", "svnserve"], &[3690]),
+    ("tacacs", &["tacacs", "tacacs+"], &[49]),
+    ("telnet", &["telnet"], &[23]),
+    ("teamspeak", &["teamspeak", "ts3"], &[10011]),
+    ("mumble", &["murmur-ice", "zeroc-ice"], &[6502]),
+    ("rcon", &["rcon", "srcds"], &[27015, 25575]),
+    // cisco goes after ssh and telnet so that they stay the default plugins for their ports
+    ("cisco", &[], &[22, 23]),
+    // tomcat goes after the http family so that http stays the default plugin for its ports
+    ("tomcat", &["http-proxy", "http-alt"], &[8080, 8443]),
+    ("vault", &["vault"], &[8200]),
+    ("vnc", &["vnc"], &[5900]),
+    ("wildfly", &[], &[9990, 9993]),
+    ("winrm", &["wsman", "wsmans"], &[5985, 5986]),
+    // wordpress goes after the http family so that http stays the default plugin for its ports
+    ("wordpress", &[], &[80, 443]),
+    ("zookeeper", &["zookeeper"], &[2181]),
+];
+
+// uri schemes that don't match a plugin name, with their plugin and default port
+static SCHEME_ALIASES: &[(&str, &str, u16)] = &[
+    ("cassandra", "scylla", 9042),
+    ("cifs", "smb", 445),
+    ("elasticsearch", "elastic", 9200),
+    ("https", "http", 443),
+    ("imaps", "imap", 993),
+    ("ldaps", "ldap", 636),
+    ("mqtts", "mqtt", 8883),
+    ("bolt", "neo4j", 7687),
+    ("pop3s", "pop3", 995),
+    ("postgres", "pgsql", 5432),
+    ("postgresql", "pgsql", 5432),
+    ("smtps", "smtp", 465),
+    ("snmp", "snmp2", 161),
+    ("socks", "socks5", 1080),
+    ("sqlserver", "mssql", 1433),
+];
+
+// returns true if the port (and optionally the detected service) is relevant for the plugin, plugins
+// that are not bound to a specific service (like cmd or port.scanner) accept everything
+pub(crate) fn is_relevant_port(plugin: Option<&str>, port: u16, service: Option<&str>) -> bool {
+    let Some(plugin) = plugin else {
+        return true;
+    };
+
+    // http.form, http.basic, etc
+    let family = plugin.split('.').next().unwrap_or(plugin);
+
+    if let Some((_, services, ports)) = PLUGIN_SERVICES
+        .iter()
+        .find(|(name, _, _)| *name == plugin || *name == family)
+    {
+        ports.contains(&port) || service.is_some_and(|s| services.contains(&s))
+    } else {
+        true
+    }
+}
+
+// returns the name of the default plugin for the given port, if any
+pub(crate) fn plugin_for_port(port: u16) -> Option<&'static str> {
+    PLUGIN_SERVICES
+        .iter()
+        .find(|(_, _, ports)| ports.contains(&port))
+        .map(|(name, _, _)| *name)
+}
+
+// returns the name of the plugin and the default port for the given uri scheme, if any
+pub(crate) fn plugin_for_scheme(scheme: &str) -> Option<(&'static str, u16)> {
+    let scheme = scheme.to_lowercase();
+
+    if let Some((_, plugin, port)) = SCHEME_ALIASES.iter().find(|(name, _, _)| *name == scheme) {
+        Some((*plugin, *port))
+    } else {
+        PLUGIN_SERVICES
+            .iter()
+            .find(|(name, _, _)| *name == scheme)
+            .map(|(name, _, ports)| (*name, ports[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_relevant_port, plugin_for_port, plugin_for_scheme};
+
+    #[test]
+    fn can_select_relevant_ports() {
+        assert!(is_relevant_port(None, 1234, None));
+        assert!(is_relevant_port(Some("cmd"), 1234, None));
+        assert!(is_relevant_port(Some("ssh"), 22, None));
+        assert!(is_relevant_port(Some("ssh"), 2222, Some("ssh")));
+        assert!(!is_relevant_port(Some("ssh"), 2222, Some("http")));
+        assert!(is_relevant_port(Some("http.form"), 8080, None));
+        assert!(!is_relevant_port(Some("http.form"), 22, Some("ssh")));
+    }
+
+    #[test]
+    fn can_map_ports_to_plugins() {
+        assert_eq!(plugin_for_port(22), Some("ssh"));
+        assert_eq!(plugin_for_port(8443), Some("http"));
+        assert_eq!(plugin_for_port(6379), Some("redis"));
+        assert_eq!(plugin_for_port(12345), None);
+    }
+
+    #[test]
+    fn can_map_schemes_to_plugins() {
+        assert_eq!(plugin_for_scheme("ssh"), Some(("ssh", 22)));
+        assert_eq!(plugin_for_scheme("HTTPS"), Some(("http", 443)));
+        assert_eq!(plugin_for_scheme("postgres"), Some(("pgsql", 5432)));
+        assert_eq!(plugin_for_scheme("smb"), Some(("smb", 445)));
+        assert_eq!(plugin_for_scheme("gopher"), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::net::IpAddr;
+
+use crate::session::Error;
+
+pub(crate) fn parse_target(target: &str, default_port: u16) -> Result<(String, u16), Error> {
+    if target.contains(' ') || target.contains(',') {
+        return Err(format!(
+            "'{}' is not a valid target, maybe you meant to use --multiple instead of --target?",
+            target
+        ));
+    }
+
+    // remove <proto>:// if present
+    let target = if target.contains("://") {
+        target.split_once("://").unwrap().1
+    } else {
+        target
+    };
+
+    // remove /<whatever> if present
+    let target = if target.contains('/') {
+        target.split_once('/').unwrap().0
+    } else {
+        target
+    };
+
+    let num_colons = target.matches(':').count();
+    let (address, port) = if num_colons <= 1 {
+        // domain or ipv4
+        if let Some((ip, prt)) = target.rsplit_once(':') {
+            (
+                ip.to_owned(),
+                prt.parse::<u16>().map_err(|e| e.to_string())?,
+            )
+        } else {
+            (target.to_owned(), default_port)
+        }
+    } else {
+        // ipv6
+        if let Some((ip, prt)) = target.rsplit_once("]:") {
+            (
+                ip.strip_prefix('[')
+                    .ok_or("invalid [ipv6]:port provided".to_string())?
+                    .to_owned(),
+                prt.parse::<u16>().map_err(|e| e.to_string())?,
+            )
+        } else {
+            (target.to_owned(), default_port)
+        }
+    };
+
+    Ok((address, port))
+}
+
+fn normalize_host(host: &str) -> String {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        ip.to_string()
+    } else {
+        host.trim_end_matches('.').to_lowercase()
+    }
+}
+
+// normalize the scheme and host part of a target so that equivalent targets can be compared
+pub(crate) fn normalize_target(target: &str) -> String {
+    let (scheme, rest) = if let Some((scheme, rest)) = target.split_once("://") {
+        (format!("{}://", scheme.to_lowercase()), rest)
+    } else {
+        ("".to_owned(), target)
+    };
+
+    let (host_part, path) = if let Some(idx) = rest.find('/') {
+        rest.split_at(idx)
+    } else {
+        (rest, "")
+    };
+
+    let num_colons = host_part.matches(':').count();
+    let host_part = if let Some(ipv6) = host_part
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+    {
+        // [ipv6]
+        format!("[{}]", normalize_host(ipv6))
+    } else if let Some((ipv6, port)) = host_part
+        .strip_prefix('[')
+        .and_then(|h| h.rsplit_once("]:"))
+    {
+        // [ipv6]:port
+        format!("[{}]:{}", normalize_host(ipv6), port)
+    } else if let Some((ipv6, port)) = host_part
+        .strip_suffix(']')
+        .and_then(|h| h.rsplit_once(":["))
+    {
+        // ipv6:[port]
+        format!("{}:[{}]", normalize_host(ipv6), port)
+    } else if num_colons == 1 {
+        // domain or ipv4 with port
+        let (host, port) = host_part.split_once(':').unwrap();
+        format!("{}:{}", normalize_host(host), port)
+    } else {
+        // domain, ipv4 or ipv6 without port
+        normalize_host(host_part)
+    };
+
+    format!("{}{}{}", scheme, host_part, path)
+}
+
+#[inline]
+pub(crate) fn parse_target_address(target: &str, default_port: u16) -> Result<String, Error> {
+    let (host, port) = parse_target(target, default_port)?;
+    Ok(format!("{}:{}", host, port))
+}
+
+// the path part of an url target, without the leading slash, if any
+pub(crate) fn parse_target_path(target: &str) -> Option<String> {
+    let rest = target
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(target);
+    let (_, path) = rest.split_once('/')?;
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        None
+    } else {
+        Some(path.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_target, parse_target, parse_target_path};
+
+    #[test]
+    fn can_parse_target_path() {
+        assert_eq!(
+            parse_target_path("https://git.corp.local/org/repo.git"),
+            Some("org/repo.git".to_owned())
+        );
+        assert_eq!(
+            parse_target_path("127.0.0.1:3690/repo/"),
+            Some("repo".to_owned())
+        );
+        assert_eq!(parse_target_path("https://git.corp.local/"), None);
+        assert_eq!(parse_target_path("git.corp.local"), None);
+    }
+
+    #[test]
+    fn returns_default_port_if_not_provided_ipv4() {
+        let (address, port) = parse_target("127.0.0.1", 4444).unwrap();
+        assert_eq!(address, "127.0.0.1");
+        assert_eq!(port, 4444);
+    }
+
+    #[test]
+    fn parses_port_if_provided_ipv4() {
+        let (address, port) = parse_target("127.0.0.1:8080", 4444).unwrap();
+        assert_eq!(address, "127.0.0.1");
+        assert_eq!(port, 8080);
+    }
+
+    #[test]
+    fn returns_default_port_if_not_provided_ipv6() {
+        let (address, port) = parse_target("::1", 4444).unwrap();
+        assert_eq!(address, "::1");
+        assert_eq!(port, 4444);
+    }
+
+    #[test]
+    fn parses_port_if_provided_ipv6() {
+        let (address, port) = parse_target("[::1]:8080", 4444).unwrap();
+        assert_eq!(address, "::1");
+        assert_eq!(port, 8080);
+    }
+
+    #[test]
+    fn can_normalize_targets() {
+        assert_eq!(normalize_target("HOST:22"), "host:22");
+        assert_eq!(normalize_target("www.Example.com."), "www.example.com");
+        assert_eq!(
+            normalize_target("www.example.com.:8080"),
+            "www.example.com:8080"
+        );
+        assert_eq!(
+            normalize_target("HTTPS://WWW.Example.com/Some/Path"),
+            "https://www.example.com/Some/Path"
+        );
+        assert_eq!(normalize_target("2001:DB8:0::1"), "2001:db8::1");
+        assert_eq!(normalize_target("[2001:DB8::1]:22"), "[2001:db8::1]:22");
+        assert_eq!(normalize_target("2001:DB8::1:[22]"), "2001:db8::1:[22]");
+        assert_eq!(normalize_target("10.0.0.1:22"), "10.0.0.1:22");
+    }
+}
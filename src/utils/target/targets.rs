@@ -0,0 +1,250 @@
+use ahash::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::session::Error;
+use crate::utils::resolver;
+use crate::Options;
+
+use super::{
+    iterate_multiple_targets, may_have_duplicates, normalize_target, parse_target,
+    set_asn_database, Exclusions, Target, TargetsIterator,
+};
+
+/// Lazily expanded list of targets, only the expressions and the number of elements are kept in memory
+/// and the targets are generated again on every iteration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct Targets {
+    expression: String,
+    exclude: Option<String>,
+    dedup: bool,
+    #[serde(default)]
+    resolve_all: bool,
+    #[serde(default)]
+    asn_database: Option<String>,
+    plugin: Option<String>,
+    count: usize,
+    // targets with their own credentials file
+    #[serde(default)]
+    overridden: usize,
+}
+
+impl Targets {
+    pub fn from_options(options: &Options) -> Result<Self, Error> {
+        if let Some(target) = options.target.as_ref() {
+            Self {
+                expression: target.to_owned(),
+                exclude: options.exclude_targets.clone(),
+                dedup: !options.no_dedup,
+                resolve_all: options.resolve_all,
+                asn_database: options.asn_database.clone(),
+                plugin: options.plugin.clone(),
+                count: 0,
+                overridden: 0,
+            }
+            .load()
+        } else {
+            Err("no --target/-T argument provided".to_owned())
+        }
+    }
+
+    #[cfg(test)]
+    pub fn parse(expression: &str, exclude: Option<&String>, dedup: bool) -> Result<Self, Error> {
+        Self {
+            expression: expression.to_owned(),
+            exclude: exclude.cloned(),
+            dedup,
+            resolve_all: false,
+            asn_database: None,
+            plugin: None,
+            count: 0,
+            overridden: 0,
+        }
+        .load()
+    }
+
+    fn load(mut self) -> Result<Self, Error> {
+        // count the elements and perform pre-emptive target validation
+        for target in self.expand()? {
+            let target = target?;
+            parse_target(&target.address, 0)?;
+            self.count += 1;
+            if target.credentials.is_some() {
+                self.overridden += 1;
+            }
+        }
+
+        if self.count == 0 {
+            return Err("empty list of target(s) provided".to_owned());
+        }
+
+        Ok(self)
+    }
+
+    fn expand(&self) -> Result<TargetsIterator, Error> {
+        set_asn_database(self.asn_database.clone());
+
+        let mut all: TargetsIterator = Box::new(
+            iterate_multiple_targets(&self.expression, self.plugin.as_deref())?.map(|target| {
+                target.map(|mut t| {
+                    t.address = normalize_target(&t.address);
+                    t
+                })
+            }),
+        );
+
+        if self.resolve_all {
+            // one target for each cached address of the hostname
+            all = Box::new(all.flat_map(|target| {
+                match target {
+                    Ok(target) => resolver::expand_target(&target.address)
+                        .into_iter()
+                        .map(|address| {
+                            Ok(Target {
+                                address,
+                                ..target.clone()
+                            })
+                        })
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                }
+            }));
+        }
+
+        if let Some(exclude) = self.exclude.as_ref() {
+            let exclusions = Exclusions::parse(exclude)?;
+            all = Box::new(all.filter(move |target| match target {
+                Ok(target) => !exclusions.contains(&target.address),
+                Err(_) => true,
+            }));
+        }
+
+        // every target is kept in memory, so only when the expression can actually repeat some
+        if self.dedup && may_have_duplicates(&self.expression) {
+            let mut seen = HashSet::default();
+            all = Box::new(all.filter(move |target| match target {
+                Ok(target) => seen.insert(target.address.to_owned()),
+                Err(_) => true,
+            }));
+        }
+
+        Ok(all)
+    }
+
+    // count the targets again once their hostnames have been resolved, for --resolve-all
+    pub fn reload(&mut self) -> Result<(), Error> {
+        if self.resolve_all {
+            self.count = 0;
+            self.overridden = 0;
+            *self = self.clone().load()?;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn overridden(&self) -> usize {
+        self.overridden
+    }
+
+    pub fn iter(&self) -> Result<Box<dyn Iterator<Item = Target>>, Error> {
+        // errors have already been reported during validation
+        Ok(Box::new(self.expand()?.filter_map(|target| target.ok())))
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<String>, Error> {
+        Ok(self.iter()?.map(|t| t.address).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use super::Targets;
+
+    #[test]
+    fn can_count_and_iterate() {
+        let targets = Targets::parse("10.0.0.1-4, www.example.com", None, true).unwrap();
+        assert_eq!(targets.len(), 5);
+        assert_eq!(
+            targets.to_vec().unwrap(),
+            vec![
+                "10.0.0.1",
+                "10.0.0.2",
+                "10.0.0.3",
+                "10.0.0.4",
+                "www.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn can_exclude_targets() {
+        let targets = Targets::parse(
+            "10.0.0.0-7:22, www.example.com, www.other.com",
+            Some(&"10.0.0.1, 10.0.0.4/30, www.other.com, 10.0.0.3".to_owned()),
+            true,
+        )
+        .unwrap();
+        assert_eq!(targets.len(), 3);
+        assert_eq!(
+            targets.to_vec().unwrap(),
+            vec!["10.0.0.0:22", "10.0.0.2:22", "www.example.com"]
+        );
+    }
+
+    #[test]
+    fn returns_error_for_empty_targets() {
+        let res = Targets::parse("10.0.0.1", Some(&"10.0.0.0/24".to_owned()), true);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn returns_error_for_invalid_line_in_file() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let tmppath = tmpdir.path().join("targets.txt");
+        let mut tmptargets = File::create(&tmppath).unwrap();
+
+        writeln!(tmptargets, "10.0.0.1").unwrap();
+        writeln!(tmptargets, "10.0.300-1.1").unwrap();
+        tmptargets.flush().unwrap();
+        drop(tmptargets);
+
+        let res = Targets::parse(&format!("@{}", tmppath.to_str().unwrap()), None, true);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn can_remove_duplicates() {
+        let targets = Targets::parse(
+            "10.0.0.0/30, 10.0.0.2-5, HOST:22, host:22, www.example.com., www.example.com",
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(targets.len(), 8);
+        assert_eq!(
+            targets.to_vec().unwrap(),
+            vec![
+                "10.0.0.0",
+                "10.0.0.1",
+                "10.0.0.2",
+                "10.0.0.3",
+                "10.0.0.4",
+                "10.0.0.5",
+                "host:22",
+                "www.example.com"
+            ]
+        );
+    }
+
+    #[test]
+    fn can_keep_duplicates() {
+        let targets =
+            Targets::parse("10.0.0.0/30, 10.0.0.2-5, HOST:22, host:22", None, false).unwrap();
+        assert_eq!(targets.len(), 10);
+    }
+}
@@ -0,0 +1,34 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+// local http server answering each connection with the next (status, body) response, None drops
+// the connection and the last response is repeated once the list is over, returns its address
+pub(crate) async fn http_server(responses: Vec<Option<(u16, &'static str)>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        let mut index = 0;
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let response = responses[index.min(responses.len() - 1)];
+            index += 1;
+
+            let Some((status, body)) = response else {
+                continue;
+            };
+
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer).await;
+            let response = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    address
+}
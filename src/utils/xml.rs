@@ -0,0 +1,29 @@
+// escape a value for xml text and attributes
+pub(crate) fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xml_escape;
+
+    #[test]
+    fn can_escape_xml() {
+        assert_eq!(xml_escape("admin"), "admin");
+        assert_eq!(
+            xml_escape(r#"<p&ss> "it's""#),
+            "&lt;p&amp;ss&gt; &quot;it&apos;s&quot;"
+        );
+    }
+}